@@ -0,0 +1,66 @@
+import json
+import os
+import re
+import unittest
+
+from xgb_language_converter import XGBoostLanguageConverter
+
+CONVERTER_DIR = os.path.dirname(os.path.abspath(__file__))
+
+
+class DumpBooster:
+    """Stands in for an XGBoost booster: only get_dump is used by the converter."""
+
+    def __init__(self, trees):
+        self.trees = trees
+
+    def get_dump(self, dump_format='json'):
+        return [json.dumps(tree) for tree in self.trees]
+
+
+def stump(feature, threshold, yes_leaf, no_leaf):
+    return {"nodeid": 0, "split": f"f{feature}", "split_condition": threshold, "yes": 1, "no": 2,
+            "children": [{"nodeid": 1, "leaf": yes_leaf}, {"nodeid": 2, "leaf": no_leaf}]}
+
+
+def converter(language):
+    return XGBoostLanguageConverter(language,
+                                    os.path.join(CONVERTER_DIR, "language_configs"),
+                                    os.path.join(CONVERTER_DIR, "language_templates"))
+
+
+class AgreementCircuitTest(unittest.TestCase):
+    def setUp(self):
+        self.converter = converter("zokrates")
+        self.current = DumpBooster([stump(1, 0.5, 0.25, -0.75), stump(0, -2.0, 1.5, 0.0)])
+        self.candidate = DumpBooster([stump(1, 0.625, 0.25, -0.5)])
+
+    def test_models_are_private_inputs(self):
+        code = self.converter.convert_agreement_to_code(self.current, self.candidate, ["a", "b"], 10)
+        self.assertIn("def main(private i64[2] f, private i64[6] pa, private i64[3] pb, "
+                      "field commitment_a, field commitment_b, u64 bound) -> bool {", code)
+        self.assertIn("assert(model_commitment(pa) == commitment_a);", code)
+        self.assertIn("x = if i64_le(f[1], p[0]) {", code)
+        # No threshold or leaf of either model is compiled in
+        for params in (self.converter.model_parameters(self.current, 10),
+                       self.converter.model_parameters(self.candidate, 10)):
+            for value in params:
+                if value != 0:
+                    self.assertNotIn(str(abs(value)), code)
+
+    def test_parameters_follow_the_circuit_order(self):
+        params = self.converter.model_parameters(self.current, 10)
+        self.assertEqual(params, [5000000000, 2500000000, -7500000000, -20000000000, 15000000000, 0])
+        self.assertEqual(self.converter.model_parameters(self.current, 1), params[:3])
+        self.assertEqual(self.converter.parameters_to_args([7, -3]), ['1', '7', '0', '3'])
+
+        code = self.converter.convert_commitment_to_code(self.current, 10)
+        self.assertIn("def main(private i64[6] p) -> field {", code)
+        references = [int(k) for k in re.findall(r"p\[(\d+)\]",
+                                                 self.converter.convert_agreement_to_code(
+                                                     self.current, self.current, ["a", "b"], 10))]
+        self.assertEqual(references[:len(params)], list(range(len(params))))
+
+
+if __name__ == "__main__":
+    unittest.main()
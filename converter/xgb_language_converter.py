@@ -39,6 +39,13 @@ class XGBoostLanguageConverter:
         ]
         
         # Add language-specific additional templates
+        if self.language == "zokrates":
+            template_files.extend([
+                f"{self.language}_model.template",
+                f"{self.language}_agreement.template",
+                f"{self.language}_commitment.template",
+                f"{self.language}_commit.template"
+            ])
         if self.language == "rust":
             template_files.extend([
                 f"{self.language}_cargo.template",
@@ -129,8 +136,15 @@ class XGBoostLanguageConverter:
         else:
             return ', '.join(field_parts)
     
-    def _generate_tree_logic(self, tree_structure: Dict, feature_indices: List[str], depth: int) -> str:
-        """Generate code for a single decision tree."""
+    def _generate_tree_logic(self, tree_structure: Dict, feature_indices: List[str], depth: int,
+                             params: List[int] = None) -> str:
+        """
+        Generate code for a single decision tree.
+        
+        With a params list, thresholds and leaves are read from the parameter
+        array `p` instead of being emitted as constants, and their scaled values
+        are appended to params in the order they are referenced.
+        """
         indent_config = self.config["indentation"]
         indent_char = " " if indent_config["type"] == "spaces" else "\t"
         indent = indent_char * (indent_config["size"] * depth)
@@ -139,7 +153,7 @@ class XGBoostLanguageConverter:
         if 'leaf' in tree_structure:
             # Convert leaf value with precision multiplier (10^10 as in old notebook)
             leaf_value_scaled = int(np.round(tree_structure['leaf'] * 10000000000, 0))
-            leaf_value = self.convert_number_to_fixed_point_from_scaled(leaf_value_scaled)
+            leaf_value = self._parameter(leaf_value_scaled, params)
             if self.language == "rust":
                 return f"{indent}{leaf_value}\n"
             else:
@@ -151,7 +165,7 @@ class XGBoostLanguageConverter:
         
         # Convert threshold with precision multiplier
         threshold_scaled = int(np.round(tree_structure['split_condition'] * 10000000000, 0))
-        threshold = self.convert_number_to_fixed_point_from_scaled(threshold_scaled)
+        threshold = self._parameter(threshold_scaled, params)
         
         # Generate condition - use numeric index for array access
         if self.language == "rust":
@@ -169,12 +183,12 @@ class XGBoostLanguageConverter:
         
         # Left branch (yes)
         yes_branch = tree_structure['children'][0]
-        result += self._generate_tree_logic(yes_branch, feature_indices, depth + 1)
+        result += self._generate_tree_logic(yes_branch, feature_indices, depth + 1, params)
         result += f"{indent}}} else {{\n"
         
         # Right branch (no)
         no_branch = tree_structure['children'][1]
-        result += self._generate_tree_logic(no_branch, feature_indices, depth + 1)
+        result += self._generate_tree_logic(no_branch, feature_indices, depth + 1, params)
         
         if self.language == "rust":
             if depth == 1:
@@ -190,7 +204,15 @@ class XGBoostLanguageConverter:
         
         return result
     
-    def convert_xgboost_to_code(self, booster, feature_names: List[str], num_trees: int) -> str:
+    def _parameter(self, scaled_value: int, params: List[int] = None) -> str:
+        """A threshold or leaf: a constant, or the next entry of the parameter array when collecting params."""
+        if params is None:
+            return self.convert_number_to_fixed_point_from_scaled(scaled_value)
+        params.append(scaled_value)
+        return f"p[{len(params) - 1}]"
+    
+    def convert_xgboost_to_code(self, booster, feature_names: List[str], num_trees: int,
+                                base_score: float = 0.0) -> str:
         """
         Convert XGBoost booster to target language code.
         
@@ -198,6 +220,7 @@ class XGBoostLanguageConverter:
             booster: XGBoost booster object
             feature_names: List of feature names (will be converted to indices)
             num_trees: Maximum number of trees to convert
+            base_score: Initial prediction the tree outputs are added to (Rust only)
             
         Returns:
             Generated code string
@@ -205,9 +228,6 @@ class XGBoostLanguageConverter:
         # Convert feature names to indices for ZoKrates array access
         feature_indices = self.feature_names_to_indices(feature_names)
         
-        # Get tree dumps
-        trees_dump = booster.get_dump(dump_format='json')
-        
         # Start building code
         code_parts = []
         
@@ -216,25 +236,14 @@ class XGBoostLanguageConverter:
             code_parts.append(self.templates["header"])
         
         # Generate tree code
-        tree_codes = []
-        for tree_idx, tree_dump in enumerate(trees_dump):
-            if tree_idx >= num_trees:
-                break
-                
-            tree_structure = json.loads(tree_dump)
-            tree_logic = self._generate_tree_logic(tree_structure, feature_indices, 1)
-            
-            if "tree" in self.templates:
-                tree_code = self.templates["tree"].format(
-                    tree_idx=tree_idx,
-                    tree_logic=tree_logic
-                )
-                tree_codes.append(tree_code)
+        tree_codes = self._generate_trees_code(booster, feature_indices, num_trees)
         
         # Generate main function
         if "main" in self.templates:
+            precision = self.config["data_types"]["fixed_point"]["precision_multiplier"]
             main_code = self.templates["main"].format(
                 num_features=len(feature_names),
+                base_score=int(np.round(base_score * precision)),
                 tree_code="\n".join(tree_codes)
             )
             code_parts.append(main_code)
@@ -248,6 +257,151 @@ class XGBoostLanguageConverter:
         
         return "\n".join(code_parts)
     
+    def convert_agreement_to_code(self, booster_a, booster_b, feature_names: List[str],
+                                  num_trees: int, reveal_flag: bool = True) -> str:
+        """
+        Convert two XGBoost boosters into a single agreement circuit.
+        
+        Both models are evaluated on the same private feature vector and only
+        the comparison of their predictions against a public bound leaves the
+        circuit: either the agreement flag itself (reveal_flag=True) or nothing,
+        in which case the circuit asserts agreement and proving fails otherwise.
+        
+        The thresholds and leaves of each model are private inputs (see
+        model_parameters), checked against the public Poseidon commitments
+        `commitment_a` and `commitment_b` (see convert_commitment_to_code).
+        Only the tree shapes and the features they split on are fixed in the
+        circuit.
+        
+        Args:
+            booster_a: XGBoost booster object for the current model version
+            booster_b: XGBoost booster object for the candidate model version
+            feature_names: List of feature names shared by both models
+            num_trees: Maximum number of trees to convert from each model
+            reveal_flag: Return the agreement flag instead of asserting it
+            
+        Returns:
+            Generated code string
+        """
+        if self.language != "zokrates":
+            raise NotImplementedError(f"Agreement circuits not implemented for {self.language}")
+        
+        feature_indices = self.feature_names_to_indices(feature_names)
+        num_features = len(feature_names)
+        
+        code_parts = [self.templates["header"], self.templates["commitment"].format()]
+        num_params = []
+        for fn_name, booster in (("model_a", booster_a), ("model_b", booster_b)):
+            params = []
+            tree_codes = self._generate_trees_code(booster, feature_indices, num_trees, params)
+            num_params.append(len(params))
+            code_parts.append(self.templates["model"].format(
+                fn_name=fn_name,
+                num_features=num_features,
+                num_params=len(params),
+                tree_code="\n".join(tree_codes)
+            ))
+        
+        if reveal_flag:
+            main_signature = "-> bool "
+            main_result = "return agree;"
+        else:
+            main_signature = ""
+            main_result = "assert(agree);"
+        code_parts.append(self.templates["agreement"].format(
+            num_features=num_features,
+            num_params_a=num_params[0],
+            num_params_b=num_params[1],
+            main_signature=main_signature,
+            main_result=main_result
+        ))
+        
+        return "\n".join(code_parts)
+    
+    def convert_commitment_to_code(self, booster, num_trees: int) -> str:
+        """
+        Convert an XGBoost booster into a program computing its model commitment.
+        
+        The program takes the private parameters from model_parameters and
+        returns the Poseidon commitment the agreement circuit checks them
+        against, so a model owner can publish it without revealing the model.
+        
+        Args:
+            booster: XGBoost booster object
+            num_trees: Maximum number of trees to commit to
+            
+        Returns:
+            Generated code string
+        """
+        if self.language != "zokrates":
+            raise NotImplementedError(f"Model commitments not implemented for {self.language}")
+        
+        num_params = len(self.model_parameters(booster, num_trees))
+        return "\n".join([
+            self.templates["header"],
+            self.templates["commitment"].format(),
+            self.templates["commit"].format(num_params=num_params)
+        ])
+    
+    def model_parameters(self, booster, num_trees: int) -> List[int]:
+        """
+        Scaled thresholds and leaves of a booster, in the order the agreement
+        circuit and the commitment program read them.
+        
+        Args:
+            booster: XGBoost booster object
+            num_trees: Maximum number of trees to read
+            
+        Returns:
+            Parameter values scaled by 10^10
+        """
+        params = []
+        for tree_dump in booster.get_dump(dump_format='json')[:num_trees]:
+            self._collect_parameters(json.loads(tree_dump), params)
+        return params
+    
+    def _collect_parameters(self, tree_structure: Dict, params: List[int]) -> None:
+        """Append a tree's scaled thresholds and leaves in the order _generate_tree_logic references them."""
+        if 'leaf' in tree_structure:
+            params.append(int(np.round(tree_structure['leaf'] * 10000000000, 0)))
+            return
+        params.append(int(np.round(tree_structure['split_condition'] * 10000000000, 0)))
+        for child in tree_structure['children']:
+            self._collect_parameters(child, params)
+    
+    def parameters_to_args(self, params: List[int]) -> List[str]:
+        """Witness arguments (sgn, v pairs) passing parameters to `zokrates compute-witness -a`."""
+        args = []
+        for value in params:
+            args.extend(['1' if value >= 0 else '0', str(abs(value))])
+        return args
+    
+    def _generate_trees_code(self, booster, feature_indices: List[str], num_trees: int,
+                             params: List[int] = None) -> List[str]:
+        """
+        Generate the per-tree code blocks for the first num_trees trees of a booster.
+        
+        With a params list, thresholds and leaves are collected into it (see _generate_tree_logic).
+        """
+        trees_dump = booster.get_dump(dump_format='json')
+        
+        tree_codes = []
+        for tree_idx, tree_dump in enumerate(trees_dump):
+            if tree_idx >= num_trees:
+                break
+                
+            tree_structure = json.loads(tree_dump)
+            tree_logic = self._generate_tree_logic(tree_structure, feature_indices, 1, params)
+            
+            if "tree" in self.templates:
+                tree_code = self.templates["tree"].format(
+                    tree_idx=tree_idx,
+                    tree_logic=tree_logic
+                )
+                tree_codes.append(tree_code)
+        
+        return tree_codes
+    
     def feature_names_to_indices(self, feature_names: List[str]) -> List[str]:
         """Convert feature names to index format."""
         return [str(i) for i in range(len(feature_names))]
@@ -0,0 +1,71 @@
+"""
+Reference leaf values from native XGBoost for the Rust cross-check harness.
+
+Usage: python xgb_reference.py <model> <samples.csv> <num_trees>
+
+Prints one line per sample with the leaf value reached in each of the first
+num_trees trees, comma-separated. The CSV is read the same way as the Rust side
+reads it: an optional header line, and empty fields as the -99999 sentinel.
+"""
+
+import json
+import sys
+from typing import Dict
+
+import numpy as np
+import xgboost as xgb
+
+MISSING_FIELD = -99999.0
+
+
+def read_samples(path: str) -> np.ndarray:
+    """Load the samples CSV into a float32 matrix."""
+    rows = []
+    with open(path, 'r') as f:
+        for index, line in enumerate(f):
+            line = line.strip()
+            if not line:
+                continue
+            fields = [field.strip() for field in line.split(',')]
+            if index == 0:
+                try:
+                    float(fields[0])
+                except ValueError:
+                    continue
+            rows.append([float(field) if field else MISSING_FIELD for field in fields])
+    return np.array(rows, dtype=np.float32)
+
+
+def leaf_values(tree: Dict) -> Dict[int, float]:
+    """Map node id to leaf value for one tree of a JSON dump."""
+    values = {}
+    stack = [tree]
+    while stack:
+        node = stack.pop()
+        if 'leaf' in node:
+            values[node['nodeid']] = node['leaf']
+        else:
+            stack.extend(node['children'])
+    return values
+
+
+def main() -> None:
+    if len(sys.argv) != 4:
+        sys.exit(__doc__)
+    model_path, samples_path, num_trees = sys.argv[1], sys.argv[2], int(sys.argv[3])
+
+    bst = xgb.Booster()
+    bst.load_model(model_path)
+    trees = [leaf_values(json.loads(dump)) for dump in bst.get_dump(dump_format='json')[:num_trees]]
+    if len(trees) != num_trees:
+        sys.exit(f"model has {len(trees)} trees, {num_trees} requested")
+
+    # Keep -99999 an ordinary value, as it was in training
+    matrix = xgb.DMatrix(read_samples(samples_path), missing=np.nan)
+    leaves = bst.predict(matrix, pred_leaf=True, iteration_range=(0, num_trees))
+    for row in np.atleast_2d(leaves):
+        print(','.join(repr(trees[tree][int(node)]) for tree, node in enumerate(row[:num_trees])))
+
+
+if __name__ == '__main__':
+    main()
@@ -0,0 +1,25 @@
+// Inputs shared by the benchmarks, drawn from the crate's `Lcg` so every run sees the same data
+// Each bench includes this module with `mod common;` and uses only part of it.
+#![allow(dead_code)]
+
+use rainfall_prediction::{Lcg, Node, NUM_FEATURES};
+
+/// Feature value in [-10, 10) on a 0.01 grid, scaled by 10^10
+pub fn random_value(rng: &mut Lcg) -> i64 {
+    (rng.next_u31() % 2000) as i64 * 100_000_000 - 100_000_000_000
+}
+
+/// `size` feature vectors of the model's width
+pub fn random_batch(rng: &mut Lcg, size: usize) -> Vec<Vec<i64>> {
+    (0..size).map(|_| (0..NUM_FEATURES).map(|_| random_value(rng)).collect()).collect()
+}
+
+/// Random complete tree of the given depth over the model's features
+pub fn random_node(rng: &mut Lcg, depth: usize) -> Node {
+    if depth == 0 {
+        return Node::Leaf((rng.next_u31() % 2_000_000_000) as i64 - 1_000_000_000);
+    }
+    let feature = (rng.next_u31() % NUM_FEATURES as u64) as usize;
+    let threshold = random_value(rng);
+    Node::split(feature, threshold, random_node(rng, depth - 1), random_node(rng, depth - 1))
+}
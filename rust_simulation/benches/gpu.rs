@@ -0,0 +1,65 @@
+// GPU batch benchmark
+// Scores a large batch with the unrolled `xgboost_predict`, the scalar `Ensemble::eval`
+// interpreter and `GpuForest`, on the bundled model and on a synthetic deep ensemble, and checks
+// that all paths agree. Upload and readback are included in the GPU time.
+//
+//   cargo bench --features gpu --bench gpu [-- <batch_size>]
+
+use std::env;
+use std::hint::black_box;
+use std::time::Instant;
+
+use rainfall_prediction::{builtin_model, xgboost_predict_batch, Ensemble, GpuForest, Lcg, Tree};
+
+mod common;
+use common::{random_batch, random_node};
+
+const ROUNDS: usize = 5;
+
+/// Nanoseconds per sample of `score` over `batch`
+fn time<F: Fn(&[Vec<i64>]) -> Vec<i64>>(batch: &[Vec<i64>], score: F) -> (f64, Vec<i64>) {
+    let predictions = score(batch);
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(score(black_box(batch)));
+    }
+    (start.elapsed().as_nanos() as f64 / (ROUNDS * batch.len()) as f64, predictions)
+}
+
+fn report(name: &str, model: &Ensemble, forest: &GpuForest, batch: &[Vec<i64>], unrolled: bool) {
+    let (scalar, expected) = time(batch, |b| b.iter().map(|f| model.eval(f)).collect());
+    let (gpu, predictions) = time(batch, |b| forest.predict_batch(b).expect("GPU scoring"));
+    assert_eq!(predictions, expected, "{}: GPU and scalar predictions differ", name);
+
+    println!("{} ({} trees)", name, model.num_trees());
+    if unrolled {
+        let (compiled, predictions) = time(batch, xgboost_predict_batch);
+        assert_eq!(predictions, expected, "{}: unrolled and scalar predictions differ", name);
+        println!("  {:<12} {:>10.1} ns/sample", "unrolled", compiled);
+    }
+    println!("  {:<12} {:>10.1} ns/sample", "scalar", scalar);
+    println!("  {:<12} {:>10.1} ns/sample  ({:.2}x)", "gpu", gpu, scalar / gpu);
+}
+
+fn main() {
+    // cargo bench passes --bench; only positional numbers are ours
+    let batch_size = env::args().skip(1).find_map(|a| a.parse().ok()).unwrap_or(1 << 16);
+
+    let mut rng = Lcg::new(7);
+    let batch = random_batch(&mut rng, batch_size);
+
+    let model = builtin_model();
+    let forest = match GpuForest::new(&model) {
+        Ok(forest) => forest,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    println!("batch of {} samples on {}", batch_size, forest.adapter_name());
+    report("bundled model", &model, &forest, &batch, true);
+
+    let trees = (0..200).map(|_| Tree::new(random_node(&mut rng, 8))).collect();
+    let deep = Ensemble::new(trees, rainfall_prediction::PRECISION_MULTIPLIER);
+    report("synthetic depth 8", &deep, &GpuForest::new(&deep).expect("GPU forest"), &batch, false);
+}
@@ -0,0 +1,101 @@
+// Code layout benchmark
+// Generates a synthetic 500-tree model, emits it in every `RustLayout`, compiles each source with
+// rustc -O and reports the compile time and the time per prediction of the compiled binary.
+// All layouts must agree on the predictions, which the benchmark checks.
+//
+//   cargo bench --bench layouts [-- <num_trees> <depth>]
+
+use std::env;
+use std::fs;
+use std::process::Command;
+use std::time::Instant;
+
+use rainfall_prediction::codegen::{generate_rust_source_with_layout, RustLayout};
+use rainfall_prediction::{Ensemble, Lcg, Tree, NUM_FEATURES};
+
+mod common;
+use common::random_node;
+
+/// Timing loop appended to every generated source
+const BENCH_MAIN: &str = r#"
+fn main() {
+    let mut state = 12345u64;
+    let inputs: Vec<Vec<i64>> = (0..2000)
+        .map(|_| {
+            (0..NUM_FEATURES)
+                .map(|_| {
+                    state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                    ((state >> 33) % 2000) as i64 * 100_000_000 - 100_000_000_000
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut checksum = 0i64;
+    let start = std::time::Instant::now();
+    for _ in 0..ROUNDS {
+        for x in &inputs {
+            checksum = checksum.wrapping_add(std::hint::black_box(xgboost_predict(std::hint::black_box(x))));
+        }
+    }
+    let per_prediction = start.elapsed().as_nanos() as f64 / (ROUNDS * inputs.len()) as f64;
+    println!("{} {}", per_prediction, checksum);
+}
+"#;
+
+const ROUNDS: usize = 20;
+
+fn main() {
+    // cargo bench passes --bench; only positional numbers are ours
+    let numbers: Vec<usize> = env::args().skip(1).filter_map(|a| a.parse().ok()).collect();
+    let num_trees = numbers.first().copied().unwrap_or(500);
+    let depth = numbers.get(1).copied().unwrap_or(6);
+
+    let mut rng = Lcg::new(2024);
+    let trees = (0..num_trees).map(|_| Tree::new(random_node(&mut rng, depth))).collect();
+    let model = Ensemble::new(trees, rainfall_prediction::PRECISION_MULTIPLIER).with_num_features(NUM_FEATURES);
+
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let dir = env::temp_dir().join(format!("zkml_layouts_{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("create temp dir");
+
+    println!("{} trees of depth {}", num_trees, depth);
+    println!("{:<10} {:>12} {:>14} {:>14}", "layout", "source (KB)", "compile (s)", "predict (ns)");
+
+    let mut checksums = Vec::new();
+    for (name, layout) in [("unrolled", RustLayout::Unrolled), ("per-tree", RustLayout::PerTree), ("table", RustLayout::Table)] {
+        let source = format!(
+            "{}\nconst NUM_FEATURES: usize = {};\nconst ROUNDS: usize = {};\n{}",
+            generate_rust_source_with_layout(&model, NUM_FEATURES, layout),
+            NUM_FEATURES,
+            ROUNDS,
+            BENCH_MAIN
+        );
+        let source_path = dir.join(format!("{}.rs", name));
+        let binary_path = dir.join(name);
+        fs::write(&source_path, &source).expect("write generated source");
+
+        let start = Instant::now();
+        let status = Command::new(&rustc)
+            .args(["--edition", "2021", "-O", "-A", "warnings", "-o"])
+            .arg(&binary_path)
+            .arg(&source_path)
+            .status()
+            .expect("run rustc");
+        let compile = start.elapsed().as_secs_f64();
+        assert!(status.success(), "{} layout failed to compile", name);
+
+        let output = Command::new(&binary_path).output().expect("run benchmark binary");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut fields = stdout.split_whitespace();
+        let per_prediction: f64 = fields.next().and_then(|v| v.parse().ok()).expect("timing");
+        let checksum: i64 = fields.next().and_then(|v| v.parse().ok()).expect("checksum");
+        checksums.push(checksum);
+
+        println!("{:<10} {:>12} {:>14.2} {:>14.1}", name, source.len() / 1024, compile, per_prediction);
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+    assert!(checksums.windows(2).all(|w| w[0] == w[1]), "layouts disagree: {:?}", checksums);
+    println!("All layouts produce identical predictions");
+}
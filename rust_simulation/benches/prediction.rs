@@ -0,0 +1,138 @@
+// Criterion benchmark suite
+// Single prediction (unrolled code vs the `Ensemble` interpreter, its oblivious mode and the decision-table layout),
+// deep synthetic trees, batch prediction, trace generation, witness preparation and model loading. After the run the mean of every benchmark is compared with its
+// limit in benches/thresholds.json and the process fails if one regressed past it.
+//
+//   cargo bench --bench prediction [-- <filter>]
+//   cargo bench --bench prediction -- --save-baseline before    (then --baseline before to compare)
+
+use std::env;
+use std::fs;
+use std::hint::black_box;
+use std::path::PathBuf;
+use std::process;
+
+use criterion::{criterion_group, Criterion, Throughput};
+use rainfall_prediction::json::{self, JsonValue};
+use rainfall_prediction::witness;
+use rainfall_prediction::{
+    builtin_model, xgboost_predict, xgboost_predict_batch, xgboost_predict_with_trace, Ensemble, Lcg, Model, Tree,
+    MODEL_JSON, PRECISION_MULTIPLIER,
+};
+
+mod common;
+use common::{random_batch, random_node};
+
+const BATCH_SIZE: usize = 1024;
+
+fn sample_batch(size: usize) -> Vec<Vec<i64>> {
+    random_batch(&mut Lcg::new(42), size)
+}
+
+fn single(c: &mut Criterion) {
+    let model = builtin_model();
+    let table = model.to_decision_table();
+    let features = sample_batch(1).remove(0);
+    let mut group = c.benchmark_group("predict");
+    group.bench_function("unrolled", |b| b.iter(|| xgboost_predict(black_box(&features))));
+    group.bench_function("interpreter", |b| b.iter(|| model.eval(black_box(&features))));
+    group.bench_function("oblivious", |b| b.iter(|| model.eval_oblivious(black_box(&features))));
+    group.bench_function("decision_table", |b| b.iter(|| table.eval(black_box(&features))));
+    group.finish();
+}
+
+fn deep(c: &mut Criterion) {
+    // 100 complete trees of depth 10, where the decision-table layout pays off
+    let mut rng = Lcg::new(7);
+    let trees = (0..100).map(|_| Tree::new(random_node(&mut rng, 10))).collect();
+    let model = Ensemble::new(trees, PRECISION_MULTIPLIER);
+    let table = model.to_decision_table();
+    let features = sample_batch(1).remove(0);
+    let mut group = c.benchmark_group("deep");
+    group.bench_function("interpreter", |b| b.iter(|| model.eval(black_box(&features))));
+    group.bench_function("decision_table", |b| b.iter(|| table.eval(black_box(&features))));
+    group.finish();
+}
+
+fn batch(c: &mut Criterion) {
+    let batch = sample_batch(BATCH_SIZE);
+    let mut group = c.benchmark_group("batch");
+    group.throughput(Throughput::Elements(BATCH_SIZE as u64));
+    group.bench_function("sequential", |b| b.iter(|| xgboost_predict_batch(black_box(&batch))));
+    #[cfg(feature = "parallel")]
+    group.bench_function("parallel", |b| {
+        b.iter(|| rainfall_prediction::xgboost_predict_batch_parallel(black_box(&batch)))
+    });
+    group.finish();
+}
+
+fn trace(c: &mut Criterion) {
+    let features = sample_batch(1).remove(0);
+    let mut group = c.benchmark_group("trace");
+    group.bench_function("builtin", |b| b.iter(|| xgboost_predict_with_trace(black_box(&features))));
+    group.finish();
+
+    // Witness bundles of a batch (on the rayon pool with --features parallel)
+    let model = builtin_model();
+    let batch = sample_batch(BATCH_SIZE);
+    let mut group = c.benchmark_group("witness");
+    group.throughput(Throughput::Elements(BATCH_SIZE as u64));
+    group.sample_size(10);
+    group.bench_function("prepare_batch", |b| b.iter(|| witness::prepare_batch(&model, black_box(&batch))));
+    group.finish();
+}
+
+fn loading(c: &mut Criterion) {
+    let bytes = builtin_model().to_bytes();
+    let mut group = c.benchmark_group("load");
+    group.bench_function("json", |b| b.iter(|| Model::from_xgboost_json(black_box(MODEL_JSON)).unwrap()));
+    group.bench_function("binary", |b| b.iter(|| Ensemble::from_bytes(black_box(&bytes)).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, single, deep, batch, trace, loading);
+
+/// Directory criterion writes its estimates to
+fn criterion_dir() -> PathBuf {
+    let target = env::var_os("CARGO_TARGET_DIR").map(PathBuf::from).unwrap_or_else(|| PathBuf::from("target"));
+    target.join("criterion")
+}
+
+/// Compare each measured mean (ns) with its limit
+///
+/// A limit whose benchmark left no estimates fails too, so a renamed or missing benchmark cannot
+/// pass unchecked.
+fn check_thresholds() -> bool {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/benches/thresholds.json");
+    let text = fs::read_to_string(path).expect("read benches/thresholds.json");
+    let thresholds = json::parse(&text).expect("valid thresholds.json");
+    let limits = thresholds.as_object().expect("thresholds.json is an object of benchmark id -> ns");
+
+    let mut passed = true;
+    for (id, limit) in limits {
+        let limit = limit.as_f64().expect("threshold in ns");
+        let estimates = criterion_dir().join(id).join("new").join("estimates.json");
+        let Ok(text) = fs::read_to_string(&estimates) else {
+            eprintln!("unchecked: {} has a limit but no estimates at {}", id, estimates.display());
+            passed = false;
+            continue;
+        };
+        let mean = json::parse(&text)
+            .ok()
+            .and_then(|e| e.get("mean").and_then(|m| m.get("point_estimate")).and_then(JsonValue::as_f64))
+            .expect("criterion estimates carry a mean");
+        if mean > limit {
+            eprintln!("regression: {} took {:.0} ns, limit {:.0} ns", id, mean, limit);
+            passed = false;
+        }
+    }
+    passed
+}
+
+fn main() {
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+    if !check_thresholds() {
+        process::exit(1);
+    }
+}
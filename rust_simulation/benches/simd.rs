@@ -0,0 +1,61 @@
+// SIMD batch benchmark
+// Scores the same batch with the unrolled `xgboost_predict`, the scalar `Ensemble::eval`
+// interpreter and the vectorized `SimdForest`, on the bundled model and on a synthetic deep
+// ensemble, and checks that all paths agree.
+//
+//   cargo +nightly bench --features simd --bench simd [-- <batch_size>]
+
+use std::env;
+use std::hint::black_box;
+use std::time::Instant;
+
+use rainfall_prediction::{builtin_model, xgboost_predict_batch, Ensemble, Lcg, SimdForest, Tree};
+
+mod common;
+use common::{random_batch, random_node};
+
+const ROUNDS: usize = 20;
+
+/// Batch scoring function
+type Scorer = fn(&[Vec<i64>]) -> Vec<i64>;
+
+/// Nanoseconds per sample of `score` over `batch`
+fn time<F: Fn(&[Vec<i64>]) -> Vec<i64>>(batch: &[Vec<i64>], score: F) -> (f64, Vec<i64>) {
+    let predictions = score(batch);
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(score(black_box(batch)));
+    }
+    (start.elapsed().as_nanos() as f64 / (ROUNDS * batch.len()) as f64, predictions)
+}
+
+fn report(name: &str, model: &Ensemble, batch: &[Vec<i64>], unrolled: Option<Scorer>) {
+    let forest = SimdForest::new(model);
+    let (scalar, expected) = time(batch, |b| b.iter().map(|f| model.eval(f)).collect());
+    let (simd, predictions) = time(batch, |b| forest.predict_batch(b));
+    assert_eq!(predictions, expected, "{}: SIMD and scalar predictions differ", name);
+
+    println!("{} ({} trees)", name, model.num_trees());
+    if let Some(unrolled) = unrolled {
+        let (compiled, predictions) = time(batch, unrolled);
+        assert_eq!(predictions, expected, "{}: unrolled and scalar predictions differ", name);
+        println!("  {:<12} {:>10.1} ns/sample", "unrolled", compiled);
+    }
+    println!("  {:<12} {:>10.1} ns/sample", "scalar", scalar);
+    println!("  {:<12} {:>10.1} ns/sample  ({:.2}x)", "simd", simd, scalar / simd);
+}
+
+fn main() {
+    // cargo bench passes --bench; only positional numbers are ours
+    let batch_size = env::args().skip(1).find_map(|a| a.parse().ok()).unwrap_or(4096);
+
+    let mut rng = Lcg::new(7);
+    let batch = random_batch(&mut rng, batch_size);
+
+    println!("batch of {} samples", batch_size);
+    report("bundled model", &builtin_model(), &batch, Some(xgboost_predict_batch));
+
+    let trees = (0..200).map(|_| Tree::new(random_node(&mut rng, 8))).collect();
+    let deep = Ensemble::new(trees, rainfall_prediction::PRECISION_MULTIPLIER);
+    report("synthetic depth 8", &deep, &batch, None);
+}
@@ -0,0 +1,33 @@
+// Build script
+// With the `grpc` feature, generates the tonic server and client of proto/inference.proto with
+// tonic-build's manual generator, so no protoc is needed (the messages are written by hand in
+// src/grpc.rs). Other builds do nothing.
+
+fn main() {
+    #[cfg(feature = "grpc")]
+    generate_grpc_service();
+}
+
+#[cfg(feature = "grpc")]
+fn generate_grpc_service() {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    println!("cargo:rerun-if-changed=build.rs");
+    let method = |name: &str, route: &str, input: &str, output: &str| {
+        Method::builder()
+            .name(name)
+            .route_name(route)
+            .input_type(format!("crate::grpc::{}", input))
+            .output_type(format!("crate::grpc::{}", output))
+            .codec_path("tonic_prost::ProstCodec")
+            .build()
+    };
+    let service = Service::builder()
+        .name("Inference")
+        .package("rainfall.inference")
+        .method(method("predict", "Predict", "PredictRequest", "PredictResponse"))
+        .method(method("predict_batch", "PredictBatch", "PredictBatchRequest", "PredictBatchResponse"))
+        .method(method("prove_prediction", "ProvePrediction", "PredictRequest", "ProveResponse"))
+        .build();
+    Builder::new().compile(&[service]);
+}
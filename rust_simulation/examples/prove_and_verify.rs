@@ -0,0 +1,114 @@
+// End-to-end proof of one prediction
+// Loads a model, scales a sample feature vector, proves the prediction with the selected backend,
+// verifies the proof and prints circuit size, proof size and timings. Every step is checked
+// against the simulator (prediction, features hash, serialization round trip, rejection of a wrong
+// prediction), and the process exits non-zero on any mismatch, so it doubles as the integration
+// test of the ZK pipeline.
+//
+//   cargo +nightly run --release --features plonky2 --example prove_and_verify [-- --model <dump.json>]
+//   cargo +nightly run --release --features plonky2,r1cs --example prove_and_verify -- --backend r1cs
+//
+// The R1CS backend has no proving system attached yet: it synthesizes the constraints and checks
+// that the witness satisfies them.
+
+use std::env;
+use std::process;
+use std::time::{Duration, Instant};
+
+use rainfall_prediction::zk::plonky2::{proof_features_hash, Plonky2Circuit};
+use rainfall_prediction::{
+    builtin_model, from_fixed_point, hash_features, rescale_round, to_fixed_point, Model, PRECISION_MULTIPLIER,
+};
+
+/// Radar measurements of the interactive demo, padded with zeros to the model's feature count
+fn sample_features(model: &Model) -> Vec<i64> {
+    let mut raw = vec![0.0220286213, 0.045, -0.018, 0.12, -0.005, 0.089, 0.234, 0.156, 0.078, 0.045];
+    raw.resize(model.num_features(), 0.0);
+    raw.iter()
+        .map(|&x| rescale_round(to_fixed_point(x), PRECISION_MULTIPLIER, model.scale()))
+        .collect()
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("FAILED: {}", message);
+    process::exit(1);
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn plonky2(model: &Model, features: &[i64], expected: i64) {
+    let start = Instant::now();
+    let circuit = Plonky2Circuit::new(model);
+    println!("circuit:  {} rows, built in {:.0} ms", circuit.num_rows(), ms(start.elapsed()));
+
+    let start = Instant::now();
+    let proof = circuit.prove_with_info(features).unwrap_or_else(|e| fail(format!("proving: {}", e)));
+    let bytes = proof.to_bytes();
+    println!("proof:    {} bytes, proved in {:.0} ms", bytes.len(), ms(start.elapsed()));
+
+    let start = Instant::now();
+    let proof = circuit.read_proof(&bytes).unwrap_or_else(|e| fail(format!("reading the proof back: {}", e)));
+    circuit.verify_with_info(&proof, expected).unwrap_or_else(|e| fail(format!("verification: {}", e)));
+    println!("verified: in {:.1} ms", ms(start.elapsed()));
+
+    if proof_features_hash(&proof.proof) != Some(hash_features(features)) {
+        fail("the proof commits to a different features hash".to_string());
+    }
+    if circuit.verify(&proof.proof, expected + 1).is_ok() {
+        fail("a wrong prediction was accepted".to_string());
+    }
+}
+
+#[cfg(feature = "r1cs")]
+fn r1cs(model: &Model, features: &[i64], expected: i64) {
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use rainfall_prediction::r1cs::{field_to_fixed, ModelCircuit};
+
+    let start = Instant::now();
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    ModelCircuit::new(model, features.to_vec())
+        .generate_constraints(cs.clone())
+        .unwrap_or_else(|e| fail(format!("synthesis: {}", e)));
+    println!("circuit:  {} constraints, synthesized in {:.0} ms", cs.num_constraints(), ms(start.elapsed()));
+
+    if !cs.is_satisfied().unwrap_or(false) {
+        fail("the witness does not satisfy the constraints".to_string());
+    }
+    let prediction = field_to_fixed(cs.borrow().expect("constraint system").instance_assignment[1]);
+    if prediction != Some(expected) {
+        fail(format!("the circuit output {:?}, the simulator {}", prediction, expected));
+    }
+    println!("verified: witness satisfies every constraint (no proving system attached)");
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let flag = |name: &str| args.iter().position(|a| a == name).and_then(|i| args.get(i + 1)).cloned();
+    let backend = flag("--backend").unwrap_or_else(|| "plonky2".to_string());
+
+    let model = match flag("--model") {
+        Some(path) => Model::from_xgboost_json_file(&path).unwrap_or_else(|e| fail(format!("loading {}: {}", path, e))),
+        None => builtin_model(),
+    };
+    let features = sample_features(&model);
+    let expected = model.eval(&features);
+    println!(
+        "model:    {} trees, {} features; prediction {} ({:.6})",
+        model.num_trees(),
+        model.num_features(),
+        expected,
+        from_fixed_point(rescale_round(expected, model.scale(), PRECISION_MULTIPLIER))
+    );
+    println!("backend:  {}", backend);
+
+    match backend.as_str() {
+        "plonky2" => plonky2(&model, &features, expected),
+        #[cfg(feature = "r1cs")]
+        "r1cs" => r1cs(&model, &features, expected),
+        other => fail(format!("unknown backend `{}` (plonky2, or r1cs with --features r1cs)", other)),
+    }
+    println!("OK");
+}
@@ -0,0 +1,7 @@
+// C library of the rainfall model
+// Builds the `ffi` feature of rainfall_prediction as a cdylib and a staticlib, so that C and C++ code
+// links the `zkml_*` functions of include/zkml.h with a plain `cargo build -p rainfall_prediction_ffi`.
+// The main crate stays an rlib: a cdylib target there would need an allocator and a panic handler in
+// its no_std builds.
+
+pub use rainfall_prediction::ffi::*;
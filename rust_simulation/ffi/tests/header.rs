@@ -0,0 +1,50 @@
+// Checked-in C header
+// Regenerates include/zkml.h with cbindgen and the crate's cbindgen.toml, and fails when the copy
+// in the repository differs, so a change of the C API cannot ship with a stale header. An
+// intended change is made by rewriting the header and reviewing its diff:
+//
+//   HEADER_UPDATE=1 cargo test -p rainfall_prediction_ffi --test header
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use rainfall_prediction_ffi::{zkml_num_features, zkml_predict, zkml_scale, ZkmlStatus};
+
+fn crate_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..")
+}
+
+#[test]
+fn test_header_is_current() {
+    let dir = crate_dir();
+    let config = cbindgen::Config::from_file(dir.join("cbindgen.toml")).expect("read cbindgen.toml");
+    let bindings = cbindgen::Builder::new()
+        .with_crate(&dir)
+        .with_config(config)
+        .generate()
+        .expect("generate the header");
+    let mut generated = Vec::new();
+    bindings.write(&mut generated);
+
+    let path = dir.join("include/zkml.h");
+    if env::var_os("HEADER_UPDATE").is_some() {
+        fs::write(&path, &generated).expect("write header");
+    }
+    let checked_in = fs::read(&path).expect("read header");
+    assert!(
+        generated == checked_in,
+        "{} is stale; regenerate it with HEADER_UPDATE=1 cargo test -p rainfall_prediction_ffi --test header",
+        path.display()
+    );
+}
+
+#[test]
+fn test_library_exports() {
+    let features = vec![0i64; zkml_num_features()];
+    let mut out = 0;
+    let status = unsafe { zkml_predict(features.as_ptr(), features.len(), &mut out) };
+    assert_eq!(status, ZkmlStatus::Ok);
+    assert_eq!(out, rainfall_prediction::xgboost_predict(&features));
+    assert_eq!(zkml_scale(), rainfall_prediction::PRECISION_MULTIPLIER);
+}
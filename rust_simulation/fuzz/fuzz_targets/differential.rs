@@ -0,0 +1,148 @@
+// Differential fuzz target
+// Builds a random small ensemble and a few feature vectors from the fuzzer's bytes and requires
+// every execution mode to agree with the branchy `Ensemble::eval`: the oblivious evaluator, the
+// witness trace, the SIMD batch traversal (feature `simd`) and the R1CS circuit (feature `r1cs`).
+// Values are drawn from a pool that favours the edges of the arithmetic (exact thresholds and
+// their neighbours, MISSING_VALUE, i64 extremes) so saturation and tie semantics get exercised.
+//
+//   cargo +nightly fuzz run differential
+//   cargo +nightly fuzz run differential --features r1cs,simd
+
+#![no_main]
+
+use arbitrary::{Result, Unstructured};
+use libfuzzer_sys::fuzz_target;
+
+use rainfall_prediction::{BitSet, Combiner, Ensemble, Node, Tree, MISSING_VALUE, PRECISION_MULTIPLIER};
+
+const MAX_FEATURES: usize = 8;
+const MAX_TREES: usize = 8;
+const MAX_DEPTH: usize = 4;
+const MAX_VECTORS: usize = 4;
+
+/// Values seen so far, so features can land exactly on (or next to) a threshold
+struct Sampler<'a, 'b> {
+    u: &'b mut Unstructured<'a>,
+    seen: Vec<i64>,
+}
+
+impl Sampler<'_, '_> {
+    fn value(&mut self) -> Result<i64> {
+        let value = match self.u.int_in_range(0..=9u8)? {
+            0 => 0,
+            1 => MISSING_VALUE,
+            2 => i64::MAX,
+            3 => i64::MIN,
+            4 => self.u.int_in_range(-1_000..=1_000i64)? * PRECISION_MULTIPLIER / 100,
+            5..=7 if !self.seen.is_empty() => {
+                let index = self.u.choose_index(self.seen.len())?;
+                self.seen[index].saturating_add(self.u.int_in_range(-1..=1i64)?)
+            }
+            _ => self.u.arbitrary()?,
+        };
+        self.seen.push(value);
+        Ok(value)
+    }
+
+    fn node(&mut self, num_features: usize, depth: usize) -> Result<Node> {
+        let kind = if depth == 0 { 0 } else { self.u.int_in_range(0..=3u8)? };
+        let feature = self.u.choose_index(num_features)?;
+        match kind {
+            0 => Ok(Node::Leaf(self.value()?)),
+            1 => {
+                let unit = *self.u.choose(&[1, PRECISION_MULTIPLIER, 3 * PRECISION_MULTIPLIER])?;
+                let mut categories = BitSet::new();
+                for _ in 0..self.u.int_in_range(0..=4u8)? {
+                    let category = self.u.int_in_range(0..=130u32)?;
+                    categories.insert(category);
+                    self.seen.push(category as i64 * unit);
+                }
+                let left = self.node(num_features, depth - 1)?;
+                let right = self.node(num_features, depth - 1)?;
+                Ok(Node::categorical(feature, categories, unit, left, right))
+            }
+            _ => {
+                let threshold = self.value()?;
+                let left = self.node(num_features, depth - 1)?;
+                let right = self.node(num_features, depth - 1)?;
+                Ok(Node::split(feature, threshold, left, right))
+            }
+        }
+    }
+}
+
+fn build(u: &mut Unstructured) -> Result<(Ensemble, Vec<Vec<i64>>)> {
+    let mut sampler = Sampler { u, seen: Vec::new() };
+    let num_features = sampler.u.int_in_range(1..=MAX_FEATURES)?;
+    let num_trees = sampler.u.int_in_range(1..=MAX_TREES)?;
+    let mut trees = Vec::with_capacity(num_trees);
+    for _ in 0..num_trees {
+        let depth = sampler.u.int_in_range(0..=MAX_DEPTH)?;
+        trees.push(Tree::new(sampler.node(num_features, depth)?));
+    }
+    let combiner = if sampler.u.arbitrary()? { Combiner::Average } else { Combiner::Sum };
+    let base_score = sampler.value()?;
+    let model = Ensemble::new(trees, PRECISION_MULTIPLIER)
+        .with_num_features(num_features)
+        .with_base_score(base_score)
+        .with_combiner(combiner);
+
+    let mut batch = Vec::new();
+    for _ in 0..sampler.u.int_in_range(1..=MAX_VECTORS)? {
+        batch.push((0..num_features).map(|_| sampler.value()).collect::<Result<Vec<_>>>()?);
+    }
+    Ok((model, batch))
+}
+
+/// The circuit range-checks values to |x| < 2^62 instead of saturating, so it is only expected
+/// to agree when no sum can leave that range
+#[cfg(feature = "r1cs")]
+fn fits_circuit(model: &Ensemble, batch: &[Vec<i64>]) -> bool {
+    fn node_fits(node: &Node) -> bool {
+        match node {
+            Node::Leaf(value) => value.unsigned_abs() < 1 << 56,
+            Node::Split { threshold, left, right, .. } => {
+                threshold.unsigned_abs() < 1 << 56 && node_fits(left) && node_fits(right)
+            }
+            Node::Categorical { left, right, .. } => node_fits(left) && node_fits(right),
+        }
+    }
+    model.base_score().unsigned_abs() < 1 << 56
+        && model.trees().iter().all(|tree| node_fits(&tree.root))
+        && batch.iter().flatten().all(|x| x.unsigned_abs() < 1 << 56)
+}
+
+#[cfg(feature = "r1cs")]
+fn check_circuit(model: &Ensemble, features: &[i64]) {
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use rainfall_prediction::r1cs::ModelCircuit;
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    ModelCircuit::new(model, features.to_vec()).generate_constraints(cs.clone()).unwrap();
+    assert!(cs.is_satisfied().unwrap(), "circuit rejects the simulated prediction for {:?}", features);
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Ok((model, batch)) = build(&mut Unstructured::new(data)) else {
+        return;
+    };
+
+    let expected: Vec<i64> = batch.iter().map(|features| model.eval(features)).collect();
+    for (features, &prediction) in batch.iter().zip(&expected) {
+        assert_eq!(model.eval_oblivious(features), prediction, "oblivious evaluation differs");
+        let (traced, trace) = model.eval_with_trace(features);
+        assert_eq!(traced, prediction, "traced evaluation differs");
+        assert_eq!(trace.output(), prediction, "trace output differs");
+    }
+
+    #[cfg(feature = "simd")]
+    assert_eq!(rainfall_prediction::SimdForest::new(&model).predict_batch(&batch), expected, "SIMD traversal differs");
+
+    #[cfg(feature = "r1cs")]
+    if fits_circuit(&model, &batch) {
+        for features in &batch {
+            check_circuit(&model, features);
+        }
+    }
+});
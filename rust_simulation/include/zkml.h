@@ -0,0 +1,76 @@
+/* C API of the fixed-point rainfall model (feature `ffi`). Generated by cbindgen; do not edit. */
+
+#ifndef ZKML_H
+#define ZKML_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+// Status returned by every prediction function
+enum ZkmlStatus
+#if defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+  : int32_t
+#endif // defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+ {
+  // Success
+  ZKML_STATUS_OK = 0,
+  // A pointer argument is null
+  ZKML_STATUS_NULL_POINTER = -1,
+  // Fewer than `zkml_num_features()` features were passed
+  ZKML_STATUS_TOO_FEW_FEATURES = -2,
+  // A feature is `INT64_MIN` or `INT64_MAX`, the value a non-finite float saturates to
+  ZKML_STATUS_SATURATED = -3,
+  // The prediction overflows the fixed-point range
+  ZKML_STATUS_OVERFLOW = -4,
+};
+#ifndef __cplusplus
+#if __STDC_VERSION__ >= 202311L
+typedef enum ZkmlStatus ZkmlStatus;
+#else
+typedef int32_t ZkmlStatus;
+#endif // __STDC_VERSION__ >= 202311L
+#endif // __cplusplus
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Predict from scaled features
+//
+// # Arguments
+// * `features` - `len` features scaled by 10^10
+// * `len` - Number of features, at least `zkml_num_features()`
+// * `out` - Receives the prediction (scaled by 10^10)
+//
+// # Returns
+// * `ZkmlStatus` - `Ok`, or the reason for failure; `out` is only written on success
+//
+// # Safety
+// `features` must point to `len` readable values and `out` to one writable value.
+ZkmlStatus zkml_predict(const int64_t *features, size_t len, int64_t *out);
+
+// Probability of the positive class from scaled features
+//
+// # Arguments
+// * `features` - `len` features scaled by 10^10
+// * `len` - Number of features, at least `zkml_num_features()`
+// * `out` - Receives the probability in [0, 10^10]
+//
+// # Returns
+// * `ZkmlStatus` - `Ok`, or the reason for failure; `out` is only written on success
+//
+// # Safety
+// `features` must point to `len` readable values and `out` to one writable value.
+ZkmlStatus zkml_predict_proba(const int64_t *features, size_t len, int64_t *out);
+
+// Number of features the model reads
+size_t zkml_num_features(void);
+
+// Fixed-point scale of features and predictions (10^10)
+int64_t zkml_scale(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ZKML_H */
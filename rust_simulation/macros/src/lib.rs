@@ -0,0 +1,159 @@
+// Compile-time model embedding
+// `embed_model!("model.json")` reads an XGBoost JSON dump while the invoking crate compiles,
+// parses it with `Model::from_xgboost_json_with_scale` and expands to the source `gen_rust_model`
+// would write for it: the fixed-point helpers and the unrolled `xgboost_predict`. A model error
+// becomes a compile error, and the build depends on the dump through `include_bytes!`, so editing
+// the model rebuilds the crate. The arguments are parsed by hand from the token stream, keeping
+// the macro free of syn and quote.
+//
+// The expansion is a private module with the generated code and, next to the invocation:
+//   pub use <module>::{xgboost_predict, to_fixed_point, from_fixed_point};
+//   pub const SCALE: i64; pub const NUM_FEATURES: usize;
+// so a crate embeds one model per module.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use proc_macro::{Delimiter, Group, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use rainfall_prediction::codegen::generate_rust_source;
+use rainfall_prediction::{Model, PRECISION_MULTIPLIER};
+
+/// Embed an XGBoost JSON dump as a fixed-point prediction function
+///
+/// ```ignore
+/// mod rain {
+///     rainfall_prediction_macros::embed_model!("models/bst1_10.json", scale = 10_000_000_000);
+/// }
+///
+/// let prediction = rain::xgboost_predict(&features); // features scaled by rain::SCALE
+/// ```
+///
+/// # Arguments
+/// * path - String literal, relative to the invoking crate's `Cargo.toml` directory
+/// * `scale = N` - Optional fixed-point scale of thresholds, leaves and features (default 10^10)
+///
+/// # Returns
+/// * Items: `xgboost_predict`, `to_fixed_point`, `from_fixed_point`, `SCALE` and `NUM_FEATURES`
+#[proc_macro]
+pub fn embed_model(input: TokenStream) -> TokenStream {
+    match expand(input) {
+        Ok(output) => output,
+        Err((message, span)) => compile_error(&message, span),
+    }
+}
+
+/// Arguments of `embed_model!`
+struct Arguments {
+    path: String,
+    scale: i64,
+}
+
+type Error = (String, Span);
+
+fn expand(input: TokenStream) -> Result<TokenStream, Error> {
+    let arguments = parse_arguments(input)?;
+    let span = Span::call_site();
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").map_err(|e| (format!("CARGO_MANIFEST_DIR: {}", e), span))?;
+    let path = PathBuf::from(manifest_dir).join(&arguments.path);
+
+    let dump = fs::read_to_string(&path).map_err(|e| (format!("cannot read {}: {}", path.display(), e), span))?;
+    let model = Model::from_xgboost_json_with_scale(&dump, arguments.scale)
+        .map_err(|e| (format!("invalid model {}: {}", path.display(), e), span))?;
+    let source = generate_rust_source(&model, model.num_features());
+
+    // Doc comments would turn the generated example into a doctest of the invoking crate
+    let code: String = source
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("///"))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    let output = format!(
+        "#[allow(dead_code, clippy::all)]
+        mod __embedded_model {{
+            const _: &[u8] = include_bytes!({:?});
+            {}
+        }}
+        pub use __embedded_model::{{xgboost_predict, to_fixed_point, from_fixed_point}};
+        /// Fixed-point scale of the embedded model's thresholds, leaves and features
+        pub const SCALE: i64 = {};
+        /// Features `xgboost_predict` expects
+        pub const NUM_FEATURES: usize = {};",
+        path.display().to_string(),
+        code,
+        model.scale(),
+        model.num_features()
+    );
+    output.parse().map_err(|e| (format!("generated code does not parse: {:?}", e), span))
+}
+
+/// Parse `"path"` followed by an optional `, scale = N` and a trailing comma
+fn parse_arguments(input: TokenStream) -> Result<Arguments, Error> {
+    let mut tokens = input.into_iter();
+    let path = match tokens.next() {
+        Some(TokenTree::Literal(literal)) => string_literal(&literal)?,
+        Some(token) => return Err(("expected the model path as a string literal".to_string(), token.span())),
+        None => return Err(("expected the model path as a string literal".to_string(), Span::call_site())),
+    };
+
+    let mut scale = PRECISION_MULTIPLIER;
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => {}
+            token => return Err(("expected `,`".to_string(), token.span())),
+        }
+        let name = match tokens.next() {
+            None => break,
+            Some(TokenTree::Ident(name)) => name,
+            Some(token) => return Err(("expected `scale = N`".to_string(), token.span())),
+        };
+        if name.to_string() != "scale" {
+            return Err((format!("unknown argument `{}` (expected `scale`)", name), name.span()));
+        }
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {}
+            _ => return Err(("expected `=` after `scale`".to_string(), name.span())),
+        }
+        scale = match tokens.next() {
+            Some(TokenTree::Literal(literal)) => integer_literal(&literal)?,
+            _ => return Err(("expected an integer after `scale =`".to_string(), name.span())),
+        };
+    }
+    Ok(Arguments { path, scale })
+}
+
+/// Contents of a plain string literal
+fn string_literal(literal: &Literal) -> Result<String, Error> {
+    let text = literal.to_string();
+    match text.strip_prefix('"').and_then(|text| text.strip_suffix('"')) {
+        Some(path) if !path.contains('\\') => Ok(path.to_string()),
+        Some(_) => Err(("use `/` instead of escapes in the model path".to_string(), literal.span())),
+        None => Err(("expected the model path as a string literal".to_string(), literal.span())),
+    }
+}
+
+/// Positive integer literal, with `_` separators and an optional `i64` suffix
+fn integer_literal(literal: &Literal) -> Result<i64, Error> {
+    let text = literal.to_string().replace('_', "");
+    match text.strip_suffix("i64").unwrap_or(&text).parse::<i64>() {
+        Ok(scale) if scale > 0 => Ok(scale),
+        _ => Err((format!("scale must be a positive i64, got {}", literal), literal.span())),
+    }
+}
+
+/// `::core::compile_error!(message);` at `span`
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    let mut message = Literal::string(message);
+    message.set_span(span);
+    let path: TokenStream = "::core::compile_error!".parse().expect("valid tokens");
+    let mut tokens: Vec<TokenTree> = path.into_iter().collect();
+    tokens.push(TokenTree::Group(Group::new(Delimiter::Parenthesis, TokenTree::Literal(message).into())));
+    tokens.push(TokenTree::Punct(Punct::new(';', Spacing::Alone)));
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
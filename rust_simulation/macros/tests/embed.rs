@@ -0,0 +1,41 @@
+// embed_model! against the library: the embedded function must predict bit for bit what the
+// runtime model parsed from the same dump predicts, at the default and at a custom scale.
+
+use rainfall_prediction::{builtin_model, Model, SyntheticGenerator, PRECISION_MULTIPLIER};
+
+mod rain {
+    rainfall_prediction_macros::embed_model!("../models/bst1_10.json");
+}
+
+mod coarse {
+    rainfall_prediction_macros::embed_model!("../models/bst1_10.json", scale = 1_000_000,);
+}
+
+#[test]
+fn test_embedded_model_matches_runtime_model() {
+    let model = builtin_model();
+    // The features the dump reads; the bundled model also counts the trailing unused ones
+    assert_eq!((rain::SCALE, rain::NUM_FEATURES), (PRECISION_MULTIPLIER, 103));
+
+    let mut generator = SyntheticGenerator::rainfall(11);
+    for _ in 0..500 {
+        let features = generator.next_scaled(rain::SCALE);
+        assert_eq!(rain::xgboost_predict(&features), model.eval(&features));
+    }
+    assert_eq!(rain::to_fixed_point(1.5), 15_000_000_000);
+    assert_eq!(rain::from_fixed_point(rain::to_fixed_point(0.25)), 0.25);
+}
+
+#[test]
+fn test_custom_scale() {
+    let dump = include_str!("../../models/bst1_10.json");
+    let model = Model::from_xgboost_json_with_scale(dump, 1_000_000).unwrap();
+    assert_eq!(coarse::SCALE, 1_000_000);
+    assert_eq!(coarse::to_fixed_point(1.5), 1_500_000);
+
+    let mut generator = SyntheticGenerator::rainfall(12);
+    for _ in 0..500 {
+        let features = generator.next_scaled(coarse::SCALE);
+        assert_eq!(coarse::xgboost_predict(&features), model.eval(&features));
+    }
+}
@@ -0,0 +1,22 @@
+// SP1 guest: prove the compiled model's predictions for a batch of feature vectors
+// Reads the scaled feature vectors and commits, in this order, the model's `content_hash`, the
+// prediction of every vector and its `hash_features` digest. The feature values stay private;
+// the digests let a verifier tie each prediction to inputs committed elsewhere, as the plonky2
+// proofs do.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use rainfall_prediction::{builtin_model, hash_features, xgboost_predict, PoseidonDigest, NUM_FEATURES};
+
+pub fn main() {
+    let inputs: Vec<Vec<i64>> = sp1_zkvm::io::read();
+    assert!(inputs.iter().all(|features| features.len() == NUM_FEATURES), "expected {} features", NUM_FEATURES);
+
+    let predictions: Vec<i64> = inputs.iter().map(|features| xgboost_predict(features)).collect();
+    let features_hashes: Vec<PoseidonDigest> = inputs.iter().map(|features| hash_features(features)).collect();
+
+    sp1_zkvm::io::commit(&builtin_model().content_hash());
+    sp1_zkvm::io::commit(&predictions);
+    sp1_zkvm::io::commit(&features_hashes);
+}
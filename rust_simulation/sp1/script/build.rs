@@ -0,0 +1,4 @@
+// Compile the guest program to a RISC-V ELF, embedded by `include_elf!`
+fn main() {
+    sp1_build::build_program("../program");
+}
@@ -0,0 +1,47 @@
+// Proving cost of one batch on SP1 (and plonky2 with --features plonky2)
+//
+//   cargo run --release --bin compare [-- <batch size> [seed]]
+//   cargo +nightly run --release --features plonky2 --bin compare -- 16
+
+use std::env;
+use std::time::Instant;
+
+use rainfall_prediction::{xgboost_predict, SyntheticGenerator, PRECISION_MULTIPLIER};
+use rainfall_sp1::Sp1Prover;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let size: usize = args.first().map_or(8, |arg| arg.parse().expect("batch size"));
+    let seed: u64 = args.get(1).map_or(42, |arg| arg.parse().expect("seed"));
+    let mut generator = SyntheticGenerator::rainfall(seed);
+    let batch: Vec<Vec<i64>> = (0..size).map(|_| generator.next_scaled(PRECISION_MULTIPLIER)).collect();
+    let predictions: Vec<i64> = batch.iter().map(|features| xgboost_predict(features)).collect();
+    println!("batch of {} predictions (seed {})", size, seed);
+
+    let start = Instant::now();
+    let prover = Sp1Prover::new();
+    println!("sp1      setup   {:>10.2?}", start.elapsed());
+    let cycles = prover.cycles(&batch).expect("execute guest");
+    println!("sp1      cycles  {:>10} ({} per prediction)", cycles, cycles / size as u64);
+    let start = Instant::now();
+    let proof = prover.prove_batch(&batch).expect("prove batch");
+    println!("sp1      prove   {:>10.2?}", start.elapsed());
+    let start = Instant::now();
+    prover.verify_batch(&proof, &predictions).expect("verify batch");
+    println!("sp1      verify  {:>10.2?}", start.elapsed());
+
+    #[cfg(feature = "plonky2")]
+    {
+        use rainfall_prediction::zk::plonky2::Plonky2Circuit;
+
+        let start = Instant::now();
+        let circuit = Plonky2Circuit::new(&rainfall_prediction::builtin_model());
+        println!("plonky2  setup   {:>10.2?}", start.elapsed());
+        let start = Instant::now();
+        let proof = circuit.prove_batch(&batch).expect("prove batch");
+        println!("plonky2  prove   {:>10.2?}", start.elapsed());
+        let start = Instant::now();
+        circuit.verify_batch(&proof, &predictions).expect("verify batch");
+        println!("plonky2  verify  {:>10.2?}", start.elapsed());
+    }
+}
@@ -0,0 +1,178 @@
+// Host side of the SP1 example
+// `prove_batch_sp1` runs the guest in ../program on a batch of scaled feature vectors and returns
+// a compressed SP1 proof of every prediction. The public values are the model's `content_hash`,
+// the predictions and the `hash_features` digest of each input, the same statements a plonky2
+// `BatchProof` carries, so the two backends can be compared on the same batch (`compare`).
+//
+// Proving runs wherever `SP1_PROVER` points: `cpu` (the default), `cuda` or `network`.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use rainfall_prediction::{builtin_model, PoseidonDigest, NUM_FEATURES};
+use sp1_sdk::{
+    include_elf, EnvProver, ProverClient, SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin, SP1VerifyingKey,
+};
+
+/// RISC-V ELF of the guest program, built by build.rs
+pub const PROGRAM_ELF: &[u8] = include_elf!("rainfall-sp1-program");
+
+/// Error type for SP1 batch proving and verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sp1Error {
+    /// A batch proof was requested for no inputs
+    EmptyBatch,
+    /// An input does not have one value per model feature
+    WrongLength { index: usize, expected: usize, got: usize },
+    /// Executing or proving the guest failed
+    Prover(String),
+    /// The proof does not verify
+    Verifier(String),
+    /// The proof was made for another model
+    ModelMismatch,
+    /// The proof verifies but for other predictions
+    PredictionMismatch { expected: Vec<i64>, got: Vec<i64> },
+}
+
+impl fmt::Display for Sp1Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sp1Error::EmptyBatch => write!(f, "cannot prove an empty batch"),
+            Sp1Error::WrongLength { index, expected, got } => {
+                write!(f, "input {}: expected {} features, got {}", index, expected, got)
+            }
+            Sp1Error::Prover(message) => write!(f, "proving failed: {}", message),
+            Sp1Error::Verifier(message) => write!(f, "verification failed: {}", message),
+            Sp1Error::ModelMismatch => write!(f, "proof was made with another model"),
+            Sp1Error::PredictionMismatch { expected, got } => {
+                write!(f, "proof is for predictions {:?}, not {:?}", got, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Sp1Error {}
+
+/// Compressed SP1 proof of a batch of predictions, with its decoded public values
+pub struct Sp1BatchProof {
+    /// `content_hash` of the model the guest ran
+    pub model_hash: [u8; 32],
+    /// Scaled prediction of each input, in order
+    pub predictions: Vec<i64>,
+    /// `hash_features` digest of each input, in order
+    pub features_hashes: Vec<PoseidonDigest>,
+    pub proof: SP1ProofWithPublicValues,
+}
+
+/// Prover client with the guest's keys, set up once
+pub struct Sp1Prover {
+    client: EnvProver,
+    proving_key: SP1ProvingKey,
+    verifying_key: SP1VerifyingKey,
+}
+
+impl Sp1Prover {
+    /// Client configured from the environment, with the keys of `PROGRAM_ELF`
+    pub fn new() -> Sp1Prover {
+        let client = ProverClient::from_env();
+        let (proving_key, verifying_key) = client.setup(PROGRAM_ELF);
+        Sp1Prover { client, proving_key, verifying_key }
+    }
+
+    /// Key that verifies this program's proofs
+    pub fn verifying_key(&self) -> &SP1VerifyingKey {
+        &self.verifying_key
+    }
+
+    /// Guest input for a batch, after checking its shape
+    fn stdin(inputs: &[impl AsRef<[i64]>]) -> Result<SP1Stdin, Sp1Error> {
+        if inputs.is_empty() {
+            return Err(Sp1Error::EmptyBatch);
+        }
+        if let Some((index, got)) =
+            inputs.iter().map(|input| input.as_ref().len()).enumerate().find(|&(_, len)| len != NUM_FEATURES)
+        {
+            return Err(Sp1Error::WrongLength { index, expected: NUM_FEATURES, got });
+        }
+        let inputs: Vec<Vec<i64>> = inputs.iter().map(|input| input.as_ref().to_vec()).collect();
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&inputs);
+        Ok(stdin)
+    }
+
+    /// RISC-V cycles the guest takes on a batch, without proving
+    pub fn cycles(&self, inputs: &[impl AsRef<[i64]>]) -> Result<u64, Sp1Error> {
+        let stdin = Self::stdin(inputs)?;
+        let (_, report) =
+            self.client.execute(PROGRAM_ELF, &stdin).run().map_err(|e| Sp1Error::Prover(e.to_string()))?;
+        Ok(report.total_instruction_count())
+    }
+
+    /// Prove the predictions of the compiled model for many feature vectors
+    ///
+    /// # Arguments
+    /// * `inputs` - Private input feature vectors (scaled by 10^10)
+    ///
+    /// # Returns
+    /// * `Result<Sp1BatchProof, Sp1Error>` - Compressed proof of every prediction, statements in order
+    pub fn prove_batch(&self, inputs: &[impl AsRef<[i64]>]) -> Result<Sp1BatchProof, Sp1Error> {
+        let stdin = Self::stdin(inputs)?;
+        let proof = self
+            .client
+            .prove(&self.proving_key, &stdin)
+            .compressed()
+            .run()
+            .map_err(|e| Sp1Error::Prover(e.to_string()))?;
+        let mut public_values = proof.public_values.clone();
+        Ok(Sp1BatchProof {
+            model_hash: public_values.read(),
+            predictions: public_values.read(),
+            features_hashes: public_values.read(),
+            proof,
+        })
+    }
+
+    /// Verify a batch proof: it must be for the compiled model and exactly `predictions`
+    pub fn verify_batch(&self, batch: &Sp1BatchProof, predictions: &[i64]) -> Result<(), Sp1Error> {
+        self.client
+            .verify(&batch.proof, &self.verifying_key)
+            .map_err(|e| Sp1Error::Verifier(e.to_string()))?;
+        // The decoded fields are plain copies, so check them against what was proved
+        let mut public_values = batch.proof.public_values.clone();
+        let model_hash: [u8; 32] = public_values.read();
+        let proved: Vec<i64> = public_values.read();
+        let features_hashes: Vec<PoseidonDigest> = public_values.read();
+        if model_hash != batch.model_hash || proved != batch.predictions || features_hashes != batch.features_hashes {
+            return Err(Sp1Error::Verifier("the proof is for other statements".to_string()));
+        }
+        if model_hash != builtin_model().content_hash() {
+            return Err(Sp1Error::ModelMismatch);
+        }
+        if proved != predictions {
+            return Err(Sp1Error::PredictionMismatch { expected: predictions.to_vec(), got: proved });
+        }
+        Ok(())
+    }
+}
+
+impl Default for Sp1Prover {
+    fn default() -> Self {
+        Sp1Prover::new()
+    }
+}
+
+/// Prover of the guest program, set up on first use
+fn prover() -> &'static Sp1Prover {
+    static PROVER: OnceLock<Sp1Prover> = OnceLock::new();
+    PROVER.get_or_init(Sp1Prover::new)
+}
+
+/// Prove `xgboost_predict` for many feature vectors with one SP1 proof
+pub fn prove_batch_sp1(inputs: &[impl AsRef<[i64]>]) -> Result<Sp1BatchProof, Sp1Error> {
+    prover().prove_batch(inputs)
+}
+
+/// Verify an SP1 batch proof of the compiled model for `predictions`
+pub fn verify_batch_sp1(batch: &Sp1BatchProof, predictions: &[i64]) -> Result<(), Sp1Error> {
+    prover().verify_batch(batch, predictions)
+}
@@ -0,0 +1,133 @@
+// Solidity ABI encoding of a proof's public inputs
+// On-chain consumers of a rainfall proof receive the prediction, the model hash and the feature
+// commitment as `abi.encode(int256 prediction, bytes32 modelHash, bytes32 featuresCommitment)`:
+// three 32-byte words, the prediction sign-extended from i64 (still at the model's scale), the
+// SHA-256 content hash of the model, and the Poseidon features hash packed as its four Goldilocks
+// elements in big-endian order. The verifier contract itself depends on the proving system: plonky2
+// proofs are not practical to verify on-chain, so `groth16` generates one for the R1CS circuit.
+
+use core::fmt;
+
+use crate::poseidon::PoseidonDigest;
+use crate::witness::WitnessBundle;
+
+/// Length of the ABI encoding: three 32-byte words
+pub const PUBLIC_INPUTS_LEN: usize = 96;
+
+/// Error decoding ABI-encoded public inputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiError {
+    /// The encoding is not `PUBLIC_INPUTS_LEN` bytes long
+    Length(usize),
+    /// The prediction word is not a sign-extended i64
+    PredictionRange,
+}
+
+impl fmt::Display for AbiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbiError::Length(len) => write!(f, "expected {} bytes of public inputs, got {}", PUBLIC_INPUTS_LEN, len),
+            AbiError::PredictionRange => write!(f, "prediction does not fit in an i64"),
+        }
+    }
+}
+
+impl core::error::Error for AbiError {}
+
+/// Public inputs of a rainfall proof as a contract consumes them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicInputs {
+    /// Prediction (at the model's scale)
+    pub prediction: i64,
+    /// SHA-256 content hash of the model
+    pub model_hash: [u8; 32],
+    /// Poseidon hash of the features
+    pub features_hash: PoseidonDigest,
+}
+
+impl PublicInputs {
+    /// Public inputs of a prepared witness
+    pub fn from_bundle(bundle: &WitnessBundle) -> PublicInputs {
+        PublicInputs { prediction: bundle.prediction, model_hash: bundle.model_hash, features_hash: bundle.features_hash() }
+    }
+
+    /// The features hash as a `bytes32`: its four elements, big-endian, in order
+    pub fn features_commitment(&self) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        for (chunk, element) in word.chunks_exact_mut(8).zip(self.features_hash) {
+            chunk.copy_from_slice(&element.to_be_bytes());
+        }
+        word
+    }
+
+    /// `abi.encode(int256 prediction, bytes32 modelHash, bytes32 featuresCommitment)`
+    ///
+    /// # Returns
+    /// * `[u8; PUBLIC_INPUTS_LEN]` - Calldata for the three public inputs
+    pub fn abi_encode(&self) -> [u8; PUBLIC_INPUTS_LEN] {
+        let mut encoded = [0u8; PUBLIC_INPUTS_LEN];
+        let sign = if self.prediction < 0 { 0xff } else { 0 };
+        encoded[..24].fill(sign);
+        encoded[24..32].copy_from_slice(&self.prediction.to_be_bytes());
+        encoded[32..64].copy_from_slice(&self.model_hash);
+        encoded[64..].copy_from_slice(&self.features_commitment());
+        encoded
+    }
+
+    /// Inverse of `abi_encode`
+    pub fn abi_decode(encoded: &[u8]) -> Result<PublicInputs, AbiError> {
+        if encoded.len() != PUBLIC_INPUTS_LEN {
+            return Err(AbiError::Length(encoded.len()));
+        }
+        let prediction = i64::from_be_bytes(encoded[24..32].try_into().expect("8 bytes"));
+        let sign = if prediction < 0 { 0xff } else { 0 };
+        if encoded[..24].iter().any(|&b| b != sign) {
+            return Err(AbiError::PredictionRange);
+        }
+
+        let mut features_hash = [0u64; 4];
+        for (element, chunk) in features_hash.iter_mut().zip(encoded[64..].chunks_exact(8)) {
+            *element = u64::from_be_bytes(chunk.try_into().expect("8 bytes"));
+        }
+        Ok(PublicInputs {
+            prediction,
+            model_hash: encoded[32..64].try_into().expect("32 bytes"),
+            features_hash,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builtin_model, witness, NUM_FEATURES};
+
+    #[test]
+    fn test_abi_layout() {
+        let inputs = PublicInputs { prediction: -2, model_hash: [7; 32], features_hash: [1, 2, 3, u64::MAX - 1] };
+        let encoded = inputs.abi_encode();
+        assert_eq!(encoded[..31], [0xff; 31]);
+        assert_eq!(encoded[31], 0xfe);
+        assert_eq!(encoded[32..64], [7; 32]);
+        assert_eq!(encoded[64..72], [0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(encoded[88..], [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe]);
+        assert_eq!(PublicInputs::abi_decode(&encoded), Ok(inputs));
+
+        let positive = PublicInputs { prediction: 300, ..inputs }.abi_encode();
+        assert_eq!(positive[..30], [0; 30]);
+        assert_eq!(positive[30..32], [1, 44]);
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed() {
+        let bundle = witness::prepare(&builtin_model(), &[0; NUM_FEATURES]);
+        let inputs = PublicInputs::from_bundle(&bundle);
+        let mut encoded = inputs.abi_encode();
+        assert_eq!(PublicInputs::abi_decode(&encoded).unwrap().features_hash, bundle.features_hash());
+
+        assert_eq!(PublicInputs::abi_decode(&encoded[1..]), Err(AbiError::Length(95)));
+        encoded[3] ^= 1;
+        assert_eq!(PublicInputs::abi_decode(&encoded), Err(AbiError::PredictionRange));
+        assert_eq!(AbiError::PredictionRange.to_string(), "prediction does not fit in an i64");
+    }
+}
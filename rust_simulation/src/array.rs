@@ -0,0 +1,77 @@
+// ndarray interop
+// Scores every row of an `Array2` feature matrix into an `Array1` of predictions, so the predictor
+// drops into existing ndarray pipelines without slicing rows by hand. Rows need not be contiguous
+// (a transposed or sliced view works too). The float wrapper quantizes raw features at the model's
+// scale and converts the predictions back.
+
+use ndarray::{Array1, ArrayBase, Data, Ix2};
+
+use crate::ensemble::Ensemble;
+use crate::fixed::round_half_away;
+
+impl Ensemble {
+    /// Predict every row of a fixed-point feature matrix
+    ///
+    /// # Arguments
+    /// * `features` - One sample per row, at the ensemble's scale (at least `num_features` columns)
+    ///
+    /// # Returns
+    /// * `Array1<i64>` - One prediction per row, in row order
+    pub fn predict_matrix<S: Data<Elem = i64>>(&self, features: &ArrayBase<S, Ix2>) -> Array1<i64> {
+        features
+            .rows()
+            .into_iter()
+            .map(|row| match row.as_slice() {
+                Some(row) => self.eval(row),
+                None => self.eval(&row.to_vec()),
+            })
+            .collect()
+    }
+
+    /// Predict every row of a raw (unscaled) feature matrix
+    ///
+    /// # Arguments
+    /// * `features` - One sample per row, in real units
+    ///
+    /// # Returns
+    /// * `Array1<f64>` - One prediction per row, in real units
+    pub fn predict_matrix_f64<S: Data<Elem = f64>>(&self, features: &ArrayBase<S, Ix2>) -> Array1<f64> {
+        let scale = self.scale() as f64;
+        let scaled = features.mapv(|x| round_half_away(x * scale));
+        self.predict_matrix(&scaled).mapv(|y| y as f64 / scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{builtin_model, to_fixed_point, NUM_FEATURES};
+    use ndarray::{s, Array2};
+
+    #[test]
+    fn test_predict_matrix() {
+        let model = builtin_model();
+        let matrix =
+            Array2::from_shape_fn((5, NUM_FEATURES), |(i, j)| ((i * 37 + j * 11) % 300) as i64 * 1_000_000_000);
+        let predictions = model.predict_matrix(&matrix);
+        assert_eq!(predictions.len(), 5);
+        for (row, prediction) in matrix.rows().into_iter().zip(&predictions) {
+            assert_eq!(*prediction, model.eval(row.as_slice().unwrap()));
+        }
+
+        // Column-major storage and sliced views give the same predictions
+        let transposed = matrix.t().to_owned();
+        assert_eq!(model.predict_matrix(&transposed.t()), predictions);
+        assert_eq!(model.predict_matrix(&matrix.slice(s![1..3, ..])), predictions.slice(s![1..3]));
+    }
+
+    #[test]
+    fn test_predict_matrix_f64() {
+        let model = builtin_model();
+        let raw = Array2::from_shape_fn((3, NUM_FEATURES), |(i, j)| (i as f64 - 1.0) * 0.5 + j as f64 * 0.01);
+        let predictions = model.predict_matrix_f64(&raw);
+        for (row, prediction) in raw.rows().into_iter().zip(predictions) {
+            let features: Vec<i64> = row.iter().map(|&x| to_fixed_point(x)).collect();
+            assert_eq!(to_fixed_point(prediction), model.eval(&features));
+        }
+    }
+}
@@ -0,0 +1,284 @@
+// Pluggable circuit backends
+// Every proof-system backend lowers the same few operations: range-checked private inputs, the
+// `fixed_le` comparison and category membership of each distinct split test, a select per tree
+// node, range-checked additions into the accumulator, the averaging combiner, and finally the
+// public statement. `CircuitBackend` names those operations; `lower_model` walks the shared
+// ensemble (`Ensemble::share_comparisons`) once and calls them in order, so the traversal and
+// its sharing of tests live in one place. The arkworks R1CS backend (`r1cs::R1csBackend`) and
+// the plonky2 backend (`CircuitBuilder` itself) implement the trait, and so can a backend for a
+// proof system this crate does not ship, without forking the traversal.
+//
+// Values are signed fixed-point numbers. A backend decides how to embed them (both shipped ones
+// wrap negatives around the field modulus) and must make `lower_le` and `lower_add` exact for
+// operands within the bit widths it is given.
+
+use alloc::vec::Vec;
+
+use crate::bitwidth::ValueBounds;
+use crate::ensemble::{BitSet, Combiner};
+use crate::sharing::{SharedNode, SplitTest};
+use crate::Model;
+
+/// Lowering of the fixed-point tree traversal into one proof system
+pub trait CircuitBackend {
+    /// Fixed-point value in the circuit: a variable or a constant
+    type Value: Clone;
+    /// Boolean in the circuit
+    type Bit: Clone;
+    /// Error raised while lowering
+    type Error;
+    /// Result of `finalize`, e.g. the built circuit
+    type Output;
+
+    /// Allocate a private fixed-point input and range-check it to |x| < 2^bits
+    ///
+    /// `value` is the witness for backends that assign witnesses while lowering; `None` when
+    /// only the circuit shape is built. Backends that assign witnesses at proving time ignore it.
+    fn private_input(&mut self, value: Option<i64>, bits: usize) -> Result<Self::Value, Self::Error>;
+
+    /// Fixed-point constant
+    fn constant(&mut self, value: i64) -> Result<Self::Value, Self::Error>;
+
+    /// Lower `fixed_le`: true iff `a <= b`, for operands known to satisfy |x| < 2^bits
+    fn lower_le(&mut self, a: &Self::Value, b: &Self::Value, bits: usize) -> Result<Self::Bit, Self::Error>;
+
+    /// Lower `BitSet::contains_value`: true iff `value` is `c * unit` for a category `c` in the set
+    fn lower_categories(
+        &mut self,
+        value: &Self::Value,
+        categories: &BitSet,
+        unit: i64,
+    ) -> Result<Self::Bit, Self::Error>;
+
+    /// Lower a select: `when_true` if `bit` holds, `when_false` otherwise
+    fn lower_select(
+        &mut self,
+        bit: &Self::Bit,
+        when_true: &Self::Value,
+        when_false: &Self::Value,
+    ) -> Result<Self::Value, Self::Error>;
+
+    /// Lower `fixed_add` with the sum range-checked to |sum| < 2^bits, so it never saturates
+    fn lower_add(&mut self, a: &Self::Value, b: &Self::Value, bits: usize) -> Result<Self::Value, Self::Error>;
+
+    /// Lower the averaging combiner: `base_score + floor(sum / num_trees)`, with |sum| < 2^bits
+    fn lower_average(
+        &mut self,
+        sum: &Self::Value,
+        num_trees: usize,
+        base_score: i64,
+        bits: usize,
+    ) -> Result<Self::Value, Self::Error>;
+
+    /// Make `prediction` the public output and bind `features` to the statement
+    ///
+    /// How the features are bound is up to the proof system: plonky2 publishes their Poseidon
+    /// hash, the R1CS backend keeps them private and unbound.
+    fn commit_public(&mut self, prediction: &Self::Value, features: &[Self::Value]) -> Result<(), Self::Error>;
+
+    /// Finish the circuit
+    fn finalize(self) -> Result<Self::Output, Self::Error>;
+}
+
+/// Lower a model evaluation on already allocated features
+///
+/// Each distinct split test is lowered once and shared by every split using it; each tree is
+/// then a select per node, and the tree outputs are added into a range-checked accumulator.
+///
+/// # Arguments
+/// * `backend` - Backend to lower into
+/// * `model` - Model to evaluate
+/// * `features` - Feature values, range-checked to |x| < 2^comparison_bits
+/// * `comparison_bits` - Width of the comparisons
+/// * `accumulator_bits` - Width of the accumulator range checks
+///
+/// # Returns
+/// * `Result<B::Value, B::Error>` - The prediction
+pub fn lower_model<B: CircuitBackend>(
+    backend: &mut B,
+    model: &Model,
+    features: &[B::Value],
+    comparison_bits: usize,
+    accumulator_bits: usize,
+) -> Result<B::Value, B::Error> {
+    let shared = model.share_comparisons();
+    let outcomes = shared
+        .tests()
+        .iter()
+        .map(|test| match *test {
+            SplitTest::Threshold { feature, threshold } => {
+                let threshold = backend.constant(threshold)?;
+                backend.lower_le(&features[feature], &threshold, comparison_bits)
+            }
+            SplitTest::Categories { feature, ref categories, unit } => {
+                backend.lower_categories(&features[feature], categories, unit)
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let combiner = model.combiner();
+    let mut y = backend.constant(combiner.initial(model.base_score()))?;
+    for tree in shared.trees() {
+        let tree_result = lower_tree(backend, tree, &outcomes)?;
+        y = backend.lower_add(&y, &tree_result, accumulator_bits)?;
+    }
+    match combiner {
+        Combiner::Sum => Ok(y),
+        Combiner::Average => backend.lower_average(&y, model.num_trees(), model.base_score(), accumulator_bits),
+    }
+}
+
+/// Build the whole circuit for `model`: private features, the evaluation and the public statement
+///
+/// # Arguments
+/// * `backend` - Backend to lower into
+/// * `model` - Model to evaluate
+/// * `features` - Witness features, or None to build the circuit shape only
+/// * `bounds` - Value bounds to size the range checks from (full `max_bits` checks if None)
+/// * `max_bits` - Widest range check the backend supports
+///
+/// # Returns
+/// * `Result<B::Output, B::Error>` - What `finalize` returns
+pub fn lower_circuit<B: CircuitBackend>(
+    mut backend: B,
+    model: &Model,
+    features: Option<&[i64]>,
+    bounds: Option<&ValueBounds>,
+    max_bits: usize,
+) -> Result<B::Output, B::Error> {
+    let (comparison_bits, accumulator_bits) =
+        bounds.map_or((max_bits, max_bits), |bounds| (bounds.comparison_bits(), bounds.accumulator_bits()));
+    let inputs = (0..model.num_features())
+        .map(|i| backend.private_input(features.map(|f| f[i]), comparison_bits))
+        .collect::<Result<Vec<_>, _>>()?;
+    let prediction = lower_model(&mut backend, model, &inputs, comparison_bits, accumulator_bits)?;
+    backend.commit_public(&prediction, &inputs)?;
+    backend.finalize()
+}
+
+/// Lower one shared tree obliviously: every split selects between its two subtree values on
+/// the outcome of its test
+fn lower_tree<B: CircuitBackend>(
+    backend: &mut B,
+    node: &SharedNode,
+    outcomes: &[B::Bit],
+) -> Result<B::Value, B::Error> {
+    match *node {
+        SharedNode::Leaf(value) => backend.constant(value),
+        SharedNode::Test { test, ref left, ref right } => {
+            let left = lower_tree(backend, left, outcomes)?;
+            let right = lower_tree(backend, right, outcomes)?;
+            backend.lower_select(&outcomes[test], &left, &right)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::{Node, Tree};
+    use crate::{builtin_model, Ensemble, SyntheticGenerator, PRECISION_MULTIPLIER};
+
+    /// Backend that computes instead of constraining, counting the operations a circuit would have
+    #[derive(Default)]
+    struct Counting {
+        inputs: usize,
+        comparisons: usize,
+        selects: usize,
+        adds: usize,
+        public: Option<i64>,
+    }
+
+    impl CircuitBackend for Counting {
+        type Value = i64;
+        type Bit = bool;
+        type Error = &'static str;
+        type Output = (i64, Counting);
+
+        fn private_input(&mut self, value: Option<i64>, bits: usize) -> Result<i64, &'static str> {
+            self.inputs += 1;
+            let value = value.ok_or("missing witness")?;
+            if value.unsigned_abs() >= 1 << bits {
+                return Err("input out of range");
+            }
+            Ok(value)
+        }
+
+        fn constant(&mut self, value: i64) -> Result<i64, &'static str> {
+            Ok(value)
+        }
+
+        fn lower_le(&mut self, a: &i64, b: &i64, _bits: usize) -> Result<bool, &'static str> {
+            self.comparisons += 1;
+            Ok(a <= b)
+        }
+
+        fn lower_categories(&mut self, value: &i64, categories: &BitSet, unit: i64) -> Result<bool, &'static str> {
+            self.comparisons += 1;
+            Ok(categories.contains_value(*value, unit))
+        }
+
+        fn lower_select(&mut self, bit: &bool, when_true: &i64, when_false: &i64) -> Result<i64, &'static str> {
+            self.selects += 1;
+            Ok(if *bit { *when_true } else { *when_false })
+        }
+
+        fn lower_add(&mut self, a: &i64, b: &i64, bits: usize) -> Result<i64, &'static str> {
+            self.adds += 1;
+            a.checked_add(*b).filter(|sum| sum.unsigned_abs() < 1 << bits).ok_or("sum out of range")
+        }
+
+        fn lower_average(
+            &mut self,
+            sum: &i64,
+            num_trees: usize,
+            base_score: i64,
+            _bits: usize,
+        ) -> Result<i64, &'static str> {
+            Ok(base_score + sum.div_euclid(num_trees.max(1) as i64))
+        }
+
+        fn commit_public(&mut self, prediction: &i64, _features: &[i64]) -> Result<(), &'static str> {
+            self.public = Some(*prediction);
+            Ok(())
+        }
+
+        fn finalize(self) -> Result<(i64, Counting), &'static str> {
+            Ok((self.public.ok_or("nothing committed")?, self))
+        }
+    }
+
+    #[test]
+    fn test_custom_backend_matches_model() {
+        let model = builtin_model();
+        let mut generator = SyntheticGenerator::rainfall(5);
+        for _ in 0..200 {
+            let features = generator.next_scaled(PRECISION_MULTIPLIER);
+            let (prediction, counts) = lower_circuit(Counting::default(), &model, Some(&features), None, 62).unwrap();
+            assert_eq!(prediction, model.eval(&features));
+            assert_eq!(counts.inputs, model.num_features());
+            assert_eq!(counts.comparisons, model.share_comparisons().tests().len());
+            // One select per split, one addition per tree
+            let splits: usize = model.trees().iter().map(|tree| (tree.num_nodes() - 1) / 2).sum();
+            assert_eq!((counts.selects, counts.adds), (splits, model.num_trees()));
+        }
+    }
+
+    #[test]
+    fn test_widths_and_errors_reach_the_backend() {
+        let categories: BitSet = [1, 3].into_iter().collect();
+        let trees = vec![
+            Tree::new(Node::categorical(1, categories, 10, Node::Leaf(4), Node::Leaf(-2))),
+            Tree::new(Node::split(0, 5, Node::Leaf(1), Node::Leaf(2))),
+        ];
+        let model = Ensemble::new(trees, 10).with_base_score(3).with_combiner(Combiner::Average);
+        let (prediction, _) = lower_circuit(Counting::default(), &model, Some(&[9, 30]), None, 62).unwrap();
+        assert_eq!(prediction, model.eval(&[9, 30]));
+
+        // Inputs are checked against the bounds' comparison width
+        let bounds = ValueBounds::analyze(&model, 15);
+        let result = lower_circuit(Counting::default(), &model, Some(&[9, 1 << 20]), Some(&bounds), 62);
+        assert_eq!(result.err(), Some("input out of range"));
+        assert_eq!(lower_circuit(Counting::default(), &model, None, None, 62).err(), Some("missing witness"));
+    }
+}
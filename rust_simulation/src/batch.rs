@@ -0,0 +1,100 @@
+// Batch prediction
+// Scores many feature vectors in one call, either eagerly over a slice, in parallel with rayon
+// (feature `parallel`), lazily over an iterator for datasets that do not fit in memory, or
+// through a `PredictionCache` when the batch repeats feature vectors.
+
+use alloc::vec::Vec;
+
+use crate::cache::PredictionCache;
+use crate::xgboost_predict;
+
+/// Run `xgboost_predict` on every feature vector of a batch
+///
+/// # Arguments
+/// * `batch` - Feature vectors (each scaled by 10^10)
+///
+/// # Returns
+/// * `Vec<i64>` - One prediction per input, in input order
+pub fn xgboost_predict_batch(batch: &[Vec<i64>]) -> Vec<i64> {
+    batch.iter().map(|features| xgboost_predict(features)).collect()
+}
+
+/// Parallel version of [`xgboost_predict_batch`] using the rayon thread pool
+///
+/// Predictions are pure functions of their input, so the output is identical to the
+/// sequential version and keeps the input order.
+#[cfg(feature = "parallel")]
+pub fn xgboost_predict_batch_parallel(batch: &[Vec<i64>]) -> Vec<i64> {
+    use rayon::prelude::*;
+
+    batch.par_iter().map(|features| xgboost_predict(features)).collect()
+}
+
+/// [`xgboost_predict_batch`] through a prediction cache
+///
+/// # Arguments
+/// * `batch` - Feature vectors (each scaled by 10^10)
+/// * `cache` - Cache of earlier `xgboost_predict` results, updated with this batch
+///
+/// # Returns
+/// * `Vec<i64>` - One prediction per input, in input order (identical to the uncached batch)
+pub fn xgboost_predict_batch_cached(batch: &[Vec<i64>], cache: &mut PredictionCache) -> Vec<i64> {
+    batch.iter().map(|features| cache.predict(features, xgboost_predict)).collect()
+}
+
+/// Lazily run `xgboost_predict` over a stream of feature vectors
+///
+/// Only one input is held at a time, so this works for inputs read incrementally
+/// from disk or a socket.
+///
+/// # Arguments
+/// * `inputs` - Any iterator of feature vectors (`Vec<i64>`, `&[i64]`, ...)
+///
+/// # Returns
+/// * `impl Iterator<Item = i64>` - Predictions in input order
+pub fn xgboost_predict_stream<I>(inputs: I) -> impl Iterator<Item = i64>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[i64]>,
+{
+    inputs.into_iter().map(|features| xgboost_predict(features.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NUM_FEATURES;
+
+    fn sample_batch() -> Vec<Vec<i64>> {
+        (0..64)
+            .map(|i| {
+                (0..NUM_FEATURES)
+                    .map(|j| ((i * 31 + j * 7) % 400) as i64 * 1_000_000_000 - 50_000_000_000)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_matches_single_predictions() {
+        let batch = sample_batch();
+        let expected: Vec<i64> = batch.iter().map(|f| xgboost_predict(f)).collect();
+
+        assert_eq!(xgboost_predict_batch(&batch), expected);
+        assert_eq!(xgboost_predict_stream(batch.iter()).collect::<Vec<_>>(), expected);
+        assert!(xgboost_predict_batch(&[]).is_empty());
+
+        // Every input of the doubled batch is scored once and then answered from the cache
+        let doubled: Vec<Vec<i64>> = batch.iter().chain(batch.iter()).cloned().collect();
+        let mut cache = PredictionCache::new(128);
+        assert_eq!(xgboost_predict_batch_cached(&doubled, &mut cache), [expected.clone(), expected].concat());
+        assert_eq!((cache.stats().hits, cache.stats().misses), (64, 64));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_batch_keeps_order() {
+        let batch = sample_batch();
+        assert_eq!(xgboost_predict_batch_parallel(&batch), xgboost_predict_batch(&batch));
+    }
+}
@@ -0,0 +1,96 @@
+// Cross-check the fixed-point port against native XGBoost
+// Runs converter/xgb_reference.py on the original model, evaluates the port on the same CSV of
+// samples and prints the per-sample and per-tree deviations. Exits non-zero on any divergence.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use rainfall_prediction::validation::{cross_check, python_reference, read_samples};
+use rainfall_prediction::{builtin_model, Model};
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let mut positional = Vec::new();
+    let mut dump = None;
+    let mut python = env::var("PYTHON").unwrap_or_else(|_| "python3".to_string());
+    let mut script = Path::new(env!("CARGO_MANIFEST_DIR")).join("../converter/xgb_reference.py");
+    let mut json = false;
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--dump" if i + 1 < args.len() => {
+                dump = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "--python" if i + 1 < args.len() => {
+                python = args[i + 1].clone();
+                i += 1;
+            }
+            "--script" if i + 1 < args.len() => {
+                script = PathBuf::from(&args[i + 1]);
+                i += 1;
+            }
+            "--json" => json = true,
+            "-h" | "--help" => {
+                print_usage(&args[0]);
+                return;
+            }
+            arg if positional.len() < 2 && !arg.starts_with('-') => positional.push(arg.to_string()),
+            arg => {
+                print_usage(&args[0]);
+                fail(&format!("unexpected argument '{}'", arg));
+            }
+        }
+        i += 1;
+    }
+
+    let [xgb_model, samples_csv] = positional.as_slice() else {
+        print_usage(&args[0]);
+        fail("missing <xgb_model> or <samples.csv>");
+    };
+
+    let model = match &dump {
+        Some(path) => match Model::from_xgboost_json_file(path) {
+            Ok(model) => model,
+            Err(e) => fail(&format!("{}: {}", path, e)),
+        },
+        None => builtin_model(),
+    };
+
+    let samples = read_samples(samples_csv, model.num_features())
+        .unwrap_or_else(|e| fail(&format!("{}: {}", samples_csv, e)));
+    let reference = python_reference(&python, &script, Path::new(xgb_model), Path::new(samples_csv), model.num_trees())
+        .unwrap_or_else(|e| fail(&e.to_string()));
+    if reference.len() != samples.len() {
+        fail(&format!("reference returned {} rows for {} samples", reference.len(), samples.len()));
+    }
+
+    let report = cross_check(&model, &samples, &reference);
+    if json {
+        println!("{}", report.to_json());
+    } else {
+        println!("{}", report);
+    }
+    if !report.passed() {
+        std::process::exit(2);
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("Error: {}", message);
+    std::process::exit(1);
+}
+
+fn print_usage(program_name: &str) {
+    eprintln!("Usage:");
+    eprintln!("  {} <xgb_model> <samples.csv> [--dump model.json] [--python PATH] [--script PATH] [--json]", program_name);
+    eprintln!();
+    eprintln!("  xgb_model     Original XGBoost model file (e.g. ../models/bst1_1_final_subm)");
+    eprintln!("  samples.csv   One sample of raw features per line, optional header");
+    eprintln!("  --dump        JSON dump to port instead of the compiled model");
+    eprintln!("  --python      Interpreter with xgboost installed (default $PYTHON or python3)");
+    eprintln!("  --script      Reference script (default converter/xgb_reference.py)");
+    eprintln!("  --json        Print the report as JSON");
+}
@@ -0,0 +1,319 @@
+// Code generator for the rainfall prediction library
+// Reads an XGBoost JSON dump and writes the nested-if Rust source of `xgboost_predict`,
+// or with `--target leo` an Aleo program with the same prediction as a `predict` transition.
+// `--bit-widths` also writes the value bounds circuit backends size their range checks from.
+// `--max-depth` truncates the trees first, averaging the removed leaves by their `--covers`;
+// `--prune` merges near-equal leaves and, with `--validation`, reports the accuracy impact.
+// `--monotone` refuses to generate a model that breaks XGBoost-style monotone constraints.
+// `--split` writes one module file per tree into the `-o` directory, `--style leaf-match` emits
+// leaf ids and a `match` instead of nesting the values, and `--rustfmt` formats the output.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use rainfall_prediction::codegen::{generate_leo_source, generate_rust_files, RustLayout, RustOptions, RustStyle};
+use rainfall_prediction::csv::parse_samples;
+use rainfall_prediction::{
+    to_fixed_point, Combiner, LeafCovers, Model, MonotoneConstraints, PruneReport, ValueBounds, MISSING_VALUE,
+    NUM_FEATURES,
+};
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let mut input = None;
+    let mut output = None;
+    let mut num_features = NUM_FEATURES;
+    let mut target = "rust".to_string();
+    let mut program_name = "rainfall_prediction".to_string();
+    let mut base_score = 0.0;
+    let mut layout = RustLayout::Unrolled;
+    let mut style = RustStyle::NestedIf;
+    let mut split_files = false;
+    let mut format = false;
+    let mut combiner = Combiner::Sum;
+    let mut bit_widths = None;
+    let mut max_feature = MISSING_VALUE.unsigned_abs();
+    let mut max_depth = None;
+    let mut covers = None;
+    let mut prune = None;
+    let mut validation = None;
+    let mut monotone = None;
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-o" | "--output" if i + 1 < args.len() => {
+                output = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "--num-features" if i + 1 < args.len() => {
+                num_features = match args[i + 1].parse() {
+                    Ok(n) => n,
+                    Err(e) => fail(&format!("invalid --num-features '{}': {}", args[i + 1], e)),
+                };
+                i += 1;
+            }
+            "--target" if i + 1 < args.len() => {
+                target = args[i + 1].clone();
+                i += 1;
+            }
+            "--base-score" if i + 1 < args.len() => {
+                base_score = match args[i + 1].parse::<f64>() {
+                    Ok(value) if value.is_finite() => value,
+                    _ => fail(&format!("invalid --base-score '{}'", args[i + 1])),
+                };
+                i += 1;
+            }
+            "--layout" if i + 1 < args.len() => {
+                layout = match args[i + 1].as_str() {
+                    "unrolled" => RustLayout::Unrolled,
+                    "per-tree" => RustLayout::PerTree,
+                    "table" => RustLayout::Table,
+                    other => fail(&format!("unknown --layout '{}' (expected unrolled, per-tree or table)", other)),
+                };
+                i += 1;
+            }
+            "--style" if i + 1 < args.len() => {
+                style = match args[i + 1].as_str() {
+                    "nested-if" => RustStyle::NestedIf,
+                    "leaf-match" => RustStyle::LeafMatch,
+                    other => fail(&format!("unknown --style '{}' (expected nested-if or leaf-match)", other)),
+                };
+                i += 1;
+            }
+            "--split" => split_files = true,
+            "--rustfmt" => format = true,
+            "--combiner" if i + 1 < args.len() => {
+                combiner = match args[i + 1].as_str() {
+                    "sum" => Combiner::Sum,
+                    "average" => Combiner::Average,
+                    other => fail(&format!("unknown --combiner '{}' (expected sum or average)", other)),
+                };
+                i += 1;
+            }
+            "--bit-widths" if i + 1 < args.len() => {
+                bit_widths = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "--max-feature" if i + 1 < args.len() => {
+                max_feature = match args[i + 1].parse::<f64>() {
+                    Ok(value) if value.is_finite() => to_fixed_point(value).unsigned_abs(),
+                    _ => fail(&format!("invalid --max-feature '{}'", args[i + 1])),
+                };
+                i += 1;
+            }
+            "--max-depth" if i + 1 < args.len() => {
+                max_depth = match args[i + 1].parse::<usize>() {
+                    Ok(depth) => Some(depth),
+                    Err(e) => fail(&format!("invalid --max-depth '{}': {}", args[i + 1], e)),
+                };
+                i += 1;
+            }
+            "--covers" if i + 1 < args.len() => {
+                covers = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "--prune" if i + 1 < args.len() => {
+                prune = match args[i + 1].parse::<f64>() {
+                    Ok(value) if value.is_finite() && value >= 0.0 => Some(to_fixed_point(value)),
+                    _ => fail(&format!("invalid --prune '{}'", args[i + 1])),
+                };
+                i += 1;
+            }
+            "--validation" if i + 1 < args.len() => {
+                validation = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "--monotone" if i + 1 < args.len() => {
+                monotone = match MonotoneConstraints::parse(&args[i + 1]) {
+                    Ok(constraints) => Some(constraints),
+                    Err(e) => fail(&format!("invalid --monotone '{}': {}", args[i + 1], e)),
+                };
+                i += 1;
+            }
+            "--program" if i + 1 < args.len() => {
+                program_name = args[i + 1].clone();
+                i += 1;
+            }
+            "-h" | "--help" => {
+                print_usage(&args[0]);
+                return;
+            }
+            arg if input.is_none() && !arg.starts_with('-') => input = Some(arg.to_string()),
+            arg => {
+                print_usage(&args[0]);
+                fail(&format!("unexpected argument '{}'", arg));
+            }
+        }
+        i += 1;
+    }
+
+    let Some(input) = input else {
+        print_usage(&args[0]);
+        fail("missing model.json");
+    };
+
+    let model = match Model::from_xgboost_json_file(&input) {
+        Ok(model) => model.with_base_score(to_fixed_point(base_score)).with_combiner(combiner),
+        Err(e) => fail(&format!("{}: {}", input, e)),
+    };
+    let model = match max_depth {
+        Some(max_depth) => {
+            let covers = match &covers {
+                Some(path) => {
+                    let text = fs::read_to_string(path).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+                    LeafCovers::from_csv(&text, &model).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)))
+                }
+                None => LeafCovers::uniform(&model),
+            };
+            let truncated = model.truncate(max_depth, &covers);
+            let count = |model: &Model| model.trees().iter().map(|tree| tree.num_nodes()).sum::<usize>();
+            eprintln!("Truncated to depth {}: {} -> {} nodes", max_depth, count(&model), count(&truncated));
+            truncated
+        }
+        None if covers.is_some() => fail("--covers requires --max-depth"),
+        None => model,
+    };
+    let model = match prune {
+        Some(max_error) => {
+            let samples = match &validation {
+                Some(path) => {
+                    let text = fs::read_to_string(path).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+                    let samples =
+                        parse_samples(&text, num_features).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+                    samples.iter().map(|sample| sample.iter().map(|&x| to_fixed_point(x)).collect()).collect()
+                }
+                None => Vec::new(),
+            };
+            let (pruned, report) = PruneReport::measure(&model, max_error, &samples);
+            eprintln!("Pruned: {}", report);
+            pruned
+        }
+        None if validation.is_some() => fail("--validation requires --prune"),
+        None => model,
+    };
+    if let Some(constraints) = &monotone {
+        if let Err(violations) = model.check_monotone(constraints) {
+            for violation in &violations {
+                eprintln!("{}", violation);
+            }
+            fail(&format!("the model breaks its monotone constraints at {} splits", violations.len()));
+        }
+    }
+    let options = RustOptions { layout, style, split_files };
+    let files = match target.as_str() {
+        "rust" => generate_rust_files(&model, num_features, &options),
+        "leo" if options != RustOptions::default() || format => {
+            fail("--layout, --style, --split and --rustfmt only apply to --target rust")
+        }
+        "leo" => vec![("main.leo".to_string(), generate_leo_source(&model, num_features, &program_name))],
+        other => fail(&format!("unknown --target '{}' (expected rust or leo)", other)),
+    };
+    let files: Vec<(String, String)> = match format {
+        true => files
+            .into_iter()
+            .map(|(name, source)| {
+                let formatted = rustfmt(&source);
+                (name.clone(), formatted.unwrap_or_else(|e| fail(&format!("rustfmt failed on {}: {}", name, e))))
+            })
+            .collect(),
+        false => files,
+    };
+
+    if let Some(path) = bit_widths {
+        let bounds = ValueBounds::analyze(&model, max_feature);
+        if let Err(e) = fs::write(&path, bounds.to_json().to_string()) {
+            fail(&format!("failed to write {}: {}", path, e));
+        }
+        eprintln!(
+            "Wrote bit widths to {}: comparisons {} bits, accumulator {} bits (recommended accumulator: {})",
+            path,
+            bounds.comparison_bits(),
+            bounds.accumulator_bits(),
+            bounds.recommended_accumulator().name()
+        );
+    }
+
+    match output {
+        Some(dir) if split_files => {
+            if let Err(e) = fs::create_dir_all(&dir) {
+                fail(&format!("failed to create {}: {}", dir, e));
+            }
+            for (name, source) in &files {
+                let path = Path::new(&dir).join(name);
+                if let Err(e) = fs::write(&path, source) {
+                    fail(&format!("failed to write {}: {}", path.display(), e));
+                }
+            }
+            eprintln!("Wrote {} trees to {} files in {}", model.num_trees(), files.len(), dir);
+        }
+        None if split_files => fail("--split requires -o DIR"),
+        Some(path) => {
+            if let Err(e) = fs::write(&path, &files[0].1) {
+                fail(&format!("failed to write {}: {}", path, e));
+            }
+            eprintln!("Wrote {} trees to {}", model.num_trees(), path);
+        }
+        None => print!("{}", files[0].1),
+    }
+}
+
+/// Format `source` with `rustfmt` (edition 2021), passing it through stdin
+fn rustfmt(source: &str) -> Result<String, String> {
+    let mut child = Command::new("rustfmt")
+        .args(["--edition", "2021"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("cannot run rustfmt: {}", e))?;
+    // Write from another thread so a full stdout pipe cannot block the write
+    let mut stdin = child.stdin.take().expect("piped stdin");
+    let source = source.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(source.as_bytes()));
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    writer.join().expect("rustfmt writer thread").map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    String::from_utf8(output.stdout).map_err(|e| e.to_string())
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("Error: {}", message);
+    std::process::exit(1);
+}
+
+fn print_usage(program_name: &str) {
+    eprintln!("Usage:");
+    eprintln!("  {} <model.json> [-o lib.rs] [--num-features N] [--target rust|leo] [--program NAME]", program_name);
+    eprintln!("      [--base-score X] [--combiner sum|average] [--layout unrolled|per-tree|table]");
+    eprintln!("      [--style nested-if|leaf-match] [--split] [--rustfmt]");
+    eprintln!("      [--bit-widths out.json [--max-feature X]] [--max-depth N [--covers covers.csv]]");
+    eprintln!("      [--prune X [--validation samples.csv]] [--monotone (1,0,-1,...)]");
+    eprintln!();
+    eprintln!("  model.json        XGBoost dump: booster.dump_model(path, dump_format='json')");
+    eprintln!("  -o, --output      Write the generated source to a file instead of stdout (a directory with --split)");
+    eprintln!("  --num-features    Feature count asserted by xgboost_predict (default {})", NUM_FEATURES);
+    eprintln!("  --target          Output language: rust (default) or leo (Aleo program)");
+    eprintln!("  --base-score      Initial prediction the trees are added to (XGBoost base_score, default 0)");
+    eprintln!("  --combiner        sum (boosting, default) or average (random forest: floored mean of the trees)");
+    eprintln!("  --layout          Rust code layout: unrolled (default), per-tree functions, or a const");
+    eprintln!("                    TREES table interpreted at runtime (fastest to compile for large models)");
+    eprintln!("  --style           Tree code: nested-if (default) with the leaf values in the branches, or");
+    eprintln!("                    leaf-match: the branches yield the leaf id and a match maps it to the value");
+    eprintln!("  --split           One module file per tree (tree_<i>.rs) next to lib.rs in the -o directory");
+    eprintln!("  --rustfmt         Format the output with rustfmt (must be on PATH)");
+    eprintln!("  --bit-widths      Also write the bounds and bit-width of every threshold, leaf and accumulator");
+    eprintln!("  --max-feature     Largest accepted |feature| for --bit-widths (default 99999, the missing value)");
+    eprintln!("  --max-depth       Cut every tree at depth N, replacing deeper subtrees by their average leaf");
+    eprintln!("  --covers          CSV of tree,leaf,cover rows weighting that average (default: uniform)");
+    eprintln!("  --prune           Merge subtrees whose leaves are within 2 * X, moving each tree's output by <= X");
+    eprintln!("  --validation      Feature CSV to measure the pruned model's prediction change on");
+    eprintln!("  --monotone        XGBoost monotone_constraints; fail if a split breaks them (1 up, -1 down)");
+    eprintln!("  --program         Aleo program name for --target leo (default rainfall_prediction)");
+}
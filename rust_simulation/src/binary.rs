@@ -0,0 +1,498 @@
+// Canonical binary model format
+// One compact encoding of an `Ensemble` shared by the simulator, the circuit generators and
+// on-chain verifiers. Every model has exactly one encoding, so the SHA-256 of the bytes is a
+// content hash that identifies the model.
+//
+// Layout (all integers little-endian):
+//   magic "ZKTE" | version u8 | scale i64 | num_features u32 | num_trees u32 | base_score i64 |
+//   combiner u8 | name str | model version str | training hash (0u8, or 1u8 | 32 bytes) | trees...
+// where a str is its UTF-8 length u32 followed by the bytes.
+// Version 1 files, written before the base score existed, have no base_score field and decode with 0;
+// versions 1 and 2 have no combiner byte (0 = sum, 1 = average) and decode as summed ensembles;
+// versions 1 to 3 have no metadata and decode with an empty name and version and no training hash.
+// Each tree is its nodes in pre-order:
+//   leaf:  0u8 | value i64
+//   split: 1u8 | feature u32 | threshold i64 | left subtree | right subtree
+//   categorical split: 2u8 | feature u32 | unit i64 | num_words u32 | words u64... | left subtree |
+//          right subtree (category bitset without trailing zero words)
+// Categorical nodes only add a tag, so models without them keep their version 3 encoding and hash.
+// Likewise an i128 accumulator only sets the high bit of the combiner byte (0x80), so models
+// with the default i64 accumulator keep their encoding and hash, and the training objective
+// takes bits 4 to 6 (0 = reg:squarederror, 1 = binary:logistic, 2 = binary:logitraw,
+// 3 = count:poisson, 4 = reg:tweedie, 5 = reg:gamma).
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::commitment::Digest;
+use crate::ensemble::{Accumulator, BitSet, Combiner, Ensemble, Node, Tree};
+use crate::objective::Objective;
+use crate::sha256::sha256;
+
+/// First bytes of every encoded model
+pub const MAGIC: [u8; 4] = *b"ZKTE";
+
+/// Current format version
+pub const FORMAT_VERSION: u8 = 4;
+
+/// Deepest tree accepted when decoding (guards the recursive decoder)
+const MAX_DEPTH: usize = 256;
+
+const TAG_LEAF: u8 = 0;
+const TAG_SPLIT: u8 = 1;
+const TAG_CATEGORICAL: u8 = 2;
+
+const COMBINER_SUM: u8 = 0;
+const COMBINER_AVERAGE: u8 = 1;
+/// Combiner byte flag of an `Accumulator::I128` model
+const WIDE_ACCUMULATOR: u8 = 0x80;
+/// Combiner byte bits holding the index of the objective in `Objective::ALL`
+const OBJECTIVE_MASK: u8 = 0x70;
+const OBJECTIVE_SHIFT: u32 = 4;
+
+/// Error raised while decoding a binary model
+#[derive(Debug)]
+pub enum BinaryError {
+    /// The file could not be read
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// The data does not start with `MAGIC`
+    BadMagic,
+    /// The data was written by an unknown format version
+    UnsupportedVersion(u8),
+    /// The data ends in the middle of a field
+    UnexpectedEnd { offset: usize },
+    /// A field has an invalid value
+    Invalid { offset: usize, message: &'static str },
+    /// Bytes remain after the last tree
+    TrailingBytes { offset: usize },
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            BinaryError::Io(e) => write!(f, "failed to read model: {}", e),
+            BinaryError::BadMagic => write!(f, "not a binary model (bad magic)"),
+            BinaryError::UnsupportedVersion(version) => {
+                write!(f, "unsupported binary model version {}", version)
+            }
+            BinaryError::UnexpectedEnd { offset } => write!(f, "unexpected end of data at byte {}", offset),
+            BinaryError::Invalid { offset, message } => write!(f, "invalid model at byte {}: {}", offset, message),
+            BinaryError::TrailingBytes { offset } => write!(f, "trailing bytes after byte {}", offset),
+        }
+    }
+}
+
+impl core::error::Error for BinaryError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for BinaryError {
+    fn from(e: std::io::Error) -> Self {
+        BinaryError::Io(e)
+    }
+}
+
+impl Ensemble {
+    /// Encode the model in the canonical binary format
+    ///
+    /// # Returns
+    /// * `Vec<u8>` - Bytes accepted by `Ensemble::from_bytes`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(30 + self.trees().iter().map(|t| 13 * t.num_nodes()).sum::<usize>());
+        out.extend_from_slice(&MAGIC);
+        out.push(FORMAT_VERSION);
+        out.extend_from_slice(&self.scale().to_le_bytes());
+        out.extend_from_slice(&to_u32(self.num_features()).to_le_bytes());
+        out.extend_from_slice(&to_u32(self.num_trees()).to_le_bytes());
+        out.extend_from_slice(&self.base_score().to_le_bytes());
+        out.push(combiner_byte(self.combiner(), self.accumulator()) | objective_bits(self.objective()));
+        write_str(&mut out, self.name());
+        write_str(&mut out, self.version());
+        write_digest(&mut out, self.training_hash());
+        for tree in self.trees() {
+            write_node(&mut out, &tree.root);
+        }
+        out
+    }
+
+    /// Decode a model written by `Ensemble::to_bytes`
+    ///
+    /// # Arguments
+    /// * `bytes` - Encoded model
+    ///
+    /// # Returns
+    /// * `Result<Ensemble, BinaryError>` - The model, identical to the one that was encoded
+    pub fn from_bytes(bytes: &[u8]) -> Result<Ensemble, BinaryError> {
+        let mut reader = ByteReader::new(bytes);
+
+        if reader.take(4)? != MAGIC {
+            return Err(BinaryError::BadMagic);
+        }
+        let version = reader.u8()?;
+        if version == 0 || version > FORMAT_VERSION {
+            return Err(BinaryError::UnsupportedVersion(version));
+        }
+
+        let offset = reader.offset;
+        let scale = reader.i64()?;
+        if scale <= 0 {
+            return Err(BinaryError::Invalid { offset, message: "scale must be positive" });
+        }
+        let num_features = reader.u32()? as usize;
+        let num_trees = reader.u32()? as usize;
+        let base_score = if version >= 2 { reader.i64()? } else { 0 };
+        let offset = reader.offset;
+        let combiner_byte = if version >= 3 { reader.u8()? } else { COMBINER_SUM };
+        let accumulator = if combiner_byte & WIDE_ACCUMULATOR != 0 { Accumulator::I128 } else { Accumulator::I64 };
+        let objective = match Objective::ALL.get(((combiner_byte & OBJECTIVE_MASK) >> OBJECTIVE_SHIFT) as usize) {
+            Some(&objective) => objective,
+            None => return Err(BinaryError::Invalid { offset, message: "unknown objective" }),
+        };
+        let combiner = match combiner_byte & !(WIDE_ACCUMULATOR | OBJECTIVE_MASK) {
+            COMBINER_SUM => Combiner::Sum,
+            COMBINER_AVERAGE => Combiner::Average,
+            _ => return Err(BinaryError::Invalid { offset, message: "unknown combiner" }),
+        };
+        let (name, model_version, training_hash) = if version >= 4 {
+            (reader.str()?, reader.str()?, reader.digest()?)
+        } else {
+            (String::new(), String::new(), None)
+        };
+
+        let mut trees = Vec::new();
+        for _ in 0..num_trees {
+            trees.push(Tree::new(read_node(&mut reader, num_features, 0)?));
+        }
+        if reader.offset != bytes.len() {
+            return Err(BinaryError::TrailingBytes { offset: reader.offset });
+        }
+
+        let model = Ensemble::new(trees, scale)
+            .with_num_features(num_features)
+            .with_base_score(base_score)
+            .with_combiner(combiner)
+            .with_accumulator(accumulator)
+            .with_objective(objective)
+            .with_name(name)
+            .with_version(model_version);
+        Ok(match training_hash {
+            Some(hash) => model.with_training_hash(hash),
+            None => model,
+        })
+    }
+
+    /// Load a binary model file
+    #[cfg(feature = "std")]
+    pub fn from_bytes_file<P: AsRef<Path>>(path: P) -> Result<Ensemble, BinaryError> {
+        Ensemble::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// SHA-256 of the canonical encoding, identifying the model for commitments
+    pub fn content_hash(&self) -> [u8; 32] {
+        sha256(&self.to_bytes())
+    }
+}
+
+/// Combiner byte of the format, shared with the model commitment
+pub(crate) fn combiner_byte(combiner: Combiner, accumulator: Accumulator) -> u8 {
+    let combiner = match combiner {
+        Combiner::Sum => COMBINER_SUM,
+        Combiner::Average => COMBINER_AVERAGE,
+    };
+    match accumulator {
+        Accumulator::I64 => combiner,
+        Accumulator::I128 => combiner | WIDE_ACCUMULATOR,
+    }
+}
+
+/// Objective bits of the combiner byte (the model commitment leaves them out: circuits prove the margin)
+fn objective_bits(objective: Objective) -> u8 {
+    let index = Objective::ALL.iter().position(|&o| o == objective).expect("objective is listed in ALL");
+    (index as u8) << OBJECTIVE_SHIFT
+}
+
+pub(crate) fn to_u32(value: usize) -> u32 {
+    u32::try_from(value).expect("count does not fit the binary format")
+}
+
+/// Write a length-prefixed UTF-8 string
+pub(crate) fn write_str(out: &mut Vec<u8>, value: &str) {
+    out.extend_from_slice(&to_u32(value.len()).to_le_bytes());
+    out.extend_from_slice(value.as_bytes());
+}
+
+/// Write an optional digest as a presence byte followed by the digest
+pub(crate) fn write_digest(out: &mut Vec<u8>, digest: Option<&Digest>) {
+    match digest {
+        Some(digest) => {
+            out.push(1);
+            out.extend_from_slice(digest);
+        }
+        None => out.push(0),
+    }
+}
+
+fn write_node(out: &mut Vec<u8>, node: &Node) {
+    match node {
+        Node::Leaf(value) => {
+            out.push(TAG_LEAF);
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        Node::Split { feature, threshold, left, right } => {
+            out.push(TAG_SPLIT);
+            out.extend_from_slice(&to_u32(*feature).to_le_bytes());
+            out.extend_from_slice(&threshold.to_le_bytes());
+            write_node(out, left);
+            write_node(out, right);
+        }
+        Node::Categorical { feature, categories, unit, left, right } => {
+            out.push(TAG_CATEGORICAL);
+            out.extend_from_slice(&to_u32(*feature).to_le_bytes());
+            out.extend_from_slice(&unit.to_le_bytes());
+            out.extend_from_slice(&to_u32(categories.words().len()).to_le_bytes());
+            for word in categories.words() {
+                out.extend_from_slice(&word.to_le_bytes());
+            }
+            write_node(out, left);
+            write_node(out, right);
+        }
+    }
+}
+
+fn read_node(reader: &mut ByteReader, num_features: usize, depth: usize) -> Result<Node, BinaryError> {
+    let offset = reader.offset;
+    if depth > MAX_DEPTH {
+        return Err(BinaryError::Invalid { offset, message: "tree too deep" });
+    }
+
+    match reader.u8()? {
+        TAG_LEAF => Ok(Node::Leaf(reader.i64()?)),
+        TAG_SPLIT => {
+            let feature = reader.u32()? as usize;
+            if feature >= num_features {
+                return Err(BinaryError::Invalid { offset, message: "split feature out of range" });
+            }
+            let threshold = reader.i64()?;
+            let left = read_node(reader, num_features, depth + 1)?;
+            let right = read_node(reader, num_features, depth + 1)?;
+            Ok(Node::Split { feature, threshold, left: Box::new(left), right: Box::new(right) })
+        }
+        TAG_CATEGORICAL => {
+            let feature = reader.u32()? as usize;
+            if feature >= num_features {
+                return Err(BinaryError::Invalid { offset, message: "split feature out of range" });
+            }
+            let unit = reader.i64()?;
+            if unit <= 0 {
+                return Err(BinaryError::Invalid { offset, message: "category unit must be positive" });
+            }
+            let num_words = reader.u32()? as usize;
+            let words = (0..num_words).map(|_| reader.u64()).collect::<Result<Vec<u64>, _>>()?;
+            if words.last() == Some(&0) {
+                return Err(BinaryError::Invalid { offset, message: "category set has trailing zero words" });
+            }
+            let left = read_node(reader, num_features, depth + 1)?;
+            let right = read_node(reader, num_features, depth + 1)?;
+            Ok(Node::categorical(feature, BitSet::from_words(words), unit, left, right))
+        }
+        _ => Err(BinaryError::Invalid { offset, message: "unknown node tag" }),
+    }
+}
+
+pub(crate) struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pub(crate) offset: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> ByteReader<'a> {
+        ByteReader { bytes, offset: 0 }
+    }
+
+    /// Whether every byte has been read
+    pub(crate) fn is_empty(&self) -> bool {
+        self.offset == self.bytes.len()
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], BinaryError> {
+        let end = self.offset + len;
+        let slice = self
+            .bytes
+            .get(self.offset..end)
+            .ok_or(BinaryError::UnexpectedEnd { offset: self.bytes.len() })?;
+        self.offset = end;
+        Ok(slice)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, BinaryError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, BinaryError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().expect("4 bytes")))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, BinaryError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().expect("8 bytes")))
+    }
+
+    pub(crate) fn i64(&mut self) -> Result<i64, BinaryError> {
+        Ok(i64::from_le_bytes(self.take(8)?.try_into().expect("8 bytes")))
+    }
+
+    /// Read a string written by `write_str`
+    pub(crate) fn str(&mut self) -> Result<String, BinaryError> {
+        let len = self.u32()? as usize;
+        let offset = self.offset;
+        let bytes = self.take(len)?;
+        core::str::from_utf8(bytes)
+            .map(String::from)
+            .map_err(|_| BinaryError::Invalid { offset, message: "string is not valid UTF-8" })
+    }
+
+    /// Read a digest written by `write_digest`
+    pub(crate) fn digest(&mut self) -> Result<Option<Digest>, BinaryError> {
+        let offset = self.offset;
+        match self.u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(self.take(32)?.try_into().expect("32 bytes"))),
+            _ => Err(BinaryError::Invalid { offset, message: "invalid digest flag" }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sha256::to_hex;
+
+    #[test]
+    fn test_round_trip_builtin_model() {
+        let model = crate::builtin_model();
+        let bytes = model.to_bytes();
+
+        assert_eq!(&bytes[..5], b"ZKTE\x04");
+        assert_eq!(Ensemble::from_bytes(&bytes).unwrap(), model);
+        assert_eq!(model.content_hash(), sha256(&bytes));
+        assert_eq!(to_hex(&model.content_hash()).len(), 64);
+    }
+
+    #[test]
+    fn test_encoding_is_exact() {
+        let model = Ensemble::new(vec![Tree::new(Node::split(1, -2, Node::Leaf(3), Node::Leaf(4)))], 10)
+            .with_base_score(5);
+        let mut expected = b"ZKTE\x04".to_vec();
+        expected.extend_from_slice(&[10, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0]);
+        expected.extend_from_slice(&[5, 0, 0, 0, 0, 0, 0, 0, 0]);
+        // Empty name and version, no training hash
+        expected.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let nodes = [
+            1, 1, 0, 0, 0, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        expected.extend_from_slice(&nodes);
+        assert_eq!(model.to_bytes(), expected);
+        assert_eq!(Ensemble::from_bytes(&expected).unwrap(), model);
+
+        // The combiner byte follows the base score
+        let forest = model.clone().with_combiner(Combiner::Average);
+        let mut averaged = expected.clone();
+        averaged[29] = 1;
+        assert_eq!(forest.to_bytes(), averaged);
+        assert_eq!(Ensemble::from_bytes(&averaged).unwrap(), forest);
+
+        // An i128 accumulator sets the high bit of the combiner byte
+        let wide = forest.clone().with_accumulator(Accumulator::I128);
+        averaged[29] = 0x81;
+        assert_eq!(wide.to_bytes(), averaged);
+        assert_eq!(Ensemble::from_bytes(&averaged).unwrap(), wide);
+
+        // The objective takes bits 4 to 6
+        let tweedie = wide.clone().with_objective(Objective::Tweedie);
+        averaged[29] = 0xc1;
+        assert_eq!(tweedie.to_bytes(), averaged);
+        assert_eq!(Ensemble::from_bytes(&averaged).unwrap(), tweedie);
+
+        // Metadata follows the combiner
+        let labelled = model.clone().with_name("rain").with_version("2.1").with_training_hash([7; 32]);
+        let bytes = labelled.to_bytes();
+        assert_eq!(&bytes[30..38], b"\x04\0\0\0rain");
+        assert_eq!(&bytes[38..45], b"\x03\0\0\x002.1");
+        assert_eq!(bytes[45], 1);
+        assert_eq!(&bytes[46..78], &[7; 32]);
+        assert_eq!(Ensemble::from_bytes(&bytes).unwrap(), labelled);
+
+        // Version 3 has no metadata
+        let mut v3 = b"ZKTE\x03".to_vec();
+        v3.extend_from_slice(&expected[5..30]);
+        v3.extend_from_slice(&nodes);
+        assert_eq!(Ensemble::from_bytes(&v3).unwrap(), model);
+
+        // Version 2 has no combiner
+        let mut v2 = b"ZKTE\x02".to_vec();
+        v2.extend_from_slice(&expected[5..29]);
+        v2.extend_from_slice(&nodes);
+        assert_eq!(Ensemble::from_bytes(&v2).unwrap(), model);
+
+        // Version 1 has no base score
+        let mut v1 = b"ZKTE\x01".to_vec();
+        v1.extend_from_slice(&[10, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0]);
+        v1.extend_from_slice(&nodes);
+        assert_eq!(Ensemble::from_bytes(&v1).unwrap(), model.with_base_score(0));
+    }
+
+    #[test]
+    fn test_categorical_split_encoding() {
+        let categories: BitSet = [0, 65].into_iter().collect();
+        let model = Ensemble::new(vec![Tree::new(Node::categorical(0, categories, 10, Node::Leaf(1), Node::Leaf(2)))], 10);
+        let bytes = model.to_bytes();
+        let mut node = vec![2, 0, 0, 0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0];
+        node.extend_from_slice(&1u64.to_le_bytes());
+        node.extend_from_slice(&2u64.to_le_bytes());
+        assert_eq!(&bytes[39..39 + node.len()], &node[..]);
+        assert_eq!(Ensemble::from_bytes(&bytes).unwrap(), model);
+
+        // A trailing zero word would give the same set a second encoding
+        let mut padded = bytes[..39].to_vec();
+        padded.extend_from_slice(&[2, 0, 0, 0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0]);
+        padded.extend_from_slice(&[0; 8]);
+        padded.extend_from_slice(&bytes[39 + node.len()..]);
+        assert!(matches!(Ensemble::from_bytes(&padded), Err(BinaryError::Invalid { offset: 39, .. })));
+    }
+
+    #[test]
+    fn test_rejects_malformed_data() {
+        let bytes = crate::builtin_model().to_bytes();
+
+        assert!(matches!(Ensemble::from_bytes(b"JSON{}"), Err(BinaryError::BadMagic)));
+        assert!(matches!(Ensemble::from_bytes(b"ZKTE\x05"), Err(BinaryError::UnsupportedVersion(5))));
+        assert!(matches!(
+            Ensemble::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(BinaryError::UnexpectedEnd { .. })
+        ));
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(Ensemble::from_bytes(&trailing), Err(BinaryError::TrailingBytes { .. })));
+
+        // Split on feature 116 of a 116-feature model
+        let root = 30 + 4 + crate::MODEL_ID.len() + 4 + crate::MODEL_VERSION.len() + 1;
+        let mut bad_feature = bytes.clone();
+        bad_feature[root + 1..root + 5].copy_from_slice(&116u32.to_le_bytes());
+        assert!(matches!(Ensemble::from_bytes(&bad_feature), Err(BinaryError::Invalid { offset, .. }) if offset == root));
+
+        let mut bad_name = bytes.clone();
+        bad_name[34] = 0xff;
+        assert!(matches!(Ensemble::from_bytes(&bad_name), Err(BinaryError::Invalid { offset: 34, .. })));
+
+        let mut bad_combiner = bytes.clone();
+        bad_combiner[29] = 2;
+        assert!(matches!(Ensemble::from_bytes(&bad_combiner), Err(BinaryError::Invalid { offset: 29, .. })));
+
+        let mut bad_objective = bytes;
+        bad_objective[29] = 0x60;
+        assert!(matches!(Ensemble::from_bytes(&bad_objective), Err(BinaryError::Invalid { offset: 29, .. })));
+    }
+}
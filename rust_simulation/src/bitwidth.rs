@@ -0,0 +1,223 @@
+// Bit-width analysis
+// Bounds every value a circuit handles for a given model: the thresholds each feature is compared
+// against, the leaves of each tree and the accumulator after each tree. Leaves and thresholds are
+// constants, so these bounds are exact and need no witness. With a bound on the inputs (explicit
+// or from a `FeatureSchema`), backends size their range checks and comparisons from this instead
+// of assuming full 64-bit values.
+
+use alloc::{string::ToString, vec, vec::Vec};
+
+use crate::ensemble::{Accumulator, Ensemble, Node};
+use crate::json::JsonValue;
+use crate::schema::{FeatureSchema, MISSING_VALUE};
+
+/// Bits of magnitude of a value: the smallest `n` with `|value| < 2^n`
+pub fn magnitude_bits(value: i128) -> usize {
+    (128 - value.unsigned_abs().leading_zeros()) as usize
+}
+
+/// Exact bounds on the values of a model evaluation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueBounds {
+    /// Largest accepted |feature| (inputs are range-checked against it)
+    pub max_feature: u64,
+    /// Largest |threshold| each feature is compared against (0 for features no split uses)
+    pub thresholds: Vec<u64>,
+    /// Smallest and largest leaf of each tree
+    pub leaves: Vec<(i64, i64)>,
+    /// Smallest and largest accumulator after each tree, starting from the base score (from 0
+    /// for averaging ensembles, whose accumulator is the tree sum before division)
+    pub accumulators: Vec<(i128, i128)>,
+}
+
+impl ValueBounds {
+    /// Analyze a model for inputs with |feature| <= `max_feature`
+    ///
+    /// # Arguments
+    /// * `model` - Model to analyze
+    /// * `max_feature` - Largest accepted |feature| (scaled)
+    ///
+    /// # Returns
+    /// * `ValueBounds` - Bounds on every threshold, leaf and accumulator
+    pub fn analyze(model: &Ensemble, max_feature: u64) -> ValueBounds {
+        let mut thresholds = vec![0u64; model.num_features()];
+        let mut leaves = Vec::with_capacity(model.num_trees());
+        let mut accumulators = Vec::with_capacity(model.num_trees());
+
+        let initial = model.combiner().initial(model.base_score()) as i128;
+        let (mut low, mut high) = (initial, initial);
+        for tree in model.trees() {
+            let (min, max) = collect(&tree.root, &mut thresholds);
+            low += min as i128;
+            high += max as i128;
+            leaves.push((min, max));
+            accumulators.push((low, high));
+        }
+
+        ValueBounds { max_feature, thresholds, leaves, accumulators }
+    }
+
+    /// Analyze a model for the inputs a schema accepts (including the missing-value sentinel)
+    pub fn analyze_with_schema(model: &Ensemble, schema: &FeatureSchema) -> ValueBounds {
+        let max_feature = schema
+            .features()
+            .iter()
+            .flat_map(|range| {
+                let missing = if range.allow_missing { MISSING_VALUE } else { 0 };
+                [range.min, range.max, missing].map(i64::unsigned_abs)
+            })
+            .max()
+            .unwrap_or(0);
+        ValueBounds::analyze(model, max_feature)
+    }
+
+    /// Bits of the largest accepted |feature|
+    pub fn feature_bits(&self) -> usize {
+        magnitude_bits(self.max_feature as i128)
+    }
+
+    /// Bits of the largest |threshold|
+    pub fn threshold_bits(&self) -> usize {
+        self.thresholds.iter().map(|&t| magnitude_bits(t as i128)).max().unwrap_or(0)
+    }
+
+    /// Bound on both operands of every comparison: |feature|, |threshold| < 2^comparison_bits
+    pub fn comparison_bits(&self) -> usize {
+        self.feature_bits().max(self.threshold_bits())
+    }
+
+    /// Bits of the largest |accumulator| over all trees (and the base score)
+    pub fn accumulator_bits(&self) -> usize {
+        self.accumulators
+            .iter()
+            .flat_map(|&(low, high)| [low, high])
+            .map(magnitude_bits)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether no accumulator can leave the i64 range, i.e. `fixed_add` never saturates
+    pub fn fits_i64(&self) -> bool {
+        self.accumulator_bits() < 64
+    }
+
+    /// Narrowest accumulator that sums this model exactly
+    ///
+    /// # Returns
+    /// * `Accumulator` - `I64` when no partial sum can leave the i64 range (the two modes then
+    ///   agree on every input), `I128` otherwise
+    pub fn recommended_accumulator(&self) -> Accumulator {
+        if self.fits_i64() {
+            Accumulator::I64
+        } else {
+            Accumulator::I128
+        }
+    }
+
+    /// Machine-readable form with the bit-width of every value
+    pub fn to_json(&self) -> JsonValue {
+        let number = |value: usize| JsonValue::Number(value.to_string());
+        let thresholds = self
+            .thresholds
+            .iter()
+            .map(|&t| number(magnitude_bits(t as i128)))
+            .collect();
+        let trees = self
+            .leaves
+            .iter()
+            .zip(&self.accumulators)
+            .map(|(&(min, max), &(low, high))| {
+                JsonValue::Object(vec![
+                    ("leaf_min".to_string(), JsonValue::Number(min.to_string())),
+                    ("leaf_max".to_string(), JsonValue::Number(max.to_string())),
+                    ("leaf_bits".to_string(), number(magnitude_bits(min as i128).max(magnitude_bits(max as i128)))),
+                    ("accumulator_min".to_string(), JsonValue::Number(low.to_string())),
+                    ("accumulator_max".to_string(), JsonValue::Number(high.to_string())),
+                    ("accumulator_bits".to_string(), number(magnitude_bits(low).max(magnitude_bits(high)))),
+                ])
+            })
+            .collect();
+        JsonValue::Object(vec![
+            ("max_feature".to_string(), JsonValue::Number(self.max_feature.to_string())),
+            ("feature_bits".to_string(), number(self.feature_bits())),
+            ("threshold_bits".to_string(), number(self.threshold_bits())),
+            ("comparison_bits".to_string(), number(self.comparison_bits())),
+            ("accumulator_bits".to_string(), number(self.accumulator_bits())),
+            ("accumulator".to_string(), JsonValue::String(self.recommended_accumulator().name().to_string())),
+            ("feature_threshold_bits".to_string(), JsonValue::Array(thresholds)),
+            ("trees".to_string(), JsonValue::Array(trees)),
+        ])
+    }
+}
+
+/// Record the thresholds of a subtree and return its smallest and largest leaf
+fn collect(node: &Node, thresholds: &mut [u64]) -> (i64, i64) {
+    match node {
+        Node::Leaf(value) => (*value, *value),
+        Node::Split { feature, threshold, left, right } => {
+            thresholds[*feature] = thresholds[*feature].max(threshold.unsigned_abs());
+            let (left_min, left_max) = collect(left, thresholds);
+            let (right_min, right_max) = collect(right, thresholds);
+            (left_min.min(right_min), left_max.max(right_max))
+        }
+        // Category membership is an equality test: no comparison to size
+        Node::Categorical { left, right, .. } => {
+            let (left_min, left_max) = collect(left, thresholds);
+            let (right_min, right_max) = collect(right, thresholds);
+            (left_min.min(right_min), left_max.max(right_max))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::Tree;
+
+    #[test]
+    fn test_bounds_of_small_model() {
+        let trees = vec![
+            Tree::new(Node::split(0, -700, Node::Leaf(5), Node::split(2, 40, Node::Leaf(-3), Node::Leaf(9)))),
+            Tree::new(Node::split(0, 1000, Node::Leaf(-20), Node::Leaf(4))),
+        ];
+        let model = Ensemble::new(trees, 1).with_base_score(2);
+        let bounds = ValueBounds::analyze(&model, 255);
+
+        assert_eq!(bounds.thresholds, vec![1000, 0, 40]);
+        assert_eq!(bounds.leaves, vec![(-3, 9), (-20, 4)]);
+        assert_eq!(bounds.accumulators, vec![(-1, 11), (-21, 15)]);
+        assert_eq!((bounds.feature_bits(), bounds.threshold_bits(), bounds.comparison_bits()), (8, 10, 10));
+        assert_eq!(bounds.accumulator_bits(), 5);
+        assert!(bounds.fits_i64());
+        assert_eq!(bounds.recommended_accumulator(), Accumulator::I64);
+        assert_eq!(magnitude_bits(0), 0);
+        assert_eq!(magnitude_bits(-16), 5);
+    }
+
+    #[test]
+    fn test_recommends_i128_for_large_sums() {
+        let trees = vec![Tree::new(Node::Leaf(1 << 62)), Tree::new(Node::Leaf(1 << 62)), Tree::new(Node::Leaf(-5))];
+        let bounds = ValueBounds::analyze(&Ensemble::new(trees, 1), 0);
+        assert_eq!(bounds.accumulator_bits(), 64);
+        assert!(!bounds.fits_i64());
+        assert_eq!(bounds.recommended_accumulator(), Accumulator::I128);
+        assert_eq!(bounds.to_json().get("accumulator").and_then(JsonValue::as_str), Some("i128"));
+    }
+
+    #[test]
+    fn test_builtin_model_with_schema() {
+        let model = crate::builtin_model();
+        let bounds = ValueBounds::analyze_with_schema(&model, &FeatureSchema::rainfall());
+        // The missing-value sentinel dominates the feature range
+        assert_eq!(bounds.max_feature, MISSING_VALUE.unsigned_abs());
+        assert_eq!(bounds.feature_bits(), 50);
+        assert!(bounds.comparison_bits() < 62);
+        assert!(bounds.accumulator_bits() < bounds.comparison_bits());
+
+        // Every running sum of an actual evaluation stays within its bounds
+        let trace = crate::xgboost_predict_with_trace(&[0; crate::NUM_FEATURES]).1;
+        for (tree, &(low, high)) in trace.trees.iter().zip(&bounds.accumulators) {
+            assert!((low..=high).contains(&tree.accumulator));
+        }
+    }
+}
@@ -0,0 +1,311 @@
+// Threshold boundary cases
+// Off-by-one errors in threshold handling (a `<` where the model means `<=`, a threshold rounded
+// the wrong way when a model is rescaled, bucketized or emitted as code) only show on inputs that
+// sit exactly at a threshold, which random inputs almost never hit. For every threshold split,
+// `boundary_cases` builds inputs that reach the split and put its feature at threshold - 1,
+// threshold and threshold + 1 (scaled units), recording the branch the reference semantics take:
+// `value <= threshold` goes left. `check_boundaries` runs an evaluator on every case and compares
+// its prediction with `Ensemble::eval`.
+//
+// Reaching a split means satisfying the conditions on the path above it. Each feature on the path
+// is narrowed to an interval by the threshold splits and must be inside or outside the category
+// sets of the categorical splits; `solve_path` picks a value per feature from a few candidates.
+// Splits whose path is contradictory, or whose boundary value falls outside the path's interval,
+// are unreachable there and produce no case.
+
+use alloc::{vec, vec::Vec};
+use core::fmt;
+
+use crate::ensemble::{BitSet, Ensemble, Node};
+
+/// Input that puts one split's feature next to its threshold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundaryCase {
+    /// Index of the tree in the ensemble
+    pub tree: usize,
+    /// Branches from the root to the split, `true` for left
+    pub path: Vec<bool>,
+    pub feature: usize,
+    pub threshold: i64,
+    /// Distance of the feature value from the threshold: -1, 0 or 1
+    pub offset: i64,
+    /// Full feature vector (at the ensemble's scale)
+    pub features: Vec<i64>,
+}
+
+impl BoundaryCase {
+    /// Feature value at the split
+    pub fn value(&self) -> i64 {
+        self.features[self.feature]
+    }
+
+    /// Branch the reference semantics take: left when `value <= threshold`
+    pub fn goes_left(&self) -> bool {
+        self.offset <= 0
+    }
+}
+
+/// Boundary case on which an evaluator disagrees with `Ensemble::eval`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundaryFailure {
+    pub case: BoundaryCase,
+    /// Prediction of `Ensemble::eval`
+    pub expected: i64,
+    /// Prediction of the evaluator under test
+    pub got: i64,
+}
+
+impl fmt::Display for BoundaryFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let case = &self.case;
+        write!(
+            f,
+            "tree {} split on f{} at threshold {}{:+}: expected {}, got {} ({} branch)",
+            case.tree,
+            case.feature,
+            case.threshold,
+            case.offset,
+            self.expected,
+            self.got,
+            if case.goes_left() { "left" } else { "right" }
+        )
+    }
+}
+
+/// Conditions a path puts on one feature
+#[derive(Clone)]
+struct FeatureConstraint<'a> {
+    low: i64,
+    high: i64,
+    /// Category sets (with their unit) the value must be in
+    inside: Vec<(&'a BitSet, i64)>,
+    /// Category sets the value must not be in
+    outside: Vec<(&'a BitSet, i64)>,
+}
+
+impl FeatureConstraint<'_> {
+    fn new() -> Self {
+        FeatureConstraint { low: i64::MIN, high: i64::MAX, inside: Vec::new(), outside: Vec::new() }
+    }
+
+    fn allows(&self, value: i64) -> bool {
+        (self.low..=self.high).contains(&value)
+            && self.inside.iter().all(|(set, unit)| set.contains_value(value, *unit))
+            && self.outside.iter().all(|(set, unit)| !set.contains_value(value, *unit))
+    }
+
+    /// A value meeting every condition, preferring 0 and the interval's ends
+    fn pick(&self) -> Option<i64> {
+        if self.low > self.high {
+            return None;
+        }
+        if let Some((set, unit)) = self.inside.first() {
+            return set.values(*unit).find(|&value| self.allows(value));
+        }
+        let clamp = |value: i64| value.clamp(self.low, self.high);
+        [clamp(0), self.low, self.high, self.low.saturating_add(1), self.high.saturating_sub(1), clamp(-1)]
+            .into_iter()
+            .find(|&value| self.allows(value))
+    }
+}
+
+/// Feature vector that follows `path` from `root`, with `pinned` features fixed to given values
+///
+/// Features the path does not test are 0. Returns None if no such vector was found.
+pub(crate) fn solve_path(
+    root: &Node,
+    path: &[bool],
+    num_features: usize,
+    pinned: &[(usize, i64)],
+) -> Option<Vec<i64>> {
+    let mut constraints = vec![FeatureConstraint::new(); num_features];
+    let mut node = root;
+    for &left in path {
+        node = match node {
+            Node::Leaf(_) => return None,
+            Node::Split { feature, threshold, left: l, right: r } => {
+                let constraint = &mut constraints[*feature];
+                if left {
+                    constraint.high = constraint.high.min(*threshold);
+                } else {
+                    constraint.low = constraint.low.max(threshold.checked_add(1)?);
+                }
+                if left { l } else { r }
+            }
+            Node::Categorical { feature, categories, unit, left: l, right: r } => {
+                let constraint = &mut constraints[*feature];
+                if left {
+                    constraint.inside.push((categories, *unit));
+                } else {
+                    constraint.outside.push((categories, *unit));
+                }
+                if left { l } else { r }
+            }
+        };
+    }
+
+    let mut features = vec![0; num_features];
+    for (feature, constraint) in constraints.iter().enumerate() {
+        features[feature] = match pinned.iter().find(|(pinned, _)| *pinned == feature) {
+            Some(&(_, value)) => Some(value).filter(|&value| constraint.allows(value))?,
+            None => constraint.pick()?,
+        };
+    }
+    Some(features)
+}
+
+/// Boundary cases of every threshold split of `model`
+///
+/// # Arguments
+/// * `model` - Ensemble whose splits are enumerated
+///
+/// # Returns
+/// * `Vec<BoundaryCase>` - Up to three cases per split, in tree and depth-first order; boundary
+///   values the path to the split rules out are left out
+pub fn boundary_cases(model: &Ensemble) -> Vec<BoundaryCase> {
+    let num_features = model.num_features();
+    let mut cases = Vec::new();
+    for (tree, root) in model.trees().iter().map(|tree| &tree.root).enumerate() {
+        let mut stack = vec![(root, Vec::new())];
+        while let Some((node, path)) = stack.pop() {
+            let (left, right) = match node {
+                Node::Leaf(_) => continue,
+                Node::Split { feature, threshold, left, right } => {
+                    for offset in [-1, 0, 1] {
+                        let Some(value) = threshold.checked_add(offset) else { continue };
+                        if let Some(features) = solve_path(root, &path, num_features, &[(*feature, value)]) {
+                            let (feature, threshold) = (*feature, *threshold);
+                            cases.push(BoundaryCase { tree, path: path.clone(), feature, threshold, offset, features });
+                        }
+                    }
+                    (left, right)
+                }
+                Node::Categorical { left, right, .. } => (left, right),
+            };
+            let mut right_path = path.clone();
+            right_path.push(false);
+            stack.push((right, right_path));
+            let mut left_path = path;
+            left_path.push(true);
+            stack.push((left, left_path));
+        }
+    }
+    cases
+}
+
+/// Run `eval` on every boundary case of `model` and compare it with `Ensemble::eval`
+///
+/// A wrong branch only shows when the two subtrees of the split give different predictions for
+/// the case, so a split with equal-valued subtrees cannot be checked this way.
+///
+/// # Arguments
+/// * `model` - Reference ensemble
+/// * `eval` - Evaluator under test, taking features at the ensemble's scale
+///
+/// # Returns
+/// * `Result<usize, BoundaryFailure>` - Number of cases checked, or the first disagreement
+pub fn check_boundaries<F>(model: &Ensemble, eval: F) -> Result<usize, BoundaryFailure>
+where
+    F: Fn(&[i64]) -> i64,
+{
+    let cases = boundary_cases(model);
+    for case in &cases {
+        let (expected, got) = (model.eval(&case.features), eval(&case.features));
+        if expected != got {
+            return Err(BoundaryFailure { case: case.clone(), expected, got });
+        }
+    }
+    Ok(cases.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::Tree;
+    use crate::{builtin_model, xgboost_predict, xgboost_predict_oblivious, DecisionTable};
+
+    fn model() -> Ensemble {
+        let categories: BitSet = [1, 3].into_iter().collect();
+        let below = Node::split(0, 3, Node::Leaf(4), Node::Leaf(5));
+        let inner = Node::categorical(1, categories, 10, Node::Leaf(3), below);
+        let left = Node::split(0, 3, inner, Node::Leaf(6));
+        let root = Node::split(0, 5, left, Node::split(1, -3, Node::Leaf(1), Node::Leaf(2)));
+        Ensemble::new(vec![Tree::new(root)], 10)
+    }
+
+    /// `value < threshold`, i.e. `value <= threshold - 1`, at every threshold split
+    fn strict(node: &Node) -> Node {
+        match node {
+            Node::Leaf(value) => Node::Leaf(*value),
+            Node::Split { feature, threshold, left, right } => {
+                Node::split(*feature, threshold - 1, strict(left), strict(right))
+            }
+            Node::Categorical { feature, categories, unit, left, right } => {
+                Node::categorical(*feature, categories.clone(), *unit, strict(left), strict(right))
+            }
+        }
+    }
+
+    #[test]
+    fn test_boundary_cases_reach_their_split() {
+        let model = model();
+        let cases = boundary_cases(&model);
+        let summary: Vec<(Vec<bool>, i64, i64)> =
+            cases.iter().map(|case| (case.path.clone(), case.threshold, case.value())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (vec![], 5, 4),
+                (vec![], 5, 5),
+                (vec![], 5, 6),
+                (vec![true], 3, 2),
+                (vec![true], 3, 3),
+                (vec![true], 3, 4),
+                // Already below 3 on this path, so threshold + 1 is unreachable
+                (vec![true, true, false], 3, 2),
+                (vec![true, true, false], 3, 3),
+                (vec![false], -3, -4),
+                (vec![false], -3, -3),
+                (vec![false], -3, -2),
+            ]
+        );
+        for case in &cases {
+            // Follow the path, then check the split sends the case to the reference branch
+            let mut node = &model.trees()[0].root;
+            for &left in &case.path {
+                assert_eq!(node.goes_left(&case.features), Some(left), "{:?}", case);
+                node = match node {
+                    Node::Split { left: l, right: r, .. } | Node::Categorical { left: l, right: r, .. } => {
+                        if left { l } else { r }
+                    }
+                    Node::Leaf(_) => unreachable!(),
+                };
+            }
+            assert_eq!(node.goes_left(&case.features), Some(case.goes_left()), "{:?}", case);
+        }
+    }
+
+    #[test]
+    fn test_builtin_evaluators_agree_at_every_threshold() {
+        let model = builtin_model();
+        let cases = boundary_cases(&model);
+        let splits: usize = model.trees().iter().map(|tree| (tree.num_nodes() - 1) / 2).sum();
+        assert_eq!(cases.len(), 3 * splits);
+        assert!(cases.iter().all(|case| case.features.len() == model.num_features()));
+
+        let table = DecisionTable::new(&model);
+        assert_eq!(check_boundaries(&model, xgboost_predict), Ok(cases.len()));
+        assert_eq!(check_boundaries(&model, xgboost_predict_oblivious), Ok(cases.len()));
+        assert_eq!(check_boundaries(&model, |features| table.eval(features)), Ok(cases.len()));
+    }
+
+    #[test]
+    fn test_detects_strict_comparison() {
+        let model = model();
+        let strict = model.with_trees(model.trees().iter().map(|tree| Tree::new(strict(&tree.root))).collect());
+        let failure = check_boundaries(&model, |features| strict.eval(features)).unwrap_err();
+        assert_eq!((failure.case.threshold, failure.case.offset), (5, 0));
+        assert_eq!((failure.expected, failure.got), (6, 2));
+        assert_eq!(failure.to_string(), "tree 0 split on f0 at threshold 5+0: expected 6, got 2 (left branch)");
+    }
+}
@@ -0,0 +1,250 @@
+// Bucketized feature quantization
+// A split only ever asks on which side of its threshold a feature falls, so a feature can be
+// replaced by the number of the model's thresholds on it that lie below the value: with the
+// distinct thresholds t_0 < t_1 < ... of a feature, `x <= t_k` holds exactly when the bucket
+// index of x is at most k. Rewriting every threshold t_k as k gives a model that predicts the same
+// on bucket indices as the original on raw features, while every comparison involves small
+// integers (|bucket| < #buckets). Features split on categorically get one bucket per member value
+// of their category sets, plus bucket 0 for every other value. Circuits built on the bucketized
+// model then need log2(#buckets)-bit comparisons instead of 64-bit ones; the mapping from raw
+// features to buckets happens outside the circuit, so a proof's features hash commits to the
+// bucket indices.
+
+use alloc::{vec, vec::Vec};
+use core::fmt;
+
+use crate::bitwidth::ValueBounds;
+use crate::ensemble::{BitSet, Ensemble, Node, Tree};
+
+/// Error building the bucket tables of a model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BucketError {
+    /// A feature is used by both threshold and categorical splits, which need different buckets
+    MixedSplits { feature: usize },
+}
+
+impl fmt::Display for BucketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BucketError::MixedSplits { feature } => {
+                write!(f, "feature {} is used by both threshold and categorical splits", feature)
+            }
+        }
+    }
+}
+
+impl core::error::Error for BucketError {}
+
+/// How one feature is mapped to its bucket index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeatureBucket {
+    /// Bucket `k` holds the values in (t_(k-1), t_k]: the index is the number of thresholds
+    /// below the value (sorted, distinct)
+    Thresholds(Vec<i64>),
+    /// Bucket `k + 1` is member value `values[k]` (sorted, distinct); bucket 0 any other value
+    Categories(Vec<i64>),
+}
+
+impl FeatureBucket {
+    /// Bucket index of a raw feature value
+    pub fn bucket(&self, value: i64) -> i64 {
+        match self {
+            FeatureBucket::Thresholds(thresholds) => thresholds.partition_point(|&t| t < value) as i64,
+            FeatureBucket::Categories(values) => values.binary_search(&value).map_or(0, |k| k as i64 + 1),
+        }
+    }
+
+    /// Number of buckets of the feature
+    pub fn num_buckets(&self) -> usize {
+        match self {
+            FeatureBucket::Thresholds(values) | FeatureBucket::Categories(values) => values.len() + 1,
+        }
+    }
+}
+
+/// Bucket tables of every feature of a model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureBuckets {
+    features: Vec<FeatureBucket>,
+}
+
+/// Record the thresholds and categorical member values each feature is split on
+fn collect(node: &Node, thresholds: &mut [Vec<i64>], members: &mut [Vec<i64>]) {
+    match node {
+        Node::Leaf(_) => {}
+        Node::Split { feature, threshold, left, right } => {
+            thresholds[*feature].push(*threshold);
+            collect(left, thresholds, members);
+            collect(right, thresholds, members);
+        }
+        Node::Categorical { feature, categories, unit, left, right } => {
+            members[*feature].extend(categories.values(*unit));
+            collect(left, thresholds, members);
+            collect(right, thresholds, members);
+        }
+    }
+}
+
+impl FeatureBuckets {
+    /// Build the bucket tables from the splits of a model
+    ///
+    /// # Arguments
+    /// * `model` - Model whose thresholds and category sets define the buckets
+    ///
+    /// # Returns
+    /// * `Result<FeatureBuckets, BucketError>` - One table per feature, or the first feature
+    ///   split both ways
+    pub fn from_model(model: &Ensemble) -> Result<FeatureBuckets, BucketError> {
+        let mut thresholds = vec![Vec::new(); model.num_features()];
+        let mut members = vec![Vec::new(); model.num_features()];
+        for tree in model.trees() {
+            collect(&tree.root, &mut thresholds, &mut members);
+        }
+
+        let features = thresholds
+            .into_iter()
+            .zip(members)
+            .enumerate()
+            .map(|(feature, (mut thresholds, mut members))| {
+                if !thresholds.is_empty() && !members.is_empty() {
+                    return Err(BucketError::MixedSplits { feature });
+                }
+                thresholds.sort_unstable();
+                thresholds.dedup();
+                members.sort_unstable();
+                members.dedup();
+                Ok(if members.is_empty() { FeatureBucket::Thresholds(thresholds) } else { FeatureBucket::Categories(members) })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(FeatureBuckets { features })
+    }
+
+    /// Bucket table of every feature
+    pub fn features(&self) -> &[FeatureBucket] {
+        &self.features
+    }
+
+    /// Largest bucket index of any feature
+    pub fn max_bucket(&self) -> u64 {
+        self.features.iter().map(|f| f.num_buckets() as u64 - 1).max().unwrap_or(0)
+    }
+
+    /// Map raw features to their bucket indices
+    pub fn bucketize(&self, features: &[i64]) -> Vec<i64> {
+        assert!(features.len() >= self.features.len(),
+                "Expected at least {} features, got {}", self.features.len(), features.len());
+        self.features.iter().zip(features).map(|(table, &value)| table.bucket(value)).collect()
+    }
+}
+
+/// Rewrite the splits of a subtree over bucket indices
+fn rewrite(node: &Node, buckets: &[FeatureBucket]) -> Node {
+    match node {
+        Node::Leaf(value) => Node::Leaf(*value),
+        Node::Split { feature, threshold, left, right } => {
+            let k = buckets[*feature].bucket(*threshold);
+            Node::split(*feature, k, rewrite(left, buckets), rewrite(right, buckets))
+        }
+        Node::Categorical { feature, categories, unit, left, right } => {
+            let categories: BitSet =
+                categories.values(*unit).map(|value| buckets[*feature].bucket(value) as u32).collect();
+            Node::categorical(*feature, categories, 1, rewrite(left, buckets), rewrite(right, buckets))
+        }
+    }
+}
+
+/// Model evaluated on bucket indices, with the tables mapping raw features to them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BucketizedModel {
+    buckets: FeatureBuckets,
+    model: Ensemble,
+}
+
+impl Ensemble {
+    /// Quantize the features to bucket indices of the model's own split points
+    ///
+    /// # Returns
+    /// * `Result<BucketizedModel, BucketError>` - Bucket tables and the adjusted model, whose
+    ///   prediction on bucket indices equals this model's on raw features
+    pub fn bucketize(&self) -> Result<BucketizedModel, BucketError> {
+        let buckets = FeatureBuckets::from_model(self)?;
+        let trees = self.trees().iter().map(|tree| Tree::new(rewrite(&tree.root, &buckets.features))).collect();
+        Ok(BucketizedModel { buckets, model: self.with_trees(trees) })
+    }
+}
+
+impl BucketizedModel {
+    /// Bucket tables mapping raw features to indices
+    pub fn buckets(&self) -> &FeatureBuckets {
+        &self.buckets
+    }
+
+    /// Adjusted model over bucket indices (thresholds are bucket indices, leaves unchanged)
+    pub fn model(&self) -> &Ensemble {
+        &self.model
+    }
+
+    /// Predict from raw features through their bucket indices, identical to the original model
+    pub fn eval(&self, features: &[i64]) -> i64 {
+        self.model.eval(&self.buckets.bucketize(features))
+    }
+
+    /// Bounds of the adjusted model for bucket-index inputs, for `with_bounds` on the circuits
+    pub fn bounds(&self) -> ValueBounds {
+        ValueBounds::analyze(&self.model, self.buckets.max_bucket())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builtin_model, xgboost_predict, Combiner, FeatureSchema, MISSING_VALUE, NUM_FEATURES};
+
+    #[test]
+    fn test_builtin_bucketized() {
+        let model = builtin_model();
+        let bucketized = model.bucketize().unwrap();
+        let bounds = bucketized.bounds();
+        assert_eq!(bounds.comparison_bits(), 5);
+        assert!(ValueBounds::analyze_with_schema(&model, &FeatureSchema::rainfall()).comparison_bits() > 40);
+
+        for i in 0..64i64 {
+            let mut features: Vec<i64> =
+                (0..NUM_FEATURES as i64).map(|j| ((i * 31 + j * 19) % 300) * 1_000_000_000 - 60_000_000_000).collect();
+            features[(i as usize * 7) % NUM_FEATURES] = MISSING_VALUE;
+            assert_eq!(bucketized.eval(&features), xgboost_predict(&features));
+        }
+    }
+
+    #[test]
+    fn test_bucket_boundaries() {
+        let categories: BitSet = [2, 5].into_iter().collect();
+        let trees = vec![
+            Tree::new(Node::split(0, 10, Node::split(0, -3, Node::Leaf(1), Node::Leaf(2)), Node::Leaf(3))),
+            Tree::new(Node::split(0, 10, Node::Leaf(4), Node::categorical(1, categories, 7, Node::Leaf(5), Node::Leaf(6)))),
+        ];
+        let model = Ensemble::new(trees, 1).with_combiner(Combiner::Average).with_base_score(9);
+        let bucketized = model.bucketize().unwrap();
+        assert_eq!(bucketized.buckets().features()[0], FeatureBucket::Thresholds(vec![-3, 10]));
+        assert_eq!(bucketized.buckets().features()[1], FeatureBucket::Categories(vec![14, 35]));
+        assert_eq!(bucketized.buckets().bucketize(&[-3, 35]), vec![0, 2]);
+        assert_eq!(bucketized.buckets().bucketize(&[-2, 13]), vec![1, 0]);
+        assert_eq!(bucketized.buckets().bucketize(&[11, 14]), vec![2, 1]);
+        assert_eq!(bucketized.buckets().max_bucket(), 2);
+
+        for x in [i64::MIN, -4, -3, -2, 10, 11, i64::MAX] {
+            for y in [0, 14, 21, 35] {
+                assert_eq!(bucketized.eval(&[x, y]), model.eval(&[x, y]), "{} {}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mixed_splits_rejected() {
+        let categories: BitSet = [1].into_iter().collect();
+        let tree = Tree::new(Node::split(0, 4, Node::categorical(0, categories, 1, Node::Leaf(1), Node::Leaf(2)), Node::Leaf(3)));
+        let error = Ensemble::new(vec![tree], 1).bucketize().unwrap_err();
+        assert_eq!(error, BucketError::MixedSplits { feature: 0 });
+        assert_eq!(error.to_string(), "feature 0 is used by both threshold and categorical splits");
+    }
+}
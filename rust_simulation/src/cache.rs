@@ -0,0 +1,197 @@
+// Prediction cache
+// Radar pipelines re-score overlapping windows, so the same feature vector is often predicted
+// many times. `PredictionCache` keeps the most recently used predictions keyed by the Poseidon
+// hash of the scaled features (`hash_features`, the digest proofs already commit to) and evicts
+// the least recently used entry once it is full. Hits, misses and evictions are counted so that
+// servers can report the hit rate. A cache belongs to one model: clear it when the model changes.
+
+use alloc::collections::BTreeMap;
+use core::fmt;
+
+use crate::poseidon::{hash_features, PoseidonDigest};
+
+/// Counters of a `PredictionCache`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// Lookups answered from the cache
+    pub hits: u64,
+    /// Lookups that had to run the model
+    pub misses: u64,
+    /// Entries dropped to make room for newer ones
+    pub evictions: u64,
+    /// Entries currently held
+    pub len: usize,
+    /// Maximum number of entries
+    pub capacity: usize,
+}
+
+impl CacheStats {
+    /// Fraction of lookups answered from the cache (0 before the first lookup)
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} hits, {} misses ({:.1}% hit rate), {} evictions, {}/{} entries",
+            self.hits,
+            self.misses,
+            100.0 * self.hit_rate(),
+            self.evictions,
+            self.len,
+            self.capacity
+        )
+    }
+}
+
+/// Least-recently-used cache from feature hash to scaled prediction
+#[derive(Debug, Clone)]
+pub struct PredictionCache {
+    /// Prediction and last use of every cached feature hash
+    entries: BTreeMap<PoseidonDigest, (i64, u64)>,
+    /// Feature hash by last use, oldest first
+    recency: BTreeMap<u64, PoseidonDigest>,
+    /// Incremented on every use
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl PredictionCache {
+    /// Create an empty cache holding at most `capacity` predictions (0 disables caching)
+    pub fn new(capacity: usize) -> PredictionCache {
+        PredictionCache {
+            entries: BTreeMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+            stats: CacheStats { capacity, ..CacheStats::default() },
+        }
+    }
+
+    /// Cached prediction for a feature hash, marking it as recently used
+    ///
+    /// Counts a hit or a miss; after a miss, `insert` the computed prediction.
+    pub fn get(&mut self, key: &PoseidonDigest) -> Option<i64> {
+        let clock = self.tick();
+        let Some((prediction, last_use)) = self.entries.get_mut(key) else {
+            self.stats.misses += 1;
+            return None;
+        };
+        self.stats.hits += 1;
+        self.recency.remove(last_use);
+        self.recency.insert(clock, *key);
+        *last_use = clock;
+        Some(*prediction)
+    }
+
+    /// Store a prediction, evicting the least recently used entry if the cache is full
+    pub fn insert(&mut self, key: PoseidonDigest, prediction: i64) {
+        if self.stats.capacity == 0 {
+            return;
+        }
+        let clock = self.tick();
+        if let Some((_, last_use)) = self.entries.insert(key, (prediction, clock)) {
+            self.recency.remove(&last_use);
+        } else if self.entries.len() > self.stats.capacity {
+            let (_, oldest) = self.recency.pop_first().expect("a full cache has a least recently used entry");
+            self.entries.remove(&oldest);
+            self.stats.evictions += 1;
+        }
+        self.recency.insert(clock, key);
+        self.stats.len = self.entries.len();
+    }
+
+    /// Prediction for `features`, from the cache or computed with `predict` and cached
+    ///
+    /// # Arguments
+    /// * `features` - Scaled feature vector
+    /// * `predict` - Model evaluation, run only on a miss
+    ///
+    /// # Returns
+    /// * `i64` - The same prediction `predict(features)` returns
+    pub fn predict<F: FnOnce(&[i64]) -> i64>(&mut self, features: &[i64], predict: F) -> i64 {
+        let key = hash_features(features);
+        if let Some(prediction) = self.get(&key) {
+            return prediction;
+        }
+        let prediction = predict(features);
+        self.insert(key, prediction);
+        prediction
+    }
+
+    /// Hit, miss and eviction counters
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Drop every entry (e.g. after loading another model), keeping the counters
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.stats.len = 0;
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_hits_and_misses() {
+        let mut cache = PredictionCache::new(4);
+        let mut calls = 0;
+        let mut predict = |features: &[i64]| {
+            calls += 1;
+            features.iter().sum::<i64>()
+        };
+        assert_eq!(cache.predict(&[1, 2], &mut predict), 3);
+        assert_eq!(cache.predict(&[1, 2], &mut predict), 3);
+        assert_eq!(cache.predict(&[2, 1], &mut predict), 3);
+        assert_eq!(calls, 2);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.len), (1, 2, 2));
+        assert!((stats.hit_rate() - 1.0 / 3.0).abs() < 1e-12);
+        assert_eq!(stats.to_string(), "1 hits, 2 misses (33.3% hit rate), 0 evictions, 2/4 entries");
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = PredictionCache::new(2);
+        let keys: Vec<PoseidonDigest> = (0..3).map(|i| hash_features(&[i])).collect();
+        cache.insert(keys[0], 10);
+        cache.insert(keys[1], 11);
+        // Using key 0 makes key 1 the oldest
+        assert_eq!(cache.get(&keys[0]), Some(10));
+        cache.insert(keys[2], 12);
+        assert_eq!(cache.get(&keys[1]), None);
+        assert_eq!(cache.get(&keys[0]), Some(10));
+        assert_eq!(cache.get(&keys[2]), Some(12));
+        assert_eq!(cache.stats().evictions, 1);
+        assert_eq!((cache.stats().hits, cache.stats().misses), (3, 1));
+
+        // Overwriting keeps the size
+        cache.insert(keys[2], 13);
+        assert_eq!(cache.get(&keys[2]), Some(13));
+        assert_eq!(cache.stats().len, 2);
+
+        cache.clear();
+        assert_eq!(cache.get(&keys[0]), None);
+        let mut disabled = PredictionCache::new(0);
+        assert_eq!(disabled.predict(&[5; 3], |f| f[0]), 5);
+        assert_eq!(disabled.stats().len, 0);
+    }
+}
@@ -0,0 +1,220 @@
+// Probability calibration
+// Maps a raw margin to a calibrated probability with the two methods of sklearn's
+// CalibratedClassifierCV, using scaled i64 parameters only:
+//   Platt scaling  p = 1 / (1 + exp(a * margin + b)), with `fixed_sigmoid`
+//   isotonic       monotone piecewise-linear map through (threshold, value) breakpoints,
+//                  clamped to the first and last value outside the thresholds
+// Between two breakpoints the isotonic map interpolates with one floor division,
+//   y[i] + floor((y[i+1] - y[i]) * (m - x[i]) / (x[i+1] - x[i]))
+// which `r1cs::isotonic_gadget` proves with a quotient and a remainder in [0, x[i+1] - x[i]).
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::fixed::{round_half_away, saturate};
+use crate::{fixed_add, fixed_sigmoid, PRECISION_MULTIPLIER};
+
+/// Error raised when building an isotonic calibration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CalibrationError {
+    /// No breakpoints were given
+    Empty,
+    /// Thresholds and values differ in length
+    LengthMismatch { thresholds: usize, values: usize },
+    /// Threshold `index` is not larger than the one before it
+    Unsorted { index: usize },
+    /// Value `index` breaks the monotonicity of the values before it
+    NotMonotone { index: usize },
+}
+
+impl fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalibrationError::Empty => write!(f, "isotonic calibration needs at least one breakpoint"),
+            CalibrationError::LengthMismatch { thresholds, values } => {
+                write!(f, "{} thresholds but {} values", thresholds, values)
+            }
+            CalibrationError::Unsorted { index } => write!(f, "threshold {} is not strictly increasing", index),
+            CalibrationError::NotMonotone { index } => write!(f, "value {} is not monotone", index),
+        }
+    }
+}
+
+impl core::error::Error for CalibrationError {}
+
+/// Platt scaling with sklearn's sign convention: `p = 1 / (1 + exp(a * margin + b))`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlattCalibration {
+    /// Slope (scaled by 10^10), negative for a classifier whose margin grows with the positive class
+    pub a: i64,
+    /// Offset (scaled by 10^10)
+    pub b: i64,
+}
+
+impl PlattCalibration {
+    /// Quantize fitted parameters (sklearn's `_SigmoidCalibration.a_` and `b_`)
+    pub fn from_f64(a: f64, b: f64) -> PlattCalibration {
+        let quantize = |value: f64| round_half_away(value * PRECISION_MULTIPLIER as f64);
+        PlattCalibration { a: quantize(a), b: quantize(b) }
+    }
+
+    /// Calibrated probability (scaled by 10^10) of a margin (scaled by 10^10)
+    ///
+    /// `a * margin` is rounded to nearest like `LinearModel::eval` before the sigmoid.
+    pub fn apply(&self, margin: i64) -> i64 {
+        let scale = PRECISION_MULTIPLIER as i128;
+        let product = saturate((self.a as i128 * margin as i128 + scale / 2).div_euclid(scale));
+        let z = fixed_add(product, self.b);
+        fixed_sigmoid(z.checked_neg().unwrap_or(i64::MAX))
+    }
+}
+
+/// Isotonic calibration: a monotone piecewise-linear map through scaled breakpoints
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IsotonicCalibration {
+    thresholds: Vec<i64>,
+    values: Vec<i64>,
+}
+
+impl IsotonicCalibration {
+    /// Build the map from its breakpoints
+    ///
+    /// # Arguments
+    /// * `thresholds` - Strictly increasing margins (sklearn's `X_thresholds_`, scaled)
+    /// * `values` - Monotone calibrated values at the thresholds (`y_thresholds_`, scaled)
+    ///
+    /// # Returns
+    /// * `Result<IsotonicCalibration, CalibrationError>` - The map, or why the breakpoints are invalid
+    pub fn new(thresholds: Vec<i64>, values: Vec<i64>) -> Result<IsotonicCalibration, CalibrationError> {
+        if thresholds.is_empty() {
+            return Err(CalibrationError::Empty);
+        }
+        if thresholds.len() != values.len() {
+            return Err(CalibrationError::LengthMismatch { thresholds: thresholds.len(), values: values.len() });
+        }
+        if let Some(index) = (1..thresholds.len()).find(|&i| thresholds[i] <= thresholds[i - 1]) {
+            return Err(CalibrationError::Unsorted { index });
+        }
+        // Non-decreasing (sklearn's default) or non-increasing (`increasing=False`)
+        let increasing = values.first() <= values.last();
+        let breaks = |i: usize| if increasing { values[i] < values[i - 1] } else { values[i] > values[i - 1] };
+        if let Some(index) = (1..values.len()).find(|&i| breaks(i)) {
+            return Err(CalibrationError::NotMonotone { index });
+        }
+        Ok(IsotonicCalibration { thresholds, values })
+    }
+
+    /// Quantize fitted breakpoints to scale 10^10
+    pub fn from_f64(thresholds: &[f64], values: &[f64]) -> Result<IsotonicCalibration, CalibrationError> {
+        let quantize = |values: &[f64]| values.iter().map(|&v| round_half_away(v * PRECISION_MULTIPLIER as f64)).collect();
+        IsotonicCalibration::new(quantize(thresholds), quantize(values))
+    }
+
+    /// Breakpoint margins, strictly increasing
+    pub fn thresholds(&self) -> &[i64] {
+        &self.thresholds
+    }
+
+    /// Calibrated values at the breakpoints
+    pub fn values(&self) -> &[i64] {
+        &self.values
+    }
+
+    /// Calibrated value of a margin
+    pub fn apply(&self, margin: i64) -> i64 {
+        let last = self.thresholds.len() - 1;
+        // Index of the last threshold <= margin, if any
+        match self.thresholds.partition_point(|&x| x <= margin) {
+            0 => self.values[0],
+            n if n > last => self.values[last],
+            n => self.interpolate(n - 1, margin),
+        }
+    }
+
+    /// Value at `margin` on segment `i`, i.e. for thresholds[i] <= margin < thresholds[i + 1]
+    pub(crate) fn interpolate(&self, i: usize, margin: i64) -> i64 {
+        let (x0, x1) = (self.thresholds[i] as i128, self.thresholds[i + 1] as i128);
+        let (y0, y1) = (self.values[i] as i128, self.values[i + 1] as i128);
+        saturate(y0 + ((y1 - y0) * (margin as i128 - x0)).div_euclid(x1 - x0))
+    }
+}
+
+/// Calibration applied to a raw margin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Calibration {
+    Platt(PlattCalibration),
+    Isotonic(IsotonicCalibration),
+}
+
+impl Calibration {
+    /// Calibrated probability (scaled by 10^10) of a margin (scaled by 10^10)
+    pub fn apply(&self, margin: i64) -> i64 {
+        match self {
+            Calibration::Platt(platt) => platt.apply(margin),
+            Calibration::Isotonic(isotonic) => isotonic.apply(margin),
+        }
+    }
+}
+
+impl From<PlattCalibration> for Calibration {
+    fn from(platt: PlattCalibration) -> Self {
+        Calibration::Platt(platt)
+    }
+}
+
+impl From<IsotonicCalibration> for Calibration {
+    fn from(isotonic: IsotonicCalibration) -> Self {
+        Calibration::Isotonic(isotonic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::to_fixed_point;
+    use alloc::vec;
+
+    #[test]
+    fn test_isotonic_interpolates_and_clamps() {
+        let map = IsotonicCalibration::new(vec![-10, 0, 30], vec![100, 200, 260]).unwrap();
+        assert_eq!(map.apply(-11), 100);
+        assert_eq!(map.apply(-10), 100);
+        assert_eq!(map.apply(-5), 150);
+        assert_eq!(map.apply(10), 220);
+        // 200 + 60 * 1 / 30 = 202; 200 + 60 * 29 / 30 = 258
+        assert_eq!(map.apply(1), 202);
+        assert_eq!(map.apply(29), 258);
+        assert_eq!(map.apply(30), 260);
+        assert_eq!(map.apply(i64::MAX), 260);
+
+        // Monotone inputs give monotone outputs
+        let outputs: Vec<i64> = (-20..40).map(|m| map.apply(m)).collect();
+        assert!(outputs.windows(2).all(|w| w[0] <= w[1]));
+
+        let decreasing = IsotonicCalibration::new(vec![0, 4], vec![10, 0]).unwrap();
+        assert_eq!(decreasing.apply(1), 7);
+    }
+
+    #[test]
+    fn test_invalid_breakpoints() {
+        assert_eq!(IsotonicCalibration::new(vec![], vec![]), Err(CalibrationError::Empty));
+        assert_eq!(
+            IsotonicCalibration::new(vec![0, 1], vec![0]),
+            Err(CalibrationError::LengthMismatch { thresholds: 2, values: 1 })
+        );
+        assert_eq!(IsotonicCalibration::new(vec![0, 0], vec![0, 1]), Err(CalibrationError::Unsorted { index: 1 }));
+        assert_eq!(IsotonicCalibration::new(vec![0, 1, 2], vec![0, 5, 3]), Err(CalibrationError::NotMonotone { index: 2 }));
+    }
+
+    #[test]
+    fn test_platt_scaling() {
+        // a = -2, b = 0.5: p = sigmoid(2 * margin - 0.5)
+        let platt = PlattCalibration::from_f64(-2.0, 0.5);
+        for margin in [-3.0, -0.25, 0.0, 0.7, 4.0] {
+            let expected = 1.0 / (1.0 + (-2.0 * margin + 0.5f64).exp());
+            let got = platt.apply(to_fixed_point(margin)) as f64 / PRECISION_MULTIPLIER as f64;
+            assert!((got - expected).abs() < crate::SIGMOID_MAX_ERROR, "{}: {} vs {}", margin, got, expected);
+        }
+        assert_eq!(Calibration::from(platt).apply(to_fixed_point(0.25)), 5_000_000_000);
+    }
+}
@@ -0,0 +1,515 @@
+// Canonical JSON documents
+// Traces, model commitments and proof metadata leave the crate wrapped in a versioned envelope,
+// `{"schema_version": 1, "kind": "...", "body": {...}}`, so verifiers written in other languages
+// know exactly what they read. `validate_document` checks a document strictly against schema
+// version 1 (also published as JSON Schema in `schema/v1.json`): every object has exactly its
+// listed members, integers are written as integers, hashes as lowercase hex, and a trace's paths,
+// comparisons and output must agree with each other. A new version is only needed when a member
+// is added, removed or changes meaning; optional members (a trace's guard, accumulator width and
+// fingerprint, the fingerprint of proof metadata) extend version 1.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::commitment::ModelCommitment;
+use crate::ensemble::{Accumulator, Combiner, Ensemble};
+use crate::fingerprint::{EvaluatorMode, Fingerprint};
+use crate::guard::{GuardDecision, OutputGuard};
+use crate::info::ModelInfo;
+use crate::json::{self, JsonError, JsonValue};
+use crate::poseidon::PoseidonDigest;
+use crate::sha256::to_hex;
+use crate::trace::ExecutionTrace;
+use crate::witness::WitnessBundle;
+
+/// Version of the document schema this crate writes and accepts
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// What a document's body holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    /// `ExecutionTrace::to_json`
+    Trace,
+    /// Root and per-tree roots of a `ModelCommitment`
+    Commitment,
+    /// `ProofMetadata`
+    ProofMetadata,
+}
+
+impl DocumentKind {
+    /// Every kind
+    pub const ALL: [DocumentKind; 3] = [DocumentKind::Trace, DocumentKind::Commitment, DocumentKind::ProofMetadata];
+
+    /// Value of the `kind` member
+    pub fn name(self) -> &'static str {
+        match self {
+            DocumentKind::Trace => "trace",
+            DocumentKind::Commitment => "commitment",
+            DocumentKind::ProofMetadata => "proof_metadata",
+        }
+    }
+}
+
+/// Document rejected by `validate_document`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    /// Not valid JSON
+    Json(JsonError),
+    /// `schema_version` is not one this crate reads (its JSON text)
+    UnsupportedVersion(String),
+    /// A member is missing, unexpected or malformed
+    Invalid { path: String, message: String },
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::Json(e) => write!(f, "{}", e),
+            SchemaError::UnsupportedVersion(version) => {
+                write!(f, "unsupported schema_version {} (expected {})", version, SCHEMA_VERSION)
+            }
+            SchemaError::Invalid { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+impl core::error::Error for SchemaError {}
+
+impl From<JsonError> for SchemaError {
+    fn from(e: JsonError) -> Self {
+        SchemaError::Json(e)
+    }
+}
+
+/// Public facts about a proof: the model it was made with and its public inputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofMetadata {
+    pub info: ModelInfo,
+    /// SHA-256 content hash of the model
+    pub model_hash: [u8; 32],
+    /// Public prediction (at the model's scale)
+    pub prediction: i64,
+    /// Poseidon hash of the private features
+    pub features_hash: PoseidonDigest,
+    /// Build that prepared the witness
+    pub fingerprint: Fingerprint,
+}
+
+impl ProofMetadata {
+    /// Metadata of a proof of `bundle`, prepared for `model`
+    pub fn new(model: &Ensemble, bundle: &WitnessBundle) -> ProofMetadata {
+        ProofMetadata {
+            info: model.model_info(),
+            model_hash: bundle.model_hash,
+            prediction: bundle.prediction,
+            features_hash: bundle.features_hash(),
+            fingerprint: bundle.trace.fingerprint.clone().unwrap_or_else(|| {
+                Fingerprint::with_model_hash(bundle.model_hash, model.scale(), EvaluatorMode::Branching)
+            }),
+        }
+    }
+
+    /// Canonical document of kind `proof_metadata`
+    pub fn to_document(&self) -> JsonValue {
+        let body = JsonValue::Object(vec![
+            ("model".to_string(), self.info.to_json()),
+            ("model_hash".to_string(), JsonValue::String(to_hex(&self.model_hash))),
+            ("prediction".to_string(), JsonValue::Number(self.prediction.to_string())),
+            ("features_hash".to_string(), digest_json(&self.features_hash)),
+            ("fingerprint".to_string(), self.fingerprint.to_json()),
+        ]);
+        document(DocumentKind::ProofMetadata, body)
+    }
+}
+
+impl ExecutionTrace {
+    /// Canonical document of kind `trace`
+    pub fn to_document(&self) -> JsonValue {
+        document(DocumentKind::Trace, self.to_json())
+    }
+}
+
+impl ModelCommitment {
+    /// Canonical document of kind `commitment`
+    pub fn to_document(&self) -> JsonValue {
+        let tree_roots = self.tree_roots().iter().map(|root| JsonValue::String(to_hex(root))).collect();
+        let body = JsonValue::Object(vec![
+            ("root".to_string(), JsonValue::String(to_hex(&self.root()))),
+            ("tree_roots".to_string(), JsonValue::Array(tree_roots)),
+        ]);
+        document(DocumentKind::Commitment, body)
+    }
+}
+
+fn document(kind: DocumentKind, body: JsonValue) -> JsonValue {
+    JsonValue::Object(vec![
+        ("schema_version".to_string(), JsonValue::Number(SCHEMA_VERSION.to_string())),
+        ("kind".to_string(), JsonValue::String(kind.name().to_string())),
+        ("body".to_string(), body),
+    ])
+}
+
+// Goldilocks elements can exceed i64, so they are written unsigned
+fn digest_json(digest: &PoseidonDigest) -> JsonValue {
+    JsonValue::Array(digest.iter().map(|x| JsonValue::Number(x.to_string())).collect())
+}
+
+/// Validate a canonical document
+///
+/// # Arguments
+/// * `text` - JSON text of the document
+///
+/// # Returns
+/// * `Result<DocumentKind, SchemaError>` - Kind of a valid document, or the first problem found
+pub fn validate_document(text: &str) -> Result<DocumentKind, SchemaError> {
+    validate_value(&json::parse(text)?)
+}
+
+/// `validate_document` for an already parsed document
+pub fn validate_value(document: &JsonValue) -> Result<DocumentKind, SchemaError> {
+    let members = object(document, "$", &["schema_version", "kind", "body"], &[])?;
+    let version = &members[0];
+    if version.as_usize().map(|v| v as u64) != Some(SCHEMA_VERSION) {
+        return Err(SchemaError::UnsupportedVersion(version.to_string()));
+    }
+    let kind = DocumentKind::ALL
+        .into_iter()
+        .find(|kind| members[1].as_str() == Some(kind.name()))
+        .ok_or_else(|| invalid("$.kind", "expected \"trace\", \"commitment\" or \"proof_metadata\""))?;
+    match kind {
+        DocumentKind::Trace => validate_trace(members[2], "$.body")?,
+        DocumentKind::Commitment => validate_commitment(members[2], "$.body")?,
+        DocumentKind::ProofMetadata => validate_metadata(members[2], "$.body")?,
+    }
+    Ok(kind)
+}
+
+fn invalid(path: &str, message: &str) -> SchemaError {
+    SchemaError::Invalid { path: path.to_string(), message: message.to_string() }
+}
+
+/// Members of an object in the order of `required` then `optional` (absent optional ones are
+/// `Null`), rejecting duplicate and unknown members
+fn object<'a>(
+    value: &'a JsonValue,
+    path: &str,
+    required: &[&str],
+    optional: &[&str],
+) -> Result<Vec<&'a JsonValue>, SchemaError> {
+    let members = value.as_object().ok_or_else(|| invalid(path, "expected an object"))?;
+    for (index, (key, _)) in members.iter().enumerate() {
+        if !required.contains(&key.as_str()) && !optional.contains(&key.as_str()) {
+            return Err(invalid(path, &format!("unexpected member `{}`", key)));
+        }
+        if members[..index].iter().any(|(other, _)| other == key) {
+            return Err(invalid(path, &format!("duplicate member `{}`", key)));
+        }
+    }
+    const NULL: JsonValue = JsonValue::Null;
+    required
+        .iter()
+        .map(|key| value.get(key).ok_or_else(|| invalid(path, &format!("missing member `{}`", key))))
+        .chain(optional.iter().map(|key| Ok(value.get(key).unwrap_or(&NULL))))
+        .collect()
+}
+
+fn member(path: &str, key: &str) -> String {
+    format!("{}.{}", path, key)
+}
+
+fn integer(value: &JsonValue, path: &str) -> Result<i64, SchemaError> {
+    value.as_i64().ok_or_else(|| invalid(path, "expected an integer in the i64 range"))
+}
+
+fn wide_integer(value: &JsonValue, path: &str) -> Result<i128, SchemaError> {
+    value.as_i128().ok_or_else(|| invalid(path, "expected an integer in the i128 range"))
+}
+
+fn index(value: &JsonValue, path: &str) -> Result<usize, SchemaError> {
+    value.as_usize().ok_or_else(|| invalid(path, "expected a non-negative integer"))
+}
+
+fn array<'a>(value: &'a JsonValue, path: &str) -> Result<&'a [JsonValue], SchemaError> {
+    value.as_array().ok_or_else(|| invalid(path, "expected an array"))
+}
+
+fn string<'a>(value: &'a JsonValue, path: &str) -> Result<&'a str, SchemaError> {
+    value.as_str().ok_or_else(|| invalid(path, "expected a string"))
+}
+
+fn hash(value: &JsonValue, path: &str) -> Result<(), SchemaError> {
+    let hex = string(value, path)?;
+    if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+        return Err(invalid(path, "expected 64 lowercase hex digits"));
+    }
+    Ok(())
+}
+
+fn digest(value: &JsonValue, path: &str) -> Result<(), SchemaError> {
+    let elements = array(value, path)?;
+    if elements.len() != 4 || !elements.iter().all(|x| matches!(x, JsonValue::Number(n) if n.parse::<u64>().is_ok())) {
+        return Err(invalid(path, "expected 4 unsigned 64-bit integers"));
+    }
+    Ok(())
+}
+
+fn validate_trace(body: &JsonValue, path: &str) -> Result<(), SchemaError> {
+    let required = ["output", "features_hash", "base_score", "combiner", "trees"];
+    let fields = object(body, path, &required, &["guard", "accumulator", "fingerprint"])?;
+    let output = integer(fields[0], &member(path, "output"))?;
+    digest(fields[1], &member(path, "features_hash"))?;
+    let base_score = integer(fields[2], &member(path, "base_score"))?;
+    let combiner = match string(fields[3], &member(path, "combiner"))? {
+        "sum" => Combiner::Sum,
+        "average" => Combiner::Average,
+        _ => return Err(invalid(&member(path, "combiner"), "expected \"sum\" or \"average\"")),
+    };
+    let width = match fields[6] {
+        JsonValue::Null => Accumulator::I64,
+        width => Accumulator::from_name(string(width, &member(path, "accumulator"))?)
+            .ok_or_else(|| invalid(&member(path, "accumulator"), "expected \"i64\" or \"i128\""))?,
+    };
+
+    let trees_path = member(path, "trees");
+    let trees = array(fields[4], &trees_path)?;
+    let mut accumulator = combiner.initial(base_score) as i128;
+    for (t, tree) in trees.iter().enumerate() {
+        let tree_path = format!("{}[{}]", trees_path, t);
+        let members = object(tree, &tree_path, &["path", "comparisons", "leaf_value", "accumulator"], &[])?;
+        let node_path = member(&tree_path, "path");
+        let nodes = array(members[0], &node_path)?
+            .iter()
+            .map(|node| index(node, &node_path))
+            .collect::<Result<Vec<_>, _>>()?;
+        let comparisons_path = member(&tree_path, "comparisons");
+        let comparisons = array(members[1], &comparisons_path)?;
+        if nodes.len() != comparisons.len() + 1 {
+            return Err(invalid(&tree_path, "the path must visit one node more than it has comparisons"));
+        }
+        for (c, comparison) in comparisons.iter().enumerate() {
+            let comparison_path = format!("{}[{}]", comparisons_path, c);
+            let members = object(
+                comparison,
+                &comparison_path,
+                &["node_id", "feature", "value", "threshold", "outcome"],
+                &["categories"],
+            )?;
+            if index(members[0], &member(&comparison_path, "node_id"))? != nodes[c] {
+                return Err(invalid(&comparison_path, "node_id differs from the path"));
+            }
+            index(members[1], &member(&comparison_path, "feature"))?;
+            integer(members[2], &member(&comparison_path, "value"))?;
+            integer(members[3], &member(&comparison_path, "threshold"))?;
+            if members[4].as_bool().is_none() {
+                return Err(invalid(&member(&comparison_path, "outcome"), "expected a boolean"));
+            }
+            if *members[5] != JsonValue::Null {
+                let categories_path = member(&comparison_path, "categories");
+                for category in array(members[5], &categories_path)? {
+                    if category.as_usize().is_none_or(|c| c > u32::MAX as usize) {
+                        return Err(invalid(&categories_path, "expected category codes (u32)"));
+                    }
+                }
+            }
+        }
+        integer(members[2], &member(&tree_path, "leaf_value"))?;
+        let accumulator_path = member(&tree_path, "accumulator");
+        accumulator = wide_integer(members[3], &accumulator_path)?;
+        if width == Accumulator::I64 && i64::try_from(accumulator).is_err() {
+            return Err(invalid(&accumulator_path, "expected an integer in the i64 range"));
+        }
+    }
+
+    let mut expected = combiner.finish_wide(base_score, accumulator, trees.len());
+    if *fields[5] != JsonValue::Null {
+        let guard_path = member(path, "guard");
+        let members = object(fields[5], &guard_path, &["min", "max", "clamp", "decision"], &[])?;
+        let min = integer(members[0], &member(&guard_path, "min"))?;
+        let max = integer(members[1], &member(&guard_path, "max"))?;
+        let clamp = members[2].as_bool().ok_or_else(|| invalid(&member(&guard_path, "clamp"), "expected a boolean"))?;
+        if min > max {
+            return Err(invalid(&guard_path, "min exceeds max"));
+        }
+        let guard = OutputGuard { min, max, clamp };
+        let (guarded, decision) = guard.apply(expected);
+        let recorded = string(members[3], &member(&guard_path, "decision"))?;
+        if ![GuardDecision::Within, GuardDecision::Below, GuardDecision::Above].iter().any(|d| d.name() == recorded) {
+            return Err(invalid(&member(&guard_path, "decision"), "expected \"within\", \"below\" or \"above\""));
+        }
+        if recorded != decision.name() {
+            return Err(invalid(&member(&guard_path, "decision"), "does not match the final accumulator"));
+        }
+        expected = guarded;
+    }
+    if output != expected {
+        return Err(invalid(&member(path, "output"), "does not match the final accumulator"));
+    }
+    if *fields[7] != JsonValue::Null {
+        fingerprint(fields[7], &member(path, "fingerprint"))?;
+    }
+    Ok(())
+}
+
+fn fingerprint(value: &JsonValue, path: &str) -> Result<Fingerprint, SchemaError> {
+    let fields = object(value, path, &["model_hash", "crate_version", "precision", "evaluator"], &[])?;
+    hash(fields[0], &member(path, "model_hash"))?;
+    string(fields[1], &member(path, "crate_version"))?;
+    integer(fields[2], &member(path, "precision"))?;
+    string(fields[3], &member(path, "evaluator"))?;
+    Fingerprint::from_json(value).map_err(|e| invalid(path, &e.to_string()))
+}
+
+fn validate_commitment(body: &JsonValue, path: &str) -> Result<(), SchemaError> {
+    let fields = object(body, path, &["root", "tree_roots"], &[])?;
+    hash(fields[0], &member(path, "root"))?;
+    let roots_path = member(path, "tree_roots");
+    for root in array(fields[1], &roots_path)? {
+        hash(root, &roots_path)?;
+    }
+    Ok(())
+}
+
+fn validate_metadata(body: &JsonValue, path: &str) -> Result<(), SchemaError> {
+    let fields = object(body, path, &["model", "model_hash", "prediction", "features_hash"], &["fingerprint"])?;
+    let model_path = member(path, "model");
+    let model = object(
+        fields[0],
+        &model_path,
+        &["name", "version", "feature_count", "tree_count", "training_hash", "precision"],
+        &[],
+    )?;
+    string(model[0], &member(&model_path, "name"))?;
+    string(model[1], &member(&model_path, "version"))?;
+    index(model[2], &member(&model_path, "feature_count"))?;
+    index(model[3], &member(&model_path, "tree_count"))?;
+    if *model[4] != JsonValue::Null {
+        hash(model[4], &member(&model_path, "training_hash"))?;
+    }
+    let precision = integer(model[5], &member(&model_path, "precision"))?;
+    if precision <= 0 {
+        return Err(invalid(&member(&model_path, "precision"), "expected a positive scale"));
+    }
+    hash(fields[1], &member(path, "model_hash"))?;
+    integer(fields[2], &member(path, "prediction"))?;
+    digest(fields[3], &member(path, "features_hash"))?;
+    if *fields[4] != JsonValue::Null {
+        let fingerprint_path = member(path, "fingerprint");
+        let fingerprint = fingerprint(fields[4], &fingerprint_path)?;
+        if Some(to_hex(&fingerprint.model_hash).as_str()) != fields[1].as_str() || fingerprint.precision != precision {
+            return Err(invalid(&fingerprint_path, "does not match the model hash and precision"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builtin_model, witness, BitSet, Node, Tree, NUM_FEATURES};
+
+    #[test]
+    fn test_documents_validate() {
+        let model = builtin_model().with_training_hash([5; 32]);
+        let mut features = vec![0i64; NUM_FEATURES];
+        features[34] = 130000000000;
+        let bundle = witness::prepare(&model, &features);
+
+        let trace = bundle.trace.clone().with_guard(OutputGuard::new(0, 1).clamping());
+        for (document, kind) in [
+            (bundle.trace.to_document(), DocumentKind::Trace),
+            (trace.to_document(), DocumentKind::Trace),
+            (ModelCommitment::new(&model).to_document(), DocumentKind::Commitment),
+            (ProofMetadata::new(&model, &bundle).to_document(), DocumentKind::ProofMetadata),
+        ] {
+            let text = document.to_string();
+            assert!(text.starts_with(&format!(r#"{{"schema_version":1,"kind":"{}","body":{{"#, kind.name())));
+            assert_eq!(validate_document(&text), Ok(kind));
+        }
+
+        let categories: BitSet = [2].into_iter().collect();
+        let tree = Tree::new(Node::categorical(0, categories, 1, Node::Leaf(1), Node::Leaf(2)));
+        let forest = Ensemble::new(vec![tree.clone(), tree], 1).with_combiner(Combiner::Average);
+        let document = forest.eval_with_trace(&[2]).1.to_document();
+        assert_eq!(validate_value(&document), Ok(DocumentKind::Trace));
+    }
+
+    #[test]
+    fn test_fingerprint_documents() {
+        let model = builtin_model();
+        let bundle = witness::prepare(&model, &[0; NUM_FEATURES]);
+        let metadata = ProofMetadata::new(&model, &bundle);
+        assert_eq!(metadata.fingerprint, Fingerprint::new(&model, EvaluatorMode::Branching));
+        let trace = bundle.trace.to_document().to_string();
+        assert!(trace.contains(&format!(r#""fingerprint":{{"model_hash":"{}""#, to_hex(&bundle.model_hash))));
+
+        // The metadata's fingerprint must describe its own model
+        let rescaled = Fingerprint { precision: 1, ..metadata.fingerprint.clone() };
+        let text = ProofMetadata { fingerprint: rescaled, ..metadata }.to_document().to_string();
+        assert_eq!(
+            validate_document(&text).unwrap_err().to_string(),
+            "$.body.fingerprint: does not match the model hash and precision"
+        );
+        let error = validate_document(&trace.replace(r#""evaluator":"branching""#, r#""evaluator":"fpga""#));
+        assert_eq!(error.unwrap_err().to_string(), "$.body.fingerprint: malformed fingerprint: unknown evaluator");
+    }
+
+    #[test]
+    fn test_wide_accumulator_trace() {
+        let trees = vec![Tree::new(Node::Leaf(1 << 62)), Tree::new(Node::Leaf(1 << 62)), Tree::new(Node::Leaf(-5))];
+        let model = Ensemble::new(trees, 1).with_accumulator(Accumulator::I128);
+        let (prediction, trace) = model.eval_with_trace(&[]);
+        assert_eq!(prediction, i64::MAX - 4);
+        assert_eq!(trace.trees[1].accumulator, 1 << 63);
+        let text = trace.to_document().to_string();
+        assert!(text.contains(r#""combiner":"sum","accumulator":"i128","trees""#), "{}", text);
+        assert_eq!(validate_document(&text), Ok(DocumentKind::Trace));
+
+        // Partial sums beyond i64 need the i128 accumulator
+        let error = validate_document(&text.replace(r#","accumulator":"i128""#, "")).unwrap_err();
+        assert_eq!(error.to_string(), "$.body.trees[1].accumulator: expected an integer in the i64 range");
+    }
+
+    #[test]
+    fn test_strict_validation() {
+        let model = Ensemble::new(vec![Tree::new(Node::split(0, 5, Node::Leaf(1), Node::Leaf(2)))], 1);
+        let text = model.eval_with_trace(&[7]).1.to_document().to_string();
+        let path = |text: &str| match validate_document(text) {
+            Err(SchemaError::Invalid { path, .. }) => path,
+            other => panic!("expected an invalid document, got {:?}", other),
+        };
+
+        assert_eq!(
+            validate_document(&text.replace(r#""schema_version":1"#, r#""schema_version":2"#)),
+            Err(SchemaError::UnsupportedVersion("2".to_string()))
+        );
+        assert!(matches!(validate_document(&text[1..]), Err(SchemaError::Json(_))));
+        assert_eq!(path(&text.replace(r#""kind":"trace""#, r#""kind":"proof""#)), "$.kind");
+        assert_eq!(path(&text.replace(r#""output":2"#, r#""output":3"#)), "$.body.output");
+        assert_eq!(path(&text.replace(r#""output":2"#, r#""output":2.0"#)), "$.body.output");
+        assert_eq!(path(&text.replace(r#""outcome":false"#, r#""outcome":0"#)), "$.body.trees[0].comparisons[0].outcome");
+        assert_eq!(path(&text.replace(r#""path":[0,2]"#, r#""path":[1,2]"#)), "$.body.trees[0].comparisons[0]");
+        assert_eq!(path(&text.replace(r#""leaf_value":2"#, r#""leaf_value":2,"leaf":2"#)), "$.body.trees[0]");
+        assert_eq!(path(&text.replace(r#","combiner":"sum""#, "")), "$.body");
+
+        let error = validate_document(&text.replace(r#""base_score":0"#, r#""base_score":0,"base_score":0"#));
+        assert_eq!(error.unwrap_err().to_string(), "$.body: duplicate member `base_score`");
+        let commitment = ModelCommitment::new(&model).to_document().to_string();
+        assert_eq!(path(&commitment.replacen(r#""root":""#, r#""root":"A"#, 1)), "$.body.root");
+    }
+
+    #[test]
+    fn test_published_schema() {
+        let schema = json::parse(include_str!("../schema/v1.json")).unwrap();
+        let version = schema.get("properties").and_then(|p| p.get("schema_version")).and_then(|v| v.get("const"));
+        assert_eq!(version.and_then(JsonValue::as_usize), Some(SCHEMA_VERSION as usize));
+        let kinds = schema.get("$defs").and_then(JsonValue::as_object).unwrap();
+        for kind in DocumentKind::ALL {
+            assert!(kinds.iter().any(|(name, _)| name == kind.name()), "{} is not in the schema", kind.name());
+        }
+    }
+}
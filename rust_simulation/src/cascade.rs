@@ -0,0 +1,228 @@
+// Model cascades
+// Most radar hours are clearly dry or clearly wet, and a few trees already tell which. A cascade
+// evaluates a small first-stage ensemble and answers with its output unless that output falls in
+// an ambiguous band, in which case the full model runs and its output is the answer. Everything
+// stays in fixed point: the band is a pair of scaled bounds and the decision is the same integer
+// comparison in the simulator and in the circuit. The first stage is often a prefix of the full
+// boosted model (`Cascade::from_prefix`), since boosting fits the large effects first.
+//
+// `CascadeStats` counts early exits over a batch. With the `r1cs` feature, `CascadeCircuit`
+// proves either stage: an early exit only constrains the first stage and that its output lies
+// outside the band, so easy inputs are proved at the cost of the small model.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::ensemble::Ensemble;
+
+/// Error raised by an invalid cascade
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CascadeError {
+    /// The ambiguous band has `low > high`
+    EmptyBand { low: i64, high: i64 },
+    /// The two stages use different fixed-point scales
+    ScaleMismatch { first: i64, full: i64 },
+    /// The prefix takes no trees, or all of them
+    InvalidPrefix { trees: usize, num_trees: usize },
+}
+
+impl fmt::Display for CascadeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CascadeError::EmptyBand { low, high } => write!(f, "empty ambiguous band [{}, {}]", low, high),
+            CascadeError::ScaleMismatch { first, full } => {
+                write!(f, "first stage has scale {}, full model has scale {}", first, full)
+            }
+            CascadeError::InvalidPrefix { trees, num_trees } => {
+                write!(f, "a first stage of {} trees out of {} is not a proper prefix", trees, num_trees)
+            }
+        }
+    }
+}
+
+impl core::error::Error for CascadeError {}
+
+/// Stage whose output a cascade returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CascadeStage {
+    /// The first stage was outside the ambiguous band
+    First,
+    /// The first stage was ambiguous and the full model answered
+    Full,
+}
+
+/// First-stage ensemble, full model and the band of first-stage outputs that defers to the full model
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cascade {
+    first: Ensemble,
+    full: Ensemble,
+    low: i64,
+    high: i64,
+}
+
+impl Cascade {
+    /// Create a cascade
+    ///
+    /// # Arguments
+    /// * `first` - Small ensemble evaluated on every input
+    /// * `full` - Model evaluated when the first stage is ambiguous
+    /// * `low`, `high` - Inclusive band of ambiguous first-stage outputs (scaled)
+    ///
+    /// # Returns
+    /// * `Result<Cascade, CascadeError>` - The cascade, or why the stages or band do not fit
+    pub fn new(first: Ensemble, full: Ensemble, low: i64, high: i64) -> Result<Cascade, CascadeError> {
+        if low > high {
+            return Err(CascadeError::EmptyBand { low, high });
+        }
+        if first.scale() != full.scale() {
+            return Err(CascadeError::ScaleMismatch { first: first.scale(), full: full.scale() });
+        }
+        Ok(Cascade { first, full, low, high })
+    }
+
+    /// Cascade whose first stage is the first `trees` trees of `full`
+    pub fn from_prefix(full: Ensemble, trees: usize, low: i64, high: i64) -> Result<Cascade, CascadeError> {
+        if trees == 0 || trees >= full.num_trees() {
+            return Err(CascadeError::InvalidPrefix { trees, num_trees: full.num_trees() });
+        }
+        let first = full.with_trees(full.trees()[..trees].to_vec());
+        Cascade::new(first, full, low, high)
+    }
+
+    /// First-stage ensemble
+    pub fn first(&self) -> &Ensemble {
+        &self.first
+    }
+
+    /// Full model
+    pub fn full(&self) -> &Ensemble {
+        &self.full
+    }
+
+    /// Inclusive band of ambiguous first-stage outputs (scaled)
+    pub fn band(&self) -> (i64, i64) {
+        (self.low, self.high)
+    }
+
+    /// Number of features either stage reads
+    pub fn num_features(&self) -> usize {
+        self.first.num_features().max(self.full.num_features())
+    }
+
+    /// Whether a first-stage output defers to the full model
+    pub fn is_ambiguous(&self, first_output: i64) -> bool {
+        (self.low..=self.high).contains(&first_output)
+    }
+
+    /// Predict, running the full model only for ambiguous inputs
+    ///
+    /// # Arguments
+    /// * `features` - Input feature vector (scaled)
+    ///
+    /// # Returns
+    /// * `(i64, CascadeStage)` - Scaled prediction and the stage that produced it
+    pub fn predict(&self, features: &[i64]) -> (i64, CascadeStage) {
+        let first_output = self.first.predict(features);
+        if self.is_ambiguous(first_output) {
+            (self.full.predict(features), CascadeStage::Full)
+        } else {
+            (first_output, CascadeStage::First)
+        }
+    }
+
+    /// Predict every sample, counting early exits
+    pub fn predict_batch(&self, batch: &[impl AsRef<[i64]>]) -> (Vec<i64>, CascadeStats) {
+        let mut stats = CascadeStats::default();
+        let predictions = batch
+            .iter()
+            .map(|sample| {
+                let (prediction, stage) = self.predict(sample.as_ref());
+                stats.add(stage);
+                prediction
+            })
+            .collect();
+        (predictions, stats)
+    }
+}
+
+/// Early exits of a cascade over a batch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CascadeStats {
+    pub samples: usize,
+    /// Samples answered by the first stage
+    pub early_exits: usize,
+}
+
+impl CascadeStats {
+    /// Record the stage that answered one sample
+    pub fn add(&mut self, stage: CascadeStage) {
+        self.samples += 1;
+        if stage == CascadeStage::First {
+            self.early_exits += 1;
+        }
+    }
+
+    /// Share of samples answered by the first stage (0 with no samples)
+    pub fn exit_rate(&self) -> f64 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            self.early_exits as f64 / self.samples as f64
+        }
+    }
+
+    /// Average cost per sample when the first stage costs `first` and a deferred sample costs
+    /// `deferred` in total (e.g. the constraint counts of the two `CascadeCircuit` stages)
+    pub fn average_cost(&self, first: usize, deferred: usize) -> f64 {
+        let rate = self.exit_rate();
+        rate * first as f64 + (1.0 - rate) * deferred as f64
+    }
+}
+
+impl fmt::Display for CascadeStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {} samples exited early ({:.1}%)", self.early_exits, self.samples, self.exit_rate() * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::{Node, Tree};
+    use alloc::vec;
+
+    #[test]
+    fn test_cascade_predict() {
+        // The first tree separates dry (f0 <= 0) from wet inputs; the second refines wet ones on f1
+        let trees = vec![
+            Tree::new(Node::split(0, 0, Node::Leaf(-100), Node::Leaf(10))),
+            Tree::new(Node::split(1, 5, Node::Leaf(-3), Node::Leaf(40))),
+        ];
+        let full = Ensemble::new(trees, 10);
+        let cascade = Cascade::from_prefix(full.clone(), 1, -50, 50).unwrap();
+        assert_eq!(cascade.predict(&[0, 9]), (-100, CascadeStage::First));
+        assert_eq!(cascade.predict(&[1, 9]), (50, CascadeStage::Full));
+        assert_eq!(cascade.predict(&[1, 0]), (full.predict(&[1, 0]), CascadeStage::Full));
+
+        let (predictions, stats) = cascade.predict_batch(&[[0, 0], [-4, 7], [2, 2], [0, 9]]);
+        assert_eq!(predictions, [-100, -100, 7, -100]);
+        assert_eq!((stats.early_exits, stats.exit_rate()), (3, 0.75));
+        assert_eq!(stats.average_cost(100, 500), 200.0);
+        assert_eq!(stats.to_string(), "3 of 4 samples exited early (75.0%)");
+    }
+
+    #[test]
+    fn test_cascade_errors() {
+        let model = || Ensemble::new(vec![Tree::new(Node::Leaf(1)), Tree::new(Node::Leaf(2))], 10);
+        assert_eq!(Cascade::new(model(), model(), 5, 4), Err(CascadeError::EmptyBand { low: 5, high: 4 }));
+        let rescaled = model().rescale(100).unwrap();
+        assert_eq!(
+            Cascade::new(model(), rescaled, 0, 0).unwrap_err().to_string(),
+            "first stage has scale 10, full model has scale 100"
+        );
+        assert_eq!(
+            Cascade::from_prefix(model(), 2, 0, 0),
+            Err(CascadeError::InvalidPrefix { trees: 2, num_trees: 2 })
+        );
+    }
+}
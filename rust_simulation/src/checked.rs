@@ -0,0 +1,183 @@
+// Checked prediction API
+// `xgboost_predict` and `Ensemble::eval` panic on a short feature slice, and silently saturate
+// non-finite inputs and an overflowing sum. Services embedding the model call these instead: the
+// feature count is either checked at compile time through the array length, or reported together
+// with the other failures as a `PredictError`.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::ensemble::{Accumulator, Combiner, Ensemble};
+use crate::{to_fixed_point, xgboost_predict, NUM_FEATURES};
+
+/// Error raised by the checked prediction functions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PredictError {
+    /// The input has fewer features than the model reads
+    TooFewFeatures { expected: usize, got: usize },
+    /// A raw feature is NaN or infinite
+    NonFinite { index: usize },
+    /// A scaled feature is `i64::MIN` or `i64::MAX`, the values an infinite or out-of-range float
+    /// saturates to, so the trees would route it as an arbitrary extreme
+    Saturated { index: usize, value: i64 },
+    /// The sum of the base score and the tree outputs does not fit in an i64
+    Overflow,
+}
+
+impl fmt::Display for PredictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PredictError::TooFewFeatures { expected, got } => {
+                write!(f, "expected at least {} features, got {}", expected, got)
+            }
+            PredictError::NonFinite { index } => write!(f, "feature {} is not a finite number", index),
+            PredictError::Saturated { index, value } => {
+                write!(f, "feature {} is the saturated value {}", index, value)
+            }
+            PredictError::Overflow => write!(f, "prediction overflows the fixed-point range"),
+        }
+    }
+}
+
+impl core::error::Error for PredictError {}
+
+/// `xgboost_predict` on a fixed-size array; a too-short array is a compile error
+///
+/// # Arguments
+/// * `features` - `N >= NUM_FEATURES` scaled features
+///
+/// # Returns
+/// * `i64` - Prediction result (scaled by 10^10)
+pub fn xgboost_predict_array<const N: usize>(features: &[i64; N]) -> i64 {
+    const { assert!(N >= NUM_FEATURES, "the array is shorter than NUM_FEATURES") };
+    xgboost_predict(features)
+}
+
+/// `xgboost_predict` that reports bad input and overflow instead of panicking or saturating
+///
+/// # Arguments
+/// * `features` - Scaled features
+///
+/// # Returns
+/// * `Result<i64, PredictError>` - Prediction result (scaled by 10^10)
+pub fn try_xgboost_predict(features: &[i64]) -> Result<i64, PredictError> {
+    check_features(features, NUM_FEATURES)?;
+    // The compiled sum saturates; ending on a bound means it did
+    match xgboost_predict(features) {
+        i64::MIN | i64::MAX => Err(PredictError::Overflow),
+        y => Ok(y),
+    }
+}
+
+/// Checked prediction on raw (unscaled) features
+///
+/// # Arguments
+/// * `features` - Raw features; the missing-value sentinel -99999 is accepted, NaN and infinities are not
+///
+/// # Returns
+/// * `Result<i64, PredictError>` - Prediction result (scaled by 10^10)
+pub fn try_predict(features: &[f64]) -> Result<i64, PredictError> {
+    if features.len() < NUM_FEATURES {
+        return Err(PredictError::TooFewFeatures { expected: NUM_FEATURES, got: features.len() });
+    }
+    if let Some(index) = features.iter().position(|x| !x.is_finite()) {
+        return Err(PredictError::NonFinite { index });
+    }
+    let scaled: Vec<i64> = features.iter().map(|&x| to_fixed_point(x)).collect();
+    try_xgboost_predict(&scaled)
+}
+
+impl Ensemble {
+    /// `Ensemble::eval` that reports bad input and overflow instead of panicking or saturating
+    ///
+    /// # Arguments
+    /// * `features` - Input feature vector (at the ensemble's scale)
+    ///
+    /// # Returns
+    /// * `Result<i64, PredictError>` - Prediction, or an error if `features` is shorter than the
+    ///   model's `num_features`, holds a saturated value, or the sum overflows
+    pub fn try_eval(&self, features: &[i64]) -> Result<i64, PredictError> {
+        check_features(features, self.num_features())?;
+        let combiner = self.combiner();
+        let initial = combiner.initial(self.base_score());
+        let mut outputs = self.trees().iter().map(|tree| tree.eval(features));
+        // Only the i64 accumulator can overflow before the last tree
+        let sum = match self.accumulator() {
+            Accumulator::I64 => outputs.try_fold(initial, i64::checked_add).ok_or(PredictError::Overflow)? as i128,
+            Accumulator::I128 => outputs.fold(initial as i128, |y, output| y + output as i128),
+        };
+        let prediction = match combiner {
+            Combiner::Sum => sum,
+            Combiner::Average => self.base_score() as i128 + sum.div_euclid(self.num_trees().max(1) as i128),
+        };
+        i64::try_from(prediction).map_err(|_| PredictError::Overflow)
+    }
+}
+
+fn check_features(features: &[i64], expected: usize) -> Result<(), PredictError> {
+    if features.len() < expected {
+        return Err(PredictError::TooFewFeatures { expected, got: features.len() });
+    }
+    match features.iter().position(|&x| x == i64::MIN || x == i64::MAX) {
+        Some(index) => Err(PredictError::Saturated { index, value: features[index] }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::{Node, Tree};
+    use alloc::vec;
+
+    #[test]
+    fn test_array_and_checked_predict() {
+        let features = [0i64; NUM_FEATURES];
+        assert_eq!(xgboost_predict_array(&features), xgboost_predict(&features));
+        assert_eq!(try_xgboost_predict(&features), Ok(xgboost_predict(&features)));
+        assert_eq!(
+            try_xgboost_predict(&features[..100]),
+            Err(PredictError::TooFewFeatures { expected: NUM_FEATURES, got: 100 })
+        );
+    }
+
+    #[test]
+    fn test_non_finite_and_saturated_features() {
+        let mut raw = [0.0f64; NUM_FEATURES];
+        assert_eq!(try_predict(&raw), Ok(xgboost_predict(&[0; NUM_FEATURES])));
+        raw[7] = f64::NAN;
+        assert_eq!(try_predict(&raw), Err(PredictError::NonFinite { index: 7 }));
+        raw[7] = 1e300;
+        assert_eq!(try_predict(&raw), Err(PredictError::Saturated { index: 7, value: i64::MAX }));
+    }
+
+    #[test]
+    fn test_try_eval_uses_model_feature_count() {
+        let model = crate::builtin_model();
+        assert_eq!(model.try_eval(&[0; NUM_FEATURES]), Ok(model.eval(&[0; NUM_FEATURES])));
+        assert!(matches!(model.try_eval(&[0; 3]), Err(PredictError::TooFewFeatures { got: 3, .. })));
+        assert_eq!(Ensemble::default().try_eval(&[]), Ok(0));
+    }
+
+    #[test]
+    fn test_try_eval_reports_overflow() {
+        let trees = vec![Tree::new(Node::Leaf(i64::MAX / 2 + 1)), Tree::new(Node::Leaf(i64::MAX / 2 + 1))];
+        let model = Ensemble::new(trees, 1);
+        assert_eq!(model.eval(&[]), i64::MAX);
+        assert_eq!(model.try_eval(&[]), Err(PredictError::Overflow));
+
+        // Averaging divides after summing, so the sum itself must not overflow
+        let forest = model.with_combiner(Combiner::Average);
+        assert_eq!(forest.try_eval(&[]), Err(PredictError::Overflow));
+        let halves = Ensemble::new(vec![Tree::new(Node::Leaf(-5)), Tree::new(Node::Leaf(2))], 1)
+            .with_combiner(Combiner::Average)
+            .with_base_score(1);
+        assert_eq!(halves.try_eval(&[]), Ok(halves.eval(&[])));
+        assert_eq!(halves.eval(&[]), -1);
+
+        // An i128 accumulator only fails when the prediction itself leaves the i64 range
+        let wide = forest.with_accumulator(Accumulator::I128);
+        assert_eq!(wide.try_eval(&[]), Ok(1 << 62));
+        assert_eq!(wide.with_combiner(Combiner::Sum).try_eval(&[]), Err(PredictError::Overflow));
+    }
+}
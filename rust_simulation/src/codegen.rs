@@ -0,0 +1,678 @@
+// Rust and Leo code generators
+// Emits the nested-if prediction function for any tree ensemble, using the same templates
+// as the Python converter (converter/language_templates/rust_*.template) so both produce
+// identical source for the same model. `RustOptions` trades that for code editors can handle on
+// large models: per-tree functions or a node table, one module file per tree, and trees that
+// compute a leaf index and `match` on it instead of nesting the leaf values in the `if`s. The
+// Leo generator targets Aleo programs with the same fixed-point encoding (leo_*.template).
+// Averaging ensembles start the accumulator at 0 and divide it by the tree count after the last
+// tree, flooring like `Combiner::Average`.
+
+use alloc::{format, string::{String, ToString}, vec, vec::Vec};
+
+use crate::ensemble::{Combiner, Node};
+use crate::Model;
+
+const HEADER_TEMPLATE: &str = include_str!("../../converter/language_templates/rust_header.template");
+const MAIN_TEMPLATE: &str = include_str!("../../converter/language_templates/rust_main.template");
+const TREE_TEMPLATE: &str = include_str!("../../converter/language_templates/rust_tree.template");
+const TREE_FN_TEMPLATE: &str = include_str!("../../converter/language_templates/rust_tree_fn.template");
+const TABLE_TEMPLATE: &str = include_str!("../../converter/language_templates/rust_table.template");
+const LEO_HEADER_TEMPLATE: &str = include_str!("../../converter/language_templates/leo_header.template");
+const LEO_MAIN_TEMPLATE: &str = include_str!("../../converter/language_templates/leo_main.template");
+const LEO_TREE_TEMPLATE: &str = include_str!("../../converter/language_templates/leo_tree.template");
+
+/// Maximum number of elements of an Aleo array
+pub const LEO_MAX_ARRAY_LEN: usize = 32;
+
+/// Code layout of the generated Rust prediction function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RustLayout {
+    /// Every tree inlined into `xgboost_predict` (the converter's layout)
+    #[default]
+    Unrolled,
+    /// One non-inlined function per tree, so each stays small for the compiler
+    PerTree,
+    /// A `const TREES: &[Tree]` node table interpreted at runtime
+    Table,
+}
+
+/// Control flow of each tree in the `Unrolled` and `PerTree` layouts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RustStyle {
+    /// Nested `if`s with the leaf values in their branches (the converter's style)
+    #[default]
+    NestedIf,
+    /// Nested `if`s that yield the leaf's pre-order node id (as in execution traces), followed by
+    /// a `match` from id to leaf value
+    LeafMatch,
+}
+
+/// Options of `generate_rust_files`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RustOptions {
+    pub layout: RustLayout,
+    pub style: RustStyle,
+    /// Put each tree in its own module file, `tree_<i>.rs` next to `lib.rs`; with the `Unrolled`
+    /// layout the trees become per-tree functions
+    pub split_files: bool,
+}
+
+/// Generate a standalone Rust source file with `xgboost_predict` for the model
+///
+/// # Arguments
+/// * `model` - Loaded model (thresholds and leaves already scaled by 10^10)
+/// * `num_features` - Feature count asserted by the generated function (at least the model's)
+///
+/// # Returns
+/// * `String` - Fixed-point helpers followed by the unrolled prediction function
+pub fn generate_rust_source(model: &Model, num_features: usize) -> String {
+    generate_rust_source_with_layout(model, num_features, RustLayout::Unrolled)
+}
+
+/// Generate a standalone Rust source file with `xgboost_predict` in the given layout
+///
+/// The unrolled layout compiles to the fastest code but its compile time grows quickly with the
+/// number of trees; `PerTree` and `Table` keep large (e.g. 500-tree) models practical to build.
+/// All layouts compute the same prediction.
+///
+/// # Arguments
+/// * `model` - Loaded model (thresholds and leaves already scaled by 10^10)
+/// * `num_features` - Feature count asserted by the generated function (at least the model's)
+/// * `layout` - Code layout of the trees
+///
+/// # Returns
+/// * `String` - Fixed-point helpers, the tree code and the prediction function
+pub fn generate_rust_source_with_layout(model: &Model, num_features: usize, layout: RustLayout) -> String {
+    let options = RustOptions { layout, ..RustOptions::default() };
+    generate_rust_files(model, num_features, &options).swap_remove(0).1
+}
+
+/// Generate the Rust source of `xgboost_predict` with the given options, as one or more files
+///
+/// With `split_files`, `lib.rs` declares one module per tree (`mod tree_3;`) and each tree file
+/// starts with `use super::*;`, so the files go together into one source directory.
+///
+/// # Arguments
+/// * `model` - Loaded model (thresholds and leaves already scaled by 10^10)
+/// * `num_features` - Feature count asserted by the generated function (at least the model's)
+/// * `options` - Layout, style and file splitting
+///
+/// # Returns
+/// * `Vec<(String, String)>` - File names and contents, `lib.rs` first, then the trees in order
+pub fn generate_rust_files(model: &Model, num_features: usize, options: &RustOptions) -> Vec<(String, String)> {
+    let num_features = num_features.max(model.num_features());
+    let split = options.split_files;
+
+    let tree_logic = |tree: &Node| {
+        let mut tree_logic = String::new();
+        match options.style {
+            RustStyle::NestedIf => {
+                let mut leaf = |_, value| format!("from_scaled_i64({})", value);
+                write_node(&mut tree_logic, tree, 1, "tree_result", &mut 0, &mut leaf);
+            }
+            RustStyle::LeafMatch => write_leaf_match(&mut tree_logic, tree),
+        }
+        tree_logic
+    };
+    let module = |tree_idx: usize, body: &str| {
+        let source = format!("// Tree {} of the model, generated with lib.rs\n\nuse super::*;\n\n{}\n", tree_idx, body);
+        (format!("tree_{}.rs", tree_idx), source)
+    };
+    let declarations = || (0..model.num_trees()).map(|tree_idx| format!("mod tree_{};", tree_idx)).collect::<Vec<_>>();
+
+    let mut files = vec![(String::new(), String::new())];
+    let (definitions, tree_code) = match options.layout {
+        RustLayout::Unrolled if !split => {
+            let tree_codes: Vec<String> = model
+                .trees()
+                .iter()
+                .enumerate()
+                .map(|(tree_idx, tree)| {
+                    fill_template(
+                        TREE_TEMPLATE,
+                        &[("tree_idx", &tree_idx.to_string()), ("tree_logic", &tree_logic(&tree.root))],
+                    )
+                })
+                .collect();
+            (String::new(), tree_codes.join("\n"))
+        }
+        RustLayout::Unrolled | RustLayout::PerTree => {
+            let visibility = if split { "pub(super) " } else { "" };
+            let functions: Vec<String> = model
+                .trees()
+                .iter()
+                .enumerate()
+                .map(|(tree_idx, tree)| {
+                    fill_template(
+                        TREE_FN_TEMPLATE,
+                        &[
+                            ("tree_idx", &tree_idx.to_string()),
+                            ("visibility", visibility),
+                            ("tree_logic", &tree_logic(&tree.root)),
+                        ],
+                    )
+                })
+                .collect();
+            let calls: Vec<String> = (0..model.num_trees())
+                .map(|tree_idx| match split {
+                    true => format!("    y = fixed_add(y, tree_{0}::tree_{0}(f));", tree_idx),
+                    false => format!("    y = fixed_add(y, tree_{}(f));", tree_idx),
+                })
+                .collect();
+            if split {
+                files.extend(functions.iter().enumerate().map(|(tree_idx, function)| module(tree_idx, function)));
+                (declarations().join("\n"), calls.join("\n"))
+            } else {
+                (functions.join("\n"), calls.join("\n"))
+            }
+        }
+        RustLayout::Table => {
+            let indent = if split { "" } else { "    " };
+            let trees: Vec<String> = model
+                .trees()
+                .iter()
+                .enumerate()
+                .map(|(tree_idx, tree)| {
+                    let nodes: Vec<String> = flatten(&tree.root)
+                        .iter()
+                        .map(|node| {
+                            let feature = match node.feature {
+                                Some(feature) => feature.to_string(),
+                                None => "LEAF".to_string(),
+                            };
+                            let categories = match &node.categories {
+                                Some(values) => format!("Some(&[{}])", join_values(values, ", ")),
+                                None => "None".to_string(),
+                            };
+                            format!(
+                                "{}    TableNode {{ feature: {}, value: {}, left: {}, right: {}, categories: {} }},",
+                                indent, feature, node.value, node.left, node.right, categories
+                            )
+                        })
+                        .collect();
+                    if split {
+                        let nodes = nodes.join("\n");
+                        let constant = format!(
+                            "/// Tree {}\npub(super) const TREE: Tree = Tree {{ nodes: &[\n{}\n] }};",
+                            tree_idx, nodes
+                        );
+                        files.push(module(tree_idx, &constant));
+                        format!("    tree_{}::TREE,", tree_idx)
+                    } else {
+                        format!("    Tree {{ nodes: &[\n{}\n    ] }},", nodes.join("\n"))
+                    }
+                })
+                .collect();
+            let mut definitions = fill_template(TABLE_TEMPLATE, &[("trees", &trees.join("\n"))]);
+            if split {
+                definitions = format!("{}\n\n{}", declarations().join("\n"), definitions);
+            }
+            let loop_code = "    for tree in TREES {\n        y = fixed_add(y, eval_tree(tree, f));\n    }".to_string();
+            (definitions, loop_code)
+        }
+    };
+    let tree_code = match model.combiner() {
+        Combiner::Sum => tree_code,
+        Combiner::Average => format!(
+            "{}
+
+    // Average over the trees (floor division, like `Combiner::Average`)
+    y = fixed_add({}i64, y.div_euclid({}));",
+            tree_code,
+            model.base_score(),
+            model.num_trees().max(1)
+        ),
+    };
+
+    let main_code = fill_template(
+        MAIN_TEMPLATE,
+        &[
+            ("num_features", &num_features.to_string()),
+            ("base_score", &model.combiner().initial(model.base_score()).to_string()),
+            ("tree_code", &tree_code),
+        ],
+    );
+
+    // The converter's header is written for 10^10; a model at another scale gets its own constant
+    let header = match model.scale() {
+        crate::PRECISION_MULTIPLIER => HEADER_TEMPLATE.to_string(),
+        scale => HEADER_TEMPLATE.replace(
+            "PRECISION_MULTIPLIER: i64 = 10_000_000_000; // 10^10 for precision",
+            &format!("PRECISION_MULTIPLIER: i64 = {}; // fixed-point scale of the model", scale),
+        ),
+    };
+    let lib = if definitions.is_empty() {
+        format!("{}\n{}\n", header, main_code)
+    } else {
+        format!("{}\n{}\n\n{}\n", header, definitions.trim_end(), main_code)
+    };
+    files[0] = ("lib.rs".to_string(), lib);
+    files
+}
+
+/// Node of a flattened tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FlatNode {
+    /// Split feature, `None` for a leaf
+    feature: Option<usize>,
+    /// Threshold of a split or output of a leaf
+    value: i64,
+    /// Feature values sent left by a categorical split (`None` for threshold splits and leaves)
+    categories: Option<Vec<i64>>,
+    left: usize,
+    right: usize,
+}
+
+/// Nodes of a tree in pre-order with child indices (the node ids of execution traces)
+fn flatten(root: &Node) -> Vec<FlatNode> {
+    fn push(nodes: &mut Vec<FlatNode>, node: &Node) -> usize {
+        let index = nodes.len();
+        match node {
+            Node::Leaf(value) => {
+                nodes.push(FlatNode { feature: None, value: *value, categories: None, left: 0, right: 0 })
+            }
+            Node::Split { feature, threshold, left, right } => {
+                nodes.push(FlatNode { feature: Some(*feature), value: *threshold, categories: None, left: 0, right: 0 });
+                nodes[index].left = push(nodes, left);
+                nodes[index].right = push(nodes, right);
+            }
+            Node::Categorical { feature, categories, unit, left, right } => {
+                let categories = Some(categories.values(*unit).collect());
+                nodes.push(FlatNode { feature: Some(*feature), value: *unit, categories, left: 0, right: 0 });
+                nodes[index].left = push(nodes, left);
+                nodes[index].right = push(nodes, right);
+            }
+        }
+        index
+    }
+
+    let mut nodes = Vec::with_capacity(root.num_nodes());
+    push(&mut nodes, root);
+    nodes
+}
+
+/// Append the code for one node, mirroring the converter's `_generate_tree_logic`
+///
+/// Leaves become the expression `leaf(node_id, value)`, with node ids in pre-order starting at
+/// `next_id`. At depth 1 the tree's value is bound to `binding`.
+fn write_node<L>(out: &mut String, node: &Node, depth: usize, binding: &str, next_id: &mut usize, leaf: &mut L)
+where
+    L: FnMut(usize, i64) -> String,
+{
+    let indent = " ".repeat(4 * depth);
+    let node_id = *next_id;
+    *next_id += 1;
+
+    let (condition, left, right) = match *node {
+        Node::Leaf(value) if depth == 1 => {
+            // A tree that is a single leaf still has to bind the tree result
+            out.push_str(&format!("{}let {} = {};\n", indent, binding, leaf(node_id, value)));
+            return;
+        }
+        Node::Leaf(value) => {
+            out.push_str(&format!("{}{}\n", indent, leaf(node_id, value)));
+            return;
+        }
+        Node::Split { feature, threshold, ref left, ref right } => {
+            (format!("fixed_le(f[{}], from_scaled_i64({}))", feature, threshold), left, right)
+        }
+        Node::Categorical { feature, ref categories, unit, ref left, ref right } => {
+            let values: Vec<i64> = categories.values(unit).collect();
+            let condition = if values.is_empty() {
+                "false".to_string()
+            } else {
+                format!("matches!(f[{}], {})", feature, join_values(&values, " | "))
+            };
+            (condition, left, right)
+        }
+    };
+
+    let (binding, terminator) = if depth == 1 { (format!("let {} = ", binding), ";") } else { (String::new(), "") };
+    out.push_str(&format!("{}{}if {} {{\n", indent, binding, condition));
+    write_node(out, left, depth + 1, "", next_id, leaf);
+    out.push_str(&format!("{}}} else {{\n", indent));
+    write_node(out, right, depth + 1, "", next_id, leaf);
+    out.push_str(&format!("{}}}{}\n", indent, terminator));
+}
+
+/// Append the code for one tree in `RustStyle::LeafMatch`: the leaf id, then its value
+fn write_leaf_match(out: &mut String, root: &Node) {
+    let mut leaves = Vec::new();
+    let mut leaf = |node_id: usize, value: i64| {
+        leaves.push((node_id, value));
+        node_id.to_string()
+    };
+    write_node(out, root, 1, "leaf", &mut 0, &mut leaf);
+    out.push_str("    let tree_result = match leaf {\n");
+    for (node_id, value) in leaves {
+        out.push_str(&format!("        {} => from_scaled_i64({}),\n", node_id, value));
+    }
+    out.push_str("        _ => unreachable!(),\n    };\n");
+}
+
+/// Values separated by `separator`
+fn join_values(values: &[i64], separator: &str) -> String {
+    values.iter().map(i64::to_string).collect::<Vec<_>>().join(separator)
+}
+
+/// Generate a Leo program with a `predict` transition for the model
+///
+/// Features are private `i64` inputs scaled by 10^10, split into arrays of at most
+/// `LEO_MAX_ARRAY_LEN` elements: feature `i` is `f{i / 32}[i % 32]`. Tree outputs are summed
+/// with a saturating `fixed_add` computed in `i128`, so the program matches `xgboost_predict`.
+///
+/// # Arguments
+/// * `model` - Loaded model (thresholds and leaves already scaled by 10^10)
+/// * `num_features` - Number of feature inputs (at least the model's)
+/// * `program_name` - Aleo program name without the `.aleo` suffix
+///
+/// # Returns
+/// * `String` - Contents of `src/main.leo`
+pub fn generate_leo_source(model: &Model, num_features: usize, program_name: &str) -> String {
+    let num_features = num_features.max(model.num_features());
+
+    let inputs: Vec<String> = (0..num_features.div_ceil(LEO_MAX_ARRAY_LEN))
+        .map(|chunk| {
+            let len = (num_features - chunk * LEO_MAX_ARRAY_LEN).min(LEO_MAX_ARRAY_LEN);
+            format!("private f{}: [i64; {}]", chunk, len)
+        })
+        .collect();
+
+    let tree_codes: Vec<String> = model
+        .trees()
+        .iter()
+        .enumerate()
+        .map(|(tree_idx, tree)| {
+            let mut tree_logic = String::new();
+            write_leo_node(&mut tree_logic, &tree.root, &format!("t{}", tree_idx), 2);
+            fill_template(
+                LEO_TREE_TEMPLATE,
+                &[("tree_idx", &tree_idx.to_string()), ("tree_logic", &tree_logic)],
+            )
+        })
+        .collect();
+    let mut tree_code = tree_codes.join("\n");
+    if model.combiner() == Combiner::Average {
+        let num_trees = model.num_trees().max(1);
+        tree_code.push_str(&format!(
+            "\n\n        // Average over the trees: `/` truncates, so step down to the floor for negative sums\n        \
+             let q: i64 = y / {n}i64;\n        y = fixed_add({}i64, q * {n}i64 > y ? q - 1i64 : q);",
+            model.base_score(),
+            n = num_trees
+        ));
+    }
+
+    let header = fill_template(
+        LEO_HEADER_TEMPLATE,
+        &[("program_name", program_name), ("array_len", &LEO_MAX_ARRAY_LEN.to_string())],
+    );
+    let main_code = fill_template(
+        LEO_MAIN_TEMPLATE,
+        &[
+            ("num_features", &num_features.to_string()),
+            ("inputs", &inputs.join(", ")),
+            ("base_score", &model.combiner().initial(model.base_score()).to_string()),
+            ("tree_code", &tree_code),
+        ],
+    );
+
+    format!("{}\n\n{}\n", header, main_code)
+}
+
+/// Append Leo statements assigning the value of `node` to `target`
+fn write_leo_node(out: &mut String, node: &Node, target: &str, depth: usize) {
+    let indent = " ".repeat(4 * depth);
+
+    match *node {
+        Node::Leaf(value) => {
+            out.push_str(&format!("{}{} = {}i64;\n", indent, target, value));
+        }
+        Node::Split { feature, threshold, ref left, ref right } => {
+            out.push_str(&format!(
+                "{}if f{}[{}u32] <= {}i64 {{\n",
+                indent,
+                feature / LEO_MAX_ARRAY_LEN,
+                feature % LEO_MAX_ARRAY_LEN,
+                threshold
+            ));
+            write_leo_node(out, left, target, depth + 1);
+            out.push_str(&format!("{}}} else {{\n", indent));
+            write_leo_node(out, right, target, depth + 1);
+            out.push_str(&format!("{}}}\n", indent));
+        }
+        Node::Categorical { feature, ref categories, unit, ref left, ref right } => {
+            let input = format!("f{}[{}u32]", feature / LEO_MAX_ARRAY_LEN, feature % LEO_MAX_ARRAY_LEN);
+            let tests: Vec<String> = categories.values(unit).map(|value| format!("{} == {}i64", input, value)).collect();
+            let condition = if tests.is_empty() { "false".to_string() } else { tests.join(" || ") };
+            out.push_str(&format!("{}if {} {{\n", indent, condition));
+            write_leo_node(out, left, target, depth + 1);
+            out.push_str(&format!("{}}} else {{\n", indent));
+            write_leo_node(out, right, target, depth + 1);
+            out.push_str(&format!("{}}}\n", indent));
+        }
+    }
+}
+
+/// Substitute `{name}` fields and unescape `{{`/`}}` like Python's `str.format`
+fn fill_template(template: &str, fields: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(pos) = rest.find(['{', '}']) {
+        out.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+
+        if let Some(after) = tail.strip_prefix("{{") {
+            out.push('{');
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("}}") {
+            out.push('}');
+            rest = after;
+        } else if let Some((name, value)) = fields
+            .iter()
+            .find(|(name, _)| tail[1..].starts_with(name) && tail[1 + name.len()..].starts_with('}'))
+        {
+            out.push_str(value);
+            rest = &tail[name.len() + 2..];
+        } else {
+            // Not a field we know about, keep it verbatim
+            out.push_str(&tail[..1]);
+            rest = &tail[1..];
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_code_matches_lib() {
+        let model = Model::from_xgboost_json(include_str!("../models/bst1_10.json")).unwrap();
+        let source = generate_rust_source(&model, crate::NUM_FEATURES);
+
+        // The prediction function in lib.rs was produced by the Python converter
+        let start = source.find("/// Main XGBoost prediction function").unwrap();
+        let main_code = source[start..].trim_end();
+        assert!(include_str!("lib.rs").contains(main_code));
+        assert!(source.starts_with("// XGBoost Decision Tree Implementation in Rust"));
+    }
+
+    #[test]
+    fn test_single_leaf_tree_binds_result() {
+        let model = Model::from_xgboost_json(r#"[{ "nodeid": 0, "leaf": 0.5 }]"#).unwrap();
+        let source = generate_rust_source(&model, 3);
+
+        assert!(source.contains("    let tree_result = from_scaled_i64(5000000000);\n"));
+        assert!(source.contains("assert!(features.len() >= 3,"));
+
+        let biased = generate_rust_source(&model.clone().with_base_score(-1250), 3);
+        assert!(biased.contains("    let mut y = -1250i64;\n"));
+
+        let coarse = generate_rust_source(&model.rescale(1_000_000).unwrap(), 3);
+        assert!(coarse.contains("const PRECISION_MULTIPLIER: i64 = 1000000; // fixed-point scale of the model\n"));
+        assert!(coarse.contains("    let tree_result = from_scaled_i64(500000);\n"));
+    }
+
+    #[test]
+    fn test_per_tree_and_table_layouts() {
+        let model = Model::from_xgboost_json(include_str!("../models/bst1_10.json")).unwrap();
+
+        let per_tree = generate_rust_source_with_layout(&model, crate::NUM_FEATURES, RustLayout::PerTree);
+        assert!(per_tree.contains("#[inline(never)]\nfn tree_9(f: &[i64]) -> i64 {\n"));
+        assert!(per_tree.contains("    y = fixed_add(y, tree_9(f));\n"));
+
+        let table = generate_rust_source_with_layout(&model, crate::NUM_FEATURES, RustLayout::Table);
+        assert_eq!(table.matches("    Tree { nodes: &[").count(), 10);
+        assert!(table.contains("        TableNode { feature: 34, value: 120000000000, left: 1, right: "));
+        assert!(table.contains("        y = fixed_add(y, eval_tree(tree, f));\n"));
+        assert_eq!(generate_rust_source_with_layout(&model, 116, RustLayout::Unrolled), generate_rust_source(&model, 116));
+    }
+
+    #[test]
+    fn test_leaf_match_style_and_split_files() {
+        let model = Model::from_xgboost_json(include_str!("../models/bst1_10.json")).unwrap();
+
+        // Leaf ids are pre-order node ids; the values move into the match
+        let options = RustOptions { style: RustStyle::LeafMatch, ..RustOptions::default() };
+        let files = generate_rust_files(&model, crate::NUM_FEATURES, &options);
+        assert_eq!(files.len(), 1);
+        let source = &files[0].1;
+        assert!(source.contains("    let leaf = if fixed_le(f[34], from_scaled_i64(120000000000)) {\n"));
+        assert!(source.contains("                3\n"));
+        assert!(source.contains("        3 => from_scaled_i64(220286213),\n"));
+        assert_eq!(source.matches("    let tree_result = match leaf {\n").count(), 10);
+        assert_eq!(source.matches("        _ => unreachable!(),\n").count(), 10);
+
+        // Unrolled trees become one function per module file
+        let options = RustOptions { split_files: true, ..RustOptions::default() };
+        let files = generate_rust_files(&model, crate::NUM_FEATURES, &options);
+        let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names[..3], ["lib.rs", "tree_0.rs", "tree_1.rs"]);
+        assert_eq!(files.len(), 11);
+        assert!(files[0].1.contains("\nmod tree_9;\n"));
+        assert!(files[0].1.contains("    y = fixed_add(y, tree_9::tree_9(f));\n"));
+        assert!(files[10].1.starts_with("// Tree 9 of the model, generated with lib.rs\n\nuse super::*;\n"));
+        assert!(files[10].1.contains("#[inline(never)]\npub(super) fn tree_9(f: &[i64]) -> i64 {\n"));
+        let per_tree = RustOptions { layout: RustLayout::PerTree, ..options };
+        assert_eq!(generate_rust_files(&model, crate::NUM_FEATURES, &per_tree), files);
+
+        let table = RustOptions { layout: RustLayout::Table, ..options };
+        let files = generate_rust_files(&model, crate::NUM_FEATURES, &table);
+        assert!(files[0].1.contains("const TREES: &[Tree] = &[\n    tree_0::TREE,\n"));
+        assert!(files[1].1.contains("pub(super) const TREE: Tree = Tree { nodes: &[\n    TableNode { feature: 34,"));
+    }
+
+    #[test]
+    fn test_flattened_table_matches_tree() {
+        // Interpret the table the way the generated `eval_tree` does
+        fn eval_table(nodes: &[FlatNode], f: &[i64]) -> i64 {
+            let mut index = 0;
+            loop {
+                let node = &nodes[index];
+                let goes_left = |x: i64| match &node.categories {
+                    Some(values) => values.contains(&x),
+                    None => x <= node.value,
+                };
+                match node.feature {
+                    None => return node.value,
+                    Some(feature) => index = if goes_left(f[feature]) { node.left } else { node.right },
+                }
+            }
+        }
+
+        let model = crate::builtin_model();
+        let categorical = crate::Tree::new(Node::categorical(
+            22,
+            [0, 2, 5].into_iter().collect(),
+            1_000_000_000,
+            model.trees()[0].root.clone(),
+            Node::Leaf(-1),
+        ));
+        let mut features = vec![0i64; crate::NUM_FEATURES];
+        for step in 0..200i64 {
+            features[34] = step * 1_000_000_000;
+            features[22] = (step % 17) * 1_000_000_000 - 5_000_000_000;
+            for tree in model.trees().iter().chain([&categorical]) {
+                let nodes = flatten(&tree.root);
+                assert_eq!(nodes.len(), tree.num_nodes());
+                assert_eq!(eval_table(&nodes, &features), tree.eval(&features));
+            }
+        }
+    }
+
+    #[test]
+    fn test_fill_template() {
+        assert_eq!(fill_template("fn {name}() {{ {body} }}", &[("name", "f"), ("body", "1")]), "fn f() { 1 }");
+        assert_eq!(fill_template("{unknown}", &[]), "{unknown}");
+    }
+
+    #[test]
+    fn test_leo_program() {
+        let model = Model::from_xgboost_json(
+            r#"[{ "nodeid": 0, "split": "f40", "split_condition": -1.5, "yes": 1, "no": 2,
+                  "children": [{ "nodeid": 1, "leaf": 0.25 }, { "nodeid": 2, "leaf": -0.5 }] },
+                { "nodeid": 0, "leaf": 0.1 }]"#,
+        )
+        .unwrap();
+        let source = generate_leo_source(&model, crate::NUM_FEATURES, "rainfall");
+
+        assert!(source.starts_with("// XGBoost Decision Tree Implementation in Leo"));
+        assert!(source.contains("program rainfall.aleo {"));
+        assert!(source.contains(
+            "transition predict(private f0: [i64; 32], private f1: [i64; 32], \
+             private f2: [i64; 32], private f3: [i64; 20]) -> i64 {"
+        ));
+        assert!(source.contains(
+            "        if f1[8u32] <= -15000000000i64 {\n            t0 = 2500000000i64;\n        } else {\n"
+        ));
+        assert!(source.contains("        let t1: i64 = 0i64;\n        t1 = 1000000000i64;\n        y = fixed_add(y, t1);\n"));
+        assert_eq!(source.matches('{').count(), source.matches('}').count());
+    }
+
+    #[test]
+    fn test_averaged_model_divides_after_the_trees() {
+        let model = Model::from_xgboost_json(include_str!("../models/bst1_10.json"))
+            .unwrap()
+            .with_base_score(-7)
+            .with_combiner(Combiner::Average);
+
+        for layout in [RustLayout::Unrolled, RustLayout::PerTree, RustLayout::Table] {
+            let source = generate_rust_source_with_layout(&model, crate::NUM_FEATURES, layout);
+            assert!(source.contains("    let mut y = 0i64;\n"));
+            assert!(source.contains("    y = fixed_add(-7i64, y.div_euclid(10));\n"));
+        }
+
+        let leo = generate_leo_source(&model, crate::NUM_FEATURES, "forest");
+        assert!(leo.contains("        let y: i64 = 0i64;\n"));
+        assert!(leo.contains("        let q: i64 = y / 10i64;\n        y = fixed_add(-7i64, q * 10i64 > y ? q - 1i64 : q);\n"));
+    }
+
+    #[test]
+    fn test_categorical_membership_checks() {
+        let unit = crate::PRECISION_MULTIPLIER;
+        let tree = Node::categorical(
+            2,
+            [0, 3].into_iter().collect(),
+            unit,
+            Node::Leaf(5),
+            Node::categorical(40, crate::BitSet::new(), unit, Node::Leaf(6), Node::Leaf(7)),
+        );
+        let model = Model::new(vec![crate::Tree::new(tree)], unit);
+
+        let rust = generate_rust_source(&model, crate::NUM_FEATURES);
+        assert!(rust.contains("    let tree_result = if matches!(f[2], 0 | 30000000000) {\n"));
+        assert!(rust.contains("        if false {\n"));
+        let table = generate_rust_source_with_layout(&model, crate::NUM_FEATURES, RustLayout::Table);
+        assert!(table.contains("TableNode { feature: 2, value: 10000000000, left: 1, right: 2, categories: Some(&[0, 30000000000]) },"));
+        assert!(table.contains("TableNode { feature: 40, value: 10000000000, left: 3, right: 4, categories: Some(&[]) },"));
+
+        let leo = generate_leo_source(&model, crate::NUM_FEATURES, "categorical");
+        assert!(leo.contains("if f0[2u32] == 0i64 || f0[2u32] == 30000000000i64 {\n"));
+        assert!(leo.contains("if false {\n"));
+    }
+}
@@ -0,0 +1,410 @@
+// Model commitment
+// Merkleizes every node of every tree with SHA-256, then the tree roots into one forest root,
+// so a verifier holding only the 32-byte commitment can check that a prediction path was taken
+// through the committed model instead of trusting whoever ran it.
+//
+//   leaf   = H(0x00 | value)
+//   split  = H(0x01 | feature u32 | threshold | left | right)
+//   categorical split = H(0x04 | feature u32 | unit | num_words u32 | words u64... | left | right)
+//   inner  = H(0x02 | left | right)            (forest of tree roots, odd node carried up)
+//   root   = H(0x03 | scale | num_features u32 | num_trees u32 | base_score | combiner u8 | forest root)
+//
+// Integers are little-endian, values i64, matching the canonical binary format (including its
+// combiner byte: 0 = sum, 1 = average, with 0x80 set for an i128 accumulator).
+
+use alloc::{vec, vec::Vec};
+
+use crate::binary::combiner_byte;
+use crate::ensemble::{Accumulator, BitSet, Combiner, Ensemble, Node};
+use crate::fixed_le;
+use crate::sha256::sha256;
+
+/// SHA-256 digest
+pub type Digest = [u8; 32];
+
+const TAG_LEAF: u8 = 0;
+const TAG_SPLIT: u8 = 1;
+const TAG_INNER: u8 = 2;
+const TAG_ROOT: u8 = 3;
+const TAG_CATEGORICAL: u8 = 4;
+
+/// Hash of a leaf node
+pub fn hash_leaf(value: i64) -> Digest {
+    let mut data = [0u8; 9];
+    data[0] = TAG_LEAF;
+    data[1..].copy_from_slice(&value.to_le_bytes());
+    sha256(&data)
+}
+
+/// Hash of a split node from its children's hashes
+pub fn hash_split(feature: usize, threshold: i64, left: &Digest, right: &Digest) -> Digest {
+    let mut data = Vec::with_capacity(77);
+    data.push(TAG_SPLIT);
+    data.extend_from_slice(&(feature as u32).to_le_bytes());
+    data.extend_from_slice(&threshold.to_le_bytes());
+    data.extend_from_slice(left);
+    data.extend_from_slice(right);
+    sha256(&data)
+}
+
+/// Hash of a categorical split node from its children's hashes
+pub fn hash_categorical(feature: usize, categories: &BitSet, unit: i64, left: &Digest, right: &Digest) -> Digest {
+    let mut data = Vec::with_capacity(81 + 8 * categories.words().len());
+    data.push(TAG_CATEGORICAL);
+    data.extend_from_slice(&(feature as u32).to_le_bytes());
+    data.extend_from_slice(&unit.to_le_bytes());
+    data.extend_from_slice(&(categories.words().len() as u32).to_le_bytes());
+    for word in categories.words() {
+        data.extend_from_slice(&word.to_le_bytes());
+    }
+    data.extend_from_slice(left);
+    data.extend_from_slice(right);
+    sha256(&data)
+}
+
+fn hash_inner(left: &Digest, right: &Digest) -> Digest {
+    let mut data = [0u8; 65];
+    data[0] = TAG_INNER;
+    data[1..33].copy_from_slice(left);
+    data[33..].copy_from_slice(right);
+    sha256(&data)
+}
+
+fn hash_root(
+    scale: i64,
+    num_features: usize,
+    num_trees: usize,
+    base_score: i64,
+    combiner: Combiner,
+    accumulator: Accumulator,
+    forest: &Digest,
+) -> Digest {
+    let mut data = Vec::with_capacity(58);
+    data.push(TAG_ROOT);
+    data.extend_from_slice(&scale.to_le_bytes());
+    data.extend_from_slice(&(num_features as u32).to_le_bytes());
+    data.extend_from_slice(&(num_trees as u32).to_le_bytes());
+    data.extend_from_slice(&base_score.to_le_bytes());
+    data.push(combiner_byte(combiner, accumulator));
+    data.extend_from_slice(forest);
+    sha256(&data)
+}
+
+/// Merkle root of the subtree rooted at `node`
+pub fn hash_node(node: &Node) -> Digest {
+    match node {
+        Node::Leaf(value) => hash_leaf(*value),
+        Node::Split { feature, threshold, left, right } => {
+            hash_split(*feature, *threshold, &hash_node(left), &hash_node(right))
+        }
+        Node::Categorical { feature, categories, unit, left, right } => {
+            hash_categorical(*feature, categories, *unit, &hash_node(left), &hash_node(right))
+        }
+    }
+}
+
+/// Merkle layers over the tree roots, from the leaves up to the single forest root
+fn forest_layers(tree_roots: &[Digest]) -> Vec<Vec<Digest>> {
+    let mut layers = vec![tree_roots.to_vec()];
+    while layers.last().is_some_and(|layer| layer.len() > 1) {
+        let layer = layers.last().expect("non-empty");
+        let next = layer
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => hash_inner(left, right),
+                [odd] => *odd,
+                _ => unreachable!(),
+            })
+            .collect();
+        layers.push(next);
+    }
+    layers
+}
+
+/// Commitment to every node of a model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelCommitment {
+    root: Digest,
+    tree_roots: Vec<Digest>,
+}
+
+impl ModelCommitment {
+    /// Commit to a model
+    ///
+    /// # Arguments
+    /// * `model` - Ensemble to commit to
+    ///
+    /// # Returns
+    /// * `ModelCommitment` - Per-tree Merkle roots and the overall commitment
+    pub fn new(model: &Ensemble) -> ModelCommitment {
+        let tree_roots: Vec<Digest> = model.trees().iter().map(|tree| hash_node(&tree.root)).collect();
+        let forest = forest_layers(&tree_roots).last().and_then(|top| top.first().copied()).unwrap_or([0; 32]);
+        let root = hash_root(
+            model.scale(),
+            model.num_features(),
+            model.num_trees(),
+            model.base_score(),
+            model.combiner(),
+            model.accumulator(),
+            &forest,
+        );
+        ModelCommitment { root, tree_roots }
+    }
+
+    /// The 32-byte commitment to publish
+    pub fn root(&self) -> Digest {
+        self.root
+    }
+
+    /// Merkle root of each tree
+    pub fn tree_roots(&self) -> &[Digest] {
+        &self.tree_roots
+    }
+
+    /// Whether `model` is the committed model
+    pub fn matches(&self, model: &Ensemble) -> bool {
+        ModelCommitment::new(model).root == self.root
+    }
+
+    /// Open the path that `features` take through tree `tree` of the committed `model`
+    ///
+    /// # Arguments
+    /// * `model` - The committed model
+    /// * `tree` - Index of the tree to open
+    /// * `features` - Input feature vector (at the model's scale)
+    ///
+    /// # Returns
+    /// * `PathProof` - Splits on the path with sibling hashes, the leaf, and the forest path
+    pub fn open_path(&self, model: &Ensemble, tree: usize, features: &[i64]) -> PathProof {
+        assert!(self.matches(model), "model does not match the commitment");
+
+        let mut steps = Vec::new();
+        let mut node = &model.trees()[tree].root;
+        let leaf_value = loop {
+            match node {
+                Node::Leaf(value) => break *value,
+                Node::Split { feature, threshold, left, right } => {
+                    let go_left = fixed_le(features[*feature], *threshold);
+                    let (next, sibling) = if go_left { (left, right) } else { (right, left) };
+                    steps.push(PathStep {
+                        feature: *feature,
+                        threshold: *threshold,
+                        categories: None,
+                        go_left,
+                        sibling: hash_node(sibling),
+                    });
+                    node = next;
+                }
+                Node::Categorical { feature, categories, unit, left, right } => {
+                    let go_left = categories.contains_value(features[*feature], *unit);
+                    let (next, sibling) = if go_left { (left, right) } else { (right, left) };
+                    steps.push(PathStep {
+                        feature: *feature,
+                        threshold: *unit,
+                        categories: Some(categories.clone()),
+                        go_left,
+                        sibling: hash_node(sibling),
+                    });
+                    node = next;
+                }
+            }
+        };
+
+        // Sibling of the tree root at every forest layer (None when carried up unpaired)
+        let mut forest_path = Vec::new();
+        let mut index = tree;
+        for layer in forest_layers(&self.tree_roots).iter().take_while(|layer| layer.len() > 1) {
+            forest_path.push(layer.get(index ^ 1).copied().map(|sibling| (sibling, index % 2 == 1)));
+            index /= 2;
+        }
+
+        PathProof {
+            tree,
+            steps,
+            leaf_value,
+            forest_path,
+            scale: model.scale(),
+            num_features: model.num_features(),
+            num_trees: model.num_trees(),
+            base_score: model.base_score(),
+            combiner: model.combiner(),
+            accumulator: model.accumulator(),
+        }
+    }
+}
+
+/// One split on an opened path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathStep {
+    pub feature: usize,
+    /// Split threshold, or the category unit of a categorical split
+    pub threshold: i64,
+    /// Categories sent left by a categorical split (`None` for a threshold split)
+    pub categories: Option<BitSet>,
+    /// Whether the path continued into the left child
+    pub go_left: bool,
+    /// Hash of the child that was not taken
+    pub sibling: Digest,
+}
+
+/// Opening of one root-to-leaf path against a `ModelCommitment`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathProof {
+    /// Index of the opened tree
+    pub tree: usize,
+    /// Splits from the root down
+    pub steps: Vec<PathStep>,
+    pub leaf_value: i64,
+    /// Sibling tree-root hashes from the bottom forest layer up, with whether the sibling is
+    /// on the left; `None` where the node was carried up without a sibling
+    pub forest_path: Vec<Option<(Digest, bool)>>,
+    pub scale: i64,
+    pub num_features: usize,
+    pub num_trees: usize,
+    /// Base score the combined leaf values of all trees are added to
+    pub base_score: i64,
+    /// Whether the leaf values of all trees are summed or averaged
+    pub combiner: Combiner,
+    /// Width of the running sum of the leaf values
+    pub accumulator: Accumulator,
+}
+
+impl PathProof {
+    /// Check the path against a commitment and the features it was taken for
+    ///
+    /// # Arguments
+    /// * `commitment` - Published model commitment (`ModelCommitment::root`)
+    /// * `features` - Input feature vector the path is claimed for
+    ///
+    /// # Returns
+    /// * `Option<i64>` - The leaf value if every comparison holds and the hashes reach the commitment
+    pub fn verify(&self, commitment: &Digest, features: &[i64]) -> Option<i64> {
+        for step in &self.steps {
+            let value = *features.get(step.feature)?;
+            let go_left = match &step.categories {
+                Some(categories) => categories.contains_value(value, step.threshold),
+                None => fixed_le(value, step.threshold),
+            };
+            if go_left != step.go_left {
+                return None;
+            }
+        }
+
+        let mut hash = hash_leaf(self.leaf_value);
+        for step in self.steps.iter().rev() {
+            let (left, right) = if step.go_left { (&hash, &step.sibling) } else { (&step.sibling, &hash) };
+            hash = match &step.categories {
+                Some(categories) => hash_categorical(step.feature, categories, step.threshold, left, right),
+                None => hash_split(step.feature, step.threshold, left, right),
+            };
+        }
+
+        for (sibling, sibling_is_left) in self.forest_path.iter().flatten() {
+            hash = if *sibling_is_left { hash_inner(sibling, &hash) } else { hash_inner(&hash, sibling) };
+        }
+
+        let root = hash_root(
+            self.scale,
+            self.num_features,
+            self.num_trees,
+            self.base_score,
+            self.combiner,
+            self.accumulator,
+            &hash,
+        );
+        (root == *commitment).then_some(self.leaf_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Tree, NUM_FEATURES};
+
+    fn sample_features() -> Vec<i64> {
+        let mut features = vec![0i64; NUM_FEATURES];
+        features[34] = 130000000000;
+        features[77] = -20000000000;
+        features
+    }
+
+    #[test]
+    fn test_paths_verify_against_commitment() {
+        let model = crate::builtin_model();
+        let commitment = ModelCommitment::new(&model);
+        let features = sample_features();
+
+        // Every tree's opened leaf sums to the prediction
+        let total = (0..model.num_trees())
+            .map(|tree| commitment.open_path(&model, tree, &features).verify(&commitment.root(), &features).unwrap())
+            .fold(0i64, crate::fixed_add);
+        assert_eq!(total, crate::xgboost_predict(&features));
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let model = crate::builtin_model();
+        let commitment = ModelCommitment::new(&model);
+        let features = sample_features();
+        let proof = commitment.open_path(&model, 3, &features);
+
+        let mut wrong_leaf = proof.clone();
+        wrong_leaf.leaf_value += 1;
+        assert_eq!(wrong_leaf.verify(&commitment.root(), &features), None);
+
+        let mut wrong_threshold = proof.clone();
+        wrong_threshold.steps[0].threshold += 1;
+        assert_eq!(wrong_threshold.verify(&commitment.root(), &features), None);
+
+        // Features that would take the other branch at the root
+        let mut other = features.clone();
+        let root = &proof.steps[0];
+        other[root.feature] = if root.go_left { root.threshold + 1 } else { root.threshold };
+        assert_eq!(proof.verify(&commitment.root(), &other), None);
+
+        let rescaled = model.rescale(1_000_000).unwrap();
+        assert!(!commitment.matches(&rescaled));
+        assert!(!commitment.matches(&model.clone().with_base_score(1)));
+        assert!(!commitment.matches(&model.clone().with_combiner(Combiner::Average)));
+        assert!(!commitment.matches(&model.clone().with_accumulator(Accumulator::I128)));
+    }
+
+    #[test]
+    fn test_odd_forest_sizes() {
+        for num_trees in 1..6i64 {
+            // Tree i: x0 <= i ? i : -i
+            let trees = (0..num_trees)
+                .map(|i| Tree::new(Node::split(0, i, Node::Leaf(i), Node::Leaf(-i))))
+                .collect();
+            let model = Ensemble::new(trees, 1);
+            let commitment = ModelCommitment::new(&model);
+            for tree in 0..num_trees {
+                let proof = commitment.open_path(&model, tree as usize, &[2]);
+                let expected = if 2 <= tree { tree } else { -tree };
+                assert_eq!(proof.verify(&commitment.root(), &[2]), Some(expected));
+            }
+        }
+    }
+
+    #[test]
+    fn test_categorical_path() {
+        let categories: BitSet = [2, 5].into_iter().collect();
+        let tree = Tree::new(Node::categorical(0, categories, 10, Node::Leaf(7), Node::split(0, 30, Node::Leaf(8), Node::Leaf(9))));
+        let model = Ensemble::new(vec![tree], 10);
+        let commitment = ModelCommitment::new(&model);
+
+        let proof = commitment.open_path(&model, 0, &[50]);
+        assert_eq!(proof.verify(&commitment.root(), &[50]), Some(7));
+        assert_eq!(proof.verify(&commitment.root(), &[40]), None);
+        assert_eq!(commitment.open_path(&model, 0, &[40]).verify(&commitment.root(), &[40]), Some(9));
+
+        // Another category set, or the set read as a threshold split, breaks the hash chain
+        let mut wider = proof.clone();
+        wider.steps[0].categories = Some([2, 4, 5].into_iter().collect());
+        assert_eq!(wider.verify(&commitment.root(), &[50]), None);
+        let mut threshold = proof;
+        threshold.steps[0].categories = None;
+        threshold.steps[0].threshold = 50;
+        assert_eq!(threshold.verify(&commitment.root(), &[50]), None);
+    }
+}
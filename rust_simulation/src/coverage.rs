@@ -0,0 +1,230 @@
+// Leaf coverage
+// Random and golden inputs exercise the leaves the data tends to reach; a leaf behind a rare
+// combination of conditions can go untested, and with it the witness for that path. `PathCoverage`
+// enumerates every root-to-leaf path of every tree and synthesizes a feature vector that follows
+// it, with the interval solver the threshold boundary cases use. Running those vectors through
+// an evaluator or through witness preparation exercises every leaf at least once. A path whose
+// conditions contradict each other (a feature both <= 3 and > 5) is dead: no input reaches the
+// leaf, and it is reported instead of tested.
+
+use alloc::{vec, vec::Vec};
+use core::fmt;
+
+use crate::boundary::solve_path;
+use crate::ensemble::{Ensemble, Node};
+use crate::witness;
+
+/// Root-to-leaf path of one tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeafPath {
+    /// Index of the tree in the ensemble
+    pub tree: usize,
+    /// Pre-order id of the leaf, as in `TreeTrace::path`
+    pub node_id: usize,
+    /// Branches from the root, `true` for left
+    pub path: Vec<bool>,
+    /// Leaf value
+    pub leaf: i64,
+    /// Feature vector that reaches the leaf, or None if the path is dead
+    pub features: Option<Vec<i64>>,
+}
+
+/// Path on which an evaluator or the witness disagrees with the model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoverageFailure {
+    /// The synthesized vector ended in another leaf of the tree
+    Leaf { path: LeafPath, reached: usize },
+    /// The prediction differs from `Ensemble::eval`
+    Prediction { path: LeafPath, expected: i64, got: i64 },
+}
+
+impl fmt::Display for CoverageFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoverageFailure::Leaf { path, reached } => {
+                write!(f, "tree {}: path to leaf {} reached node {}", path.tree, path.node_id, reached)
+            }
+            CoverageFailure::Prediction { path, expected, got } => write!(
+                f,
+                "tree {}: on the path to leaf {} expected {}, got {}",
+                path.tree, path.node_id, expected, got
+            ),
+        }
+    }
+}
+
+/// Every root-to-leaf path of an ensemble with an input for each live one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathCoverage {
+    paths: Vec<LeafPath>,
+}
+
+impl PathCoverage {
+    /// Enumerate the paths of `model` and solve each for a feature vector
+    ///
+    /// # Arguments
+    /// * `model` - Ensemble to cover
+    ///
+    /// # Returns
+    /// * `PathCoverage` - One path per leaf, in tree and pre-order
+    pub fn new(model: &Ensemble) -> PathCoverage {
+        let mut paths = Vec::new();
+        for (tree, root) in model.trees().iter().map(|tree| &tree.root).enumerate() {
+            collect(root, &mut vec![], &mut 0, &mut |path, node_id, leaf| {
+                let features = solve_path(root, path, model.num_features(), &[]);
+                paths.push(LeafPath { tree, node_id, path: path.to_vec(), leaf, features });
+            });
+        }
+        PathCoverage { paths }
+    }
+
+    /// All paths, dead ones included
+    pub fn paths(&self) -> &[LeafPath] {
+        &self.paths
+    }
+
+    /// Paths that no input can follow
+    pub fn dead(&self) -> impl Iterator<Item = &LeafPath> {
+        self.paths.iter().filter(|path| path.features.is_none())
+    }
+
+    /// Number of leaves with a synthesized input
+    pub fn covered(&self) -> usize {
+        self.paths.len() - self.dead().count()
+    }
+
+    /// Synthesized inputs, one per live path
+    pub fn vectors(&self) -> Vec<Vec<i64>> {
+        self.paths.iter().filter_map(|path| path.features.clone()).collect()
+    }
+
+    /// Run `eval` on the input of every live path and compare it with `Ensemble::eval`
+    ///
+    /// The traced evaluation must also end each path's tree in the path's leaf.
+    ///
+    /// # Arguments
+    /// * `model` - Ensemble the coverage was built for
+    /// * `eval` - Evaluator under test, taking features at the ensemble's scale
+    ///
+    /// # Returns
+    /// * `Result<usize, CoverageFailure>` - Number of paths checked, or the first failure
+    pub fn check<F>(&self, model: &Ensemble, eval: F) -> Result<usize, CoverageFailure>
+    where
+        F: Fn(&[i64]) -> i64,
+    {
+        for (path, features) in self.live() {
+            let (expected, trace) = model.eval_with_trace(features);
+            check_leaf(path, trace.trees[path.tree].path.last().copied())?;
+            let got = eval(features);
+            if got != expected {
+                return Err(CoverageFailure::Prediction { path: path.clone(), expected, got });
+            }
+        }
+        Ok(self.covered())
+    }
+
+    /// Prepare a witness for the input of every live path and check that its trace takes the path
+    ///
+    /// # Arguments
+    /// * `model` - Ensemble the coverage was built for
+    ///
+    /// # Returns
+    /// * `Result<usize, CoverageFailure>` - Number of witnesses checked, or the first failure
+    pub fn check_witnesses(&self, model: &Ensemble) -> Result<usize, CoverageFailure> {
+        let bundles = witness::prepare_batch(model, &self.vectors());
+        for ((path, features), bundle) in self.live().zip(&bundles) {
+            check_leaf(path, bundle.trace.trees[path.tree].path.last().copied())?;
+            let expected = model.eval(features);
+            if bundle.prediction != expected || bundle.trace.output() != expected {
+                return Err(CoverageFailure::Prediction { path: path.clone(), expected, got: bundle.prediction });
+            }
+        }
+        Ok(bundles.len())
+    }
+
+    fn live(&self) -> impl Iterator<Item = (&LeafPath, &Vec<i64>)> {
+        self.paths.iter().filter_map(|path| path.features.as_ref().map(|features| (path, features)))
+    }
+}
+
+/// Call `visit(path, node_id, leaf)` for every leaf below `node`, numbering nodes in pre-order
+fn collect<V>(node: &Node, path: &mut Vec<bool>, next_id: &mut usize, visit: &mut V)
+where
+    V: FnMut(&[bool], usize, i64),
+{
+    let node_id = *next_id;
+    *next_id += 1;
+    match node {
+        Node::Leaf(value) => visit(path, node_id, *value),
+        Node::Split { left, right, .. } | Node::Categorical { left, right, .. } => {
+            for (child, branch) in [(left, true), (right, false)] {
+                path.push(branch);
+                collect(child, path, next_id, visit);
+                path.pop();
+            }
+        }
+    }
+}
+
+fn check_leaf(path: &LeafPath, reached: Option<usize>) -> Result<(), CoverageFailure> {
+    match reached {
+        Some(reached) if reached == path.node_id => Ok(()),
+        reached => Err(CoverageFailure::Leaf { path: path.clone(), reached: reached.unwrap_or(0) }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::{BitSet, Tree};
+    use crate::{builtin_model, xgboost_predict, xgboost_predict_oblivious};
+
+    #[test]
+    fn test_every_leaf_has_an_input() {
+        // 0: f0 <= 5 ? (1: f0 <= 3 ? [2] : (3: f1 in {1, 3} ? [4] : [5])) : (6: f0 <= 2 ? [7] : [8])
+        let categories: BitSet = [1, 3].into_iter().collect();
+        let categorical = Node::categorical(1, categories, 10, Node::Leaf(2), Node::Leaf(3));
+        let inner = Node::split(0, 3, Node::Leaf(1), categorical);
+        let root = Node::split(0, 5, inner, Node::split(0, 2, Node::Leaf(4), Node::Leaf(5)));
+        let model = Ensemble::new(vec![Tree::new(root)], 10);
+
+        let coverage = PathCoverage::new(&model);
+        let ids: Vec<(usize, i64)> = coverage.paths().iter().map(|path| (path.node_id, path.leaf)).collect();
+        assert_eq!(ids, vec![(2, 1), (4, 2), (5, 3), (7, 4), (8, 5)]);
+        assert_eq!(coverage.paths()[1].path, vec![true, false, true]);
+
+        // f0 > 5 and f0 <= 2 cannot both hold
+        let dead: Vec<usize> = coverage.dead().map(|path| path.node_id).collect();
+        assert_eq!(dead, vec![7]);
+        assert_eq!(coverage.covered(), 4);
+        assert_eq!(coverage.paths()[1].features, Some(vec![4, 10]));
+        for path in coverage.paths().iter().filter(|path| path.features.is_some()) {
+            assert_eq!(model.eval(path.features.as_ref().unwrap()), path.leaf);
+        }
+        assert_eq!(coverage.check(&model, |features| model.eval(features)), Ok(4));
+        assert_eq!(coverage.check_witnesses(&model), Ok(4));
+    }
+
+    #[test]
+    fn test_builtin_model_coverage() {
+        let model = builtin_model();
+        let coverage = PathCoverage::new(&model);
+        let leaves: usize = model.trees().iter().map(|tree| tree.num_nodes().div_ceil(2)).sum();
+        assert_eq!(coverage.paths().len(), leaves);
+        // No path of the bundled model is dead
+        assert_eq!(coverage.covered(), leaves);
+
+        assert_eq!(coverage.check(&model, xgboost_predict), Ok(coverage.covered()));
+        assert_eq!(coverage.check(&model, xgboost_predict_oblivious), Ok(coverage.covered()));
+        assert_eq!(coverage.check_witnesses(&model), Ok(coverage.covered()));
+    }
+
+    #[test]
+    fn test_reports_wrong_evaluator() {
+        let model = Ensemble::new(vec![Tree::new(Node::split(0, 5, Node::Leaf(1), Node::Leaf(2)))], 1);
+        let coverage = PathCoverage::new(&model);
+        let failure = coverage.check(&model, |features| if features[0] > 0 { 7 } else { 1 }).unwrap_err();
+        assert!(matches!(failure, CoverageFailure::Prediction { expected: 2, got: 7, .. }));
+        assert_eq!(failure.to_string(), "tree 0: on the path to leaf 2 expected 2, got 7");
+    }
+}
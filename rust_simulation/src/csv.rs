@@ -0,0 +1,194 @@
+// Feature CSV reader
+// One sample per line, comma-separated raw (unscaled) feature values. An optional header line is
+// skipped, and empty fields stand for the -99999 missing-value sentinel used in training.
+// `parse_scaled_row` reads rows that already hold fixed-point integers, exactly as proved, and
+// `parse_decimal_row` scales decimal fields without going through f64, so the witness inputs are
+// the exactly rounded values of the text.
+
+use alloc::{format, string::{String, ToString}, vec::Vec};
+use core::fmt;
+
+use crate::fixed::{parse_decimal, ParseFixedError};
+use crate::{to_fixed_point_at, PRECISION_MULTIPLIER};
+
+/// Raw value an empty CSV field is read as
+pub const MISSING_FIELD: f64 = -99999.0;
+
+/// Malformed line in a feature CSV
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvError {
+    /// 1-based line number
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl core::error::Error for CsvError {}
+
+/// Parse one comma-separated line of raw feature values
+///
+/// # Arguments
+/// * `line` - CSV line without its newline
+///
+/// # Returns
+/// * `Result<Vec<f64>, String>` - The values, or a description of the first bad field
+pub fn parse_row(line: &str) -> Result<Vec<f64>, String> {
+    line.split(',')
+        .enumerate()
+        .map(|(column, field)| {
+            let field = field.trim();
+            if field.is_empty() {
+                return Ok(MISSING_FIELD);
+            }
+            match field.parse::<f64>() {
+                Ok(value) if value.is_finite() => Ok(value),
+                _ => Err(format!("column {}: '{}' is not a finite number", column + 1, field)),
+            }
+        })
+        .collect()
+}
+
+/// Parse one comma-separated line of feature values already scaled to fixed point
+///
+/// # Arguments
+/// * `line` - CSV line without its newline
+///
+/// # Returns
+/// * `Result<Vec<i64>, String>` - The values (empty fields as `MISSING_VALUE`), or a
+///   description of the first field that is not an i64
+pub fn parse_scaled_row(line: &str) -> Result<Vec<i64>, String> {
+    parse_scaled_row_at(line, PRECISION_MULTIPLIER)
+}
+
+/// [`parse_scaled_row`] for values at a runtime scale, such as a loaded model's
+///
+/// Empty fields are read as `MISSING_FIELD` at `scale`.
+pub fn parse_scaled_row_at(line: &str, scale: i64) -> Result<Vec<i64>, String> {
+    let missing = to_fixed_point_at(MISSING_FIELD, scale);
+    line.split(',')
+        .enumerate()
+        .map(|(column, field)| {
+            let field = field.trim();
+            if field.is_empty() {
+                return Ok(missing);
+            }
+            field.parse::<i64>().map_err(|_| format!("column {}: '{}' is not a scaled integer", column + 1, field))
+        })
+        .collect()
+}
+
+/// Parse one comma-separated line of raw decimal feature values straight to fixed point
+///
+/// Same fields as `parse_row`, but each is scaled with `Fixed::from_decimal_str`, which rounds the
+/// exact decimal once instead of rounding to f64 and then to the scale.
+///
+/// # Arguments
+/// * `line` - CSV line without its newline
+///
+/// # Returns
+/// * `Result<Vec<i64>, String>` - The scaled values (empty fields as `MISSING_VALUE`), or a
+///   description of the first bad field
+pub fn parse_decimal_row(line: &str) -> Result<Vec<i64>, String> {
+    parse_decimal_row_at(line, PRECISION_MULTIPLIER)
+}
+
+/// [`parse_decimal_row`] at a runtime scale, such as a loaded model's (see `parse_decimal`)
+///
+/// Empty fields are read as `MISSING_FIELD` at `scale`.
+pub fn parse_decimal_row_at(line: &str, scale: i64) -> Result<Vec<i64>, String> {
+    let missing = to_fixed_point_at(MISSING_FIELD, scale);
+    line.split(',')
+        .enumerate()
+        .map(|(column, field)| {
+            let field = field.trim();
+            if field.is_empty() {
+                return Ok(missing);
+            }
+            parse_decimal(field, scale).map_err(|error| match error {
+                ParseFixedError::Invalid => format!("column {}: '{}' is not a finite number", column + 1, field),
+                ParseFixedError::OutOfRange => format!("column {}: '{}' is out of range", column + 1, field),
+            })
+        })
+        .collect()
+}
+
+/// Whether a line is a header rather than a sample (its first field is not a number)
+pub fn is_header(line: &str) -> bool {
+    let first = line.split(',').next().unwrap_or("").trim();
+    !first.is_empty() && first.parse::<f64>().is_err()
+}
+
+/// Parse a whole feature CSV
+///
+/// # Arguments
+/// * `text` - File contents
+/// * `num_features` - Number of columns every sample must have
+///
+/// # Returns
+/// * `Result<Vec<Vec<f64>>, CsvError>` - One raw feature vector per non-empty line
+pub fn parse_samples(text: &str, num_features: usize) -> Result<Vec<Vec<f64>>, CsvError> {
+    let mut samples = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || (index == 0 && is_header(line)) {
+            continue;
+        }
+        let error = |message: String| CsvError { line: index + 1, message };
+        let row = parse_row(line).map_err(error)?;
+        if row.len() != num_features {
+            return Err(error(format!("expected {} columns, got {}", num_features, row.len())));
+        }
+        samples.push(row);
+    }
+    if samples.is_empty() {
+        return Err(CsvError { line: 0, message: "no samples".to_string() });
+    }
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::MISSING_VALUE;
+
+    #[test]
+    fn test_parse_samples() {
+        let text = "a,b,c\n1,2.5,-3\n\n,4e1, 0 \n";
+        assert_eq!(
+            parse_samples(text, 3).unwrap(),
+            vec![vec![1.0, 2.5, -3.0], vec![MISSING_FIELD, 40.0, 0.0]]
+        );
+    }
+
+    #[test]
+    fn test_rejects_malformed_rows() {
+        let error = parse_samples("1,2,3\n1,x,3\n", 3).unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!(error.message.contains("column 2"));
+
+        assert_eq!(parse_samples("1,2,3\n1,2\n", 3).unwrap_err().line, 2);
+        assert!(parse_samples("1,inf,3\n", 3).is_err());
+        assert!(parse_samples("a,b,c\n", 3).is_err());
+    }
+
+    #[test]
+    fn test_parse_scaled_row() {
+        assert_eq!(parse_scaled_row("12345678901234567, -5,").unwrap(), vec![12345678901234567, -5, MISSING_VALUE]);
+        assert_eq!(parse_scaled_row("1,0.5").unwrap_err(), "column 2: '0.5' is not a scaled integer");
+    }
+
+    #[test]
+    fn test_parse_decimal_row() {
+        let row = parse_decimal_row("0.0220286213,, -4e1,1438.10345095225").unwrap();
+        assert_eq!(row, vec![220_286_213, MISSING_VALUE, -400_000_000_000, 14_381_034_509_523]);
+        assert_eq!(MISSING_VALUE, crate::to_fixed_point(MISSING_FIELD));
+        assert_eq!(parse_decimal_row("1,inf").unwrap_err(), "column 2: 'inf' is not a finite number");
+        assert_eq!(parse_decimal_row("1e10").unwrap_err(), "column 1: '1e10' is out of range");
+        assert_eq!(parse_decimal_row_at("0.0220286213,,1e10", 1000).unwrap(), vec![22, -99_999_000, 10_000_000_000_000]);
+        assert_eq!(parse_scaled_row_at("7,", 1000).unwrap(), vec![7, -99_999_000]);
+    }
+}
@@ -0,0 +1,116 @@
+// Graphviz export
+// Renders trees as DOT digraphs for auditing a port against the original XGBoost dump by eye.
+// Nodes are numbered in pre-order (the root is n0). Splits show the feature, the scaled threshold
+// and the threshold as a float; categorical splits show their category set; leaves show the
+// scaled value and its float. The left edge ("yes") is taken when the test holds, as in
+// XGBoost's own `to_graphviz`.
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::Write;
+
+use crate::ensemble::{Ensemble, Node, Tree};
+
+/// Append the statements of `node` and its subtree, returning the next free node id
+fn write_node(out: &mut String, node: &Node, id: usize, scale: i64) -> usize {
+    let float = |value: i64| value as f64 / scale as f64;
+    let (test, left, right) = match node {
+        Node::Leaf(value) => {
+            let _ = writeln!(out, "    n{} [shape=box, label=\"leaf {}\\n({})\"];", id, value, float(*value));
+            return id + 1;
+        }
+        Node::Split { feature, threshold, left, right } => {
+            (format!("f{} <= {}\\n({})", feature, threshold, float(*threshold)), left, right)
+        }
+        Node::Categorical { feature, categories, left, right, .. } => {
+            let members: Vec<String> = categories.iter().map(|category| format!("{}", category)).collect();
+            (format!("f{} in {{{}}}", feature, members.join(", ")), left, right)
+        }
+    };
+    let _ = writeln!(out, "    n{} [label=\"{}\"];", id, test);
+    let left_id = id + 1;
+    let right_id = write_node(out, left, left_id, scale);
+    let _ = writeln!(out, "    n{} -> n{} [label=\"yes\"];", id, left_id);
+    let _ = writeln!(out, "    n{} -> n{} [label=\"no\"];", id, right_id);
+    write_node(out, right, right_id, scale)
+}
+
+impl Tree {
+    /// Render the tree as a DOT digraph
+    ///
+    /// # Arguments
+    /// * `name` - Graph name
+    /// * `scale` - Fixed-point scale of the thresholds and leaves, for the float labels
+    ///
+    /// # Returns
+    /// * `String` - `digraph name { ... }` with one statement per line
+    pub fn to_dot(&self, name: &str, scale: i64) -> String {
+        let mut out = format!("digraph {} {{\n    node [fontname=\"monospace\"];\n", name);
+        write_node(&mut out, &self.root, 0, scale);
+        out.push_str("}\n");
+        out
+    }
+}
+
+impl Ensemble {
+    /// Render every tree as a DOT digraph
+    ///
+    /// # Returns
+    /// * `Vec<String>` - One digraph per tree, named `tree0`, `tree1`, ...
+    pub fn to_dot(&self) -> Vec<String> {
+        self.trees()
+            .iter()
+            .enumerate()
+            .map(|(index, tree)| tree.to_dot(&format!("tree{}", index), self.scale()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::BitSet;
+    use alloc::vec;
+
+    #[test]
+    fn test_tree_to_dot() {
+        let tree = Tree::new(Node::split(3, 25, Node::Leaf(-5), Node::split(0, 0, Node::Leaf(1), Node::Leaf(2))));
+        let dot = tree.to_dot("t", 10);
+        let expected = [
+            "digraph t {",
+            "    node [fontname=\"monospace\"];",
+            "    n0 [label=\"f3 <= 25\\n(2.5)\"];",
+            "    n1 [shape=box, label=\"leaf -5\\n(-0.5)\"];",
+            "    n0 -> n1 [label=\"yes\"];",
+            "    n0 -> n2 [label=\"no\"];",
+            "    n2 [label=\"f0 <= 0\\n(0)\"];",
+            "    n3 [shape=box, label=\"leaf 1\\n(0.1)\"];",
+            "    n2 -> n3 [label=\"yes\"];",
+            "    n2 -> n4 [label=\"no\"];",
+            "    n4 [shape=box, label=\"leaf 2\\n(0.2)\"];",
+            "}",
+        ];
+        assert_eq!(dot.lines().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_ensemble_to_dot() {
+        let categories: BitSet = [1, 4].into_iter().collect();
+        let model = Ensemble::new(
+            vec![
+                Tree::new(Node::Leaf(7)),
+                Tree::new(Node::categorical(1, categories, 10, Node::Leaf(0), Node::Leaf(3))),
+            ],
+            10,
+        );
+        let dots = model.to_dot();
+        assert_eq!(dots.len(), 2);
+        assert!(dots[0].starts_with("digraph tree0 {"));
+        assert!(dots[1].contains("n0 [label=\"f1 in {1, 4}\"];"));
+
+        // One node statement per node of the bundled model
+        let builtin = crate::builtin_model();
+        for (dot, tree) in builtin.to_dot().iter().zip(builtin.trees()) {
+            assert_eq!(dot.matches("label=\"yes\"").count() * 2 + 1, tree.num_nodes());
+        }
+    }
+}
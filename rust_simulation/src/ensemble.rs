@@ -0,0 +1,910 @@
+// Structured tree ensemble
+// Owned Node/Tree/Ensemble types that every loader produces and every backend (simulation,
+// code generation, circuits) consumes, so arbitrary ensembles can be inspected and tested
+// instead of only the unrolled if-else compiled into `xgboost_predict`.
+// With the `serde` feature every type here (de)serializes; a deserialized ensemble is checked
+// like a constructed one (positive scale, every split feature within the feature count, positive
+// category units).
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt;
+
+use crate::commitment::Digest;
+use crate::fixed::{rescale_floor, rescale_round};
+use crate::objective::Objective;
+use crate::{fixed_add, fixed_le, PRECISION_MULTIPLIER};
+
+/// Set of category codes of a categorical split, one bit per category
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "Vec<u64>", into = "Vec<u64>"))]
+pub struct BitSet {
+    /// Bit `c % 64` of word `c / 64` is set for category `c`; no trailing zero words
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    /// Empty set
+    pub fn new() -> BitSet {
+        BitSet { words: Vec::new() }
+    }
+
+    /// Set from its 64-bit words, least significant bit first
+    pub fn from_words(mut words: Vec<u64>) -> BitSet {
+        while words.last() == Some(&0) {
+            words.pop();
+        }
+        BitSet { words }
+    }
+
+    /// The 64-bit words of the set (without trailing zero words)
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Add a category
+    pub fn insert(&mut self, category: u32) {
+        let word = category as usize / 64;
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        self.words[word] |= 1 << (category % 64);
+    }
+
+    /// Whether `category` is in the set
+    pub fn contains(&self, category: u32) -> bool {
+        self.words.get(category as usize / 64).is_some_and(|word| word >> (category % 64) & 1 == 1)
+    }
+
+    /// Whether a fixed-point feature value is `c * unit` for a category `c` in the set
+    ///
+    /// Negative values, values between two categories and `MISSING_VALUE` are in no set.
+    pub fn contains_value(&self, value: i64, unit: i64) -> bool {
+        value >= 0 && value % unit == 0 && u32::try_from(value / unit).is_ok_and(|category| self.contains(category))
+    }
+
+    /// Feature values in the set at `unit`: `c * unit` for every category `c` that fits an i64
+    pub fn values(&self, unit: i64) -> impl Iterator<Item = i64> + '_ {
+        self.iter().map_while(move |category| (category as i64).checked_mul(unit))
+    }
+
+    /// Categories in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.words.iter().enumerate().flat_map(|(index, &word)| word_categories(index, word))
+    }
+
+    /// Number of categories in the set
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Whether the set has no category
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// Categories of word `index` of a set, in increasing order
+fn word_categories(index: usize, word: u64) -> impl Iterator<Item = u32> {
+    // Indices are u64: the word past category 2^32 - 65 already ends at 2^32
+    let mut rest = word;
+    core::iter::from_fn(move || {
+        let bit = (rest != 0).then(|| rest.trailing_zeros() as u64)?;
+        rest &= rest - 1;
+        Some(index as u64 * 64 + bit)
+    })
+    .map(|category| u32::try_from(category).expect("categories are u32"))
+}
+
+impl FromIterator<u32> for BitSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(categories: I) -> Self {
+        let mut set = BitSet::new();
+        for category in categories {
+            set.insert(category);
+        }
+        set
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Vec<u64>> for BitSet {
+    fn from(words: Vec<u64>) -> Self {
+        BitSet::from_words(words)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<BitSet> for Vec<u64> {
+    fn from(set: BitSet) -> Self {
+        set.words
+    }
+}
+
+/// Decision tree node with fixed-point threshold and leaf values
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Node {
+    /// Leaf value (scaled)
+    Leaf(i64),
+    /// Go left when `features[feature] <= threshold`, right otherwise
+    Split {
+        feature: usize,
+        threshold: i64,
+        left: Box<Node>,
+        right: Box<Node>,
+    },
+    /// Go left when `features[feature]` is `c * unit` for a category `c` in `categories`, right
+    /// otherwise (XGBoost's partition-based categorical split; category codes are scaled like
+    /// any other feature value, so `unit` is the ensemble's scale)
+    Categorical {
+        feature: usize,
+        categories: BitSet,
+        unit: i64,
+        left: Box<Node>,
+        right: Box<Node>,
+    },
+}
+
+impl Node {
+    /// Build a split node
+    pub fn split(feature: usize, threshold: i64, left: Node, right: Node) -> Node {
+        Node::Split { feature, threshold, left: Box::new(left), right: Box::new(right) }
+    }
+
+    /// Build a categorical split node
+    pub fn categorical(feature: usize, categories: BitSet, unit: i64, left: Node, right: Node) -> Node {
+        assert!(unit > 0, "category unit must be positive");
+        Node::Categorical { feature, categories, unit, left: Box::new(left), right: Box::new(right) }
+    }
+
+    /// Whether a split node sends `features` to its left child (`None` for a leaf)
+    pub fn goes_left(&self, f: &[i64]) -> Option<bool> {
+        match self {
+            Node::Leaf(_) => None,
+            Node::Split { feature, threshold, .. } => Some(fixed_le(f[*feature], *threshold)),
+            Node::Categorical { feature, categories, unit, .. } => Some(categories.contains_value(f[*feature], *unit)),
+        }
+    }
+
+    /// Evaluate the subtree rooted at this node
+    pub fn eval(&self, f: &[i64]) -> i64 {
+        let mut node = self;
+        loop {
+            match node {
+                Node::Leaf(value) => return *value,
+                Node::Split { feature, threshold, left, right } => {
+                    node = if fixed_le(f[*feature], *threshold) { left } else { right };
+                }
+                Node::Categorical { feature, categories, unit, left, right } => {
+                    node = if categories.contains_value(f[*feature], *unit) { left } else { right };
+                }
+            }
+        }
+    }
+
+    /// Number of nodes in the subtree
+    pub fn num_nodes(&self) -> usize {
+        match self {
+            Node::Leaf(_) => 1,
+            Node::Split { left, right, .. } | Node::Categorical { left, right, .. } => {
+                1 + left.num_nodes() + right.num_nodes()
+            }
+        }
+    }
+
+    /// Number of edges on the longest root-to-leaf path
+    pub fn depth(&self) -> usize {
+        match self {
+            Node::Leaf(_) => 0,
+            Node::Split { left, right, .. } | Node::Categorical { left, right, .. } => {
+                1 + left.depth().max(right.depth())
+            }
+        }
+    }
+
+    /// Largest feature index used by a split, if any
+    pub fn max_feature(&self) -> Option<usize> {
+        match self {
+            Node::Leaf(_) => None,
+            Node::Split { feature, left, right, .. } | Node::Categorical { feature, left, right, .. } => {
+                Some((*feature).max(left.max_feature().unwrap_or(0)).max(right.max_feature().unwrap_or(0)))
+            }
+        }
+    }
+
+    /// Smallest category unit of a categorical split, if any
+    pub fn min_unit(&self) -> Option<i64> {
+        match self {
+            Node::Leaf(_) => None,
+            Node::Split { left, right, .. } => left.min_unit().into_iter().chain(right.min_unit()).min(),
+            Node::Categorical { unit, left, right, .. } => {
+                Some((*unit).min(left.min_unit().unwrap_or(i64::MAX)).min(right.min_unit().unwrap_or(i64::MAX)))
+            }
+        }
+    }
+
+    /// Copy of the subtree with thresholds and leaves transformed (category units are values at
+    /// the threshold scale, so they go through `threshold_fn` too)
+    pub fn map_values<T, L>(&self, threshold_fn: &T, leaf_fn: &L) -> Node
+    where
+        T: Fn(i64) -> i64,
+        L: Fn(i64) -> i64,
+    {
+        match self {
+            Node::Leaf(value) => Node::Leaf(leaf_fn(*value)),
+            Node::Split { feature, threshold, left, right } => Node::split(
+                *feature,
+                threshold_fn(*threshold),
+                left.map_values(threshold_fn, leaf_fn),
+                right.map_values(threshold_fn, leaf_fn),
+            ),
+            Node::Categorical { feature, categories, unit, left, right } => Node::categorical(
+                *feature,
+                categories.clone(),
+                threshold_fn(*unit),
+                left.map_values(threshold_fn, leaf_fn),
+                right.map_values(threshold_fn, leaf_fn),
+            ),
+        }
+    }
+}
+
+/// Error converting an ensemble to another scale
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RescaleError {
+    /// A category unit is no whole number of units at the new scale, so the category values
+    /// could not be matched exactly there
+    InexactCategoryUnit { unit: i64, from: i64, to: i64 },
+}
+
+impl fmt::Display for RescaleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RescaleError::InexactCategoryUnit { unit, from, to } => {
+                write!(f, "category unit {} at scale {} is not a whole number of units at scale {}", unit, from, to)
+            }
+        }
+    }
+}
+
+impl core::error::Error for RescaleError {}
+
+/// Check that every category unit of a subtree rescales exactly from `from` to `to`
+fn check_units(node: &Node, from: i64, to: i64) -> Result<(), RescaleError> {
+    match node {
+        Node::Leaf(_) => Ok(()),
+        Node::Split { left, right, .. } => check_units(left, from, to).and_then(|_| check_units(right, from, to)),
+        Node::Categorical { unit, left, right, .. } => {
+            let scaled = *unit as i128 * to as i128;
+            if scaled % from as i128 != 0 || scaled / from as i128 > i64::MAX as i128 {
+                return Err(RescaleError::InexactCategoryUnit { unit: *unit, from, to });
+            }
+            check_units(left, from, to).and_then(|_| check_units(right, from, to))
+        }
+    }
+}
+
+/// Single decision tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tree {
+    pub root: Node,
+}
+
+impl Tree {
+    /// Create a tree from its root node
+    pub fn new(root: Node) -> Tree {
+        Tree { root }
+    }
+
+    /// Evaluate the tree on a feature vector
+    pub fn eval(&self, f: &[i64]) -> i64 {
+        self.root.eval(f)
+    }
+
+    /// Number of nodes in the tree
+    pub fn num_nodes(&self) -> usize {
+        self.root.num_nodes()
+    }
+
+    /// Depth of the tree (a single leaf has depth 0)
+    pub fn depth(&self) -> usize {
+        self.root.depth()
+    }
+}
+
+/// How the tree outputs of an ensemble are combined into a prediction
+///
+/// Tree outputs are accumulated with `fixed_add`. Summing starts the accumulator at the base
+/// score; averaging starts it at 0, divides the final sum by the number of trees with floor
+/// division and then adds the base score. The remainder `sum - q * n` is always in `[0, n)`, so
+/// -7 averaged over 2 trees is -4 (not -3): this is what a circuit proves with a quotient and
+/// remainder witness, and every evaluator rounds the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Combiner {
+    /// Gradient boosting (XGBoost, LightGBM): base score plus the sum of the tree outputs
+    #[default]
+    Sum,
+    /// Random forest (sklearn): base score plus the floored mean of the tree outputs
+    Average,
+}
+
+impl Combiner {
+    /// Accumulator value before the first tree: the base score when summing, 0 when averaging
+    pub fn initial(self, base_score: i64) -> i64 {
+        match self {
+            Combiner::Sum => base_score,
+            Combiner::Average => 0,
+        }
+    }
+
+    /// Prediction from the accumulator after all `num_trees` tree outputs were added with `fixed_add`
+    pub fn finish(self, base_score: i64, accumulator: i64, num_trees: usize) -> i64 {
+        match self {
+            Combiner::Sum => accumulator,
+            Combiner::Average => fixed_add(base_score, accumulator.div_euclid(num_trees.max(1) as i64)),
+        }
+    }
+
+    /// Prediction from an exact i128 accumulator, clamped to the i64 range
+    ///
+    /// Agrees with `finish` whenever the accumulator fits in i64.
+    pub fn finish_wide(self, base_score: i64, accumulator: i128, num_trees: usize) -> i64 {
+        let prediction = match self {
+            Combiner::Sum => accumulator,
+            Combiner::Average => base_score as i128 + accumulator.div_euclid(num_trees.max(1) as i128),
+        };
+        prediction.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+    }
+
+    /// Combine tree outputs into a prediction
+    pub fn combine<I: IntoIterator<Item = i64>>(self, base_score: i64, tree_outputs: I) -> i64 {
+        let (accumulator, num_trees) = tree_outputs
+            .into_iter()
+            .fold((self.initial(base_score), 0), |(y, n), output| (fixed_add(y, output), n + 1));
+        self.finish(base_score, accumulator, num_trees)
+    }
+
+    /// Combine tree outputs with an exact i128 accumulator (see [`Accumulator::I128`])
+    pub fn combine_wide<I: IntoIterator<Item = i64>>(self, base_score: i64, tree_outputs: I) -> i64 {
+        let (accumulator, num_trees) = tree_outputs
+            .into_iter()
+            .fold((self.initial(base_score) as i128, 0), |(y, n), output| (y + output as i128, n + 1));
+        self.finish_wide(base_score, accumulator, num_trees)
+    }
+
+    /// Name used by the model formats ("sum" or "average")
+    pub fn name(self) -> &'static str {
+        match self {
+            Combiner::Sum => "sum",
+            Combiner::Average => "average",
+        }
+    }
+}
+
+/// Integer width of the running sum of tree outputs
+///
+/// The i64 accumulator saturates at every `fixed_add`, which is exact as long as no partial sum
+/// leaves the i64 range. With hundreds of large leaves a partial sum can saturate even when the
+/// final prediction would fit, and the saturated value then depends on the tree order. The i128
+/// accumulator sums exactly (i64 leaves over fewer than 2^32 trees cannot overflow it) and only
+/// clamps the prediction to i64 at the end. `ValueBounds::recommended_accumulator` tells which one
+/// a model needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Accumulator {
+    /// Saturating i64 sum, what the circuits prove
+    #[default]
+    I64,
+    /// Exact i128 sum, clamped to i64 after the last tree
+    I128,
+}
+
+impl Accumulator {
+    /// Name used by trace exports ("i64" or "i128")
+    pub fn name(self) -> &'static str {
+        match self {
+            Accumulator::I64 => "i64",
+            Accumulator::I128 => "i128",
+        }
+    }
+
+    /// Parse a name written by `Accumulator::name`
+    pub fn from_name(name: &str) -> Option<Accumulator> {
+        match name {
+            "i64" => Some(Accumulator::I64),
+            "i128" => Some(Accumulator::I128),
+            _ => None,
+        }
+    }
+
+    /// Width of the accumulator in bits
+    pub fn bits(self) -> usize {
+        match self {
+            Accumulator::I64 => 64,
+            Accumulator::I128 => 128,
+        }
+    }
+
+    /// Combine tree outputs into a prediction with this accumulator
+    pub fn combine<I: IntoIterator<Item = i64>>(self, combiner: Combiner, base_score: i64, tree_outputs: I) -> i64 {
+        match self {
+            Accumulator::I64 => combiner.combine(base_score, tree_outputs),
+            Accumulator::I128 => combiner.combine_wide(base_score, tree_outputs),
+        }
+    }
+}
+
+/// Tree ensemble: the prediction is the base score plus the tree outputs combined by a
+/// [`Combiner`] (by default their saturating sum)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "EnsembleFields"))]
+pub struct Ensemble {
+    trees: Vec<Tree>,
+    num_features: usize,
+    scale: i64,
+    base_score: i64,
+    combiner: Combiner,
+    accumulator: Accumulator,
+    objective: Objective,
+    name: String,
+    version: String,
+    training_hash: Option<Digest>,
+    feature_names: Vec<String>,
+}
+
+/// Serialized form of an `Ensemble`, validated on the way in
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct EnsembleFields {
+    trees: Vec<Tree>,
+    num_features: usize,
+    scale: i64,
+    base_score: i64,
+    combiner: Combiner,
+    accumulator: Accumulator,
+    objective: Objective,
+    name: String,
+    version: String,
+    training_hash: Option<Digest>,
+    #[serde(default)]
+    feature_names: Vec<String>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<EnsembleFields> for Ensemble {
+    type Error = String;
+
+    fn try_from(fields: EnsembleFields) -> Result<Self, String> {
+        if fields.scale <= 0 {
+            return Err(alloc::format!("fixed-point scale must be positive, got {}", fields.scale));
+        }
+        let used = fields.trees.iter().filter_map(|tree| tree.root.max_feature()).max();
+        if let Some(feature) = used.filter(|&feature| feature >= fields.num_features) {
+            return Err(alloc::format!("split on feature {} of a {}-feature model", feature, fields.num_features));
+        }
+        let unit = fields.trees.iter().filter_map(|tree| tree.root.min_unit()).min();
+        if let Some(unit) = unit.filter(|&unit| unit <= 0) {
+            return Err(alloc::format!("category unit must be positive, got {}", unit));
+        }
+        if !fields.feature_names.is_empty() && fields.feature_names.len() != fields.num_features {
+            let (names, features) = (fields.feature_names.len(), fields.num_features);
+            return Err(alloc::format!("{} feature names for a {}-feature model", names, features));
+        }
+        let EnsembleFields {
+            trees, num_features, scale, base_score, combiner, accumulator, objective, name, version, training_hash,
+            feature_names,
+        } = fields;
+        Ok(Ensemble {
+            trees, num_features, scale, base_score, combiner, accumulator, objective, name, version, training_hash,
+            feature_names,
+        })
+    }
+}
+
+impl Ensemble {
+    /// Create an ensemble from trees whose values are expressed at `scale`
+    ///
+    /// The feature count is inferred from the largest feature index used by any split.
+    pub fn new(trees: Vec<Tree>, scale: i64) -> Ensemble {
+        assert!(scale > 0, "fixed-point scale must be positive");
+        let num_features = trees
+            .iter()
+            .filter_map(|tree| tree.root.max_feature())
+            .map(|feature| feature + 1)
+            .max()
+            .unwrap_or(0);
+
+        Ensemble {
+            trees,
+            num_features,
+            scale,
+            base_score: 0,
+            combiner: Combiner::Sum,
+            accumulator: Accumulator::I64,
+            objective: Objective::SquaredError,
+            name: String::new(),
+            version: String::new(),
+            training_hash: None,
+            feature_names: Vec::new(),
+        }
+    }
+
+    /// Override the number of features (splits only reveal the features they use)
+    pub fn with_num_features(mut self, num_features: usize) -> Self {
+        self.num_features = self.num_features.max(num_features);
+        if !self.feature_names.is_empty() {
+            let named = self.feature_names.len();
+            self.feature_names.extend((named..self.num_features).map(|index| alloc::format!("f{}", index)));
+        }
+        self
+    }
+
+    /// Set the initial prediction (XGBoost's `base_score`) the tree outputs are added to
+    ///
+    /// XGBoost dumps do not record it, so loaders leave it at 0 unless the format carries it.
+    pub fn with_base_score(mut self, base_score: i64) -> Self {
+        self.base_score = base_score;
+        self
+    }
+
+    /// Set how the tree outputs are combined (averaging for random forests)
+    pub fn with_combiner(mut self, combiner: Combiner) -> Self {
+        self.combiner = combiner;
+        self
+    }
+
+    /// Set the width of the running sum of tree outputs (see [`Accumulator`])
+    pub fn with_accumulator(mut self, accumulator: Accumulator) -> Self {
+        self.accumulator = accumulator;
+        self
+    }
+
+    /// Set the training objective, whose inverse link `predict_response` applies to the margin
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    /// Name the model is published under (see [`Ensemble::model_info`])
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Version of the model, e.g. a release number or training run id
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Hash of the training data or configuration the model was fitted from
+    pub fn with_training_hash(mut self, training_hash: Digest) -> Self {
+        self.training_hash = Some(training_hash);
+        self
+    }
+
+    /// Name the features, in input order (see [`Ensemble::predict_named`])
+    ///
+    /// The feature count grows to `names.len()` if needed; features past the end of `names` are
+    /// named `f<index>`. Panics if two features get the same name.
+    pub fn with_feature_names(mut self, mut names: Vec<String>) -> Self {
+        self.num_features = self.num_features.max(names.len());
+        names.extend((names.len()..self.num_features).map(|index| alloc::format!("f{}", index)));
+        for (index, name) in names.iter().enumerate() {
+            assert!(!names[..index].contains(name), "feature name '{}' is used twice", name);
+        }
+        self.feature_names = names;
+        self
+    }
+
+    /// Trees of the ensemble
+    pub fn trees(&self) -> &[Tree] {
+        &self.trees
+    }
+
+    /// Number of trees in the ensemble
+    pub fn num_trees(&self) -> usize {
+        self.trees.len()
+    }
+
+    /// Minimum number of features a prediction input must provide
+    pub fn num_features(&self) -> usize {
+        self.num_features
+    }
+
+    /// Fixed-point scale of thresholds, leaves and predictions
+    pub fn scale(&self) -> i64 {
+        self.scale
+    }
+
+    /// Initial prediction before any tree is added (at the ensemble's scale)
+    pub fn base_score(&self) -> i64 {
+        self.base_score
+    }
+
+    /// How the tree outputs are combined
+    pub fn combiner(&self) -> Combiner {
+        self.combiner
+    }
+
+    /// Width of the running sum of tree outputs
+    pub fn accumulator(&self) -> Accumulator {
+        self.accumulator
+    }
+
+    /// Training objective (`Objective::SquaredError` unless set with `with_objective`)
+    pub fn objective(&self) -> Objective {
+        self.objective
+    }
+
+    /// Model name (empty unless set with `with_name`)
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Model version (empty unless set with `with_version`)
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Training data hash, if one was recorded
+    pub fn training_hash(&self) -> Option<&Digest> {
+        self.training_hash.as_ref()
+    }
+
+    /// Feature names in input order (empty unless set with `with_feature_names`)
+    pub fn feature_names(&self) -> &[String] {
+        &self.feature_names
+    }
+
+    /// Evaluate the ensemble on a feature vector
+    ///
+    /// # Arguments
+    /// * `features` - Input feature vector as slice of i64 values (at the ensemble's scale)
+    ///
+    /// # Returns
+    /// * `i64` - Prediction result, same semantics as `xgboost_predict`
+    ///
+    /// Panics if `features` is shorter than `num_features`; see `try_eval`.
+    pub fn eval(&self, features: &[i64]) -> i64 {
+        assert!(features.len() >= self.num_features,
+                "Expected at least {} features, got {}", self.num_features, features.len());
+
+        self.accumulator.combine(self.combiner, self.base_score, self.trees.iter().map(|tree| tree.eval(features)))
+    }
+
+    /// Same ensemble (scale, base score, combiner, accumulator, objective and metadata) with other trees
+    pub(crate) fn with_trees(&self, trees: Vec<Tree>) -> Ensemble {
+        Ensemble {
+            trees,
+            num_features: self.num_features,
+            scale: self.scale,
+            base_score: self.base_score,
+            combiner: self.combiner,
+            accumulator: self.accumulator,
+            objective: self.objective,
+            name: self.name.clone(),
+            version: self.version.clone(),
+            training_hash: self.training_hash,
+            feature_names: self.feature_names.clone(),
+        }
+    }
+
+    /// Alias of [`Ensemble::eval`]
+    pub fn predict(&self, features: &[i64]) -> i64 {
+        self.eval(features)
+    }
+
+    /// Convert the ensemble to another fixed-point scale
+    ///
+    /// Thresholds are floored so that, for inputs quantized at the new scale, every split takes
+    /// the same branch as the original model on the same real value. Leaves are rounded to nearest.
+    /// Category units must convert exactly, since category values are matched exactly.
+    ///
+    /// # Arguments
+    /// * `scale` - New fixed-point scale
+    ///
+    /// # Returns
+    /// * `Result<Ensemble, RescaleError>` - The converted ensemble, or the first category unit
+    ///   that is no whole number of units at `scale`
+    pub fn rescale(&self, scale: i64) -> Result<Ensemble, RescaleError> {
+        let from = self.scale;
+        assert!(scale > 0, "fixed-point scale must be positive");
+        for tree in &self.trees {
+            check_units(&tree.root, from, scale)?;
+        }
+        let trees = self
+            .trees
+            .iter()
+            .map(|tree| {
+                Tree::new(tree.root.map_values(
+                    &|threshold| rescale_floor(threshold, from, scale),
+                    &|leaf| rescale_round(leaf, from, scale),
+                ))
+            })
+            .collect();
+
+        Ok(Ensemble {
+            trees,
+            num_features: self.num_features,
+            scale,
+            base_score: rescale_round(self.base_score, from, scale),
+            combiner: self.combiner,
+            accumulator: self.accumulator,
+            objective: self.objective,
+            name: self.name.clone(),
+            version: self.version.clone(),
+            training_hash: self.training_hash,
+            feature_names: self.feature_names.clone(),
+        })
+    }
+}
+
+impl Default for Ensemble {
+    fn default() -> Self {
+        Ensemble::new(Vec::new(), PRECISION_MULTIPLIER)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stump(feature: usize, threshold: i64, left: i64, right: i64) -> Tree {
+        Tree::new(Node::split(feature, threshold, Node::Leaf(left), Node::Leaf(right)))
+    }
+
+    #[test]
+    fn test_ensemble_eval() {
+        let deep = Tree::new(Node::split(
+            0,
+            10,
+            Node::split(2, -5, Node::Leaf(1), Node::Leaf(2)),
+            Node::Leaf(3),
+        ));
+        let ensemble = Ensemble::new(vec![deep, stump(1, 0, 100, 200)], PRECISION_MULTIPLIER);
+
+        assert_eq!(ensemble.num_features(), 3);
+        assert_eq!(ensemble.trees()[0].depth(), 2);
+        assert_eq!(ensemble.trees()[0].num_nodes(), 5);
+        assert_eq!(ensemble.eval(&[10, 0, -5]), 101);
+        assert_eq!(ensemble.eval(&[10, 1, -4]), 202);
+        assert_eq!(ensemble.eval(&[11, 1, 0]), 203);
+
+        let biased = ensemble.with_base_score(-50);
+        assert_eq!(biased.eval(&[10, 0, -5]), 51);
+        assert_eq!(biased.rescale(PRECISION_MULTIPLIER / 10).unwrap().base_score(), -5);
+        assert_eq!(Ensemble::new(Vec::new(), 1).with_base_score(7).eval(&[]), 7);
+    }
+
+    #[test]
+    fn test_categorical_split() {
+        let categories: BitSet = [1, 4, 70].into_iter().collect();
+        assert_eq!(categories.words(), &[0b10010, 1 << 6]);
+        assert_eq!(categories.iter().collect::<Vec<_>>(), vec![1, 4, 70]);
+        assert_eq!(BitSet::from_words(vec![0b10, 0]), [1].into_iter().collect());
+
+        let unit = PRECISION_MULTIPLIER;
+        let tree = Tree::new(Node::categorical(0, categories, unit, Node::Leaf(1), Node::split(1, 0, Node::Leaf(2), Node::Leaf(3))));
+        assert_eq!(tree.eval(&[4 * unit, 5]), 1);
+        assert_eq!(tree.eval(&[70 * unit, 5]), 1);
+        // Other categories, values between categories, negative and missing values go right
+        for value in [0, 2 * unit, 4 * unit + 1, -unit, crate::MISSING_VALUE] {
+            assert_eq!(tree.eval(&[value, 0]), 2, "{}", value);
+        }
+
+        let rescaled = Ensemble::new(vec![tree], unit).rescale(1000).unwrap();
+        assert_eq!(rescaled.num_features(), 2);
+        let root = &rescaled.trees()[0].root;
+        assert_eq!(root.goes_left(&[4000, 5]), Some(true));
+        assert_eq!(root.goes_left(&[4001, 5]), Some(false));
+
+        // Units rescale exactly or not at all: 1 at scale 10 would be 2.5 at 25 and 0.1 at 1
+        let coded = |unit| {
+            let split = Node::categorical(0, [2].into_iter().collect(), unit, Node::Leaf(10), Node::Leaf(20));
+            Ensemble::new(vec![Tree::new(split)], 10)
+        };
+        let rescaled = coded(2).rescale(25).unwrap();
+        assert_eq!(rescaled.eval(&[10]), 25);
+        assert_eq!(rescaled.eval(&[5]), 50);
+        for to in [25, 1] {
+            assert_eq!(coded(1).rescale(to), Err(RescaleError::InexactCategoryUnit { unit: 1, from: 10, to }));
+        }
+    }
+
+    #[test]
+    fn test_bitset_high_categories() {
+        // The last word of a set ends at 2^32
+        let last = word_categories((1 << 26) - 1, (1 << 63) | 1).collect::<Vec<_>>();
+        assert_eq!(last, alloc::vec![u32::MAX - 63, u32::MAX]);
+        assert_eq!(word_categories(0, 0b100).collect::<Vec<_>>(), alloc::vec![2]);
+        assert_eq!(word_categories(3, 0).count(), 0);
+    }
+
+    #[test]
+    #[ignore = "allocates the 512 MiB of words up to category 2^32"]
+    fn test_bitset_all_categories() {
+        let mut words = alloc::vec![0u64; 1 << 26];
+        words[(1 << 26) - 1] = (1 << 63) | 1;
+        words[0] = 0b100;
+        let categories = BitSet::from_words(words);
+        assert_eq!(categories.iter().collect::<Vec<_>>(), alloc::vec![2, u32::MAX - 63, u32::MAX]);
+        assert_eq!(categories.len(), 3);
+    }
+
+    #[test]
+    fn test_sum_saturates_like_fixed_add() {
+        let ensemble = Ensemble::new(vec![stump(0, 0, i64::MAX, 0), stump(0, 0, 1, 0)], 1);
+        assert_eq!(ensemble.eval(&[0]), i64::MAX);
+        assert_eq!(Ensemble::default().eval(&[]), 0);
+    }
+
+    #[test]
+    fn test_i128_accumulator_sums_exactly() {
+        let half = 1i64 << 62;
+        let trees = vec![stump(0, 0, half, 0), stump(0, 0, half, 0), stump(0, 0, -half, 0)];
+        let ensemble = Ensemble::new(trees, 1);
+        // The first two trees saturate the i64 sum at 2^63 - 1, so the third leaves 2^62 - 1
+        assert_eq!(ensemble.eval(&[0]), half - 1);
+        let wide = ensemble.clone().with_accumulator(Accumulator::I128);
+        assert_eq!(wide.eval(&[0]), half);
+        assert_eq!(wide.rescale(1).unwrap().accumulator(), Accumulator::I128);
+
+        // Only the prediction is clamped; averaging divides the exact sum
+        let wide = Ensemble::new(vec![stump(0, 0, i64::MAX, 0), stump(0, 0, 1, 0)], 1)
+            .with_accumulator(Accumulator::I128);
+        assert_eq!(wide.eval(&[0]), i64::MAX);
+        assert_eq!(wide.with_combiner(Combiner::Average).eval(&[0]), 1 << 62);
+        assert_eq!(Accumulator::from_name(Accumulator::I128.name()), Some(Accumulator::I128));
+    }
+
+    #[test]
+    fn test_average_floors_the_mean() {
+        let forest = Ensemble::new(vec![stump(0, 0, -3, 6), stump(0, 0, -4, 1)], 1).with_combiner(Combiner::Average);
+        // (-3 - 4) / 2 = -3.5 floors to -4 (remainder 1); (6 + 1) / 2 = 3.5 floors to 3
+        assert_eq!(forest.eval(&[0]), -4);
+        assert_eq!(forest.eval(&[1]), 3);
+        assert_eq!(forest.clone().with_base_score(10).eval(&[0]), 6);
+        assert_eq!(forest.rescale(1).unwrap().combiner(), Combiner::Average);
+        assert_eq!(Ensemble::default().with_combiner(Combiner::Average).with_base_score(2).eval(&[]), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected at least 2 features")]
+    fn test_eval_checks_feature_count() {
+        Ensemble::new(vec![stump(1, 0, 1, 2)], 1).eval(&[0]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let categories: BitSet = [3, 70].into_iter().collect();
+        let categorical = Node::categorical(2, categories, 10, Node::Leaf(1), Node::Leaf(2));
+        let tree = Tree::new(Node::split(0, 5, categorical, Node::Leaf(3)));
+        let model = Ensemble::new(vec![tree], 100)
+            .with_num_features(4)
+            .with_combiner(Combiner::Average)
+            .with_name("rain")
+            .with_training_hash([9; 32]);
+        let json = serde_json::to_string(&model).unwrap();
+        assert_eq!(serde_json::from_str::<Ensemble>(&json).unwrap(), model);
+        assert!(json.contains(r#""categories":[8,64]"#), "{}", json);
+
+        let builtin = crate::builtin_model();
+        assert_eq!(serde_json::from_str::<Ensemble>(&serde_json::to_string(&builtin).unwrap()).unwrap(), builtin);
+
+        let error = serde_json::from_str::<Ensemble>(&json.replace(r#""scale":100"#, r#""scale":0"#)).unwrap_err();
+        assert!(error.to_string().contains("scale must be positive"), "{}", error);
+        let narrow = json.replace(r#""num_features":4"#, r#""num_features":2"#);
+        let error = serde_json::from_str::<Ensemble>(&narrow).unwrap_err();
+        assert!(error.to_string().contains("split on feature 2 of a 2-feature model"), "{}", error);
+        for unit in ["0", "-10"] {
+            let error = serde_json::from_str::<Ensemble>(&json.replace(r#""unit":10"#, &format!(r#""unit":{}"#, unit)))
+                .unwrap_err();
+            assert!(error.to_string().contains("category unit must be positive"), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,266 @@
+// Evaluator equivalence
+// The branching evaluator (`Node::eval`) and the select-based one (`Node::eval_numeric`, which
+// `eval_oblivious` and the circuits use) are separate code paths over the same tree IR. Both look
+// at the input only through the tree's tests, `f[i] <= t` and category membership, so every input
+// falls into one of finitely many classes that all tests answer the same way. Per feature, the
+// sorted thresholds of a tree cut the line into cells; each category value is a class of its own.
+// `check_equivalence` takes one representative per class (the upper end of each cell, so a `<`
+// where the IR means `<=` is caught, every category value and a non-member per cell) and runs
+// both evaluators on every combination of the features a tree tests. For threshold trees this
+// covers every outcome vector the tests can produce, so agreement on all cases proves the two
+// evaluators equal on all inputs. The number of combinations is the product of the class counts,
+// so the check is bounded: a tree above the case limit is reported instead of enumerated.
+
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+use core::fmt;
+
+use crate::ensemble::{Ensemble, Node};
+
+/// Default bound on the cases enumerated for one tree
+pub const DEFAULT_CASE_LIMIT: u64 = 1 << 22;
+
+/// Input on which the evaluator under test disagrees with `Node::eval`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample {
+    pub tree: usize,
+    pub features: Vec<i64>,
+    /// Output of `Node::eval`
+    pub expected: i64,
+    /// Output of the evaluator under test
+    pub got: i64,
+}
+
+/// Outcome of the check for one tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeEquivalence {
+    /// Both evaluators agree on every class of inputs
+    Equivalent { cases: u64 },
+    /// More cases than the limit; not checked
+    TooLarge { cases: u64 },
+    /// The first disagreement found
+    Counterexample(Counterexample),
+}
+
+/// Per-tree outcome of `check_equivalence`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EquivalenceReport {
+    pub trees: Vec<TreeEquivalence>,
+}
+
+impl EquivalenceReport {
+    /// Whether every tree was checked and found equivalent
+    pub fn is_proved(&self) -> bool {
+        self.trees.iter().all(|tree| matches!(tree, TreeEquivalence::Equivalent { .. }))
+    }
+
+    /// Cases run over all checked trees
+    pub fn cases(&self) -> u64 {
+        self.trees
+            .iter()
+            .map(|tree| match tree {
+                TreeEquivalence::Equivalent { cases } => *cases,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// First disagreement, if any
+    pub fn counterexample(&self) -> Option<&Counterexample> {
+        self.trees.iter().find_map(|tree| match tree {
+            TreeEquivalence::Counterexample(counterexample) => Some(counterexample),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for EquivalenceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(counterexample) = self.counterexample() {
+            return write!(
+                f,
+                "tree {} differs: expected {}, got {}",
+                counterexample.tree, counterexample.expected, counterexample.got
+            );
+        }
+        let skipped = self.trees.iter().filter(|tree| matches!(tree, TreeEquivalence::TooLarge { .. })).count();
+        if skipped > 0 {
+            return write!(f, "{} of {} trees exceed the case limit", skipped, self.trees.len());
+        }
+        write!(f, "equivalent on all {} trees ({} cases)", self.trees.len(), self.cases())
+    }
+}
+
+/// Compare `eval` with `Node::eval` on every input class of every tree of `model`
+///
+/// # Arguments
+/// * `model` - Ensemble whose trees are checked
+/// * `limit` - Largest number of cases to enumerate for one tree
+/// * `eval` - Evaluator under test, called with a tree's root and a feature vector
+///
+/// # Returns
+/// * `EquivalenceReport` - Per tree: equivalent, too large to enumerate, or a counterexample
+pub fn check_equivalence<F>(model: &Ensemble, limit: u64, eval: F) -> EquivalenceReport
+where
+    F: Fn(&Node, &[i64]) -> i64,
+{
+    let trees = model
+        .trees()
+        .iter()
+        .enumerate()
+        .map(|(tree, root)| check_tree(tree, &root.root, model.num_features(), limit, &eval))
+        .collect();
+    EquivalenceReport { trees }
+}
+
+fn check_tree<F>(tree: usize, root: &Node, num_features: usize, limit: u64, eval: &F) -> TreeEquivalence
+where
+    F: Fn(&Node, &[i64]) -> i64,
+{
+    let classes: Vec<(usize, Vec<i64>)> = representatives(root).into_iter().collect();
+    let cases = classes
+        .iter()
+        .try_fold(1u64, |cases, (_, values)| cases.checked_mul(values.len() as u64))
+        .unwrap_or(u64::MAX);
+    if cases > limit {
+        return TreeEquivalence::TooLarge { cases };
+    }
+
+    // Odometer over one representative per tested feature
+    let mut digits = vec![0; classes.len()];
+    let mut features = vec![0; num_features];
+    loop {
+        for ((feature, values), &digit) in classes.iter().zip(&digits) {
+            features[*feature] = values[digit];
+        }
+        let (expected, got) = (root.eval(&features), eval(root, &features));
+        if expected != got {
+            return TreeEquivalence::Counterexample(Counterexample { tree, features, expected, got });
+        }
+        let Some(position) = digits.iter().zip(&classes).position(|(&digit, (_, values))| digit + 1 < values.len())
+        else {
+            return TreeEquivalence::Equivalent { cases };
+        };
+        digits[position] += 1;
+        digits[..position].fill(0);
+    }
+}
+
+/// One value per input class of each feature the tree tests
+fn representatives(root: &Node) -> BTreeMap<usize, Vec<i64>> {
+    let mut thresholds: BTreeMap<usize, Vec<i64>> = BTreeMap::new();
+    let mut members: BTreeMap<usize, Vec<i64>> = BTreeMap::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        match node {
+            Node::Leaf(_) => {}
+            Node::Split { feature, threshold, left, right } => {
+                thresholds.entry(*feature).or_default().push(*threshold);
+                members.entry(*feature).or_default();
+                stack.extend([&**left, &**right]);
+            }
+            Node::Categorical { feature, categories, unit, left, right } => {
+                thresholds.entry(*feature).or_default();
+                members.entry(*feature).or_default().extend(categories.values(*unit));
+                stack.extend([&**left, &**right]);
+            }
+        }
+    }
+
+    thresholds
+        .into_iter()
+        .zip(members.into_values())
+        .map(|((feature, mut cuts), mut members)| {
+            cuts.sort_unstable();
+            cuts.dedup();
+            members.sort_unstable();
+            members.dedup();
+            // Upper ends of the cells (-inf, t1], (t1, t2], ..., (tk, +inf)
+            let mut ends = cuts.clone();
+            ends.push(cuts.last().map_or(i64::MAX, |&last| last.saturating_add(1)));
+            ends.dedup();
+            let mut values = Vec::with_capacity(ends.len() + members.len());
+            let mut low = i64::MIN;
+            for &end in &ends {
+                // A non-member of the cell, searched down from its upper end
+                let member = |value: &i64| members.binary_search(value).is_ok();
+                if let Some(value) = (0..=members.len() as i64)
+                    .filter_map(|step| end.checked_sub(step))
+                    .take_while(|&value| value >= low)
+                    .find(|value| !member(value))
+                {
+                    values.push(value);
+                }
+                low = end.saturating_add(1);
+            }
+            values.extend(&members);
+            values.sort_unstable();
+            values.dedup();
+            (feature, values)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin_model;
+    use crate::ensemble::{BitSet, Tree};
+
+    /// Select-based evaluation with a strict comparison, as a refactor might introduce
+    fn strict_oblivious(node: &Node, f: &[i64]) -> i64 {
+        match node {
+            Node::Leaf(value) => *value,
+            Node::Split { feature, threshold, left, right } => {
+                let b = (f[*feature] < *threshold) as i64;
+                b * strict_oblivious(left, f) + (1 - b) * strict_oblivious(right, f)
+            }
+            Node::Categorical { .. } => node.eval_oblivious(f),
+        }
+    }
+
+    #[test]
+    fn test_builtin_evaluators_are_equivalent() {
+        let model = builtin_model();
+        let report = check_equivalence(&model, DEFAULT_CASE_LIMIT, |root, f| root.eval_oblivious(f));
+        assert!(report.is_proved(), "{}", report);
+        // The product of the cell counts of each tree
+        assert_eq!(report.cases(), 9216 + 6912 + 128 + 40960 + 4608 + 384 + 497664 + 3456 + 192 + 51840);
+
+        let wide = check_equivalence(&model, DEFAULT_CASE_LIMIT, |root, f| {
+            let f: Vec<i128> = f.iter().map(|&value| value as i128).collect();
+            root.eval_numeric(&f) as i64
+        });
+        assert!(wide.is_proved());
+    }
+
+    #[test]
+    fn test_finds_strict_comparison() {
+        let tree = Tree::new(Node::split(0, 5, Node::split(1, -2, Node::Leaf(1), Node::Leaf(2)), Node::Leaf(3)));
+        let model = Ensemble::new(vec![tree], 1);
+        let report = check_equivalence(&model, DEFAULT_CASE_LIMIT, strict_oblivious);
+        let counterexample = report.counterexample().unwrap();
+        assert_eq!((counterexample.expected, counterexample.got), (1, 3));
+        // Both features at their threshold: the first case enumerated
+        assert_eq!(counterexample.features, vec![5, -2]);
+        assert_eq!(report.to_string(), "tree 0 differs: expected 1, got 3");
+        assert!(!report.is_proved());
+
+        // Two cells per feature
+        assert_eq!(check_equivalence(&model, 3, strict_oblivious).trees, vec![TreeEquivalence::TooLarge { cases: 4 }]);
+    }
+
+    #[test]
+    fn test_categorical_classes() {
+        let categories: BitSet = [0, 2].into_iter().collect();
+        let inner = Node::categorical(0, categories, 10, Node::Leaf(7), Node::Leaf(8));
+        let root = Node::split(0, 15, inner, Node::split(1, 0, Node::Leaf(9), Node::Leaf(4)));
+        // f0: category values 0 and 20, a non-member at or below 15 and one above
+        assert_eq!(representatives(&root)[&0], vec![0, 15, 16, 20]);
+        assert_eq!(representatives(&root)[&1], vec![0, 1]);
+
+        let model = Ensemble::new(vec![Tree::new(root)], 10);
+        let report = check_equivalence(&model, DEFAULT_CASE_LIMIT, |root, f| root.eval_oblivious(f));
+        assert_eq!(report.trees, vec![TreeEquivalence::Equivalent { cases: 8 }]);
+        assert_eq!(report.to_string(), "equivalent on all 1 trees (8 cases)");
+    }
+}
@@ -0,0 +1,189 @@
+// Dataset evaluation
+// Accuracy of the fixed-point predictions on labelled data: mean absolute error, root mean square
+// error and bias (mean signed error) against the labels, in real units. When the float model's
+// own predictions are given too, the same statistics for the float model and for the difference
+// between the two show how much of the error is quantization loss rather than model error.
+// Needs `std` for the square root of the RMSE.
+
+use alloc::{string::ToString, vec};
+use core::fmt;
+
+use crate::json::JsonValue;
+
+/// Error statistics of a series of (prediction - target) differences
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ErrorStats {
+    pub count: usize,
+    sum: f64,
+    sum_abs: f64,
+    sum_squares: f64,
+    /// Largest |difference|
+    pub max_abs: f64,
+}
+
+impl ErrorStats {
+    /// Record one difference
+    pub fn add(&mut self, error: f64) {
+        self.count += 1;
+        self.sum += error;
+        self.sum_abs += error.abs();
+        self.sum_squares += error * error;
+        self.max_abs = self.max_abs.max(error.abs());
+    }
+
+    fn mean(&self, total: f64) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            total / self.count as f64
+        }
+    }
+
+    /// Mean absolute error
+    pub fn mae(&self) -> f64 {
+        self.mean(self.sum_abs)
+    }
+
+    /// Root mean square error
+    pub fn rmse(&self) -> f64 {
+        self.mean(self.sum_squares).sqrt()
+    }
+
+    /// Mean signed error (positive: predictions too high)
+    pub fn bias(&self) -> f64 {
+        self.mean(self.sum)
+    }
+
+    /// Machine-readable form
+    pub fn to_json(&self) -> JsonValue {
+        let float = |value: f64| JsonValue::Number(value.to_string());
+        JsonValue::Object(vec![
+            ("count".to_string(), JsonValue::Number(self.count.to_string())),
+            ("mae".to_string(), float(self.mae())),
+            ("rmse".to_string(), float(self.rmse())),
+            ("bias".to_string(), float(self.bias())),
+            ("max_abs".to_string(), float(self.max_abs)),
+        ])
+    }
+}
+
+impl fmt::Display for ErrorStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "MAE {:.6}, RMSE {:.6}, bias {:+.6}, max {:.6} ({} rows)",
+            self.mae(),
+            self.rmse(),
+            self.bias(),
+            self.max_abs,
+            self.count
+        )
+    }
+}
+
+/// Accuracy of fixed-point predictions against labels, and against the float model if given
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    /// Fixed-point scale of the predictions
+    pub scale: i64,
+    /// Fixed-point prediction - label
+    pub fixed: ErrorStats,
+    /// Float reference prediction - label, over the rows that have a reference
+    pub reference: ErrorStats,
+    /// Fixed-point prediction - float reference prediction
+    pub delta: ErrorStats,
+}
+
+impl Evaluation {
+    /// Empty evaluation of predictions at `scale`
+    pub fn new(scale: i64) -> Evaluation {
+        let empty = ErrorStats::default();
+        Evaluation { scale, fixed: empty, reference: empty, delta: empty }
+    }
+
+    /// Record one labelled row
+    ///
+    /// # Arguments
+    /// * `prediction` - Fixed-point prediction (at the evaluation's scale)
+    /// * `label` - True value, in real units
+    /// * `reference` - Float model's prediction for the row, if known
+    pub fn add(&mut self, prediction: i64, label: f64, reference: Option<f64>) {
+        let prediction = prediction as f64 / self.scale as f64;
+        self.fixed.add(prediction - label);
+        if let Some(reference) = reference {
+            self.reference.add(reference - label);
+            self.delta.add(prediction - reference);
+        }
+    }
+
+    /// Evaluate a model's predictions over labelled samples
+    ///
+    /// # Arguments
+    /// * `predictions` - Fixed-point predictions at `scale`
+    /// * `labels` - One label per prediction
+    /// * `references` - Float predictions, one per prediction, if available
+    pub fn from_predictions(scale: i64, predictions: &[i64], labels: &[f64], references: Option<&[f64]>) -> Evaluation {
+        assert_eq!(predictions.len(), labels.len(), "one label per prediction");
+        let mut evaluation = Evaluation::new(scale);
+        for (index, (&prediction, &label)) in predictions.iter().zip(labels).enumerate() {
+            evaluation.add(prediction, label, references.map(|references| references[index]));
+        }
+        evaluation
+    }
+
+    /// Machine-readable form (`reference` and `delta` are null without reference predictions)
+    pub fn to_json(&self) -> JsonValue {
+        let optional = |stats: &ErrorStats| if stats.count == 0 { JsonValue::Null } else { stats.to_json() };
+        JsonValue::Object(vec![
+            ("fixed".to_string(), self.fixed.to_json()),
+            ("reference".to_string(), optional(&self.reference)),
+            ("delta".to_string(), optional(&self.delta)),
+        ])
+    }
+}
+
+impl fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fixed point vs labels: {}", self.fixed)?;
+        if self.reference.count > 0 {
+            write!(f, "\nFloat vs labels:       {}", self.reference)?;
+            write!(f, "\nFixed vs float:        {}", self.delta)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_stats() {
+        let mut stats = ErrorStats::default();
+        assert_eq!((stats.mae(), stats.rmse(), stats.bias()), (0.0, 0.0, 0.0));
+        for error in [3.0, -4.0, 1.0] {
+            stats.add(error);
+        }
+        assert_eq!(stats.count, 3);
+        assert!((stats.mae() - 8.0 / 3.0).abs() < 1e-12);
+        assert!((stats.rmse() - (26.0f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert_eq!(stats.bias(), 0.0);
+        assert_eq!(stats.max_abs, 4.0);
+    }
+
+    #[test]
+    fn test_evaluation() {
+        // Predictions 1.5 and 0.25 at scale 100, labels 1.0 and 0.5, float predictions 1.49 and 0.26
+        let evaluation = Evaluation::from_predictions(100, &[150, 25], &[1.0, 0.5], Some(&[1.49, 0.26]));
+        assert!((evaluation.fixed.mae() - 0.375).abs() < 1e-12);
+        assert!((evaluation.fixed.bias() - 0.125).abs() < 1e-12);
+        assert!((evaluation.delta.max_abs - 0.01).abs() < 1e-12);
+        assert_eq!(evaluation.reference.count, 2);
+
+        let json = evaluation.to_json();
+        assert_eq!(json.get("delta").and_then(|delta| delta.get("count")).and_then(JsonValue::as_usize), Some(2));
+        let unlabelled = Evaluation::from_predictions(100, &[150], &[1.0], None);
+        assert_eq!(unlabelled.to_json().get("reference"), Some(&JsonValue::Null));
+        assert!(!unlabelled.to_string().contains("Float"));
+    }
+}
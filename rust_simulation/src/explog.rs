@@ -0,0 +1,200 @@
+// Fixed-point exponential and natural logarithm
+// Log-link objectives (Poisson, Tweedie, Gamma) predict exp(margin), so porting them needs an
+// exponential every platform and circuit computes bit-identically. Both functions work in 64-bit
+// binary fractions (Q64) with shift-and-add (CORDIC-style) iterations over the table
+// ln(1 + 2^-j), j = 1..=62, and integer arithmetic only:
+//
+//   exp: x = k ln 2 + r with r in [0, ln 2); subtracting table entries from r while multiplying
+//        y = 1 by the matching (1 + 2^-j) = shift and add leaves y = e^r up to a residual
+//        below 2^-62, applied as y (1 + residual); the result is y 2^k, rounded to the scale.
+//   ln:  x = m 2^e with m in [1, 2); multiplying m by (1 + 2^-j) while it stays <= 2 and
+//        adding the table entries gives ln m = ln 2 - sum - ln(2 / u), with the last term
+//        below 2^-61 and taken as (2 - u) / 2.
+//
+// Error bounds (real units, x the exact rational input/10^10):
+//   Every table entry and ln 2 are rounded to nearest (<= 2^-65 each), each shift-and-add step
+//   floors (<= 2^-64 relative), the input conversion floors (<= 2^-64) and |k|, |e| <= 100, so
+//   before the final rounding the exponent of fixed_exp and the value of fixed_ln are off by
+//   less than 2^-54. Hence |fixed_ln(x) - ln(x)| <= 0.5e-10 + 2^-54 < LN_MAX_ERROR (one unit),
+//   and fixed_exp(x) is within 0.5e-10 + EXP_MAX_RELATIVE_ERROR * exp(x) of exp(x), which is
+//   one unit up to exp(x) = 2^53 units (x below about 13.7).
+
+use crate::PRECISION_MULTIPLIER;
+
+/// Guaranteed bound on |fixed_ln(x) - ln(x)|, in real units (one unit of 10^-10)
+pub const LN_MAX_ERROR: f64 = 1e-10;
+
+/// Bound on the relative error of `fixed_exp` before its final rounding to a unit of 10^-10
+pub const EXP_MAX_RELATIVE_ERROR: f64 = 5.6e-17; // 2^-54
+
+/// 1.0 in Q64
+const ONE: u128 = 1 << 64;
+
+/// ln 2 in Q64, rounded to nearest
+const LN2: u128 = 12786308645202655660;
+
+/// ln(1 + 2^-j) in Q64 for j = 1..=62, rounded to nearest
+const LN_TABLE: [u128; 62] = [
+    7479511080090283979, 4116271982791902040, 2172713514977912298,
+    1118326883213202225, 567636912149151763, 286001761100004474,
+    143555153080252560, 71917221983051266, 35993658392855450,
+    18005608138891807, 9005000947051281, 4503049961018713,
+    2251662385915563, 1125865548502293, 562941363661479,
+    281472829248853, 140736951487147, 70368609960277,
+    35184338534443, 17592177655813, 8796090925057,
+    4398045986816, 2199023124480, 1099511595008,
+    549755805696, 274877904896, 137438952960,
+    68719476608, 34359738336, 17179869176,
+    8589934590, 4294967296, 2147483648,
+    1073741824, 536870912, 268435456,
+    134217728, 67108864, 33554432,
+    16777216, 8388608, 4194304,
+    2097152, 1048576, 524288,
+    262144, 131072, 65536,
+    32768, 16384, 8192,
+    4096, 2048, 1024,
+    512, 256, 128,
+    64, 32, 16,
+    8, 4,
+];
+
+/// Inputs above this saturate `fixed_exp` (exp(21) * 10^10 exceeds i64::MAX)
+const EXP_MAX_INPUT: i64 = 21 * PRECISION_MULTIPLIER;
+
+/// Inputs below this round `fixed_exp` to 0 (exp(-24) * 10^10 < 0.4)
+const EXP_MIN_INPUT: i64 = -24 * PRECISION_MULTIPLIER;
+
+/// `value / 2^shift` rounded to nearest (ties up)
+fn round_shift(value: i128, shift: u32) -> i128 {
+    (value + (1 << (shift - 1))) >> shift
+}
+
+/// Fixed-point exponential
+///
+/// # Arguments
+/// * `x` - Exponent (scaled by 10^10)
+///
+/// # Returns
+/// * `i64` - exp(x) (scaled by 10^10), saturating at i64::MAX and rounding to 0 below about -23
+pub fn fixed_exp(x: i64) -> i64 {
+    if x > EXP_MAX_INPUT {
+        return i64::MAX;
+    }
+    if x < EXP_MIN_INPUT {
+        return 0;
+    }
+
+    // x in Q64 (|t| < 2^69), split into k ln 2 + r
+    let t = ((x as i128) << 64).div_euclid(PRECISION_MULTIPLIER as i128);
+    let k = t.div_euclid(LN2 as i128);
+    let mut r = (t - k * LN2 as i128) as u128;
+
+    let mut y = ONE;
+    for (j, &entry) in LN_TABLE.iter().enumerate() {
+        if r >= entry {
+            r -= entry;
+            y += y >> (j + 1);
+        }
+    }
+    // e^r = 1 + r up to r^2 / 2 < 2^-124 for the residual r < 2^-62
+    y += (y * r) >> 64;
+
+    // y 2^k at the scale: k is in [-35, 30], so the shift is positive and y * 10^10 < 2^99
+    let scaled = round_shift((y * PRECISION_MULTIPLIER as u128) as i128, (64 - k) as u32);
+    i64::try_from(scaled).unwrap_or(i64::MAX)
+}
+
+/// Fixed-point natural logarithm
+///
+/// # Arguments
+/// * `x` - Positive value (scaled by 10^10)
+///
+/// # Returns
+/// * `Option<i64>` - ln(x) (scaled by 10^10), within one unit; `None` if `x <= 0`
+pub fn fixed_ln(x: i64) -> Option<i64> {
+    if x <= 0 {
+        return None;
+    }
+
+    // Normalize x / 10^10 to m 2^exponent with m in [1, 2) in Q64, keeping 92+ bits of quotient
+    let shift = (x as u128).leading_zeros() - 1;
+    let quotient = ((x as u128) << shift) / PRECISION_MULTIPLIER as u128;
+    let top = 127 - quotient.leading_zeros();
+    let mut u = quotient >> (top - 64);
+    let exponent = top as i128 - shift as i128;
+
+    let mut sum = 0;
+    for (j, &entry) in LN_TABLE.iter().enumerate() {
+        let next = u + (u >> (j + 1));
+        if next <= 2 * ONE {
+            u = next;
+            sum += entry;
+        }
+    }
+    // ln(2 / u) = (2 - u) / 2 up to its square, below 2^-120
+    let ln_m = LN2 as i128 - sum as i128 - ((2 * ONE - u) / 2) as i128;
+    let ln_x = ln_m + exponent * LN2 as i128;
+    Some(round_shift(ln_x * PRECISION_MULTIPLIER as i128, 64) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCALE: f64 = PRECISION_MULTIPLIER as f64;
+
+    #[test]
+    fn test_table_converges() {
+        // Each entry is at most the sum of the later ones plus the final residual bound, which
+        // keeps the greedy residual of fixed_exp below 4 / 2^64
+        for k in 0..LN_TABLE.len() {
+            assert!(LN_TABLE[k] <= LN_TABLE[k + 1..].iter().sum::<u128>() + 4, "entry {}", k + 1);
+        }
+        assert!(LN2 < LN_TABLE.iter().sum::<u128>());
+    }
+
+    #[test]
+    fn test_exp_error_bound() {
+        for i in -2_400..=2_100i64 {
+            let x = i * 100_000_000 + i * 7_919; // -24..21 in steps of about 0.01
+            let exact = ((x as f64 / SCALE).exp() * SCALE).min(i64::MAX as f64);
+            let error = (fixed_exp(x) as f64 - exact).abs();
+            // f64 rounds the argument to 2^-53 relative, which exp turns into a relative error
+            let reference = 4e-16 * exact * (1.0 + (x as f64 / SCALE).abs());
+            assert!(error <= 0.5 + reference, "exp({}) off by {}", x, error);
+        }
+        assert_eq!(fixed_exp(0), PRECISION_MULTIPLIER);
+        assert_eq!(fixed_exp(PRECISION_MULTIPLIER), 27182818285);
+        assert_eq!(fixed_exp(-PRECISION_MULTIPLIER), 3678794412);
+        assert_eq!(fixed_exp(i64::MAX), i64::MAX);
+        assert_eq!(fixed_exp(21 * PRECISION_MULTIPLIER), i64::MAX);
+        assert_eq!(fixed_exp(i64::MIN), 0);
+    }
+
+    #[test]
+    fn test_ln_error_bound() {
+        let mut x = 1i64;
+        while x < i64::MAX / 3 {
+            for value in [x, x + 1, x * 2 - 1] {
+                let exact = (value as f64 / SCALE).ln() * SCALE;
+                let error = (fixed_ln(value).unwrap() as f64 - exact).abs();
+                assert!(error <= LN_MAX_ERROR * SCALE + 1e-5, "ln({}) off by {}", value, error);
+            }
+            x = x * 3 + 1;
+        }
+        assert_eq!(fixed_ln(PRECISION_MULTIPLIER), Some(0));
+        assert_eq!(fixed_ln(27182818285), Some(10000000000));
+        assert_eq!(fixed_ln(2 * PRECISION_MULTIPLIER), Some(6931471806));
+        assert_eq!(fixed_ln(0), None);
+        assert_eq!(fixed_ln(-1), None);
+    }
+
+    #[test]
+    fn test_exp_ln_round_trip() {
+        for x in [1, 12345, PRECISION_MULTIPLIER / 7, 3 * PRECISION_MULTIPLIER, 123456 * PRECISION_MULTIPLIER] {
+            let back = fixed_exp(fixed_ln(x).unwrap());
+            // ln is within one unit, which exp magnifies by x / 10^10
+            assert!((back - x).abs() <= 1 + x / PRECISION_MULTIPLIER, "{} -> {}", x, back);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+// C FFI
+// `extern "C"` wrappers around the checked prediction API for C/C++ radar-processing firmware.
+// Every function reports failure through a status code instead of unwinding across the boundary.
+// The header is generated with cbindgen (`include/zkml.h`, see cbindgen.toml) and checked by
+// ffi/tests/header.rs; the `ffi/` crate builds these functions as a cdylib and a staticlib. The
+// feature does not need `std`: on bare-metal targets the firmware's Rust crate supplies the
+// allocator and panic handler and re-exports these symbols from its own staticlib.
+
+use core::slice;
+
+use crate::checked::{try_xgboost_predict, PredictError};
+use crate::{fixed_sigmoid, NUM_FEATURES, PRECISION_MULTIPLIER};
+
+/// Status returned by every prediction function
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZkmlStatus {
+    /// Success
+    Ok = 0,
+    /// A pointer argument is null
+    NullPointer = -1,
+    /// Fewer than `zkml_num_features()` features were passed
+    TooFewFeatures = -2,
+    /// A feature is `INT64_MIN` or `INT64_MAX`, the value a non-finite float saturates to
+    Saturated = -3,
+    /// The prediction overflows the fixed-point range
+    Overflow = -4,
+}
+
+impl From<PredictError> for ZkmlStatus {
+    fn from(error: PredictError) -> ZkmlStatus {
+        match error {
+            PredictError::TooFewFeatures { .. } => ZkmlStatus::TooFewFeatures,
+            PredictError::NonFinite { .. } | PredictError::Saturated { .. } => ZkmlStatus::Saturated,
+            PredictError::Overflow => ZkmlStatus::Overflow,
+        }
+    }
+}
+
+/// Run `predict` on `len` features at `features` and store the result in `out`
+unsafe fn call(
+    features: *const i64,
+    len: usize,
+    out: *mut i64,
+    predict: fn(&[i64]) -> Result<i64, PredictError>,
+) -> ZkmlStatus {
+    if features.is_null() || out.is_null() {
+        return ZkmlStatus::NullPointer;
+    }
+    match predict(slice::from_raw_parts(features, len)) {
+        Ok(y) => {
+            *out = y;
+            ZkmlStatus::Ok
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Predict from scaled features
+///
+/// # Arguments
+/// * `features` - `len` features scaled by 10^10
+/// * `len` - Number of features, at least `zkml_num_features()`
+/// * `out` - Receives the prediction (scaled by 10^10)
+///
+/// # Returns
+/// * `ZkmlStatus` - `Ok`, or the reason for failure; `out` is only written on success
+///
+/// # Safety
+/// `features` must point to `len` readable values and `out` to one writable value.
+#[no_mangle]
+pub unsafe extern "C" fn zkml_predict(features: *const i64, len: usize, out: *mut i64) -> ZkmlStatus {
+    call(features, len, out, try_xgboost_predict)
+}
+
+/// Probability of the positive class from scaled features
+///
+/// # Arguments
+/// * `features` - `len` features scaled by 10^10
+/// * `len` - Number of features, at least `zkml_num_features()`
+/// * `out` - Receives the probability in [0, 10^10]
+///
+/// # Returns
+/// * `ZkmlStatus` - `Ok`, or the reason for failure; `out` is only written on success
+///
+/// # Safety
+/// `features` must point to `len` readable values and `out` to one writable value.
+#[no_mangle]
+pub unsafe extern "C" fn zkml_predict_proba(features: *const i64, len: usize, out: *mut i64) -> ZkmlStatus {
+    call(features, len, out, |f| try_xgboost_predict(f).map(fixed_sigmoid))
+}
+
+/// Number of features the model reads
+#[no_mangle]
+pub extern "C" fn zkml_num_features() -> usize {
+    NUM_FEATURES
+}
+
+/// Fixed-point scale of features and predictions (10^10)
+#[no_mangle]
+pub extern "C" fn zkml_scale() -> i64 {
+    PRECISION_MULTIPLIER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xgboost_predict;
+
+    #[test]
+    fn test_predict_status_codes() {
+        let features = [0i64; NUM_FEATURES];
+        let mut out = 0i64;
+        unsafe {
+            assert_eq!(zkml_predict(features.as_ptr(), features.len(), &mut out), ZkmlStatus::Ok);
+            assert_eq!(out, xgboost_predict(&features));
+            assert_eq!(zkml_predict_proba(features.as_ptr(), features.len(), &mut out), ZkmlStatus::Ok);
+            assert_eq!(out, fixed_sigmoid(xgboost_predict(&features)));
+
+            out = 7;
+            assert_eq!(zkml_predict(features.as_ptr(), 3, &mut out), ZkmlStatus::TooFewFeatures);
+            assert_eq!(zkml_predict(core::ptr::null(), 0, &mut out), ZkmlStatus::NullPointer);
+            assert_eq!(zkml_predict(features.as_ptr(), features.len(), core::ptr::null_mut()), ZkmlStatus::NullPointer);
+            assert_eq!(out, 7);
+        }
+    }
+
+    #[test]
+    fn test_saturated_feature() {
+        let mut features = [0i64; NUM_FEATURES];
+        features[4] = i64::MIN;
+        let mut out = 0i64;
+        assert_eq!(unsafe { zkml_predict(features.as_ptr(), features.len(), &mut out) }, ZkmlStatus::Saturated);
+    }
+}
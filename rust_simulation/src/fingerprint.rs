@@ -0,0 +1,264 @@
+// Build fingerprint
+// Two machines produce the same output bits only if they ran the same model with the same
+// arithmetic. A `Fingerprint` names everything that decides those bits: the model's content hash,
+// the crate version (rounding and saturation live in the code), the fixed-point precision and the
+// evaluator mode. It is deterministic: no timestamps or host names, so the same build scoring the
+// same model anywhere gets the same fingerprint. Traces, proof metadata and CSV output carry it,
+// and `Fingerprint::check` compares two before their results are, naming the first field that
+// differs instead of reporting a confusing prediction mismatch.
+//
+// `id()` is the short form written in CSV rows: the first 8 bytes of
+//   SHA-256("ZKFP" | model hash 32 bytes | version len u8 | version | precision i64 LE | mode u8)
+// in hex.
+
+use alloc::{
+    string::{String, ToString},
+    vec,
+};
+use core::fmt;
+
+use crate::ensemble::Ensemble;
+use crate::json::JsonValue;
+use crate::sha256::{sha256, to_hex};
+
+/// Version of this crate, as recorded in fingerprints
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Evaluation path that produced an output
+///
+/// All paths agree bit for bit on supported inputs; the mode is recorded so that a disagreement
+/// can be traced to the path that caused it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EvaluatorMode {
+    /// `Ensemble::eval` and the traced evaluation, one branch per split
+    #[default]
+    Branching,
+    /// `xgboost_predict_oblivious` / `eval_numeric`, every node evaluated
+    Oblivious,
+    /// `DecisionTable` lookups
+    Table,
+    /// `SimdForest` lanes
+    Simd,
+    /// `GpuForest` compute shader
+    Gpu,
+}
+
+impl EvaluatorMode {
+    /// Every mode, in tag order
+    pub const ALL: [EvaluatorMode; 5] = [
+        EvaluatorMode::Branching,
+        EvaluatorMode::Oblivious,
+        EvaluatorMode::Table,
+        EvaluatorMode::Simd,
+        EvaluatorMode::Gpu,
+    ];
+
+    /// Lowercase name used in JSON
+    pub fn name(self) -> &'static str {
+        match self {
+            EvaluatorMode::Branching => "branching",
+            EvaluatorMode::Oblivious => "oblivious",
+            EvaluatorMode::Table => "table",
+            EvaluatorMode::Simd => "simd",
+            EvaluatorMode::Gpu => "gpu",
+        }
+    }
+
+    /// Mode with the given `name`
+    pub fn from_name(name: &str) -> Option<EvaluatorMode> {
+        EvaluatorMode::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+}
+
+/// Error raised when a fingerprint cannot be read or does not match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FingerprintError {
+    /// The JSON form is missing a member or has one of the wrong type
+    Malformed(String),
+    /// The fingerprints differ; `field` is the first differing one
+    Mismatch { field: &'static str, expected: String, got: String },
+}
+
+impl fmt::Display for FingerprintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FingerprintError::Malformed(message) => write!(f, "malformed fingerprint: {}", message),
+            FingerprintError::Mismatch { field, expected, got } => {
+                write!(f, "fingerprints differ in {}: expected {}, got {}", field, expected, got)
+            }
+        }
+    }
+}
+
+impl core::error::Error for FingerprintError {}
+
+/// What decides the output bits of a run
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fingerprint {
+    /// `Ensemble::content_hash` of the model
+    pub model_hash: [u8; 32],
+    /// Version of the crate that evaluated it
+    pub crate_version: String,
+    /// Fixed-point scale of the model
+    pub precision: i64,
+    pub evaluator: EvaluatorMode,
+}
+
+impl Fingerprint {
+    /// Fingerprint of evaluating `model` with `evaluator` in this build
+    pub fn new(model: &Ensemble, evaluator: EvaluatorMode) -> Fingerprint {
+        Fingerprint::with_model_hash(model.content_hash(), model.scale(), evaluator)
+    }
+
+    /// `new` for a model whose content hash is already known
+    pub fn with_model_hash(model_hash: [u8; 32], precision: i64, evaluator: EvaluatorMode) -> Fingerprint {
+        Fingerprint { model_hash, crate_version: CRATE_VERSION.to_string(), precision, evaluator }
+    }
+
+    /// SHA-256 over all fields (layout at the top of this file)
+    pub fn digest(&self) -> [u8; 32] {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(b"ZKFP");
+        bytes.extend_from_slice(&self.model_hash);
+        bytes.push(self.crate_version.len().min(255) as u8);
+        bytes.extend_from_slice(&self.crate_version.as_bytes()[..self.crate_version.len().min(255)]);
+        bytes.extend_from_slice(&self.precision.to_le_bytes());
+        bytes.push(self.evaluator as u8);
+        sha256(&bytes)
+    }
+
+    /// Short form: 16 hex digits of the digest
+    pub fn id(&self) -> String {
+        to_hex(&self.digest()[..8])
+    }
+
+    /// Check that `other` names the same model, build, precision and evaluator
+    ///
+    /// # Arguments
+    /// * `other` - Fingerprint recorded with the results to compare
+    ///
+    /// # Returns
+    /// * `Result<(), FingerprintError>` - Ok if they match, or the first field that differs
+    pub fn check(&self, other: &Fingerprint) -> Result<(), FingerprintError> {
+        let mismatch = |field, expected: String, got: String| Err(FingerprintError::Mismatch { field, expected, got });
+        if self.model_hash != other.model_hash {
+            return mismatch("model_hash", to_hex(&self.model_hash), to_hex(&other.model_hash));
+        }
+        if self.crate_version != other.crate_version {
+            return mismatch("crate_version", self.crate_version.clone(), other.crate_version.clone());
+        }
+        if self.precision != other.precision {
+            return mismatch("precision", self.precision.to_string(), other.precision.to_string());
+        }
+        if self.evaluator != other.evaluator {
+            return mismatch("evaluator", self.evaluator.name().to_string(), other.evaluator.name().to_string());
+        }
+        Ok(())
+    }
+
+    /// Check a short form read back from a CSV column against this fingerprint
+    pub fn check_id(&self, id: &str) -> Result<(), FingerprintError> {
+        if self.id() != id {
+            return Err(FingerprintError::Mismatch { field: "id", expected: self.id(), got: id.to_string() });
+        }
+        Ok(())
+    }
+
+    /// JSON form: `{"model_hash": hex, "crate_version": "...", "precision": n, "evaluator": "..."}`
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::Object(vec![
+            ("model_hash".to_string(), JsonValue::String(to_hex(&self.model_hash))),
+            ("crate_version".to_string(), JsonValue::String(self.crate_version.clone())),
+            ("precision".to_string(), JsonValue::Number(self.precision.to_string())),
+            ("evaluator".to_string(), JsonValue::String(self.evaluator.name().to_string())),
+        ])
+    }
+
+    /// Read the JSON form written by `to_json`
+    pub fn from_json(value: &JsonValue) -> Result<Fingerprint, FingerprintError> {
+        let malformed = |message: &str| FingerprintError::Malformed(message.to_string());
+        let text = |key: &str| value.get(key).and_then(JsonValue::as_str);
+        let hex = text("model_hash").ok_or_else(|| malformed("missing model_hash"))?;
+        let mut model_hash = [0u8; 32];
+        if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(malformed("model_hash must be 64 hex digits"));
+        }
+        for (i, byte) in model_hash.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).expect("hex digits");
+        }
+        let crate_version = text("crate_version").ok_or_else(|| malformed("missing crate_version"))?.to_string();
+        let precision = value
+            .get("precision")
+            .and_then(JsonValue::as_i64)
+            .filter(|&precision| precision > 0)
+            .ok_or_else(|| malformed("precision must be a positive integer"))?;
+        let evaluator = text("evaluator")
+            .and_then(EvaluatorMode::from_name)
+            .ok_or_else(|| malformed("unknown evaluator"))?;
+        Ok(Fingerprint { model_hash, crate_version, precision, evaluator })
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (model {}, version {}, precision {}, {})",
+            self.id(),
+            &to_hex(&self.model_hash)[..12],
+            self.crate_version,
+            self.precision,
+            self.evaluator.name()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::{Node, Tree};
+    use crate::json;
+
+    fn model() -> Ensemble {
+        Ensemble::new(vec![Tree::new(Node::split(0, 5, Node::Leaf(1), Node::Leaf(2)))], 10)
+    }
+
+    #[test]
+    fn test_fingerprint_is_deterministic() {
+        let fingerprint = Fingerprint::new(&model(), EvaluatorMode::Branching);
+        assert_eq!(fingerprint, Fingerprint::new(&model(), EvaluatorMode::Branching));
+        assert_eq!(fingerprint.id().len(), 16);
+        assert_eq!(fingerprint.id(), Fingerprint::new(&model(), EvaluatorMode::Branching).id());
+        assert_ne!(fingerprint.id(), Fingerprint::new(&model(), EvaluatorMode::Oblivious).id());
+        assert_ne!(fingerprint.id(), Fingerprint::new(&model().rescale(100).unwrap(), EvaluatorMode::Branching).id());
+        assert_eq!(fingerprint.crate_version, CRATE_VERSION);
+
+        let text = fingerprint.to_json().to_string();
+        assert_eq!(Fingerprint::from_json(&json::parse(&text).unwrap()), Ok(fingerprint.clone()));
+        assert!(fingerprint.to_string().starts_with(&fingerprint.id()));
+        assert!(fingerprint.to_string().ends_with(", precision 10, branching)"));
+    }
+
+    #[test]
+    fn test_fingerprint_check() {
+        let fingerprint = Fingerprint::new(&model(), EvaluatorMode::Branching);
+        fingerprint.check(&fingerprint.clone()).unwrap();
+        fingerprint.check_id(&fingerprint.id()).unwrap();
+        assert!(fingerprint.check_id("0000000000000000").is_err());
+
+        let upgraded = Fingerprint { crate_version: "9.9.9".to_string(), ..fingerprint.clone() };
+        assert_eq!(
+            fingerprint.check(&upgraded).unwrap_err().to_string(),
+            format!("fingerprints differ in crate_version: expected {}, got 9.9.9", CRATE_VERSION)
+        );
+        let gpu = Fingerprint { evaluator: EvaluatorMode::Gpu, ..fingerprint.clone() };
+        assert!(matches!(fingerprint.check(&gpu), Err(FingerprintError::Mismatch { field: "evaluator", .. })));
+        let retrained = Fingerprint::new(&model().with_version("2"), EvaluatorMode::Gpu);
+        assert!(matches!(gpu.check(&retrained), Err(FingerprintError::Mismatch { field: "model_hash", .. })));
+
+        let malformed = json::parse(r#"{"model_hash":"00","crate_version":"1","precision":1,"evaluator":"simd"}"#);
+        assert!(matches!(Fingerprint::from_json(&malformed.unwrap()), Err(FingerprintError::Malformed(_))));
+    }
+}
@@ -0,0 +1,421 @@
+// Configurable fixed-point scale
+// `FixedPoint<SCALE>` carries its multiplier in the type so values quantized at 10^10, 10^6
+// or 2^16 cannot be mixed by accident. The runtime `rescale_*` helpers convert raw values
+// between scales and are what `Model::rescale` uses for thresholds and leaves.
+// `Fixed` is the value type at the model's own precision: it prints as a decimal and parses from
+// one, so user code can keep scaled values apart from plain i64s. The raw-slice APIs remain the
+// fast path. Parsing never goes through f64: `from_decimal_str` rounds the exact decimal value
+// once, where `from_f64(text.parse())` rounds to a double first and to the scale second.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Add, Neg, Sub};
+use core::str::FromStr;
+
+use crate::{fixed_add, fixed_neg, fixed_sub, PRECISION_MULTIPLIER};
+
+/// Fixed-point number with `SCALE` units per 1.0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedPoint<const SCALE: i64>(i64);
+
+/// Fixed-point type used by the compiled model (scale 10^10)
+pub type Fixed10 = FixedPoint<PRECISION_MULTIPLIER>;
+
+/// Scaled value at the model's precision, as read by `xgboost_predict`
+pub type Fixed = Fixed10;
+
+/// Error raised when a string is not a fixed-point number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseFixedError {
+    /// The string is not a decimal number
+    Invalid,
+    /// The number does not fit in the i64 range at this scale
+    OutOfRange,
+}
+
+impl fmt::Display for ParseFixedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseFixedError::Invalid => write!(f, "not a decimal number"),
+            ParseFixedError::OutOfRange => write!(f, "number outside the fixed-point range"),
+        }
+    }
+}
+
+impl core::error::Error for ParseFixedError {}
+
+impl<const SCALE: i64> FixedPoint<SCALE> {
+    /// Units per 1.0
+    pub const SCALE: i64 = SCALE;
+
+    /// Wrap an already scaled integer
+    pub const fn from_raw(raw: i64) -> Self {
+        FixedPoint(raw)
+    }
+
+    /// Underlying scaled integer
+    pub const fn raw(self) -> i64 {
+        self.0
+    }
+
+    /// Convert a float, rounding to the nearest unit
+    pub fn from_f64(value: f64) -> Self {
+        FixedPoint(round_half_away(value * SCALE as f64))
+    }
+
+    /// Convert back to a float
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / SCALE as f64
+    }
+
+    /// Parse a decimal string directly to the scaled integer, without a float intermediary
+    ///
+    /// Accepts an optional sign, digits with an optional decimal point and an optional exponent
+    /// (`0.0220286213`, `-.5`, `1e-05`). The exact value is rounded to the nearest unit once, ties
+    /// away from zero, so the result is what `round(value * SCALE)` gives in exact arithmetic.
+    ///
+    /// # Arguments
+    /// * `text` - Decimal number; surrounding whitespace is ignored
+    ///
+    /// # Returns
+    /// * `Result<Self, ParseFixedError>` - The value, `Invalid` for anything that is not a decimal
+    ///   number (including `inf` and `NaN`), or `OutOfRange` if it does not fit in an i64
+    pub fn from_decimal_str(text: &str) -> Result<Self, ParseFixedError> {
+        parse_decimal(text, SCALE).map(FixedPoint)
+    }
+
+    /// Convert to another scale, rounding to the nearest unit (ties away from zero)
+    pub fn rescale<const TO: i64>(self) -> FixedPoint<TO> {
+        FixedPoint(rescale_round(self.0, SCALE, TO))
+    }
+
+    /// Convert to another scale, rounding toward negative infinity
+    ///
+    /// Use this for split thresholds: for any `x` on the new grid,
+    /// `x <= t.rescale_floor()` holds exactly when `x <= t` holds on the real line.
+    pub fn rescale_floor<const TO: i64>(self) -> FixedPoint<TO> {
+        FixedPoint(rescale_floor(self.0, SCALE, TO))
+    }
+}
+
+impl<const SCALE: i64> Add for FixedPoint<SCALE> {
+    type Output = Self;
+
+    /// Saturating addition, same as `fixed_add`
+    fn add(self, rhs: Self) -> Self {
+        FixedPoint(fixed_add(self.0, rhs.0))
+    }
+}
+
+impl<const SCALE: i64> Sub for FixedPoint<SCALE> {
+    type Output = Self;
+
+    /// Saturating subtraction, same as `fixed_sub`
+    fn sub(self, rhs: Self) -> Self {
+        FixedPoint(fixed_sub(self.0, rhs.0))
+    }
+}
+
+impl<const SCALE: i64> Neg for FixedPoint<SCALE> {
+    type Output = Self;
+
+    /// Saturating negation, same as `fixed_neg`
+    fn neg(self) -> Self {
+        FixedPoint(fixed_neg(self.0))
+    }
+}
+
+/// Exact decimal value, e.g. `0.0220286213` for raw 220286213 at scale 10^10
+///
+/// The fraction is written by long division and stops when it terminates, which it does for
+/// power-of-ten and power-of-two scales; other scales are cut after 19 digits.
+impl<const SCALE: i64> fmt::Display for FixedPoint<SCALE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scale = SCALE.unsigned_abs();
+        let magnitude = self.0.unsigned_abs();
+        if self.0 < 0 {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", magnitude / scale)?;
+        let mut remainder = magnitude % scale;
+        if remainder != 0 {
+            write!(f, ".")?;
+        }
+        for _ in 0..19 {
+            if remainder == 0 {
+                break;
+            }
+            let shifted = remainder as u128 * 10;
+            write!(f, "{}", shifted / scale as u128)?;
+            remainder = (shifted % scale as u128) as u64;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a decimal number exactly, see `from_decimal_str`
+impl<const SCALE: i64> FromStr for FixedPoint<SCALE> {
+    type Err = ParseFixedError;
+
+    fn from_str(text: &str) -> Result<Self, ParseFixedError> {
+        FixedPoint::from_decimal_str(text)
+    }
+}
+
+/// Parse a decimal string to an integer at a runtime `scale`, without a float intermediary
+///
+/// `FixedPoint::from_decimal_str` for scales only known at run time, such as a loaded model's.
+///
+/// # Arguments
+/// * `text` - Decimal number; surrounding whitespace is ignored
+/// * `scale` - Units per 1.0 (positive)
+///
+/// # Returns
+/// * `Result<i64, ParseFixedError>` - `round(value * scale)` in exact arithmetic, ties away from
+///   zero, or the error `from_decimal_str` would give
+pub fn parse_decimal(text: &str, scale: i64) -> Result<i64, ParseFixedError> {
+    assert!(scale > 0, "fixed-point scales must be positive");
+    let text = text.trim();
+    let (negative, text) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(at) => (&text[..at], Some(&text[at + 1..])),
+        None => (text, None),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if integer.len() + fraction.len() == 0 || !is_digits(integer) || !is_digits(fraction) {
+        return Err(ParseFixedError::Invalid);
+    }
+    let exponent = match exponent {
+        Some(exponent) => parse_exponent(exponent)?,
+        None => 0,
+    };
+
+    // value = 0.digits * 10^point, with leading zeros removed
+    let digits = integer.bytes().chain(fraction.bytes()).map(|b| b - b'0');
+    let leading = digits.clone().take_while(|&d| d == 0).count();
+    let digits: Vec<u8> = digits.skip(leading).collect();
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    let point = integer.len() as i64 - leading as i64 + exponent;
+    // Below 10^-20 the value times any i64 scale is under 0.1 and rounds to zero; at 10^20
+    // and above it is out of range even at scale 1. This only bounds the whole part below
+    // 10^20: times a scale near 2^63 that is past u128, so the products below are checked
+    if point < -20 {
+        return Ok(0);
+    }
+    if point > 20 {
+        return Err(ParseFixedError::OutOfRange);
+    }
+
+    let scale = scale as u128;
+    let split = point.clamp(0, digits.len() as i64) as usize;
+    let mut whole: u128 = 0;
+    for &digit in &digits[..split] {
+        whole = whole * 10 + digit as u128;
+    }
+    for _ in digits.len() as i64..point {
+        whole *= 10;
+    }
+    // floor(scale * 0.d1 d2 ...) by Horner's rule from the last digit; flooring each step is
+    // exact because floor((n + x) / 10) = floor((n + floor(x)) / 10) for integer n
+    let zeros = core::iter::repeat_n(0, (-point).max(0) as usize);
+    let mut carry: u128 = 0;
+    let mut round_up = false;
+    for digit in zeros.chain(digits[split..].iter().copied()).rev() {
+        let numerator = digit as u128 * scale + carry;
+        carry = numerator / 10;
+        round_up = numerator % 10 >= 5;
+    }
+    let magnitude = whole
+        .checked_mul(scale)
+        .and_then(|scaled| scaled.checked_add(carry + round_up as u128))
+        .ok_or(ParseFixedError::OutOfRange)?;
+    let limit = if negative { i64::MIN.unsigned_abs() as u128 } else { i64::MAX as u128 };
+    if magnitude > limit {
+        return Err(ParseFixedError::OutOfRange);
+    }
+    Ok(if negative { (magnitude as i64).wrapping_neg() } else { magnitude as i64 })
+}
+
+/// Exponent of a decimal number; huge exponents are clamped, they only decide zero or overflow
+fn parse_exponent(text: &str) -> Result<i64, ParseFixedError> {
+    let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseFixedError::Invalid);
+    }
+    let magnitude = digits.bytes().fold(0i64, |value, b| (value * 10 + (b - b'0') as i64).min(1 << 32));
+    Ok(if text.starts_with('-') { -magnitude } else { magnitude })
+}
+
+/// `value.round() as i64` without the standard library (ties away from zero)
+pub(crate) fn round_half_away(value: f64) -> i64 {
+    let truncated = value as i64;
+    // Beyond 2^52 every f64 is an integer; the cast also saturates and maps NaN to 0
+    if value.abs() >= 4_503_599_627_370_496.0 || value.is_nan() {
+        return truncated;
+    }
+    let fraction = value - truncated as f64;
+    if fraction >= 0.5 {
+        truncated + 1
+    } else if fraction <= -0.5 {
+        truncated - 1
+    } else {
+        truncated
+    }
+}
+
+/// `value.round_ties_even() as i64` without the standard library
+pub(crate) fn round_half_even(value: f64) -> i64 {
+    let rounded = round_half_away(value);
+    let truncated = value as i64;
+    // Only exact ties differ: move back to the even neighbour
+    if rounded != truncated && (value - truncated as f64).abs() == 0.5 && rounded % 2 != 0 {
+        truncated
+    } else {
+        rounded
+    }
+}
+
+/// Saturate an i128 intermediate back into i64
+pub(crate) fn saturate(value: i128) -> i64 {
+    value.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// Rescale a raw value from `from` to `to` units per 1.0, rounding to nearest (ties away from zero)
+pub fn rescale_round(value: i64, from: i64, to: i64) -> i64 {
+    assert!(from > 0 && to > 0, "fixed-point scales must be positive");
+    let scaled = value as i128 * to as i128;
+    let from = from as i128;
+    let magnitude = (scaled.abs() + from / 2) / from;
+    saturate(if scaled < 0 { -magnitude } else { magnitude })
+}
+
+/// Rescale a raw value from `from` to `to` units per 1.0, rounding toward negative infinity
+pub fn rescale_floor(value: i64, from: i64, to: i64) -> i64 {
+    assert!(from > 0 && to > 0, "fixed-point scales must be positive");
+    saturate((value as i128 * to as i128).div_euclid(from as i128))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_point_conversions() {
+        let x = Fixed10::from_f64(1.25);
+        assert_eq!(x.raw(), 12_500_000_000);
+        assert_eq!(x.to_f64(), 1.25);
+        assert_eq!((x + Fixed10::from_raw(i64::MAX)).raw(), i64::MAX);
+
+        let y: FixedPoint<1_000_000> = x.rescale();
+        assert_eq!(y.raw(), 1_250_000);
+        assert_eq!(FixedPoint::<65536>::from_f64(-0.5).raw(), -32768);
+    }
+
+    #[test]
+    fn test_fixed_display_and_parse() {
+        assert_eq!(Fixed::from_raw(220_286_213).to_string(), "0.0220286213");
+        assert_eq!(Fixed::from_raw(-5_000_000_000).to_string(), "-0.5");
+        assert_eq!(Fixed::from_raw(30_000_000_000).to_string(), "3");
+        assert_eq!(Fixed::from_raw(i64::MIN).to_string(), "-922337203.6854775808");
+        assert_eq!(Fixed::from_raw(i64::MAX).to_string(), "922337203.6854775807");
+        assert_eq!(FixedPoint::<65536>::from_raw(1).to_string(), "0.0000152587890625");
+        assert_eq!(FixedPoint::<3>::from_raw(1).to_string(), "0.3333333333333333333");
+
+        let x: Fixed = "0.0220286213".parse().unwrap();
+        assert_eq!(x, Fixed::from_raw(220_286_213));
+        assert_eq!(x.to_string().parse::<Fixed>(), Ok(x));
+        assert_eq!(" -1.5 ".parse::<Fixed>(), Ok(Fixed::from_f64(-1.5)));
+        assert!(Fixed::from_f64(0.5) < "0.6".parse().unwrap());
+        assert_eq!((x - x + Fixed::from_f64(2.0)).to_string(), "2");
+
+        assert_eq!("abc".parse::<Fixed>(), Err(ParseFixedError::Invalid));
+        assert_eq!("".parse::<Fixed>(), Err(ParseFixedError::Invalid));
+        assert_eq!("NaN".parse::<Fixed>(), Err(ParseFixedError::Invalid));
+        assert_eq!("1e9".parse::<Fixed>(), Err(ParseFixedError::OutOfRange));
+        assert_eq!("1e9".parse::<FixedPoint<1_000_000>>().map(|v| v.raw()), Ok(1_000_000_000_000_000));
+    }
+
+    #[test]
+    fn test_from_decimal_str_is_exact() {
+        let parse = |text: &str| Fixed::from_decimal_str(text).map(Fixed::raw);
+        assert_eq!(parse("0.0220286213"), Ok(220_286_213));
+        assert_eq!(parse("+12.5"), Ok(125_000_000_000));
+        assert_eq!(parse("-.5"), Ok(-5_000_000_000));
+        assert_eq!(parse("3."), Ok(30_000_000_000));
+        assert_eq!(parse("1e-05"), Ok(100_000));
+        assert_eq!(parse("0.000125E2"), Ok(125_000_000));
+        assert_eq!(parse("-0"), Ok(0));
+        assert_eq!(parse("1e-999999999999"), Ok(0));
+
+        // Ties round away from zero, digits past the tie still count
+        assert_eq!(parse("0.00000000005"), Ok(1));
+        assert_eq!(parse("-0.00000000005"), Ok(-1));
+        assert_eq!(parse("0.000000000049999999999999"), Ok(0));
+        assert_eq!(parse("0.00000000015"), Ok(2));
+
+        // The f64 route rounds twice: the tie 1438.10345095225 becomes 14381034509522.498
+        assert_eq!(parse("1438.10345095225"), Ok(14_381_034_509_523));
+        assert_eq!(Fixed::from_f64(1438.10345095225).raw(), 14_381_034_509_522);
+        // ...and loses digits beyond f64 precision
+        assert_eq!(parse("123456789.0123456789"), Ok(1_234_567_890_123_456_789));
+        assert_ne!(Fixed::from_f64("123456789.0123456789".parse().unwrap()).raw(), 1_234_567_890_123_456_789);
+
+        assert_eq!(parse("-922337203.6854775808"), Ok(i64::MIN));
+        assert_eq!(parse("922337203.6854775807"), Ok(i64::MAX));
+        assert_eq!(parse("922337203.68547758075"), Err(ParseFixedError::OutOfRange));
+        assert_eq!(parse("1e99999999999999999999"), Err(ParseFixedError::OutOfRange));
+        assert_eq!(FixedPoint::<1>::from_decimal_str("9223372036854775807.4").map(|v| v.raw()), Ok(i64::MAX));
+        // The runtime-scale form agrees with the typed one
+        assert_eq!(parse_decimal("1438.10345095225", PRECISION_MULTIPLIER), parse("1438.10345095225"));
+        assert_eq!(parse_decimal("-0.0005", 1000), Ok(-1));
+        // At a large scale the whole part times the scale is past u128, not only past i64
+        type Large = FixedPoint<{ 1 << 62 }>;
+        assert_eq!(Large::from_decimal_str("99999999999999999999"), Err(ParseFixedError::OutOfRange));
+        assert_eq!(Large::from_decimal_str("-2"), Ok(FixedPoint(i64::MIN)));
+        assert_eq!(Large::from_decimal_str("1.5").map(|v| v.raw()), Ok(3 << 61));
+
+        for bad in ["", ".", "-", "1.2.3", "1e", "1e+", "e5", "inf", "0x10", "1_000", "- 1"] {
+            assert_eq!(parse(bad), Err(ParseFixedError::Invalid), "{:?}", bad);
+        }
+        for raw in [0, 1, -1, 220_286_213, -123_456_789_012, i64::MIN, i64::MAX] {
+            assert_eq!(parse(&Fixed::from_raw(raw).to_string()), Ok(raw));
+            let coarse = FixedPoint::<65536>::from_raw(raw / 1000);
+            assert_eq!(coarse.to_string().parse(), Ok(coarse));
+        }
+    }
+
+    #[test]
+    fn test_manual_rounding_matches_std() {
+        let values = [
+            0.0, 0.5, -0.5, 1.5, 2.5, -2.5, 0.49999999999999994, 2.0000000001, -7.999,
+            4503599627370495.5, 1e300, -1e300, f64::NAN, f64::INFINITY, 0.845000029 * 1e10,
+        ];
+        for &v in &values {
+            assert_eq!(round_half_away(v), v.round() as i64, "{}", v);
+            assert_eq!(round_half_even(v), v.round_ties_even() as i64, "{}", v);
+        }
+        for i in -2000..2000 {
+            let v = i as f64 * 0.25 + 0.125 * (i % 3) as f64;
+            assert_eq!(round_half_away(v), v.round() as i64);
+            assert_eq!(round_half_even(v), v.round_ties_even() as i64);
+        }
+    }
+
+    #[test]
+    fn test_rescale_rounding() {
+        // 0.00000015 at 10^10 is 1500 units, 0.15 units at 10^6
+        assert_eq!(rescale_round(1500, 10_000_000_000, 1_000_000), 0);
+        assert_eq!(rescale_round(5000, 10_000_000_000, 1_000_000), 1);
+        assert_eq!(rescale_round(-5000, 10_000_000_000, 1_000_000), -1);
+        assert_eq!(rescale_floor(-1500, 10_000_000_000, 1_000_000), -1);
+        assert_eq!(rescale_floor(1500, 10_000_000_000, 1_000_000), 0);
+        assert_eq!(rescale_round(i64::MAX, 1, 2), i64::MAX);
+    }
+}
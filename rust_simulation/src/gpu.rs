@@ -0,0 +1,462 @@
+// GPU batch scoring
+// Offline scoring of large datasets (millions of rows, e.g. to validate a port against the
+// training data) with a wgpu compute shader (gpu.wgsl). The shader runs the oblivious form of the
+// ensemble, one invocation per sample: every node of every tree is evaluated and the reached leaf
+// is selected arithmetically, so all invocations of a workgroup follow the same control flow.
+// WGSL has no 64-bit integers; values travel as their little-endian i64 bytes and the shader
+// compares them as (low, high) u32 pairs. The per-tree outputs are read back and combined on the
+// host with `Combiner::combine`, so predictions are bit-identical to `Ensemble::eval`.
+// Needs feature `gpu` and a Vulkan, Metal, DX12 or OpenGL adapter (software ones work too).
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+use std::sync::{mpsc, OnceLock};
+
+use wgpu::util::DeviceExt;
+
+use crate::ensemble::{Accumulator, Combiner, Ensemble, Node};
+
+/// Samples per workgroup, as declared in the shader
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Nodes one invocation evaluates per dispatch; larger forests are split into several dispatches
+/// of whole trees, since drivers abort long-running shaders (watchdogs, or the loop limiter of
+/// Mesa's llvmpipe)
+const NODES_PER_DISPATCH: u32 = 8192;
+
+const SHADER: &str = include_str!("gpu.wgsl");
+
+/// Error raised while setting up or running the GPU backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpuError {
+    /// No adapter is available, or it cannot run compute shaders
+    Adapter(String),
+    /// The device could not be created
+    Device(String),
+    /// Reading the results back failed
+    Readback(String),
+}
+
+impl fmt::Display for GpuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GpuError::Adapter(message) => write!(f, "no usable GPU adapter: {}", message),
+            GpuError::Device(message) => write!(f, "GPU device creation failed: {}", message),
+            GpuError::Readback(message) => write!(f, "GPU readback failed: {}", message),
+        }
+    }
+}
+
+impl core::error::Error for GpuError {}
+
+/// Ensemble uploaded to a GPU for [`GpuForest::predict_batch`]
+#[derive(Debug)]
+pub struct GpuForest {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    nodes: wgpu::Buffer,
+    trees: wgpu::Buffer,
+    members: wgpu::Buffer,
+    base_score: i64,
+    combiner: Combiner,
+    accumulator: Accumulator,
+    num_features: usize,
+    num_trees: usize,
+    /// Tree ranges evaluated by one dispatch each
+    tree_groups: Vec<(u32, u32)>,
+    /// Samples per dispatch, bounded by the device's buffer and dispatch limits
+    chunk_size: usize,
+    adapter_name: String,
+}
+
+/// Tree nodes in the shader's `Node` layout (32 bytes each), pre-order within every tree
+#[derive(Default)]
+struct NodeTable {
+    nodes: Vec<u8>,
+    tree_starts: Vec<u32>,
+    members: Vec<u8>,
+    count: u32,
+    max_tree_nodes: u32,
+}
+
+impl NodeTable {
+    fn new(ensemble: &Ensemble) -> NodeTable {
+        let mut table = NodeTable::default();
+        for tree in ensemble.trees() {
+            let first = table.count;
+            table.tree_starts.push(first);
+            table.push(&tree.root, first);
+            table.max_tree_nodes = table.max_tree_nodes.max(table.count - first);
+        }
+        table.tree_starts.push(table.count);
+        table
+    }
+
+    /// Append the subtree and return its index within the tree starting at `first`
+    fn push(&mut self, node: &Node, first: u32) -> u32 {
+        let index = self.count - first;
+        let offset = self.nodes.len();
+        self.count += 1;
+        self.nodes.resize(offset + 32, 0);
+        let (kind, feature, value, children) = match node {
+            Node::Leaf(value) => (0u32, 0, *value, None),
+            Node::Split { feature, threshold, left, right } => (1, *feature, *threshold, Some((left, right))),
+            Node::Categorical { feature, categories, unit, left, right } => {
+                let start = self.member_count();
+                for value in categories.values(*unit) {
+                    self.members.extend_from_slice(&value.to_le_bytes());
+                }
+                let end = self.member_count();
+                self.nodes[offset + 24..offset + 28].copy_from_slice(&start.to_le_bytes());
+                self.nodes[offset + 28..offset + 32].copy_from_slice(&end.to_le_bytes());
+                (2, *feature, 0, Some((left, right)))
+            }
+        };
+        let (left, right) = match children {
+            Some((left, right)) => (self.push(left, first), self.push(right, first)),
+            None => (0, 0),
+        };
+        let word = |i: usize, v: u32| (offset + 4 * i, v.to_le_bytes());
+        for (at, bytes) in [word(0, kind), word(1, feature as u32), word(2, left), word(3, right)] {
+            self.nodes[at..at + 4].copy_from_slice(&bytes);
+        }
+        self.nodes[offset + 16..offset + 24].copy_from_slice(&value.to_le_bytes());
+        index
+    }
+
+    /// Consecutive tree ranges of at most `budget` nodes (or a single larger tree)
+    fn groups(&self, budget: u32) -> Vec<(u32, u32)> {
+        let mut groups = Vec::new();
+        let mut first = 0;
+        for tree in 1..self.tree_starts.len() {
+            if tree - 1 > first && self.tree_starts[tree] - self.tree_starts[first] > budget {
+                groups.push((first as u32, tree as u32 - 1));
+                first = tree - 1;
+            }
+        }
+        groups.push((first as u32, self.tree_starts.len() as u32 - 1));
+        groups
+    }
+
+    fn member_count(&self) -> u32 {
+        (self.members.len() / 8) as u32
+    }
+}
+
+/// Bytes of `data`, padded to hold at least one element of any binding (a `Node` is 32 bytes)
+fn padded(mut data: Vec<u8>) -> Vec<u8> {
+    data.resize(data.len().max(32), 0);
+    data
+}
+
+impl GpuForest {
+    /// Upload an ensemble to the default GPU adapter
+    ///
+    /// # Arguments
+    /// * `ensemble` - Model to score with
+    ///
+    /// # Returns
+    /// * `Result<GpuForest, GpuError>` - The forest, or why no GPU could be used
+    pub fn new(ensemble: &Ensemble) -> Result<GpuForest, GpuError> {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }))
+        .map_err(|e| GpuError::Adapter(e.to_string()))?;
+        if !adapter.get_downlevel_capabilities().flags.contains(wgpu::DownlevelFlags::COMPUTE_SHADERS) {
+            return Err(GpuError::Adapter("compute shaders are not supported".to_string()));
+        }
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("rainfall_prediction"),
+            required_limits: adapter.limits(),
+            ..Default::default()
+        }))
+        .map_err(|e| GpuError::Device(e.to_string()))?;
+
+        let table = NodeTable::new(ensemble);
+        let source = SHADER.replace("MAX_NODES_PLACEHOLDER", &table.max_tree_nodes.max(1).to_string());
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("oblivious ensemble"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("oblivious ensemble"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let storage = |label: &str, contents: Vec<u8>| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: &padded(contents),
+                usage: wgpu::BufferUsages::STORAGE,
+            })
+        };
+        let tree_groups = table.groups(NODES_PER_DISPATCH);
+        let nodes = storage("nodes", table.nodes);
+        let trees = storage("trees", table.tree_starts.iter().flat_map(|s| s.to_le_bytes()).collect());
+        let members = storage("members", table.members);
+
+        let num_features = ensemble.num_features();
+        let num_trees = ensemble.num_trees();
+        let limits = device.limits();
+        let row_bytes = 8 * num_features.max(num_trees).max(1) as u64;
+        let max_binding = limits.max_storage_buffer_binding_size.min(limits.max_buffer_size);
+        let max_workgroups = limits.max_compute_workgroups_per_dimension as u64;
+        let max_dispatch = WORKGROUP_SIZE as u64 * max_workgroups * max_workgroups;
+        let chunk_size = (max_binding / row_bytes).min(max_dispatch).max(1) as usize;
+
+        Ok(GpuForest {
+            device,
+            queue,
+            pipeline,
+            nodes,
+            trees,
+            members,
+            base_score: ensemble.base_score(),
+            combiner: ensemble.combiner(),
+            accumulator: ensemble.accumulator(),
+            num_features,
+            num_trees,
+            tree_groups,
+            chunk_size,
+            adapter_name: adapter.get_info().name,
+        })
+    }
+
+    /// Name of the adapter the forest runs on
+    pub fn adapter_name(&self) -> &str {
+        &self.adapter_name
+    }
+
+    /// Score a batch on the GPU
+    ///
+    /// # Arguments
+    /// * `batch` - Feature vectors at the ensemble's scale
+    ///
+    /// # Returns
+    /// * `Result<Vec<i64>, GpuError>` - One prediction per input, identical to `Ensemble::eval`
+    ///
+    /// Panics if a feature vector is shorter than the ensemble's `num_features`.
+    pub fn predict_batch(&self, batch: &[Vec<i64>]) -> Result<Vec<i64>, GpuError> {
+        let mut predictions = Vec::with_capacity(batch.len());
+        for chunk in batch.chunks(self.chunk_size) {
+            let outputs = self.tree_outputs(chunk)?;
+            predictions.extend((0..chunk.len()).map(|i| {
+                let row = &outputs[i * self.num_trees..(i + 1) * self.num_trees];
+                self.accumulator.combine(self.combiner, self.base_score, row.iter().copied())
+            }));
+        }
+        Ok(predictions)
+    }
+
+    /// Leaf reached in every tree by every sample of one dispatch, sample-major
+    fn tree_outputs(&self, chunk: &[Vec<i64>]) -> Result<Vec<i64>, GpuError> {
+        let mut features = Vec::with_capacity(8 * self.num_features * chunk.len());
+        for row in chunk {
+            assert!(row.len() >= self.num_features,
+                    "Expected at least {} features, got {}", self.num_features, row.len());
+            features.extend(row[..self.num_features].iter().flat_map(|x| x.to_le_bytes()));
+        }
+
+        // A 2D grid when the samples need more workgroups than one dimension allows
+        let workgroups = (chunk.len() as u32).div_ceil(WORKGROUP_SIZE);
+        let max_row = self.device.limits().max_compute_workgroups_per_dimension;
+        let (columns, rows) = (workgroups.min(max_row), workgroups.div_ceil(max_row));
+        let features = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("features"),
+            contents: &padded(features),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let output_size = (8 * self.num_trees * chunk.len()).max(8) as u64;
+        let outputs = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("outputs"),
+            size: output_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size: output_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // One dispatch per tree group, each with its own parameters
+        let layout = self.pipeline.get_bind_group_layout(0);
+        let bind_groups: Vec<wgpu::BindGroup> = self
+            .tree_groups
+            .iter()
+            .map(|&(first_tree, end_tree)| {
+                let params: Vec<u8> = [
+                    chunk.len() as u32,
+                    self.num_features as u32,
+                    self.num_trees as u32,
+                    columns * WORKGROUP_SIZE,
+                    first_tree,
+                    end_tree,
+                    0,
+                    0,
+                ]
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect();
+                let params = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("params"),
+                    contents: &params,
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+                let bindings = [&params, &features, &self.nodes, &self.trees, &self.members, &outputs];
+                let entries: Vec<wgpu::BindGroupEntry> = bindings
+                    .iter()
+                    .enumerate()
+                    .map(|(i, buffer)| wgpu::BindGroupEntry { binding: i as u32, resource: buffer.as_entire_binding() })
+                    .collect();
+                self.device.create_bind_group(&wgpu::BindGroupDescriptor { label: None, layout: &layout, entries: &entries })
+            })
+            .collect();
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass.set_pipeline(&self.pipeline);
+            for bind_group in &bind_groups {
+                pass.set_bind_group(0, bind_group, &[]);
+                pass.dispatch_workgroups(columns, rows, 1);
+            }
+        }
+        encoder.copy_buffer_to_buffer(&outputs, 0, &readback, 0, output_size);
+        self.queue.submit([encoder.finish()]);
+
+        let slice = readback.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(|e| GpuError::Readback(e.to_string()))?;
+        receiver
+            .recv()
+            .map_err(|e| GpuError::Readback(e.to_string()))?
+            .map_err(|e| GpuError::Readback(e.to_string()))?;
+        let view = slice.get_mapped_range().map_err(|e| GpuError::Readback(e.to_string()))?;
+        Ok(view
+            .chunks_exact(8)
+            .map(|bytes| i64::from_le_bytes(bytes.try_into().expect("8-byte chunk")))
+            .collect())
+    }
+}
+
+/// [`crate::xgboost_predict_batch`] on the GPU with the bundled model
+///
+/// The forest is uploaded on the first call and reused.
+pub fn xgboost_predict_batch_gpu(batch: &[Vec<i64>]) -> Result<Vec<i64>, GpuError> {
+    static BUILTIN: OnceLock<Result<GpuForest, GpuError>> = OnceLock::new();
+    BUILTIN.get_or_init(|| GpuForest::new(&crate::builtin_model())).as_ref().map_err(Clone::clone)?.predict_batch(batch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::Tree;
+    use crate::{xgboost_predict_batch, MISSING_VALUE, NUM_FEATURES};
+    use alloc::vec;
+
+    /// The forest, or None (skipping the test) on machines without a usable adapter
+    fn forest(ensemble: &Ensemble) -> Option<GpuForest> {
+        match GpuForest::new(ensemble) {
+            Ok(forest) => Some(forest),
+            Err(e) => {
+                std::eprintln!("skipping GPU test: {}", e);
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn test_gpu_matches_scalar_batch() {
+        let batch: Vec<Vec<i64>> = (0..300)
+            .map(|i| {
+                (0..NUM_FEATURES)
+                    .map(|j| match (i * 7 + j) % 23 {
+                        0 => MISSING_VALUE,
+                        _ => ((i * 37 + j * 11) % 400) as i64 * 1_000_000_000 - 50_000_000_000,
+                    })
+                    .collect()
+            })
+            .collect();
+        let Some(mut forest) = forest(&crate::builtin_model()) else {
+            return;
+        };
+        let expected = xgboost_predict_batch(&batch);
+        assert_eq!(forest.predict_batch(&batch).unwrap(), expected);
+        // Several dispatches, the last one partial
+        forest.chunk_size = 70;
+        assert_eq!(forest.predict_batch(&batch).unwrap(), expected);
+        assert!(forest.predict_batch(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_gpu_extremes_and_categories() {
+        let trees = vec![
+            Tree::new(Node::split(1, -5, Node::Leaf(1), Node::split(0, 1 << 32, Node::Leaf(2), Node::Leaf(3)))),
+            Tree::new(Node::Leaf(i64::MAX)),
+            Tree::new(Node::categorical(0, [0, 2, 100].into_iter().collect(), 3, Node::Leaf(-4), Node::Leaf(i64::MIN))),
+        ];
+        let model = Ensemble::new(trees, 1).with_base_score(10);
+        let empty = Ensemble::new(Vec::new(), 1).with_base_score(3);
+        if let Some(forest) = forest(&empty) {
+            assert_eq!(forest.predict_batch(&[vec![], vec![]]).unwrap(), vec![3, 3]);
+        }
+
+        let batch = vec![
+            vec![0, 0],
+            vec![6, -9],
+            vec![1 << 32, -4],
+            vec![(1 << 32) + 1, i64::MIN],
+            vec![-1, i64::MAX],
+            vec![300, -5],
+            vec![i64::MIN, 7],
+        ];
+        let Some(forest) = forest(&model) else {
+            return;
+        };
+        let expected: Vec<i64> = batch.iter().map(|f| model.eval(f)).collect();
+        assert_eq!(forest.predict_batch(&batch).unwrap(), expected);
+
+        let averaged = model.with_combiner(Combiner::Average);
+        let expected: Vec<i64> = batch.iter().map(|f| averaged.eval(f)).collect();
+        assert_eq!(GpuForest::new(&averaged).unwrap().predict_batch(&batch).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_gpu_splits_large_forests() {
+        fn node(depth: usize, seed: i64) -> Node {
+            match depth {
+                0 => Node::Leaf(seed * 1_000_003 - 7_000_000),
+                _ => Node::split(seed as usize % 3, seed % 11 - 5, node(depth - 1, seed * 3 + 1), node(depth - 1, seed * 5 + 2)),
+            }
+        }
+        // 400 trees of 31 nodes: two dispatches
+        let model = Ensemble::new((0..400).map(|i| Tree::new(node(4, i))).collect(), 1);
+        let table = NodeTable::new(&model);
+        assert_eq!(table.groups(NODES_PER_DISPATCH), vec![(0, 264), (264, 400)]);
+        assert_eq!(table.groups(10), (0..400).map(|t| (t, t + 1)).collect::<Vec<_>>());
+
+        let batch: Vec<Vec<i64>> = (-6..6).map(|x| vec![x, -x, x * x]).collect();
+        let Some(forest) = forest(&model) else {
+            return;
+        };
+        let expected: Vec<i64> = batch.iter().map(|f| model.eval(f)).collect();
+        assert_eq!(forest.predict_batch(&batch).unwrap(), expected);
+    }
+}
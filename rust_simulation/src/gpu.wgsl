@@ -0,0 +1,75 @@
+// Oblivious ensemble evaluation, one invocation per sample (host side: gpu.rs)
+// WGSL has no 64-bit integers, so every fixed-point value is a vec2<u32> (low word, high word)
+// in two's complement, exactly the little-endian bytes of the i64. Each tree's nodes are stored
+// in pre-order, so walking them backwards evaluates the children before their parent: every node
+// of every tree is visited and the reached leaf is selected without data-dependent branches, as
+// in `Ensemble::eval_oblivious`. A dispatch covers a range of trees; the tree outputs are written
+// per sample and combined on the host.
+
+struct Params {
+    num_samples: u32,
+    num_features: u32,
+    num_trees: u32,
+    // Invocations per row of the dispatch grid
+    row_stride: u32,
+    // Trees evaluated by this dispatch
+    first_tree: u32,
+    end_tree: u32,
+    _padding: vec2<u32>,
+}
+
+// 0: leaf (`value` is the output), 1: split on `value` as threshold, 2: categorical split on
+// the member values members[members_start..members_end]; children are indices within the tree
+struct Node {
+    kind: u32,
+    feature: u32,
+    left: u32,
+    right: u32,
+    value: vec2<u32>,
+    members_start: u32,
+    members_end: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> features: array<vec2<u32>>;
+@group(0) @binding(2) var<storage, read> nodes: array<Node>;
+// First node of every tree, followed by the total node count
+@group(0) @binding(3) var<storage, read> trees: array<u32>;
+@group(0) @binding(4) var<storage, read> members: array<vec2<u32>>;
+@group(0) @binding(5) var<storage, read_write> outputs: array<vec2<u32>>;
+
+// Largest tree of the forest, substituted when the shader is built
+const MAX_NODES: u32 = MAX_NODES_PLACEHOLDERu;
+
+// Signed a <= b: compare the high words as i32, the low words unsigned on a tie
+fn fixed_le(a: vec2<u32>, b: vec2<u32>) -> bool {
+    let a_high = bitcast<i32>(a.y);
+    let b_high = bitcast<i32>(b.y);
+    return select(a_high < b_high, a.x <= b.x, a_high == b_high);
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let sample = id.y * params.row_stride + id.x;
+    if (sample >= params.num_samples) {
+        return;
+    }
+
+    let row = sample * params.num_features;
+    var values: array<vec2<u32>, MAX_NODES>;
+    for (var t = params.first_tree; t < params.end_tree; t++) {
+        let first = trees[t];
+        for (var i = trees[t + 1u] - first; i > 0u; i--) {
+            let node = nodes[first + i - 1u];
+            let x = features[row + node.feature];
+            var in_set = false;
+            for (var m = node.members_start; m < node.members_end; m++) {
+                in_set = in_set | all(members[m] == x);
+            }
+            let go_left = select(fixed_le(x, node.value), in_set, node.kind == 2u);
+            let child = select(values[node.right], values[node.left], go_left);
+            values[i - 1u] = select(child, node.value, node.kind == 0u);
+        }
+        outputs[sample * params.num_trees + t] = values[0];
+    }
+}
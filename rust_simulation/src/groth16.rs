@@ -0,0 +1,529 @@
+// Groth16 proofs of the R1CS circuit over BN254, and the Solidity contract verifying them
+// `Groth16Keys` runs the circuit-specific setup of `ModelCircuit` for one model and proves its
+// predictions. `VerifierContract` holds the verifying key as 32-byte words and renders it as a
+// Solidity contract whose `verifyProof(proof, publicInputs)` takes the prediction and model hash
+// words of `PublicInputs::abi_encode` and checks the proof with the BN254 precompiles (ecAdd 0x06,
+// ecMul 0x07, ecPairing 0x08).
+// `VerifierContract::verify` runs the same steps with arkworks in place of the precompiles, on the
+// same byte layouts, so the calldata can be checked without an EVM.
+//
+// The circuit's only public input is the prediction. The model is fixed by the keys, and the
+// contract checks the `modelHash` word against the hash it was generated for. The features stay
+// private, and the circuit does not hash them, so the contract takes no `featuresCommitment`: it
+// could not tell a true one from any other. A contract that needs the features bound must take
+// them from a plonky2 proof.
+//
+// The setup draws its toxic waste from the OS and forgets it, which is fine for one operator but
+// not a ceremony: whoever runs `setup` could forge proofs.
+
+use std::fmt;
+
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BigInt, BigInteger, PrimeField, Zero};
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_snark::SNARK;
+use ark_std::rand::rngs::OsRng;
+
+use crate::abi::{PublicInputs, PUBLIC_INPUTS_LEN};
+use crate::keys::{KeyError, KeyId, KeyKind, KeySource, KeyStore};
+use crate::r1cs::ModelCircuit;
+use crate::sha256::to_hex;
+use crate::{witness, Model};
+
+/// Length of a proof's calldata: `abi.encode(uint256[2] a, uint256[4] b, uint256[2] c)`
+pub const PROOF_CALLDATA_LEN: usize = 256;
+
+/// Length of the contract's public inputs: `abi.encode(int256 prediction, bytes32 modelHash)`
+pub const INPUTS_CALLDATA_LEN: usize = 64;
+
+/// Backend name of the cached proving keys
+const KEY_BACKEND: &str = concat!("groth16-bn254-", env!("CARGO_PKG_VERSION"));
+
+/// One big-endian 256-bit EVM word
+type Word = [u8; 32];
+
+/// Error raised by Groth16 setup and proving
+#[derive(Debug)]
+pub enum Groth16Error {
+    /// Generating the keys or the proof failed
+    Synthesis(String),
+    /// The keys were made for another model
+    ModelMismatch,
+    /// Reading or writing the cached proving key failed
+    Key(KeyError),
+}
+
+impl fmt::Display for Groth16Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Groth16Error::Synthesis(message) => write!(f, "constraint synthesis failed: {}", message),
+            Groth16Error::ModelMismatch => write!(f, "the keys were generated for another model"),
+            Groth16Error::Key(e) => write!(f, "proving key: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Groth16Error {}
+
+impl From<KeyError> for Groth16Error {
+    fn from(e: KeyError) -> Self {
+        Groth16Error::Key(e)
+    }
+}
+
+/// Proving key of one model's R1CS circuit
+pub struct Groth16Keys {
+    model_hash: [u8; 32],
+    proving_key: ProvingKey<Bn254>,
+}
+
+/// Groth16 proof with the public inputs it is for
+#[derive(Debug, Clone)]
+pub struct Groth16Proof {
+    pub proof: Proof<Bn254>,
+    pub public_inputs: PublicInputs,
+}
+
+impl Groth16Keys {
+    /// Run the circuit-specific setup of `model` with randomness from the OS
+    pub fn setup(model: &Model) -> Result<Groth16Keys, Groth16Error> {
+        let (proving_key, _) = Groth16::<Bn254>::circuit_specific_setup(ModelCircuit::blank(model), &mut OsRng)
+            .map_err(|e| Groth16Error::Synthesis(e.to_string()))?;
+        Ok(Groth16Keys { model_hash: model.content_hash(), proving_key })
+    }
+
+    /// Keys for `model` from `store`, running the setup (and storing its keys) on a miss
+    ///
+    /// A proof only verifies against the contract generated from the same setup, so both must come
+    /// from one store.
+    pub fn cached(model: &Model, store: &KeyStore) -> Result<(Groth16Keys, KeySource), Groth16Error> {
+        let model_hash = model.content_hash();
+        let id = KeyId::new(model_hash, KEY_BACKEND, KeyKind::Proving);
+        // The store checks the payload against its SHA-256, so the points need no curve checks
+        let cached = store
+            .load(&id)
+            .ok()
+            .flatten()
+            .and_then(|bytes| ProvingKey::<Bn254>::deserialize_uncompressed_unchecked(bytes.as_slice()).ok());
+        if let Some(proving_key) = cached {
+            return Ok((Groth16Keys { model_hash, proving_key }, KeySource::Cached));
+        }
+        let keys = Groth16Keys::setup(model)?;
+        let mut bytes = Vec::new();
+        keys.proving_key.serialize_uncompressed(&mut bytes).expect("writing to a Vec cannot fail");
+        store.store(&id, &bytes)?;
+        Ok((keys, KeySource::Generated))
+    }
+
+    /// Key that verifies this setup's proofs
+    pub fn verifying_key(&self) -> &VerifyingKey<Bn254> {
+        &self.proving_key.vk
+    }
+
+    /// Contract verifying this setup's proofs
+    pub fn contract(&self) -> VerifierContract {
+        VerifierContract::new(self.verifying_key(), self.model_hash)
+    }
+
+    /// Prove the prediction of `model` for one feature vector
+    ///
+    /// # Arguments
+    /// * `model` - Model the keys were generated for
+    /// * `features` - Private input features (scaled by 10^10, within the circuit's 62-bit range)
+    ///
+    /// # Returns
+    /// * `Result<Groth16Proof, Groth16Error>` - The proof and its public inputs
+    pub fn prove(&self, model: &Model, features: &[i64]) -> Result<Groth16Proof, Groth16Error> {
+        if model.content_hash() != self.model_hash {
+            return Err(Groth16Error::ModelMismatch);
+        }
+        let public_inputs = PublicInputs::from_bundle(&witness::prepare_with_hash(model, self.model_hash, features));
+        let proof = Groth16::<Bn254>::prove(&self.proving_key, ModelCircuit::new(model, features.to_vec()), &mut OsRng)
+            .map_err(|e| Groth16Error::Synthesis(e.to_string()))?;
+        Ok(Groth16Proof { proof, public_inputs })
+    }
+}
+
+impl Groth16Proof {
+    /// `proof` argument of `verifyProof`: A, B and C, each G2 coordinate imaginary part first
+    pub fn calldata(&self) -> [u8; PROOF_CALLDATA_LEN] {
+        let mut calldata = [0u8; PROOF_CALLDATA_LEN];
+        calldata[..64].copy_from_slice(&g1_words(&self.proof.a).concat());
+        calldata[64..192].copy_from_slice(&g2_words(&self.proof.b).concat());
+        calldata[192..].copy_from_slice(&g1_words(&self.proof.c).concat());
+        calldata
+    }
+
+    /// `publicInputs` argument of `verifyProof`: the first two words of `PublicInputs::abi_encode`
+    ///
+    /// The features commitment is left out, as the proof does not bind it.
+    pub fn inputs_calldata(&self) -> [u8; INPUTS_CALLDATA_LEN] {
+        self.public_inputs.abi_encode()[..INPUTS_CALLDATA_LEN].try_into().expect("64 bytes")
+    }
+}
+
+/// Verifying key of one model as the constants of its Solidity verifier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifierContract {
+    /// `content_hash` of the model the keys were generated for
+    pub model_hash: [u8; 32],
+    /// alpha in G1: x, y
+    pub alpha: [Word; 2],
+    /// beta, gamma and delta in G2: x.im, x.re, y.im, y.re (the precompile's order)
+    pub beta: [Word; 4],
+    pub gamma: [Word; 4],
+    pub delta: [Word; 4],
+    /// Input commitments IC0 (constant term) and IC1 (prediction) in G1
+    pub ic: [[Word; 2]; 2],
+}
+
+impl VerifierContract {
+    /// Constants of a verifying key of `ModelCircuit` for the model with content hash `model_hash`
+    ///
+    /// # Panics
+    /// If `key` has another number of public inputs than an unguarded `ModelCircuit`
+    pub fn new(key: &VerifyingKey<Bn254>, model_hash: [u8; 32]) -> VerifierContract {
+        assert_eq!(key.gamma_abc_g1.len(), 2, "the circuit has one public input");
+        VerifierContract {
+            model_hash,
+            alpha: g1_words(&key.alpha_g1),
+            beta: g2_words(&key.beta_g2),
+            gamma: g2_words(&key.gamma_g2),
+            delta: g2_words(&key.delta_g2),
+            ic: [g1_words(&key.gamma_abc_g1[0]), g1_words(&key.gamma_abc_g1[1])],
+        }
+    }
+
+    /// Named `uint256` constants of the contract, in declaration order
+    pub fn constants(&self) -> Vec<(String, Word)> {
+        let mut constants = Vec::new();
+        let mut push = |name: &str, words: &[Word], parts: &[&str]| {
+            for (word, part) in words.iter().zip(parts) {
+                constants.push((format!("{}_{}", name, part), *word));
+            }
+        };
+        push("ALPHA", &self.alpha, &["X", "Y"]);
+        for (name, point) in [("BETA", &self.beta), ("GAMMA", &self.gamma), ("DELTA", &self.delta)] {
+            push(name, point, &["X_IM", "X_RE", "Y_IM", "Y_RE"]);
+        }
+        push("IC0", &self.ic[0], &["X", "Y"]);
+        push("IC1", &self.ic[1], &["X", "Y"]);
+        constants
+    }
+
+    /// Solidity source of the verifier contract `RainfallVerifier`
+    pub fn solidity(&self) -> String {
+        let mut constants = String::new();
+        for (name, word) in self.constants() {
+            constants.push_str(&format!("    uint256 constant {} = 0x{};\n", name, to_hex(&word)));
+        }
+        // Pairing input of `pairingCheck`: -A, B, then alpha, beta, vk_x, gamma, C, delta
+        let pairing = ["a[0]", "(Q - a[1]) % Q", "b[0]", "b[1]", "b[2]", "b[3]"]
+            .into_iter()
+            .map(String::from)
+            .chain(["ALPHA_X", "ALPHA_Y"].map(String::from))
+            .chain(["X_IM", "X_RE", "Y_IM", "Y_RE"].map(|part| format!("BETA_{}", part)))
+            .chain(["vkX[0]", "vkX[1]"].map(String::from))
+            .chain(["X_IM", "X_RE", "Y_IM", "Y_RE"].map(|part| format!("GAMMA_{}", part)))
+            .chain(["c[0]", "c[1]"].map(String::from))
+            .chain(["X_IM", "X_RE", "Y_IM", "Y_RE"].map(|part| format!("DELTA_{}", part)))
+            .enumerate()
+            .map(|(i, value)| format!("        input[{}] = {};\n", i, value))
+            .collect::<String>();
+        format!(
+            r#"// SPDX-License-Identifier: MIT
+// Generated by `predict solidity-verifier`; proofs come from `predict prove-groth16` with the same key cache
+pragma solidity ^0.8.20;
+
+/// @title Groth16 verifier of rainfall predictions
+/// @notice Checks BN254 Groth16 proofs that the model with content hash MODEL_HASH predicts a value
+contract RainfallVerifier {{
+    /// BN254 base field modulus
+    uint256 constant Q = 0x30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47;
+    /// BN254 scalar field modulus
+    uint256 constant R = 0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001;
+
+    bytes32 public constant MODEL_HASH = 0x{model_hash};
+
+{constants}
+    /// @param proof abi.encode(uint256[2] a, uint256[4] b, uint256[2] c), G2 coordinates imaginary part first
+    /// @param publicInputs abi.encode(int256 prediction, bytes32 modelHash)
+    /// @return Whether the model predicts `prediction` for some features, which the proof keeps private
+    function verifyProof(bytes calldata proof, bytes calldata publicInputs) external view returns (bool) {{
+        if (proof.length != {proof_len} || publicInputs.length != {inputs_len}) return false;
+        (int256 prediction, bytes32 modelHash) = abi.decode(publicInputs, (int256, bytes32));
+        if (modelHash != MODEL_HASH || prediction < type(int64).min || prediction > type(int64).max) return false;
+        (uint256[2] memory a, uint256[4] memory b, uint256[2] memory c) =
+            abi.decode(proof, (uint256[2], uint256[4], uint256[2]));
+        if (a[1] >= Q) return false;
+        // The prediction as a field element is the circuit's public input
+        return pairingCheck(a, b, c, prediction < 0 ? R - uint256(-prediction) : uint256(prediction));
+    }}
+
+    /// e(-A, B) e(alpha, beta) e(vk_x, gamma) e(C, delta) == 1, with vk_x = IC0 + publicInput * IC1
+    function pairingCheck(uint256[2] memory a, uint256[4] memory b, uint256[2] memory c, uint256 publicInput)
+        internal
+        view
+        returns (bool)
+    {{
+        uint256[3] memory term;
+        term[0] = IC1_X;
+        term[1] = IC1_Y;
+        term[2] = publicInput;
+        uint256[4] memory sum;
+        bool ok;
+        assembly ("memory-safe") {{
+            ok := staticcall(gas(), 0x07, term, 0x60, sum, 0x40)
+        }}
+        sum[2] = IC0_X;
+        sum[3] = IC0_Y;
+        uint256[2] memory vkX;
+        assembly ("memory-safe") {{
+            ok := and(ok, staticcall(gas(), 0x06, sum, 0x80, vkX, 0x40))
+        }}
+
+        uint256[24] memory input;
+{pairing}        uint256[1] memory result;
+        assembly ("memory-safe") {{
+            ok := and(ok, staticcall(gas(), 0x08, input, 0x300, result, 0x20))
+        }}
+        return ok && result[0] == 1;
+    }}
+}}
+"#,
+            model_hash = to_hex(&self.model_hash),
+            constants = constants,
+            proof_len = PROOF_CALLDATA_LEN,
+            inputs_len = INPUTS_CALLDATA_LEN,
+            pairing = pairing,
+        )
+    }
+
+    /// `verifyProof(proof, publicInputs)`, with the precompiles evaluated by arkworks
+    ///
+    /// Runs the contract's checks in its order on the same words, so calldata accepted here is
+    /// accepted on-chain.
+    ///
+    /// # Arguments
+    /// * `proof` - `Groth16Proof::calldata`
+    /// * `public_inputs` - `Groth16Proof::inputs_calldata`
+    ///
+    /// # Returns
+    /// * `bool` - What the contract returns
+    pub fn verify(&self, proof: &[u8], public_inputs: &[u8]) -> bool {
+        if proof.len() != PROOF_CALLDATA_LEN || public_inputs.len() != INPUTS_CALLDATA_LEN {
+            return false;
+        }
+        // `abi_decode` rejects exactly the words outside int64 that the contract rejects; the
+        // commitment word it also reads is left zero
+        let mut encoded = [0u8; PUBLIC_INPUTS_LEN];
+        encoded[..INPUTS_CALLDATA_LEN].copy_from_slice(public_inputs);
+        let Ok(inputs) = PublicInputs::abi_decode(&encoded) else {
+            return false;
+        };
+        if inputs.model_hash != self.model_hash {
+            return false;
+        }
+        let word = |i: usize| -> Word { proof[32 * i..32 * (i + 1)].try_into().expect("32 bytes") };
+        let Some(a_y) = word_to_fq(&word(1)) else {
+            return false;
+        };
+
+        let input = fr_word(Fr::from(inputs.prediction));
+        let Some(term) = ec_mul(&self.ic[1], &input) else {
+            return false;
+        };
+        let Some(vk_x) = ec_add(&term, &self.ic[0]) else {
+            return false;
+        };
+
+        let mut pairing: Vec<Word> = vec![word(0), fq_word(-a_y), word(2), word(3), word(4), word(5)];
+        pairing.extend(self.alpha);
+        pairing.extend(self.beta);
+        pairing.extend(vk_x);
+        pairing.extend(self.gamma);
+        pairing.extend([word(6), word(7)]);
+        pairing.extend(self.delta);
+        ec_pairing(&pairing) == Some(true)
+    }
+}
+
+/// Big-endian word of a base field element
+fn fq_word(value: Fq) -> Word {
+    value.into_bigint().to_bytes_be().try_into().expect("32 bytes")
+}
+
+/// Big-endian word of a scalar field element
+fn fr_word(value: Fr) -> Word {
+    value.into_bigint().to_bytes_be().try_into().expect("32 bytes")
+}
+
+/// Base field element of a word, or None if it is not below the modulus
+fn word_to_fq(word: &Word) -> Option<Fq> {
+    let mut limbs = [0u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(word.rchunks_exact(8)) {
+        *limb = u64::from_be_bytes(chunk.try_into().expect("8 bytes"));
+    }
+    Fq::from_bigint(BigInt::new(limbs))
+}
+
+/// Precompile encoding of a G1 point: x, y (the point at infinity is 0, 0)
+fn g1_words(point: &G1Affine) -> [Word; 2] {
+    match point.xy() {
+        Some((x, y)) => [fq_word(x), fq_word(y)],
+        None => [[0; 32]; 2],
+    }
+}
+
+/// Precompile encoding of a G2 point: x.im, x.re, y.im, y.re (the point at infinity is all zero)
+fn g2_words(point: &G2Affine) -> [Word; 4] {
+    match point.xy() {
+        Some((x, y)) => [fq_word(x.c1), fq_word(x.c0), fq_word(y.c1), fq_word(y.c0)],
+        None => [[0; 32]; 4],
+    }
+}
+
+/// G1 point of its precompile encoding, or None if it is not on the curve
+fn g1_point(words: &[Word]) -> Option<G1Affine> {
+    let (x, y) = (word_to_fq(&words[0])?, word_to_fq(&words[1])?);
+    if x.is_zero() && y.is_zero() {
+        return Some(G1Affine::zero());
+    }
+    let point = G1Affine::new_unchecked(x, y);
+    point.is_on_curve().then_some(point)
+}
+
+/// G2 point of its precompile encoding, or None if it is not in the prime-order subgroup
+fn g2_point(words: &[Word]) -> Option<G2Affine> {
+    let [x_im, x_re, y_im, y_re] = [0, 1, 2, 3].map(|i| word_to_fq(&words[i]));
+    let (x, y) = (Fq2::new(x_re?, x_im?), Fq2::new(y_re?, y_im?));
+    if x.is_zero() && y.is_zero() {
+        return Some(G2Affine::zero());
+    }
+    let point = G2Affine::new_unchecked(x, y);
+    (point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve()).then_some(point)
+}
+
+/// ecAdd (0x06)
+fn ec_add(a: &[Word; 2], b: &[Word; 2]) -> Option<[Word; 2]> {
+    Some(g1_words(&(g1_point(a)? + g1_point(b)?).into_affine()))
+}
+
+/// ecMul (0x07); the scalar is any 256-bit word, reduced by the group order
+fn ec_mul(point: &[Word; 2], scalar: &Word) -> Option<[Word; 2]> {
+    Some(g1_words(&(g1_point(point)? * Fr::from_be_bytes_mod_order(scalar)).into_affine()))
+}
+
+/// ecPairing (0x08): whether the product of the pairings of the (G1, G2) pairs is one
+fn ec_pairing(words: &[Word]) -> Option<bool> {
+    let mut g1 = Vec::new();
+    let mut g2 = Vec::new();
+    for pair in words.chunks(6) {
+        g1.push(g1_point(&pair[..2])?);
+        g2.push(g2_point(&pair[2..])?);
+    }
+    Some(Bn254::multi_pairing(g1, g2).is_zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builtin_model, NUM_FEATURES};
+
+    /// The contract's constants as Solidity declares them
+    fn parse_constants(source: &str) -> Vec<(String, Word)> {
+        source
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("uint256 constant "))
+            .filter_map(|line| {
+                let (name, value) = line.strip_suffix(';')?.split_once(" = 0x")?;
+                Some((name.to_string(), hex_word(value)))
+            })
+            .filter(|(name, _)| name != "Q" && name != "R")
+            .collect()
+    }
+
+    #[test]
+    fn test_contract_accepts_abi_encoded_inputs() {
+        let model = builtin_model();
+        let keys = Groth16Keys::setup(&model).unwrap();
+        let mut features = vec![0i64; NUM_FEATURES];
+        features[34] = 130000000000;
+        features[77] = 40000000000;
+        let proof = keys.prove(&model, &features).unwrap();
+        assert_eq!(proof.public_inputs.prediction, model.predict(&features));
+        assert_eq!(proof.public_inputs.features_hash, crate::hash_features(&features));
+
+        // The generated source declares exactly the words `verify` runs on
+        let contract = keys.contract();
+        let source = contract.solidity();
+        assert_eq!(parse_constants(&source), contract.constants());
+        assert!(source.contains(&format!("MODEL_HASH = 0x{};", to_hex(&model.content_hash()))));
+        assert_eq!(source.matches("        input[").count(), 24);
+
+        let calldata = proof.calldata();
+        let encoded = proof.inputs_calldata();
+        assert_eq!(encoded[..], proof.public_inputs.abi_encode()[..INPUTS_CALLDATA_LEN]);
+        assert!(contract.verify(&calldata, &encoded));
+
+        // Another prediction, another model or a truncated argument is refused
+        let inputs = |public_inputs: PublicInputs| Groth16Proof { public_inputs, ..proof.clone() }.inputs_calldata();
+        let shifted = PublicInputs { prediction: proof.public_inputs.prediction + 1, ..proof.public_inputs };
+        assert!(!contract.verify(&calldata, &inputs(shifted)));
+        let other = PublicInputs { model_hash: [0; 32], ..proof.public_inputs };
+        assert!(!contract.verify(&calldata, &inputs(other)));
+        assert!(!contract.verify(&calldata[1..], &encoded));
+        assert!(!contract.verify(&calldata, &encoded[..32]));
+        // The features commitment is not an argument, as the proof does not bind it
+        assert!(!contract.verify(&calldata, &proof.public_inputs.abi_encode()));
+        assert!(source.contains("abi.decode(publicInputs, (int256, bytes32))"));
+
+        // A point off the curve fails the precompile; A.y at the modulus is refused before it
+        let mut broken = calldata;
+        broken[255] ^= 1;
+        assert!(!contract.verify(&broken, &encoded));
+        let mut unreduced = calldata;
+        unreduced[32..64].copy_from_slice(&hex_word("30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47"));
+        assert!(!contract.verify(&unreduced, &encoded));
+    }
+
+    #[test]
+    fn test_negative_prediction_input() {
+        let model = builtin_model().with_base_score(-50_000_000_000);
+        let keys = Groth16Keys::setup(&model).unwrap();
+        let features = vec![0i64; NUM_FEATURES];
+        let proof = keys.prove(&model, &features).unwrap();
+        assert!(proof.public_inputs.prediction < 0);
+        assert_eq!(proof.inputs_calldata()[0], 0xff);
+        assert!(keys.contract().verify(&proof.calldata(), &proof.inputs_calldata()));
+
+        assert!(matches!(keys.prove(&builtin_model(), &features), Err(Groth16Error::ModelMismatch)));
+    }
+
+    #[test]
+    fn test_precompile_encoding() {
+        let generator = G1Affine::generator();
+        assert_eq!(g1_words(&generator)[0][31], 1);
+        assert_eq!(g1_words(&generator)[1][31], 2);
+        assert_eq!(g1_point(&g1_words(&G1Affine::zero())), Some(G1Affine::zero()));
+        assert_eq!(g2_point(&g2_words(&G2Affine::generator())), Some(G2Affine::generator()));
+        // Swapping the real and imaginary parts leaves the curve
+        let mut swapped = g2_words(&G2Affine::generator());
+        swapped.swap(0, 1);
+        assert_eq!(g2_point(&swapped), None);
+
+        let two = fr_word(Fr::from(2u64));
+        let doubled = ec_mul(&g1_words(&generator), &two).unwrap();
+        assert_eq!(ec_add(&g1_words(&generator), &g1_words(&generator)), Some(doubled));
+        let minus_one = fr_word(-Fr::from(1u64));
+        assert_eq!(ec_mul(&g1_words(&generator), &minus_one), Some(g1_words(&-generator)));
+    }
+
+    fn hex_word(hex: &str) -> Word {
+        let bytes: Vec<u8> = (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect();
+        bytes.try_into().expect("a 32-byte word")
+    }
+}
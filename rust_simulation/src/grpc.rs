@@ -0,0 +1,351 @@
+// gRPC inference and proving service (feature `grpc`)
+// A tonic server exposing the `Inference` service of proto/inference.proto, so other services of
+// a weather platform can score the model remotely: `Predict` and `PredictBatch` evaluate
+// fixed-point feature vectors, and `ProvePrediction` (with the `plonky2` feature) also returns a
+// `ModelProof` of the prediction. Requests go through a `Predictor`, which picks the model they
+// name and records the outcome; `serve` scores one model with `Ensemble::try_eval`. The messages below
+// mirror the .proto file; the service code is generated by build.rs.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use tonic::{Request, Response, Status};
+
+use crate::model::Model;
+use crate::registry::{ModelRegistry, Predictor, RegisteredModel, RegistryError};
+
+/// `Predict` and `ProvePrediction` input: one feature vector at the model's scale
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PredictRequest {
+    #[prost(sint64, repeated, tag = "1")]
+    pub features: Vec<i64>,
+    /// Registry id of the model to score with; the default model if empty
+    #[prost(string, tag = "2")]
+    pub model: String,
+    /// Version of that model; its active version if empty
+    #[prost(string, tag = "3")]
+    pub version: String,
+}
+
+/// `Predict` output
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PredictResponse {
+    #[prost(sint64, tag = "1")]
+    pub prediction: i64,
+    /// Fixed-point scale of the prediction
+    #[prost(int64, tag = "2")]
+    pub scale: i64,
+}
+
+/// One row of a `PredictBatchRequest`
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FeatureVector {
+    #[prost(sint64, repeated, tag = "1")]
+    pub features: Vec<i64>,
+}
+
+/// `PredictBatch` input
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PredictBatchRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub rows: Vec<FeatureVector>,
+    /// Model and version every row is scored with, as in `PredictRequest`
+    #[prost(string, tag = "2")]
+    pub model: String,
+    #[prost(string, tag = "3")]
+    pub version: String,
+}
+
+/// `PredictBatch` output, one prediction per row in request order
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PredictBatchResponse {
+    #[prost(sint64, repeated, tag = "1")]
+    pub predictions: Vec<i64>,
+    #[prost(int64, tag = "2")]
+    pub scale: i64,
+}
+
+/// `ProvePrediction` output
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProveResponse {
+    #[prost(sint64, tag = "1")]
+    pub prediction: i64,
+    /// `ModelProof::to_bytes` of the proof
+    #[prost(bytes = "vec", tag = "2")]
+    pub proof: Vec<u8>,
+    /// Poseidon hash of the private features
+    #[prost(uint64, repeated, tag = "3")]
+    pub features_hash: Vec<u64>,
+}
+
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/rainfall.inference.Inference.rs"));
+}
+
+pub use generated::inference_client::InferenceClient;
+pub use generated::inference_server::{Inference, InferenceServer};
+
+/// `Inference` implementation scoring through a `Predictor`
+#[derive(Clone)]
+pub struct InferenceService {
+    predictor: Arc<dyn Predictor>,
+}
+
+impl InferenceService {
+    /// Serve predictions (and proofs) of `model`
+    pub fn new(model: Model) -> InferenceService {
+        InferenceService::with_predictor(Arc::new(ModelRegistry::from(model)))
+    }
+
+    /// Serve the entries `predictor` hands out, through its scoring path
+    pub fn with_predictor(predictor: Arc<dyn Predictor>) -> InferenceService {
+        InferenceService { predictor }
+    }
+
+    /// Entry a request names with its `model` and `version` (empty for the default), reporting a
+    /// request naming no registered model as rejected
+    fn route(&self, model: &str, version: &str) -> Result<Arc<RegisteredModel>, Status> {
+        let (model, version) = (Some(model).filter(|id| !id.is_empty()), Some(version).filter(|v| !v.is_empty()));
+        self.predictor.entry(model, version).map_err(|e| {
+            self.predictor.rejected();
+            match e {
+                RegistryError::UnknownModel(_) | RegistryError::UnknownVersion { .. } => {
+                    Status::not_found(e.to_string())
+                }
+                _ => Status::unavailable(e.to_string()),
+            }
+        })
+    }
+
+    /// Entry to score a request with, and its validated features
+    fn validated(&self, request: &PredictRequest) -> Result<Arc<RegisteredModel>, Status> {
+        let entry = self.route(&request.model, &request.version)?;
+        self.check(&entry, &request.features)?;
+        Ok(entry)
+    }
+
+    fn check(&self, entry: &RegisteredModel, features: &[i64]) -> Result<(), Status> {
+        self.predictor.validate(entry, features).map_err(|message| {
+            self.predictor.rejected();
+            Status::invalid_argument(message)
+        })
+    }
+
+    fn eval(&self, entry: &RegisteredModel, features: &[i64], started: Instant) -> Result<i64, Status> {
+        let prediction = self.predictor.predict(entry, features).map_err(Status::invalid_argument)?;
+        self.predictor.served(started.elapsed());
+        Ok(prediction)
+    }
+}
+
+#[tonic::async_trait]
+impl Inference for InferenceService {
+    async fn predict(&self, request: Request<PredictRequest>) -> Result<Response<PredictResponse>, Status> {
+        let started = Instant::now();
+        let entry = self.validated(request.get_ref())?;
+        let prediction = self.eval(&entry, &request.get_ref().features, started)?;
+        Ok(Response::new(PredictResponse { prediction, scale: entry.model.scale() }))
+    }
+
+    async fn predict_batch(
+        &self,
+        request: Request<PredictBatchRequest>,
+    ) -> Result<Response<PredictBatchResponse>, Status> {
+        let started = Instant::now();
+        // One entry for the whole batch, so a model swapped in meanwhile never scores half of it
+        let entry = self.route(&request.get_ref().model, &request.get_ref().version)?;
+        let rows = &request.get_ref().rows;
+        let in_row = |row: usize| move |e: Status| Status::invalid_argument(format!("row {}: {}", row, e.message()));
+        for (row, vector) in rows.iter().enumerate() {
+            self.check(&entry, &vector.features).map_err(in_row(row))?;
+        }
+        let predictions = rows
+            .iter()
+            .enumerate()
+            .map(|(row, vector)| self.eval(&entry, &vector.features, started).map_err(in_row(row)))
+            .collect::<Result<Vec<i64>, Status>>()?;
+        Ok(Response::new(PredictBatchResponse { predictions, scale: entry.model.scale() }))
+    }
+
+    #[cfg(feature = "plonky2")]
+    async fn prove_prediction(&self, request: Request<PredictRequest>) -> Result<Response<ProveResponse>, Status> {
+        use crate::zk::plonky2::proof_features_hash;
+
+        let started = Instant::now();
+        let entry = self.validated(request.get_ref())?;
+        let features = request.into_inner().features;
+        let prediction = self.eval(&entry, &features, started)?;
+        // Building the circuit and proving take seconds, so they run off the async workers
+        let proof = tokio::task::spawn_blocking(move || {
+            let started = Instant::now();
+            (entry.prove(&features), started.elapsed())
+        })
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?;
+        let (proof, duration) = proof;
+        self.predictor.proved(duration, proof.is_ok());
+        let proof = proof.map_err(|e| Status::internal(e.to_string()))?;
+        let features_hash = proof_features_hash(&proof.proof).map(Vec::from).unwrap_or_default();
+        Ok(Response::new(ProveResponse { prediction, proof: proof.to_bytes(), features_hash }))
+    }
+
+    #[cfg(not(feature = "plonky2"))]
+    async fn prove_prediction(&self, request: Request<PredictRequest>) -> Result<Response<ProveResponse>, Status> {
+        self.validated(request.get_ref())?;
+        Err(Status::unimplemented("built without the plonky2 feature"))
+    }
+}
+
+/// Serve the `Inference` service for `model` on `address` until the process exits
+///
+/// # Arguments
+/// * `model` - Model to score and prove
+/// * `address` - Socket address to listen on, e.g. 127.0.0.1:50051
+///
+/// # Returns
+/// * `Result<(), tonic::transport::Error>` - Error if the address cannot be bound or serving fails
+pub async fn serve(model: Model, address: SocketAddr) -> Result<(), tonic::transport::Error> {
+    serve_with(Arc::new(ModelRegistry::from(model)), address).await
+}
+
+/// Serve the `Inference` service on `address`, scoring every request through `predictor`
+///
+/// # Arguments
+/// * `predictor` - Scoring path: the entry each request is routed to and what is recorded
+/// * `address` - Socket address to listen on, e.g. 127.0.0.1:50051
+///
+/// # Returns
+/// * `Result<(), tonic::transport::Error>` - Error if the address cannot be bound or serving fails
+pub async fn serve_with(predictor: Arc<dyn Predictor>, address: SocketAddr) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(InferenceServer::new(InferenceService::with_predictor(predictor)))
+        .serve(address)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builtin_model, xgboost_predict, NUM_FEATURES};
+    use tonic::transport::server::TcpIncoming;
+
+    fn features(seed: i64) -> Vec<i64> {
+        (0..NUM_FEATURES as i64).map(|j| (j * seed % 200 - 100) * 1_000_000_000).collect()
+    }
+
+    /// Request for the default model
+    fn request(features: Vec<i64>) -> PredictRequest {
+        PredictRequest { features, ..Default::default() }
+    }
+
+    fn batch(rows: Vec<FeatureVector>) -> PredictBatchRequest {
+        PredictBatchRequest { rows, ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_predict_matches_eval() {
+        let service = InferenceService::new(builtin_model());
+        let response = service.predict(Request::new(request(features(37)))).await.unwrap();
+        assert_eq!(response.get_ref().prediction, xgboost_predict(&features(37)));
+        assert_eq!(response.get_ref().scale, crate::PRECISION_MULTIPLIER);
+
+        let rows = vec![FeatureVector { features: features(3) }, FeatureVector { features: features(5) }];
+        let predicted = service.predict_batch(Request::new(batch(rows))).await.unwrap();
+        assert_eq!(predicted.get_ref().predictions, vec![xgboost_predict(&features(3)), xgboost_predict(&features(5))]);
+
+        let short = service.predict(Request::new(request(vec![0; 3]))).await.unwrap_err();
+        assert_eq!(short.code(), tonic::Code::InvalidArgument);
+        let rows = vec![FeatureVector { features: features(3) }, FeatureVector { features: vec![] }];
+        let status = service.predict_batch(Request::new(batch(rows))).await.unwrap_err();
+        assert!(status.message().starts_with("row 1:"), "{}", status.message());
+    }
+
+    /// Registry scoring that counts what the service reports
+    #[derive(Default)]
+    struct Counting {
+        registry: ModelRegistry,
+        served: std::sync::atomic::AtomicUsize,
+        rejected: std::sync::atomic::AtomicUsize,
+    }
+
+    impl Predictor for Counting {
+        fn entry(&self, id: Option<&str>, version: Option<&str>) -> Result<Arc<RegisteredModel>, crate::RegistryError> {
+            self.registry.entry(id, version)
+        }
+
+        fn served(&self, _latency: std::time::Duration) {
+            self.served.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+
+        fn rejected(&self) {
+            self.rejected.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_predictor_routes_and_records() {
+        use std::sync::atomic::Ordering;
+
+        let counting = Arc::new(Counting::default());
+        let service = InferenceService::with_predictor(counting.clone());
+        let unavailable = service.predict(Request::new(request(features(3)))).await.unwrap_err();
+        assert_eq!(unavailable.code(), tonic::Code::Unavailable);
+
+        counting.registry.register("rain", "1", builtin_model());
+        service.predict(Request::new(request(features(3)))).await.unwrap();
+        let rows = vec![FeatureVector { features: features(3) }, FeatureVector { features: features(5) }];
+        service.predict_batch(Request::new(batch(rows))).await.unwrap();
+        assert_eq!(counting.served.load(Ordering::Relaxed), 3);
+
+        // A batch with a bad row is rejected before any row is scored
+        let rows = vec![FeatureVector { features: features(3) }, FeatureVector { features: vec![0; 3] }];
+        service.predict_batch(Request::new(batch(rows))).await.unwrap_err();
+        // The request to the empty registry counts as rejected too
+        assert_eq!((counting.served.load(Ordering::Relaxed), counting.rejected.load(Ordering::Relaxed)), (3, 2));
+
+        // A newly registered version serves the next request
+        let model = builtin_model();
+        let upgraded = Model::new(model.trees()[..1].to_vec(), model.scale()).with_num_features(NUM_FEATURES);
+        counting.registry.register("rain", "2", upgraded.clone());
+        let response = service.predict(Request::new(request(features(3)))).await.unwrap();
+        assert_eq!(response.get_ref().prediction, upgraded.predict(&features(3)));
+
+        // Requests and batches can name a model and version, as JSON lines can
+        let named = |model: &str, version: &str| PredictRequest {
+            features: features(3),
+            model: model.to_string(),
+            version: version.to_string(),
+        };
+        let response = service.predict(Request::new(named("rain", "1"))).await.unwrap();
+        assert_eq!(response.get_ref().prediction, xgboost_predict(&features(3)));
+        let rows = vec![FeatureVector { features: features(3) }];
+        let pinned = PredictBatchRequest { rows, model: "rain".to_string(), version: "1".to_string() };
+        let predicted = service.predict_batch(Request::new(pinned)).await.unwrap();
+        assert_eq!(predicted.get_ref().predictions, vec![xgboost_predict(&features(3))]);
+        let unknown = service.predict(Request::new(named("snow", ""))).await.unwrap_err();
+        assert_eq!((unknown.code(), unknown.message()), (tonic::Code::NotFound, "unknown model 'snow'"));
+        let unknown = service.predict(Request::new(named("rain", "3"))).await.unwrap_err();
+        assert_eq!(unknown.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_client_round_trip() {
+        let incoming = TcpIncoming::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let address = incoming.local_addr().unwrap();
+        let server = tonic::transport::Server::builder()
+            .add_service(InferenceServer::new(InferenceService::new(builtin_model())))
+            .serve_with_incoming(incoming);
+        tokio::spawn(server);
+
+        let mut client = InferenceClient::connect(format!("http://{}", address)).await.unwrap();
+        let response = client.predict(request(features(11))).await.unwrap();
+        assert_eq!(response.into_inner().prediction, xgboost_predict(&features(11)));
+
+        #[cfg(not(feature = "plonky2"))]
+        {
+            let status = client.prove_prediction(request(features(11))).await.unwrap_err();
+            assert_eq!(status.code(), tonic::Code::Unimplemented);
+        }
+    }
+}
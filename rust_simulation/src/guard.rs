@@ -0,0 +1,133 @@
+// Output guardrail
+// A prediction outside the physically plausible rainfall range points at corrupted inputs or a
+// model used outside its training domain. `OutputGuard` checks the prediction against a
+// fixed-point [min, max] range and either only flags it or also clamps it into the range. The
+// decision is recorded in the execution trace, and `ModelCircuit::with_guard` (feature `r1cs`)
+// proves the same decision as a public flag, so a verifier can tell that the guard ran.
+
+use core::fmt;
+
+/// Where a prediction lies relative to the guard range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GuardDecision {
+    /// Inside [min, max]
+    Within,
+    /// Below min
+    Below,
+    /// Above max
+    Above,
+}
+
+impl GuardDecision {
+    /// Whether the prediction was outside the range
+    pub fn is_flagged(self) -> bool {
+        self != GuardDecision::Within
+    }
+
+    /// Name used in trace exports
+    pub fn name(self) -> &'static str {
+        match self {
+            GuardDecision::Within => "within",
+            GuardDecision::Below => "below",
+            GuardDecision::Above => "above",
+        }
+    }
+}
+
+impl fmt::Display for GuardDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Plausible range of a prediction, in fixed point at the model's scale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutputGuard {
+    pub min: i64,
+    pub max: i64,
+    /// Replace out-of-range predictions by the nearest bound instead of only flagging them
+    pub clamp: bool,
+}
+
+impl OutputGuard {
+    /// Guard that flags predictions outside [min, max]
+    ///
+    /// # Arguments
+    /// * `min` - Smallest plausible prediction (scaled)
+    /// * `max` - Largest plausible prediction (scaled, >= min)
+    ///
+    /// # Returns
+    /// * `OutputGuard` - Flagging guard; see `clamping`
+    pub fn new(min: i64, max: i64) -> OutputGuard {
+        assert!(min <= max, "guard range is empty: {} > {}", min, max);
+        OutputGuard { min, max, clamp: false }
+    }
+
+    /// Also clamp out-of-range predictions into [min, max]
+    pub fn clamping(mut self) -> Self {
+        self.clamp = true;
+        self
+    }
+
+    /// Classify a prediction
+    pub fn decide(&self, prediction: i64) -> GuardDecision {
+        if prediction < self.min {
+            GuardDecision::Below
+        } else if prediction > self.max {
+            GuardDecision::Above
+        } else {
+            GuardDecision::Within
+        }
+    }
+
+    /// Guarded prediction and the decision behind it
+    pub fn apply(&self, prediction: i64) -> (i64, GuardDecision) {
+        let decision = self.decide(prediction);
+        let guarded = match decision {
+            GuardDecision::Below if self.clamp => self.min,
+            GuardDecision::Above if self.clamp => self.max,
+            _ => prediction,
+        };
+        (guarded, decision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builtin_model, NUM_FEATURES};
+
+    #[test]
+    fn test_guard_decisions() {
+        let guard = OutputGuard::new(-5, 10);
+        assert_eq!(guard.apply(-6), (-6, GuardDecision::Below));
+        assert_eq!(guard.apply(-5), (-5, GuardDecision::Within));
+        assert_eq!(guard.apply(10), (10, GuardDecision::Within));
+        assert_eq!(guard.apply(11), (11, GuardDecision::Above));
+        assert!(guard.decide(11).is_flagged());
+
+        let clamping = guard.clamping();
+        assert_eq!(clamping.apply(-6), (-5, GuardDecision::Below));
+        assert_eq!(clamping.apply(i64::MAX), (10, GuardDecision::Above));
+        assert_eq!(clamping.apply(3), (3, GuardDecision::Within));
+    }
+
+    #[test]
+    fn test_guarded_trace() {
+        let model = builtin_model();
+        let features = [0; NUM_FEATURES];
+        let prediction = model.eval(&features);
+
+        let trace = model.eval_with_trace(&features).1.with_guard(OutputGuard::new(prediction + 1, prediction + 9));
+        assert_eq!((trace.raw_output(), trace.output()), (prediction, prediction));
+        assert_eq!(trace.guard.unwrap().decision, GuardDecision::Below);
+
+        let guard = OutputGuard::new(prediction + 1, prediction + 9).clamping();
+        let trace = model.eval_with_trace(&features).1.with_guard(guard);
+        assert_eq!(trace.output(), prediction + 1);
+        let json = trace.to_json().to_string();
+        assert!(json.contains(r#""clamp":true,"decision":"below"}"#), "{}", json);
+    }
+}
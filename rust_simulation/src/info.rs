@@ -0,0 +1,182 @@
+// Model metadata
+// `ModelInfo` names the model a prediction or proof was made with: the name and version set on the
+// ensemble, its shape, the hash of the training data it was fitted from and its fixed-point
+// precision. Provers ship it next to their proofs and verifiers compare it with the model they
+// hold, so a proof is never checked against the wrong model version.
+//
+// Byte layout (little-endian, strings as a u32 length and UTF-8 bytes):
+//   magic "ZKMI" | version u8 | name str | model version str | feature_count u32 | tree_count u32 |
+//   precision i64 | training hash (0u8, or 1u8 | 32 bytes)
+
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::binary::{to_u32, write_digest, write_str, BinaryError, ByteReader};
+use crate::commitment::Digest;
+use crate::ensemble::Ensemble;
+use crate::json::JsonValue;
+use crate::sha256::to_hex;
+
+/// First bytes of an encoded `ModelInfo`
+pub const INFO_MAGIC: [u8; 4] = *b"ZKMI";
+
+const INFO_VERSION: u8 = 1;
+
+/// Identity and shape of a model
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModelInfo {
+    /// Name the model is published under
+    pub name: String,
+    /// Model version
+    pub version: String,
+    /// Number of input features
+    pub feature_count: usize,
+    /// Number of trees
+    pub tree_count: usize,
+    /// Hash of the training data or configuration, if recorded
+    pub training_hash: Option<Digest>,
+    /// Fixed-point scale of thresholds, leaves and predictions
+    pub precision: i64,
+}
+
+impl ModelInfo {
+    /// Encode the metadata to be stored or sent alongside a proof
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(64 + self.name.len() + self.version.len());
+        out.extend_from_slice(&INFO_MAGIC);
+        out.push(INFO_VERSION);
+        write_str(&mut out, &self.name);
+        write_str(&mut out, &self.version);
+        out.extend_from_slice(&to_u32(self.feature_count).to_le_bytes());
+        out.extend_from_slice(&to_u32(self.tree_count).to_le_bytes());
+        out.extend_from_slice(&self.precision.to_le_bytes());
+        write_digest(&mut out, self.training_hash.as_ref());
+        out
+    }
+
+    /// Decode metadata written by `ModelInfo::to_bytes`
+    ///
+    /// # Arguments
+    /// * `bytes` - Encoded metadata
+    ///
+    /// # Returns
+    /// * `Result<ModelInfo, BinaryError>` - The metadata, or why the bytes are malformed
+    pub fn from_bytes(bytes: &[u8]) -> Result<ModelInfo, BinaryError> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.take(4)? != INFO_MAGIC {
+            return Err(BinaryError::BadMagic);
+        }
+        let version = reader.u8()?;
+        if version != INFO_VERSION {
+            return Err(BinaryError::UnsupportedVersion(version));
+        }
+
+        let name = reader.str()?;
+        let model_version = reader.str()?;
+        let feature_count = reader.u32()? as usize;
+        let tree_count = reader.u32()? as usize;
+        let offset = reader.offset;
+        let precision = reader.i64()?;
+        if precision <= 0 {
+            return Err(BinaryError::Invalid { offset, message: "precision must be positive" });
+        }
+        let training_hash = reader.digest()?;
+        if !reader.is_empty() {
+            return Err(BinaryError::TrailingBytes { offset: reader.offset });
+        }
+        Ok(ModelInfo { name, version: model_version, feature_count, tree_count, training_hash, precision })
+    }
+
+    /// JSON form, with the training hash as lowercase hex (or null)
+    pub fn to_json(&self) -> JsonValue {
+        let number = |v: usize| JsonValue::Number(v.to_string());
+        let training_hash = match &self.training_hash {
+            Some(hash) => JsonValue::String(to_hex(hash)),
+            None => JsonValue::Null,
+        };
+        JsonValue::Object(vec![
+            ("name".to_string(), JsonValue::String(self.name.clone())),
+            ("version".to_string(), JsonValue::String(self.version.clone())),
+            ("feature_count".to_string(), number(self.feature_count)),
+            ("tree_count".to_string(), number(self.tree_count)),
+            ("training_hash".to_string(), training_hash),
+            ("precision".to_string(), JsonValue::Number(self.precision.to_string())),
+        ])
+    }
+}
+
+impl fmt::Display for ModelInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = if self.name.is_empty() { "unnamed model" } else { &self.name };
+        write!(f, "{}", name)?;
+        if !self.version.is_empty() {
+            write!(f, " {}", self.version)?;
+        }
+        write!(f, " ({} trees, {} features, scale {})", self.tree_count, self.feature_count, self.precision)?;
+        if let Some(hash) = &self.training_hash {
+            write!(f, " trained on {}", to_hex(hash))?;
+        }
+        Ok(())
+    }
+}
+
+impl Ensemble {
+    /// Metadata identifying this model
+    ///
+    /// # Returns
+    /// * `ModelInfo` - Name, version and training hash set on the ensemble, with its feature
+    ///   count, tree count and scale
+    pub fn model_info(&self) -> ModelInfo {
+        ModelInfo {
+            name: self.name().to_string(),
+            version: self.version().to_string(),
+            feature_count: self.num_features(),
+            tree_count: self.num_trees(),
+            training_hash: self.training_hash().copied(),
+            precision: self.scale(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builtin_model, sha256, MODEL_ID, MODEL_VERSION, NUM_FEATURES, PRECISION_MULTIPLIER};
+
+    #[test]
+    fn test_builtin_model_info() {
+        let info = builtin_model().model_info();
+        assert_eq!(info.name, MODEL_ID);
+        assert_eq!(info.version, MODEL_VERSION);
+        assert_eq!((info.feature_count, info.tree_count), (NUM_FEATURES, 10));
+        assert_eq!(info.precision, PRECISION_MULTIPLIER);
+        assert_eq!(info.training_hash, None);
+        assert_eq!(info.to_string(), "bst1_10 1 (10 trees, 116 features, scale 10000000000)");
+        assert_eq!(
+            info.to_json().to_string(),
+            r#"{"name":"bst1_10","version":"1","feature_count":116,"tree_count":10,"training_hash":null,"precision":10000000000}"#
+        );
+    }
+
+    #[test]
+    fn test_info_round_trip() {
+        let model = builtin_model().with_version("2.0").with_training_hash(sha256(b"train.csv"));
+        let info = model.model_info();
+        let bytes = info.to_bytes();
+        assert_eq!(&bytes[..5], b"ZKMI\x01");
+        assert_eq!(ModelInfo::from_bytes(&bytes).unwrap(), info);
+        // Rescaling keeps the identity but reports the new precision
+        assert_eq!(model.rescale(1000).unwrap().model_info(), ModelInfo { precision: 1000, ..info });
+
+        assert!(matches!(ModelInfo::from_bytes(&bytes[..bytes.len() - 1]), Err(BinaryError::UnexpectedEnd { .. })));
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(ModelInfo::from_bytes(&trailing), Err(BinaryError::TrailingBytes { .. })));
+        assert!(matches!(ModelInfo::from_bytes(b"ZKTE\x04"), Err(BinaryError::BadMagic)));
+    }
+}
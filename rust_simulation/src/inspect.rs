@@ -0,0 +1,489 @@
+// Model inspection
+// Summarizes what a model costs to prove before any circuit is built: the shape of each tree
+// (depth, nodes, leaf range), which features the splits test and over which thresholds, and an
+// estimate of the R1CS constraints `r1cs::ModelCircuit` generates for it. The estimate follows
+// the gadgets one by one:
+//   inputs       one range check of comparison_bits + 2 constraints per feature
+//   comparisons  comparison_bits + 3 per distinct threshold test, 2 per member of a category set
+//                plus up to 2 to combine the members, each shared test counted once
+//   selections   one per split with a split child; a split over two leaves is a linear
+//                combination of its outcome
+//   accumulation accumulator_bits + 2 per tree once the running sum is no longer constant
+//   combiner     the quotient and remainder checks of an averaging ensemble
+//   output       1 for the public prediction
+// Full-width estimates assume 62-bit values; `ModelReport::with_bounds` adds the estimate for
+// range checks sized by a bit-width analysis, and `with_output_bounds` the guaranteed output range
+// over a schema.
+
+use alloc::{string::ToString, vec, vec::Vec};
+use core::fmt;
+
+use crate::bitwidth::{magnitude_bits, ValueBounds};
+use crate::ensemble::{Combiner, Ensemble, Node};
+use crate::interval::OutputBounds;
+use crate::json::JsonValue;
+use crate::prune::leaf_range;
+use crate::sharing::{SharedNode, SplitTest};
+
+/// Value width of the full-range R1CS circuit (`r1cs::VALUE_BITS`)
+const FULL_WIDTH_BITS: usize = 62;
+
+/// Shape of one tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeReport {
+    /// Depth (a single leaf has depth 0) and node counts
+    pub depth: usize,
+    pub nodes: usize,
+    pub leaves: usize,
+    /// Smallest and largest leaf value
+    pub leaf_min: i64,
+    pub leaf_max: i64,
+    /// Distinct split tests this tree is the first to use
+    pub new_tests: usize,
+}
+
+impl TreeReport {
+    /// Split nodes (threshold and categorical)
+    pub fn splits(&self) -> usize {
+        self.nodes - self.leaves
+    }
+}
+
+/// How the splits of an ensemble use one feature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureUsage {
+    /// Feature index
+    pub feature: usize,
+    /// Threshold splits on the feature, and the smallest and largest threshold (scaled)
+    pub threshold_splits: usize,
+    pub threshold_min: i64,
+    pub threshold_max: i64,
+    /// Categorical splits on the feature
+    pub categorical_splits: usize,
+}
+
+impl FeatureUsage {
+    /// All splits on the feature
+    pub fn splits(&self) -> usize {
+        self.threshold_splits + self.categorical_splits
+    }
+}
+
+/// Estimated R1CS constraints of a model circuit, by gadget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConstraintEstimate {
+    /// Width the features are range-checked to and compared at
+    pub comparison_bits: usize,
+    /// Width the running sum is range-checked to
+    pub accumulator_bits: usize,
+    pub inputs: usize,
+    pub comparisons: usize,
+    pub selections: usize,
+    pub accumulation: usize,
+    pub combiner: usize,
+    pub output: usize,
+}
+
+impl ConstraintEstimate {
+    /// Estimate the constraints of `model` with comparisons and accumulator checks of the given widths
+    ///
+    /// # Arguments
+    /// * `model` - Model to estimate
+    /// * `comparison_bits` - Width of the feature range checks and comparisons
+    /// * `accumulator_bits` - Width of the accumulator range checks
+    ///
+    /// # Returns
+    /// * `ConstraintEstimate` - Constraints per gadget
+    pub fn new(model: &Ensemble, comparison_bits: usize, accumulator_bits: usize) -> ConstraintEstimate {
+        let shared = model.share_comparisons();
+        let comparisons = shared
+            .tests()
+            .iter()
+            .map(|test| match test {
+                SplitTest::Threshold { .. } => comparison_bits + 3,
+                SplitTest::Categories { categories, .. } => {
+                    // Members are or-ed pairwise up to three, by one inequality test beyond
+                    let members = categories.len();
+                    2 * members + if members > 3 { 2 } else { members.saturating_sub(1) }
+                }
+            })
+            .sum();
+
+        let mut variable = false;
+        let mut accumulation = 0;
+        for tree in shared.trees() {
+            variable |= matches!(tree, SharedNode::Test { .. });
+            if variable {
+                accumulation += accumulator_bits + 2;
+            }
+        }
+        let combiner = match model.combiner() {
+            Combiner::Sum => 0,
+            _ if !variable => 0,
+            Combiner::Average if model.num_trees() <= 1 => FULL_WIDTH_BITS + 2,
+            Combiner::Average => {
+                let remainder_bits = magnitude_bits(model.num_trees() as i128 - 1);
+                accumulator_bits + 2 + 2 * (remainder_bits + 1) + FULL_WIDTH_BITS + 2
+            }
+        };
+
+        ConstraintEstimate {
+            comparison_bits,
+            accumulator_bits,
+            inputs: model.num_features() * (comparison_bits + 2),
+            comparisons,
+            selections: shared.trees().iter().map(selections).sum(),
+            accumulation,
+            combiner,
+            output: 1,
+        }
+    }
+
+    /// Total constraints
+    pub fn total(&self) -> usize {
+        self.inputs + self.comparisons + self.selections + self.accumulation + self.combiner + self.output
+    }
+
+    /// Machine-readable form
+    pub fn to_json(&self) -> JsonValue {
+        let number = |value: usize| JsonValue::Number(value.to_string());
+        JsonValue::Object(vec![
+            ("comparison_bits".to_string(), number(self.comparison_bits)),
+            ("accumulator_bits".to_string(), number(self.accumulator_bits)),
+            ("inputs".to_string(), number(self.inputs)),
+            ("comparisons".to_string(), number(self.comparisons)),
+            ("selections".to_string(), number(self.selections)),
+            ("accumulation".to_string(), number(self.accumulation)),
+            ("combiner".to_string(), number(self.combiner)),
+            ("output".to_string(), number(self.output)),
+            ("total".to_string(), number(self.total())),
+        ])
+    }
+}
+
+impl fmt::Display for ConstraintEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}-bit comparisons, {}-bit accumulator: inputs {}, comparisons {}, selections {}, accumulation {}",
+            self.total(),
+            self.comparison_bits,
+            self.accumulator_bits,
+            self.inputs,
+            self.comparisons,
+            self.selections,
+            self.accumulation,
+        )?;
+        if self.combiner > 0 {
+            write!(f, ", combiner {}", self.combiner)?;
+        }
+        write!(f, ", output {})", self.output)
+    }
+}
+
+/// Selections of the oblivious evaluation that cost a constraint
+fn selections(node: &SharedNode) -> usize {
+    match node {
+        SharedNode::Leaf(_) => 0,
+        SharedNode::Test { left, right, .. } => {
+            let both_leaves = matches!((&**left, &**right), (SharedNode::Leaf(_), SharedNode::Leaf(_)));
+            usize::from(!both_leaves) + selections(left) + selections(right)
+        }
+    }
+}
+
+/// Count the leaves of a subtree and record the features its splits use
+fn walk(node: &Node, usage: &mut [Option<FeatureUsage>]) -> usize {
+    let (feature, threshold, left, right) = match node {
+        Node::Leaf(_) => return 1,
+        Node::Split { feature, threshold, left, right } => (*feature, Some(*threshold), left, right),
+        Node::Categorical { feature, left, right, .. } => (*feature, None, left, right),
+    };
+    let entry = usage[feature].get_or_insert(FeatureUsage {
+        feature,
+        threshold_splits: 0,
+        threshold_min: i64::MAX,
+        threshold_max: i64::MIN,
+        categorical_splits: 0,
+    });
+    match threshold {
+        Some(threshold) => {
+            entry.threshold_splits += 1;
+            entry.threshold_min = entry.threshold_min.min(threshold);
+            entry.threshold_max = entry.threshold_max.max(threshold);
+        }
+        None => entry.categorical_splits += 1,
+    }
+    walk(left, usage) + walk(right, usage)
+}
+
+/// Structure and estimated proving cost of a model
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelReport {
+    /// Feature count and scale of the model
+    pub num_features: usize,
+    pub scale: i64,
+    pub trees: Vec<TreeReport>,
+    /// Features used by at least one split, in index order
+    pub features: Vec<FeatureUsage>,
+    /// Distinct split tests after comparison sharing
+    pub distinct_tests: usize,
+    /// Estimate for the full-width circuit
+    pub constraints: ConstraintEstimate,
+    /// Estimate with range checks sized by a bit-width analysis
+    pub bounded: Option<ConstraintEstimate>,
+    /// Output range over the inputs a schema accepts
+    pub output: Option<OutputBounds>,
+}
+
+impl Ensemble {
+    /// Summarize the trees, feature usage and estimated constraint count of the ensemble
+    ///
+    /// # Returns
+    /// * `ModelReport` - Per-tree shape, per-feature usage and a full-width constraint estimate
+    pub fn inspect(&self) -> ModelReport {
+        let shared = self.share_comparisons();
+        let mut seen = vec![false; shared.tests().len()];
+        let mut usage = vec![None; self.num_features()];
+        let trees = self
+            .trees()
+            .iter()
+            .zip(shared.trees())
+            .map(|(tree, shared_tree)| {
+                let (leaf_min, leaf_max) = leaf_range(&tree.root);
+                TreeReport {
+                    depth: tree.depth(),
+                    nodes: tree.num_nodes(),
+                    leaves: walk(&tree.root, &mut usage),
+                    leaf_min,
+                    leaf_max,
+                    new_tests: mark_tests(shared_tree, &mut seen),
+                }
+            })
+            .collect();
+
+        ModelReport {
+            num_features: self.num_features(),
+            scale: self.scale(),
+            trees,
+            features: usage.into_iter().flatten().collect(),
+            distinct_tests: shared.tests().len(),
+            constraints: ConstraintEstimate::new(self, FULL_WIDTH_BITS, FULL_WIDTH_BITS),
+            bounded: None,
+            output: None,
+        }
+    }
+}
+
+/// Mark the tests of a shared tree as seen, returning how many were new
+fn mark_tests(node: &SharedNode, seen: &mut [bool]) -> usize {
+    match node {
+        SharedNode::Leaf(_) => 0,
+        SharedNode::Test { test, left, right } => {
+            let new = usize::from(!core::mem::replace(&mut seen[*test], true));
+            new + mark_tests(left, seen) + mark_tests(right, seen)
+        }
+    }
+}
+
+impl ModelReport {
+    /// Add the constraint estimate for range checks sized by `bounds`
+    ///
+    /// # Arguments
+    /// * `model` - The model this report was made from
+    /// * `bounds` - Bit-width analysis of the model
+    pub fn with_bounds(mut self, model: &Ensemble, bounds: &ValueBounds) -> ModelReport {
+        self.bounded = Some(ConstraintEstimate::new(model, bounds.comparison_bits(), bounds.accumulator_bits()));
+        self
+    }
+
+    /// Add the output range of an interval analysis
+    pub fn with_output_bounds(mut self, bounds: OutputBounds) -> ModelReport {
+        self.output = Some(bounds);
+        self
+    }
+
+    /// Split nodes over all trees
+    pub fn splits(&self) -> usize {
+        self.trees.iter().map(TreeReport::splits).sum()
+    }
+
+    /// Machine-readable form
+    pub fn to_json(&self) -> JsonValue {
+        let number = |value: usize| JsonValue::Number(value.to_string());
+        let fixed = |value: i64| JsonValue::Number(value.to_string());
+        let float = |value: i64| JsonValue::Number((value as f64 / self.scale as f64).to_string());
+        let trees = self
+            .trees
+            .iter()
+            .enumerate()
+            .map(|(index, tree)| {
+                JsonValue::Object(vec![
+                    ("tree".to_string(), number(index)),
+                    ("depth".to_string(), number(tree.depth)),
+                    ("nodes".to_string(), number(tree.nodes)),
+                    ("leaves".to_string(), number(tree.leaves)),
+                    ("splits".to_string(), number(tree.splits())),
+                    ("leaf_min".to_string(), fixed(tree.leaf_min)),
+                    ("leaf_max".to_string(), fixed(tree.leaf_max)),
+                    ("new_tests".to_string(), number(tree.new_tests)),
+                ])
+            })
+            .collect();
+        let features = self
+            .features
+            .iter()
+            .map(|usage| {
+                let mut fields = vec![
+                    ("feature".to_string(), number(usage.feature)),
+                    ("splits".to_string(), number(usage.splits())),
+                    ("threshold_splits".to_string(), number(usage.threshold_splits)),
+                    ("categorical_splits".to_string(), number(usage.categorical_splits)),
+                ];
+                if usage.threshold_splits > 0 {
+                    fields.push(("threshold_min".to_string(), fixed(usage.threshold_min)));
+                    fields.push(("threshold_max".to_string(), fixed(usage.threshold_max)));
+                    fields.push(("threshold_min_float".to_string(), float(usage.threshold_min)));
+                    fields.push(("threshold_max_float".to_string(), float(usage.threshold_max)));
+                }
+                JsonValue::Object(fields)
+            })
+            .collect();
+        JsonValue::Object(vec![
+            ("num_features".to_string(), number(self.num_features)),
+            ("scale".to_string(), fixed(self.scale)),
+            ("splits".to_string(), number(self.splits())),
+            ("distinct_tests".to_string(), number(self.distinct_tests)),
+            ("trees".to_string(), JsonValue::Array(trees)),
+            ("features".to_string(), JsonValue::Array(features)),
+            ("constraints".to_string(), self.constraints.to_json()),
+            ("bounded_constraints".to_string(), self.bounded.map_or(JsonValue::Null, |bounded| bounded.to_json())),
+            ("output_bounds".to_string(), self.output.as_ref().map_or(JsonValue::Null, OutputBounds::to_json)),
+        ])
+    }
+}
+
+impl fmt::Display for ModelReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let float = |value: i64| value as f64 / self.scale as f64;
+        writeln!(f, "Tree   Depth  Nodes  Leaves  New tests  Leaf range")?;
+        for (index, tree) in self.trees.iter().enumerate() {
+            writeln!(
+                f,
+                "{:<6} {:>5}  {:>5}  {:>6}  {:>9}  [{:.6}, {:.6}]",
+                index,
+                tree.depth,
+                tree.nodes,
+                tree.leaves,
+                tree.new_tests,
+                float(tree.leaf_min),
+                float(tree.leaf_max),
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{} of {} features used by {} splits ({} distinct tests)",
+            self.features.len(),
+            self.num_features,
+            self.splits(),
+            self.distinct_tests,
+        )?;
+        let mut features: Vec<&FeatureUsage> = self.features.iter().collect();
+        features.sort_by_key(|usage| (core::cmp::Reverse(usage.splits()), usage.feature));
+        writeln!(f, "Feature  Splits  Threshold range")?;
+        for usage in features {
+            write!(f, "f{:<7} {:>6}  ", usage.feature, usage.splits())?;
+            if usage.threshold_splits > 0 {
+                write!(f, "[{:.6}, {:.6}]", float(usage.threshold_min), float(usage.threshold_max))?;
+            }
+            if usage.categorical_splits > 0 {
+                write!(f, " {} categorical", usage.categorical_splits)?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f)?;
+        write!(f, "Estimated constraints: {}", self.constraints)?;
+        if let Some(bounded) = &self.bounded {
+            write!(f, "\nWith bit-width bounds:  {}", bounded)?;
+        }
+        if let Some(output) = &self.output {
+            let (low, high) = output.prediction;
+            let float = |value: i128| value as f64 / self.scale as f64;
+            write!(f, "\n\nOutput range over the schema: [{:.6}, {:.6}] ({})", float(low), float(high), output)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::{BitSet, Tree};
+    use crate::json;
+
+    fn small_model() -> Ensemble {
+        let mut categories = BitSet::new();
+        categories.insert(1);
+        categories.insert(3);
+        let inner = Node::split(1, -2, Node::Leaf(1), Node::Leaf(2));
+        let categorical = Node::categorical(2, categories, 10, Node::Leaf(0), Node::Leaf(6));
+        Ensemble::new(
+            vec![
+                Tree::new(Node::split(0, 5, inner, Node::Leaf(3))),
+                Tree::new(Node::split(0, 5, Node::Leaf(-4), categorical)),
+            ],
+            10,
+        )
+    }
+
+    #[test]
+    fn test_inspect_shape_and_usage() {
+        let report = small_model().inspect();
+        assert_eq!(report.trees.len(), 2);
+        assert_eq!(
+            report.trees[0],
+            TreeReport { depth: 2, nodes: 5, leaves: 3, leaf_min: 1, leaf_max: 3, new_tests: 2 }
+        );
+        // Tree 1 repeats the f0 <= 5 test of tree 0
+        assert_eq!(report.trees[1].new_tests, 1);
+        assert_eq!(report.splits(), 4);
+        assert_eq!(report.distinct_tests, 3);
+
+        let f0 = &report.features[0];
+        assert_eq!((f0.feature, f0.threshold_splits, f0.threshold_min, f0.threshold_max), (0, 2, 5, 5));
+        assert_eq!((report.features[2].feature, report.features[2].categorical_splits), (2, 1));
+    }
+
+    #[test]
+    fn test_constraint_estimate() {
+        let estimate = ConstraintEstimate::new(&small_model(), 10, 12);
+        assert_eq!(estimate.inputs, 3 * 12);
+        // Two threshold tests and one two-member category set
+        assert_eq!(estimate.comparisons, 2 * 13 + 5);
+        // Each root has a split child; the inner splits only have leaves
+        assert_eq!(estimate.selections, 2);
+        assert_eq!(estimate.accumulation, 2 * 14);
+        assert_eq!(estimate.total(), 36 + 31 + 2 + 28 + 1);
+    }
+
+    #[test]
+    fn test_report_json_and_display() {
+        let model = small_model();
+        let bounds = ValueBounds::analyze(&model, 100);
+        let report = model.inspect().with_bounds(&model, &bounds);
+        let parsed = json::parse(&report.to_json().to_string()).unwrap();
+        assert_eq!(parsed.get("distinct_tests").and_then(JsonValue::as_usize), Some(3));
+        assert_eq!(parsed.get("trees").and_then(JsonValue::as_array).map(<[JsonValue]>::len), Some(2));
+        let total = report.bounded.unwrap().total();
+        let bounded = parsed.get("bounded_constraints").and_then(|c| c.get("total"));
+        assert_eq!(bounded.and_then(JsonValue::as_usize), Some(total));
+        assert!(report.bounded.unwrap().total() < report.constraints.total());
+
+        let text = report.to_string();
+        assert!(text.contains("3 of 3 features used by 4 splits (3 distinct tests)"));
+        assert!(text.contains("With bit-width bounds"));
+    }
+}
@@ -0,0 +1,283 @@
+// Interval bounds on the output
+// `ValueBounds` bounds each tree by its smallest and largest leaf, whatever the input. With a
+// `FeatureSchema`, many leaves cannot be reached by any accepted input: a split on a count at 80
+// never goes right when counts stop at 64. `OutputBounds` evaluates every tree over the schema's
+// box instead of a point. Each feature is an interval plus, if accepted, the missing-value
+// sentinel; a split narrows its feature's domain on each side and only explores the sides the
+// domain can reach. The result is a guaranteed range for every tree output, running sum and
+// prediction, which documents what the model can return. When every running sum fits in an i64,
+// it also shows that `fixed_add` never saturates for valid inputs.
+
+use alloc::{string::ToString, vec, vec::Vec};
+use core::fmt;
+
+use crate::ensemble::{Accumulator, Combiner, Ensemble, Node};
+use crate::json::JsonValue;
+use crate::schema::{FeatureSchema, MISSING_VALUE};
+
+/// Values a feature can take on a path: an interval and possibly `MISSING_VALUE`
+#[derive(Debug, Clone, Copy)]
+struct Domain {
+    /// Interval bounds; the interval is empty when `low > high`
+    low: i128,
+    high: i128,
+    missing: bool,
+}
+
+impl Domain {
+    fn is_empty(&self) -> bool {
+        self.low > self.high && !self.missing
+    }
+}
+
+/// Guaranteed range of a model's outputs over the inputs a schema accepts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputBounds {
+    /// Smallest and largest output of each tree
+    pub trees: Vec<(i64, i64)>,
+    /// Smallest and largest accumulator after each tree, starting from the base score (from 0
+    /// for averaging ensembles, whose accumulator is the tree sum before division)
+    pub accumulators: Vec<(i128, i128)>,
+    /// Smallest and largest prediction, computed without saturation
+    pub prediction: (i128, i128),
+    /// Leaves at least one accepted input may reach
+    pub reachable_leaves: usize,
+    /// All leaves of the model
+    pub leaves: usize,
+    accumulator: Accumulator,
+}
+
+impl OutputBounds {
+    /// Evaluate `model` over every input `schema` accepts
+    ///
+    /// Features past the end of the schema are unconstrained. Panics if a range is empty and
+    /// does not accept the missing value.
+    ///
+    /// # Arguments
+    /// * `model` - Model to bound
+    /// * `schema` - Accepted range of each feature (at the model's scale)
+    ///
+    /// # Returns
+    /// * `OutputBounds` - Ranges of each tree output, running sum and the prediction
+    pub fn analyze(model: &Ensemble, schema: &FeatureSchema) -> OutputBounds {
+        let mut domains: Vec<Domain> = (0..model.num_features())
+            .map(|feature| match schema.features().get(feature) {
+                Some(range) => Domain { low: range.min as i128, high: range.max as i128, missing: range.allow_missing },
+                None => Domain { low: i64::MIN as i128, high: i64::MAX as i128, missing: false },
+            })
+            .collect();
+        assert!(!domains.iter().any(Domain::is_empty), "the schema accepts no value for some feature");
+
+        let mut reachable_leaves = 0;
+        let mut trees = Vec::with_capacity(model.num_trees());
+        let mut accumulators = Vec::with_capacity(model.num_trees());
+        let initial = model.combiner().initial(model.base_score()) as i128;
+        let (mut low, mut high) = (initial, initial);
+        for tree in model.trees() {
+            let (min, max) = bound(&tree.root, &mut domains, &mut reachable_leaves).expect("a leaf is reachable");
+            low += min as i128;
+            high += max as i128;
+            trees.push((min, max));
+            accumulators.push((low, high));
+        }
+        let prediction = match model.combiner() {
+            Combiner::Sum => (low, high),
+            Combiner::Average => {
+                let (n, base) = (model.num_trees().max(1) as i128, model.base_score() as i128);
+                (base + low.div_euclid(n), base + high.div_euclid(n))
+            }
+        };
+        let leaves = model.trees().iter().map(|tree| tree.num_nodes().div_ceil(2)).sum();
+        OutputBounds { trees, accumulators, prediction, reachable_leaves, leaves, accumulator: model.accumulator() }
+    }
+
+    /// Whether no accepted input can make the evaluation saturate
+    ///
+    /// With an i64 accumulator every running sum must fit in an i64, with an i128 accumulator
+    /// only the prediction. When this holds, the prediction is the exact sum of the tree outputs.
+    pub fn saturation_free(&self) -> bool {
+        let fits = |(low, high): (i128, i128)| low >= i64::MIN as i128 && high <= i64::MAX as i128;
+        fits(self.prediction)
+            && (self.accumulator == Accumulator::I128 || self.accumulators.iter().copied().all(fits))
+    }
+
+    /// Whether `prediction` lies in the guaranteed range
+    pub fn contains(&self, prediction: i64) -> bool {
+        (self.prediction.0..=self.prediction.1).contains(&(prediction as i128))
+    }
+
+    /// Machine-readable form
+    pub fn to_json(&self) -> JsonValue {
+        let number = |value: i128| JsonValue::Number(value.to_string());
+        let trees = self
+            .trees
+            .iter()
+            .zip(&self.accumulators)
+            .map(|(&(min, max), &(low, high))| {
+                JsonValue::Object(vec![
+                    ("output_min".to_string(), number(min as i128)),
+                    ("output_max".to_string(), number(max as i128)),
+                    ("accumulator_min".to_string(), number(low)),
+                    ("accumulator_max".to_string(), number(high)),
+                ])
+            })
+            .collect();
+        JsonValue::Object(vec![
+            ("prediction_min".to_string(), number(self.prediction.0)),
+            ("prediction_max".to_string(), number(self.prediction.1)),
+            ("saturation_free".to_string(), JsonValue::Bool(self.saturation_free())),
+            ("reachable_leaves".to_string(), number(self.reachable_leaves as i128)),
+            ("leaves".to_string(), number(self.leaves as i128)),
+            ("trees".to_string(), JsonValue::Array(trees)),
+        ])
+    }
+}
+
+impl fmt::Display for OutputBounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}, {}], {} of {} leaves reachable, {}",
+            self.prediction.0,
+            self.prediction.1,
+            self.reachable_leaves,
+            self.leaves,
+            if self.saturation_free() { "no saturation possible" } else { "may saturate" }
+        )
+    }
+}
+
+/// Smallest and largest leaf of `node` reachable within `domains`, counting reachable leaves
+///
+/// Returns None if no leaf is reachable. `domains` is narrowed on the way down and restored.
+fn bound(node: &Node, domains: &mut [Domain], reachable: &mut usize) -> Option<(i64, i64)> {
+    let (feature, left, right, left_domain, right_domain) = match node {
+        Node::Leaf(value) => {
+            *reachable += 1;
+            return Some((*value, *value));
+        }
+        Node::Split { feature, threshold, left, right } => {
+            let (domain, threshold) = (domains[*feature], *threshold as i128);
+            let missing_left = MISSING_VALUE as i128 <= threshold;
+            let left_domain =
+                Domain { high: domain.high.min(threshold), missing: domain.missing && missing_left, ..domain };
+            let right_domain =
+                Domain { low: domain.low.max(threshold + 1), missing: domain.missing && !missing_left, ..domain };
+            (*feature, left, right, left_domain, right_domain)
+        }
+        Node::Categorical { feature, categories, unit, left, right } => {
+            // Membership does not narrow the interval; the missing value is in no set
+            let domain = domains[*feature];
+            let members = categories
+                .values(*unit)
+                .filter(|&value| (domain.low..=domain.high).contains(&(value as i128)))
+                .count() as i128;
+            let empty = Domain { high: domain.low - 1, missing: false, ..domain };
+            let left_domain = if members > 0 { Domain { missing: false, ..domain } } else { empty };
+            let others = domain.missing || domain.high - domain.low + 1 > members;
+            let right_domain = if others { domain } else { empty };
+            (*feature, left, right, left_domain, right_domain)
+        }
+    };
+
+    let saved = domains[feature];
+    let mut range: Option<(i64, i64)> = None;
+    for (child, domain) in [(left, left_domain), (right, right_domain)] {
+        if domain.is_empty() {
+            continue;
+        }
+        domains[feature] = domain;
+        if let Some((min, max)) = bound(child, domains, reachable) {
+            range = Some(range.map_or((min, max), |(low, high)| (low.min(min), high.max(max))));
+        }
+    }
+    domains[feature] = saved;
+    range
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::{BitSet, Tree};
+    use crate::schema::FeatureRange;
+    use crate::{builtin_model, xgboost_predict, SyntheticGenerator, ValueBounds, PRECISION_MULTIPLIER};
+
+    fn range(min: i64, max: i64, allow_missing: bool) -> FeatureRange {
+        FeatureRange { name: "x".to_string(), unit: String::new(), min, max, allow_missing }
+    }
+
+    #[test]
+    fn test_unreachable_leaves_are_excluded() {
+        let categories: BitSet = [1, 3].into_iter().collect();
+        let categorical = Node::categorical(1, categories, 10, Node::Leaf(-40), Node::Leaf(7));
+        let trees = vec![
+            // f0 never exceeds 8, so the 100 leaf is dead; missing goes left
+            Tree::new(Node::split(0, 8, Node::split(0, 2, Node::Leaf(1), Node::Leaf(5)), Node::Leaf(100))),
+            // f1 in [11, 29] holds no category value but 20, which is not in the set
+            Tree::new(categorical),
+        ];
+        let model = Ensemble::new(trees, 10).with_base_score(3);
+        let schema = FeatureSchema::new(vec![range(0, 8, true), range(11, 29, false)]);
+        let bounds = OutputBounds::analyze(&model, &schema);
+
+        assert_eq!(bounds.trees, vec![(1, 5), (7, 7)]);
+        assert_eq!(bounds.accumulators, vec![(4, 8), (11, 15)]);
+        assert_eq!(bounds.prediction, (11, 15));
+        assert_eq!((bounds.reachable_leaves, bounds.leaves), (3, 5));
+        assert!(bounds.saturation_free());
+        assert_eq!(bounds.to_string(), "[11, 15], 3 of 5 leaves reachable, no saturation possible");
+
+        // Every accepted input lands in the range
+        for f0 in (0..=8).chain([MISSING_VALUE]) {
+            for f1 in 11..=29 {
+                assert!(bounds.contains(model.eval(&[f0, f1])));
+            }
+        }
+
+        // Averaging divides the tree sum once
+        let forest = model.with_combiner(Combiner::Average);
+        assert_eq!(OutputBounds::analyze(&forest, &schema).prediction, (3 + 4, 3 + 6));
+        let json = bounds.to_json().to_string();
+        assert!(json.starts_with(r#"{"prediction_min":11,"prediction_max":15,"saturation_free":true"#));
+    }
+
+    #[test]
+    fn test_saturation_proof() {
+        let big = i64::MAX / 2 + 1;
+        let trees = vec![
+            Tree::new(Node::split(0, 10, Node::Leaf(big), Node::Leaf(0))),
+            Tree::new(Node::split(0, 20, Node::Leaf(big), Node::Leaf(1))),
+        ];
+        let model = Ensemble::new(trees, 1);
+        let wide = OutputBounds::analyze(&model, &FeatureSchema::new(vec![range(0, 100, false)]));
+        assert!(!wide.saturation_free());
+        assert_eq!(model.eval(&[0]), i64::MAX);
+
+        // Above 10 the first tree adds 0, so the sum stays below i64::MAX
+        let narrow = OutputBounds::analyze(&model, &FeatureSchema::new(vec![range(11, 100, false)]));
+        assert!(narrow.saturation_free());
+        assert_eq!(narrow.prediction, (1, big as i128));
+    }
+
+    #[test]
+    fn test_builtin_model_over_rainfall_schema() {
+        let model = builtin_model();
+        let schema = FeatureSchema::rainfall();
+        let bounds = OutputBounds::analyze(&model, &schema);
+        assert!(bounds.saturation_free());
+        assert!(bounds.reachable_leaves <= bounds.leaves);
+
+        // Never wider than the schema-free leaf bounds
+        let leaf_bounds = ValueBounds::analyze(&model, u64::MAX);
+        let &(low, high) = leaf_bounds.accumulators.last().unwrap();
+        assert!(low <= bounds.prediction.0 && bounds.prediction.1 <= high);
+        assert!(bounds.prediction.1 - bounds.prediction.0 < 100 * PRECISION_MULTIPLIER as i128);
+
+        let mut generator = SyntheticGenerator::rainfall(7);
+        for _ in 0..500 {
+            let features = generator.next_scaled(PRECISION_MULTIPLIER);
+            schema.validate(&features).unwrap();
+            assert!(bounds.contains(xgboost_predict(&features)));
+        }
+    }
+}
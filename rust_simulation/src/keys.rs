@@ -0,0 +1,323 @@
+// Proving and verification key cache
+// Building a model's circuit (its proving key) costs more than proving one prediction with it, so
+// every `predict prove` paid the key generation again. `KeyStore` keeps encoded keys in a
+// directory, one file per (model content hash, backend, kind), and hands them back on the next
+// run. Backends encode and decode their own keys (`Plonky2Circuit::cached` for plonky2); the store
+// only names, frames and checks them. A backend name should include whatever changes its circuits,
+// such as the crate version, so that an upgraded build does not pick up keys of the old layout.
+//
+// Key file layout: magic "ZKKC" | version u8 | kind u8 | backend len u8 | backend |
+// model hash 32 bytes | SHA-256 of the payload 32 bytes | payload len u64 | payload (little-endian)
+//
+// A file whose header names another key, or whose payload no longer matches its hash, is reported
+// by `load` and overwritten by the next `store`; files are written to a temporary name and renamed
+// so that a crashed run never leaves a truncated key under the real name.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::sha256::{sha256, to_hex};
+
+/// First bytes of a cached key file
+pub const KEY_FILE_MAGIC: [u8; 4] = *b"ZKKC";
+
+const KEY_FILE_VERSION: u8 = 1;
+
+/// Error raised by a `KeyStore`
+#[derive(Debug)]
+pub enum KeyError {
+    /// Reading or writing the key file failed
+    Io(std::io::Error),
+    /// The file is not a key file of this version, or is truncated
+    Malformed(String),
+    /// The file holds the key of another model, backend or kind
+    Mismatch,
+    /// The payload does not match the integrity hash in the header
+    Corrupt,
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::Io(e) => write!(f, "I/O error: {}", e),
+            KeyError::Malformed(message) => write!(f, "malformed key file: {}", message),
+            KeyError::Mismatch => write!(f, "key file holds another key"),
+            KeyError::Corrupt => write!(f, "key file failed its integrity check"),
+        }
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+impl From<std::io::Error> for KeyError {
+    fn from(e: std::io::Error) -> Self {
+        KeyError::Io(e)
+    }
+}
+
+/// Whether a key is used to prove or to verify
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyKind {
+    Proving,
+    Verifying,
+}
+
+impl KeyKind {
+    fn byte(self) -> u8 {
+        match self {
+            KeyKind::Proving => 0,
+            KeyKind::Verifying => 1,
+        }
+    }
+
+    /// File extension of keys of this kind
+    pub fn extension(self) -> &'static str {
+        match self {
+            KeyKind::Proving => "pk",
+            KeyKind::Verifying => "vk",
+        }
+    }
+}
+
+/// Where a key came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeySource {
+    /// Read from the store
+    Cached,
+    /// Generated, then written to the store
+    Generated,
+}
+
+impl fmt::Display for KeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeySource::Cached => write!(f, "cached"),
+            KeySource::Generated => write!(f, "generated"),
+        }
+    }
+}
+
+/// Identity of one key: the model, the proving backend and the kind of key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyId {
+    /// `content_hash` of the model
+    pub model: [u8; 32],
+    /// Backend name, e.g. "plonky2-0.1.0" (ASCII letters, digits, '.', '-' and '_', at most 64 bytes)
+    pub backend: &'static str,
+    pub kind: KeyKind,
+}
+
+impl KeyId {
+    /// Key of kind `kind` for the model with content hash `model` on `backend`
+    pub fn new(model: [u8; 32], backend: &'static str, kind: KeyKind) -> KeyId {
+        debug_assert!(
+            backend.len() <= 64 && backend.bytes().all(|b| b.is_ascii_alphanumeric() || b"._-".contains(&b)),
+            "backend names are used in file names"
+        );
+        KeyId { model, backend, kind }
+    }
+
+    /// Name of the file the key is stored in
+    pub fn file_name(&self) -> String {
+        format!("{}-{}.{}", to_hex(&self.model), self.backend, self.kind.extension())
+    }
+
+    /// Frame `payload` as a key file for this id
+    pub fn encode(&self, payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(4 + 3 + self.backend.len() + 32 + 32 + 8 + payload.len());
+        out.extend_from_slice(&KEY_FILE_MAGIC);
+        out.push(KEY_FILE_VERSION);
+        out.push(self.kind.byte());
+        out.push(self.backend.len() as u8);
+        out.extend_from_slice(self.backend.as_bytes());
+        out.extend_from_slice(&self.model);
+        out.extend_from_slice(&sha256(payload));
+        out.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        out.extend_from_slice(payload);
+        out
+    }
+
+    /// Check a key file written by `encode` for this id and return its payload
+    ///
+    /// # Arguments
+    /// * `bytes` - Contents of the key file
+    ///
+    /// # Returns
+    /// * `Result<&[u8], KeyError>` - The payload, or why the file cannot be used for this key
+    pub fn decode<'a>(&self, bytes: &'a [u8]) -> Result<&'a [u8], KeyError> {
+        let truncated = || KeyError::Malformed("truncated header".to_string());
+        if bytes.get(..4) != Some(&KEY_FILE_MAGIC[..]) {
+            return Err(KeyError::Malformed("not a key file".to_string()));
+        }
+        match bytes.get(4) {
+            Some(&KEY_FILE_VERSION) => {}
+            Some(version) => return Err(KeyError::Malformed(format!("unsupported version {}", version))),
+            None => return Err(truncated()),
+        }
+        let (&kind, &backend_len) = bytes.get(5).zip(bytes.get(6)).ok_or_else(truncated)?;
+        let backend_end = 7 + backend_len as usize;
+        let header = bytes.get(..backend_end + 72).ok_or_else(truncated)?;
+        if kind != self.kind.byte()
+            || &header[7..backend_end] != self.backend.as_bytes()
+            || header[backend_end..backend_end + 32] != self.model
+        {
+            return Err(KeyError::Mismatch);
+        }
+        let payload = &bytes[header.len()..];
+        let len = u64::from_le_bytes(header[backend_end + 64..].try_into().expect("8 bytes"));
+        if payload.len() as u64 != len {
+            return Err(KeyError::Malformed(format!("payload of {} bytes, header says {}", payload.len(), len)));
+        }
+        if sha256(payload)[..] != header[backend_end + 32..backend_end + 64] {
+            return Err(KeyError::Corrupt);
+        }
+        Ok(payload)
+    }
+}
+
+/// Directory of cached keys
+#[derive(Debug, Clone)]
+pub struct KeyStore {
+    dir: PathBuf,
+}
+
+impl KeyStore {
+    /// Store keeping its keys in `dir`, created on the first `store`
+    pub fn new(dir: impl Into<PathBuf>) -> KeyStore {
+        KeyStore { dir: dir.into() }
+    }
+
+    /// Directory holding the keys
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of the file holding `id`
+    pub fn path(&self, id: &KeyId) -> PathBuf {
+        self.dir.join(id.file_name())
+    }
+
+    /// Read the payload of a key
+    ///
+    /// # Arguments
+    /// * `id` - Key to read
+    ///
+    /// # Returns
+    /// * `Result<Option<Vec<u8>>, KeyError>` - The payload, `None` if the key was never stored, or
+    ///   why the stored file cannot be used
+    pub fn load(&self, id: &KeyId) -> Result<Option<Vec<u8>>, KeyError> {
+        match std::fs::read(self.path(id)) {
+            Ok(bytes) => Ok(Some(id.decode(&bytes)?.to_vec())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the payload of a key, replacing any previous file
+    pub fn store(&self, id: &KeyId, payload: &[u8]) -> Result<(), KeyError> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(id);
+        let partial = path.with_extension(format!("{}.{}.tmp", id.kind.extension(), std::process::id()));
+        std::fs::write(&partial, id.encode(payload))?;
+        std::fs::rename(&partial, &path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&partial);
+        })?;
+        Ok(())
+    }
+
+    /// Payload of a key, generating and storing it when it is missing or unusable
+    ///
+    /// # Arguments
+    /// * `id` - Key to read
+    /// * `generate` - Produces the payload on a miss
+    ///
+    /// # Returns
+    /// * `Result<(Vec<u8>, KeySource), KeyError>` - The payload and whether it was cached; only
+    ///   errors reading an existing file or writing the new one are returned
+    pub fn get_or_generate(
+        &self,
+        id: &KeyId,
+        generate: impl FnOnce() -> Vec<u8>,
+    ) -> Result<(Vec<u8>, KeySource), KeyError> {
+        match self.load(id) {
+            Ok(Some(payload)) => return Ok((payload, KeySource::Cached)),
+            Err(KeyError::Io(e)) => return Err(KeyError::Io(e)),
+            Ok(None) | Err(_) => {}
+        }
+        let payload = generate();
+        self.store(id, &payload)?;
+        Ok((payload, KeySource::Generated))
+    }
+
+    /// Delete a key (nothing happens if it was never stored)
+    pub fn remove(&self, id: &KeyId) -> Result<(), KeyError> {
+        match std::fs::remove_file(self.path(id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(name: &str) -> KeyStore {
+        let dir = std::env::temp_dir().join(format!("keys_test_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        KeyStore::new(dir)
+    }
+
+    #[test]
+    fn test_key_store_cache() {
+        let store = temp_store("cache");
+        let id = KeyId::new([7; 32], "test-1.0", KeyKind::Proving);
+        assert!(store.load(&id).unwrap().is_none());
+
+        let mut generated = 0;
+        for expected in [KeySource::Generated, KeySource::Cached] {
+            let (payload, source) = store
+                .get_or_generate(&id, || {
+                    generated += 1;
+                    b"proving key".to_vec()
+                })
+                .unwrap();
+            assert_eq!((payload.as_slice(), source), (&b"proving key"[..], expected));
+        }
+        assert_eq!(generated, 1);
+        assert!(store.path(&id).ends_with(format!("{}-test-1.0.pk", to_hex(&[7; 32]))));
+
+        // The verifying key of the same model is a different file
+        let verifying = KeyId { kind: KeyKind::Verifying, ..id };
+        assert!(store.load(&verifying).unwrap().is_none());
+        store.remove(&id).unwrap();
+        store.remove(&id).unwrap();
+        assert!(store.load(&id).unwrap().is_none());
+        std::fs::remove_dir_all(store.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_key_file_checks() {
+        let id = KeyId::new([1; 32], "test", KeyKind::Verifying);
+        let file = id.encode(b"key");
+        assert_eq!(id.decode(&file).unwrap(), b"key");
+
+        let mut flipped = file.clone();
+        *flipped.last_mut().unwrap() ^= 1;
+        assert!(matches!(id.decode(&flipped), Err(KeyError::Corrupt)));
+        assert!(matches!(KeyId { model: [2; 32], ..id }.decode(&file), Err(KeyError::Mismatch)));
+        assert!(matches!(KeyId { backend: "other", ..id }.decode(&file), Err(KeyError::Mismatch)));
+        assert_eq!(id.decode(&file[..20]).unwrap_err().to_string(), "malformed key file: truncated header");
+        assert!(matches!(id.decode(&file[..file.len() - 1]), Err(KeyError::Malformed(_))));
+
+        // An unusable file is regenerated and replaced
+        let store = temp_store("checks");
+        std::fs::create_dir_all(store.dir()).unwrap();
+        std::fs::write(store.path(&id), &flipped).unwrap();
+        assert!(matches!(store.load(&id), Err(KeyError::Corrupt)));
+        let (_, source) = store.get_or_generate(&id, || b"key".to_vec()).unwrap();
+        assert_eq!(source, KeySource::Generated);
+        assert_eq!(store.load(&id).unwrap().unwrap(), b"key");
+        std::fs::remove_dir_all(store.dir()).unwrap();
+    }
+}
@@ -1,9 +1,200 @@
 // XGBoost Decision Tree Implementation in Rust
 // Uses custom fixed-point arithmetic with i64 for precision compatible with zero-knowledge proofs
 // All values are scaled by 10^10 for precision (maintaining compatibility with original implementation)
+// Builds without the standard library (`--no-default-features`) for zkVM guests and microcontrollers;
+// only file I/O, the replay log and parallel batches need the `std` feature.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
+extern crate alloc;
+
+mod abi;
+#[cfg(feature = "ndarray")]
+mod array;
+mod backend;
+mod batch;
+mod binary;
+mod bitwidth;
+mod boundary;
+mod bucket;
+mod cache;
+mod calibration;
+mod canonical;
+mod cascade;
+mod checked;
+pub mod codegen;
+mod commitment;
+mod coverage;
+pub mod csv;
+mod dot;
+mod ensemble;
+mod equivalence;
+#[cfg(feature = "std")]
+mod evaluation;
+mod explog;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod fingerprint;
+mod fixed;
+#[cfg(feature = "gpu")]
+mod gpu;
+#[cfg(feature = "groth16")]
+pub mod groth16;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+mod guard;
+mod info;
+mod inspect;
+mod interval;
+pub mod json;
+#[cfg(feature = "std")]
+mod keys;
+pub mod lightgbm;
+mod linear;
+#[cfg(target_has_atomic = "64")]
+mod metrics;
+mod model;
+mod monotone;
+mod multiclass;
+mod names;
+mod numeric;
+mod objective;
+mod oblivious;
+pub mod onnx;
+#[cfg(feature = "parquet")]
+mod parquet_input;
+mod policy;
+mod port_check;
+mod poseidon;
+mod preprocess;
+mod protobuf;
+mod prune;
+mod quantization;
+#[cfg(feature = "r1cs")]
+pub mod r1cs;
+#[cfg(feature = "std")]
+mod registry;
+#[cfg(feature = "std")]
+pub mod replay;
+mod request;
+#[cfg(feature = "rest")]
+pub mod rest;
+mod schema;
+mod sha256;
+mod shadow;
+mod shap;
+mod sharing;
+mod sigmoid;
+#[cfg(feature = "simd")]
+mod simd;
+mod sparse;
+mod stats;
+mod synthetic;
+mod table;
+pub mod trace;
+mod truncate;
+#[cfg(feature = "validation")]
+pub mod validation;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod window;
+pub mod witness;
+#[cfg(feature = "verifier")]
+pub mod zk;
+
+pub use abi::{AbiError, PublicInputs, PUBLIC_INPUTS_LEN};
+pub use backend::{lower_circuit, lower_model, CircuitBackend};
+#[cfg(feature = "parallel")]
+pub use batch::xgboost_predict_batch_parallel;
+pub use batch::{xgboost_predict_batch, xgboost_predict_batch_cached, xgboost_predict_stream};
+pub use binary::{BinaryError, FORMAT_VERSION};
+pub use bitwidth::{magnitude_bits, ValueBounds};
+pub use boundary::{boundary_cases, check_boundaries, BoundaryCase, BoundaryFailure};
+pub use bucket::{BucketError, BucketizedModel, FeatureBucket, FeatureBuckets};
+pub use cache::{CacheStats, PredictionCache};
+pub use calibration::{Calibration, CalibrationError, IsotonicCalibration, PlattCalibration};
+pub use cascade::{Cascade, CascadeError, CascadeStage, CascadeStats};
+pub use canonical::{validate_document, validate_value, DocumentKind, ProofMetadata, SchemaError, SCHEMA_VERSION};
+pub use checked::{try_predict, try_xgboost_predict, xgboost_predict_array, PredictError};
+pub use commitment::{hash_categorical, hash_leaf, hash_node, hash_split, Digest, ModelCommitment, PathProof, PathStep};
+pub use coverage::{CoverageFailure, LeafPath, PathCoverage};
+pub use ensemble::{Accumulator, BitSet, Combiner, Ensemble, Node, RescaleError, Tree};
+pub use equivalence::{check_equivalence, Counterexample, EquivalenceReport, TreeEquivalence, DEFAULT_CASE_LIMIT};
+#[cfg(feature = "std")]
+pub use evaluation::{ErrorStats, Evaluation};
+pub use explog::{fixed_exp, fixed_ln, EXP_MAX_RELATIVE_ERROR, LN_MAX_ERROR};
+pub use fingerprint::{EvaluatorMode, Fingerprint, FingerprintError, CRATE_VERSION};
+pub use fixed::{parse_decimal, rescale_floor, rescale_round, Fixed, Fixed10, FixedPoint, ParseFixedError};
+#[cfg(feature = "gpu")]
+pub use gpu::{xgboost_predict_batch_gpu, GpuError, GpuForest};
+pub use guard::{GuardDecision, OutputGuard};
+pub use info::{ModelInfo, INFO_MAGIC};
+pub use inspect::{ConstraintEstimate, FeatureUsage, ModelReport, TreeReport};
+pub use interval::OutputBounds;
+#[cfg(feature = "std")]
+pub use keys::{KeyError, KeyId, KeyKind, KeySource, KeyStore, KEY_FILE_MAGIC};
+pub use linear::LinearModel;
+pub use model::{Model, ModelError};
+#[cfg(target_has_atomic = "64")]
+pub use metrics::{Histogram, ServerMetrics, LATENCY_BUCKETS, PROOF_BUCKETS};
+pub use monotone::{ConstraintParseError, Monotone, MonotoneAssert, MonotoneConstraints, MonotoneViolation};
+pub use multiclass::{fixed_argmax, xgboost_tree_info, MultiClassEnsemble};
+pub use names::{parse_feature_names, FeatureNameError};
+pub use numeric::Numeric;
+pub use objective::{Link, Objective};
+pub use oblivious::xgboost_predict_oblivious;
+#[cfg(feature = "parquet")]
+pub use parquet_input::{read_parquet_rows, ParquetInputError};
+pub use policy::{PolicyError, ProofPolicy, ProofValue, Visibility};
+pub use port_check::{MismatchKind, PortCheck, PortMismatch, PORT_TOLERANCE};
+pub use poseidon::{hash_features, poseidon_hash, PoseidonDigest};
+pub use prune::PruneReport;
+pub use preprocess::{ConstantImputer, MinMaxScaler, Pipeline, StandardScaler, Transform};
+pub use quantization::{LeafQuantization, QuantizationReport, SplitQuantization};
+#[cfg(feature = "std")]
+pub use registry::{ModelListing, ModelRegistry, Predictor, RegisteredModel, RegistryError};
+pub use request::RequestFeatures;
+pub use schema::{FeatureRange, FeatureSchema, RangeViolation, ValidationError, FEATURE_NAMES, MISSING_VALUE};
+pub use sha256::{sha256, to_hex};
+pub use shadow::Divergence;
+pub use sharing::{SharedEnsemble, SharingStats, SplitTest};
+#[cfg(feature = "simd")]
+pub use simd::{xgboost_predict_batch_simd, SimdForest, LANES};
+pub use sigmoid::{fixed_sigmoid, xgboost_predict_proba, SIGMOID_MAX_ERROR};
+pub use sparse::{expand_sparse, expand_sparse_f64, expand_sparse_f64_at, xgboost_predict_sparse, SparseFeatureError};
+pub use stats::{fixed_mean, fixed_median, fixed_percentile, fixed_std, fixed_sum, fixed_variance, percentile_rank};
+pub use synthetic::{Distribution, FeatureDistribution, Lcg, SyntheticError, SyntheticGenerator};
+pub use table::DecisionTable;
+pub use trace::{xgboost_predict_with_trace, ExecutionTrace, GuardCheck};
+pub use truncate::{CoverError, LeafCovers};
+pub use window::{
+    predict_scan_stream, Scan, ScanWindow, NOT_COLLECTED, NO_ECHO, NUM_QUANTITIES, RADAR_QUANTITIES, RANGE_FOLDED,
+};
 
 /// Fixed-point arithmetic constants
-const PRECISION_MULTIPLIER: i64 = 10_000_000_000; // 10^10 for precision
+pub const PRECISION_MULTIPLIER: i64 = 10_000_000_000; // 10^10 for precision
+
+/// Number of input features expected by the model
+pub const NUM_FEATURES: usize = 116;
+
+/// Identifier of the model compiled into `xgboost_predict` (first 10 trees of bst1)
+pub const MODEL_ID: &str = "bst1_10";
+
+/// Version of the compiled model, reported by `builtin_model().model_info()`
+pub const MODEL_VERSION: &str = "1";
+
+/// XGBoost JSON dump of the model compiled into `xgboost_predict`
+pub const MODEL_JSON: &str = include_str!("../models/bst1_10.json");
+
+/// Structured form of the compiled model, for introspection, code generation and circuits
+pub fn builtin_model() -> Model {
+    Model::from_xgboost_json(MODEL_JSON)
+        .expect("bundled model dump is valid")
+        .with_num_features(NUM_FEATURES)
+        .with_name(MODEL_ID)
+        .with_version(MODEL_VERSION)
+}
 
 /// Fixed-point less-than-or-equal comparison
 /// 
@@ -27,10 +218,65 @@ fn fixed_le(a: i64, b: i64) -> bool {
 /// # Returns
 /// * `i64` - Sum (scaled by 10^10), saturated on overflow
 #[inline]
-fn fixed_add(a: i64, b: i64) -> i64 {
+pub fn fixed_add(a: i64, b: i64) -> i64 {
     a.saturating_add(b)
 }
 
+/// Fixed-point subtraction with overflow protection, saturating like `fixed_add`
+/// 
+/// # Arguments
+/// * `a` - Minuend (scaled by 10^10)
+/// * `b` - Subtrahend (scaled by 10^10)
+/// 
+/// # Returns
+/// * `i64` - Difference (scaled by 10^10), saturated on overflow
+#[inline]
+pub fn fixed_sub(a: i64, b: i64) -> i64 {
+    a.saturating_sub(b)
+}
+
+/// Fixed-point negation with overflow protection
+/// 
+/// `i64::MIN` has no positive counterpart and negates to `i64::MAX`, so
+/// `fixed_neg(a)` always equals `fixed_sub(0, a)`.
+/// 
+/// # Arguments
+/// * `a` - Value (scaled by 10^10)
+/// 
+/// # Returns
+/// * `i64` - Negated value (scaled by 10^10), saturated on overflow
+#[inline]
+pub fn fixed_neg(a: i64) -> i64 {
+    a.saturating_neg()
+}
+
+/// `fixed_add` that reports overflow instead of saturating
+/// 
+/// # Returns
+/// * `Option<i64>` - Sum (scaled by 10^10), or None if it leaves the i64 range
+#[inline]
+pub fn checked_fixed_add(a: i64, b: i64) -> Option<i64> {
+    a.checked_add(b)
+}
+
+/// `fixed_sub` that reports overflow instead of saturating
+/// 
+/// # Returns
+/// * `Option<i64>` - Difference (scaled by 10^10), or None if it leaves the i64 range
+#[inline]
+pub fn checked_fixed_sub(a: i64, b: i64) -> Option<i64> {
+    a.checked_sub(b)
+}
+
+/// `fixed_neg` that reports overflow instead of saturating
+/// 
+/// # Returns
+/// * `Option<i64>` - Negated value (scaled by 10^10), or None for `i64::MIN`
+#[inline]
+pub fn checked_fixed_neg(a: i64) -> Option<i64> {
+    a.checked_neg()
+}
+
 /// Convert floating-point value to fixed-point representation
 /// 
 /// # Arguments
@@ -40,7 +286,20 @@ fn fixed_add(a: i64, b: i64) -> i64 {
 /// * `i64` - Fixed-point value (scaled by 10^10)
 #[inline]
 pub fn to_fixed_point(value: f64) -> i64 {
-    (value * PRECISION_MULTIPLIER as f64).round() as i64
+    to_fixed_point_at(value, PRECISION_MULTIPLIER)
+}
+
+/// Convert floating-point value to fixed-point at a runtime scale, such as a loaded model's
+/// 
+/// # Arguments
+/// * `value` - Floating-point value
+/// * `scale` - Units per 1.0
+/// 
+/// # Returns
+/// * `i64` - Fixed-point value (scaled by `scale`)
+#[inline]
+pub fn to_fixed_point_at(value: f64, scale: i64) -> i64 {
+    fixed::round_half_away(value * scale as f64)
 }
 
 /// Convert fixed-point value back to floating-point
@@ -52,7 +311,20 @@ pub fn to_fixed_point(value: f64) -> i64 {
 /// * `f64` - Floating-point value
 #[inline]
 pub fn from_fixed_point(fixed_value: i64) -> f64 {
-    fixed_value as f64 / PRECISION_MULTIPLIER as f64
+    from_fixed_point_at(fixed_value, PRECISION_MULTIPLIER)
+}
+
+/// Convert fixed-point value at a runtime scale back to floating-point
+/// 
+/// # Arguments
+/// * `fixed_value` - Fixed-point value (scaled by `scale`)
+/// * `scale` - Units per 1.0
+/// 
+/// # Returns
+/// * `f64` - Floating-point value
+#[inline]
+pub fn from_fixed_point_at(fixed_value: i64, scale: i64) -> f64 {
+    fixed_value as f64 / scale as f64
 }
 
 /// Create fixed-point value from pre-scaled integer (already multiplied by 10^10)
@@ -885,4 +1157,29 @@ mod tests {
         println!("As floats: {:.1} + {:.1} = {:.1}", 
                 from_fixed_point(a), from_fixed_point(b), from_fixed_point(sum));
     }
+
+    #[test]
+    fn test_fixed_point_saturation() {
+        let a = to_fixed_point(1.5);
+        let b = to_fixed_point(2.3);
+        assert_eq!(fixed_sub(b, a), to_fixed_point(0.8));
+        assert_eq!(fixed_neg(a), to_fixed_point(-1.5));
+        assert_eq!(checked_fixed_sub(a, b), Some(to_fixed_point(-0.8)));
+
+        // Saturating helpers clamp to the i64 range, checked ones report it
+        assert_eq!(fixed_add(i64::MAX, 1), i64::MAX);
+        assert_eq!(fixed_sub(i64::MIN, 1), i64::MIN);
+        assert_eq!(fixed_sub(0, i64::MIN), i64::MAX);
+        assert_eq!(fixed_neg(i64::MIN), i64::MAX);
+        assert_eq!(fixed_neg(i64::MAX), i64::MIN + 1);
+        assert_eq!(checked_fixed_add(i64::MAX, 1), None);
+        assert_eq!(checked_fixed_sub(i64::MIN, 1), None);
+        assert_eq!(checked_fixed_neg(i64::MIN), None);
+        assert_eq!(checked_fixed_neg(i64::MAX), Some(i64::MIN + 1));
+
+        for value in [i64::MIN, -1, 0, 1, i64::MAX] {
+            assert_eq!(fixed_neg(value), fixed_sub(0, value));
+            assert_eq!((-Fixed10::from_raw(value)).raw(), fixed_neg(value));
+        }
+    }
 }
\ No newline at end of file
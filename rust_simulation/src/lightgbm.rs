@@ -0,0 +1,403 @@
+// LightGBM model import
+// Parses the text model format written by `booster.save_model("model.txt")` into the
+// fixed-point `Ensemble`. Numerical splits only; the `decision_type` default-direction bit
+// is honoured for zero-as-missing splits by expanding them into plain `<=` splits. The
+// `objective=` header sets the ensemble's objective (poisson, tweedie and gamma use a log link,
+// binary a sigmoid); objectives without an inverse link here keep the raw score.
+
+use alloc::{format, string::{String, ToString}, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::ensemble::{Ensemble, Node, Tree};
+use crate::model::scale_dump_value;
+use crate::objective::Objective;
+use crate::PRECISION_MULTIPLIER;
+
+/// `decision_type` bit marking a categorical split
+const CATEGORICAL_MASK: u32 = 1;
+/// `decision_type` bit sending missing values to the left child
+const DEFAULT_LEFT_MASK: u32 = 2;
+/// `decision_type` missing-type value for "zero means missing"
+const MISSING_ZERO: u32 = 1;
+
+/// Error raised while loading a LightGBM model file
+#[derive(Debug)]
+pub enum LightGbmError {
+    /// The model file could not be read
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// A line could not be parsed (1-based line number)
+    Parse { line: usize, message: String },
+    /// The model uses a feature the fixed-point ensemble cannot express
+    Unsupported(String),
+}
+
+impl fmt::Display for LightGbmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            LightGbmError::Io(e) => write!(f, "failed to read LightGBM model: {}", e),
+            LightGbmError::Parse { line, message } => {
+                write!(f, "LightGBM model line {}: {}", line, message)
+            }
+            LightGbmError::Unsupported(what) => write!(f, "unsupported LightGBM model: {}", what),
+        }
+    }
+}
+
+impl core::error::Error for LightGbmError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for LightGbmError {
+    fn from(e: std::io::Error) -> Self {
+        LightGbmError::Io(e)
+    }
+}
+
+/// `key=value` lines of one section, with their line numbers
+struct Section<'a> {
+    line: usize,
+    entries: Vec<(usize, &'a str, &'a str)>,
+}
+
+impl<'a> Section<'a> {
+    fn get(&self, key: &str) -> Option<(usize, &'a str)> {
+        self.entries.iter().find(|(_, k, _)| *k == key).map(|&(line, _, value)| (line, value))
+    }
+
+    fn require(&self, key: &str) -> Result<(usize, &'a str), LightGbmError> {
+        self.get(key).ok_or_else(|| LightGbmError::Parse {
+            line: self.line,
+            message: format!("missing '{}'", key),
+        })
+    }
+
+    /// Space-separated list, checked against the expected length
+    fn list<T: core::str::FromStr>(&self, key: &str, len: usize) -> Result<Vec<T>, LightGbmError> {
+        let (line, value) = self.require(key)?;
+        let items = value
+            .split_whitespace()
+            .map(|item| {
+                item.parse()
+                    .map_err(|_| LightGbmError::Parse { line, message: format!("invalid {} value '{}'", key, item) })
+            })
+            .collect::<Result<Vec<T>, _>>()?;
+        if items.len() != len {
+            return Err(LightGbmError::Parse {
+                line,
+                message: format!("{} has {} values, expected {}", key, items.len(), len),
+            });
+        }
+        Ok(items)
+    }
+}
+
+/// Split the file into the header and one section per `Tree=` block
+fn sections(text: &str) -> (Section<'_>, Vec<Section<'_>>) {
+    let mut header = Section { line: 1, entries: Vec::new() };
+    let mut trees: Vec<Section> = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.trim();
+        if line == "end of trees" {
+            break;
+        }
+        let Some((key, value)) = line.split_once('=') else { continue };
+
+        if key == "Tree" {
+            trees.push(Section { line: line_no, entries: Vec::new() });
+        } else {
+            let section = trees.last_mut().unwrap_or(&mut header);
+            section.entries.push((line_no, key, value));
+        }
+    }
+
+    (header, trees)
+}
+
+/// Internal node of a LightGBM tree after parsing
+struct Split {
+    feature: usize,
+    threshold: i64,
+    decision_type: u32,
+    left: i64,
+    right: i64,
+}
+
+/// Child reference: non-negative is an internal node, negative is `!leaf_index`
+fn build_child(child: i64, splits: &[Split], leaves: &[i64], depth: usize) -> Result<Node, String> {
+    if depth > splits.len() {
+        return Err("tree contains a cycle".to_string());
+    }
+    if child < 0 {
+        let leaf = (!child) as usize;
+        return leaves.get(leaf).map(|&v| Node::Leaf(v)).ok_or_else(|| format!("unknown leaf {}", leaf));
+    }
+
+    let split = splits.get(child as usize).ok_or_else(|| format!("unknown node {}", child))?;
+    let left = build_child(split.left, splits, leaves, depth + 1)?;
+    let right = build_child(split.right, splits, leaves, depth + 1)?;
+    Ok(numerical_split(split, left, right))
+}
+
+/// Express LightGBM's numerical decision with `<=` splits only
+///
+/// Inputs are fixed-point integers, so NaN cannot occur and "missing" only matters for
+/// zero-as-missing splits: there, 0 follows the default direction whatever the threshold.
+fn numerical_split(split: &Split, left: Node, right: Node) -> Node {
+    let (feature, t) = (split.feature, split.threshold);
+    let zero_missing = (split.decision_type >> 2) & 3 == MISSING_ZERO;
+    let default_left = split.decision_type & DEFAULT_LEFT_MASK != 0;
+
+    match (zero_missing, default_left) {
+        // 0 > t would go right, but must go left: x <= t | x <= -1 -> right | x <= 0 -> left | right
+        (true, true) if t < 0 => Node::split(
+            feature,
+            t,
+            left.clone(),
+            Node::split(feature, -1, right.clone(), Node::split(feature, 0, left, right)),
+        ),
+        // 0 <= t would go left, but must go right: x <= -1 -> left | x <= 0 -> right | x <= t
+        (true, false) if t >= 0 => Node::split(
+            feature,
+            -1,
+            left.clone(),
+            Node::split(feature, 0, right.clone(), Node::split(feature, t, left, right)),
+        ),
+        _ => Node::split(feature, t, left, right),
+    }
+}
+
+fn parse_tree(section: &Section<'_>, scale: i64) -> Result<Tree, LightGbmError> {
+    let parse_err = |message: String| LightGbmError::Parse { line: section.line, message };
+
+    let (line, num_leaves) = section.require("num_leaves")?;
+    let num_leaves: usize = num_leaves
+        .parse()
+        .map_err(|_| LightGbmError::Parse { line, message: format!("invalid num_leaves '{}'", num_leaves) })?;
+    if num_leaves == 0 {
+        return Err(parse_err("tree without leaves".to_string()));
+    }
+    if section.get("is_linear").is_some_and(|(_, v)| v != "0") {
+        return Err(LightGbmError::Unsupported("linear trees".to_string()));
+    }
+
+    let leaves: Vec<i64> = section
+        .list::<f64>("leaf_value", num_leaves)?
+        .into_iter()
+        .map(|v| scale_dump_value(v, scale))
+        .collect();
+    if num_leaves == 1 {
+        return Ok(Tree::new(Node::Leaf(leaves[0])));
+    }
+
+    let num_splits = num_leaves - 1;
+    let features: Vec<usize> = section.list("split_feature", num_splits)?;
+    let thresholds: Vec<f64> = section.list("threshold", num_splits)?;
+    let decision_types: Vec<u32> = section.list("decision_type", num_splits)?;
+    let left_children: Vec<i64> = section.list("left_child", num_splits)?;
+    let right_children: Vec<i64> = section.list("right_child", num_splits)?;
+
+    if decision_types.iter().any(|d| d & CATEGORICAL_MASK != 0) {
+        return Err(LightGbmError::Unsupported("categorical splits".to_string()));
+    }
+
+    let splits: Vec<Split> = (0..num_splits)
+        .map(|i| Split {
+            feature: features[i],
+            threshold: scale_dump_value(thresholds[i], scale),
+            decision_type: decision_types[i],
+            left: left_children[i],
+            right: right_children[i],
+        })
+        .collect();
+
+    build_child(0, &splits, &leaves, 0).map(Tree::new).map_err(parse_err)
+}
+
+impl Ensemble {
+    /// Load a LightGBM text model (`model.txt`)
+    ///
+    /// # Arguments
+    /// * `text` - Contents written by `booster.save_model`
+    ///
+    /// # Returns
+    /// * `Result<Ensemble, LightGbmError>` - Ensemble with thresholds and leaves scaled by 10^10
+    pub fn from_lightgbm(text: &str) -> Result<Ensemble, LightGbmError> {
+        Ensemble::from_lightgbm_with_scale(text, PRECISION_MULTIPLIER)
+    }
+
+    /// Load a LightGBM text model file
+    #[cfg(feature = "std")]
+    pub fn from_lightgbm_file<P: AsRef<Path>>(path: P) -> Result<Ensemble, LightGbmError> {
+        Ensemble::from_lightgbm(&std::fs::read_to_string(path)?)
+    }
+
+    /// Load a LightGBM text model at a custom fixed-point scale
+    pub fn from_lightgbm_with_scale(text: &str, scale: i64) -> Result<Ensemble, LightGbmError> {
+        let (header, tree_sections) = sections(text);
+
+        if let Some((_, per_iteration)) = header.get("num_tree_per_iteration") {
+            if per_iteration.trim() != "1" {
+                return Err(LightGbmError::Unsupported(format!("{} trees per iteration (multiclass)", per_iteration)));
+            }
+        }
+        if tree_sections.is_empty() {
+            return Err(LightGbmError::Parse { line: 1, message: "no 'Tree=' sections found".to_string() });
+        }
+
+        let trees = tree_sections
+            .iter()
+            .map(|section| parse_tree(section, scale))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut ensemble = Ensemble::new(trees, scale).with_objective(objective(&header)?);
+        if let Some((_, max_feature)) = header.get("max_feature_idx") {
+            if let Ok(max_feature) = max_feature.trim().parse::<usize>() {
+                ensemble = ensemble.with_num_features(max_feature + 1);
+            }
+        }
+        Ok(ensemble)
+    }
+}
+
+/// Objective from the `objective=` header, e.g. "tweedie tweedie_variance_power:1.5"
+fn objective(header: &Section) -> Result<Objective, LightGbmError> {
+    let Some((_, value)) = header.get("objective") else {
+        return Ok(Objective::SquaredError);
+    };
+    let mut tokens = value.split_whitespace();
+    let objective = tokens.next().and_then(Objective::from_name).unwrap_or(Objective::SquaredError);
+    // binary predicts sigmoid(sigmoid * score); only the standard logistic is supported
+    if objective == Objective::Logistic {
+        if let Some(sigmoid) = tokens.find_map(|token| token.strip_prefix("sigmoid:")) {
+            if sigmoid.parse::<f64>() != Ok(1.0) {
+                return Err(LightGbmError::Unsupported(format!("binary objective with sigmoid:{}", sigmoid)));
+            }
+        }
+    }
+    Ok(objective)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = "tree
+version=v3
+num_class=1
+num_tree_per_iteration=1
+label_index=0
+max_feature_idx=3
+objective=regression
+
+Tree=0
+num_leaves=3
+num_cat=0
+split_feature=2 0
+split_gain=10 5
+threshold=1.5 -0.25
+decision_type=2 6
+left_child=1 -1
+right_child=-2 -3
+leaf_value=0.1 0.2 0.3
+shrinkage=1
+
+
+Tree=1
+num_leaves=2
+num_cat=0
+split_feature=1
+threshold=0.5
+decision_type=4
+left_child=-1
+right_child=-2
+leaf_value=-1 1
+shrinkage=0.1
+
+
+Tree=2
+num_leaves=1
+num_cat=0
+leaf_value=0.05
+shrinkage=1
+
+
+end of trees
+
+feature_importances:
+Column_2=1
+";
+
+    fn fp(x: f64) -> i64 {
+        crate::to_fixed_point(x)
+    }
+
+    /// LightGBM's NumericalDecision for finite inputs
+    fn reference(x: &[f64]) -> f64 {
+        let tree0 = if x[2] <= 1.5 {
+            // zero-as-missing, default left
+            if x[0] == 0.0 || x[0] <= -0.25 { 0.1 } else { 0.3 }
+        } else {
+            0.2
+        };
+        // zero-as-missing, default right
+        let tree1 = if x[1] != 0.0 && x[1] <= 0.5 { -1.0 } else { 1.0 };
+        tree0 + tree1 + 0.05
+    }
+
+    #[test]
+    fn test_load_lightgbm_model() {
+        let ensemble = Ensemble::from_lightgbm(MODEL).unwrap();
+        assert_eq!(ensemble.num_trees(), 3);
+        assert_eq!(ensemble.num_features(), 4);
+
+        let values = [-1.0, -0.25, -0.0000000001, 0.0, 0.0000000001, 0.5, 0.5000000001, 1.5, 2.0];
+        for &a in &values {
+            for &b in &values {
+                for &c in &values {
+                    let x = [a, b, c, 0.0];
+                    let scaled: Vec<i64> = x.iter().map(|&v| fp(v)).collect();
+                    assert_eq!(ensemble.eval(&scaled), fp(reference(&x)), "{:?}", x);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsupported_models_are_rejected() {
+        let categorical = MODEL.replace("decision_type=2 6", "decision_type=1 6");
+        assert!(matches!(Ensemble::from_lightgbm(&categorical), Err(LightGbmError::Unsupported(_))));
+
+        let multiclass = MODEL.replace("num_tree_per_iteration=1", "num_tree_per_iteration=3");
+        assert!(matches!(Ensemble::from_lightgbm(&multiclass), Err(LightGbmError::Unsupported(_))));
+
+        let scaled_sigmoid = MODEL.replace("objective=regression", "objective=binary sigmoid:2");
+        assert!(matches!(Ensemble::from_lightgbm(&scaled_sigmoid), Err(LightGbmError::Unsupported(_))));
+    }
+
+    #[test]
+    fn test_objective_header() {
+        assert_eq!(Ensemble::from_lightgbm(MODEL).unwrap().objective(), Objective::SquaredError);
+        let tweedie = MODEL.replace("objective=regression", "objective=tweedie tweedie_variance_power:1.5");
+        let model = Ensemble::from_lightgbm(&tweedie).unwrap();
+        assert_eq!(model.objective(), Objective::Tweedie);
+        let features = [fp(-1.0), fp(0.25), fp(2.0), 0];
+        assert_eq!(model.predict_response(&features), crate::fixed_exp(model.eval(&features)));
+
+        let binary = MODEL.replace("objective=regression", "objective=binary sigmoid:1");
+        assert_eq!(Ensemble::from_lightgbm(&binary).unwrap().objective(), Objective::Logistic);
+        let huber = MODEL.replace("objective=regression", "objective=huber alpha:0.9");
+        assert_eq!(Ensemble::from_lightgbm(&huber).unwrap().objective(), Objective::SquaredError);
+    }
+
+    #[test]
+    fn test_malformed_models_report_lines() {
+        let short = MODEL.replace("leaf_value=0.1 0.2 0.3", "leaf_value=0.1 0.2");
+        assert!(matches!(Ensemble::from_lightgbm(&short), Err(LightGbmError::Parse { line: 18, .. })));
+        assert!(matches!(Ensemble::from_lightgbm("tree\nversion=v3\n"), Err(LightGbmError::Parse { .. })));
+    }
+}
@@ -0,0 +1,136 @@
+// Fixed-point linear models
+// Linear and logistic regression (e.g. sklearn's LinearRegression / LogisticRegression, or a
+// calibration layer stacked on a tree ensemble's margin) as scaled i64 weights and bias.
+//
+// The dot product is accumulated exactly in i128: each product of two values at `scale` is at
+// scale^2, so the sum is brought back to `scale` with a single division, rounded to nearest with
+// ties toward +infinity, i.e. floor((dot + scale / 2) / scale). That is one floor division, which
+// a circuit proves with a quotient and a remainder in [0, scale) (`r1cs::linear_gadget`). The
+// bias is then added with `fixed_add`, so the result saturates like every other prediction.
+
+use alloc::vec::Vec;
+
+use crate::fixed::{rescale_round, round_half_away, saturate};
+use crate::{fixed_add, fixed_sigmoid, PRECISION_MULTIPLIER};
+
+/// Linear model `bias + sum(weights[i] * features[i])` in fixed point
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinearModel {
+    weights: Vec<i64>,
+    bias: i64,
+    scale: i64,
+}
+
+impl LinearModel {
+    /// Create a model from weights and bias already scaled to `scale`
+    pub fn new(weights: Vec<i64>, bias: i64, scale: i64) -> LinearModel {
+        assert!(scale > 0, "fixed-point scale must be positive");
+        LinearModel { weights, bias, scale }
+    }
+
+    /// Quantize floating-point coefficients (e.g. sklearn's `coef_` and `intercept_`)
+    ///
+    /// # Arguments
+    /// * `weights` - One coefficient per feature
+    /// * `bias` - Intercept
+    /// * `scale` - Fixed-point scale of weights, inputs and output
+    ///
+    /// # Returns
+    /// * `LinearModel` - Weights and bias rounded to nearest at `scale`
+    pub fn from_f64(weights: &[f64], bias: f64, scale: i64) -> LinearModel {
+        let quantize = |value: f64| round_half_away(value * scale as f64);
+        LinearModel::new(weights.iter().map(|&w| quantize(w)).collect(), quantize(bias), scale)
+    }
+
+    /// Weights (at the model's scale)
+    pub fn weights(&self) -> &[i64] {
+        &self.weights
+    }
+
+    /// Bias (at the model's scale)
+    pub fn bias(&self) -> i64 {
+        self.bias
+    }
+
+    /// Fixed-point scale of weights, inputs and output
+    pub fn scale(&self) -> i64 {
+        self.scale
+    }
+
+    /// Minimum number of features a prediction input must provide
+    pub fn num_features(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Exact dot product of the weights with `features`, at scale^2
+    pub fn dot(&self, features: &[i64]) -> i128 {
+        assert!(features.len() >= self.weights.len(),
+                "Expected at least {} features, got {}", self.weights.len(), features.len());
+
+        self.weights
+            .iter()
+            .zip(features)
+            .fold(0i128, |sum, (&w, &x)| sum.saturating_add(w as i128 * x as i128))
+    }
+
+    /// Evaluate the model on a feature vector
+    ///
+    /// # Arguments
+    /// * `features` - Input feature vector (at the model's scale)
+    ///
+    /// # Returns
+    /// * `i64` - `bias + dot / scale`, the division rounded to nearest (ties toward +infinity)
+    pub fn eval(&self, features: &[i64]) -> i64 {
+        let scale = self.scale as i128;
+        let product = self.dot(features).saturating_add(scale / 2).div_euclid(scale);
+        fixed_add(self.bias, saturate(product))
+    }
+
+    /// Probability of the positive class (logistic regression), for models at scale 10^10
+    pub fn predict_proba(&self, features: &[i64]) -> i64 {
+        assert_eq!(self.scale, PRECISION_MULTIPLIER, "fixed_sigmoid expects margins scaled by 10^10");
+        fixed_sigmoid(self.eval(features))
+    }
+
+    /// Convert the model to another fixed-point scale (weights and bias rounded to nearest)
+    pub fn rescale(&self, scale: i64) -> LinearModel {
+        let from = self.scale;
+        LinearModel::new(
+            self.weights.iter().map(|&w| rescale_round(w, from, scale)).collect(),
+            rescale_round(self.bias, from, scale),
+            scale,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::to_fixed_point;
+    use alloc::vec;
+
+    #[test]
+    fn test_eval_rounds_once() {
+        // 0.5 * 3 - 0.3 * 2 + 1 = 1.9 at scale 10
+        let model = LinearModel::new(vec![5, -3], 10, 10);
+        assert_eq!(model.dot(&[30, 20]), 150 - 60);
+        assert_eq!(model.eval(&[30, 20]), 19);
+        // 0.5 * 0.1 = 0.05 is a tie: rounds up to 0.1; -0.05 rounds up to 0.0
+        assert_eq!(model.eval(&[1, 0]), 11);
+        assert_eq!(model.eval(&[-1, 0]), 10);
+        assert_eq!(LinearModel::new(vec![i64::MAX], i64::MAX, 1).eval(&[2]), i64::MAX);
+    }
+
+    #[test]
+    fn test_from_f64_matches_float_evaluation() {
+        let weights = [0.75, -1.5, 0.0625, 2.0];
+        let model = LinearModel::from_f64(&weights, -0.3, PRECISION_MULTIPLIER);
+        let inputs = [1.25, -0.4, 12.0, 0.001];
+        let features: Vec<i64> = inputs.iter().map(|&x| to_fixed_point(x)).collect();
+        let expected = -0.3 + weights.iter().zip(&inputs).map(|(w, x)| w * x).sum::<f64>();
+
+        assert!((model.eval(&features) - to_fixed_point(expected)).abs() <= 1);
+        assert_eq!(model.rescale(1000).weights(), &[750, -1500, 63, 2000]);
+        assert_eq!(model.predict_proba(&[0; 4]), fixed_sigmoid(model.bias()));
+    }
+}
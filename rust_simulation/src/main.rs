@@ -1,45 +1,543 @@
 // Test program for XGBoost rainfall prediction model
 // This demonstrates how to use the generated Rust prediction function
 
-use std::env;
-use std::io::{self, Write};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 // Import the generated rainfall prediction library
-use rainfall_prediction::{xgboost_predict, from_fixed_point, to_fixed_point};
+use rainfall_prediction::codegen::{generate_leo_source, generate_rust_source};
+use rainfall_prediction::csv::{is_header, parse_decimal_row_at, parse_scaled_row_at, MISSING_FIELD};
+use rainfall_prediction::json::{self, JsonValue};
+use rainfall_prediction::replay::{read_replay_log, replay_records, ReplayLog, ReplayRecord};
+use rainfall_prediction::{
+    builtin_model, expand_sparse_f64_at, hash_features, parse_decimal, xgboost_predict, from_fixed_point,
+    from_fixed_point_at, to_fixed_point, to_fixed_point_at, to_hex, Divergence, Evaluation, EvaluatorMode, FeatureSchema, Fingerprint, Model, ModelRegistry, OutputBounds,
+    ParseFixedError, PortCheck, PoseidonDigest, PredictionCache, RegisteredModel, RequestFeatures, ServerMetrics,
+    SyntheticGenerator, ValueBounds,
+    MODEL_ID, NUM_FEATURES, PORT_TOLERANCE, PRECISION_MULTIPLIER,
+};
+
+/// Replay log that every prediction is appended to when `--record` is given
+static RECORDER: OnceLock<Mutex<ReplayLog>> = OnceLock::new();
+
+/// Runtime model (identifier, model) used instead of the compiled one when `--model` is given
+static RUNTIME_MODEL: OnceLock<(String, Model)> = OnceLock::new();
+
+/// Prediction cache shared by all requests when `--cache <entries>` is given
+static CACHE: OnceLock<Mutex<PredictionCache>> = OnceLock::new();
+
+/// Models the server modes route requests to, created on first use (see `registry`)
+static REGISTRY: OnceLock<ModelRegistry> = OnceLock::new();
+
+/// Model (id, version) that `--shadow` scores every input with as well
+static SHADOW: OnceLock<(String, Option<String>)> = OnceLock::new();
+
+/// Divergence of the shadow model's predictions from the served ones
+static SHADOW_DIVERGENCE: Mutex<Divergence> = Mutex::new(Divergence::new());
 
-/// Convert array of float features to scaled integers
+/// Whether requests may change the registry (`serve --admin`)
+static ADMIN: AtomicBool = AtomicBool::new(false);
+
+/// Key cache used by the proving commands when `--key-cache <dir>` is given
+#[cfg(any(feature = "plonky2", feature = "groth16"))]
+static KEY_STORE: OnceLock<rainfall_prediction::KeyStore> = OnceLock::new();
+
+/// Request counters and latencies of the server modes, exposed by `--metrics <addr>`
+static METRICS: ServerMetrics = ServerMetrics::new();
+
+/// Convert array of float features to scaled integers at the current model's scale
 fn prepare_features(float_features: &[f64]) -> Vec<i64> {
+    let scale = current_model().scale();
     float_features.iter()
-        .map(|&x| to_fixed_point(x))
+        .map(|&x| to_fixed_point_at(x, scale))
         .collect()
 }
 
+/// Rainfall prediction with the fixed-point XGBoost model
+///
+/// Without a command, starts the interactive menu.
+#[derive(Debug, Parser)]
+#[command(name = "predict", version)]
+struct Cli {
+    /// XGBoost JSON dump, ONNX tree ensemble (.onnx), LightGBM model (.txt) or binary model (.bin)
+    /// to use instead of the compiled model
+    #[arg(long, global = true, value_name = "PATH")]
+    model: Option<String>,
+
+    /// Sidecar JSON naming the model's features (an array, or an object from `f<index>` to name)
+    #[arg(long, global = true, value_name = "PATH")]
+    feature_names: Option<String>,
+
+    /// Append every prediction to a replay log
+    #[arg(long, global = true, value_name = "LOG")]
+    record: Option<String>,
+
+    /// Reuse the predictions of repeated feature vectors (hit rate reported on stderr)
+    #[arg(long, global = true, value_name = "ENTRIES")]
+    cache: Option<usize>,
+
+    /// Also register the model at PATH under ID and VERSION ("1" if omitted); repeatable
+    #[arg(long, global = true, value_name = "ID[@VERSION]=PATH")]
+    register: Vec<String>,
+
+    /// Also score every batch row and server request with this registered model and report how
+    /// its predictions diverge (the served predictions are unchanged)
+    #[arg(long, global = true, value_name = "ID[@VERSION]")]
+    shadow: Option<String>,
+
+    /// Keep proving and verification keys in DIR and reuse them across runs (prove, verify,
+    /// solidity-verifier, prove-groth16)
+    #[arg(long, global = true, value_name = "DIR")]
+    key_cache: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Predict the rainfall for one feature vector
+    Predict(FeatureArgs),
+    /// Score every row of a CSV file of raw features
+    Batch {
+        /// CSV file with 116 raw features per row and an optional header
+        input: String,
+        /// File to write `line,prediction,prediction_scaled,fingerprint` rows to
+        #[arg(short, long)]
+        output: String,
+        /// Also write the leaf value of every tree
+        #[arg(long)]
+        contributions: bool,
+        /// Rows hold values already scaled by 10^10 (i64) instead of raw floats
+        #[arg(long)]
+        scaled: bool,
+    },
+    /// Measure the error of the predictions against labels in a CSV (or, with `parquet`, Parquet) file
+    Evaluate {
+        /// CSV or .parquet file with 116 features, the label and optionally the float model's prediction per row
+        input: String,
+        /// Feature columns hold values already scaled by 10^10 (labels stay raw)
+        #[arg(long)]
+        scaled: bool,
+        /// Print the metrics as one JSON object
+        #[arg(long)]
+        json: bool,
+    },
+    /// Time predictions on generated feature vectors
+    Bench {
+        /// Number of predictions
+        #[arg(long, default_value_t = 1000, value_parser = clap::value_parser!(u32).range(1..))]
+        count: u32,
+        /// Seed of the synthetic feature vectors
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+    },
+    /// Write reproducible synthetic radar feature vectors as CSV
+    Generate {
+        /// Number of rows
+        #[arg(long, default_value_t = 1000)]
+        count: usize,
+        /// Seed of the random stream: the same seed gives the same rows
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// JSON file overriding per-feature distributions
+        #[arg(long, value_name = "PATH")]
+        config: Option<String>,
+        /// File to write to instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Write values scaled by 10^10 (i64) instead of raw floats
+        #[arg(long)]
+        scaled: bool,
+    },
+    /// Write the model as a Leo program or Rust source
+    GenCircuit {
+        /// Language to emit
+        #[arg(long, value_enum, default_value_t = CircuitTarget::Leo)]
+        target: CircuitTarget,
+        /// Program name of the Leo target
+        #[arg(long, default_value = "rainfall_prediction")]
+        program: String,
+        /// Output file (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Prove the prediction for one feature vector with plonky2
+    #[cfg(feature = "plonky2")]
+    Prove {
+        #[command(flatten)]
+        features: FeatureArgs,
+        /// File to write the `ModelProof` bundle to
+        #[arg(short, long)]
+        output: String,
+    },
+    /// Write the model's plonky2 verifier key, for verifying on machines without the prover
+    #[cfg(feature = "plonky2")]
+    ExportVerifier {
+        /// File to write the key to
+        #[arg(short, long)]
+        output: String,
+    },
+    /// Verify a proof written by `prove` against the model, or against a key from `export-verifier`
+    #[cfg(feature = "verifier")]
+    Verify {
+        /// `ModelProof` bundle
+        proof: String,
+        /// Scaled prediction the proof must be for (default: the one it claims)
+        #[arg(long, allow_negative_numbers = true)]
+        prediction: Option<i64>,
+        /// Verifier key to check the proof with instead of building the model's circuit
+        #[arg(long)]
+        key: Option<String>,
+    },
+    /// Write a Solidity contract verifying Groth16 proofs of the model's R1CS circuit (needs --key-cache)
+    #[cfg(feature = "groth16")]
+    SolidityVerifier {
+        /// Output file (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Prove the prediction for one feature vector with Groth16 and print the contract's calldata
+    #[cfg(feature = "groth16")]
+    ProveGroth16(FeatureArgs),
+    /// Print the model's metadata, per-tree shape, feature usage and estimated constraint count
+    Inspect {
+        /// Print the report as one JSON object
+        #[arg(long)]
+        json: bool,
+        /// Also write each tree as a Graphviz file DIR/tree<i>.dot
+        #[arg(long, value_name = "DIR")]
+        dot: Option<String>,
+    },
+    /// Print the build fingerprint (model hash, crate version, precision, evaluator) of this run
+    Fingerprint {
+        /// Print it as JSON
+        #[arg(long)]
+        json: bool,
+        /// Exit with status 1 unless it matches this fingerprint id (from a CSV column) or the
+        /// fingerprint in this JSON file (a fingerprint, trace or proof metadata document)
+        #[arg(long, value_name = "ID|PATH")]
+        check: Option<String>,
+    },
+    /// Compare the model node by node with the XGBoost JSON dump it was ported from
+    VerifyPort {
+        /// Original XGBoost JSON dump
+        dump: String,
+        /// Largest accepted difference of a threshold or leaf, in scaled units
+        #[arg(long, default_value_t = PORT_TOLERANCE)]
+        tolerance: f64,
+    },
+    /// Answer JSON-lines requests from stdin on stdout, or serve them over the network
+    Serve(ServeArgs),
+    /// Re-run a replay log and diff the outputs
+    Replay {
+        /// Log written with --record
+        log: String,
+    },
+    /// Run the demonstration
+    Demo,
+    /// Run the built-in self-tests
+    Test,
+    /// Interactive menu (the default)
+    Interactive,
+}
+
+/// Feature vector given on the command line
+#[derive(Debug, Args)]
+struct FeatureArgs {
+    /// The 116 feature values: raw floats, or fixed-point integers with --scaled
+    #[arg(allow_negative_numbers = true, required_unless_present_any = ["sparse", "file"], value_name = "FEATURE")]
+    features: Vec<String>,
+    /// Only the non-zero features, as <index>:<value> pairs
+    #[arg(long, num_args = 1.., value_name = "INDEX:VALUE", value_parser = sparse_pair, conflicts_with = "features")]
+    sparse: Vec<(usize, f64)>,
+    /// JSON (array, or object with a `features` array) or CSV (one row) file holding all 116 values
+    #[arg(long = "features", value_name = "PATH", conflicts_with_all = ["features", "sparse"])]
+    file: Option<String>,
+    /// Values are already scaled by 10^10 (i64) and used bit-exactly instead of converting floats
+    #[arg(long, conflicts_with = "sparse")]
+    scaled: bool,
+}
+
+/// Where `serve` answers requests
+#[derive(Debug, Args)]
+struct ServeArgs {
+    /// Serve the JSON-lines protocol over TCP instead of stdin
+    #[cfg(feature = "server")]
+    #[arg(long, value_name = "ADDR:PORT")]
+    listen: Option<String>,
+    /// Expose Prometheus metrics at http://<ADDR:PORT>/metrics
+    #[cfg(feature = "server")]
+    #[arg(long, value_name = "ADDR:PORT")]
+    metrics: Option<String>,
+    /// Serve the gRPC service of proto/inference.proto
+    #[cfg(feature = "grpc")]
+    #[arg(long, value_name = "ADDR:PORT")]
+    grpc: Option<String>,
+    /// Serve the HTTP API described at /openapi.json
+    #[cfg(feature = "rest")]
+    #[arg(long, value_name = "ADDR:PORT")]
+    rest: Option<String>,
+    /// Accept `load`, `activate` and `default` requests that swap the served models
+    #[arg(long)]
+    admin: bool,
+}
+
+/// Source language of `gen-circuit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CircuitTarget {
+    /// Aleo program with a `predict` transition
+    Leo,
+    /// Nested-if Rust source of `xgboost_predict`
+    Rust,
+}
+
 fn main() {
-    println!("XGBoost Rainfall Prediction Test Program");
-    println!("========================================");
+    let cli = Cli::parse();
 
-    let args: Vec<String> = env::args().collect();
+    // Status messages go to stderr so that stdout only carries results (responses, source, reports)
+    let mut runtime = cli.model.as_ref().map(|path| match load_model_file(path) {
+        Ok(model) => {
+            let hash = to_hex(&model.content_hash());
+            eprintln!("Loaded {} trees from {} (sha256 {})", model.num_trees(), path, hash);
+            let id = std::path::Path::new(path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.clone());
+            (id, model)
+        }
+        Err(e) => fail(&format!("Error loading model {}: {}", path, e)),
+    });
 
-    match args.len() {
-        1 => {
-            // Interactive mode
-            interactive_mode();
+    // The sidecar names whichever model is current, so it is applied before that model is fixed
+    if let Some(path) = &cli.feature_names {
+        let (id, model) = runtime.take().unwrap_or_else(|| (MODEL_ID.to_string(), builtin_model()));
+        let named = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|sidecar| model.with_feature_names_json(&sidecar).map_err(|e| e.to_string()));
+        match named {
+            Ok(model) => {
+                eprintln!("Named {} features from {}", model.num_features(), path);
+                runtime = Some((id, model));
+            }
+            Err(e) => fail(&format!("Error loading feature names {}: {}", path, e)),
         }
-        2 if args[1] == "--test" => {
-            // Run built-in tests
-            run_tests();
+    }
+
+    if let Some(runtime) = runtime {
+        if RUNTIME_MODEL.set(runtime).is_err() {
+            fail("Error: the runtime model was already set");
         }
-        2 if args[1] == "--demo" => {
-            // Run demonstration with sample data
+    }
+
+    if let Some(path) = &cli.record {
+        match ReplayLog::open(path) {
+            Ok(log) => {
+                eprintln!("Recording predictions to {}", path);
+                let _ = RECORDER.set(Mutex::new(log));
+            }
+            Err(e) => fail(&format!("Error opening replay log {}: {}", path, e)),
+        }
+    }
+
+    if let Some(entries) = cli.cache {
+        eprintln!("Caching up to {} predictions", entries);
+        let _ = CACHE.set(Mutex::new(PredictionCache::new(entries)));
+    }
+
+    for spec in &cli.register {
+        let Some((key, path)) = spec.split_once('=') else {
+            fail(&format!("Invalid --register {}: expected ID[@VERSION]=PATH", spec));
+        };
+        let (id, version) = key.split_once('@').unwrap_or((key, "1"));
+        let model = load_model_file(path).unwrap_or_else(|e| fail(&format!("Error loading model {}: {}", path, e)));
+        eprintln!("Registered {}@{} from {} (sha256 {})", id, version, path, to_hex(&model.content_hash()));
+        registry().register(id, version, model);
+    }
+
+    if let Some(spec) = &cli.shadow {
+        let (id, version) = match spec.split_once('@') {
+            Some((id, version)) => (id.to_string(), Some(version.to_string())),
+            None => (spec.clone(), None),
+        };
+        match registry().get(Some(&id), version.as_deref()).map_err(|e| e.to_string()) {
+            Ok(entry) => match check_shadow(current_model(), &entry.model) {
+                Ok(()) => eprintln!("Shadow scoring with {}@{}", entry.id, entry.version),
+                Err(e) => fail(&format!("Invalid --shadow {}: {}", spec, e)),
+            },
+            Err(e) => fail(&format!("Invalid --shadow {}: {}", spec, e)),
+        }
+        let _ = SHADOW.set((id, version));
+    }
+
+    if let Some(dir) = &cli.key_cache {
+        #[cfg(any(feature = "plonky2", feature = "groth16"))]
+        let _ = KEY_STORE.set(rainfall_prediction::KeyStore::new(dir));
+        #[cfg(not(any(feature = "plonky2", feature = "groth16")))]
+        eprintln!("Ignoring --key-cache {}: built without the plonky2 and groth16 features", dir);
+    }
+
+    match cli.command.unwrap_or(Command::Interactive) {
+        Command::Predict(features) => predict_mode(&features),
+        Command::Batch { input, output, contributions, scaled } => csv_mode(&input, &output, contributions, scaled),
+        Command::Evaluate { input, scaled, json } => evaluate_mode(&input, scaled, json),
+        Command::Bench { count, seed } => performance_test(count, seed),
+        Command::Generate { count, seed, config, output, scaled } => {
+            generate_mode(count, seed, config.as_deref(), output.as_deref(), scaled)
+        }
+        Command::GenCircuit { target, program, output } => gen_circuit_mode(target, &program, output.as_deref()),
+        #[cfg(feature = "plonky2")]
+        Command::Prove { features, output } => prove_mode(&features, &output),
+        #[cfg(feature = "plonky2")]
+        Command::ExportVerifier { output } => export_verifier_mode(&output),
+        #[cfg(feature = "verifier")]
+        Command::Verify { proof, prediction, key } => verify_mode(&proof, prediction, key.as_deref()),
+        #[cfg(feature = "groth16")]
+        Command::SolidityVerifier { output } => solidity_verifier_mode(output.as_deref()),
+        #[cfg(feature = "groth16")]
+        Command::ProveGroth16(features) => prove_groth16_mode(&features),
+        Command::Inspect { json, dot } => inspect_mode(json, dot.as_deref()),
+        Command::Fingerprint { json, check } => fingerprint_mode(json, check.as_deref()),
+        Command::VerifyPort { dump, tolerance } => verify_port_mode(&dump, tolerance),
+        Command::Serve(args) => serve_mode(&args),
+        Command::Replay { log } => replay_mode(&log),
+        Command::Demo => {
+            print_banner();
             run_demo();
         }
-        117.. => {
-            // Command line mode with 116 features
-            command_line_mode(&args[1..117]);
+        Command::Test => {
+            print_banner();
+            run_tests();
+        }
+        Command::Interactive => {
+            print_banner();
+            interactive_mode();
         }
-        _ => {
-            print_usage(&args[0]);
+    }
+    report_cache();
+    report_shadow();
+}
+
+fn print_banner() {
+    println!("XGBoost Rainfall Prediction Test Program");
+    println!("========================================");
+}
+
+/// Load a model file by extension: binary (.bin), ONNX (.onnx), LightGBM (.txt) or an XGBoost JSON dump
+fn load_model_file(path: &str) -> Result<Model, String> {
+    if path.ends_with(".bin") {
+        Model::from_bytes_file(path).map_err(|e| e.to_string())
+    } else if path.ends_with(".onnx") {
+        Model::from_onnx_file(path).map_err(|e| e.to_string())
+    } else if path.ends_with(".txt") {
+        Model::from_lightgbm_file(path).map_err(|e| e.to_string())
+    } else {
+        Model::from_xgboost_json_file(path).map_err(|e| e.to_string())
+    }
+}
+
+/// Print an error and exit with status 1
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+/// Scaled feature vector stored in a JSON or CSV file
+///
+/// JSON is an array of values or an object with a `features` array (`features_scaled` for scaled
+/// values); anything else is read as CSV with one row and an optional header. Values are raw
+/// floats, or integers already scaled by 10^10 with `scaled`, and must number exactly 116. Empty
+/// CSV fields are the missing-value sentinel.
+fn read_feature_file(path: &str, scaled: bool) -> Result<Vec<i64>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let in_file = |message: String| format!("{}: {}", path, message);
+    let (fields, scaled) = if text.trim_start().starts_with(['[', '{']) {
+        let document = json::parse(&text).map_err(|e| in_file(e.to_string()))?;
+        let (values, scaled) = match (&document, document.get("features"), document.get("features_scaled")) {
+            (JsonValue::Array(values), _, _) => (values.as_slice(), scaled),
+            (_, Some(JsonValue::Array(values)), None) => (values.as_slice(), scaled),
+            (_, None, Some(JsonValue::Array(values))) => (values.as_slice(), true),
+            (_, Some(JsonValue::Object(entries)), None) if !scaled => {
+                return read_named_features(entries).map_err(in_file);
+            }
+            _ => return Err(in_file("expected an array or an object with a \"features\" array".to_string())),
+        };
+        (values.iter().map(JsonValue::to_string).collect::<Vec<String>>(), scaled)
+    } else {
+        let mut rows: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+        if rows.first().is_some_and(|line| is_header(line)) {
+            rows.remove(0);
         }
+        let [row] = rows.as_slice() else {
+            return Err(in_file(format!("expected one row of features, found {}", rows.len())));
+        };
+        (row.split(',').map(|field| field.trim().to_string()).collect(), scaled)
+    };
+    parse_feature_values(&fields, scaled, current_model()).map_err(in_file)
+}
+
+/// Assemble the features of a `{"features": {name: value}}` file in input order (null is missing)
+fn read_named_features(entries: &[(String, JsonValue)]) -> Result<Vec<i64>, String> {
+    let values = entries
+        .iter()
+        .map(|(name, value)| match value {
+            JsonValue::Null => Ok((name.clone(), f64::NAN)),
+            _ => value.as_f64().map(|v| (name.clone(), v)).ok_or_else(|| format!("'{}' is not a number", name)),
+        })
+        .collect::<Result<HashMap<String, f64>, String>>()?;
+    let model = current_model();
+    let mut scaled_features = model.named_features(&values).map_err(|e| e.to_string())?;
+    // Features past the model's own are never read, so a narrower model still takes a full vector
+    scaled_features.resize(input_len(model), 0);
+    Ok(scaled_features)
+}
+
+/// Scale the textual values of a feature file or the command line for `model`, naming the first bad one
+fn parse_feature_values(fields: &[String], scaled: bool, model: &Model) -> Result<Vec<i64>, String> {
+    let expected = input_len(model);
+    if fields.len() != expected {
+        return Err(format!("expected {} features, got {}", expected, fields.len()));
+    }
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match (field.is_empty(), scaled) {
+            (true, _) => Ok(to_fixed_point_at(MISSING_FIELD, model.scale())),
+            (false, true) => field.parse::<i64>().map_err(|_| format!("feature {} ({}) is not an i64", index, field)),
+            (false, false) => parse_decimal(field, model.scale()).map_err(|error| match error {
+                ParseFixedError::Invalid => format!("feature {} ({}) is not a finite number", index, field),
+                ParseFixedError::OutOfRange => format!("feature {} ({}) is out of range", index, field),
+            }),
+        })
+        .collect()
+}
+
+/// `--sparse` value parser
+fn sparse_pair(arg: &str) -> Result<(usize, f64), String> {
+    parse_sparse_pair(arg).ok_or_else(|| format!("'{}' is not of the form <index>:<value>", arg))
+}
+
+impl FeatureArgs {
+    /// Feature vector at the current model's scale, checked against its input schema
+    fn scaled(&self) -> Result<Vec<i64>, String> {
+        let model = current_model();
+        let scaled_features = if !self.sparse.is_empty() {
+            expand_sparse_f64_at(&self.sparse, input_len(model), model.scale()).map_err(|e| e.to_string())?
+        } else {
+            match &self.file {
+                Some(path) => read_feature_file(path, self.scaled)?,
+                None => parse_feature_values(&self.features, self.scaled, model)?,
+            }
+        };
+        // Reject physically impossible inputs instead of predicting on them
+        check_features(&scaled_features, model)?;
+        Ok(scaled_features)
     }
 }
 
@@ -52,9 +550,10 @@ fn interactive_mode() {
         println!("Choose an option:");
         println!("1. Test with sample data");
         println!("2. Enter custom features");
-        println!("3. Batch prediction demo");
-        println!("4. Performance test");
-        println!("5. Exit");
+        println!("3. Load all features from a JSON or CSV file");
+        println!("4. Batch prediction demo");
+        println!("5. Performance test");
+        println!("6. Exit");
         print!("> ");
         io::stdout().flush().unwrap();
 
@@ -64,9 +563,10 @@ fn interactive_mode() {
         match input.trim() {
             "1" => test_with_sample_data(),
             "2" => custom_feature_input(),
-            "3" => batch_prediction_demo(),
-            "4" => performance_test(),
-            "5" => break,
+            "3" => file_feature_input(),
+            "4" => batch_prediction_demo(),
+            "5" => performance_test(1000, 0),
+            "6" => break,
             _ => println!("Invalid option. Please try again."),
         }
         println!();
@@ -93,7 +593,7 @@ fn test_with_sample_data() {
 
     // Pad with zeros to reach 116 features
     let mut full_features = sample_features;
-    full_features.resize(116, 0.0);
+    full_features.resize(NUM_FEATURES, 0.0);
 
     let prediction = make_prediction(&full_features);
     
@@ -132,7 +632,7 @@ fn custom_feature_input() {
         "Correlation Coefficient (unitless, range: 0 to 1)",
     ];
 
-    let mut features = vec![0.0; 116];
+    let mut features = vec![0.0; NUM_FEATURES];
     
     for (i, name) in feature_names.iter().enumerate() {
         print!("Enter {}: ", name);
@@ -155,11 +655,35 @@ fn custom_feature_input() {
     println!("Prediction with custom features: {:.6} mm", prediction);
 }
 
+fn file_feature_input() {
+    print!("Feature file (.json or .csv): ");
+    io::stdout().flush().unwrap();
+    let mut path = String::new();
+    io::stdin().read_line(&mut path).unwrap();
+    print!("Are the values already scaled by 10^10? [y/N]: ");
+    io::stdout().flush().unwrap();
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).unwrap();
+
+    match read_feature_file(path.trim(), answer.trim().eq_ignore_ascii_case("y")) {
+        Ok(scaled_features) => {
+            if let Err(e) = check_features(&scaled_features, current_model()) {
+                println!("Invalid features: {}", e);
+                return;
+            }
+            let prediction = from_model_scale(predict_and_record(scaled_features));
+            println!();
+            println!("Prediction with features from {}: {:.6} mm", path.trim(), prediction);
+        }
+        Err(e) => println!("Error: {}", e),
+    }
+}
+
 fn batch_prediction_demo() {
     println!("Batch prediction demonstration...");
     
     // Generate sample batch data
-    let batch_data = vec![
+    let batch_data = [
         vec![0.02, 0.04, -0.01, 0.1, 0.08],     // Light rain scenario
         vec![0.15, 0.12, 0.02, 0.25, 0.15],     // Moderate rain scenario  
         vec![0.45, 0.30, 0.08, 0.40, 0.35],     // Heavy rain scenario
@@ -173,7 +697,7 @@ fn batch_prediction_demo() {
 
     for (i, data) in batch_data.iter().enumerate() {
         let mut full_features = data.clone();
-        full_features.resize(116, 0.0);
+        full_features.resize(NUM_FEATURES, 0.0);
         
         let prediction = make_prediction(&full_features);
         
@@ -182,29 +706,24 @@ fn batch_prediction_demo() {
     }
 }
 
-fn performance_test() {
-    println!("Performance test - processing 1000 predictions...");
+fn performance_test(count: u32, seed: u64) {
+    println!("Performance test - processing {} predictions...", count);
     
+    // Synthetic radar rows, generated before the clock starts
+    let rows: Vec<Vec<f64>> = SyntheticGenerator::rainfall(seed).take(count as usize).collect();
     let start = std::time::Instant::now();
     
-    // Generate random-ish test data
     let mut predictions = Vec::new();
-    for i in 0..1000 {
-        let mut features = vec![0.0; 116];
-        // Fill with some variation
-        for j in 0..10 {
-            features[j] = (i as f64 * 0.001 + j as f64 * 0.01) % 1.0;
-        }
-        
-        let prediction = make_prediction(&features);
+    for features in &rows {
+        let prediction = make_prediction(features);
         predictions.push(prediction);
     }
     
     let duration = start.elapsed();
     
-    println!("Processed 1000 predictions in {:?}", duration);
-    println!("Average time per prediction: {:?}", duration / 1000);
-    println!("Predictions per second: {:.0}", 1000.0 / duration.as_secs_f64());
+    println!("Processed {} predictions in {:?}", count, duration);
+    println!("Average time per prediction: {:?}", duration / count);
+    println!("Predictions per second: {:.0}", count as f64 / duration.as_secs_f64());
     
     // Show some statistics
     let avg = predictions.iter().sum::<f64>() / predictions.len() as f64;
@@ -218,22 +737,949 @@ fn performance_test() {
     println!("  Maximum: {:.6} mm", max);
 }
 
-fn command_line_mode(feature_args: &[String]) {
-    println!("Command line mode with {} features", feature_args.len());
-    
-    let features: Result<Vec<f64>, _> = feature_args.iter()
-        .map(|s| s.parse::<f64>())
-        .collect();
-    
-    match features {
-        Ok(feature_values) => {
-            let prediction = make_prediction(&feature_values);
+fn predict_mode(features: &FeatureArgs) {
+    match features.scaled() {
+        Ok(scaled_features) => {
+            let prediction = from_model_scale(predict_and_record(scaled_features));
             println!("Prediction: {:.6} mm", prediction);
         }
+        Err(e) => fail(&format!("Invalid features: {}", e)),
+    }
+}
+
+/// Run the server modes selected by `args`, or answer JSON lines from stdin
+fn serve_mode(args: &ServeArgs) {
+    ADMIN.store(args.admin, Ordering::Relaxed);
+    #[cfg(feature = "server")]
+    if let Some(address) = &args.metrics {
+        metrics_mode(address);
+    }
+    #[cfg(feature = "grpc")]
+    if let Some(address) = &args.grpc {
+        grpc_mode(address);
+        return;
+    }
+    #[cfg(feature = "rest")]
+    if let Some(address) = &args.rest {
+        rest_mode(address);
+        return;
+    }
+    #[cfg(feature = "server")]
+    if let Some(address) = &args.listen {
+        listen_mode(address);
+        return;
+    }
+    let _ = args;
+
+    if let Err(e) = serve_lines(io::stdin().lock(), &mut io::stdout().lock()) {
+        fail(&format!("Error serving stdin: {}", e));
+    }
+}
+
+/// Write the source of the current model in `target`'s language to `output` or stdout
+fn gen_circuit_mode(target: CircuitTarget, program: &str, output: Option<&str>) {
+    let model = current_model();
+    let source = match target {
+        CircuitTarget::Leo => generate_leo_source(model, model.num_features(), program),
+        CircuitTarget::Rust => generate_rust_source(model, model.num_features()),
+    };
+    match output {
+        Some(path) => match std::fs::write(path, source) {
+            Ok(()) => eprintln!("Wrote {:?} source of {} trees to {}", target, model.num_trees(), path),
+            Err(e) => fail(&format!("Error writing {}: {}", path, e)),
+        },
+        None => print!("{}", source),
+    }
+}
+
+/// Print the fingerprint of the current model and build, checking it against another one if given
+fn fingerprint_mode(json: bool, check: Option<&str>) {
+    let fingerprint = Fingerprint::new(current_model(), EvaluatorMode::Branching);
+    if json {
+        println!("{}", fingerprint.to_json());
+    } else {
+        println!("Fingerprint: {}", fingerprint);
+    }
+    let Some(other) = check else {
+        return;
+    };
+    let result = if other.len() == 16 && other.bytes().all(|b| b.is_ascii_hexdigit()) {
+        fingerprint.check_id(other)
+    } else {
+        let text = std::fs::read_to_string(other).unwrap_or_else(|e| fail(&format!("Error reading {}: {}", other, e)));
+        let value = json::parse(&text).unwrap_or_else(|e| fail(&format!("Invalid JSON in {}: {}", other, e)));
+        // A bare fingerprint, or the `fingerprint` member of a canonical document's body
+        let recorded = value.get("body").and_then(|body| body.get("fingerprint")).unwrap_or(&value);
+        let recorded = Fingerprint::from_json(recorded).unwrap_or_else(|e| fail(&format!("{}: {}", other, e)));
+        fingerprint.check(&recorded)
+    };
+    match result {
+        Ok(()) => println!("Fingerprints match ✓"),
+        Err(e) => fail(&format!("Results are not comparable: {}", e)),
+    }
+}
+
+/// Print the metadata and inspection report of the current model, optionally writing its trees as DOT
+fn inspect_mode(json: bool, dot: Option<&str>) {
+    let model = current_model();
+    if let Some(dir) = dot {
+        write_dot_files(model, dir);
+    }
+    let info = model.model_info();
+    let mut report = model.inspect();
+    // Range checks can only be sized from the input schema for models over the rainfall features
+    let schema = FeatureSchema::rainfall();
+    if model.num_features() == schema.len() {
+        report = report
+            .with_bounds(model, &ValueBounds::analyze_with_schema(model, &schema))
+            .with_output_bounds(OutputBounds::analyze(model, &schema));
+    }
+    if json {
+        let mut fields = vec![
+            ("model".to_string(), info.to_json()),
+            ("objective".to_string(), JsonValue::String(model.objective().name().to_string())),
+            ("content_hash".to_string(), JsonValue::String(to_hex(&model.content_hash()))),
+        ];
+        if let JsonValue::Object(report) = report.to_json() {
+            fields.extend(report);
+        }
+        println!("{}", JsonValue::Object(fields));
+        return;
+    }
+
+    let depth = report.trees.iter().map(|tree| tree.depth).max().unwrap_or(0);
+    let nodes: usize = report.trees.iter().map(|tree| tree.nodes).sum();
+    println!("Model:        {} {}", info.name, info.version);
+    println!("Trees:        {} ({} nodes, max depth {})", info.tree_count, nodes, depth);
+    println!("Features:     {}", info.feature_count);
+    println!("Scale:        {}", info.precision);
+    println!("Objective:    {}", model.objective().name());
+    println!("Combiner:     {:?}", model.combiner());
+    println!("Base score:   {}", model.base_score());
+    println!("Content hash: {}", to_hex(&model.content_hash()));
+    if let Some(hash) = info.training_hash {
+        println!("Training:     {}", to_hex(&hash));
+    }
+    println!();
+    println!("{}", report);
+}
+
+/// Write every tree of `model` to `dir/tree<i>.dot`, creating the directory if needed
+fn write_dot_files(model: &Model, dir: &str) {
+    let dir = std::path::Path::new(dir);
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        fail(&format!("Error creating {}: {}", dir.display(), e));
+    }
+    for (index, dot) in model.to_dot().iter().enumerate() {
+        let path = dir.join(format!("tree{}.dot", index));
+        if let Err(e) = std::fs::write(&path, dot) {
+            fail(&format!("Error writing {}: {}", path.display(), e));
+        }
+    }
+    eprintln!("Wrote {} trees to {}", model.num_trees(), dir.display());
+}
+
+/// Prove the prediction for one feature vector and write the `ModelProof` bundle to `output`
+#[cfg(feature = "plonky2")]
+fn prove_mode(features: &FeatureArgs, output: &str) {
+    use rainfall_prediction::zk::plonky2::proof_features_hash;
+
+    let scaled_features = features.scaled().unwrap_or_else(|e| fail(&format!("Invalid features: {}", e)));
+    let circuit = proving_circuit();
+    eprintln!("Built a circuit of {} rows", circuit.num_rows());
+    let started = Instant::now();
+    let proof = circuit.prove_with_info(&scaled_features).unwrap_or_else(|e| fail(&format!("Proving failed: {}", e)));
+    if let Err(e) = std::fs::write(output, proof.to_bytes()) {
+        fail(&format!("Error writing {}: {}", output, e));
+    }
+    let prediction = current_model().predict(&scaled_features);
+    println!("Prediction: {:.6} mm ({})", from_model_scale(prediction), prediction);
+    if let Some(hash) = proof_features_hash(&proof.proof) {
+        println!("Features hash: {:016x}{:016x}{:016x}{:016x}", hash[0], hash[1], hash[2], hash[3]);
+    }
+    println!("Proved in {:?}, wrote {}", started.elapsed(), output);
+}
+
+/// Compare the current model with the dump it was ported from, exiting with status 1 on any mismatch
+fn verify_port_mode(path: &str, tolerance: f64) {
+    let dump = std::fs::read_to_string(path).unwrap_or_else(|e| fail(&format!("Error reading {}: {}", path, e)));
+    let check = PortCheck::compare_with_tolerance(&dump, current_model(), tolerance)
+        .unwrap_or_else(|e| fail(&format!("Invalid dump {}: {}", path, e)));
+    println!("{}", check);
+    if !check.is_ok() {
+        std::process::exit(1);
+    }
+}
+
+/// Circuit of the current model, from the `--key-cache` store when one is given
+#[cfg(feature = "plonky2")]
+fn proving_circuit() -> rainfall_prediction::zk::plonky2::Plonky2Circuit {
+    use rainfall_prediction::zk::plonky2::Plonky2Circuit;
+
+    let Some(store) = KEY_STORE.get() else {
+        return Plonky2Circuit::new(current_model());
+    };
+    let started = Instant::now();
+    let (circuit, source) =
+        Plonky2Circuit::cached(current_model(), store).unwrap_or_else(|e| fail(&format!("Proving key: {}", e)));
+    eprintln!("Proving key {} in {:?} ({})", source, started.elapsed(), store.dir().display());
+    circuit
+}
+
+/// Write the current model's verifier key to `output`
+#[cfg(feature = "plonky2")]
+fn export_verifier_mode(output: &str) {
+    let circuit = proving_circuit();
+    let key = circuit.verifier().to_bytes();
+    if let Err(e) = std::fs::write(output, &key) {
+        fail(&format!("Error writing {}: {}", output, e));
+    }
+    println!("Wrote a {} byte verifier key for {} to {}", key.len(), circuit.info(), output);
+}
+
+/// Groth16 keys of the current model from the `--key-cache` store
+///
+/// The contract and the proofs must come from the same setup, so both commands need the store.
+#[cfg(feature = "groth16")]
+fn groth16_keys() -> rainfall_prediction::groth16::Groth16Keys {
+    use rainfall_prediction::groth16::Groth16Keys;
+
+    let Some(store) = KEY_STORE.get() else {
+        fail("Groth16 keys are kept with --key-cache DIR, so that proofs match the generated contract");
+    };
+    let started = Instant::now();
+    let (keys, source) =
+        Groth16Keys::cached(current_model(), store).unwrap_or_else(|e| fail(&format!("Groth16 keys: {}", e)));
+    eprintln!("Groth16 keys {} in {:?} ({})", source, started.elapsed(), store.dir().display());
+    keys
+}
+
+/// Write the Solidity verifier of the current model's Groth16 keys to `output` (stdout if None)
+#[cfg(feature = "groth16")]
+fn solidity_verifier_mode(output: Option<&str>) {
+    let source = groth16_keys().contract().solidity();
+    match output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, &source) {
+                fail(&format!("Error writing {}: {}", path, e));
+            }
+            eprintln!("Wrote the verifier of {} to {}", to_hex(&current_model().content_hash()), path);
+        }
+        None => print!("{}", source),
+    }
+}
+
+/// Prove one prediction with Groth16 and print the two arguments of the contract's `verifyProof`
+#[cfg(feature = "groth16")]
+fn prove_groth16_mode(features: &FeatureArgs) {
+    let scaled_features = features.scaled().unwrap_or_else(|e| fail(&format!("Invalid features: {}", e)));
+    let keys = groth16_keys();
+    let started = Instant::now();
+    let proof = keys
+        .prove(current_model(), &scaled_features)
+        .unwrap_or_else(|e| fail(&format!("Proving failed: {}", e)));
+    let prediction = proof.public_inputs.prediction;
+    println!("Prediction: {:.6} mm ({})", from_model_scale(prediction), prediction);
+    println!("proof: 0x{}", to_hex(&proof.calldata()));
+    println!("publicInputs: 0x{}", to_hex(&proof.inputs_calldata()));
+    eprintln!("Proved in {:?}", started.elapsed());
+}
+
+/// Verifier to check proofs with: the key at `path`, or the current model's circuit
+#[cfg(feature = "verifier")]
+fn load_verifier(path: Option<&str>) -> rainfall_prediction::zk::verifier::Plonky2Verifier {
+    use rainfall_prediction::zk::verifier::Plonky2Verifier;
+
+    if let Some(path) = path {
+        let bytes = std::fs::read(path).unwrap_or_else(|e| fail(&format!("Error reading {}: {}", path, e)));
+        return Plonky2Verifier::from_bytes(&bytes).unwrap_or_else(|e| fail(&format!("Invalid key {}: {}", path, e)));
+    }
+    #[cfg(feature = "plonky2")]
+    {
+        use rainfall_prediction::zk::plonky2::Plonky2Circuit;
+
+        let Some(store) = KEY_STORE.get() else {
+            return Plonky2Circuit::new(current_model()).verifier().clone();
+        };
+        let (verifier, source) = Plonky2Circuit::cached_verifier(current_model(), store)
+            .unwrap_or_else(|e| fail(&format!("Verifier key: {}", e)));
+        eprintln!("Verifier key {} ({})", source, store.dir().display());
+        verifier
+    }
+    #[cfg(not(feature = "plonky2"))]
+    fail("--key is required without the plonky2 feature")
+}
+
+/// Verify a `ModelProof` bundle, exiting with status 1 if it is invalid
+#[cfg(feature = "verifier")]
+fn verify_mode(path: &str, prediction: Option<i64>, key: Option<&str>) {
+    use rainfall_prediction::zk::verifier::proof_prediction;
+
+    let bytes = std::fs::read(path).unwrap_or_else(|e| fail(&format!("Error reading {}: {}", path, e)));
+    let verifier = load_verifier(key);
+    let proof = verifier.read_proof(&bytes).unwrap_or_else(|e| fail(&format!("Invalid proof {}: {}", path, e)));
+    let Some(prediction) = prediction.or_else(|| proof_prediction(&proof.proof)) else {
+        fail("The proof does not state a prediction");
+    };
+    match verifier.verify_with_info(&proof, prediction) {
+        Ok(()) => {
+            println!("Valid proof of {:.6} mm ({}) by {} ✓", from_fixed_point(prediction), prediction, proof.info)
+        }
+        Err(e) => fail(&format!("Invalid proof: {}", e)),
+    }
+}
+
+fn csv_mode(input_path: &str, output_path: &str, contributions: bool, scaled: bool) {
+    println!("CSV mode: scoring {} into {}", input_path, output_path);
+
+    let input = match File::open(input_path) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => {
+            eprintln!("Error opening {}: {}", input_path, e);
+            std::process::exit(1);
+        }
+    };
+    let mut output = match File::create(output_path) {
+        Ok(file) => BufWriter::new(file),
+        Err(e) => {
+            eprintln!("Error creating {}: {}", output_path, e);
+            std::process::exit(1);
+        }
+    };
+
+    let primary = registry().get(None, None).expect("the current model is registered");
+    let shadow = shadow_model(&primary);
+    let scored = score_csv(input, &mut output, &primary, contributions, scaled, shadow.as_deref());
+    match scored.and_then(|summary| output.flush().map(|_| summary)) {
+        Ok(summary) => {
+            for (line, reason) in &summary.skipped {
+                eprintln!("  Skipped line {}: {}", line, reason);
+            }
+            println!("  Scored: {}", summary.scored);
+            println!("  Skipped (malformed): {}", summary.skipped.len());
+        }
+        Err(e) => {
+            eprintln!("Error scoring {}: {}", input_path, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Outcome of a CSV scoring run
+#[derive(Debug, Default)]
+struct CsvSummary {
+    /// Rows written to the output
+    scored: usize,
+    /// (line number, reason) of every row that was not scored
+    skipped: Vec<(usize, String)>,
+}
+
+/// Stream rows of features (raw, or fixed point with `scaled`) from `input` and write one prediction per row
+///
+/// Rows have the columns and scale of the `primary` model's inputs (see `check_features`). Output
+/// columns are the input line number, the prediction in mm, its scaled value and the build
+/// fingerprint id (see `Fingerprint`), and with `contributions` the leaf value of every tree.
+/// With a `shadow` model, a `shadow_scaled` column holds its prediction (empty if it cannot score
+/// the row) and the difference is added to the shadow divergence. Malformed or out-of-range rows
+/// are skipped and reported in the summary; a leading header line is ignored.
+fn score_csv<R: BufRead, W: Write>(
+    input: R,
+    output: &mut W,
+    primary: &RegisteredModel,
+    contributions: bool,
+    scaled: bool,
+    shadow: Option<&RegisteredModel>,
+) -> io::Result<CsvSummary> {
+    let model = &primary.model;
+    let (columns, schema, scale) = (input_len(model), input_schema(model), model.scale());
+    let mut summary = CsvSummary::default();
+    let fingerprint = Fingerprint::new(model, EvaluatorMode::Branching).id();
+
+    write!(output, "line,prediction,prediction_scaled,fingerprint")?;
+    if shadow.is_some() {
+        write!(output, ",shadow_scaled")?;
+    }
+    if contributions {
+        for tree in 0..model.num_trees() {
+            write!(output, ",tree_{}", tree)?;
+        }
+    }
+    writeln!(output)?;
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        let line_number = index + 1;
+        if line.trim().is_empty() || (index == 0 && is_header(&line)) {
+            continue;
+        }
+
+        let row = if scaled { parse_scaled_row_at(&line, scale) } else { parse_decimal_row_at(&line, scale) };
+        let scaled_features = match row {
+            Ok(row) if row.len() == columns => row,
+            Ok(row) => {
+                summary.skipped.push((line_number, format!("expected {} columns, got {}", columns, row.len())));
+                continue;
+            }
+            Err(reason) => {
+                summary.skipped.push((line_number, reason));
+                continue;
+            }
+        };
+        if let Some(Err(e)) = schema.as_ref().map(|schema| schema.validate(&scaled_features)) {
+            summary.skipped.push((line_number, e.to_string()));
+            continue;
+        }
+
+        let tree_values: Vec<i64> = if contributions {
+            model.trees().iter().map(|tree| tree.eval(&scaled_features)).collect()
+        } else {
+            Vec::new()
+        };
+        // A row the shadow cannot score leaves its column empty rather than stopping the batch
+        let shadow_prediction = shadow.map(|shadow| shadow.model.try_eval(&scaled_features).ok());
+        let prediction = predict_routed(primary, &scaled_features);
+        write!(output, "{},{:.10},{},{}", line_number, from_fixed_point_at(prediction, scale), prediction, fingerprint)?;
+        match shadow_prediction {
+            Some(Some(shadow_prediction)) => {
+                SHADOW_DIVERGENCE.lock().unwrap().add(prediction, shadow_prediction);
+                write!(output, ",{}", shadow_prediction)?;
+            }
+            Some(None) => write!(output, ",")?,
+            None => {}
+        }
+        for value in tree_values {
+            write!(output, ",{:.10}", from_fixed_point_at(value, scale))?;
+        }
+        writeln!(output)?;
+        summary.scored += 1;
+    }
+
+    Ok(summary)
+}
+
+/// Write `count` synthetic rows to `output` (stdout if none)
+fn generate_mode(count: usize, seed: u64, config: Option<&str>, output: Option<&str>, scaled: bool) {
+    let mut generator = SyntheticGenerator::rainfall(seed);
+    if let Some(path) = config {
+        let text = std::fs::read_to_string(path).unwrap_or_else(|e| fail(&format!("Error reading {}: {}", path, e)));
+        generator = generator.with_json(&text).unwrap_or_else(|e| fail(&format!("Error in {}: {}", path, e)));
+    }
+    let result = match output {
+        Some(path) => {
+            let file = File::create(path).unwrap_or_else(|e| fail(&format!("Error creating {}: {}", path, e)));
+            write_synthetic_csv(&mut generator, count, scaled, &mut BufWriter::new(file))
+        }
+        None => write_synthetic_csv(&mut generator, count, scaled, &mut io::stdout().lock()),
+    };
+    match result {
+        Ok(()) => eprintln!("Generated {} rows with seed {}", count, seed),
+        Err(e) => fail(&format!("Error writing rows: {}", e)),
+    }
+}
+
+/// Write a header of feature names and `count` rows, raw or scaled to the builtin precision
+fn write_synthetic_csv<W: Write>(
+    generator: &mut SyntheticGenerator,
+    count: usize,
+    scaled: bool,
+    output: &mut W,
+) -> io::Result<()> {
+    writeln!(output, "{}", generator.names().join(","))?;
+    for _ in 0..count {
+        let line = if scaled {
+            let row = generator.next_scaled(PRECISION_MULTIPLIER);
+            row.iter().map(i64::to_string).collect::<Vec<_>>().join(",")
+        } else {
+            generator.next_row().iter().map(f64::to_string).collect::<Vec<_>>().join(",")
+        };
+        writeln!(output, "{}", line)?;
+    }
+    output.flush()
+}
+
+/// Print the error metrics of the current model on a labelled CSV file
+fn evaluate_mode(path: &str, scaled: bool, json: bool) {
+    let primary = registry().get(None, None).expect("the current model is registered");
+    // Parquet rows go through `evaluate_csv` as the lines of a CSV export, so line n is row n
+    let (evaluation, skipped) = if path.ends_with(".parquet") {
+        evaluate_csv(io::Cursor::new(parquet_lines(path).join("\n")), &primary, scaled)
+    } else {
+        let input = File::open(path).unwrap_or_else(|e| fail(&format!("Error opening {}: {}", path, e)));
+        evaluate_csv(BufReader::new(input), &primary, scaled)
+    }
+    .unwrap_or_else(|e| fail(&format!("Error reading {}: {}", path, e)));
+    for (line, reason) in &skipped {
+        eprintln!("  Skipped line {}: {}", line, reason);
+    }
+    if json {
+        println!("{}", evaluation.to_json());
+    } else {
+        println!("{}", evaluation);
+        if !skipped.is_empty() {
+            println!("Skipped (malformed): {}", skipped.len());
+        }
+    }
+}
+
+/// Rows of a Parquet file as CSV lines (see `read_parquet_rows`)
+#[cfg(feature = "parquet")]
+fn parquet_lines(path: &str) -> Vec<String> {
+    rainfall_prediction::read_parquet_rows(std::path::Path::new(path))
+        .unwrap_or_else(|e| fail(&format!("Error reading {}: {}", path, e)))
+}
+
+#[cfg(not(feature = "parquet"))]
+fn parquet_lines(_path: &str) -> Vec<String> {
+    fail("Parquet input needs the `parquet` feature; export the data to CSV instead")
+}
+
+/// Predict every row of features followed by a label and an optional float reference prediction
+///
+/// Features have the columns and scale of the `primary` model's inputs, as in `score_csv`. Rows
+/// with the wrong column count, unparsable values or features outside the schema are skipped and
+/// returned with their line numbers; a leading header line is ignored.
+fn evaluate_csv<R: BufRead>(
+    input: R,
+    primary: &RegisteredModel,
+    scaled: bool,
+) -> io::Result<(Evaluation, Vec<(usize, String)>)> {
+    let model = &primary.model;
+    let (columns, schema, scale) = (input_len(model), input_schema(model), model.scale());
+    let mut evaluation = Evaluation::new(model.scale());
+    let mut skipped = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() || (index == 0 && is_header(&line)) {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').collect();
+        if fields.len() != columns + 1 && fields.len() != columns + 2 {
+            let (label, reference) = (columns + 1, columns + 2);
+            skipped.push((index + 1, format!("expected {} or {} columns, got {}", label, reference, fields.len())));
+            continue;
+        }
+        let features = fields[..columns].join(",");
+        let row = if scaled { parse_scaled_row_at(&features, scale) } else { parse_decimal_row_at(&features, scale) };
+        // The label is required; an empty reference column means the row has no float prediction
+        let number = |column: usize| match fields.get(column).map(|field| field.trim()) {
+            None | Some("") => Ok(None),
+            Some(field) => match field.parse::<f64>() {
+                Ok(value) if value.is_finite() => Ok(Some(value)),
+                _ => Err(format!("column {}: '{}' is not a finite number", column + 1, field)),
+            },
+        };
+        let targets = number(columns).and_then(|label| {
+            let label = label.ok_or_else(|| format!("column {}: missing label", columns + 1))?;
+            Ok((label, number(columns + 1)?))
+        });
+        let (scaled_features, (label, reference)) = match (row, targets) {
+            (Ok(row), Ok(targets)) => (row, targets),
+            (Err(reason), _) | (_, Err(reason)) => {
+                skipped.push((index + 1, reason));
+                continue;
+            }
+        };
+        if let Some(Err(e)) = schema.as_ref().map(|schema| schema.validate(&scaled_features)) {
+            skipped.push((index + 1, e.to_string()));
+            continue;
+        }
+        evaluation.add(predict_routed(primary, &scaled_features), label, reference);
+    }
+    Ok((evaluation, skipped))
+}
+
+/// Answer one JSON request per input line with one JSON response line
+///
+/// A request is either an array of 116 raw features or an object `{"id": ..., "features": [...]}`, with
+/// `"features_scaled"` instead of `"features"` for values already scaled by 10^10 (see `RequestFeatures`).
+/// The response is `{"id": ..., "prediction": mm, "prediction_scaled": n}`, or `{"id": ..., "error": "..."}`
+/// for a request that cannot be scored; the `id` is echoed back unchanged when given.
+fn serve_lines<R: BufRead, W: Write>(input: R, output: &mut W) -> io::Result<usize> {
+    let mut served = 0;
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        writeln!(output, "{}", handle_request(&line))?;
+        output.flush()?;
+        served += 1;
+    }
+    Ok(served)
+}
+
+/// Answer a single JSONL request: a prediction, or a registry operation (`"op"`) with `serve --admin`
+fn handle_request(line: &str) -> JsonValue {
+    let started = Instant::now();
+    let request = json::parse(line);
+    let id = request.as_ref().ok().and_then(|r| r.get("id")).cloned();
+    let mut response = Vec::new();
+    if let Some(id) = id {
+        response.push(("id".to_string(), id));
+    }
+
+    if let Ok(request) = &request {
+        if let Some(op) = request.get("op").and_then(JsonValue::as_str).filter(|&op| op != "predict") {
+            match admin_request(op, request) {
+                Ok(fields) => response.extend(fields),
+                Err(message) => response.push(("error".to_string(), JsonValue::String(message))),
+            }
+            return JsonValue::Object(response);
+        }
+    }
+
+    let routed = request.as_ref().map_err(|e| e.to_string()).and_then(|request| {
+        let entry = route_request(request)?;
+        let scaled_features = request_features(request, &entry.model)?;
+        Ok((entry, scaled_features))
+    });
+    match routed {
+        Ok((entry, scaled_features)) => {
+            #[cfg(feature = "plonky2")]
+            let proof = request.as_ref().ok().and_then(|r| r.get("prove")).and_then(JsonValue::as_bool) == Some(true);
+            #[cfg(feature = "plonky2")]
+            let proof = proof.then(|| prove_request(&entry, &scaled_features));
+            let prediction = predict_routed(&entry, &scaled_features);
+            shadow_score(&entry, &scaled_features, prediction);
+            response.push(("model".to_string(), JsonValue::String(entry.id.clone())));
+            response.push(("version".to_string(), JsonValue::String(entry.version.clone())));
+            response.push(("prediction".to_string(), JsonValue::Number(format!("{:.10}", from_fixed_point_at(prediction, entry.model.scale())))));
+            response.push(("prediction_scaled".to_string(), JsonValue::Number(prediction.to_string())));
+            #[cfg(feature = "plonky2")]
+            match proof {
+                Some(Ok(proof)) => response.push(("proof".to_string(), JsonValue::String(proof))),
+                Some(Err(message)) => response.push(("proof_error".to_string(), JsonValue::String(message))),
+                None => {}
+            }
+            METRICS.record_prediction(started.elapsed());
+        }
+        Err(message) => {
+            METRICS.record_validation_failure();
+            response.push(("error".to_string(), JsonValue::String(message)));
+        }
+    }
+    JsonValue::Object(response)
+}
+
+/// Registry entry a request names with its optional `"model"` and `"version"` strings
+fn route_request(request: &JsonValue) -> Result<Arc<RegisteredModel>, String> {
+    let field = |key: &str| match request.get(key) {
+        None => Ok(None),
+        Some(value) => value.as_str().map(Some).ok_or(format!("\"{}\" must be a string", key)),
+    };
+    registry().get(field("model")?, field("version")?).map_err(|e| e.to_string())
+}
+
+/// Load, activate or list models (`"op"` of `load`, `activate`, `default` or `models`), or report
+/// the shadow model's divergence (`shadow`)
+///
+/// `load`, `activate` and `default` change what later requests are routed to, so they need `serve --admin`.
+fn admin_request(op: &str, request: &JsonValue) -> Result<Vec<(String, JsonValue)>, String> {
+    let string = |key: &str| {
+        request.get(key).and_then(JsonValue::as_str).ok_or(format!("\"{}\" must be a string", key))
+    };
+    if op == "shadow" {
+        let divergence = match SHADOW.get() {
+            Some(_) => SHADOW_DIVERGENCE.lock().unwrap().to_json(),
+            None => JsonValue::Null,
+        };
+        return Ok(vec![("shadow".to_string(), divergence)]);
+    }
+    if op != "models" && !ADMIN.load(Ordering::Relaxed) {
+        return Err(format!("\"{}\" needs a server started with --admin", op));
+    }
+    let registry = registry();
+    match op {
+        "load" => {
+            let (id, version, path) = (string("model")?, string("version")?, string("path")?);
+            let model = load_model_file(path)?;
+            eprintln!("Registered {}@{} from {} (sha256 {})", id, version, path, to_hex(&model.content_hash()));
+            registry.register(id, version, model);
+        }
+        "activate" => registry.activate(string("model")?, string("version")?).map_err(|e| e.to_string())?,
+        "default" => registry.set_default(string("model")?).map_err(|e| e.to_string())?,
+        "models" => {}
+        _ => return Err(format!("unknown op \"{}\"", op)),
+    }
+    let models = registry
+        .models()
+        .into_iter()
+        .map(|listing| {
+            JsonValue::Object(vec![
+                ("model".to_string(), JsonValue::String(listing.entry.id.clone())),
+                ("version".to_string(), JsonValue::String(listing.entry.version.clone())),
+                ("content_hash".to_string(), JsonValue::String(to_hex(&listing.entry.content_hash))),
+                ("active".to_string(), JsonValue::Bool(listing.active)),
+                ("default".to_string(), JsonValue::Bool(listing.default)),
+            ])
+        })
+        .collect();
+    Ok(vec![("models".to_string(), JsonValue::Array(models))])
+}
+
+/// Prove the prediction of a request with plonky2, returning the hex `ModelProof` bundle
+#[cfg(feature = "plonky2")]
+fn prove_request(entry: &RegisteredModel, scaled_features: &[i64]) -> Result<String, String> {
+    let started = Instant::now();
+    let proof = entry.prove(scaled_features);
+    METRICS.record_proof(started.elapsed(), proof.is_ok());
+    proof.map(|proof| to_hex(&proof.to_bytes())).map_err(|e| e.to_string())
+}
+
+/// Scaled, validated feature vector of a request for `model`
+///
+/// Requests carry the 116 rainfall features, checked against their schema, unless the model
+/// reads more. A model loaded from a dump only knows the highest feature it splits on, so one
+/// reading fewer still takes the full vector.
+fn request_features(request: &JsonValue, model: &Model) -> Result<Vec<i64>, String> {
+    let scaled_features = RequestFeatures::parse(request)?.to_scaled(model.scale())?;
+    check_features(&scaled_features, model)?;
+    Ok(scaled_features)
+}
+
+/// Check the count of a scaled feature vector for `model`, and its schema (see `request_features`)
+fn check_features(scaled_features: &[i64], model: &Model) -> Result<(), String> {
+    let expected = input_len(model);
+    if scaled_features.len() != expected {
+        return Err(format!("expected {} features, got {}", expected, scaled_features.len()));
+    }
+    if let Some(schema) = input_schema(model) {
+        schema.validate(scaled_features).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Length of the feature vectors `model` is given: the 116 rainfall features, or more if it reads more
+fn input_len(model: &Model) -> usize {
+    model.num_features().max(NUM_FEATURES)
+}
+
+/// Schema of the inputs of `model`: the rainfall one, unless it reads more features or another scale
+fn input_schema(model: &Model) -> Option<FeatureSchema> {
+    (input_len(model) == NUM_FEATURES && model.scale() == PRECISION_MULTIPLIER).then(FeatureSchema::rainfall)
+}
+
+/// Scoring path of `serve --grpc` and `serve --rest`, the same as the JSONL server's
+///
+/// Requests go to the registry model they name, or its default, and are validated, cached,
+/// recorded, shadow scored and counted in the metrics as JSONL requests are.
+#[cfg(any(feature = "grpc", feature = "rest"))]
+struct ServedPredictor;
+
+#[cfg(any(feature = "grpc", feature = "rest"))]
+impl rainfall_prediction::Predictor for ServedPredictor {
+    fn entry(&self, id: Option<&str>, version: Option<&str>) -> Result<Arc<RegisteredModel>, rainfall_prediction::RegistryError> {
+        registry().get(id, version)
+    }
+
+    fn validate(&self, entry: &RegisteredModel, features: &[i64]) -> Result<(), String> {
+        check_features(features, &entry.model)
+    }
+
+    fn predict(&self, entry: &RegisteredModel, features: &[i64]) -> Result<i64, String> {
+        let prediction = predict_routed(entry, features);
+        shadow_score(entry, features, prediction);
+        Ok(prediction)
+    }
+
+    fn served(&self, latency: std::time::Duration) {
+        METRICS.record_prediction(latency);
+    }
+
+    fn rejected(&self) {
+        METRICS.record_validation_failure();
+    }
+
+    fn proved(&self, duration: std::time::Duration, succeeded: bool) {
+        METRICS.record_proof(duration, succeeded);
+    }
+}
+
+/// Serve `GET /metrics` (Prometheus text format) on `address` from a background thread
+#[cfg(feature = "server")]
+fn metrics_mode(address: &str) {
+    let listener = match std::net::TcpListener::bind(address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Error binding {}: {}", address, e);
+            std::process::exit(1);
+        }
+    };
+    eprintln!("Serving metrics on http://{}/metrics", address);
+
+    // One thread per connection, with a read timeout, so a client that connects and sends nothing
+    // (a TCP health check) neither blocks other scrapes nor holds its thread forever
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || {
+                if stream.set_read_timeout(Some(METRICS_READ_TIMEOUT)).is_err() {
+                    return;
+                }
+                let mut request_line = String::new();
+                let mut reader = BufReader::new(&stream);
+                if reader.read_line(&mut request_line).is_err() {
+                    return;
+                }
+                // Drain the headers so the client sees a complete exchange
+                let mut header = String::new();
+                while reader.read_line(&mut header).is_ok_and(|n| n > 2) {
+                    header.clear();
+                }
+                let _ = (&stream).write_all(metrics_response(&request_line).as_bytes());
+            });
+        }
+    });
+}
+
+/// How long a `/metrics` connection may take to send its request
+#[cfg(feature = "server")]
+const METRICS_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Serve the gRPC `Inference` service on `address` until the process is killed
+#[cfg(feature = "grpc")]
+fn grpc_mode(address: &str) {
+    let address: std::net::SocketAddr = match address.parse() {
+        Ok(address) => address,
+        Err(e) => {
+            eprintln!("Invalid gRPC address {}: {}", address, e);
+            std::process::exit(1);
+        }
+    };
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Error starting the async runtime: {}", e);
+            std::process::exit(1);
+        }
+    };
+    eprintln!("Serving gRPC (rainfall.inference.Inference) on {}", address);
+    if let Err(e) = runtime.block_on(rainfall_prediction::grpc::serve_with(Arc::new(ServedPredictor), address)) {
+        eprintln!("Error serving gRPC on {}: {}", address, e);
+        std::process::exit(1);
+    }
+}
+
+/// Serve the REST API and its OpenAPI document on `address` until the process is killed
+#[cfg(feature = "rest")]
+fn rest_mode(address: &str) {
+    let address: std::net::SocketAddr = match address.parse() {
+        Ok(address) => address,
+        Err(e) => {
+            eprintln!("Invalid REST address {}: {}", address, e);
+            std::process::exit(1);
+        }
+    };
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Error starting the async runtime: {}", e);
+            std::process::exit(1);
+        }
+    };
+    eprintln!("Serving the REST API on http://{} (spec at /openapi.json)", address);
+    if let Err(e) = runtime.block_on(rainfall_prediction::rest::serve_with(Arc::new(ServedPredictor), address)) {
+        eprintln!("Error serving REST on {}: {}", address, e);
+        std::process::exit(1);
+    }
+}
+
+/// HTTP response to one request line: the metrics for `GET /metrics`, 404 otherwise
+#[cfg(feature = "server")]
+fn metrics_response(request_line: &str) -> String {
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let cache = CACHE.get().map(|cache| cache.lock().unwrap().stats());
+            ("200 OK", "text/plain; version=0.0.4", METRICS.render(cache.as_ref()))
+        }
+        _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+    };
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+/// Serve JSONL over TCP, one thread per connection
+#[cfg(feature = "server")]
+fn listen_mode(address: &str) {
+    let listener = match std::net::TcpListener::bind(address) {
+        Ok(listener) => listener,
         Err(e) => {
-            eprintln!("Error parsing features: {}", e);
+            eprintln!("Error binding {}: {}", address, e);
             std::process::exit(1);
         }
+    };
+    eprintln!("Serving JSONL predictions on {}", address);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Connection failed: {}", e);
+                continue;
+            }
+        };
+        std::thread::spawn(move || {
+            let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+            let result = stream
+                .try_clone()
+                .and_then(|writer| serve_lines(BufReader::new(stream), &mut BufWriter::new(writer)));
+            match result {
+                Ok(served) => eprintln!("{}: served {} requests", peer, served),
+                Err(e) => eprintln!("{}: {}", peer, e),
+            }
+            report_cache();
+            report_shadow();
+        });
+    }
+}
+
+/// Parse an `<index>:<value>` command line argument
+fn parse_sparse_pair(arg: &str) -> Option<(usize, f64)> {
+    let (index, value) = arg.split_once(':')?;
+    Some((index.trim().parse().ok()?, value.trim().parse().ok()?))
+}
+
+fn replay_mode(path: &str) {
+    println!("Replaying {}", path);
+
+    let records = match read_replay_log(path) {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("Error reading replay log: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let report = replay_records(&records, registry());
+
+    println!("  Records: {}", records.len());
+    println!("  Replayed: {}", report.replayed);
+    println!("  Skipped (model not registered): {}", report.skipped);
+    if report.model_changed > 0 {
+        println!("  Skipped (registered model has other content, retrained since): {}", report.model_changed);
+    }
+    for diff in &report.diffs {
+        let scale = records[diff.index].scale;
+        println!(
+            "  Record {}: recorded {} ({:.6} mm), now {} ({:.6} mm)",
+            diff.index,
+            diff.recorded,
+            from_fixed_point_at(diff.recorded, scale),
+            diff.replayed,
+            from_fixed_point_at(diff.replayed, scale)
+        );
+    }
+
+    if report.diffs.is_empty() {
+        println!("All replayed predictions match ✓");
+    } else {
+        println!("{} predictions differ", report.diffs.len());
+        std::process::exit(1);
     }
 }
 
@@ -267,7 +1713,7 @@ fn run_tests() {
     println!("  Scaled features: {:?}", scaled_features);
     
     for (i, (&original, &scaled)) in float_features.iter().zip(scaled_features.iter()).enumerate() {
-        let expected = to_fixed_point(original);
+        let expected = to_fixed_point_at(original, current_model().scale());
         assert_eq!(scaled, expected, "Feature {} scaling mismatch", i);
     }
     println!("  ✓ Feature preparation tests passed");
@@ -275,7 +1721,7 @@ fn run_tests() {
 
     // Test 3: Prediction consistency
     println!("Test 3: Prediction consistency");
-    let test_features = vec![0.0; 116];
+    let test_features = vec![0.0; NUM_FEATURES];
     
     let prediction1 = make_prediction(&test_features);
     let prediction2 = make_prediction(&test_features);
@@ -317,29 +1763,186 @@ fn run_demo() {
 fn make_prediction(features: &[f64]) -> f64 {
     // Ensure we have enough features
     let mut full_features = features.to_vec();
-    full_features.resize(116, 0.0);
+    full_features.resize(input_len(current_model()), 0.0);
     
     // Scale features to fixed-point
     let scaled_features = prepare_features(&full_features);
     
     // Make prediction (this would call the generated function in real usage)
-    let prediction_scaled = xgboost_predict(&scaled_features);
+    let prediction_scaled = predict_and_record(scaled_features);
     
     // Convert back to float
-    from_fixed_point(prediction_scaled)
+    from_model_scale(prediction_scaled)
 }
 
-fn print_usage(program_name: &str) {
-    println!("Usage:");
-    println!("  {}                          # Interactive mode", program_name);
-    println!("  {} --test                   # Run built-in tests", program_name);
-    println!("  {} --demo                   # Run demonstration", program_name);
-    println!("  {} <f1> <f2> ... <f116>     # Command line with 116 features", program_name);
-    println!();
-    println!("Examples:");
-    println!("  {} --demo", program_name);
-    println!("  {} --test", program_name);
-    println!("  {} $(python -c \"print(' '.join(['0.1'] * 116))\")", program_name);
+/// Convert a prediction of the current model back to floating-point
+fn from_model_scale(prediction: i64) -> f64 {
+    from_fixed_point_at(prediction, current_model().scale())
+}
+
+/// Identifier of the model predictions are made with
+fn model_id() -> &'static str {
+    match RUNTIME_MODEL.get() {
+        Some((id, _)) => id,
+        None => MODEL_ID,
+    }
+}
+
+/// Model predictions are made with: the runtime model if one was loaded, else the compiled one
+fn current_model() -> &'static Model {
+    static BUILTIN: OnceLock<Model> = OnceLock::new();
+    match RUNTIME_MODEL.get() {
+        Some((_, model)) => model,
+        None => BUILTIN.get_or_init(builtin_model),
+    }
+}
+
+/// Version of the current model, as it is registered: its own, or "1" if it has none
+fn current_version() -> &'static str {
+    let model = current_model();
+    if model.version().is_empty() {
+        "1"
+    } else {
+        model.version()
+    }
+}
+
+/// `content_hash` of the current model, computed once
+fn current_commitment() -> &'static [u8; 32] {
+    static COMMITMENT: OnceLock<[u8; 32]> = OnceLock::new();
+    COMMITMENT.get_or_init(|| current_model().content_hash())
+}
+
+/// Predict on scaled features with the runtime model if one was loaded
+fn predict_scaled(scaled_features: &[i64]) -> i64 {
+    match RUNTIME_MODEL.get() {
+        Some((_, model)) => model.predict(scaled_features),
+        None => xgboost_predict(scaled_features),
+    }
+}
+
+/// Models the server modes route requests to, starting with the current model as the default
+fn registry() -> &'static ModelRegistry {
+    REGISTRY.get_or_init(|| {
+        let registry = ModelRegistry::new();
+        registry.register(model_id(), current_version(), current_model().clone());
+        registry
+    })
+}
+
+/// Look a prediction up in the cache, if enabled, running `predict` and storing its result on a miss
+///
+/// The lock is not held while the model runs, so concurrent connections only serialize on lookups.
+fn cached(key: impl FnOnce() -> PoseidonDigest, predict: impl FnOnce() -> i64) -> i64 {
+    let Some(cache) = CACHE.get() else {
+        return predict();
+    };
+    let key = key();
+    if let Some(prediction) = cache.lock().unwrap().get(&key) {
+        return prediction;
+    }
+    let prediction = predict();
+    cache.lock().unwrap().insert(key, prediction);
+    prediction
+}
+
+/// Predict on scaled features through the cache, if enabled
+fn predict_cached(scaled_features: &[i64]) -> i64 {
+    cached(|| hash_features(scaled_features), || predict_scaled(scaled_features))
+}
+
+/// Predict with a registry entry through the cache, appending the request to the replay log if recording
+///
+/// Cache keys of routed predictions include the model's content hash, so a swapped-in model never
+/// answers from its predecessor's entries.
+fn predict_routed(entry: &RegisteredModel, scaled_features: &[i64]) -> i64 {
+    let key = || {
+        let mut keyed = scaled_features.to_vec();
+        keyed.extend(entry.content_hash.chunks(8).map(|word| i64::from_le_bytes(word.try_into().unwrap())));
+        hash_features(&keyed)
+    };
+    let prediction = cached(key, || entry.model.predict(scaled_features));
+    let scale = entry.model.scale();
+    record_prediction(&entry.id, &entry.version, &entry.content_hash, scale, scaled_features.to_vec(), prediction);
+    prediction
+}
+
+/// Print the cache counters (hit rate) to stderr, if caching
+fn report_cache() {
+    if let Some(cache) = CACHE.get() {
+        eprintln!("Prediction cache: {}", cache.lock().unwrap().stats());
+    }
+}
+
+/// Shadow model for inputs served by `primary`: the `--shadow` model, unless that is `primary` itself
+/// or cannot read its inputs (see `check_shadow`)
+///
+/// The shadow is looked up per input, so it follows hot-swaps of its active version.
+fn shadow_model(primary: &RegisteredModel) -> Option<Arc<RegisteredModel>> {
+    let (id, version) = SHADOW.get()?;
+    let shadow = registry().get(Some(id), version.as_deref()).ok()?;
+    let distinct = shadow.id != primary.id || shadow.version != primary.version;
+    (distinct && check_shadow(&primary.model, &shadow.model).is_ok()).then_some(shadow)
+}
+
+/// Check that `shadow` can score the inputs of `primary`: it reads no more features, at the same scale
+fn check_shadow(primary: &Model, shadow: &Model) -> Result<(), String> {
+    if shadow.num_features() > input_len(primary) {
+        return Err(format!("it reads {} features, the served model's inputs have {}", shadow.num_features(), input_len(primary)));
+    }
+    if shadow.scale() != primary.scale() {
+        return Err(format!("its scale is {}, the served model's is {}", shadow.scale(), primary.scale()));
+    }
+    Ok(())
+}
+
+/// Score `features` with the shadow model, if any, adding the difference to the divergence statistics
+///
+/// Inputs the shadow cannot score, such as ones that overflow it, are left out of the statistics.
+fn shadow_score(primary: &RegisteredModel, features: &[i64], prediction: i64) -> Option<i64> {
+    let shadow = shadow_model(primary)?;
+    let shadow_prediction = shadow.model.try_eval(features).ok()?;
+    SHADOW_DIVERGENCE.lock().unwrap().add(prediction, shadow_prediction);
+    Some(shadow_prediction)
+}
+
+/// Print the divergence of the shadow model to stderr, if shadow scoring
+fn report_shadow() {
+    if let Some((id, version)) = SHADOW.get() {
+        let model = format!("{}@{}", id, version.as_deref().unwrap_or("active"));
+        eprintln!("Shadow {} vs served: {}", model, SHADOW_DIVERGENCE.lock().unwrap());
+    }
+}
+
+/// Predict on scaled features, appending the request to the replay log if recording
+fn predict_and_record(scaled_features: Vec<i64>) -> i64 {
+    let prediction_scaled = predict_cached(&scaled_features);
+    let scale = current_model().scale();
+    record_prediction(model_id(), current_version(), current_commitment(), scale, scaled_features, prediction_scaled);
+    prediction_scaled
+}
+
+/// Append a prediction of `model` to the replay log, if recording
+///
+/// # Arguments
+/// * `model`, `version` - Registry id and version of the model that predicted
+/// * `commitment` - Its `content_hash`, which tells a retrained model of the same name apart
+/// * `scale` - Its fixed-point scale, which the features and output are at
+/// * `features`, `output` - Scaled inputs and prediction
+fn record_prediction(model: &str, version: &str, commitment: &[u8; 32], scale: i64, features: Vec<i64>, output: i64) {
+    if let Some(recorder) = RECORDER.get() {
+        let record = ReplayRecord {
+            model: model.to_string(),
+            version: version.to_string(),
+            commitment: Some(*commitment),
+            scale,
+            features,
+            output,
+        };
+        if let Err(e) = recorder.lock().unwrap().append(&record) {
+            eprintln!("Warning: failed to record prediction: {}", e);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -372,9 +1975,359 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_sparse_pair() {
+        assert_eq!(parse_sparse_pair("34:13.5"), Some((34, 13.5)));
+        assert_eq!(parse_sparse_pair("34"), None);
+        assert_eq!(parse_sparse_pair("x:1.0"), None);
+    }
+
+    #[test]
+    fn test_cli_parsing() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+
+        let args = ["predict", "predict", "--sparse", "34:13.5", "1:0.5", "--model", "m.json"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.model.as_deref(), Some("m.json"));
+        let Some(Command::Predict(features)) = cli.command else { panic!("expected predict") };
+        assert_eq!(features.sparse, vec![(34, 13.5), (1, 0.5)]);
+        assert_eq!(features.scaled().unwrap().len(), NUM_FEATURES);
+
+        let dense = Cli::try_parse_from(["predict", "predict", "-1.5", "2"]).unwrap();
+        let Some(Command::Predict(features)) = dense.command else { panic!("expected predict") };
+        assert_eq!(features.features, vec!["-1.5", "2"]);
+        assert_eq!(features.scaled().unwrap_err(), "expected 116 features, got 2");
+
+        assert!(Cli::try_parse_from(["predict", "predict"]).is_err());
+        assert!(Cli::try_parse_from(["predict", "predict", "1", "--sparse", "1:1"]).is_err());
+        assert!(Cli::try_parse_from(["predict", "batch", "in.csv"]).is_err());
+        assert!(Cli::try_parse_from(["predict"]).unwrap().command.is_none());
+        let gen = Cli::try_parse_from(["predict", "gen-circuit", "--target", "rust"]).unwrap();
+        assert!(matches!(gen.command, Some(Command::GenCircuit { target: CircuitTarget::Rust, .. })));
+        let inspect = Cli::try_parse_from(["predict", "inspect", "--json"]).unwrap();
+        assert!(matches!(inspect.command, Some(Command::Inspect { json: true, dot: None })));
+        let fingerprint = Cli::try_parse_from(["predict", "fingerprint", "--check", "bee1766df169ff95"]).unwrap();
+        assert!(matches!(fingerprint.command, Some(Command::Fingerprint { json: false, check: Some(_) })));
+        let port = Cli::try_parse_from(["predict", "verify-port", "bst.json"]).unwrap();
+        assert!(matches!(port.command, Some(Command::VerifyPort { tolerance, .. }) if tolerance == PORT_TOLERANCE));
+        assert!(Cli::try_parse_from(["predict", "predict", "--features", "x.json", "--scaled"]).is_ok());
+        assert!(Cli::try_parse_from(["predict", "predict", "--scaled", "--sparse", "1:1"]).is_err());
+        #[cfg(feature = "groth16")]
+        {
+            let solidity = Cli::try_parse_from(["predict", "--key-cache", "k", "solidity-verifier", "-o", "V.sol"]);
+            assert!(matches!(solidity.unwrap().command, Some(Command::SolidityVerifier { output: Some(_) })));
+            let prove = Cli::try_parse_from(["predict", "prove-groth16", "--features", "x.json"]).unwrap();
+            assert!(matches!(prove.command, Some(Command::ProveGroth16(FeatureArgs { file: Some(_), .. }))));
+        }
+
+        // Sparse values get the same checks as dense ones
+        let sparse = |value: &str| {
+            let cli = Cli::try_parse_from(["predict", "predict", "--sparse", &format!("34:{}", value)]).unwrap();
+            let Some(Command::Predict(features)) = cli.command else { panic!("expected predict") };
+            features.scaled()
+        };
+        assert_eq!(sparse("nan").unwrap_err(), "feature 34 is not a finite number");
+        assert_eq!(sparse("-inf").unwrap_err(), "feature 34 is not a finite number");
+        let dense = |value: &str| {
+            let mut args = vec!["predict".to_string(), "predict".to_string()];
+            args.extend((0..NUM_FEATURES).map(|i| if i == 34 { value.to_string() } else { "0".to_string() }));
+            let Some(Command::Predict(features)) = Cli::try_parse_from(&args).unwrap().command else { panic!() };
+            features.scaled()
+        };
+        // Dense values are scaled exactly, so a huge one is out of the i64 range before the schema sees it
+        assert_eq!(dense("1e300").unwrap_err(), "feature 34 (1e300) is out of range");
+        assert!(sparse("1e300").unwrap_err().starts_with("features out of range: #34 ="));
+
+        // Scaled values reach the model bit-exactly, with no float conversion in between
+        let mut values = vec![0; NUM_FEATURES];
+        values[..3].copy_from_slice(&[220_286_213, 449_999_999, 180_000_001]);
+        let mut args = vec!["predict".to_string(), "predict".to_string(), "--scaled".to_string()];
+        args.extend(values.iter().map(i64::to_string));
+        let Some(Command::Predict(features)) = Cli::try_parse_from(&args).unwrap().command else { panic!() };
+        assert_eq!(features.scaled().unwrap(), values);
+        args[3] = "1.5".to_string();
+        let Some(Command::Predict(features)) = Cli::try_parse_from(&args).unwrap().command else { panic!() };
+        assert_eq!(features.scaled().unwrap_err(), "feature 0 (1.5) is not an i64");
+    }
+
+    #[test]
+    fn test_read_feature_file() {
+        let write = |name: &str, contents: &str| {
+            let path = std::env::temp_dir().join(format!("features_test_{}_{}", std::process::id(), name));
+            std::fs::write(&path, contents).unwrap();
+            path.to_string_lossy().into_owned()
+        };
+        let raw: Vec<String> = (0..NUM_FEATURES).map(|i| format!("{}.5", i % 3)).collect();
+        let expected: Vec<i64> = (0..NUM_FEATURES).map(|i| to_fixed_point(i as f64 % 3.0 + 0.5)).collect();
+
+        let json = write("raw.json", &format!("{{\"features\": [{}]}}", raw.join(", ")));
+        assert_eq!(read_feature_file(&json, false).unwrap(), expected);
+        let csv = write("raw.csv", &format!("{}\n{}\n", rainfall_prediction::FEATURE_NAMES.join(","), raw.join(",")));
+        assert_eq!(read_feature_file(&csv, false).unwrap(), expected);
+
+        let scaled: Vec<String> = expected.iter().map(i64::to_string).collect();
+        let array = write("scaled.json", &format!("[{}]", scaled.join(",")));
+        assert_eq!(read_feature_file(&array, true).unwrap(), expected);
+        let object = write("object.json", &format!("{{\"features_scaled\": [{}]}}", scaled.join(",")));
+        assert_eq!(read_feature_file(&object, false).unwrap(), expected);
+        assert!(read_feature_file(&json, true).unwrap_err().ends_with("feature 0 (0.5) is not an i64"));
+        // The compiled model has no feature names, so named files use the XGBoost ids
+        let pairs: Vec<String> =
+            raw.iter().enumerate().rev().map(|(i, value)| format!("\"f{}\": {}", i, value)).collect();
+        let named = write("named.json", &format!("{{\"features\": {{{}}}}}", pairs.join(", ")));
+        assert_eq!(read_feature_file(&named, false).unwrap(), expected);
+        let partial = write("partial.json", &format!("{{\"features\": {{{}}}}}", pairs[1..].join(", ")));
+        assert!(read_feature_file(&partial, false).unwrap_err().ends_with("no value for feature 'f115'"));
+
+        let short = write("short.csv", "1,2,3\n");
+        assert!(read_feature_file(&short, false).unwrap_err().ends_with("expected 116 features, got 3"));
+        let two_rows = write("rows.csv", &format!("{}\n{}\n", raw.join(","), raw.join(",")));
+        assert!(read_feature_file(&two_rows, false).unwrap_err().ends_with("expected one row of features, found 2"));
+        let mut bad = raw.clone();
+        bad[7] = "abc".to_string();
+        let bad = write("bad.csv", &bad.join(","));
+        assert!(read_feature_file(&bad, false).unwrap_err().ends_with("feature 7 (abc) is not a finite number"));
+        // Values are scaled from their text: through f64 this tie would round down
+        let mut tie = raw.clone();
+        tie[0] = "1438.10345095225".to_string();
+        let tie = write("tie.csv", &tie.join(","));
+        assert_eq!(read_feature_file(&tie, false).unwrap()[0], 14_381_034_509_523);
+        assert!(read_feature_file("/nonexistent/features.json", false).unwrap_err().starts_with("cannot read"));
+
+        for path in [json, csv, array, object, named, partial, short, two_rows, bad, tie] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn test_feature_values_follow_model() {
+        // Inputs take the model's feature count and scale, and the schema only applies to rainfall vectors
+        let model = Model::new(builtin_model().trees().to_vec(), 1000).with_num_features(NUM_FEATURES + 4);
+        let mut fields = vec!["0".to_string(); NUM_FEATURES + 4];
+        fields[119] = "-1e6".to_string();
+        fields[2] = String::new();
+        let scaled_features = parse_feature_values(&fields, false, &model).unwrap();
+        assert_eq!(scaled_features.len(), NUM_FEATURES + 4);
+        assert_eq!((scaled_features[2], scaled_features[119]), (-99_999_000, -1_000_000_000));
+        assert!(check_features(&scaled_features, &model).is_ok());
+        assert!(model.try_eval(&scaled_features).is_ok());
+        let short = parse_feature_values(&fields[..NUM_FEATURES], false, &model).unwrap_err();
+        assert_eq!(short, "expected 120 features, got 116");
+        assert_eq!(input_schema(&model).map(|schema| schema.len()), None);
+        assert_eq!(input_schema(&builtin_model()).map(|schema| schema.len()), Some(NUM_FEATURES));
+    }
+
+    #[test]
+    fn test_evaluate_csv() {
+        let zeros = vec!["0"; NUM_FEATURES].join(",");
+        let prediction = from_fixed_point(xgboost_predict(&vec![0; NUM_FEATURES]));
+        let rows = [zeros.clone(), format!("{},1.5", zeros), format!("{},0.5,{}", zeros, prediction + 0.25)];
+        let bad = [zeros.clone(), format!("{},", zeros), format!("{},abc", zeros)];
+        let input = format!("{}\n{}\n", rows.join("\n"), bad.join("\n"));
+        let primary = registry().get(None, None).unwrap();
+        let (evaluation, skipped) = evaluate_csv(input.as_bytes(), &primary, false).unwrap();
+        assert_eq!(evaluation.fixed.count, 2);
+        let errors = [prediction - 1.5, prediction - 0.5];
+        assert!((evaluation.fixed.mae() - (errors[0].abs() + errors[1].abs()) / 2.0).abs() < 1e-9);
+        assert!((evaluation.fixed.bias() - (errors[0] + errors[1]) / 2.0).abs() < 1e-9);
+        assert_eq!(evaluation.delta.count, 1);
+        assert!((evaluation.delta.bias() + 0.25).abs() < 1e-9);
+
+        let reasons: Vec<&str> = skipped.iter().map(|(_, reason)| reason.as_str()).collect();
+        assert_eq!(skipped.iter().map(|(line, _)| *line).collect::<Vec<_>>(), [1, 4, 5, 6]);
+        let wrong_count = "expected 117 or 118 columns, got 116";
+        let bad_label = "column 117: 'abc' is not a finite number";
+        assert_eq!(reasons, [wrong_count, wrong_count, "column 117: missing label", bad_label]);
+    }
+
+    #[test]
+    fn test_synthetic_csv_scores() {
+        for scaled in [false, true] {
+            let mut csv = Vec::new();
+            write_synthetic_csv(&mut SyntheticGenerator::rainfall(5), 20, scaled, &mut csv).unwrap();
+            let mut again = Vec::new();
+            write_synthetic_csv(&mut SyntheticGenerator::rainfall(5), 20, scaled, &mut again).unwrap();
+            assert_eq!(csv, again);
+
+            let primary = registry().get(None, None).unwrap();
+            let summary = score_csv(csv.as_slice(), &mut Vec::new(), &primary, false, scaled, None).unwrap();
+            assert_eq!((summary.scored, summary.skipped.len()), (20, 0));
+        }
+    }
+
+    #[test]
+    fn test_shadow_scoring() {
+        let zeros = vec!["0"; NUM_FEATURES].join(",");
+        let expected = xgboost_predict(&vec![0; NUM_FEATURES]);
+        let candidates = ModelRegistry::new();
+        candidates.register("candidate", "1", builtin_model().with_base_score(builtin_model().base_score() - 3));
+        let shadow = candidates.get(None, None).unwrap();
+
+        let before = SHADOW_DIVERGENCE.lock().unwrap().count;
+        let mut output = Vec::new();
+        let input = format!("{}\n{}\n", zeros, zeros);
+        let primary = registry().get(None, None).unwrap();
+        score_csv(input.as_bytes(), &mut output, &primary, false, false, Some(&shadow)).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "line,prediction,prediction_scaled,fingerprint,shadow_scaled");
+        let fingerprint = Fingerprint::new(&builtin_model(), EvaluatorMode::Branching).id();
+        assert!(lines[1].ends_with(&format!(",{},{},{}", expected, fingerprint, expected - 3)));
+        assert_eq!(SHADOW_DIVERGENCE.lock().unwrap().count, before + 2);
+
+        // A shadow that reads more features or another scale than the served model is refused
+        let model = builtin_model();
+        let wide = Model::new(model.trees().to_vec(), model.scale()).with_num_features(NUM_FEATURES + 1);
+        let rescaled = Model::new(model.trees().to_vec(), 1000).with_num_features(NUM_FEATURES);
+        assert_eq!(check_shadow(&model, &wide).unwrap_err(), "it reads 117 features, the served model's inputs have 116");
+        assert_eq!(check_shadow(&model, &rescaled).unwrap_err(), "its scale is 1000, the served model's is 10000000000");
+        assert!(check_shadow(&wide, &model).is_ok());
+
+        // Without --shadow the server has no divergence to report
+        assert_eq!(handle_request(r#"{"op": "shadow"}"#).get("shadow"), Some(&JsonValue::Null));
+    }
+
+    #[test]
+    fn test_score_csv() {
+        let zeros = vec!["0"; NUM_FEATURES].join(",");
+        let mut bad_value = vec!["0"; NUM_FEATURES];
+        bad_value[3] = "abc";
+        let input = format!("{}\n{}\n1,2,3\n{}\n\n{}\n", rainfall_prediction::FEATURE_NAMES.join(","), zeros, bad_value.join(","), zeros);
+
+        let mut output = Vec::new();
+        let primary = registry().get(None, None).unwrap();
+        let summary = score_csv(input.as_bytes(), &mut output, &primary, true, false, None).unwrap();
+        assert_eq!(summary.scored, 2);
+        assert_eq!(summary.skipped.iter().map(|(line, _)| *line).collect::<Vec<_>>(), vec![3, 4]);
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("line,prediction,prediction_scaled,fingerprint,tree_0,"));
+        let expected = xgboost_predict(&vec![0; NUM_FEATURES]);
+        let fingerprint = Fingerprint::new(&builtin_model(), EvaluatorMode::Branching);
+        let first_row = format!("2,{:.10},{},{},", from_fixed_point(expected), expected, fingerprint.id());
+        assert!(lines[1].starts_with(&first_row));
+        assert!(lines[2].starts_with("6,"));
+        assert_eq!(lines[1].split(',').count(), 4 + builtin_model().num_trees());
+
+        // Scaled rows give the same predictions
+        let scaled_zeros = vec!["0"; NUM_FEATURES].join(",");
+        let mut output = Vec::new();
+        let input = format!("{}\n0.5{}\n", scaled_zeros, &scaled_zeros[1..]);
+        let summary = score_csv(input.as_bytes(), &mut output, &primary, false, true, None).unwrap();
+        assert_eq!(summary.scored, 1);
+        assert_eq!(summary.skipped[0].1, "column 1: '0.5' is not a scaled integer");
+        let first_row = format!("\n1,{:.10},{},{}\n", from_fixed_point(expected), expected, fingerprint.id());
+        assert!(String::from_utf8(output).unwrap().contains(&first_row));
+    }
+
+    #[test]
+    fn test_score_csv_other_model() {
+        // Rows take the model's columns and scale, with no rainfall schema for a wider model
+        let model = Model::new(builtin_model().trees().to_vec(), 1000).with_num_features(NUM_FEATURES + 4);
+        let primary = ModelRegistry::from(model.clone()).get(None, None).unwrap();
+        let mut wide = vec!["0"; NUM_FEATURES + 4];
+        wide[119] = "-1e6";
+        let (wide, narrow) = (wide.join(","), vec!["0"; NUM_FEATURES].join(","));
+        let expected = model.predict(&parse_decimal_row_at(&wide, 1000).unwrap());
+
+        let mut output = Vec::new();
+        let input = format!("{}\n{}\n", wide, narrow);
+        let summary = score_csv(input.as_bytes(), &mut output, &primary, false, false, None).unwrap();
+        assert_eq!(summary.scored, 1);
+        assert_eq!(summary.skipped, vec![(2, "expected 120 columns, got 116".to_string())]);
+        let output = String::from_utf8(output).unwrap();
+        let fingerprint = Fingerprint::new(&model, EvaluatorMode::Branching).id();
+        let row = format!("\n1,{:.10},{},{}\n", from_fixed_point_at(expected, 1000), expected, fingerprint);
+        assert!(output.contains(&row));
+
+        let input = format!("{},1.5\n{},1.5\n", wide, narrow);
+        let (evaluation, skipped) = evaluate_csv(input.as_bytes(), &primary, false).unwrap();
+        assert_eq!(evaluation.fixed.count, 1);
+        assert_eq!(skipped, vec![(2, "expected 121 or 122 columns, got 117".to_string())]);
+    }
+
+    #[test]
+    fn test_serve_lines() {
+        let zeros = vec!["0"; NUM_FEATURES].join(",");
+        let input = format!("[{}]\n\n{{\"id\": \"a\", \"features\": [{}]}}\n{{\"id\": 7, \"features\": [1]}}\nnot json\n", zeros, zeros);
+        // The REST API's `features_scaled` form is accepted too, and mixing both forms is refused
+        let scaled = format!("{{\"id\": 8, \"features_scaled\": [{}]}}\n", zeros);
+        let input = format!("{}{}{{\"features\": [], \"features_scaled\": []}}\n", input, scaled);
+
+        let mut output = Vec::new();
+        assert_eq!(serve_lines(input.as_bytes(), &mut output).unwrap(), 6);
+
+        let expected = xgboost_predict(&vec![0; NUM_FEATURES]);
+        let responses: Vec<JsonValue> =
+            String::from_utf8(output).unwrap().lines().map(|line| json::parse(line).unwrap()).collect();
+        assert_eq!(responses[0].get("prediction_scaled").and_then(JsonValue::as_i64), Some(expected));
+        assert_eq!(responses[1].get("id").and_then(JsonValue::as_str), Some("a"));
+        assert_eq!(responses[1].get("prediction_scaled"), responses[0].get("prediction_scaled"));
+        assert_eq!(responses[2].get("id").and_then(JsonValue::as_i64), Some(7));
+        assert!(responses[2].get("error").and_then(JsonValue::as_str).unwrap().contains("expected 116"));
+        assert!(responses[3].get("error").is_some());
+        assert_eq!(responses[4].get("id").and_then(JsonValue::as_i64), Some(8));
+        assert_eq!(responses[4].get("prediction_scaled"), responses[0].get("prediction_scaled"));
+        let mixed = responses[5].get("error").and_then(JsonValue::as_str);
+        assert_eq!(mixed, Some("expected exactly one of \"features\" and \"features_scaled\""));
+    }
+
+    #[test]
+    fn test_registry_requests() {
+        let zeros = vec!["0"; NUM_FEATURES].join(",");
+        let expected = xgboost_predict(&vec![0; NUM_FEATURES]);
+        let scaled = |response: &JsonValue| response.get("prediction_scaled").and_then(JsonValue::as_i64);
+        let field = |response: &JsonValue, key: &str| response.get(key).and_then(JsonValue::as_str).map(String::from);
+        let load = r#"{"op": "load", "model": "port", "version": "a", "path": "models/bst1_10.json"}"#;
+        assert!(field(&handle_request(load), "error").unwrap().contains("--admin"));
+
+        ADMIN.store(true, Ordering::Relaxed);
+        let models = handle_request(load);
+        let listed = models.get("models").and_then(JsonValue::as_array).unwrap();
+        assert!(listed.iter().any(|m| field(m, "model").as_deref() == Some("port")));
+        let request = format!(r#"{{"id": 1, "model": "port", "features": [{}]}}"#, zeros);
+        let response = handle_request(&request);
+        assert_eq!((field(&response, "version").as_deref(), scaled(&response)), (Some("a"), Some(expected)));
+
+        // Swapping in a new version reroutes the next request; activating the old one rolls back
+        registry().register("port", "b", builtin_model().with_base_score(builtin_model().base_score() + 7));
+        let response = handle_request(&request);
+        assert_eq!((field(&response, "version").as_deref(), scaled(&response)), (Some("b"), Some(expected + 7)));
+        handle_request(r#"{"op": "activate", "model": "port", "version": "a"}"#);
+        assert_eq!(scaled(&handle_request(&request)), Some(expected));
+        let pinned = format!(r#"{{"model": "port", "version": "b", "features": [{}]}}"#, zeros);
+        assert_eq!(scaled(&handle_request(&pinned)), Some(expected + 7));
+
+        let unknown = handle_request(&format!(r#"{{"model": "snow", "features": [{}]}}"#, zeros));
+        assert_eq!(field(&unknown, "error").as_deref(), Some("unknown model 'snow'"));
+        let bad_op = handle_request(r#"{"op": "reload"}"#);
+        assert_eq!(field(&bad_op, "error").as_deref(), Some("unknown op \"reload\""));
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn test_metrics_response() {
+        let zeros = vec!["0"; NUM_FEATURES].join(",");
+        let (predictions, failures) = (METRICS.predictions(), METRICS.validation_failures());
+        handle_request(&format!("[{}]", zeros));
+        handle_request("[1]");
+        // Other tests share the counters, so only check they moved
+        assert!(METRICS.predictions() > predictions);
+        assert!(METRICS.validation_failures() > failures);
+
+        let response = metrics_response("GET /metrics HTTP/1.1\r\n");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\n"));
+        assert!(response.contains("\r\n\r\n# HELP rainfall_predictions_total"));
+        assert!(response.contains("rainfall_prediction_latency_seconds_count"));
+        assert!(metrics_response("GET / HTTP/1.1\r\n").starts_with("HTTP/1.1 404"));
+    }
+
     #[test]
     fn test_prediction_deterministic() {
-        let features = vec![0.0; 116];
+        let features = vec![0.0; NUM_FEATURES];
         let pred1 = make_prediction(&features);
         let pred2 = make_prediction(&features);
         
@@ -0,0 +1,216 @@
+// Server metrics
+// Counters and latency histograms of the inference and proving service, rendered in the
+// Prometheus text exposition format (version 0.0.4) for the `predict serve` `/metrics`
+// endpoint. Everything is a relaxed atomic, so a `ServerMetrics` can live in a `static` and be
+// updated from every connection thread without locking; counts are only ever added to. Targets
+// without 64-bit atomics (e.g. Cortex-M) leave the module out.
+
+use alloc::string::String;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+
+use crate::cache::CacheStats;
+
+/// Upper bounds (seconds) of the prediction latency buckets
+pub const LATENCY_BUCKETS: [f64; 11] = [0.00001, 0.00005, 0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0];
+
+/// Upper bounds (seconds) of the proof generation time buckets
+pub const PROOF_BUCKETS: [f64; 9] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Cumulative histogram of durations with fixed bucket bounds
+#[derive(Debug)]
+pub struct Histogram<const N: usize> {
+    bounds: [f64; N],
+    /// Observations per bucket (not cumulative); the `+Inf` bucket is `count` minus their sum
+    buckets: [AtomicU64; N],
+    count: AtomicU64,
+    sum_nanos: AtomicU64,
+}
+
+impl<const N: usize> Histogram<N> {
+    /// Empty histogram with increasing bucket bounds in seconds
+    pub const fn new(bounds: [f64; N]) -> Histogram<N> {
+        Histogram {
+            bounds,
+            buckets: [const { AtomicU64::new(0) }; N],
+            count: AtomicU64::new(0),
+            sum_nanos: AtomicU64::new(0),
+        }
+    }
+
+    /// Record one duration
+    pub fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        if let Some(bucket) = self.bounds.iter().position(|&bound| seconds <= bound) {
+            self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.sum_nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    /// Number of recorded durations
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Append the `_bucket`, `_sum` and `_count` series of the histogram `name`
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        let mut cumulative = 0;
+        for (bound, bucket) in self.bounds.iter().zip(&self.buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative);
+        }
+        let count = self.count();
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, count);
+        let _ = writeln!(out, "{}_sum {}", name, self.sum_nanos.load(Ordering::Relaxed) as f64 / 1e9);
+        let _ = writeln!(out, "{}_count {}", name, count);
+    }
+}
+
+/// Counters and histograms of a prediction server
+#[derive(Debug)]
+pub struct ServerMetrics {
+    predictions: AtomicU64,
+    validation_failures: AtomicU64,
+    proofs: AtomicU64,
+    proof_failures: AtomicU64,
+    latency: Histogram<11>,
+    proof_time: Histogram<9>,
+}
+
+impl ServerMetrics {
+    /// All counters at zero, usable in a `static`
+    pub const fn new() -> ServerMetrics {
+        ServerMetrics {
+            predictions: AtomicU64::new(0),
+            validation_failures: AtomicU64::new(0),
+            proofs: AtomicU64::new(0),
+            proof_failures: AtomicU64::new(0),
+            latency: Histogram::new(LATENCY_BUCKETS),
+            proof_time: Histogram::new(PROOF_BUCKETS),
+        }
+    }
+
+    /// Record a served prediction and the time it took to answer the request
+    pub fn record_prediction(&self, latency: Duration) {
+        self.predictions.fetch_add(1, Ordering::Relaxed);
+        self.latency.observe(latency);
+    }
+
+    /// Record a request rejected before scoring (malformed, wrong feature count or out of schema)
+    pub fn record_validation_failure(&self) {
+        self.validation_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a proof generation attempt and its duration
+    pub fn record_proof(&self, duration: Duration, succeeded: bool) {
+        if succeeded {
+            self.proofs.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.proof_failures.fetch_add(1, Ordering::Relaxed);
+        }
+        self.proof_time.observe(duration);
+    }
+
+    /// Number of served predictions
+    pub fn predictions(&self) -> u64 {
+        self.predictions.load(Ordering::Relaxed)
+    }
+
+    /// Number of rejected requests
+    pub fn validation_failures(&self) -> u64 {
+        self.validation_failures.load(Ordering::Relaxed)
+    }
+
+    /// Render every metric in the Prometheus text format
+    ///
+    /// # Arguments
+    /// * `cache` - Counters of the server's prediction cache, if it has one
+    ///
+    /// # Returns
+    /// * `String` - Body of a `/metrics` response (content type `text/plain; version=0.0.4`)
+    pub fn render(&self, cache: Option<&CacheStats>) -> String {
+        let mut out = String::new();
+        let counter = |out: &mut String, name: &str, help: &str, value: u64| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, value);
+        };
+        counter(&mut out, "rainfall_predictions_total", "Predictions served.", self.predictions());
+        counter(
+            &mut out,
+            "rainfall_validation_failures_total",
+            "Requests rejected before scoring.",
+            self.validation_failures(),
+        );
+        let _ = writeln!(out, "# HELP rainfall_proofs_total Proof generation attempts.");
+        let _ = writeln!(out, "# TYPE rainfall_proofs_total counter");
+        let (proofs, failures) = (self.proofs.load(Ordering::Relaxed), self.proof_failures.load(Ordering::Relaxed));
+        let _ = writeln!(out, "rainfall_proofs_total{{result=\"ok\"}} {}", proofs);
+        let _ = writeln!(out, "rainfall_proofs_total{{result=\"error\"}} {}", failures);
+        self.latency.render(&mut out, "rainfall_prediction_latency_seconds", "Time to answer a prediction request.");
+        self.proof_time.render(&mut out, "rainfall_proof_duration_seconds", "Time to generate a proof.");
+        if let Some(cache) = cache {
+            counter(&mut out, "rainfall_cache_hits_total", "Predictions answered from the cache.", cache.hits);
+            counter(&mut out, "rainfall_cache_misses_total", "Predictions that ran the model.", cache.misses);
+            counter(&mut out, "rainfall_cache_evictions_total", "Cache entries evicted.", cache.evictions);
+            let _ = writeln!(out, "# HELP rainfall_cache_entries Predictions held in the cache.");
+            let _ = writeln!(out, "# TYPE rainfall_cache_entries gauge");
+            let _ = writeln!(out, "rainfall_cache_entries {}", cache.len);
+        }
+        out
+    }
+}
+
+impl Default for ServerMetrics {
+    fn default() -> Self {
+        ServerMetrics::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_is_cumulative() {
+        let histogram = Histogram::new([0.001, 0.01]);
+        histogram.observe(Duration::from_micros(500));
+        histogram.observe(Duration::from_millis(5));
+        histogram.observe(Duration::from_millis(5));
+        histogram.observe(Duration::from_secs(2));
+
+        let mut out = String::new();
+        histogram.render(&mut out, "t_seconds", "Test.");
+        let expected = "# HELP t_seconds Test.\n# TYPE t_seconds histogram\n\
+            t_seconds_bucket{le=\"0.001\"} 1\nt_seconds_bucket{le=\"0.01\"} 3\nt_seconds_bucket{le=\"+Inf\"} 4\n\
+            t_seconds_sum 2.0105\nt_seconds_count 4\n";
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_render_server_metrics() {
+        let metrics = ServerMetrics::new();
+        metrics.record_prediction(Duration::from_micros(20));
+        metrics.record_prediction(Duration::from_micros(70));
+        metrics.record_validation_failure();
+        metrics.record_proof(Duration::from_millis(800), true);
+
+        let text = metrics.render(None);
+        assert!(text.contains("rainfall_predictions_total 2\n"));
+        assert!(text.contains("rainfall_validation_failures_total 1\n"));
+        assert!(text.contains("rainfall_proofs_total{result=\"ok\"} 1\n"));
+        assert!(text.contains("rainfall_proofs_total{result=\"error\"} 0\n"));
+        assert!(text.contains("rainfall_prediction_latency_seconds_bucket{le=\"0.00005\"} 1\n"));
+        assert!(text.contains("rainfall_prediction_latency_seconds_bucket{le=\"0.0001\"} 2\n"));
+        assert!(text.contains("rainfall_proof_duration_seconds_bucket{le=\"1\"} 1\n"));
+        assert!(!text.contains("cache"));
+
+        let stats = CacheStats { hits: 3, misses: 2, evictions: 0, len: 2, capacity: 8 };
+        assert!(metrics.render(Some(&stats)).ends_with("rainfall_cache_entries 2\n"));
+    }
+}
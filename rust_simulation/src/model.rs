@@ -0,0 +1,297 @@
+// Runtime model loader
+// Builds a tree ensemble from an XGBoost JSON dump (`booster.dump_model(path, dump_format='json')`)
+// so models can be swapped without recompiling. Thresholds and leaf values are scaled by 10^10
+// exactly like the code generator does, so predictions match the generated `xgboost_predict`.
+// Categorical splits (XGBoost 1.6+, `enable_categorical=True`) list their categories in
+// `split_condition`; their "yes" branch, taken for those categories, becomes the left child.
+
+use alloc::{boxed::Box, format, string::{String, ToString}, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::ensemble::{BitSet, Ensemble, Node, Tree};
+use crate::fixed::round_half_even;
+use crate::json::{self, JsonError, JsonValue};
+use crate::PRECISION_MULTIPLIER;
+
+/// Error raised while loading a model dump
+#[derive(Debug)]
+pub enum ModelError {
+    /// The dump file could not be read
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// The dump is not valid JSON
+    Json(JsonError),
+    /// A node is missing fields or references unknown children
+    InvalidNode { tree: usize, message: String },
+    /// A split refers to a feature name that is not of the form `f<index>`
+    UnknownFeature { tree: usize, name: String },
+    /// A saved XGBoost model lacks a field or has it in an unexpected form
+    InvalidField { path: &'static str },
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            ModelError::Io(e) => write!(f, "failed to read model: {}", e),
+            ModelError::Json(e) => write!(f, "invalid model JSON: {}", e),
+            ModelError::InvalidNode { tree, message } => {
+                write!(f, "invalid node in tree {}: {}", tree, message)
+            }
+            ModelError::UnknownFeature { tree, name } => {
+                write!(f, "unknown feature '{}' in tree {}", name, tree)
+            }
+            ModelError::InvalidField { path } => write!(f, "missing or invalid field {}", path),
+        }
+    }
+}
+
+impl core::error::Error for ModelError {}
+
+impl From<JsonError> for ModelError {
+    fn from(e: JsonError) -> Self {
+        ModelError::Json(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for ModelError {
+    fn from(e: std::io::Error) -> Self {
+        ModelError::Io(e)
+    }
+}
+
+/// Runtime model: a tree ensemble loaded from a dump instead of compiled in
+pub type Model = Ensemble;
+
+impl Ensemble {
+    /// Load a model from the text of an XGBoost JSON dump
+    ///
+    /// # Arguments
+    /// * `dump` - JSON array with one nested tree object per tree
+    ///
+    /// # Returns
+    /// * `Result<Model, ModelError>` - Model with thresholds and leaves scaled by 10^10
+    pub fn from_xgboost_json(dump: &str) -> Result<Model, ModelError> {
+        Model::from_xgboost_json_with_scale(dump, PRECISION_MULTIPLIER)
+    }
+
+    /// Load a model from the text of an XGBoost JSON dump at a custom fixed-point scale
+    ///
+    /// # Arguments
+    /// * `dump` - JSON array with one nested tree object per tree
+    /// * `scale` - Units per 1.0 for thresholds and leaves (e.g. 10^6 or 2^16)
+    ///
+    /// # Returns
+    /// * `Result<Model, ModelError>` - Model with thresholds and leaves scaled by `scale`
+    pub fn from_xgboost_json_with_scale(dump: &str, scale: i64) -> Result<Model, ModelError> {
+        assert!(scale > 0, "fixed-point scale must be positive");
+        let trees = parse_float_trees(dump)?.iter().map(|tree| Tree::new(tree.to_node(scale))).collect();
+        Ok(Ensemble::new(trees, scale))
+    }
+
+    /// Load a model from an XGBoost JSON dump file
+    #[cfg(feature = "std")]
+    pub fn from_xgboost_json_file<P: AsRef<Path>>(path: P) -> Result<Model, ModelError> {
+        Model::from_xgboost_json(&std::fs::read_to_string(path)?)
+    }
+}
+
+/// Scale a dump value the way the converter does (`np.round(value * scale)`, ties to even)
+pub(crate) fn scale_dump_value(value: f64, scale: i64) -> i64 {
+    round_half_even(value * scale as f64)
+}
+
+/// Parse an XGBoost feature reference such as `f34`
+fn parse_feature(name: &str) -> Option<usize> {
+    name.strip_prefix('f').unwrap_or(name).parse().ok()
+}
+
+/// Dump node with its original floating-point threshold and leaf values
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum FloatNode {
+    Leaf(f64),
+    /// Go left when `features[feature] <= threshold`
+    Split { feature: usize, threshold: f64, left: Box<FloatNode>, right: Box<FloatNode> },
+    /// Go left when `features[feature]` is one of `categories`
+    Categorical { feature: usize, categories: BitSet, left: Box<FloatNode>, right: Box<FloatNode> },
+}
+
+impl FloatNode {
+    /// Quantize thresholds and leaves to `scale`
+    pub(crate) fn to_node(&self, scale: i64) -> Node {
+        match self {
+            FloatNode::Leaf(value) => Node::Leaf(scale_dump_value(*value, scale)),
+            FloatNode::Split { feature, threshold, left, right } => {
+                Node::split(*feature, scale_dump_value(*threshold, scale), left.to_node(scale), right.to_node(scale))
+            }
+            FloatNode::Categorical { feature, categories, left, right } => {
+                Node::categorical(*feature, categories.clone(), scale, left.to_node(scale), right.to_node(scale))
+            }
+        }
+    }
+}
+
+/// Parse every tree of a dump without quantizing it
+pub(crate) fn parse_float_trees(dump: &str) -> Result<Vec<FloatNode>, ModelError> {
+    let value = json::parse(dump)?;
+    let tree_values = value.as_array().ok_or_else(|| ModelError::InvalidNode {
+        tree: 0,
+        message: "model dump must be a JSON array of trees".to_string(),
+    })?;
+
+    tree_values
+        .iter()
+        .enumerate()
+        .map(|(tree_idx, tree_value)| build_node(tree_value, tree_idx))
+        .collect()
+}
+
+/// Build the node and its subtree from a dump object
+fn build_node(value: &JsonValue, tree: usize) -> Result<FloatNode, ModelError> {
+    let invalid = |message: &str| ModelError::InvalidNode { tree, message: message.to_string() };
+
+    if let Some(leaf) = value.get("leaf") {
+        let leaf = leaf.as_f64().ok_or_else(|| invalid("leaf value is not a number"))?;
+        return Ok(FloatNode::Leaf(leaf));
+    }
+
+    let name = value
+        .get("split")
+        .and_then(JsonValue::as_str)
+        .ok_or_else(|| invalid("node has neither 'leaf' nor 'split'"))?;
+    let feature = parse_feature(name)
+        .ok_or_else(|| ModelError::UnknownFeature { tree, name: name.to_string() })?;
+    let yes = value.get("yes").and_then(JsonValue::as_usize).ok_or_else(|| invalid("split without 'yes'"))?;
+    let no = value.get("no").and_then(JsonValue::as_usize).ok_or_else(|| invalid("split without 'no'"))?;
+    let children = value
+        .get("children")
+        .and_then(JsonValue::as_array)
+        .ok_or_else(|| invalid("split without 'children'"))?;
+
+    let find_child = |id: usize| {
+        children
+            .iter()
+            .find(|c| c.get("nodeid").and_then(JsonValue::as_usize) == Some(id))
+            .ok_or_else(|| invalid(&format!("child node {} not found", id)))
+    };
+    let left = build_node(find_child(yes)?, tree)?;
+    let right = build_node(find_child(no)?, tree)?;
+    let (left, right) = (Box::new(left), Box::new(right));
+
+    // Categorical splits list the categories of their "yes" branch instead of a threshold
+    let condition = value.get("split_condition");
+    if let Some(list) = condition.and_then(JsonValue::as_array) {
+        let categories = list
+            .iter()
+            .map(|c| c.as_usize().and_then(|c| u32::try_from(c).ok()))
+            .collect::<Option<BitSet>>()
+            .ok_or_else(|| invalid("category is not a non-negative integer"))?;
+        return Ok(FloatNode::Categorical { feature, categories, left, right });
+    }
+    let threshold = condition
+        .and_then(JsonValue::as_f64)
+        .ok_or_else(|| invalid("split without numeric 'split_condition'"))?;
+    Ok(FloatNode::Split { feature, threshold, left, right })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rescale_round, xgboost_predict, Lcg, NUM_FEATURES};
+
+    const BST1_10: &str = include_str!("../models/bst1_10.json");
+
+    #[test]
+    fn test_loaded_model_matches_generated_code() {
+        let model = Model::from_xgboost_json(BST1_10).unwrap();
+        assert_eq!(model.num_trees(), 10);
+        assert!(model.num_features() <= NUM_FEATURES);
+
+        let mut rng = Lcg::new(42);
+        for _ in 0..2000 {
+            let features: Vec<i64> = (0..NUM_FEATURES)
+                .map(|_| (rng.next_u31() % 600) as i64 * 1_000_000_000 - 100_000_000_000)
+                .collect();
+            assert_eq!(model.predict(&features), xgboost_predict(&features));
+        }
+
+        let zeros = vec![0i64; NUM_FEATURES];
+        assert_eq!(model.predict(&zeros), xgboost_predict(&zeros));
+        assert_eq!(crate::builtin_model().num_features(), NUM_FEATURES);
+    }
+
+    #[test]
+    fn test_thresholds_use_converter_rounding() {
+        let dump = r#"[{ "nodeid": 0, "depth": 0, "split": "f1", "split_condition": 0.845000029,
+                         "yes": 1, "no": 2, "missing": 1, "children": [
+                           { "nodeid": 2, "leaf": -0.5 }, { "nodeid": 1, "leaf": 0.25 } ]}]"#;
+        let model = Model::from_xgboost_json(dump).unwrap();
+
+        assert_eq!(model.num_features(), 2);
+        // Children are matched by node id, not by position
+        assert_eq!(model.predict(&[0, 8450000290]), 2500000000);
+        assert_eq!(model.predict(&[0, 8450000291]), -5000000000);
+    }
+
+    #[test]
+    fn test_categorical_split_dump() {
+        let dump = r#"[{ "nodeid": 0, "depth": 0, "split": "f2", "split_condition": [0, 3, 4],
+                         "yes": 2, "no": 1, "missing": 1, "children": [
+                           { "nodeid": 1, "leaf": -0.5 }, { "nodeid": 2, "leaf": 0.25 } ]}]"#;
+        let model = Model::from_xgboost_json(dump).unwrap();
+
+        assert_eq!(model.num_features(), 3);
+        assert_eq!(model.predict(&[0, 0, 3 * PRECISION_MULTIPLIER]), 2500000000);
+        assert_eq!(model.predict(&[0, 0, 0]), 2500000000);
+        assert_eq!(model.predict(&[0, 0, PRECISION_MULTIPLIER]), -5000000000);
+        assert_eq!(model.predict(&[0, 0, crate::MISSING_VALUE]), -5000000000);
+
+        let negative = dump.replace("[0, 3, 4]", "[0, -3]");
+        assert!(matches!(Model::from_xgboost_json(&negative), Err(ModelError::InvalidNode { .. })));
+    }
+
+    #[test]
+    fn test_rescaled_model_takes_same_branches() {
+        let model = Model::from_xgboost_json(BST1_10).unwrap();
+        let scale = 1 << 16;
+        let rescaled = model.rescale(scale).unwrap();
+        assert_eq!(rescaled.scale(), scale);
+
+        let mut rng = Lcg::new(7);
+        for _ in 0..500 {
+            // Inputs on the 2^16 grid, and the smallest 10^10 value not below each of them
+            let coarse: Vec<i64> = (0..NUM_FEATURES)
+                .map(|_| (rng.next_u31() % 40_000) as i64 * 16 - 65536 * 100)
+                .collect();
+            let fine: Vec<i64> = coarse
+                .iter()
+                .map(|&x| (x as i128 * PRECISION_MULTIPLIER as i128 + scale as i128 - 1).div_euclid(scale as i128) as i64)
+                .collect();
+
+            // Same leaves are selected, so the outputs differ by at most half a unit per tree
+            let expected = rescale_round(model.predict(&fine), PRECISION_MULTIPLIER, scale);
+            assert!((rescaled.predict(&coarse) - expected).abs() <= model.num_trees() as i64 / 2 + 1);
+        }
+
+        let direct = Model::from_xgboost_json_with_scale(BST1_10, 1_000_000).unwrap();
+        assert_eq!(direct.scale(), 1_000_000);
+        assert_eq!(direct.num_trees(), model.num_trees());
+    }
+
+    #[test]
+    fn test_invalid_dumps_are_rejected() {
+        assert!(matches!(Model::from_xgboost_json("{}"), Err(ModelError::InvalidNode { .. })));
+        assert!(matches!(Model::from_xgboost_json("[{"), Err(ModelError::Json(_))));
+        assert!(matches!(
+            Model::from_xgboost_json(r#"[{"nodeid":0,"split":"rain","split_condition":1,"yes":1,"no":2,"children":[]}]"#),
+            Err(ModelError::UnknownFeature { .. })
+        ));
+        assert!(matches!(
+            Model::from_xgboost_json(r#"[{"nodeid":0,"split":"f0","split_condition":1,"yes":1,"no":2,"children":[{"nodeid":1,"leaf":0}]}]"#),
+            Err(ModelError::InvalidNode { .. })
+        ));
+    }
+}
@@ -0,0 +1,353 @@
+// Monotonicity constraints
+// Meteorology expects some features to act in one direction only: more reflectivity must not mean
+// less rain. XGBoost enforces such constraints during training by keeping, at every split on a
+// constrained feature, all leaves below the threshold on one side of all leaves above it.
+// `Ensemble::check_monotone` verifies that condition on every split of a ported ensemble, which is
+// sufficient for the whole prediction to be monotone in the feature (sums and floored means of
+// monotone trees are monotone). `MonotoneAssert` checks each evaluated sample against its
+// neighbours across the nearest thresholds of every constrained feature, for models that were not
+// trained with the constraint.
+
+use alloc::{vec, vec::Vec};
+use core::fmt;
+
+use crate::ensemble::{Ensemble, Node};
+use crate::prune::leaf_range;
+
+/// Direction a constrained feature may move the prediction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Monotone {
+    /// A larger feature never lowers the prediction
+    Increasing,
+    /// A larger feature never raises the prediction
+    Decreasing,
+}
+
+/// Malformed XGBoost `monotone_constraints` string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintParseError {
+    /// 0-based feature whose entry is not -1, 0 or 1
+    pub feature: usize,
+}
+
+impl fmt::Display for ConstraintParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "monotone constraint of feature {} must be -1, 0 or 1", self.feature)
+    }
+}
+
+impl core::error::Error for ConstraintParseError {}
+
+/// Monotonicity declared per feature
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MonotoneConstraints {
+    directions: Vec<Option<Monotone>>,
+}
+
+impl MonotoneConstraints {
+    /// No constrained feature
+    pub fn new() -> MonotoneConstraints {
+        MonotoneConstraints::default()
+    }
+
+    /// Constrain one feature
+    pub fn with(mut self, feature: usize, direction: Monotone) -> Self {
+        if self.directions.len() <= feature {
+            self.directions.resize(feature + 1, None);
+        }
+        self.directions[feature] = Some(direction);
+        self
+    }
+
+    /// Parse XGBoost's `monotone_constraints` parameter, e.g. `(1,0,-1)`
+    ///
+    /// # Arguments
+    /// * `constraints` - One entry per feature: 1 increasing, -1 decreasing, 0 unconstrained
+    ///
+    /// # Returns
+    /// * `Result<MonotoneConstraints, ConstraintParseError>` - The constraints, or the first bad entry
+    pub fn parse(constraints: &str) -> Result<MonotoneConstraints, ConstraintParseError> {
+        let inner = constraints.trim().trim_start_matches('(').trim_end_matches(')');
+        let mut parsed = MonotoneConstraints::new();
+        if inner.trim().is_empty() {
+            return Ok(parsed);
+        }
+        for (feature, entry) in inner.split(',').enumerate() {
+            match entry.trim() {
+                "1" => parsed = parsed.with(feature, Monotone::Increasing),
+                "-1" => parsed = parsed.with(feature, Monotone::Decreasing),
+                "0" => {}
+                _ => return Err(ConstraintParseError { feature }),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Declared direction of a feature
+    pub fn direction(&self, feature: usize) -> Option<Monotone> {
+        self.directions.get(feature).copied().flatten()
+    }
+
+    /// Constrained features and their directions
+    pub fn iter(&self) -> impl Iterator<Item = (usize, Monotone)> + '_ {
+        self.directions.iter().enumerate().filter_map(|(feature, direction)| direction.map(|d| (feature, d)))
+    }
+}
+
+/// Place where an ensemble breaks a monotonicity constraint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonotoneViolation {
+    /// A leaf below the threshold lies on the wrong side of a leaf above it
+    Split { tree: usize, feature: usize, threshold: i64, below: i64, above: i64 },
+    /// Category sets have no order, so a constrained feature cannot be split on categorically
+    Categorical { tree: usize, feature: usize },
+    /// Moving an evaluated sample's feature across a threshold moved the prediction the wrong way
+    Sample { feature: usize, value: i64, prediction: i64, neighbour: i64, neighbour_prediction: i64 },
+}
+
+impl fmt::Display for MonotoneViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MonotoneViolation::Split { tree, feature, threshold, below, above } => write!(
+                f,
+                "tree {}: split f{} <= {} has leaf {} below the threshold and {} above it",
+                tree, feature, threshold, below, above
+            ),
+            MonotoneViolation::Categorical { tree, feature } => {
+                write!(f, "tree {}: constrained feature f{} has a categorical split", tree, feature)
+            }
+            MonotoneViolation::Sample { feature, value, prediction, neighbour, neighbour_prediction } => write!(
+                f,
+                "f{} = {} predicts {}, but f{} = {} predicts {}",
+                feature, value, prediction, feature, neighbour, neighbour_prediction
+            ),
+        }
+    }
+}
+
+impl core::error::Error for MonotoneViolation {}
+
+/// Whether `lower` (at a smaller feature value) and `higher` respect a direction
+fn ordered(direction: Monotone, lower: i64, higher: i64) -> bool {
+    match direction {
+        Monotone::Increasing => lower <= higher,
+        Monotone::Decreasing => lower >= higher,
+    }
+}
+
+fn check_node(node: &Node, tree: usize, constraints: &MonotoneConstraints, violations: &mut Vec<MonotoneViolation>) {
+    match node {
+        Node::Leaf(_) => {}
+        Node::Split { feature, threshold, left, right } => {
+            if let Some(direction) = constraints.direction(*feature) {
+                let (left_min, left_max) = leaf_range(left);
+                let (right_min, right_max) = leaf_range(right);
+                // The extreme leaves closest to breaking the order on each side
+                let (below, above) = match direction {
+                    Monotone::Increasing => (left_max, right_min),
+                    Monotone::Decreasing => (left_min, right_max),
+                };
+                if !ordered(direction, below, above) {
+                    violations.push(MonotoneViolation::Split {
+                        tree,
+                        feature: *feature,
+                        threshold: *threshold,
+                        below,
+                        above,
+                    });
+                }
+            }
+            check_node(left, tree, constraints, violations);
+            check_node(right, tree, constraints, violations);
+        }
+        Node::Categorical { feature, left, right, .. } => {
+            if constraints.direction(*feature).is_some() {
+                violations.push(MonotoneViolation::Categorical { tree, feature: *feature });
+            }
+            check_node(left, tree, constraints, violations);
+            check_node(right, tree, constraints, violations);
+        }
+    }
+}
+
+impl Ensemble {
+    /// Verify that every split on a constrained feature respects its direction
+    ///
+    /// # Arguments
+    /// * `constraints` - Declared monotone features
+    ///
+    /// # Returns
+    /// * `Result<(), Vec<MonotoneViolation>>` - Ok if the prediction is monotone as declared,
+    ///   otherwise every offending split
+    pub fn check_monotone(&self, constraints: &MonotoneConstraints) -> Result<(), Vec<MonotoneViolation>> {
+        let mut violations = Vec::new();
+        for (tree, t) in self.trees().iter().enumerate() {
+            check_node(&t.root, tree, constraints, &mut violations);
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+/// Record the thresholds each feature is split on
+fn collect_thresholds(node: &Node, thresholds: &mut [Vec<i64>]) {
+    match node {
+        Node::Leaf(_) => {}
+        Node::Split { feature, threshold, left, right } => {
+            if let Some(list) = thresholds.get_mut(*feature) {
+                list.push(*threshold);
+            }
+            collect_thresholds(left, thresholds);
+            collect_thresholds(right, thresholds);
+        }
+        Node::Categorical { left, right, .. } => {
+            collect_thresholds(left, thresholds);
+            collect_thresholds(right, thresholds);
+        }
+    }
+}
+
+/// Evaluation that asserts monotonicity around every sample
+#[derive(Debug, Clone)]
+pub struct MonotoneAssert<'a> {
+    model: &'a Ensemble,
+    /// Constrained features, their direction and sorted distinct thresholds
+    features: Vec<(usize, Monotone, Vec<i64>)>,
+}
+
+impl<'a> MonotoneAssert<'a> {
+    /// Prepare the threshold tables of the constrained features
+    pub fn new(model: &'a Ensemble, constraints: &MonotoneConstraints) -> MonotoneAssert<'a> {
+        let mut thresholds = vec![Vec::new(); constraints.directions.len()];
+        for tree in model.trees() {
+            collect_thresholds(&tree.root, &mut thresholds);
+        }
+        let features = constraints
+            .iter()
+            .map(|(feature, direction)| {
+                let mut list = core::mem::take(&mut thresholds[feature]);
+                list.sort_unstable();
+                list.dedup();
+                (feature, direction, list)
+            })
+            .collect();
+        MonotoneAssert { model, features }
+    }
+
+    /// Evaluate a sample and check it against its neighbours across the nearest threshold below
+    /// and above each constrained feature (two extra evaluations per constrained feature)
+    ///
+    /// # Arguments
+    /// * `features` - Feature vector (at the model's scale)
+    ///
+    /// # Returns
+    /// * `Result<i64, MonotoneViolation>` - The prediction, or the first neighbour out of order
+    pub fn eval(&self, features: &[i64]) -> Result<i64, MonotoneViolation> {
+        let prediction = self.model.eval(features);
+        let mut neighbour = features.to_vec();
+        for (feature, direction, thresholds) in &self.features {
+            let Some(&value) = features.get(*feature) else { continue };
+            // Largest value on the left of the nearest threshold below, smallest on the right of
+            // the nearest threshold at or above
+            let split = thresholds.partition_point(|&t| t < value);
+            let lower = split.checked_sub(1).map(|k| thresholds[k]);
+            let higher = thresholds.get(split).and_then(|t| t.checked_add(1));
+
+            for (other, other_is_lower) in [(lower, true), (higher, false)] {
+                let Some(other) = other else { continue };
+                neighbour[*feature] = other;
+                let other_prediction = self.model.eval(&neighbour);
+                let in_order = if other_is_lower {
+                    ordered(*direction, other_prediction, prediction)
+                } else {
+                    ordered(*direction, prediction, other_prediction)
+                };
+                if !in_order {
+                    return Err(MonotoneViolation::Sample {
+                        feature: *feature,
+                        value,
+                        prediction,
+                        neighbour: other,
+                        neighbour_prediction: other_prediction,
+                    });
+                }
+            }
+            neighbour[*feature] = value;
+        }
+        Ok(prediction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::Tree;
+    use crate::{builtin_model, BitSet, NUM_FEATURES};
+
+    fn model() -> Ensemble {
+        // Increasing in f0, decreasing in f1 except for the right subtree of tree 1
+        let first = Tree::new(Node::split(0, 10, Node::split(1, 0, Node::Leaf(5), Node::Leaf(2)), Node::Leaf(7)));
+        let second = Tree::new(Node::split(0, 20, Node::Leaf(-1), Node::split(1, 5, Node::Leaf(0), Node::Leaf(3))));
+        Ensemble::new(vec![first, second], 1)
+    }
+
+    #[test]
+    fn test_parse_constraints() {
+        let constraints = MonotoneConstraints::parse("(1, 0,-1)").unwrap();
+        assert_eq!(constraints, MonotoneConstraints::new().with(0, Monotone::Increasing).with(2, Monotone::Decreasing));
+        assert_eq!(constraints.direction(1), None);
+        assert_eq!(constraints.direction(7), None);
+        assert_eq!(constraints.iter().count(), 2);
+        assert_eq!(MonotoneConstraints::parse("()"), Ok(MonotoneConstraints::new()));
+        assert_eq!(MonotoneConstraints::parse("(1,2)"), Err(ConstraintParseError { feature: 1 }));
+    }
+
+    #[test]
+    fn test_check_monotone() {
+        let model = model();
+        let increasing = MonotoneConstraints::new().with(0, Monotone::Increasing);
+        assert_eq!(model.check_monotone(&increasing), Ok(()));
+        assert!(model.check_monotone(&MonotoneConstraints::new().with(0, Monotone::Decreasing)).is_err());
+
+        let violations = model.check_monotone(&increasing.with(1, Monotone::Decreasing)).unwrap_err();
+        assert_eq!(
+            violations,
+            [MonotoneViolation::Split { tree: 1, feature: 1, threshold: 5, below: 0, above: 3 }]
+        );
+        assert_eq!(violations[0].to_string(), "tree 1: split f1 <= 5 has leaf 0 below the threshold and 3 above it");
+
+        let categories: BitSet = [1].into_iter().collect();
+        let tree = Tree::new(Node::categorical(0, categories, 1, Node::Leaf(0), Node::Leaf(1)));
+        let categorical = Ensemble::new(vec![tree], 1);
+        assert_eq!(
+            categorical.check_monotone(&MonotoneConstraints::new().with(0, Monotone::Increasing)),
+            Err(vec![MonotoneViolation::Categorical { tree: 0, feature: 0 }])
+        );
+    }
+
+    #[test]
+    fn test_assert_mode() {
+        let model = model();
+        let constraints = MonotoneConstraints::new().with(0, Monotone::Increasing).with(1, Monotone::Decreasing);
+        let guard = MonotoneAssert::new(&model, &constraints);
+        assert_eq!(guard.eval(&[0, 0]), Ok(4));
+        // Past f0 = 20, tree 1 rises when f1 crosses 5
+        assert_eq!(
+            guard.eval(&[30, 3]),
+            Err(MonotoneViolation::Sample {
+                feature: 1,
+                value: 3,
+                prediction: 7,
+                neighbour: 6,
+                neighbour_prediction: 10
+            })
+        );
+
+        let builtin = builtin_model();
+        let unconstrained = MonotoneAssert::new(&builtin, &MonotoneConstraints::new());
+        assert_eq!(unconstrained.eval(&[0; NUM_FEATURES]), Ok(builtin.eval(&[0; NUM_FEATURES])));
+    }
+}
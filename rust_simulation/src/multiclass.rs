@@ -0,0 +1,230 @@
+// Multi-class prediction
+// XGBoost (`multi:softmax` / `multi:softprob`) and LightGBM (`multiclass`) train one tree per
+// class per boosting round, so tree i of the flat dump belongs to class i % num_classes.
+// With `num_parallel_tree` > 1 (boosted random forests) each round grows that many trees per
+// output group, so tree i belongs to group (i / num_parallel_tree) % num_groups; XGBoost's
+// `tree_info` lists the group of every tree explicitly. Multi-output regressors use the same
+// layout with one group per target.
+// Each class accumulates its own margin; the predicted class is the argmax of the margins.
+
+use alloc::{vec, vec::Vec};
+
+use crate::ensemble::{Ensemble, Tree};
+use crate::fixed_le;
+use crate::json;
+use crate::model::ModelError;
+
+/// Ensemble with one additive margin per class
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiClassEnsemble {
+    classes: Vec<Ensemble>,
+}
+
+impl MultiClassEnsemble {
+    /// Create from one ensemble per class (all at the same scale)
+    pub fn new(classes: Vec<Ensemble>) -> MultiClassEnsemble {
+        assert!(!classes.is_empty(), "at least one class is required");
+        let scale = classes[0].scale();
+        assert!(classes.iter().all(|c| c.scale() == scale), "all classes must use the same scale");
+
+        // Every class must accept the same feature vectors
+        let num_features = classes.iter().map(Ensemble::num_features).max().unwrap_or(0);
+        let classes = classes.into_iter().map(|c| c.with_num_features(num_features)).collect();
+        MultiClassEnsemble { classes }
+    }
+
+    /// Split a flat dump where tree i belongs to class `i % num_classes`
+    ///
+    /// # Arguments
+    /// * `ensemble` - All trees in boosting order, e.g. from `Model::from_xgboost_json`
+    /// * `num_classes` - Number of classes (`num_class` in XGBoost and LightGBM)
+    ///
+    /// # Returns
+    /// * `MultiClassEnsemble` - One ensemble per class
+    pub fn from_interleaved(ensemble: &Ensemble, num_classes: usize) -> MultiClassEnsemble {
+        MultiClassEnsemble::from_parallel_trees(ensemble, num_classes, 1)
+    }
+
+    /// Split a flat dump grown with XGBoost's `num_parallel_tree`
+    ///
+    /// # Arguments
+    /// * `ensemble` - All trees in boosting order
+    /// * `num_groups` - Number of output groups (classes, or targets of a multi-output regressor)
+    /// * `num_parallel_tree` - Trees grown per group in every boosting round
+    ///
+    /// # Returns
+    /// * `MultiClassEnsemble` - One ensemble per group, tree i in group `(i / num_parallel_tree) % num_groups`
+    pub fn from_parallel_trees(ensemble: &Ensemble, num_groups: usize, num_parallel_tree: usize) -> MultiClassEnsemble {
+        assert!(num_groups > 0, "at least one class is required");
+        assert!(num_parallel_tree > 0, "num_parallel_tree must be positive");
+        let round = num_groups * num_parallel_tree;
+        assert!(ensemble.num_trees().is_multiple_of(round),
+                "{} trees cannot be split evenly into {} classes of {} parallel trees",
+                ensemble.num_trees(), num_groups, num_parallel_tree);
+
+        let groups: Vec<usize> = (0..ensemble.num_trees()).map(|i| (i / num_parallel_tree) % num_groups).collect();
+        MultiClassEnsemble::from_tree_info(ensemble, &groups, num_groups)
+    }
+
+    /// Split a flat dump by the output group of every tree (XGBoost's `tree_info`)
+    ///
+    /// # Arguments
+    /// * `ensemble` - All trees in boosting order
+    /// * `tree_info` - Output group of each tree
+    /// * `num_groups` - Number of output groups (groups without trees predict their base score)
+    ///
+    /// # Returns
+    /// * `MultiClassEnsemble` - One ensemble per group, keeping the trees' relative order
+    pub fn from_tree_info(ensemble: &Ensemble, tree_info: &[usize], num_groups: usize) -> MultiClassEnsemble {
+        assert!(num_groups > 0, "at least one class is required");
+        assert_eq!(tree_info.len(), ensemble.num_trees(), "tree_info needs one group per tree");
+
+        let mut per_class: Vec<Vec<Tree>> = vec![Vec::new(); num_groups];
+        for (tree, &group) in ensemble.trees().iter().zip(tree_info) {
+            assert!(group < num_groups, "tree group {} out of range for {} groups", group, num_groups);
+            per_class[group].push(tree.clone());
+        }
+
+        MultiClassEnsemble::new(
+            per_class
+                .into_iter()
+                .map(|trees| {
+                    Ensemble::new(trees, ensemble.scale())
+                        .with_num_features(ensemble.num_features())
+                        .with_base_score(ensemble.base_score())
+                        .with_combiner(ensemble.combiner())
+                })
+                .collect(),
+        )
+    }
+
+    /// Number of classes
+    pub fn num_classes(&self) -> usize {
+        self.classes.len()
+    }
+
+    /// Per-class ensembles
+    pub fn classes(&self) -> &[Ensemble] {
+        &self.classes
+    }
+
+    /// Minimum number of features a prediction input must provide
+    pub fn num_features(&self) -> usize {
+        self.classes[0].num_features()
+    }
+
+    /// Raw margin (base score plus leaves) of every class
+    pub fn margins(&self, features: &[i64]) -> Vec<i64> {
+        self.classes.iter().map(|c| c.eval(features)).collect()
+    }
+
+    /// Index of the class with the largest margin
+    pub fn predict_class(&self, features: &[i64]) -> usize {
+        fixed_argmax(&self.margins(features))
+    }
+}
+
+/// Output group of every tree of an XGBoost model saved with `booster.save_model("model.json")`
+///
+/// The dump (`dump_model`) that the trees are loaded from does not record the groups; the saved
+/// model lists them in `learner.gradient_booster.model.tree_info`, in the same tree order.
+///
+/// # Arguments
+/// * `model_json` - Text of the saved model
+///
+/// # Returns
+/// * `Result<Vec<usize>, ModelError>` - Group of each tree, for `MultiClassEnsemble::from_tree_info`
+pub fn xgboost_tree_info(model_json: &str) -> Result<Vec<usize>, ModelError> {
+    const PATH: &str = "learner.gradient_booster.model.tree_info";
+    let document = json::parse(model_json)?;
+    document
+        .get("learner")
+        .and_then(|learner| learner.get("gradient_booster"))
+        .and_then(|booster| booster.get("model"))
+        .and_then(|model| model.get("tree_info"))
+        .and_then(|info| info.as_array())
+        .ok_or(ModelError::InvalidField { path: PATH })?
+        .iter()
+        .map(|group| group.as_usize().ok_or(ModelError::InvalidField { path: PATH }))
+        .collect()
+}
+
+/// Argmax of fixed-point margins, ties resolved to the lowest index
+///
+/// Written as a running maximum with one `fixed_le` comparison and one select per class,
+/// which is exactly the shape of the constraint version: k-1 comparisons for k classes.
+pub fn fixed_argmax(margins: &[i64]) -> usize {
+    assert!(!margins.is_empty(), "argmax of an empty margin list");
+
+    let mut best_index = 0;
+    let mut best = margins[0];
+    for (i, &margin) in margins.iter().enumerate().skip(1) {
+        // Strictly greater wins, so the first maximum is kept
+        let keep = fixed_le(margin, best);
+        best_index = if keep { best_index } else { i };
+        best = if keep { best } else { margin };
+    }
+
+    best_index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Node;
+
+    fn stump(threshold: i64, left: i64, right: i64) -> Tree {
+        Tree::new(Node::split(0, threshold, Node::Leaf(left), Node::Leaf(right)))
+    }
+
+    #[test]
+    fn test_interleaved_trees_are_grouped_by_class() {
+        // Two rounds of three classes
+        let flat = Ensemble::new(
+            vec![stump(0, 5, 0), stump(0, 0, 5), stump(0, 1, 1), stump(10, 2, 0), stump(10, 0, 0), stump(10, 0, 9)],
+            1,
+        );
+        let model = MultiClassEnsemble::from_interleaved(&flat, 3);
+
+        assert_eq!(model.num_classes(), 3);
+        assert_eq!(model.classes()[0].num_trees(), 2);
+        assert_eq!(model.margins(&[0]), vec![7, 0, 1]);
+        assert_eq!(model.predict_class(&[0]), 0);
+        assert_eq!(model.margins(&[20]), vec![0, 5, 10]);
+        assert_eq!(model.predict_class(&[20]), 2);
+    }
+
+    #[test]
+    fn test_parallel_trees_and_tree_info() {
+        // Two rounds of two groups with two parallel trees each: groups 0 0 1 1 0 0 1 1
+        let flat = Ensemble::new((1..=8).map(|i| stump(0, i, -i)).collect(), 1);
+        let model = MultiClassEnsemble::from_parallel_trees(&flat, 2, 2);
+        assert_eq!(model.margins(&[0]), vec![1 + 2 + 5 + 6, 3 + 4 + 7 + 8]);
+        assert_eq!(model.margins(&[10]), vec![-14, -22]);
+
+        let tree_info = [0, 0, 1, 1, 0, 0, 1, 1];
+        assert_eq!(MultiClassEnsemble::from_tree_info(&flat, &tree_info, 2), model);
+
+        // A multi-output regressor whose third target has no trees yet
+        let sparse = MultiClassEnsemble::from_tree_info(&flat.clone().with_base_score(3), &[1; 8], 3);
+        assert_eq!(sparse.margins(&[0]), vec![3, 3 + 36, 3]);
+
+        let saved = r#"{"learner": {"gradient_booster": {"model": {"tree_info": [0, 0, 1, 1, 0, 0, 1, 1]}}}}"#;
+        assert_eq!(xgboost_tree_info(saved).unwrap(), tree_info);
+        assert!(matches!(xgboost_tree_info("{}"), Err(ModelError::InvalidField { .. })));
+    }
+
+    #[test]
+    fn test_fixed_argmax() {
+        assert_eq!(fixed_argmax(&[-3]), 0);
+        assert_eq!(fixed_argmax(&[1, 9, 4]), 1);
+        assert_eq!(fixed_argmax(&[5, 5, 2, 5]), 0);
+        assert_eq!(fixed_argmax(&[i64::MIN, -1, i64::MIN]), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be split evenly")]
+    fn test_uneven_tree_count_is_rejected() {
+        MultiClassEnsemble::from_interleaved(&Ensemble::new(vec![stump(0, 1, 2)], 1), 2);
+    }
+}
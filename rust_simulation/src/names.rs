@@ -0,0 +1,199 @@
+// Named features
+// A model only knows its features by position, so a caller that builds the 116-value vector in
+// the wrong order gets a wrong prediction and no error. Feature names attached to the ensemble
+// (from a sidecar JSON next to the model dump) let callers pass a name -> value map instead and
+// have the vector assembled in input order, failing on any unknown or missing name. Names are
+// metadata: they are not part of the binary format or the content hash.
+//
+// Sidecar files are either an array of names in input order or an object mapping feature ids
+// (`"f34"` or `"34"`) to names; features the object leaves out keep their `f<index>` name.
+
+use alloc::{format, string::{String, ToString}, vec::Vec};
+use core::fmt;
+
+use crate::ensemble::Ensemble;
+use crate::json::{self, JsonValue};
+
+/// Error raised while reading feature names or assembling a named feature vector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeatureNameError {
+    /// The sidecar is not valid JSON or not an array or object of names
+    InvalidSidecar(String),
+    /// The sidecar names a feature past the model's features
+    IndexOutOfBounds { index: usize, num_features: usize },
+    /// Two features have the same name
+    DuplicateName(String),
+    /// A named input is not a feature of the model
+    UnknownFeature(String),
+    /// A feature of the model has no value in a named input
+    MissingFeature(String),
+}
+
+impl fmt::Display for FeatureNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeatureNameError::InvalidSidecar(message) => write!(f, "invalid feature names: {}", message),
+            FeatureNameError::IndexOutOfBounds { index, num_features } => {
+                write!(f, "feature f{} named, but the model has {} features", index, num_features)
+            }
+            FeatureNameError::DuplicateName(name) => write!(f, "feature name '{}' is used twice", name),
+            FeatureNameError::UnknownFeature(name) => write!(f, "unknown feature '{}'", name),
+            FeatureNameError::MissingFeature(name) => write!(f, "no value for feature '{}'", name),
+        }
+    }
+}
+
+impl core::error::Error for FeatureNameError {}
+
+/// Parse a feature-name sidecar for a model with `num_features` features
+///
+/// # Arguments
+/// * `sidecar` - JSON array of names in input order, or object from `f<index>` / `<index>` to name
+/// * `num_features` - Feature count of the model the names are for
+///
+/// # Returns
+/// * `Result<Vec<String>, FeatureNameError>` - One name per feature (`f<index>` where none is given)
+pub fn parse_feature_names(sidecar: &str, num_features: usize) -> Result<Vec<String>, FeatureNameError> {
+    let invalid = |message: &str| FeatureNameError::InvalidSidecar(message.to_string());
+    let document = json::parse(sidecar).map_err(|e| FeatureNameError::InvalidSidecar(e.to_string()))?;
+    let mut names: Vec<String> = (0..num_features).map(|index| format!("f{}", index)).collect();
+    match &document {
+        JsonValue::Array(values) => {
+            if values.len() > num_features {
+                return Err(FeatureNameError::IndexOutOfBounds { index: values.len() - 1, num_features });
+            }
+            for (name, value) in names.iter_mut().zip(values) {
+                *name = value.as_str().ok_or_else(|| invalid("names must be strings"))?.to_string();
+            }
+        }
+        JsonValue::Object(entries) => {
+            for (key, value) in entries {
+                let index: usize = key
+                    .strip_prefix('f')
+                    .unwrap_or(key)
+                    .parse()
+                    .map_err(|_| FeatureNameError::InvalidSidecar(format!("'{}' is not a feature id", key)))?;
+                let name = names.get_mut(index).ok_or(FeatureNameError::IndexOutOfBounds { index, num_features })?;
+                *name = value.as_str().ok_or_else(|| invalid("names must be strings"))?.to_string();
+            }
+        }
+        _ => return Err(invalid("expected an array or object of names")),
+    }
+    for (index, name) in names.iter().enumerate() {
+        if names[..index].contains(name) {
+            return Err(FeatureNameError::DuplicateName(name.clone()));
+        }
+    }
+    Ok(names)
+}
+
+impl Ensemble {
+    /// Attach the feature names of a sidecar JSON (see [`parse_feature_names`])
+    pub fn with_feature_names_json(self, sidecar: &str) -> Result<Ensemble, FeatureNameError> {
+        let names = parse_feature_names(sidecar, self.num_features())?;
+        Ok(self.with_feature_names(names))
+    }
+
+    /// Input position of a feature, by name, or by `f<index>` for a model without names
+    pub fn feature_index(&self, name: &str) -> Option<usize> {
+        if !self.feature_names().is_empty() {
+            return self.feature_names().iter().position(|n| n == name);
+        }
+        name.strip_prefix('f')?.parse().ok().filter(|&index| index < self.num_features())
+    }
+
+    /// Name of a feature: the attached name, or `f<index>`
+    pub fn feature_name(&self, index: usize) -> String {
+        match self.feature_names().get(index) {
+            Some(name) => name.clone(),
+            None => format!("f{}", index),
+        }
+    }
+
+    /// Assemble the scaled feature vector from values by name
+    ///
+    /// # Arguments
+    /// * `values` - One raw value per feature name; NaN is the missing-value sentinel (-99999)
+    ///
+    /// # Returns
+    /// * `Result<Vec<i64>, FeatureNameError>` - Features in input order at the ensemble's scale,
+    ///   or the first unknown name or unnamed feature
+    #[cfg(feature = "std")]
+    pub fn named_features<S: core::hash::BuildHasher>(
+        &self,
+        values: &std::collections::HashMap<String, f64, S>,
+    ) -> Result<Vec<i64>, FeatureNameError> {
+        let mut features = alloc::vec![None; self.num_features()];
+        for (name, &value) in values {
+            let index = self.feature_index(name).ok_or_else(|| FeatureNameError::UnknownFeature(name.clone()))?;
+            let value = if value.is_nan() { crate::csv::MISSING_FIELD } else { value };
+            features[index] = Some(crate::fixed::round_half_away(value * self.scale() as f64));
+        }
+        features
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| value.ok_or_else(|| FeatureNameError::MissingFeature(self.feature_name(index))))
+            .collect()
+    }
+
+    /// Predict from values by name (see [`Ensemble::named_features`])
+    #[cfg(feature = "std")]
+    pub fn predict_named<S: core::hash::BuildHasher>(
+        &self,
+        values: &std::collections::HashMap<String, f64, S>,
+    ) -> Result<i64, FeatureNameError> {
+        Ok(self.predict(&self.named_features(values)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FEATURE_NAMES;
+
+    #[test]
+    fn test_parse_feature_names() {
+        assert_eq!(parse_feature_names(r#"{"f2": "rain", "0": "wind"}"#, 3).unwrap(), ["wind", "f1", "rain"]);
+        assert_eq!(parse_feature_names(r#"["a", "b"]"#, 3).unwrap(), ["a", "b", "f2"]);
+        assert_eq!(
+            parse_feature_names(r#"{"f3": "x"}"#, 3),
+            Err(FeatureNameError::IndexOutOfBounds { index: 3, num_features: 3 })
+        );
+        assert_eq!(parse_feature_names(r#"["a", "a"]"#, 3), Err(FeatureNameError::DuplicateName("a".to_string())));
+        assert!(matches!(parse_feature_names(r#"{"x": "a"}"#, 3), Err(FeatureNameError::InvalidSidecar(_))));
+
+        // The sidecar of the bundled model names the schema's features
+        let sidecar = include_str!("../models/bst1_10.features.json");
+        assert_eq!(parse_feature_names(sidecar, FEATURE_NAMES.len()).unwrap(), FEATURE_NAMES);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_predict_named() {
+        use crate::ensemble::{Node, Tree};
+        use std::collections::HashMap;
+
+        let model = || {
+            let tree = Node::split(0, 10, Node::split(2, 0, Node::Leaf(1), Node::Leaf(2)), Node::Leaf(3));
+            Ensemble::new(vec![Tree::new(tree)], 10)
+        };
+
+        let named = model().with_feature_names_json(r#"["wind", "pressure", "rain"]"#).unwrap();
+        assert_eq!(named.feature_index("rain"), Some(2));
+        assert_eq!(named.feature_index("f2"), None);
+        let values: HashMap<String, f64> =
+            [("rain".to_string(), 0.5), ("wind".to_string(), 0.25), ("pressure".to_string(), f64::NAN)].into();
+        assert_eq!(named.named_features(&values).unwrap(), [3, -999990, 5]);
+        assert_eq!(named.predict_named(&values), Ok(2));
+
+        let mut missing = values.clone();
+        missing.remove("pressure");
+        assert_eq!(named.predict_named(&missing), Err(FeatureNameError::MissingFeature("pressure".to_string())));
+        missing.insert("f1".to_string(), 0.0);
+        assert_eq!(named.predict_named(&missing), Err(FeatureNameError::UnknownFeature("f1".to_string())));
+        // Without names, features go by their `f<index>` ids
+        let by_id: HashMap<String, f64> =
+            [("f0", 0.25), ("f1", 0.0), ("f2", 0.5)].map(|(id, value)| (id.to_string(), value)).into();
+        assert_eq!(model().predict_named(&by_id), Ok(2));
+    }
+}
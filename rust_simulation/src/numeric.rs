@@ -0,0 +1,236 @@
+// Arithmetic backends
+// `Numeric` is the handful of operations a tree evaluation needs: embed a fixed-point constant,
+// compare, add and select. The oblivious evaluator is written once against it, so the exact code
+// that simulates a prediction over i64 (saturating, like `fixed_add`) or i128 (exact, like
+// `Accumulator::I128`) also computes the witness values over BN254 or BLS12-381 scalar field
+// elements (features `bn254` and `bls12-381`), where negative values wrap around the modulus and
+// compare as their signed representatives, as in the circuits.
+
+use core::fmt::Debug;
+
+use crate::ensemble::{Combiner, Ensemble, Node};
+use crate::fixed_add;
+
+/// Number type a tree ensemble can be evaluated over
+pub trait Numeric: Copy + Debug + PartialEq {
+    /// Embed a fixed-point value
+    fn from_scaled(value: i64) -> Self;
+
+    /// Signed value, if it fits in i128
+    fn to_i128(self) -> Option<i128>;
+
+    /// Whether `self <= other` as signed fixed-point values
+    fn le(self, other: Self) -> bool;
+
+    /// Sum, saturating for i64 and exact otherwise
+    fn add(self, other: Self) -> Self;
+
+    /// `if_true` when `condition` holds, else `if_false`, computed as `b * if_true + (1 - b) * if_false`
+    fn select(condition: bool, if_true: Self, if_false: Self) -> Self;
+}
+
+impl Numeric for i64 {
+    fn from_scaled(value: i64) -> Self {
+        value
+    }
+
+    fn to_i128(self) -> Option<i128> {
+        Some(self as i128)
+    }
+
+    fn le(self, other: Self) -> bool {
+        crate::fixed_le(self, other)
+    }
+
+    fn add(self, other: Self) -> Self {
+        fixed_add(self, other)
+    }
+
+    fn select(condition: bool, if_true: Self, if_false: Self) -> Self {
+        let b = condition as i64;
+        // One product is always 0, so neither the products nor the sum can overflow
+        b * if_true + (1 - b) * if_false
+    }
+}
+
+impl Numeric for i128 {
+    fn from_scaled(value: i64) -> Self {
+        value as i128
+    }
+
+    fn to_i128(self) -> Option<i128> {
+        Some(self)
+    }
+
+    fn le(self, other: Self) -> bool {
+        self <= other
+    }
+
+    fn add(self, other: Self) -> Self {
+        self.saturating_add(other)
+    }
+
+    fn select(condition: bool, if_true: Self, if_false: Self) -> Self {
+        let b = condition as i128;
+        b * if_true + (1 - b) * if_false
+    }
+}
+
+/// `Numeric` for a prime field, whose elements above (p - 1) / 2 stand for negative values
+#[cfg(any(feature = "bn254", feature = "bls12-381"))]
+macro_rules! impl_field_numeric {
+    ($field:ty) => {
+        impl Numeric for $field {
+            fn from_scaled(value: i64) -> Self {
+                <$field>::from(value)
+            }
+
+            fn to_i128(self) -> Option<i128> {
+                use ark_ff::PrimeField;
+
+                let negative = self.into_bigint() > <$field>::MODULUS_MINUS_ONE_DIV_TWO;
+                let magnitude = if negative { (-self).into_bigint() } else { self.into_bigint() };
+                let limbs = magnitude.as_ref();
+                if limbs[2..].iter().any(|&limb| limb != 0) {
+                    return None;
+                }
+                let magnitude = i128::try_from(limbs[0] as u128 | (limbs[1] as u128) << 64).ok()?;
+                Some(if negative { -magnitude } else { magnitude })
+            }
+
+            fn le(self, other: Self) -> bool {
+                use ark_ff::PrimeField;
+
+                let half = <$field>::MODULUS_MINUS_ONE_DIV_TWO;
+                let (a, b) = (self.into_bigint(), other.into_bigint());
+                // Negative values are the upper half of the field, in increasing order
+                match (a > half, b > half) {
+                    (false, true) => false,
+                    (true, false) => true,
+                    _ => a <= b,
+                }
+            }
+
+            fn add(self, other: Self) -> Self {
+                self + other
+            }
+
+            fn select(condition: bool, if_true: Self, if_false: Self) -> Self {
+                <$field>::from(condition) * (if_true - if_false) + if_false
+            }
+        }
+    };
+}
+
+#[cfg(feature = "bn254")]
+impl_field_numeric!(ark_bn254::Fr);
+#[cfg(feature = "bls12-381")]
+impl_field_numeric!(ark_bls12_381::Fr);
+
+impl Node {
+    /// Evaluate every node of the subtree over `N` and select the reached leaf arithmetically
+    ///
+    /// Categorical splits test membership of the feature's signed value; a value outside the
+    /// i64 range is in no category.
+    pub fn eval_numeric<N: Numeric>(&self, f: &[N]) -> N {
+        match self {
+            Node::Leaf(value) => N::from_scaled(*value),
+            Node::Split { feature, threshold, left, right } => {
+                let b = f[*feature].le(N::from_scaled(*threshold));
+                N::select(b, left.eval_numeric(f), right.eval_numeric(f))
+            }
+            Node::Categorical { feature, categories, unit, left, right } => {
+                let value = f[*feature].to_i128().and_then(|value| i64::try_from(value).ok());
+                let b = value.is_some_and(|value| categories.contains_value(value, *unit));
+                N::select(b, left.eval_numeric(f), right.eval_numeric(f))
+            }
+        }
+    }
+}
+
+impl Ensemble {
+    /// Evaluate the ensemble over any `Numeric` type with the oblivious evaluator
+    ///
+    /// # Arguments
+    /// * `features` - Input feature vector (at the ensemble's scale), embedded in `N`
+    ///
+    /// # Returns
+    /// * `N` - Prediction: `eval` with an i64 accumulator for `i64`; for `i128` and field elements
+    ///   the exact sum, which `Accumulator::I128` would then clamp to i64
+    ///
+    /// The accumulator semantics follow `N`, not `Ensemble::accumulator`. Averaging ensembles
+    /// divide the final sum natively, so it must fit in i128.
+    pub fn eval_numeric<N: Numeric>(&self, features: &[N]) -> N {
+        assert!(features.len() >= self.num_features(),
+                "Expected at least {} features, got {}", self.num_features(), features.len());
+
+        let combiner = self.combiner();
+        let initial = N::from_scaled(combiner.initial(self.base_score()));
+        let sum = self.trees().iter().fold(initial, |y, tree| y.add(tree.root.eval_numeric(features)));
+        match combiner {
+            Combiner::Sum => sum,
+            Combiner::Average => {
+                let sum = sum.to_i128().expect("averaged tree sum exceeds i128");
+                N::from_scaled(combiner.finish_wide(self.base_score(), sum, self.num_trees()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builtin_model, Accumulator, BitSet, Tree, NUM_FEATURES};
+    use alloc::{vec, vec::Vec};
+
+    fn model() -> Ensemble {
+        let categories: BitSet = [2].into_iter().collect();
+        let trees = vec![
+            Tree::new(Node::split(0, -4, Node::Leaf(1 << 62), Node::Leaf(-7))),
+            Tree::new(Node::categorical(1, categories, 1, Node::Leaf(1 << 62), Node::Leaf(3))),
+        ];
+        Ensemble::new(trees, 1).with_base_score(5)
+    }
+
+    #[test]
+    fn test_integer_backends_match_eval() {
+        let model = model();
+        let wide = model.clone().with_accumulator(Accumulator::I128);
+        for features in [[-4i64, 3], [0, 2], [i64::MIN, i64::MAX]] {
+            assert_eq!(model.eval_numeric(&features), model.eval(&features));
+            assert_eq!(model.eval_numeric(&features.map(i128::from)), wide.eval(&features) as i128);
+        }
+        // Two 2^62 leaves saturate the i64 sum but not the i128 one
+        assert_eq!(model.eval_numeric(&[-5i64, 2]), i64::MAX);
+        assert_eq!(model.eval_numeric(&[-5i128, 2]), (1 << 63) + 5);
+
+        let builtin = builtin_model();
+        let features: Vec<i64> = (0..NUM_FEATURES as i64).map(|j| (j * 37 % 200 - 100) * 1_000_000_000).collect();
+        assert_eq!(builtin.eval_numeric(&features), builtin.eval(&features));
+        let forest = model.with_combiner(Combiner::Average);
+        assert_eq!(forest.eval_numeric(&[-5i128, 2]), (1 << 62) + 5);
+    }
+
+    #[cfg(feature = "bn254")]
+    #[test]
+    fn test_field_backends_match_eval() {
+        fn check<F: Numeric>() {
+            assert!(F::from_scaled(-3).le(F::from_scaled(2)));
+            assert!(!F::from_scaled(2).le(F::from_scaled(-3)));
+            assert!(F::from_scaled(-3).le(F::from_scaled(-3)));
+            assert_eq!(F::from_scaled(i64::MIN).to_i128(), Some(i64::MIN as i128));
+
+            let model = model().with_accumulator(Accumulator::I128);
+            for features in [[-4i64, 3], [0, 2], [i64::MIN, i64::MAX]] {
+                let embedded = features.map(F::from_scaled);
+                assert_eq!(model.eval_numeric(&embedded).to_i128(), Some(model.eval(&features) as i128));
+            }
+            // The field sum does not clamp: 2^63 + 5 is the exact sum of the trees
+            let features = [F::from_scaled(-5), F::from_scaled(2)];
+            assert_eq!(model.eval_numeric(&features).to_i128(), Some((1 << 63) + 5));
+        }
+        check::<ark_bn254::Fr>();
+        #[cfg(feature = "bls12-381")]
+        check::<ark_bls12_381::Fr>();
+    }
+}
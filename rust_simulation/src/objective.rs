@@ -0,0 +1,184 @@
+// Training objectives and inverse links
+// The trees of a boosted model add up to a margin; the training objective decides how that margin
+// becomes a prediction. Regression with squared error uses it as is, log-link objectives (Poisson,
+// Tweedie, Gamma) predict exp(margin) and binary:logistic predicts sigmoid(margin). The ensemble
+// keeps the margin for evaluation, traces and circuits, and `Ensemble::predict_response` applies
+// the objective's fixed-point inverse link (`fixed_exp`, `fixed_sigmoid`) after accumulation.
+
+use core::fmt;
+
+use crate::ensemble::Ensemble;
+use crate::explog::fixed_exp;
+use crate::fixed::rescale_round;
+use crate::sigmoid::fixed_sigmoid;
+use crate::PRECISION_MULTIPLIER;
+
+/// How a training objective maps the margin to the response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Link {
+    /// The margin is the prediction
+    Identity,
+    /// The prediction is exp(margin)
+    Log,
+    /// The prediction is sigmoid(margin)
+    Logit,
+}
+
+/// Objective a model was trained with (XGBoost naming)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Objective {
+    /// `reg:squarederror`: the margin is the prediction
+    #[default]
+    SquaredError,
+    /// `binary:logistic`: probability sigmoid(margin)
+    Logistic,
+    /// `binary:logitraw`: the raw logit, without the sigmoid
+    LogitRaw,
+    /// `count:poisson`: expected count exp(margin)
+    Poisson,
+    /// `reg:tweedie`: mean exp(margin)
+    Tweedie,
+    /// `reg:gamma`: mean exp(margin)
+    Gamma,
+}
+
+impl Objective {
+    /// Every objective, in the order of their binary format codes
+    pub const ALL: [Objective; 6] = [
+        Objective::SquaredError,
+        Objective::Logistic,
+        Objective::LogitRaw,
+        Objective::Poisson,
+        Objective::Tweedie,
+        Objective::Gamma,
+    ];
+
+    /// XGBoost name of the objective
+    pub fn name(self) -> &'static str {
+        match self {
+            Objective::SquaredError => "reg:squarederror",
+            Objective::Logistic => "binary:logistic",
+            Objective::LogitRaw => "binary:logitraw",
+            Objective::Poisson => "count:poisson",
+            Objective::Tweedie => "reg:tweedie",
+            Objective::Gamma => "reg:gamma",
+        }
+    }
+
+    /// Parse an XGBoost or LightGBM objective name
+    ///
+    /// # Arguments
+    /// * `name` - e.g. "reg:tweedie", "count:poisson", or LightGBM's "regression", "poisson",
+    ///   "tweedie", "gamma", "binary"
+    ///
+    /// # Returns
+    /// * `Option<Objective>` - The objective, or `None` for names without a supported link
+    pub fn from_name(name: &str) -> Option<Objective> {
+        match name {
+            "reg:squarederror" | "reg:linear" | "regression" | "regression_l2" | "l2" | "mse" => {
+                Some(Objective::SquaredError)
+            }
+            "binary:logistic" | "reg:logistic" | "binary" => Some(Objective::Logistic),
+            "binary:logitraw" => Some(Objective::LogitRaw),
+            "count:poisson" | "poisson" => Some(Objective::Poisson),
+            "reg:tweedie" | "tweedie" => Some(Objective::Tweedie),
+            "reg:gamma" | "gamma" => Some(Objective::Gamma),
+            _ => None,
+        }
+    }
+
+    /// Inverse link applied to the margin
+    pub fn link(self) -> Link {
+        match self {
+            Objective::SquaredError | Objective::LogitRaw => Link::Identity,
+            Objective::Logistic => Link::Logit,
+            Objective::Poisson | Objective::Tweedie | Objective::Gamma => Link::Log,
+        }
+    }
+
+    /// Turn a margin into a prediction
+    ///
+    /// # Arguments
+    /// * `margin` - Accumulated tree outputs (fixed point at `scale`)
+    /// * `scale` - Fixed-point scale of the margin and the prediction
+    ///
+    /// # Returns
+    /// * `i64` - Prediction at `scale`: the margin, `fixed_exp(margin)` or `fixed_sigmoid(margin)`
+    ///   (computed at 10^10 and rescaled for other scales)
+    pub fn inverse_link(self, margin: i64, scale: i64) -> i64 {
+        let function = match self.link() {
+            Link::Identity => return margin,
+            Link::Log => fixed_exp,
+            Link::Logit => fixed_sigmoid,
+        };
+        if scale == PRECISION_MULTIPLIER {
+            return function(margin);
+        }
+        let response = function(rescale_round(margin, scale, PRECISION_MULTIPLIER));
+        rescale_round(response, PRECISION_MULTIPLIER, scale)
+    }
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Ensemble {
+    /// Prediction on the response scale: the objective's inverse link applied to `eval`
+    ///
+    /// # Arguments
+    /// * `features` - Input feature vector (at the ensemble's scale)
+    ///
+    /// # Returns
+    /// * `i64` - Prediction at the ensemble's scale, e.g. exp(margin) for a Tweedie model
+    pub fn predict_response(&self, features: &[i64]) -> i64 {
+        self.objective().inverse_link(self.eval(features), self.scale())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Node, Tree};
+    use alloc::vec;
+
+    #[test]
+    fn test_inverse_links() {
+        let one = PRECISION_MULTIPLIER;
+        assert_eq!(Objective::SquaredError.inverse_link(-3 * one, one), -3 * one);
+        assert_eq!(Objective::LogitRaw.inverse_link(-3 * one, one), -3 * one);
+        assert_eq!(Objective::Poisson.inverse_link(one, one), 27182818285);
+        assert_eq!(Objective::Tweedie.inverse_link(0, one), one);
+        assert_eq!(Objective::Logistic.inverse_link(0, one), one / 2);
+        // At scale 1000 the margin and the prediction are rescaled around the 10^10 functions
+        assert_eq!(Objective::Gamma.inverse_link(1000, 1000), 2718);
+        assert_eq!(Objective::Gamma.inverse_link(-2000, 1000), 135);
+    }
+
+    #[test]
+    fn test_names_round_trip() {
+        for objective in Objective::ALL {
+            assert_eq!(Objective::from_name(objective.name()), Some(objective));
+        }
+        assert_eq!(Objective::from_name("tweedie"), Some(Objective::Tweedie));
+        assert_eq!(Objective::from_name("binary"), Some(Objective::Logistic));
+        assert_eq!(Objective::from_name("multi:softprob"), None);
+        assert_eq!(Objective::Tweedie.to_string(), "reg:tweedie");
+    }
+
+    #[test]
+    fn test_predict_response() {
+        let tree = Tree::new(Node::split(0, 0, Node::Leaf(-PRECISION_MULTIPLIER), Node::Leaf(PRECISION_MULTIPLIER)));
+        let model = Ensemble::new(vec![tree], PRECISION_MULTIPLIER).with_objective(Objective::Tweedie);
+        assert_eq!(model.eval(&[1]), PRECISION_MULTIPLIER);
+        assert_eq!(model.predict_response(&[1]), 27182818285);
+        assert_eq!(model.predict_response(&[0]), 3678794412);
+        let margin = model.clone().with_objective(Objective::SquaredError);
+        assert_eq!(margin.predict_response(&[0]), -PRECISION_MULTIPLIER);
+        let classifier = model.with_objective(Objective::Logistic);
+        assert_eq!(classifier.predict_response(&[1]), classifier.predict_proba(&[1]));
+    }
+}
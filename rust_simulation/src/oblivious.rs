@@ -0,0 +1,84 @@
+// Oblivious evaluation
+// Evaluates every node of every tree and combines the children with the arithmetic select a
+// circuit uses, `b * left + (1 - b) * right`, so the work done and the memory touched depend only
+// on the model and never on the input. This is the computation the R1CS and plonky2 backends
+// constrain, and a side-channel-resistant mode for deployments where timing must not leak the
+// path. The comparison is turned into 0/1 without branching; the compiler is not formally
+// constrained, so deployments with strict constant-time requirements should check the assembly.
+// It is `Node::eval_numeric` over i64, the evaluator shared with the field-element backends.
+
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+
+use crate::ensemble::{Ensemble, Node};
+
+impl Node {
+    /// Evaluate every node of the subtree and select the reached leaf arithmetically
+    pub fn eval_oblivious(&self, f: &[i64]) -> i64 {
+        self.eval_numeric(f)
+    }
+}
+
+impl Ensemble {
+    /// Evaluate the ensemble visiting all nodes, without input-dependent branches
+    ///
+    /// # Arguments
+    /// * `features` - Input feature vector (at the ensemble's scale)
+    ///
+    /// # Returns
+    /// * `i64` - Prediction, identical to `Ensemble::eval`
+    pub fn eval_oblivious(&self, features: &[i64]) -> i64 {
+        assert!(features.len() >= self.num_features(),
+                "Expected at least {} features, got {}", self.num_features(), features.len());
+
+        let outputs = self.trees().iter().map(|tree| tree.root.eval_oblivious(features));
+        self.accumulator().combine(self.combiner(), self.base_score(), outputs)
+    }
+}
+
+/// `xgboost_predict` through the oblivious evaluation of the bundled model
+///
+/// # Arguments
+/// * `features` - Input feature vector (scaled by 10^10)
+///
+/// # Returns
+/// * `i64` - Prediction result (scaled by 10^10), identical to `xgboost_predict`
+pub fn xgboost_predict_oblivious(features: &[i64]) -> i64 {
+    #[cfg(feature = "std")]
+    {
+        static BUILTIN: OnceLock<Ensemble> = OnceLock::new();
+        BUILTIN.get_or_init(crate::builtin_model).eval_oblivious(features)
+    }
+    // Without std there is no lazy static; parse the bundled dump on every call
+    #[cfg(not(feature = "std"))]
+    {
+        crate::builtin_model().eval_oblivious(features)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::Tree;
+    use crate::{xgboost_predict, NUM_FEATURES};
+    use alloc::{vec, vec::Vec};
+
+    #[test]
+    fn test_matches_branching_evaluation() {
+        for i in 0..32i64 {
+            let features: Vec<i64> = (0..NUM_FEATURES as i64)
+                .map(|j| ((i * 29 + j * 13) % 400) * 1_000_000_000 - 50_000_000_000)
+                .collect();
+            assert_eq!(xgboost_predict_oblivious(&features), xgboost_predict(&features));
+        }
+    }
+
+    #[test]
+    fn test_extreme_leaves_and_base_score() {
+        let root = Node::split(0, 0, Node::Leaf(i64::MIN), Node::split(1, 3, Node::Leaf(i64::MAX), Node::Leaf(-1)));
+        let model = Ensemble::new(vec![Tree::new(root)], 1).with_base_score(5);
+        for features in [[0, 0], [1, 3], [1, 4], [i64::MIN, i64::MAX]] {
+            assert_eq!(model.eval_oblivious(&features), model.eval(&features));
+        }
+    }
+}
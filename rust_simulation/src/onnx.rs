@@ -0,0 +1,553 @@
+// ONNX tree-ensemble import
+// Reads the `ai.onnx.ml` TreeEnsembleRegressor operator from an ONNX model (as exported by
+// skl2onnx or onnxmltools for scikit-learn, LightGBM and XGBoost) and converts it into the
+// crate's fixed-point `Ensemble`. Only the fields needed for the trees are decoded. Random
+// forests (aggregate_function AVERAGE, e.g. sklearn's RandomForestRegressor) import as
+// averaging ensembles. A LOGISTIC post transform imports as the `binary:logistic` objective.
+
+use alloc::{format, string::{String, ToString}, vec::Vec};
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::ensemble::{Combiner, Ensemble, Node, Tree};
+use crate::model::scale_dump_value;
+use crate::objective::Objective;
+use crate::protobuf::{push_doubles, push_floats, push_int64s, DecodeError, Reader, WireValue};
+use crate::PRECISION_MULTIPLIER;
+
+/// Operator converted by this module
+const TREE_ENSEMBLE_REGRESSOR: &str = "TreeEnsembleRegressor";
+
+/// TensorProto data types used for tree values
+const TENSOR_FLOAT: i64 = 1;
+const TENSOR_DOUBLE: i64 = 11;
+
+/// Error raised while importing an ONNX model
+#[derive(Debug)]
+pub enum OnnxError {
+    /// The model file could not be read
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// The protobuf encoding is invalid
+    Decode(DecodeError),
+    /// The graph has no TreeEnsembleRegressor node
+    MissingOperator,
+    /// An attribute is missing or inconsistent with the others
+    InvalidAttribute { name: String, message: String },
+    /// The operator uses a feature the fixed-point ensemble cannot express
+    Unsupported(String),
+}
+
+impl fmt::Display for OnnxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            OnnxError::Io(e) => write!(f, "failed to read ONNX model: {}", e),
+            OnnxError::Decode(e) => write!(f, "invalid ONNX protobuf: {}", e),
+            OnnxError::MissingOperator => {
+                write!(f, "ONNX graph has no {} node", TREE_ENSEMBLE_REGRESSOR)
+            }
+            OnnxError::InvalidAttribute { name, message } => {
+                write!(f, "invalid attribute '{}': {}", name, message)
+            }
+            OnnxError::Unsupported(what) => write!(f, "unsupported ONNX tree ensemble: {}", what),
+        }
+    }
+}
+
+impl core::error::Error for OnnxError {}
+
+impl From<DecodeError> for OnnxError {
+    fn from(e: DecodeError) -> Self {
+        OnnxError::Decode(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for OnnxError {
+    fn from(e: std::io::Error) -> Self {
+        OnnxError::Io(e)
+    }
+}
+
+/// Decoded AttributeProto (only the value kinds tree ensembles use)
+#[derive(Debug, Default)]
+struct Attribute {
+    name: String,
+    int: Option<i64>,
+    string: Option<String>,
+    floats: Vec<f64>,
+    ints: Vec<i64>,
+    strings: Vec<String>,
+    tensor: Option<Vec<f64>>,
+}
+
+fn utf8(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+fn expect_bytes<'a>(value: WireValue<'a>) -> Result<&'a [u8], DecodeError> {
+    value.bytes().ok_or_else(|| DecodeError {
+        offset: 0,
+        message: "expected a length-delimited field".to_string(),
+    })
+}
+
+/// Decode a TensorProto holding float or double values
+fn decode_tensor(buf: &[u8]) -> Result<Vec<f64>, DecodeError> {
+    let mut data_type = TENSOR_FLOAT;
+    let mut values = Vec::new();
+    let mut raw = None;
+
+    let mut reader = Reader::new(buf);
+    while let Some((field, value)) = reader.next_field()? {
+        match field {
+            2 => data_type = value.int64().unwrap_or(TENSOR_FLOAT),
+            4 => push_floats(value, &mut values)?,
+            9 => raw = Some(expect_bytes(value)?),
+            10 => push_doubles(value, &mut values)?,
+            _ => {}
+        }
+    }
+
+    // raw_data is little-endian element data whose width depends on the data type
+    if let Some(raw) = raw {
+        match data_type {
+            TENSOR_FLOAT => push_floats(WireValue::Bytes(raw), &mut values)?,
+            TENSOR_DOUBLE => push_doubles(WireValue::Bytes(raw), &mut values)?,
+            _ => {
+                return Err(DecodeError {
+                    offset: 0,
+                    message: format!("unsupported tensor data type {}", data_type),
+                })
+            }
+        }
+    }
+
+    Ok(values)
+}
+
+fn decode_attribute(buf: &[u8]) -> Result<Attribute, DecodeError> {
+    let mut attribute = Attribute::default();
+
+    let mut reader = Reader::new(buf);
+    while let Some((field, value)) = reader.next_field()? {
+        match field {
+            1 => attribute.name = utf8(expect_bytes(value)?),
+            3 => attribute.int = value.int64(),
+            4 => attribute.string = Some(utf8(expect_bytes(value)?)),
+            5 => attribute.tensor = Some(decode_tensor(expect_bytes(value)?)?),
+            7 => push_floats(value, &mut attribute.floats)?,
+            8 => push_int64s(value, &mut attribute.ints)?,
+            9 => attribute.strings.push(utf8(expect_bytes(value)?)),
+            _ => {}
+        }
+    }
+
+    Ok(attribute)
+}
+
+/// Attributes of the first TreeEnsembleRegressor node in the graph
+fn find_tree_ensemble(model: &[u8]) -> Result<Vec<Attribute>, OnnxError> {
+    let mut graph = None;
+    let mut reader = Reader::new(model);
+    while let Some((field, value)) = reader.next_field()? {
+        if field == 7 {
+            graph = Some(expect_bytes(value)?);
+        }
+    }
+    let graph = graph.ok_or(OnnxError::MissingOperator)?;
+
+    let mut reader = Reader::new(graph);
+    while let Some((field, value)) = reader.next_field()? {
+        if field != 1 {
+            continue;
+        }
+
+        let mut op_type = String::new();
+        let mut attributes = Vec::new();
+        let mut node = Reader::new(expect_bytes(value)?);
+        while let Some((field, value)) = node.next_field()? {
+            match field {
+                4 => op_type = utf8(expect_bytes(value)?),
+                5 => attributes.push(decode_attribute(expect_bytes(value)?)?),
+                _ => {}
+            }
+        }
+
+        if op_type == TREE_ENSEMBLE_REGRESSOR {
+            return Ok(attributes);
+        }
+    }
+
+    Err(OnnxError::MissingOperator)
+}
+
+/// Attribute lookup by name
+struct Attributes(Vec<Attribute>);
+
+impl Attributes {
+    fn get(&self, name: &str) -> Option<&Attribute> {
+        self.0.iter().find(|a| a.name == name)
+    }
+
+    fn ints(&self, name: &str) -> Result<&[i64], OnnxError> {
+        self.get(name).map(|a| a.ints.as_slice()).ok_or_else(|| missing(name))
+    }
+
+    fn strings(&self, name: &str) -> Result<&[String], OnnxError> {
+        self.get(name).map(|a| a.strings.as_slice()).ok_or_else(|| missing(name))
+    }
+
+    /// Float list, falling back to the `<name>_as_tensor` form used for double precision
+    fn floats(&self, name: &str) -> Result<&[f64], OnnxError> {
+        if let Some(attribute) = self.get(name) {
+            return Ok(&attribute.floats);
+        }
+        self.get(&format!("{}_as_tensor", name))
+            .and_then(|a| a.tensor.as_deref())
+            .ok_or_else(|| missing(name))
+    }
+
+    fn string(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(|a| a.string.as_deref())
+    }
+}
+
+fn missing(name: &str) -> OnnxError {
+    OnnxError::InvalidAttribute { name: name.to_string(), message: "missing".to_string() }
+}
+
+/// Comparison applied by a branch node, in ONNX `nodes_modes` terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Leaf,
+    Leq,
+    Lt,
+    Gte,
+    Gt,
+}
+
+fn parse_mode(mode: &str) -> Result<Mode, OnnxError> {
+    match mode {
+        "LEAF" => Ok(Mode::Leaf),
+        "BRANCH_LEQ" => Ok(Mode::Leq),
+        "BRANCH_LT" => Ok(Mode::Lt),
+        "BRANCH_GTE" => Ok(Mode::Gte),
+        "BRANCH_GT" => Ok(Mode::Gt),
+        other => Err(OnnxError::Unsupported(format!("node mode {}", other))),
+    }
+}
+
+/// One row of the flattened `nodes_*` attributes
+struct FlatNode {
+    feature: usize,
+    value: f64,
+    mode: Mode,
+    true_id: i64,
+    false_id: i64,
+}
+
+/// Build the subtree rooted at `id`, turning every comparison into a `<=` split
+fn build_node(
+    tree: i64,
+    id: i64,
+    nodes: &BTreeMap<(i64, i64), FlatNode>,
+    leaves: &BTreeMap<(i64, i64), f64>,
+    scale: i64,
+    depth: usize,
+) -> Result<Node, OnnxError> {
+    let invalid = |message: String| OnnxError::InvalidAttribute { name: "nodes_nodeids".to_string(), message };
+    if depth > nodes.len() {
+        return Err(invalid(format!("tree {} contains a cycle", tree)));
+    }
+    let node = nodes
+        .get(&(tree, id))
+        .ok_or_else(|| invalid(format!("tree {} references unknown node {}", tree, id)))?;
+
+    if node.mode == Mode::Leaf {
+        let weight = leaves.get(&(tree, id)).copied().unwrap_or(0.0);
+        return Ok(Node::Leaf(scale_dump_value(weight, scale)));
+    }
+
+    let threshold = scale_dump_value(node.value, scale);
+    let true_branch = build_node(tree, node.true_id, nodes, leaves, scale, depth + 1)?;
+    let false_branch = build_node(tree, node.false_id, nodes, leaves, scale, depth + 1)?;
+
+    // On integers, x < t is x <= t - 1 and x > t is !(x <= t)
+    Ok(match node.mode {
+        Mode::Leq => Node::split(node.feature, threshold, true_branch, false_branch),
+        Mode::Lt => Node::split(node.feature, threshold - 1, true_branch, false_branch),
+        Mode::Gt => Node::split(node.feature, threshold, false_branch, true_branch),
+        Mode::Gte => Node::split(node.feature, threshold - 1, false_branch, true_branch),
+        Mode::Leaf => unreachable!(),
+    })
+}
+
+impl Ensemble {
+    /// Import the TreeEnsembleRegressor of a serialized ONNX model
+    ///
+    /// # Arguments
+    /// * `model` - Bytes of a `.onnx` file
+    ///
+    /// # Returns
+    /// * `Result<Ensemble, OnnxError>` - Ensemble with thresholds and leaves scaled by 10^10
+    pub fn from_onnx(model: &[u8]) -> Result<Ensemble, OnnxError> {
+        Ensemble::from_onnx_with_scale(model, PRECISION_MULTIPLIER)
+    }
+
+    /// Import an ONNX model file
+    #[cfg(feature = "std")]
+    pub fn from_onnx_file<P: AsRef<Path>>(path: P) -> Result<Ensemble, OnnxError> {
+        Ensemble::from_onnx(&std::fs::read(path)?)
+    }
+
+    /// Import the TreeEnsembleRegressor of an ONNX model at a custom fixed-point scale
+    ///
+    /// Branch modes LEQ/LT/GTE/GT are rewritten as `<=` splits; the base value becomes the
+    /// ensemble's base score. Only single-target SUM or AVERAGE ensembles with post transform
+    /// NONE or LOGISTIC are accepted; AVERAGE selects `Combiner::Average` and LOGISTIC
+    /// `Objective::Logistic`.
+    pub fn from_onnx_with_scale(model: &[u8], scale: i64) -> Result<Ensemble, OnnxError> {
+        let attributes = Attributes(find_tree_ensemble(model)?);
+
+        let combiner = match attributes.string("aggregate_function") {
+            None | Some("SUM") => Combiner::Sum,
+            Some("AVERAGE") => Combiner::Average,
+            Some(aggregate) => return Err(OnnxError::Unsupported(format!("aggregate_function {}", aggregate))),
+        };
+        let objective = match attributes.string("post_transform") {
+            None | Some("NONE") => Objective::SquaredError,
+            Some("LOGISTIC") => Objective::Logistic,
+            Some(transform) => return Err(OnnxError::Unsupported(format!("post_transform {}", transform))),
+        };
+        if let Some(n_targets) = attributes.get("n_targets").and_then(|a| a.int) {
+            if n_targets != 1 {
+                return Err(OnnxError::Unsupported(format!("{} targets", n_targets)));
+            }
+        }
+
+        let tree_ids = attributes.ints("nodes_treeids")?;
+        let node_ids = attributes.ints("nodes_nodeids")?;
+        let feature_ids = attributes.ints("nodes_featureids")?;
+        let values = attributes.floats("nodes_values")?;
+        let modes = attributes.strings("nodes_modes")?;
+        let true_ids = attributes.ints("nodes_truenodeids")?;
+        let false_ids = attributes.ints("nodes_falsenodeids")?;
+
+        let count = tree_ids.len();
+        for (name, len) in [
+            ("nodes_nodeids", node_ids.len()),
+            ("nodes_featureids", feature_ids.len()),
+            ("nodes_values", values.len()),
+            ("nodes_modes", modes.len()),
+            ("nodes_truenodeids", true_ids.len()),
+            ("nodes_falsenodeids", false_ids.len()),
+        ] {
+            if len != count {
+                return Err(OnnxError::InvalidAttribute {
+                    name: name.to_string(),
+                    message: format!("has {} entries, nodes_treeids has {}", len, count),
+                });
+            }
+        }
+
+        let mut nodes = BTreeMap::new();
+        let mut children = BTreeSet::new();
+        let mut tree_order = Vec::new();
+        for i in 0..count {
+            let mode = parse_mode(&modes[i])?;
+            let feature = usize::try_from(feature_ids[i]).map_err(|_| OnnxError::InvalidAttribute {
+                name: "nodes_featureids".to_string(),
+                message: format!("negative feature id {}", feature_ids[i]),
+            })?;
+            if !tree_order.contains(&tree_ids[i]) {
+                tree_order.push(tree_ids[i]);
+            }
+            if mode != Mode::Leaf {
+                children.insert((tree_ids[i], true_ids[i]));
+                children.insert((tree_ids[i], false_ids[i]));
+            }
+            nodes.insert(
+                (tree_ids[i], node_ids[i]),
+                FlatNode { feature, value: values[i], mode, true_id: true_ids[i], false_id: false_ids[i] },
+            );
+        }
+
+        let target_tree_ids = attributes.ints("target_treeids")?;
+        let target_node_ids = attributes.ints("target_nodeids")?;
+        let target_weights = attributes.floats("target_weights")?;
+        if target_node_ids.len() != target_tree_ids.len() || target_weights.len() != target_tree_ids.len() {
+            return Err(OnnxError::InvalidAttribute {
+                name: "target_weights".to_string(),
+                message: "target_* attributes have different lengths".to_string(),
+            });
+        }
+        let mut leaves = BTreeMap::new();
+        for ((&tree, &node), &weight) in target_tree_ids.iter().zip(target_node_ids).zip(target_weights) {
+            *leaves.entry((tree, node)).or_insert(0.0) += weight;
+        }
+
+        let mut trees = Vec::with_capacity(tree_order.len());
+        for &tree in &tree_order {
+            // The root is the only node of the tree that no branch points to
+            let root = (0..count)
+                .find(|&i| tree_ids[i] == tree && !children.contains(&(tree, node_ids[i])))
+                .ok_or_else(|| OnnxError::InvalidAttribute {
+                    name: "nodes_nodeids".to_string(),
+                    message: format!("tree {} has no root", tree),
+                })?;
+            trees.push(Tree::new(build_node(tree, node_ids[root], &nodes, &leaves, scale, 0)?));
+        }
+
+        let base = attributes.floats("base_values").ok().and_then(|b| b.first().copied()).unwrap_or(0.0);
+        Ok(Ensemble::new(trees, scale)
+            .with_base_score(scale_dump_value(base, scale))
+            .with_combiner(combiner)
+            .with_objective(objective))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protobuf::writer;
+
+    fn attr_ints(name: &str, values: &[i64]) -> Vec<u8> {
+        let mut a = Vec::new();
+        writer::bytes(&mut a, 1, name.as_bytes());
+        writer::packed_int64s(&mut a, 8, values);
+        a
+    }
+
+    fn attr_floats(name: &str, values: &[f32]) -> Vec<u8> {
+        let mut a = Vec::new();
+        writer::bytes(&mut a, 1, name.as_bytes());
+        writer::packed_floats(&mut a, 7, values);
+        a
+    }
+
+    fn attr_strings(name: &str, values: &[&str]) -> Vec<u8> {
+        let mut a = Vec::new();
+        writer::bytes(&mut a, 1, name.as_bytes());
+        values.iter().for_each(|v| writer::bytes(&mut a, 9, v.as_bytes()));
+        a
+    }
+
+    fn attr_string(name: &str, value: &str) -> Vec<u8> {
+        let mut a = Vec::new();
+        writer::bytes(&mut a, 1, name.as_bytes());
+        writer::bytes(&mut a, 4, value.as_bytes());
+        a
+    }
+
+    /// ModelProto { graph { node { op_type, attribute* } } }
+    fn onnx_model(op_type: &str, attributes: &[Vec<u8>]) -> Vec<u8> {
+        let mut node = Vec::new();
+        writer::bytes(&mut node, 1, b"X");
+        writer::bytes(&mut node, 4, op_type.as_bytes());
+        attributes.iter().for_each(|a| writer::bytes(&mut node, 5, a));
+
+        let mut graph = Vec::new();
+        writer::bytes(&mut graph, 1, &node);
+        let mut model = Vec::new();
+        writer::int64(&mut model, 1, 8);
+        writer::bytes(&mut model, 7, &graph);
+        model
+    }
+
+    /// Tree 0: x0 < 0.5 ? (x1 >= 2 ? 1.0 : 2.0) : 3.0, tree 1: x1 <= 0.25 ? -0.5 : 0.5, base 0.125
+    fn sample_attributes(mode0: &str) -> Vec<Vec<u8>> {
+        vec![
+            attr_ints("nodes_treeids", &[0, 0, 0, 0, 0, 1, 1, 1]),
+            attr_ints("nodes_nodeids", &[0, 1, 2, 3, 4, 0, 1, 2]),
+            attr_ints("nodes_featureids", &[0, 1, 0, 0, 0, 1, 0, 0]),
+            attr_floats("nodes_values", &[0.5, 2.0, 0.0, 0.0, 0.0, 0.25, 0.0, 0.0]),
+            attr_strings(
+                "nodes_modes",
+                &[mode0, "BRANCH_GTE", "LEAF", "LEAF", "LEAF", "BRANCH_LEQ", "LEAF", "LEAF"],
+            ),
+            attr_ints("nodes_truenodeids", &[1, 3, 0, 0, 0, 1, 0, 0]),
+            attr_ints("nodes_falsenodeids", &[2, 4, 0, 0, 0, 2, 0, 0]),
+            attr_ints("target_treeids", &[0, 0, 0, 1, 1]),
+            attr_ints("target_nodeids", &[2, 3, 4, 1, 2]),
+            attr_ints("target_ids", &[0, 0, 0, 0, 0]),
+            attr_floats("target_weights", &[3.0, 1.0, 2.0, -0.5, 0.5]),
+            attr_floats("base_values", &[0.125]),
+            attr_string("aggregate_function", "SUM"),
+            attr_string("post_transform", "NONE"),
+        ]
+    }
+
+    fn fp(x: f64) -> i64 {
+        crate::to_fixed_point(x)
+    }
+
+    #[test]
+    fn test_import_tree_ensemble_regressor() {
+        let ensemble = Ensemble::from_onnx(&onnx_model("TreeEnsembleRegressor", &sample_attributes("BRANCH_LT"))).unwrap();
+        assert_eq!(ensemble.num_trees(), 2);
+        assert_eq!(ensemble.num_features(), 2);
+        assert_eq!(ensemble.base_score(), fp(0.125));
+
+        // x0 < 0.5 and x1 >= 2 -> 1.0, plus x1 > 0.25 -> 0.5, plus base
+        assert_eq!(ensemble.eval(&[fp(0.4999), fp(2.0)]), fp(1.625));
+        assert_eq!(ensemble.eval(&[fp(0.4999), fp(1.9999)]), fp(2.625));
+        // BRANCH_LT is strict: exactly 0.5 goes to the false branch
+        assert_eq!(ensemble.eval(&[fp(0.5), fp(0.25)]), fp(2.625));
+
+        // A random forest averages the same trees: (1.0 + 0.5) / 2 plus base
+        let mut averaged = sample_attributes("BRANCH_LT");
+        averaged[12] = attr_string("aggregate_function", "AVERAGE");
+        let forest = Ensemble::from_onnx(&onnx_model("TreeEnsembleRegressor", &averaged)).unwrap();
+        assert_eq!(forest.combiner(), Combiner::Average);
+        assert_eq!(forest.eval(&[fp(0.4999), fp(2.0)]), fp(0.875));
+
+        // A logistic post transform keeps the margin and records the objective
+        let mut logistic = sample_attributes("BRANCH_LT");
+        logistic[13] = attr_string("post_transform", "LOGISTIC");
+        let classifier = Ensemble::from_onnx(&onnx_model("TreeEnsembleRegressor", &logistic)).unwrap();
+        assert_eq!(classifier.objective(), Objective::Logistic);
+        assert_eq!(classifier.eval(&[fp(0.4999), fp(2.0)]), fp(1.625));
+        assert_eq!(classifier.predict_response(&[fp(0.4999), fp(2.0)]), crate::fixed_sigmoid(fp(1.625)));
+    }
+
+    #[test]
+    fn test_branch_modes_are_rewritten() {
+        let leq = Ensemble::from_onnx(&onnx_model("TreeEnsembleRegressor", &sample_attributes("BRANCH_LEQ"))).unwrap();
+        let gt = Ensemble::from_onnx(&onnx_model("TreeEnsembleRegressor", &sample_attributes("BRANCH_GT"))).unwrap();
+
+        assert_eq!(leq.eval(&[fp(0.5), fp(3.0)]), fp(1.625));
+        // GT swaps the branches of LEQ at the same threshold
+        assert_eq!(gt.eval(&[fp(0.5), fp(3.0)]), fp(3.625));
+        assert_eq!(gt.eval(&[fp(0.5000000001), fp(3.0)]), fp(1.625));
+    }
+
+    #[test]
+    fn test_unsupported_models_are_rejected() {
+        assert!(matches!(
+            Ensemble::from_onnx(&onnx_model("TreeEnsembleClassifier", &sample_attributes("BRANCH_LEQ"))),
+            Err(OnnxError::MissingOperator)
+        ));
+        assert!(matches!(
+            Ensemble::from_onnx(&onnx_model("TreeEnsembleRegressor", &sample_attributes("BRANCH_EQ"))),
+            Err(OnnxError::Unsupported(_))
+        ));
+
+        let mut maximum = sample_attributes("BRANCH_LEQ");
+        maximum[12] = attr_string("aggregate_function", "MAX");
+        assert!(matches!(
+            Ensemble::from_onnx(&onnx_model("TreeEnsembleRegressor", &maximum)),
+            Err(OnnxError::Unsupported(_))
+        ));
+        let mut softmax = sample_attributes("BRANCH_LEQ");
+        softmax[13] = attr_string("post_transform", "SOFTMAX");
+        assert!(matches!(
+            Ensemble::from_onnx(&onnx_model("TreeEnsembleRegressor", &softmax)),
+            Err(OnnxError::Unsupported(_))
+        ));
+        assert!(matches!(Ensemble::from_onnx(&[0x3a, 0x05]), Err(OnnxError::Decode(_))));
+    }
+}
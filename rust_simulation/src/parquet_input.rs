@@ -0,0 +1,192 @@
+// Parquet input
+// `predict evaluate` reads labelled Parquet files as well as CSV. Each row becomes the line a CSV
+// export of it would hold, so it goes through the same column checks, exact decimal scaling and
+// schema validation as a CSV row. Integer and decimal columns are written exactly, float columns
+// as their shortest round-trip decimal, and nulls as empty fields (a missing feature, or no float
+// reference prediction). Columns of any other type are refused.
+
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+use parquet::data_type::Decimal;
+use parquet::errors::ParquetError;
+use parquet::file::reader::SerializedFileReader;
+use parquet::record::Field;
+
+/// Error reading a Parquet file as feature rows
+#[derive(Debug)]
+pub enum ParquetInputError {
+    /// The file cannot be opened or decoded
+    Parquet(ParquetError),
+    /// A value is not a number or null
+    Unsupported {
+        /// 1-based row number
+        row: usize,
+        column: String,
+        /// Type of the value
+        kind: &'static str,
+    },
+}
+
+impl fmt::Display for ParquetInputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParquetInputError::Parquet(e) => write!(f, "{}", e),
+            ParquetInputError::Unsupported { row, column, kind } => {
+                write!(f, "row {}: column '{}' holds a {}, not a number", row, column, kind)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParquetInputError {}
+
+impl From<ParquetError> for ParquetInputError {
+    fn from(e: ParquetError) -> ParquetInputError {
+        ParquetInputError::Parquet(e)
+    }
+}
+
+/// Read every row of a Parquet file as a comma-separated line, columns in file order
+///
+/// # Arguments
+/// * `path` - Parquet file
+///
+/// # Returns
+/// * `Result<Vec<String>, ParquetInputError>` - One line per row, row `n` at index `n - 1`
+pub fn read_parquet_rows(path: &Path) -> Result<Vec<String>, ParquetInputError> {
+    let file = File::open(path).map_err(|e| ParquetError::External(Box::new(e)))?;
+    let mut lines = Vec::new();
+    for (index, row) in SerializedFileReader::new(file)?.into_iter().enumerate() {
+        let row = row?;
+        let mut fields = Vec::with_capacity(row.len());
+        for (column, value) in row.get_column_iter() {
+            let text = field_text(value).map_err(|kind| ParquetInputError::Unsupported {
+                row: index + 1,
+                column: column.clone(),
+                kind,
+            })?;
+            fields.push(text);
+        }
+        lines.push(fields.join(","));
+    }
+    Ok(lines)
+}
+
+/// CSV text of one value, or the name of its type if it is not a number
+fn field_text(value: &Field) -> Result<String, &'static str> {
+    Ok(match value {
+        Field::Null => String::new(),
+        Field::Byte(v) => v.to_string(),
+        Field::Short(v) => v.to_string(),
+        Field::Int(v) => v.to_string(),
+        Field::Long(v) => v.to_string(),
+        Field::UByte(v) => v.to_string(),
+        Field::UShort(v) => v.to_string(),
+        Field::UInt(v) => v.to_string(),
+        Field::ULong(v) => v.to_string(),
+        // f32 values print at their own precision, so 0.1f32 reads as 0.1
+        Field::Float16(v) => f32::from(*v).to_string(),
+        Field::Float(v) => v.to_string(),
+        Field::Double(v) => v.to_string(),
+        Field::Decimal(v) => decimal_text(v).ok_or("decimal wider than 128 bits")?,
+        Field::Bool(_) => return Err("boolean"),
+        Field::Str(_) => return Err("string"),
+        Field::Bytes(_) => return Err("byte array"),
+        Field::Date(_) => return Err("date"),
+        Field::TimestampMillis(_) | Field::TimestampMicros(_) => return Err("timestamp"),
+        Field::Group(_) => return Err("group"),
+        Field::ListInternal(_) => return Err("list"),
+        Field::MapInternal(_) => return Err("map"),
+    })
+}
+
+/// Exact decimal text of a Parquet decimal (a big-endian two's complement integer and a scale)
+fn decimal_text(decimal: &Decimal) -> Option<String> {
+    let bytes = decimal.data();
+    if bytes.len() > 16 {
+        return None;
+    }
+    let fill = if bytes.first().is_some_and(|&b| b & 0x80 != 0) { 0xff } else { 0 };
+    let mut word = [fill; 16];
+    word[16 - bytes.len()..].copy_from_slice(bytes);
+    let unscaled = i128::from_be_bytes(word);
+
+    let scale = decimal.scale();
+    let digits = unscaled.unsigned_abs().to_string();
+    let sign = if unscaled < 0 { "-" } else { "" };
+    if scale <= 0 {
+        let zeros = if unscaled == 0 { 0 } else { scale.unsigned_abs() as usize };
+        return Some(format!("{}{}{}", sign, digits, "0".repeat(zeros)));
+    }
+    let scale = scale as usize;
+    let digits = format!("{:0>width$}", digits, width = scale + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - scale);
+    Some(format!("{}{}.{}", sign, whole, fraction))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, FloatType, Int32Type, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+    use std::sync::Arc;
+
+    /// Write one row group of two rows; `label` is a FLOAT column, or a UTF8 one if `text_label`
+    fn write_file(path: &Path, text_label: bool) {
+        let label = if text_label { "REQUIRED BYTE_ARRAY label (UTF8);" } else { "REQUIRED FLOAT label;" };
+        let message = format!(
+            "message rows {{ OPTIONAL DOUBLE f0; REQUIRED INT64 f1; REQUIRED INT32 f2 (DECIMAL(5, 2)); {} }}",
+            label
+        );
+        let schema = Arc::new(parse_message_type(&message).unwrap());
+        let properties = Arc::new(WriterProperties::builder().build());
+        let mut writer = SerializedFileWriter::new(File::create(path).unwrap(), schema, properties).unwrap();
+        let mut group = writer.next_row_group().unwrap();
+        let mut column = 0;
+        while let Some(mut writer) = group.next_column().unwrap() {
+            match column {
+                0 => writer.typed::<DoubleType>().write_batch(&[0.1], Some(&[1, 0]), None),
+                1 => writer.typed::<Int64Type>().write_batch(&[3, -4], None, None),
+                2 => writer.typed::<Int32Type>().write_batch(&[12345, -5], None, None),
+                _ if text_label => {
+                    writer.typed::<ByteArrayType>().write_batch(&[ByteArray::from("1.5"), ByteArray::from("0")], None, None)
+                }
+                _ => writer.typed::<FloatType>().write_batch(&[1.5, 0.1], None, None),
+            }
+            .unwrap();
+            writer.close().unwrap();
+            column += 1;
+        }
+        group.close().unwrap();
+        writer.close().unwrap();
+    }
+
+    #[test]
+    fn test_rows_read_as_csv_lines() {
+        let path = std::env::temp_dir().join(format!("parquet_rows_{}.parquet", std::process::id()));
+        write_file(&path, false);
+        // Nulls are empty fields, decimals exact and floats at their own precision
+        assert_eq!(read_parquet_rows(&path).unwrap(), vec!["0.1,3,123.45,1.5", ",-4,-0.05,0.1"]);
+
+        write_file(&path, true);
+        let error = read_parquet_rows(&path).unwrap_err().to_string();
+        assert_eq!(error, "row 1: column 'label' holds a string, not a number");
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(read_parquet_rows(&path), Err(ParquetInputError::Parquet(_))));
+    }
+
+    #[test]
+    fn test_decimal_text() {
+        let decimal = |value: i32, scale: i32| Decimal::from_i32(value, 9, scale);
+        assert_eq!(decimal_text(&decimal(12345, 2)).unwrap(), "123.45");
+        assert_eq!(decimal_text(&decimal(-5, 3)).unwrap(), "-0.005");
+        assert_eq!(decimal_text(&decimal(7, -2)).unwrap(), "700");
+        assert_eq!(decimal_text(&decimal(0, -2)).unwrap(), "0");
+        assert_eq!(decimal_text(&Decimal::from_i64(i64::MIN, 19, 0)).unwrap(), i64::MIN.to_string());
+    }
+}
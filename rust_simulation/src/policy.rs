@@ -0,0 +1,229 @@
+// Public-input policy
+// Which values of a prediction a proof reveals depends on the deployment: a weather service may
+// publish its predictions but keep the station readings private, an auditor may need the model
+// hash but not the prediction. `ProofPolicy` declares the visibility of each value once, can be
+// loaded from a JSON config, and the witness exports split their values into a public and a
+// private section accordingly. A policy that makes the trace public must also make the features
+// and the prediction public, since the trace contains both.
+
+use alloc::{string::String, string::ToString, vec::Vec};
+use core::fmt;
+
+use crate::json::{self, JsonError, JsonValue};
+
+/// Visibility of a value in the proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// Public input, known to the verifier
+    Public,
+    /// Witness, known only to the prover
+    Private,
+}
+
+impl Visibility {
+    /// Name used in policy configs
+    pub fn name(self) -> &'static str {
+        match self {
+            Visibility::Public => "public",
+            Visibility::Private => "private",
+        }
+    }
+}
+
+/// Value a proof can reveal or hide
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofValue {
+    /// Input feature vector
+    Features,
+    /// Model output
+    Prediction,
+    /// Poseidon hash of the features
+    FeaturesHash,
+    /// SHA-256 content hash of the model
+    ModelHash,
+    /// Execution trace: comparisons, paths and accumulators
+    Trace,
+}
+
+impl ProofValue {
+    /// Every value, in export order
+    pub const ALL: [ProofValue; 5] =
+        [ProofValue::Features, ProofValue::Prediction, ProofValue::FeaturesHash, ProofValue::ModelHash, ProofValue::Trace];
+
+    /// Name used in policy configs and exports
+    pub fn name(self) -> &'static str {
+        match self {
+            ProofValue::Features => "features",
+            ProofValue::Prediction => "prediction",
+            ProofValue::FeaturesHash => "features_hash",
+            ProofValue::ModelHash => "model_hash",
+            ProofValue::Trace => "trace",
+        }
+    }
+}
+
+/// Error building or loading a proof policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyError {
+    /// The config is not valid JSON
+    Json(JsonError),
+    /// The config is not an object of value name -> "public" / "private"
+    InvalidConfig(String),
+    /// A value a public value reveals is declared private
+    Leak { public: ProofValue, private: ProofValue },
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyError::Json(e) => write!(f, "invalid policy JSON: {}", e),
+            PolicyError::InvalidConfig(message) => write!(f, "invalid policy: {}", message),
+            PolicyError::Leak { public, private } => {
+                write!(f, "public {} reveals private {}", public.name(), private.name())
+            }
+        }
+    }
+}
+
+impl core::error::Error for PolicyError {}
+
+impl From<JsonError> for PolicyError {
+    fn from(e: JsonError) -> Self {
+        PolicyError::Json(e)
+    }
+}
+
+/// Visibility of every value of a prediction in its proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofPolicy {
+    visibility: [Visibility; 5],
+}
+
+impl Default for ProofPolicy {
+    /// Private features and trace; public prediction, features hash and model hash
+    fn default() -> Self {
+        ProofPolicy {
+            visibility: [
+                Visibility::Private,
+                Visibility::Public,
+                Visibility::Public,
+                Visibility::Public,
+                Visibility::Private,
+            ],
+        }
+    }
+}
+
+impl ProofPolicy {
+    /// Set the visibility of one value
+    pub fn with(mut self, value: ProofValue, visibility: Visibility) -> Self {
+        self.visibility[value as usize] = visibility;
+        self
+    }
+
+    /// Visibility of a value
+    pub fn visibility(&self, value: ProofValue) -> Visibility {
+        self.visibility[value as usize]
+    }
+
+    /// Whether a value is a public input
+    pub fn is_public(&self, value: ProofValue) -> bool {
+        self.visibility(value) == Visibility::Public
+    }
+
+    /// Public values, in export order
+    pub fn public_values(&self) -> impl Iterator<Item = ProofValue> + '_ {
+        ProofValue::ALL.into_iter().filter(|&value| self.is_public(value))
+    }
+
+    /// Check that no public value reveals a private one (the trace holds the feature values
+    /// and determines the prediction)
+    pub fn validate(&self) -> Result<(), PolicyError> {
+        if self.is_public(ProofValue::Trace) {
+            for private in [ProofValue::Features, ProofValue::Prediction] {
+                if !self.is_public(private) {
+                    return Err(PolicyError::Leak { public: ProofValue::Trace, private });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Load a policy from a JSON config such as `{"features": "private", "prediction": "public"}`
+    ///
+    /// # Arguments
+    /// * `config` - Object of value name -> visibility; values not listed keep the default
+    ///
+    /// # Returns
+    /// * `Result<ProofPolicy, PolicyError>` - Validated policy, or the first problem found
+    pub fn from_json(config: &str) -> Result<ProofPolicy, PolicyError> {
+        let config = json::parse(config)?;
+        let entries = config
+            .as_object()
+            .ok_or_else(|| PolicyError::InvalidConfig("expected an object".to_string()))?;
+
+        let mut policy = ProofPolicy::default();
+        for (name, visibility) in entries {
+            let value = ProofValue::ALL
+                .into_iter()
+                .find(|value| value.name() == name)
+                .ok_or_else(|| PolicyError::InvalidConfig(alloc::format!("unknown value `{}`", name)))?;
+            let visibility = match visibility.as_str() {
+                Some("public") => Visibility::Public,
+                Some("private") => Visibility::Private,
+                _ => {
+                    return Err(PolicyError::InvalidConfig(alloc::format!(
+                        "`{}` must be \"public\" or \"private\"",
+                        name
+                    )))
+                }
+            };
+            policy = policy.with(value, visibility);
+        }
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    /// JSON config form, listing every value
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::Object(
+            ProofValue::ALL
+                .into_iter()
+                .map(|value| (value.name().to_string(), JsonValue::String(self.visibility(value).name().to_string())))
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy() {
+        let policy = ProofPolicy::default();
+        let public: Vec<ProofValue> = policy.public_values().collect();
+        assert_eq!(public, [ProofValue::Prediction, ProofValue::FeaturesHash, ProofValue::ModelHash]);
+        assert!(policy.validate().is_ok());
+        assert_eq!(
+            policy.to_json().to_string(),
+            r#"{"features":"private","prediction":"public","features_hash":"public","model_hash":"public","trace":"private"}"#
+        );
+        assert_eq!(ProofPolicy::from_json(&policy.to_json().to_string()), Ok(policy));
+    }
+
+    #[test]
+    fn test_policy_config() {
+        let policy = ProofPolicy::from_json(r#"{"prediction": "private", "model_hash": "private"}"#).unwrap();
+        assert!(!policy.is_public(ProofValue::Prediction));
+        assert!(policy.is_public(ProofValue::FeaturesHash));
+        assert_eq!(policy.public_values().count(), 1);
+
+        let error = ProofPolicy::from_json(r#"{"trace": "public", "features": "public", "prediction": "private"}"#).unwrap_err();
+        assert_eq!(error, PolicyError::Leak { public: ProofValue::Trace, private: ProofValue::Prediction });
+        assert_eq!(error.to_string(), "public trace reveals private prediction");
+        assert!(matches!(ProofPolicy::from_json(r#"{"weights": "public"}"#), Err(PolicyError::InvalidConfig(_))));
+        assert!(matches!(ProofPolicy::from_json(r#"{"features": true}"#), Err(PolicyError::InvalidConfig(_))));
+        assert!(matches!(ProofPolicy::from_json("[1"), Err(PolicyError::Json(_))));
+    }
+}
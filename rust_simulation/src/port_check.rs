@@ -0,0 +1,254 @@
+// Port verification
+// Walks an XGBoost JSON dump and a fixed-point ensemble side by side and checks that the port is
+// the dump: the same tree count and shape, the same feature at every split, every threshold and
+// leaf within a rounding tolerance of the dump value times the port's scale, and the same
+// category sets. Unlike `QuantizationReport`, which stops at the first structural difference,
+// every mismatch is collected with the path that leads to it, so a model produced by another
+// converter (or edited by hand) can be audited in one run.
+
+use alloc::{string::ToString, vec, vec::Vec};
+use core::fmt;
+
+use crate::ensemble::{BitSet, Ensemble, Node};
+use crate::model::{parse_float_trees, FloatNode, ModelError};
+
+/// Default tolerance in scaled units: what rounding to nearest can move a value
+pub const PORT_TOLERANCE: f64 = 0.5;
+
+/// What differs at a node
+#[derive(Debug, Clone, PartialEq)]
+pub enum MismatchKind {
+    /// The tree exists in only one of the models
+    MissingTree { in_port: bool },
+    /// One model has a leaf, a threshold split or a categorical split where the other has another kind
+    NodeKind { dump: &'static str, port: &'static str },
+    /// The split tests a different feature
+    Feature { dump: usize, port: usize },
+    /// The threshold is further than the tolerance from the scaled dump threshold
+    Threshold { dump: f64, port: i64 },
+    /// The leaf is further than the tolerance from the scaled dump leaf
+    Leaf { dump: f64, port: i64 },
+    /// The categorical split has a different category set
+    Categories { dump: BitSet, port: BitSet },
+}
+
+/// A node where the port differs from the dump
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortMismatch {
+    pub tree: usize,
+    /// Branches from the root to the node (true: the "yes" child)
+    pub path: Vec<bool>,
+    pub kind: MismatchKind,
+}
+
+impl fmt::Display for PortMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tree {} at ", self.tree)?;
+        if self.path.is_empty() {
+            write!(f, "root")?;
+        }
+        for (depth, &yes) in self.path.iter().enumerate() {
+            write!(f, "{}{}", if depth > 0 { "/" } else { "" }, if yes { "yes" } else { "no" })?;
+        }
+        match &self.kind {
+            MismatchKind::MissingTree { in_port: true } => write!(f, ": tree only in the port"),
+            MismatchKind::MissingTree { in_port: false } => write!(f, ": tree missing from the port"),
+            MismatchKind::NodeKind { dump, port } => write!(f, ": dump has a {}, port has a {}", dump, port),
+            MismatchKind::Feature { dump, port } => write!(f, ": dump splits on f{}, port on f{}", dump, port),
+            MismatchKind::Threshold { dump, port } => write!(f, ": dump threshold {}, port threshold {}", dump, port),
+            MismatchKind::Leaf { dump, port } => write!(f, ": dump leaf {}, port leaf {}", dump, port),
+            MismatchKind::Categories { dump, port } => {
+                let list = |set: &BitSet| set.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(", ");
+                write!(f, ": dump categories {{{}}}, port categories {{{}}}", list(dump), list(port))
+            }
+        }
+    }
+}
+
+/// Result of comparing a port with its dump
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortCheck {
+    /// Scale of the port
+    pub scale: i64,
+    /// Largest accepted |port value - dump value * scale|
+    pub tolerance: f64,
+    /// Node pairs compared
+    pub nodes_checked: usize,
+    /// Every difference, trees in order and nodes in pre-order
+    pub mismatches: Vec<PortMismatch>,
+}
+
+impl PortCheck {
+    /// Compare a dump with its port at the default tolerance
+    ///
+    /// # Arguments
+    /// * `dump` - XGBoost JSON dump of the float model
+    /// * `port` - Fixed-point model that should have been built from it (at any scale)
+    ///
+    /// # Returns
+    /// * `Result<PortCheck, ModelError>` - The mismatches found, or an error if the dump is invalid
+    pub fn compare(dump: &str, port: &Ensemble) -> Result<PortCheck, ModelError> {
+        PortCheck::compare_with_tolerance(dump, port, PORT_TOLERANCE)
+    }
+
+    /// Compare a dump with its port, accepting thresholds and leaves within `tolerance` scaled units
+    pub fn compare_with_tolerance(dump: &str, port: &Ensemble, tolerance: f64) -> Result<PortCheck, ModelError> {
+        let float_trees = parse_float_trees(dump)?;
+        let mut check = PortCheck { scale: port.scale(), tolerance, nodes_checked: 0, mismatches: Vec::new() };
+        for tree in 0..float_trees.len().max(port.num_trees()) {
+            match (float_trees.get(tree), port.trees().get(tree)) {
+                (Some(float), Some(fixed)) => check.walk(tree, &mut vec![], float, &fixed.root),
+                (dump, _) => check.mismatches.push(PortMismatch {
+                    tree,
+                    path: Vec::new(),
+                    kind: MismatchKind::MissingTree { in_port: dump.is_none() },
+                }),
+            }
+        }
+        Ok(check)
+    }
+
+    /// Whether the port matches the dump everywhere
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    fn within(&self, dump: f64, port: i64) -> bool {
+        let expected = dump * self.scale as f64;
+        // Slack for the rounding of the product itself
+        (port as f64 - expected).abs() <= self.tolerance + expected.abs() * 2.0 * f64::EPSILON
+    }
+
+    fn walk(&mut self, tree: usize, path: &mut Vec<bool>, float: &FloatNode, port: &Node) {
+        self.nodes_checked += 1;
+        let mismatch = |kind| PortMismatch { tree, path: path.clone(), kind };
+        let (float_left, float_right, port_left, port_right) = match (float, port) {
+            (FloatNode::Leaf(value), Node::Leaf(leaf)) => {
+                if !self.within(*value, *leaf) {
+                    self.mismatches.push(mismatch(MismatchKind::Leaf { dump: *value, port: *leaf }));
+                }
+                return;
+            }
+            (
+                FloatNode::Split { feature, threshold, left, right },
+                Node::Split { feature: port_feature, threshold: port_threshold, left: port_left, right: port_right },
+            ) => {
+                if feature != port_feature {
+                    self.mismatches.push(mismatch(MismatchKind::Feature { dump: *feature, port: *port_feature }));
+                }
+                if !self.within(*threshold, *port_threshold) {
+                    let kind = MismatchKind::Threshold { dump: *threshold, port: *port_threshold };
+                    self.mismatches.push(mismatch(kind));
+                }
+                (left, right, port_left, port_right)
+            }
+            (
+                FloatNode::Categorical { feature, categories, left, right },
+                Node::Categorical {
+                    feature: port_feature,
+                    categories: port_categories,
+                    left: port_left,
+                    right: port_right,
+                    ..
+                },
+            ) => {
+                if feature != port_feature {
+                    self.mismatches.push(mismatch(MismatchKind::Feature { dump: *feature, port: *port_feature }));
+                }
+                if categories != port_categories {
+                    let kind = MismatchKind::Categories { dump: categories.clone(), port: port_categories.clone() };
+                    self.mismatches.push(mismatch(kind));
+                }
+                (left, right, port_left, port_right)
+            }
+            _ => {
+                // The subtrees cannot be paired up below a differing node
+                let kind = MismatchKind::NodeKind { dump: float_kind(float), port: node_kind(port) };
+                self.mismatches.push(mismatch(kind));
+                return;
+            }
+        };
+        for (yes, float_child, port_child) in [(true, float_left, port_left), (false, float_right, port_right)] {
+            path.push(yes);
+            self.walk(tree, path, float_child, port_child);
+            path.pop();
+        }
+    }
+}
+
+fn float_kind(node: &FloatNode) -> &'static str {
+    match node {
+        FloatNode::Leaf(_) => "leaf",
+        FloatNode::Split { .. } => "threshold split",
+        FloatNode::Categorical { .. } => "categorical split",
+    }
+}
+
+fn node_kind(node: &Node) -> &'static str {
+    match node {
+        Node::Leaf(_) => "leaf",
+        Node::Split { .. } => "threshold split",
+        Node::Categorical { .. } => "categorical split",
+    }
+}
+
+impl fmt::Display for PortCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} nodes checked at scale {}: ", self.nodes_checked, self.scale)?;
+        if self.is_ok() {
+            return write!(f, "port matches the dump");
+        }
+        let plural = if self.mismatches.len() == 1 { "" } else { "es" };
+        write!(f, "{} mismatch{}", self.mismatches.len(), plural)?;
+        for mismatch in &self.mismatches {
+            write!(f, "\n  {}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::Tree;
+    use crate::Model;
+
+    const DUMP: &str = include_str!("../models/bst1_10.json");
+
+    #[test]
+    fn test_port_matches_dump() {
+        for scale in [crate::PRECISION_MULTIPLIER, 1 << 16] {
+            let port = Model::from_xgboost_json_with_scale(DUMP, scale).unwrap();
+            let check = PortCheck::compare(DUMP, &port).unwrap();
+            assert!(check.is_ok(), "{}", check);
+            assert_eq!(check.nodes_checked, 330);
+        }
+        assert!(PortCheck::compare(DUMP, &crate::builtin_model()).unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_mismatches_have_paths() {
+        let dump = r#"[{"nodeid": 0, "split": "f1", "split_condition": 0.5, "yes": 1, "no": 2, "children": [
+            {"nodeid": 1, "leaf": 0.25},
+            {"nodeid": 2, "split": "f3", "split_condition": -1.0, "yes": 3, "no": 4, "children": [
+                {"nodeid": 3, "leaf": 1.0}, {"nodeid": 4, "leaf": 2.0}]}]}]"#;
+        let right = Node::split(4, -10, Node::Leaf(10), Node::split(0, 0, Node::Leaf(0), Node::Leaf(0)));
+        let port = Ensemble::new(
+            vec![Tree::new(Node::split(1, 5, Node::Leaf(4), right)), Tree::new(Node::Leaf(0))],
+            10,
+        );
+        let check = PortCheck::compare(dump, &port).unwrap();
+        let found: Vec<_> = check.mismatches.iter().map(|m| m.to_string()).collect();
+        assert_eq!(
+            found,
+            [
+                "tree 0 at yes: dump leaf 0.25, port leaf 4",
+                "tree 0 at no: dump splits on f3, port on f4",
+                "tree 0 at no/no: dump has a leaf, port has a threshold split",
+                "tree 1 at root: tree only in the port",
+            ]
+        );
+        // The leaf is 1.5 scaled units off: within a wider tolerance
+        assert_eq!(PortCheck::compare_with_tolerance(dump, &port, 1.5).unwrap().mismatches.len(), 3);
+    }
+}
@@ -0,0 +1,282 @@
+// Fixed-point feature preprocessing
+// sklearn-style transforms with parameters stored as scaled i64, so a proof can start from the raw
+// sensor readings instead of already-scaled model inputs:
+//   StandardScaler    (x - mean) * inv_scale
+//   MinMaxScaler      x * scale + min
+//   ConstantImputer   MISSING_VALUE -> fill
+// Products are rounded back to scale 10^10 with floor((p + S/2) / S), one floor division like
+// `LinearModel::eval`. Scalers pass `MISSING_VALUE` through unchanged (as sklearn passes NaN), so
+// an imputer placed after them still sees it and the trees keep their default direction otherwise.
+
+use alloc::vec::Vec;
+
+use crate::fixed::{round_half_away, saturate};
+use crate::{fixed_add, Model, MISSING_VALUE, PRECISION_MULTIPLIER};
+
+/// `floor((a * b + S/2) / S)`: product of two values at scale 10^10, rounded back to 10^10
+pub(crate) fn round_product(a: i128, b: i64) -> i64 {
+    let scale = PRECISION_MULTIPLIER as i128;
+    saturate((a * b as i128 + scale / 2).div_euclid(scale))
+}
+
+fn quantize(values: &[f64]) -> Vec<i64> {
+    values.iter().map(|&v| round_half_away(v * PRECISION_MULTIPLIER as f64)).collect()
+}
+
+/// Standardization `(x - mean) / scale`, stored with the reciprocal of the scale
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StandardScaler {
+    mean: Vec<i64>,
+    inv_scale: Vec<i64>,
+}
+
+impl StandardScaler {
+    /// Create a scaler from means and reciprocal scales, both scaled by 10^10
+    pub fn new(mean: Vec<i64>, inv_scale: Vec<i64>) -> StandardScaler {
+        assert_eq!(mean.len(), inv_scale.len(), "one mean and one scale per feature");
+        StandardScaler { mean, inv_scale }
+    }
+
+    /// Quantize sklearn's fitted `mean_` and `scale_`
+    pub fn from_f64(mean: &[f64], scale: &[f64]) -> StandardScaler {
+        let inv_scale: Vec<f64> = scale.iter().map(|&s| 1.0 / s).collect();
+        StandardScaler::new(quantize(mean), quantize(&inv_scale))
+    }
+
+    /// Feature means
+    pub fn mean(&self) -> &[i64] {
+        &self.mean
+    }
+
+    /// Reciprocal feature scales
+    pub fn inv_scale(&self) -> &[i64] {
+        &self.inv_scale
+    }
+
+    /// Standardize feature `i`
+    pub fn transform_value(&self, i: usize, x: i64) -> i64 {
+        if x == MISSING_VALUE {
+            return x;
+        }
+        round_product(x as i128 - self.mean[i] as i128, self.inv_scale[i])
+    }
+}
+
+/// Min-max scaling `x * scale + min` (sklearn's `scale_` and `min_`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinMaxScaler {
+    scale: Vec<i64>,
+    min: Vec<i64>,
+}
+
+impl MinMaxScaler {
+    /// Create a scaler from per-feature scales and offsets, both scaled by 10^10
+    pub fn new(scale: Vec<i64>, min: Vec<i64>) -> MinMaxScaler {
+        assert_eq!(scale.len(), min.len(), "one scale and one offset per feature");
+        MinMaxScaler { scale, min }
+    }
+
+    /// Quantize sklearn's fitted `scale_` and `min_`
+    pub fn from_f64(scale: &[f64], min: &[f64]) -> MinMaxScaler {
+        MinMaxScaler::new(quantize(scale), quantize(min))
+    }
+
+    /// Per-feature scales
+    pub fn scale(&self) -> &[i64] {
+        &self.scale
+    }
+
+    /// Per-feature offsets
+    pub fn min(&self) -> &[i64] {
+        &self.min
+    }
+
+    /// Scale feature `i`
+    pub fn transform_value(&self, i: usize, x: i64) -> i64 {
+        if x == MISSING_VALUE {
+            return x;
+        }
+        fixed_add(round_product(x as i128, self.scale[i]), self.min[i])
+    }
+}
+
+/// Constant imputation: replaces `MISSING_VALUE` with a per-feature fill value
+/// (sklearn's `SimpleImputer.statistics_`, whatever the strategy it was fitted with)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstantImputer {
+    fill: Vec<i64>,
+}
+
+impl ConstantImputer {
+    /// Create an imputer from fill values scaled by 10^10
+    pub fn new(fill: Vec<i64>) -> ConstantImputer {
+        ConstantImputer { fill }
+    }
+
+    /// Quantize floating-point fill values
+    pub fn from_f64(fill: &[f64]) -> ConstantImputer {
+        ConstantImputer::new(quantize(fill))
+    }
+
+    /// Per-feature fill values
+    pub fn fill(&self) -> &[i64] {
+        &self.fill
+    }
+
+    /// Impute feature `i`
+    pub fn transform_value(&self, i: usize, x: i64) -> i64 {
+        if x == MISSING_VALUE { self.fill[i] } else { x }
+    }
+}
+
+/// One preprocessing step; it transforms the first `num_features()` features and keeps the rest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transform {
+    Impute(ConstantImputer),
+    Standard(StandardScaler),
+    MinMax(MinMaxScaler),
+}
+
+impl Transform {
+    /// Number of leading features the step transforms
+    pub fn num_features(&self) -> usize {
+        match self {
+            Transform::Impute(imputer) => imputer.fill.len(),
+            Transform::Standard(scaler) => scaler.mean.len(),
+            Transform::MinMax(scaler) => scaler.scale.len(),
+        }
+    }
+
+    /// Transform feature `i` (`i < num_features()`)
+    pub fn transform_value(&self, i: usize, x: i64) -> i64 {
+        match self {
+            Transform::Impute(imputer) => imputer.transform_value(i, x),
+            Transform::Standard(scaler) => scaler.transform_value(i, x),
+            Transform::MinMax(scaler) => scaler.transform_value(i, x),
+        }
+    }
+
+    /// Transform a feature vector in place
+    pub fn apply(&self, features: &mut [i64]) {
+        assert!(features.len() >= self.num_features(),
+                "Expected at least {} features, got {}", self.num_features(), features.len());
+        for (i, x) in features.iter_mut().take(self.num_features()).enumerate() {
+            *x = self.transform_value(i, *x);
+        }
+    }
+}
+
+impl From<ConstantImputer> for Transform {
+    fn from(imputer: ConstantImputer) -> Self {
+        Transform::Impute(imputer)
+    }
+}
+
+impl From<StandardScaler> for Transform {
+    fn from(scaler: StandardScaler) -> Self {
+        Transform::Standard(scaler)
+    }
+}
+
+impl From<MinMaxScaler> for Transform {
+    fn from(scaler: MinMaxScaler) -> Self {
+        Transform::MinMax(scaler)
+    }
+}
+
+/// Preprocessing steps followed by a tree ensemble, from raw readings to prediction
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    steps: Vec<Transform>,
+    model: Model,
+}
+
+impl Pipeline {
+    /// Pipeline without preprocessing; the model must take inputs scaled by 10^10
+    pub fn new(model: Model) -> Pipeline {
+        assert_eq!(model.scale(), PRECISION_MULTIPLIER, "preprocessing outputs features scaled by 10^10");
+        Pipeline { steps: Vec::new(), model }
+    }
+
+    /// Append a preprocessing step (steps run in the order they were added)
+    pub fn with_step(mut self, step: impl Into<Transform>) -> Pipeline {
+        self.steps.push(step.into());
+        self
+    }
+
+    /// Preprocessing steps
+    pub fn steps(&self) -> &[Transform] {
+        &self.steps
+    }
+
+    /// Model predicting on the preprocessed features
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+
+    /// Run the preprocessing steps on raw features
+    pub fn transform(&self, raw: &[i64]) -> Vec<i64> {
+        let mut features = raw.to_vec();
+        for step in &self.steps {
+            step.apply(&mut features);
+        }
+        features
+    }
+
+    /// Preprocess raw features and predict
+    ///
+    /// # Arguments
+    /// * `raw` - Raw feature vector (scaled by 10^10, `MISSING_VALUE` for missing readings)
+    ///
+    /// # Returns
+    /// * `i64` - Model margin on the preprocessed features
+    pub fn predict(&self, raw: &[i64]) -> i64 {
+        self.model.predict(&self.transform(raw))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::{Ensemble, Node, Tree};
+    use crate::to_fixed_point;
+    use alloc::{boxed::Box, vec};
+
+    #[test]
+    fn test_scalers_match_float_transforms() {
+        let standard = StandardScaler::from_f64(&[12.5, -3.0], &[4.0, 0.3]);
+        let minmax = MinMaxScaler::from_f64(&[0.05, 2.0], &[-0.5, 0.25]);
+        for (x0, x1) in [(12.5, -3.0), (20.1, 0.7), (-4.0, -3.3)] {
+            let mut features = vec![to_fixed_point(x0), to_fixed_point(x1), 7];
+            Transform::from(standard.clone()).apply(&mut features);
+            assert!((features[0] - to_fixed_point((x0 - 12.5) / 4.0)).abs() <= 1);
+            assert!((features[1] - to_fixed_point((x1 + 3.0) / 0.3)).abs() <= 1);
+            assert_eq!(features[2], 7);
+
+            assert!((minmax.transform_value(0, to_fixed_point(x0)) - to_fixed_point(x0 * 0.05 - 0.5)).abs() <= 1);
+            assert!((minmax.transform_value(1, to_fixed_point(x1)) - to_fixed_point(x1 * 2.0 + 0.25)).abs() <= 1);
+        }
+        assert_eq!(standard.transform_value(0, MISSING_VALUE), MISSING_VALUE);
+        assert_eq!(minmax.transform_value(1, MISSING_VALUE), MISSING_VALUE);
+    }
+
+    #[test]
+    fn test_pipeline_imputes_before_predicting() {
+        // f0 <= 0.5 after min-max scaling to [0, 1] over readings in [10, 30]
+        let tree = Tree::new(Node::Split {
+            feature: 0,
+            threshold: to_fixed_point(0.5),
+            left: Box::new(Node::Leaf(-1)),
+            right: Box::new(Node::Leaf(1)),
+        });
+        let pipeline = Pipeline::new(Ensemble::new(vec![tree], PRECISION_MULTIPLIER))
+            .with_step(ConstantImputer::from_f64(&[25.0]))
+            .with_step(MinMaxScaler::from_f64(&[0.05], &[-0.5]));
+
+        assert_eq!(pipeline.transform(&[to_fixed_point(14.0)]), vec![to_fixed_point(0.2)]);
+        assert_eq!(pipeline.predict(&[to_fixed_point(14.0)]), -1);
+        assert_eq!(pipeline.predict(&[to_fixed_point(21.0)]), 1);
+        assert_eq!(pipeline.transform(&[MISSING_VALUE]), vec![to_fixed_point(0.75)]);
+        assert_eq!(pipeline.predict(&[MISSING_VALUE]), 1);
+    }
+}
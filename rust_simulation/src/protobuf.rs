@@ -0,0 +1,222 @@
+// Minimal protobuf wire-format reader
+// Just enough to walk ONNX models without generated code: fields are read one by one and
+// nested messages are handed back as byte slices to be decoded by the caller.
+
+use alloc::{string::{String, ToString}, vec::Vec};
+use core::fmt;
+
+/// Error raised while decoding the wire format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    /// Byte offset inside the message being decoded
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+/// Raw value of one field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+impl<'a> WireValue<'a> {
+    /// Length-delimited payload (strings, bytes, nested messages, packed arrays)
+    pub fn bytes(&self) -> Option<&'a [u8]> {
+        match *self {
+            WireValue::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Varint as a signed int64 (two's complement, not zigzag)
+    pub fn int64(&self) -> Option<i64> {
+        match *self {
+            WireValue::Varint(v) => Some(v as i64),
+            _ => None,
+        }
+    }
+}
+
+/// Sequential reader over the fields of one message
+pub struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Reader { buf, pos: 0 }
+    }
+
+    fn error(&self, message: &str) -> DecodeError {
+        DecodeError { offset: self.pos, message: message.to_string() }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.buf.len() - self.pos < len {
+            return Err(self.error("unexpected end of message"));
+        }
+        let bytes = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(self.error("varint longer than 10 bytes"))
+    }
+
+    /// Read the next field, or `None` at the end of the message
+    pub fn next_field(&mut self) -> Result<Option<(u32, WireValue<'a>)>, DecodeError> {
+        if self.pos == self.buf.len() {
+            return Ok(None);
+        }
+
+        let key = self.varint()?;
+        let field = (key >> 3) as u32;
+        let value = match key & 7 {
+            0 => WireValue::Varint(self.varint()?),
+            1 => WireValue::Fixed64(u64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+            2 => {
+                let len = self.varint()? as usize;
+                WireValue::Bytes(self.take(len)?)
+            }
+            5 => WireValue::Fixed32(u32::from_le_bytes(self.take(4)?.try_into().unwrap())),
+            _ => return Err(self.error("unsupported wire type")),
+        };
+
+        Ok(Some((field, value)))
+    }
+}
+
+/// Append the elements of a repeated int64 field (packed or not)
+pub fn push_int64s(value: WireValue<'_>, out: &mut Vec<i64>) -> Result<(), DecodeError> {
+    match value {
+        WireValue::Varint(v) => out.push(v as i64),
+        WireValue::Bytes(packed) => {
+            let mut reader = Reader::new(packed);
+            while reader.pos < packed.len() {
+                out.push(reader.varint()? as i64);
+            }
+        }
+        _ => return Err(DecodeError { offset: 0, message: "expected int64 values".to_string() }),
+    }
+    Ok(())
+}
+
+/// Append the elements of a repeated float field (packed or not) as f64
+pub fn push_floats(value: WireValue<'_>, out: &mut Vec<f64>) -> Result<(), DecodeError> {
+    match value {
+        WireValue::Fixed32(v) => out.push(f32::from_bits(v) as f64),
+        WireValue::Bytes(packed) if packed.len() % 4 == 0 => {
+            out.extend(packed.chunks_exact(4).map(|c| f32::from_le_bytes(c.try_into().unwrap()) as f64));
+        }
+        _ => return Err(DecodeError { offset: 0, message: "expected float values".to_string() }),
+    }
+    Ok(())
+}
+
+/// Append the elements of a repeated double field (packed or not)
+pub fn push_doubles(value: WireValue<'_>, out: &mut Vec<f64>) -> Result<(), DecodeError> {
+    match value {
+        WireValue::Fixed64(v) => out.push(f64::from_bits(v)),
+        WireValue::Bytes(packed) if packed.len() % 8 == 0 => {
+            out.extend(packed.chunks_exact(8).map(|c| f64::from_le_bytes(c.try_into().unwrap())));
+        }
+        _ => return Err(DecodeError { offset: 0, message: "expected double values".to_string() }),
+    }
+    Ok(())
+}
+
+/// Wire-format writer, used by tests to build messages
+#[cfg(test)]
+pub mod writer {
+    fn varint(out: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    pub fn int64(out: &mut Vec<u8>, field: u32, value: i64) {
+        varint(out, (field as u64) << 3);
+        varint(out, value as u64);
+    }
+
+    pub fn float(out: &mut Vec<u8>, field: u32, value: f32) {
+        varint(out, ((field as u64) << 3) | 5);
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn bytes(out: &mut Vec<u8>, field: u32, value: &[u8]) {
+        varint(out, ((field as u64) << 3) | 2);
+        varint(out, value.len() as u64);
+        out.extend_from_slice(value);
+    }
+
+    pub fn packed_int64s(out: &mut Vec<u8>, field: u32, values: &[i64]) {
+        let mut packed = Vec::new();
+        values.iter().for_each(|&v| varint(&mut packed, v as u64));
+        bytes(out, field, &packed);
+    }
+
+    pub fn packed_floats(out: &mut Vec<u8>, field: u32, values: &[f32]) {
+        let packed: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        bytes(out, field, &packed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_fields() {
+        let mut msg = Vec::new();
+        writer::int64(&mut msg, 1, -2);
+        writer::bytes(&mut msg, 2, b"leaf");
+        writer::float(&mut msg, 3, 0.5);
+        writer::packed_int64s(&mut msg, 4, &[1, 300]);
+
+        let mut reader = Reader::new(&msg);
+        assert_eq!(reader.next_field().unwrap().unwrap().1.int64(), Some(-2));
+        assert_eq!(reader.next_field().unwrap().unwrap(), (2, WireValue::Bytes(b"leaf")));
+        let mut floats = Vec::new();
+        push_floats(reader.next_field().unwrap().unwrap().1, &mut floats).unwrap();
+        assert_eq!(floats, vec![0.5]);
+
+        let (field, packed) = reader.next_field().unwrap().unwrap();
+        let mut ints = Vec::new();
+        push_int64s(packed, &mut ints).unwrap();
+        assert_eq!((field, ints), (4, vec![1, 300]));
+        assert_eq!(reader.next_field().unwrap(), None);
+    }
+
+    #[test]
+    fn test_truncated_message_is_rejected() {
+        let mut msg = Vec::new();
+        writer::bytes(&mut msg, 1, b"abcdef");
+        msg.truncate(5);
+        assert!(Reader::new(&msg).next_field().is_err());
+        assert!(Reader::new(&[0x0b]).next_field().is_err());
+    }
+}
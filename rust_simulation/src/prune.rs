@@ -0,0 +1,184 @@
+// Ensemble pruning under an error budget
+// Late boosting rounds often split into leaves that barely differ. Every subtree whose leaves all
+// lie within 2 * max_error of each other is replaced by one leaf at the midpoint of their range,
+// so each tree's output moves by at most max_error and the prediction by at most
+// num_trees * max_error (max_error for averaging ensembles). Each removed split saves a comparison
+// and a select in every circuit. `PruneReport` measures the actual deviation on validation
+// samples next to that bound.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::ensemble::{Combiner, Ensemble, Node, Tree};
+
+/// Smallest and largest leaf of a subtree
+pub(crate) fn leaf_range(node: &Node) -> (i64, i64) {
+    match node {
+        Node::Leaf(value) => (*value, *value),
+        Node::Split { left, right, .. } | Node::Categorical { left, right, .. } => {
+            let (left_min, left_max) = leaf_range(left);
+            let (right_min, right_max) = leaf_range(right);
+            (left_min.min(right_min), left_max.max(right_max))
+        }
+    }
+}
+
+/// Collapse every maximal subtree whose leaves span at most 2 * max_error
+fn prune_node(node: &Node, max_error: i64) -> Node {
+    let (min, max) = leaf_range(node);
+    if (max as i128 - min as i128) <= 2 * max_error as i128 {
+        // Midpoint rounded down: within max_error of both ends
+        return Node::Leaf(((min as i128 + max as i128).div_euclid(2)) as i64);
+    }
+    match node {
+        Node::Leaf(value) => Node::Leaf(*value),
+        Node::Split { feature, threshold, left, right } => {
+            Node::split(*feature, *threshold, prune_node(left, max_error), prune_node(right, max_error))
+        }
+        Node::Categorical { feature, categories, unit, left, right } => Node::categorical(
+            *feature,
+            categories.clone(),
+            *unit,
+            prune_node(left, max_error),
+            prune_node(right, max_error),
+        ),
+    }
+}
+
+impl Ensemble {
+    /// Merge subtrees whose leaf values differ by at most `2 * max_error`
+    ///
+    /// # Arguments
+    /// * `max_error` - Largest change allowed in any tree output (at the ensemble's scale, >= 0)
+    ///
+    /// # Returns
+    /// * `Ensemble` - Pruned ensemble, with the same metadata; see `prune_error_bound`
+    pub fn prune(&self, max_error: i64) -> Ensemble {
+        assert!(max_error >= 0, "pruning tolerance must not be negative");
+        self.with_trees(self.trees().iter().map(|tree| Tree::new(prune_node(&tree.root, max_error))).collect())
+    }
+
+    /// Largest change of the prediction `prune(max_error)` can cause
+    pub fn prune_error_bound(&self, max_error: i64) -> i64 {
+        match self.combiner() {
+            Combiner::Sum => (max_error as i128 * self.num_trees() as i128).min(i64::MAX as i128) as i64,
+            // |a - b| <= n * e implies |floor(a / n) - floor(b / n)| <= e
+            Combiner::Average => max_error,
+        }
+    }
+}
+
+/// Size and accuracy of a pruned ensemble against the original
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Nodes before and after pruning
+    pub nodes_before: usize,
+    pub nodes_after: usize,
+    /// Guaranteed bound on the prediction change (see `Ensemble::prune_error_bound`)
+    pub error_bound: i64,
+    /// Number of validation samples
+    pub samples: usize,
+    /// Largest and mean absolute prediction change on the validation samples
+    pub max_deviation: i64,
+    pub mean_deviation: i64,
+}
+
+impl PruneReport {
+    /// Compare a pruned ensemble with the original on validation samples
+    ///
+    /// # Arguments
+    /// * `original` - Ensemble before pruning
+    /// * `max_error` - Tolerance the ensemble was pruned with
+    /// * `samples` - Validation feature vectors (at the ensemble's scale)
+    ///
+    /// # Returns
+    /// * `(Ensemble, PruneReport)` - Pruned ensemble and its size and deviations
+    pub fn measure(original: &Ensemble, max_error: i64, samples: &[Vec<i64>]) -> (Ensemble, PruneReport) {
+        let pruned = original.prune(max_error);
+        let deviations: Vec<u64> =
+            samples.iter().map(|features| original.eval(features).abs_diff(pruned.eval(features))).collect();
+        let total: u128 = deviations.iter().map(|&d| d as u128).sum();
+        let count = |model: &Ensemble| model.trees().iter().map(Tree::num_nodes).sum();
+
+        let report = PruneReport {
+            nodes_before: count(original),
+            nodes_after: count(&pruned),
+            error_bound: original.prune_error_bound(max_error),
+            samples: samples.len(),
+            max_deviation: deviations.iter().copied().max().unwrap_or(0) as i64,
+            mean_deviation: if samples.is_empty() { 0 } else { (total / samples.len() as u128) as i64 },
+        };
+        (pruned, report)
+    }
+
+    /// Split nodes removed
+    pub fn removed_splits(&self) -> usize {
+        (self.nodes_before - self.nodes_after) / 2
+    }
+}
+
+impl fmt::Display for PruneReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} nodes ({} splits removed); on {} samples the prediction moved by at most {} (mean {}), bound {}",
+            self.nodes_before,
+            self.nodes_after,
+            self.removed_splits(),
+            self.samples,
+            self.max_deviation,
+            self.mean_deviation,
+            self.error_bound
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builtin_model, BitSet, NUM_FEATURES};
+    use alloc::vec;
+
+    #[test]
+    fn test_prune_merges_close_leaves() {
+        let categories: BitSet = [1].into_iter().collect();
+        let tree = Tree::new(Node::split(
+            0,
+            5,
+            Node::split(1, 0, Node::Leaf(10), Node::categorical(1, categories, 1, Node::Leaf(13), Node::Leaf(11))),
+            Node::Leaf(100),
+        ));
+        let model = Ensemble::new(vec![tree.clone()], 1).with_base_score(3);
+        assert_eq!(model.prune(0), model);
+
+        let pruned = model.prune(1);
+        assert_eq!(pruned.trees()[0].num_nodes(), 5);
+        let pruned = model.prune(2);
+        assert_eq!(pruned.trees()[0].root, Node::split(0, 5, Node::Leaf(11), Node::Leaf(100)));
+        assert_eq!(pruned.base_score(), 3);
+        assert_eq!(model.prune(50).trees()[0].root, Node::Leaf(55));
+        let extreme = Ensemble::new(vec![Tree::new(Node::Leaf(i64::MIN)), tree], 1).prune(i64::MAX);
+        assert_eq!(extreme.trees()[0].root, Node::Leaf(i64::MIN));
+    }
+
+    #[test]
+    fn test_report_within_bound() {
+        let model = builtin_model();
+        let samples: Vec<Vec<i64>> = (0..200i64)
+            .map(|i| {
+                (0..NUM_FEATURES as i64).map(|j| ((i * 53 + j * 11) % 400) * 500_000_000 - 80_000_000_000).collect()
+            })
+            .collect();
+        for max_error in [0, 10_000_000, 100_000_000] {
+            let (pruned, report) = PruneReport::measure(&model, max_error, &samples);
+            assert!(report.max_deviation <= report.error_bound, "{}", report);
+            assert!(report.mean_deviation <= report.max_deviation);
+            assert_eq!(report.nodes_after, pruned.trees().iter().map(Tree::num_nodes).sum::<usize>());
+            assert_eq!(report.error_bound, max_error * 10);
+        }
+        let (_, lossless) = PruneReport::measure(&model, 0, &samples);
+        assert_eq!((lossless.max_deviation, lossless.samples), (0, 200));
+        let (_, coarse) = PruneReport::measure(&model, 100_000_000, &samples);
+        assert!(coarse.removed_splits() > 0, "{}", coarse);
+    }
+}
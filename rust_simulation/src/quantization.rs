@@ -0,0 +1,256 @@
+// Quantization analysis
+// Compares the floating-point model in an XGBoost dump with its fixed-point port node by node:
+// how far every threshold and leaf moved when it was scaled, the bound this puts on the prediction
+// error, and the band of inputs around each split where the port can branch differently from the
+// float model. Running it at several scales shows how much precision a circuit actually needs.
+
+use alloc::{string::{String, ToString}, vec, vec::Vec};
+
+use crate::ensemble::{Ensemble, Node};
+use crate::fixed::round_half_away;
+use crate::json::JsonValue;
+use crate::model::{parse_float_trees, FloatNode, ModelError};
+
+/// Quantization of one split threshold
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitQuantization {
+    pub tree: usize,
+    pub feature: usize,
+    /// Threshold in the float model
+    pub threshold: f64,
+    /// Threshold in the port (raw, at the report's scale)
+    pub quantized: i64,
+    /// Raw input at which the port switches branch: inputs are rounded to nearest, so the port
+    /// goes left below `(quantized + 0.5) / scale`
+    pub boundary: f64,
+}
+
+impl SplitQuantization {
+    /// Distance between the float and the port decision boundary; inputs within it of the
+    /// threshold may take different branches
+    pub fn error(&self) -> f64 {
+        (self.boundary - self.threshold).abs()
+    }
+
+    /// Whether the float model and the port branch differently on raw input `value`
+    pub fn disagrees(&self, value: f64, scale: i64) -> bool {
+        (value <= self.threshold) != (round_half_away(value * scale as f64) <= self.quantized)
+    }
+}
+
+/// Quantization of one leaf value
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeafQuantization {
+    pub tree: usize,
+    /// Leaf value in the float model
+    pub value: f64,
+    /// Leaf value in the port (raw, at the report's scale)
+    pub quantized: i64,
+}
+
+impl LeafQuantization {
+    /// Rounding error of the leaf in real units
+    pub fn error(&self, scale: i64) -> f64 {
+        (self.quantized as f64 / scale as f64 - self.value).abs()
+    }
+}
+
+/// Rounding error of every threshold and leaf of a fixed-point port
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantizationReport {
+    /// Scale of the port
+    pub scale: i64,
+    /// Splits of all trees, in pre-order
+    pub splits: Vec<SplitQuantization>,
+    /// Leaves of all trees, in pre-order
+    pub leaves: Vec<LeafQuantization>,
+    num_trees: usize,
+}
+
+impl QuantizationReport {
+    /// Compare a model dump with its fixed-point port
+    ///
+    /// # Arguments
+    /// * `dump` - XGBoost JSON dump of the float model
+    /// * `port` - Fixed-point model built from it (any scale, rescaled or not)
+    ///
+    /// # Returns
+    /// * `Result<QuantizationReport, ModelError>` - Per-node errors, or an error if the dump is
+    ///   invalid or its trees do not have the port's shape
+    pub fn analyze(dump: &str, port: &Ensemble) -> Result<QuantizationReport, ModelError> {
+        let float_trees = parse_float_trees(dump)?;
+        if float_trees.len() != port.num_trees() {
+            return Err(ModelError::InvalidNode {
+                tree: float_trees.len().min(port.num_trees()),
+                message: "dump and port have different tree counts".to_string(),
+            });
+        }
+
+        let mut report = QuantizationReport {
+            scale: port.scale(),
+            splits: Vec::new(),
+            leaves: Vec::new(),
+            num_trees: port.num_trees(),
+        };
+        for (tree, (float_tree, port_tree)) in float_trees.iter().zip(port.trees()).enumerate() {
+            report.collect(tree, float_tree, &port_tree.root)?;
+        }
+        Ok(report)
+    }
+
+    fn collect(&mut self, tree: usize, float: &FloatNode, port: &Node) -> Result<(), ModelError> {
+        match (float, port) {
+            (FloatNode::Leaf(value), Node::Leaf(quantized)) => {
+                self.leaves.push(LeafQuantization { tree, value: *value, quantized: *quantized });
+                Ok(())
+            }
+            (
+                FloatNode::Split { feature, threshold, left, right },
+                Node::Split { feature: port_feature, threshold: quantized, left: port_left, right: port_right },
+            ) if feature == port_feature => {
+                self.splits.push(SplitQuantization {
+                    tree,
+                    feature: *feature,
+                    threshold: *threshold,
+                    quantized: *quantized,
+                    boundary: (*quantized as f64 + 0.5) / self.scale as f64,
+                });
+                self.collect(tree, left, port_left)?;
+                self.collect(tree, right, port_right)
+            }
+            // Category sets are copied exactly: nothing to quantize
+            (
+                FloatNode::Categorical { feature, categories, left, right },
+                Node::Categorical { feature: port_feature, categories: port_categories, left: port_left, right: port_right, .. },
+            ) if feature == port_feature && categories == port_categories => {
+                self.collect(tree, left, port_left)?;
+                self.collect(tree, right, port_right)
+            }
+            _ => Err(ModelError::InvalidNode { tree, message: "dump and port trees differ in shape".to_string() }),
+        }
+    }
+
+    /// Largest threshold boundary error
+    pub fn max_threshold_error(&self) -> f64 {
+        self.splits.iter().map(SplitQuantization::error).fold(0.0, f64::max)
+    }
+
+    /// Largest leaf rounding error
+    pub fn max_leaf_error(&self) -> f64 {
+        self.leaves.iter().map(|leaf| leaf.error(self.scale)).fold(0.0, f64::max)
+    }
+
+    /// Worst-case prediction error while the port takes the same paths as the float model:
+    /// the sum over trees of each tree's largest leaf error
+    pub fn prediction_error_bound(&self) -> f64 {
+        let mut per_tree = vec![0.0f64; self.num_trees];
+        for leaf in &self.leaves {
+            per_tree[leaf.tree] = per_tree[leaf.tree].max(leaf.error(self.scale));
+        }
+        per_tree.iter().sum()
+    }
+
+    /// Splits on which the port branches differently from the float model for raw input `features`
+    ///
+    /// Every split is checked, not only those on the sample's paths, so this flags inputs that
+    /// sit near any decision boundary.
+    pub fn disagreements(&self, features: &[f64]) -> Vec<&SplitQuantization> {
+        self.splits
+            .iter()
+            .filter(|split| features.get(split.feature).is_some_and(|&x| split.disagrees(x, self.scale)))
+            .collect()
+    }
+
+    /// Machine-readable form of the report
+    pub fn to_json(&self) -> JsonValue {
+        let number = |value: String| JsonValue::Number(value);
+        let float = |value: f64| JsonValue::Number(format_f64(value));
+        let splits = self
+            .splits
+            .iter()
+            .map(|s| {
+                JsonValue::Object(vec![
+                    ("tree".to_string(), number(s.tree.to_string())),
+                    ("feature".to_string(), number(s.feature.to_string())),
+                    ("threshold".to_string(), float(s.threshold)),
+                    ("quantized".to_string(), number(s.quantized.to_string())),
+                    ("boundary".to_string(), float(s.boundary)),
+                    ("error".to_string(), float(s.error())),
+                ])
+            })
+            .collect();
+        let leaves = self
+            .leaves
+            .iter()
+            .map(|l| {
+                JsonValue::Object(vec![
+                    ("tree".to_string(), number(l.tree.to_string())),
+                    ("value".to_string(), float(l.value)),
+                    ("quantized".to_string(), number(l.quantized.to_string())),
+                    ("error".to_string(), float(l.error(self.scale))),
+                ])
+            })
+            .collect();
+        JsonValue::Object(vec![
+            ("scale".to_string(), number(self.scale.to_string())),
+            ("max_threshold_error".to_string(), float(self.max_threshold_error())),
+            ("max_leaf_error".to_string(), float(self.max_leaf_error())),
+            ("prediction_error_bound".to_string(), float(self.prediction_error_bound())),
+            ("splits".to_string(), JsonValue::Array(splits)),
+            ("leaves".to_string(), JsonValue::Array(leaves)),
+        ])
+    }
+}
+
+/// Float as JSON number text (exponent form round-trips exactly)
+fn format_f64(value: f64) -> String {
+    alloc::format!("{:e}", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = r#"[{ "nodeid": 0, "split": "f0", "split_condition": 0.845000029, "yes": 1, "no": 2,
+                            "children": [ { "nodeid": 1, "leaf": 0.123456789 }, { "nodeid": 2, "leaf": -0.5 } ]}]"#;
+
+    #[test]
+    fn test_errors_shrink_with_scale() {
+        let coarse = Ensemble::from_xgboost_json_with_scale(DUMP, 100).unwrap();
+        let report = QuantizationReport::analyze(DUMP, &coarse).unwrap();
+
+        assert_eq!(report.splits[0].quantized, 85);
+        assert!((report.splits[0].boundary - 0.855).abs() < 1e-12);
+        assert!((report.max_threshold_error() - 0.009999971).abs() < 1e-9);
+        assert!((report.max_leaf_error() - 0.003456789).abs() < 1e-9);
+        assert_eq!(report.prediction_error_bound(), report.max_leaf_error());
+
+        let fine = Ensemble::from_xgboost_json(DUMP).unwrap();
+        let fine_report = QuantizationReport::analyze(DUMP, &fine).unwrap();
+        assert!(fine_report.max_threshold_error() < 1e-9);
+        assert!(fine_report.max_leaf_error() < 1e-9);
+    }
+
+    #[test]
+    fn test_disagreements_near_boundary() {
+        let coarse = Ensemble::from_xgboost_json_with_scale(DUMP, 100).unwrap();
+        let report = QuantizationReport::analyze(DUMP, &coarse).unwrap();
+
+        // Float goes right above 0.845, the port only from 0.855
+        assert_eq!(report.disagreements(&[0.85]).len(), 1);
+        assert!(report.disagreements(&[0.84]).is_empty());
+        assert!(report.disagreements(&[0.86]).is_empty());
+        assert!(report.to_json().get("splits").and_then(JsonValue::as_array).is_some_and(|s| s.len() == 1));
+    }
+
+    #[test]
+    fn test_builtin_model_and_shape_mismatch() {
+        let model = crate::builtin_model();
+        let report = QuantizationReport::analyze(crate::MODEL_JSON, &model).unwrap();
+        assert_eq!(report.leaves.len() - report.splits.len(), model.num_trees());
+        assert!(report.prediction_error_bound() <= model.num_trees() as f64 * 0.5e-10 + 1e-15);
+
+        let other = Ensemble::from_xgboost_json(DUMP).unwrap();
+        assert!(matches!(QuantizationReport::analyze(crate::MODEL_JSON, &other), Err(ModelError::InvalidNode { .. })));
+    }
+}
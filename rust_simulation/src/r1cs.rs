@@ -0,0 +1,1190 @@
+// arkworks R1CS gadgets for the fixed-point tree traversal
+// Mirrors `fixed_le` / `fixed_add` as constraints and evaluates the same `Model` trees that the
+// simulator runs, so the circuit and the Rust simulation cannot drift apart.
+//
+// Fixed-point values are embedded as signed field elements (negative values wrap around the
+// modulus). Every witness is range-checked to |x| < 2^62, which leaves room for the 64-bit
+// comparison below and rules out the saturation case of `fixed_add`. With `ValueBounds` from
+// the bit-width analysis, `ModelCircuit::with_bounds` sizes the input range checks, comparisons
+// and accumulator checks to the model instead. Categorical splits test equality with each member
+// value of their category set instead of comparing. Each distinct split test of the ensemble is
+// constrained once (`Ensemble::share_comparisons`) and selected on by every split using it.
+// Averaging ensembles divide the tree sum with a witnessed quotient and a remainder range-checked
+// to [0, num_trees); `linear_gadget` rescales a linear model's dot product the same way,
+// `transform_gadget` the products of the preprocessing scalers, and `isotonic_gadget` the
+// interpolation step of an isotonic calibration.
+// `guard_gadget` compares the prediction with an `OutputGuard` range and exposes the decision as
+// a public flag. `mean_gadget` and `order_statistic_gadget` prove the feature aggregates of `stats`: the mean
+// by a witnessed quotient, a percentile by counting the values below a witnessed order statistic.
+// Variance and standard deviation stay native-only, as their squared sums exceed the width
+// `enforce_bits` decomposes.
+// `R1csBackend` implements `CircuitBackend` with these gadgets; `model_gadget` and `ModelCircuit`
+// lower the model through it.
+
+use alloc::vec::Vec;
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::backend::{lower_model, CircuitBackend};
+use crate::bitwidth::{magnitude_bits, ValueBounds};
+use crate::calibration::IsotonicCalibration;
+use crate::cascade::{Cascade, CascadeStage};
+use crate::ensemble::BitSet;
+use crate::guard::OutputGuard;
+use crate::linear::LinearModel;
+use crate::preprocess::{round_product, Pipeline, Transform};
+use crate::{Model, MISSING_VALUE, PRECISION_MULTIPLIER};
+
+/// Bits of magnitude allowed for any fixed-point value inside the circuit
+pub const VALUE_BITS: usize = 62;
+
+/// Embed a fixed-point value as a field element (negative values wrap around the modulus)
+pub fn fixed_to_field<F: PrimeField>(value: i64) -> F {
+    F::from(value)
+}
+
+/// Decompose `value` into `num_bits` little-endian bits, enforcing that it fits
+fn enforce_bits<F: PrimeField>(
+    value: &FpVar<F>,
+    num_bits: usize,
+) -> Result<Vec<Boolean<F>>, SynthesisError> {
+    if value.is_constant() {
+        // A constant (e.g. the sum of leaf-only trees) has no variable to decompose: check it
+        // natively, as a constant out of range can never be satisfied
+        let bits = value.value()?.into_bigint().to_bits_le();
+        if bits[num_bits..].iter().any(|&bit| bit) {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        return Ok(bits[..num_bits].iter().map(|&bit| Boolean::constant(bit)).collect());
+    }
+
+    let cs = value.cs();
+    let native = value.value().ok().map(|v| {
+        let limbs = v.into_bigint();
+        let limbs = limbs.as_ref();
+        (limbs[0] as u128) | ((limbs.get(1).copied().unwrap_or(0) as u128) << 64)
+    });
+
+    let bits = (0..num_bits)
+        .map(|i| {
+            Boolean::new_witness(cs.clone(), || {
+                native
+                    .map(|v| (v >> i) & 1 == 1)
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Boolean::le_bits_to_fp(&bits)?.enforce_equal(value)?;
+    Ok(bits)
+}
+
+/// Enforce |value| < 2^62 on a fixed-point variable
+pub fn enforce_fixed_range<F: PrimeField>(value: &FpVar<F>) -> Result<(), SynthesisError> {
+    enforce_range(value, VALUE_BITS)
+}
+
+/// Enforce |value| < 2^bits on a fixed-point variable (`bits <= VALUE_BITS`)
+pub fn enforce_range<F: PrimeField>(value: &FpVar<F>, bits: usize) -> Result<(), SynthesisError> {
+    assert!(bits <= VALUE_BITS, "range of {} bits exceeds the circuit range", bits);
+    let shifted = value + FpVar::constant(F::from(1u64 << bits));
+    enforce_bits(&shifted, bits + 1)?;
+    Ok(())
+}
+
+/// Allocate a private fixed-point witness and range-check it
+pub fn alloc_fixed_witness<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    value: Option<i64>,
+) -> Result<FpVar<F>, SynthesisError> {
+    alloc_fixed_witness_bits(cs, value, VALUE_BITS)
+}
+
+/// Allocate a private fixed-point witness and range-check it to |x| < 2^bits
+pub fn alloc_fixed_witness_bits<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    value: Option<i64>,
+    bits: usize,
+) -> Result<FpVar<F>, SynthesisError> {
+    let var = FpVar::new_witness(cs, || {
+        value.map(fixed_to_field::<F>).ok_or(SynthesisError::AssignmentMissing)
+    })?;
+    enforce_range(&var, bits)?;
+    Ok(var)
+}
+
+/// Constraint version of `fixed_le`: returns a boolean that is true iff a <= b
+///
+/// Both inputs must already be range-checked to |x| < 2^62, so b - a + 2^63 lies in
+/// [1, 2^64) and its top bit is exactly the comparison result.
+pub fn fixed_le_gadget<F: PrimeField>(
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+) -> Result<Boolean<F>, SynthesisError> {
+    fixed_le_gadget_bits(a, b, VALUE_BITS)
+}
+
+/// `fixed_le_gadget` for inputs known to satisfy |x| < 2^bits: b - a + 2^(bits+1) lies in
+/// [1, 2^(bits+2)) and its top bit is the comparison result
+pub fn fixed_le_gadget_bits<F: PrimeField>(
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+    bits: usize,
+) -> Result<Boolean<F>, SynthesisError> {
+    assert!(bits <= VALUE_BITS, "comparison of {} bits exceeds the circuit range", bits);
+    let shifted = b - a + FpVar::constant(F::from(1u64 << (bits + 1)));
+    let decomposition = enforce_bits(&shifted, bits + 2)?;
+    Ok(decomposition[bits + 1].clone())
+}
+
+/// Constraint version of `fixed_add`: the sum must stay in range, so it never saturates
+pub fn fixed_add_gadget<F: PrimeField>(
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+) -> Result<FpVar<F>, SynthesisError> {
+    fixed_add_gadget_bits(a, b, VALUE_BITS)
+}
+
+/// `fixed_add_gadget` with the sum range-checked to |sum| < 2^bits
+pub fn fixed_add_gadget_bits<F: PrimeField>(
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+    bits: usize,
+) -> Result<FpVar<F>, SynthesisError> {
+    let sum = a + b;
+    enforce_range(&sum, bits)?;
+    Ok(sum)
+}
+
+/// Constraint version of the averaging combiner: `base_score + floor(sum / num_trees)`
+///
+/// `sum` must be range-checked to |sum| < 2^bits. The quotient q is witnessed and range-checked
+/// to the same width, and the remainder `sum - q * num_trees` to [0, num_trees), so the field
+/// cannot wrap and q is the floored quotient.
+pub fn average_gadget<F: PrimeField>(
+    sum: &FpVar<F>,
+    num_trees: usize,
+    base_score: i64,
+    bits: usize,
+) -> Result<FpVar<F>, SynthesisError> {
+    let base_score = FpVar::constant(fixed_to_field(base_score));
+    if num_trees <= 1 {
+        return fixed_add_gadget(&base_score, sum);
+    }
+
+    let quotient = sum.value().ok().and_then(field_to_fixed).map(|s| s.div_euclid(num_trees as i64));
+    let quotient = if sum.is_constant() {
+        FpVar::constant(fixed_to_field(quotient.expect("constant sum has a value")))
+    } else {
+        alloc_fixed_witness_bits(sum.cs(), quotient, bits)?
+    };
+    let remainder = sum - &quotient * F::from(num_trees as u64);
+    let remainder_bits = magnitude_bits(num_trees as i128 - 1);
+    enforce_bits(&remainder, remainder_bits)?;
+    enforce_bits(&(FpVar::constant(F::from(num_trees as u64 - 1)) - &remainder), remainder_bits)?;
+
+    fixed_add_gadget(&base_score, &quotient)
+}
+
+/// Constraint version of `LinearModel::eval` on range-checked features
+///
+/// The dot product is a linear combination (no constraints); the BN254 field holds it exactly.
+/// The rescaled value q = floor((dot + scale / 2) / scale) is witnessed and range-checked to
+/// |q| < 2^62 and the remainder to [0, scale), then the bias is added.
+pub fn linear_gadget<F: PrimeField>(model: &LinearModel, features: &[FpVar<F>]) -> Result<FpVar<F>, SynthesisError> {
+    assert!(features.len() >= model.num_features(),
+            "Expected at least {} features, got {}", model.num_features(), features.len());
+
+    let to_field = |value: i128| if value < 0 { -F::from((-value) as u128) } else { F::from(value as u128) };
+    let scale = model.scale() as i128;
+    let mut shifted = FpVar::constant(to_field(scale / 2));
+    for (&weight, feature) in model.weights().iter().zip(features) {
+        shifted += feature * to_field(weight as i128);
+    }
+
+    let cs = features.iter().fold(ConstraintSystemRef::None, |cs, f| cs.or(f.cs()));
+    let quotient = features
+        .iter()
+        .map(|f| f.value().ok().and_then(field_to_fixed))
+        .collect::<Option<Vec<i64>>>()
+        .map(|values| (model.dot(&values) + scale / 2).div_euclid(scale))
+        .and_then(|q| i64::try_from(q).ok());
+    let quotient = alloc_fixed_witness(cs, quotient)?;
+
+    let remainder = shifted - &quotient * to_field(scale);
+    let remainder_bits = magnitude_bits(scale - 1);
+    enforce_bits(&remainder, remainder_bits)?;
+    enforce_bits(&(FpVar::constant(to_field(scale - 1)) - &remainder), remainder_bits)?;
+
+    fixed_add_gadget(&FpVar::constant(fixed_to_field(model.bias())), &quotient)
+}
+
+/// `floor((a * b + S/2) / S)` at S = 10^10 for a variable `a` and a constant `b`, with the
+/// rounded product witnessed and range-checked to |q| < 2^62 and the remainder to [0, S)
+fn round_product_gadget<F: PrimeField>(a: &FpVar<F>, b: i64) -> Result<FpVar<F>, SynthesisError> {
+    let scale = PRECISION_MULTIPLIER;
+    let quotient = a.value().ok().and_then(field_to_fixed).map(|a| round_product(a as i128, b));
+    if a.is_constant() {
+        return Ok(FpVar::constant(fixed_to_field(quotient.expect("constant input has a value"))));
+    }
+    let shifted = a * fixed_to_field::<F>(b) + FpVar::constant(fixed_to_field::<F>(scale / 2));
+    let quotient = alloc_fixed_witness(a.cs(), quotient)?;
+
+    let remainder = shifted - &quotient * fixed_to_field::<F>(scale);
+    let remainder_bits = magnitude_bits(scale as i128 - 1);
+    enforce_bits(&remainder, remainder_bits)?;
+    enforce_bits(&(FpVar::constant(fixed_to_field(scale - 1)) - &remainder), remainder_bits)?;
+    Ok(quotient)
+}
+
+/// Constraint version of `Transform::apply` on range-checked features
+///
+/// Each transformed feature is compared with `MISSING_VALUE`. Scalers run on a neutral value
+/// for missing features (the mean, or zero) and select `MISSING_VALUE` back afterwards, so the
+/// rounded products stay in range whatever the input.
+pub fn transform_gadget<F: PrimeField>(step: &Transform, features: &[FpVar<F>]) -> Result<Vec<FpVar<F>>, SynthesisError> {
+    assert!(features.len() >= step.num_features(),
+            "Expected at least {} features, got {}", step.num_features(), features.len());
+
+    let missing_value = FpVar::constant(fixed_to_field::<F>(MISSING_VALUE));
+    features
+        .iter()
+        .enumerate()
+        .map(|(i, x)| {
+            if i >= step.num_features() {
+                return Ok(x.clone());
+            }
+            let missing = x.is_eq(&missing_value)?;
+            match step {
+                Transform::Impute(imputer) => missing.select(&FpVar::constant(fixed_to_field(imputer.fill()[i])), x),
+                Transform::Standard(scaler) => {
+                    let mean = FpVar::constant(fixed_to_field(scaler.mean()[i]));
+                    let centered = missing.select(&mean, x)? - &mean;
+                    let scaled = round_product_gadget(&centered, scaler.inv_scale()[i])?;
+                    missing.select(&missing_value, &scaled)
+                }
+                Transform::MinMax(scaler) => {
+                    let value = missing.select(&FpVar::zero(), x)?;
+                    let min = FpVar::constant(fixed_to_field(scaler.min()[i]));
+                    let scaled = fixed_add_gadget(&round_product_gadget(&value, scaler.scale()[i])?, &min)?;
+                    missing.select(&missing_value, &scaled)
+                }
+            }
+        })
+        .collect()
+}
+
+/// Constraints for `Pipeline::predict` on range-checked raw features
+pub fn pipeline_gadget<F: PrimeField>(pipeline: &Pipeline, raw: &[FpVar<F>]) -> Result<FpVar<F>, SynthesisError> {
+    let mut features = raw.to_vec();
+    for step in pipeline.steps() {
+        features = transform_gadget(step, &features)?;
+    }
+    model_gadget(pipeline.model(), &features)
+}
+
+/// Constraint version of `IsotonicCalibration::apply` on a range-checked margin
+///
+/// Every threshold is compared with the margin; the comparison bits are monotone, so adjacent
+/// differences select exactly one segment (or the clamped ends) as linear combinations of the
+/// breakpoints. The interpolation step q = floor(dy * (margin - x) / dx) is witnessed with q
+/// range-checked to the largest |dy| and the remainder to [0, dx).
+pub fn isotonic_gadget<F: PrimeField>(map: &IsotonicCalibration, margin: &FpVar<F>) -> Result<FpVar<F>, SynthesisError> {
+    let (thresholds, values) = (map.thresholds(), map.values());
+    assert!(thresholds.iter().chain(values).all(|v| v.unsigned_abs() < 1 << VALUE_BITS),
+            "calibration breakpoints exceed the circuit range");
+    if margin.is_constant() {
+        let margin = field_to_fixed(margin.value()?).expect("constant margin is a fixed-point value");
+        return Ok(FpVar::constant(fixed_to_field(map.apply(margin))));
+    }
+
+    let to_field = |value: i128| if value < 0 { -F::from((-value) as u128) } else { F::from(value as u128) };
+    let last = thresholds.len() - 1;
+    let reached = thresholds
+        .iter()
+        .map(|&x| fixed_le_gadget(&FpVar::constant(fixed_to_field(x)), margin).map(FpVar::from))
+        .collect::<Result<Vec<FpVar<F>>, _>>()?;
+
+    let below = FpVar::one() - &reached[0];
+    let mut start = &below * fixed_to_field::<F>(values[0]) + &reached[last] * fixed_to_field::<F>(values[last]);
+    let mut offset = FpVar::zero();
+    let mut rise = FpVar::zero();
+    let mut run = below + &reached[last];
+    for i in 0..last {
+        let segment = &reached[i] - &reached[i + 1];
+        start += &segment * fixed_to_field::<F>(values[i]);
+        offset += &segment * fixed_to_field::<F>(thresholds[i]);
+        rise += &segment * to_field(values[i + 1] as i128 - values[i] as i128);
+        run += &segment * to_field(thresholds[i + 1] as i128 - thresholds[i] as i128);
+    }
+    let numerator = rise * (margin - offset);
+
+    let quotient = margin.value().ok().and_then(field_to_fixed).map(|m| {
+        match thresholds.partition_point(|&x| x <= m) {
+            n if n == 0 || n > last => 0,
+            n => map.interpolate(n - 1, m) - values[n - 1],
+        }
+    });
+    let quotient_bits = magnitude_bits(values.windows(2).map(|w| (w[1] as i128 - w[0] as i128).abs()).max().unwrap_or(0));
+    let quotient = alloc_fixed_witness_bits(margin.cs(), quotient, quotient_bits)?;
+
+    let remainder = numerator - &quotient * &run;
+    let remainder_bits = magnitude_bits(thresholds.windows(2).map(|w| w[1] as i128 - w[0] as i128).max().unwrap_or(1) - 1);
+    enforce_bits(&remainder, remainder_bits)?;
+    enforce_bits(&(run - FpVar::one() - &remainder), remainder_bits)?;
+
+    fixed_add_gadget(&start, &quotient)
+}
+
+/// Constraint version of `BitSet::contains_value`: true iff `value` equals `c * unit` for a
+/// category `c` in the set
+///
+/// One equality test per category; `value` must be range-checked so that field equality is
+/// integer equality.
+pub fn category_gadget<F: PrimeField>(
+    value: &FpVar<F>,
+    categories: &BitSet,
+    unit: i64,
+) -> Result<Boolean<F>, SynthesisError> {
+    let matches = categories
+        .values(unit)
+        .map(|member| value.is_eq(&FpVar::constant(fixed_to_field(member))))
+        .collect::<Result<Vec<_>, _>>()?;
+    if matches.is_empty() {
+        return Ok(Boolean::FALSE);
+    }
+    Boolean::kary_or(&matches)
+}
+
+/// Constraints for a full model evaluation on allocated features
+pub fn model_gadget<F: PrimeField>(
+    model: &Model,
+    features: &[FpVar<F>],
+) -> Result<FpVar<F>, SynthesisError> {
+    model_gadget_bits(model, features, VALUE_BITS, VALUE_BITS)
+}
+
+/// Constraints for a model evaluation on features range-checked to the bounds' `comparison_bits`,
+/// with comparisons and accumulator checks sized from the bit-width analysis
+pub fn model_gadget_with_bounds<F: PrimeField>(
+    model: &Model,
+    features: &[FpVar<F>],
+    bounds: &ValueBounds,
+) -> Result<FpVar<F>, SynthesisError> {
+    model_gadget_bits(model, features, bounds.comparison_bits(), bounds.accumulator_bits())
+}
+
+fn model_gadget_bits<F: PrimeField>(
+    model: &Model,
+    features: &[FpVar<F>],
+    comparison_bits: usize,
+    accumulator_bits: usize,
+) -> Result<FpVar<F>, SynthesisError> {
+    let mut backend = R1csBackend::new(features.cs(), None);
+    lower_model(&mut backend, model, features, comparison_bits, accumulator_bits)
+}
+
+/// `CircuitBackend` over an arkworks constraint system
+///
+/// Features are private witnesses that the statement does not bind; `commit_public` allocates the
+/// claimed prediction as the public input and enforces that the circuit's output equals it.
+#[derive(Debug, Clone)]
+pub struct R1csBackend<F: PrimeField> {
+    cs: ConstraintSystemRef<F>,
+    /// Claimed scaled prediction (None when generating keys)
+    prediction: Option<i64>,
+}
+
+impl<F: PrimeField> R1csBackend<F> {
+    /// Backend allocating into `cs`, claiming `prediction` as the public output
+    pub fn new(cs: ConstraintSystemRef<F>, prediction: Option<i64>) -> Self {
+        R1csBackend { cs, prediction }
+    }
+}
+
+impl<F: PrimeField> CircuitBackend for R1csBackend<F> {
+    type Value = FpVar<F>;
+    type Bit = Boolean<F>;
+    type Error = SynthesisError;
+    type Output = ConstraintSystemRef<F>;
+
+    fn private_input(&mut self, value: Option<i64>, bits: usize) -> Result<FpVar<F>, SynthesisError> {
+        alloc_fixed_witness_bits(self.cs.clone(), value, bits)
+    }
+
+    fn constant(&mut self, value: i64) -> Result<FpVar<F>, SynthesisError> {
+        Ok(FpVar::constant(fixed_to_field(value)))
+    }
+
+    fn lower_le(&mut self, a: &FpVar<F>, b: &FpVar<F>, bits: usize) -> Result<Boolean<F>, SynthesisError> {
+        fixed_le_gadget_bits(a, b, bits)
+    }
+
+    fn lower_categories(
+        &mut self,
+        value: &FpVar<F>,
+        categories: &BitSet,
+        unit: i64,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        category_gadget(value, categories, unit)
+    }
+
+    fn lower_select(
+        &mut self,
+        bit: &Boolean<F>,
+        when_true: &FpVar<F>,
+        when_false: &FpVar<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        bit.select(when_true, when_false)
+    }
+
+    fn lower_add(&mut self, a: &FpVar<F>, b: &FpVar<F>, bits: usize) -> Result<FpVar<F>, SynthesisError> {
+        fixed_add_gadget_bits(a, b, bits)
+    }
+
+    fn lower_average(
+        &mut self,
+        sum: &FpVar<F>,
+        num_trees: usize,
+        base_score: i64,
+        bits: usize,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        average_gadget(sum, num_trees, base_score, bits)
+    }
+
+    fn commit_public(&mut self, prediction: &FpVar<F>, _features: &[FpVar<F>]) -> Result<(), SynthesisError> {
+        let claimed = FpVar::new_input(self.cs.clone(), || {
+            self.prediction.map(fixed_to_field::<F>).ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        prediction.enforce_equal(&claimed)
+    }
+
+    fn finalize(self) -> Result<ConstraintSystemRef<F>, SynthesisError> {
+        Ok(self.cs)
+    }
+}
+
+/// Constraint version of `fixed_argmax`: index and value of the first largest margin
+///
+/// Margins must be range-checked (e.g. outputs of `model_gadget`).
+pub fn argmax_gadget<F: PrimeField>(
+    margins: &[FpVar<F>],
+) -> Result<(FpVar<F>, FpVar<F>), SynthesisError> {
+    assert!(!margins.is_empty(), "argmax of an empty margin list");
+
+    let mut best_index = FpVar::zero();
+    let mut best = margins[0].clone();
+    for (i, margin) in margins.iter().enumerate().skip(1) {
+        let keep = fixed_le_gadget(margin, &best)?;
+        best_index = keep.select(&best_index, &FpVar::constant(F::from(i as u64)))?;
+        best = keep.select(&best, margin)?;
+    }
+
+    Ok((best_index, best))
+}
+
+/// Constraint version of `fixed_mean` on range-checked values
+///
+/// The quotient q = floor((2 * sum + n) / (2n)) is witnessed and range-checked to |q| < 2^62,
+/// and the remainder 2 * sum + n - 2n * q to [0, 2n).
+pub fn mean_gadget<F: PrimeField>(values: &[FpVar<F>]) -> Result<FpVar<F>, SynthesisError> {
+    assert!(!values.is_empty(), "mean of no values");
+    let n = values.len() as u64;
+    let sum: FpVar<F> = values.iter().sum();
+
+    let native = values
+        .iter()
+        .map(|v| v.value().ok().and_then(field_to_fixed))
+        .collect::<Option<Vec<i64>>>()
+        .and_then(|values| crate::fixed_mean(&values));
+    let quotient = if sum.is_constant() {
+        FpVar::constant(fixed_to_field(native.expect("constant sum has a value")))
+    } else {
+        alloc_fixed_witness(sum.cs(), native)?
+    };
+    let remainder = sum.double()? + FpVar::constant(F::from(n)) - &quotient * F::from(2 * n);
+    let remainder_bits = magnitude_bits(2 * n as i128 - 1);
+    enforce_bits(&remainder, remainder_bits)?;
+    enforce_bits(&(FpVar::constant(F::from(2 * n - 1)) - &remainder), remainder_bits)?;
+    Ok(quotient)
+}
+
+/// The `rank`-th smallest (1-based) of range-checked values, without sorting in the circuit
+///
+/// The result y is witnessed and range-checked; fewer than `rank` values may lie below it and at
+/// least `rank` values must lie at or below it, which also forces y to be one of the values.
+/// Costs two comparisons per value.
+pub fn order_statistic_gadget<F: PrimeField>(values: &[FpVar<F>], rank: usize) -> Result<FpVar<F>, SynthesisError> {
+    assert!((1..=values.len()).contains(&rank), "rank {} out of 1..={}", rank, values.len());
+
+    let cs = values.iter().fold(ConstraintSystemRef::None, |cs, v| cs.or(v.cs()));
+    let native = values.iter().map(|v| v.value().ok().and_then(field_to_fixed)).collect::<Option<Vec<i64>>>();
+    let y = alloc_fixed_witness(cs, native.map(|mut sorted| {
+        sorted.sort_unstable();
+        sorted[rank - 1]
+    }))?;
+    enforce_order_statistic(values, rank, &y)?;
+    Ok(y)
+}
+
+/// Enforce that the range-checked `y` is the `rank`-th smallest of `values`
+fn enforce_order_statistic<F: PrimeField>(values: &[FpVar<F>], rank: usize, y: &FpVar<F>) -> Result<(), SynthesisError> {
+    let mut below = FpVar::zero();
+    let mut at_or_below = FpVar::zero();
+    for value in values {
+        below += FpVar::from(!fixed_le_gadget(y, value)?);
+        at_or_below += FpVar::from(fixed_le_gadget(value, y)?);
+    }
+    // Both counts lie in [0, n], so a negative difference wraps out of range
+    let count_bits = magnitude_bits(values.len() as i128);
+    enforce_bits(&(FpVar::constant(F::from(rank as u64 - 1)) - below), count_bits)?;
+    enforce_bits(&(at_or_below - FpVar::constant(F::from(rank as u64))), count_bits)?;
+    Ok(())
+}
+
+/// Constraint version of `fixed_percentile` (nearest rank) on range-checked values
+pub fn percentile_gadget<F: PrimeField>(values: &[FpVar<F>], percent: i64) -> Result<FpVar<F>, SynthesisError> {
+    order_statistic_gadget(values, crate::percentile_rank(values.len(), percent))
+}
+
+/// Circuit proving knowledge of private a, b with public result `a <= b`
+#[derive(Debug, Clone, Default)]
+pub struct FixedLeGadget {
+    pub a: Option<i64>,
+    pub b: Option<i64>,
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for FixedLeGadget {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let result = Boolean::new_input(cs.clone(), || {
+            self.a
+                .zip(self.b)
+                .map(|(a, b)| a <= b)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let a = alloc_fixed_witness(cs.clone(), self.a)?;
+        let b = alloc_fixed_witness(cs, self.b)?;
+        fixed_le_gadget(&a, &b)?.enforce_equal(&result)
+    }
+}
+
+/// Circuit proving knowledge of private a, b with public sum `a + b`
+#[derive(Debug, Clone, Default)]
+pub struct FixedAddGadget {
+    pub a: Option<i64>,
+    pub b: Option<i64>,
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for FixedAddGadget {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let sum = FpVar::new_input(cs.clone(), || {
+            self.a
+                .zip(self.b)
+                .map(|(a, b)| fixed_to_field::<F>(a.saturating_add(b)))
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let a = alloc_fixed_witness(cs.clone(), self.a)?;
+        let b = alloc_fixed_witness(cs, self.b)?;
+        fixed_add_gadget(&a, &b)?.enforce_equal(&sum)
+    }
+}
+
+/// Constraint version of `OutputGuard::apply`: the guarded output and whether it was flagged
+///
+/// `output` must be range-checked to |x| < 2^62 (as every model output is).
+pub fn guard_gadget<F: PrimeField>(
+    guard: &OutputGuard,
+    output: &FpVar<F>,
+) -> Result<(FpVar<F>, Boolean<F>), SynthesisError> {
+    let min = FpVar::constant(fixed_to_field(guard.min));
+    let max = FpVar::constant(fixed_to_field(guard.max));
+    let above_min = fixed_le_gadget(&min, output)?;
+    let below_max = fixed_le_gadget(output, &max)?;
+    let flagged = !(&above_min & &below_max);
+    let guarded = if guard.clamp {
+        let upper = below_max.select(output, &max)?;
+        above_min.select(&upper, &min)?
+    } else {
+        output.clone()
+    };
+    Ok((guarded, flagged))
+}
+
+/// Circuit proving that private features produce the public prediction under `model`
+#[derive(Debug, Clone)]
+pub struct ModelCircuit<'a> {
+    pub model: &'a Model,
+    /// Private scaled features (None when generating keys)
+    pub features: Option<Vec<i64>>,
+    /// Public scaled prediction
+    pub prediction: Option<i64>,
+    /// Value bounds to size the range checks from (full 62-bit checks if None)
+    pub bounds: Option<ValueBounds>,
+    /// Output guard whose decision becomes a second public input
+    pub guard: Option<OutputGuard>,
+}
+
+impl<'a> ModelCircuit<'a> {
+    /// Circuit for a concrete input, with the prediction computed by the simulator
+    pub fn new(model: &'a Model, features: Vec<i64>) -> Self {
+        let prediction = model.predict(&features);
+        ModelCircuit { model, features: Some(features), prediction: Some(prediction), bounds: None, guard: None }
+    }
+
+    /// Circuit shape without witnesses, for key generation
+    pub fn blank(model: &'a Model) -> Self {
+        ModelCircuit { model, features: None, prediction: None, bounds: None, guard: None }
+    }
+
+    /// Size the range checks from a bit-width analysis of `model`
+    ///
+    /// Features are then range-checked to the bounds' `comparison_bits`, so an input outside the
+    /// analyzed range makes the constraints unsatisfiable.
+    pub fn with_bounds(mut self, bounds: ValueBounds) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Apply an output guard inside the circuit
+    ///
+    /// The public prediction becomes the guarded one, and a second public input is 1 exactly
+    /// when the model's output was outside the guard range.
+    pub fn with_guard(mut self, guard: OutputGuard) -> Self {
+        assert!(
+            guard.min.unsigned_abs() < 1 << VALUE_BITS && guard.max.unsigned_abs() < 1 << VALUE_BITS,
+            "guard bounds exceed the circuit range"
+        );
+        self.prediction = self.prediction.map(|prediction| guard.apply(prediction).0);
+        self.guard = Some(guard);
+        self
+    }
+}
+
+impl<'a, F: PrimeField> ConstraintSynthesizer<F> for ModelCircuit<'a> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let mut backend = R1csBackend::new(cs.clone(), self.prediction);
+        let (comparison_bits, accumulator_bits) = self
+            .bounds
+            .as_ref()
+            .map_or((VALUE_BITS, VALUE_BITS), |bounds| (bounds.comparison_bits(), bounds.accumulator_bits()));
+        let features = (0..self.model.num_features())
+            .map(|i| backend.private_input(self.features.as_ref().map(|f| f[i]), comparison_bits))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let output = lower_model(&mut backend, self.model, &features, comparison_bits, accumulator_bits)?;
+        let Some(guard) = &self.guard else {
+            return backend.commit_public(&output, &features);
+        };
+        let (guarded, flagged) = guard_gadget(guard, &output)?;
+        backend.commit_public(&guarded, &features)?;
+        let flag_value = self.features.as_ref().map(|f| guard.decide(self.model.predict(f)).is_flagged());
+        let flag = Boolean::new_input(cs, || flag_value.ok_or(SynthesisError::AssignmentMissing))?;
+        flagged.enforce_equal(&flag)
+    }
+}
+
+/// Circuit proving one stage of a cascade: private features produce the public prediction
+///
+/// The `First` stage constrains the first-stage ensemble and that its output lies outside the
+/// ambiguous band; the `Full` stage additionally constrains the full model and that the first
+/// output lies inside the band. Each stage has its own keys, so the verifier knows which stage a
+/// proof covers and early exits never pay for the full model.
+#[derive(Debug, Clone)]
+pub struct CascadeCircuit<'a> {
+    pub cascade: &'a Cascade,
+    pub stage: CascadeStage,
+    /// Private scaled features (None when generating keys)
+    pub features: Option<Vec<i64>>,
+    /// Public scaled prediction
+    pub prediction: Option<i64>,
+}
+
+impl<'a> CascadeCircuit<'a> {
+    /// Circuit for a concrete input, with the stage and prediction computed by the simulator
+    pub fn new(cascade: &'a Cascade, features: Vec<i64>) -> Self {
+        let (prediction, stage) = cascade.predict(&features);
+        CascadeCircuit::blank(cascade, stage).with_witness(features, prediction)
+    }
+
+    /// Circuit shape of one stage without witnesses, for key generation
+    pub fn blank(cascade: &'a Cascade, stage: CascadeStage) -> Self {
+        let (low, high) = cascade.band();
+        assert!(
+            low.unsigned_abs() < 1 << VALUE_BITS && high.unsigned_abs() < 1 << VALUE_BITS,
+            "cascade band exceeds the circuit range"
+        );
+        CascadeCircuit { cascade, stage, features: None, prediction: None }
+    }
+
+    fn with_witness(mut self, features: Vec<i64>, prediction: i64) -> Self {
+        self.features = Some(features);
+        self.prediction = Some(prediction);
+        self
+    }
+}
+
+impl<'a, F: PrimeField> ConstraintSynthesizer<F> for CascadeCircuit<'a> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let prediction = FpVar::new_input(cs.clone(), || {
+            self.prediction
+                .map(fixed_to_field::<F>)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // An early exit only reads the features of the first stage
+        let num_features = match self.stage {
+            CascadeStage::First => self.cascade.first().num_features(),
+            CascadeStage::Full => self.cascade.num_features(),
+        };
+        let features = (0..num_features)
+            .map(|i| alloc_fixed_witness(cs.clone(), self.features.as_ref().map(|f| f[i])))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let first = model_gadget(self.cascade.first(), &features)?;
+        let (low, high) = self.cascade.band();
+        let above_low = fixed_le_gadget(&FpVar::constant(fixed_to_field(low)), &first)?;
+        let below_high = fixed_le_gadget(&first, &FpVar::constant(fixed_to_field(high)))?;
+        let ambiguous = &above_low & &below_high;
+        match self.stage {
+            CascadeStage::First => {
+                ambiguous.enforce_equal(&Boolean::FALSE)?;
+                first.enforce_equal(&prediction)
+            }
+            CascadeStage::Full => {
+                ambiguous.enforce_equal(&Boolean::TRUE)?;
+                model_gadget(self.cascade.full(), &features)?.enforce_equal(&prediction)
+            }
+        }
+    }
+}
+
+/// Field element as a signed fixed-point value, if it fits in i64
+pub fn field_to_fixed<F: PrimeField>(value: F) -> Option<i64> {
+    let bits = value.into_bigint().to_bits_le();
+    let negated = (-value).into_bigint().to_bits_le();
+    let fits = |bits: &[bool]| bits.iter().skip(63).all(|b| !b);
+
+    let to_u64 = |bits: &[bool]| {
+        bits.iter().take(63).enumerate().fold(0u64, |acc, (i, &b)| acc | ((b as u64) << i))
+    };
+    if fits(&bits) {
+        Some(to_u64(&bits) as i64)
+    } else if fits(&negated) {
+        Some(-(to_u64(&negated) as i64))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::{Combiner, Node};
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    fn test_fixed_le_gadget() {
+        for (a, b) in [(1, 2), (2, 1), (5, 5), (-7, 3), (3, -7), (-999990000000000, 120000000000)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            FixedLeGadget { a: Some(a), b: Some(b) }.generate_constraints(cs.clone()).unwrap();
+            assert!(cs.is_satisfied().unwrap(), "{} <= {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_fixed_add_gadget() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        FixedAddGadget { a: Some(15000000000), b: Some(-23000000000) }
+            .generate_constraints(cs.clone())
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // Values outside the circuit range cannot be witnessed
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        FixedAddGadget { a: Some(i64::MAX), b: Some(1) }.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_model_circuit_matches_simulation() {
+        let model = Model::from_xgboost_json(include_str!("../models/bst1_10.json")).unwrap();
+        let mut features = vec![0i64; model.num_features()];
+        features[34] = 130000000000;
+        features[22] = 9000000000;
+        features[77] = 40000000000;
+
+        let circuit = ModelCircuit::new(&model, features.clone());
+        let expected = circuit.prediction;
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        assert_eq!(field_to_fixed(cs.borrow().unwrap().instance_assignment[1]), expected);
+
+        // A wrong public prediction must not satisfy the constraints
+        let mut wrong = ModelCircuit::new(&model, features.clone());
+        wrong.prediction = wrong.prediction.map(|p| p + 1);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        wrong.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+
+        // The base score is a constant added to the accumulator
+        let biased = model.with_base_score(-5_000_000_000);
+        let circuit = ModelCircuit::new(&biased, features);
+        assert_eq!(circuit.prediction, expected.map(|p| p - 5_000_000_000));
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // Leaf-only trees add constants, whose range is checked natively
+        let stump = Model::new(vec![crate::Tree::new(Node::Leaf(7)), crate::Tree::new(Node::Leaf(-2))], 10)
+            .with_num_features(1);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        ModelCircuit::new(&stump, vec![0]).generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        assert_eq!(field_to_fixed(cs.borrow().unwrap().instance_assignment[1]), Some(5));
+    }
+
+    #[test]
+    fn test_bounds_shrink_model_circuit() {
+        let model = Model::from_xgboost_json(include_str!("../models/bst1_10.json")).unwrap();
+        let bounds = crate::ValueBounds::analyze_with_schema(&model, &crate::FeatureSchema::rainfall());
+        let mut features = vec![0i64; model.num_features()];
+        features[34] = 130000000000;
+        features[0] = crate::MISSING_VALUE;
+
+        let full = ConstraintSystem::<Fr>::new_ref();
+        ModelCircuit::new(&model, features.clone()).generate_constraints(full.clone()).unwrap();
+        let sized = ConstraintSystem::<Fr>::new_ref();
+        ModelCircuit::new(&model, features.clone())
+            .with_bounds(bounds.clone())
+            .generate_constraints(sized.clone())
+            .unwrap();
+        assert!(sized.is_satisfied().unwrap());
+        assert!(sized.num_constraints() < full.num_constraints() * 9 / 10);
+
+        // Inputs outside the analyzed range cannot be witnessed
+        features[5] = 1 << bounds.comparison_bits();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        ModelCircuit::new(&model, features).with_bounds(bounds).generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_bucketized_model_circuit() {
+        let model = Model::from_xgboost_json(include_str!("../models/bst1_10.json")).unwrap();
+        let bucketized = model.bucketize().unwrap();
+        let mut features = vec![0i64; model.num_features()];
+        features[34] = 130000000000;
+        features[0] = crate::MISSING_VALUE;
+
+        let raw = ConstraintSystem::<Fr>::new_ref();
+        let bounds = crate::ValueBounds::analyze_with_schema(&model, &crate::FeatureSchema::rainfall());
+        ModelCircuit::new(&model, features.clone()).with_bounds(bounds).generate_constraints(raw.clone()).unwrap();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        ModelCircuit::new(bucketized.model(), bucketized.buckets().bucketize(&features))
+            .with_bounds(bucketized.bounds())
+            .generate_constraints(cs.clone())
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        assert_eq!(field_to_fixed(cs.borrow().unwrap().instance_assignment[1]), Some(model.eval(&features)));
+        assert!(cs.num_constraints() < raw.num_constraints() / 2, "{} vs {}", cs.num_constraints(), raw.num_constraints());
+    }
+
+    #[test]
+    fn test_guarded_circuit() {
+        let stump = Model::new(vec![crate::Tree::new(Node::split(0, 0, Node::Leaf(-3), Node::Leaf(20)))], 1)
+            .with_num_features(1);
+        let guard = OutputGuard::new(0, 10);
+        let public = |circuit: ModelCircuit| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            assert!(cs.is_satisfied().unwrap());
+            let inputs = cs.borrow().unwrap().instance_assignment.clone();
+            (field_to_fixed(inputs[1]), field_to_fixed(inputs[2]))
+        };
+        assert_eq!(public(ModelCircuit::new(&stump, vec![-1]).with_guard(guard)), (Some(-3), Some(1)));
+        assert_eq!(public(ModelCircuit::new(&stump, vec![-1]).with_guard(guard.clamping())), (Some(0), Some(1)));
+        assert_eq!(public(ModelCircuit::new(&stump, vec![1]).with_guard(guard.clamping())), (Some(10), Some(1)));
+        let within = OutputGuard::new(-3, 20).clamping();
+        assert_eq!(public(ModelCircuit::new(&stump, vec![1]).with_guard(within)), (Some(20), Some(0)));
+
+        // Claiming the unclamped output under a clamping guard
+        let mut circuit = ModelCircuit::new(&stump, vec![-1]).with_guard(guard.clamping());
+        circuit.prediction = Some(-3);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_cascade_circuit() {
+        let model = crate::builtin_model();
+        let features = vec![0i64; model.num_features()];
+        let first_output = Cascade::from_prefix(model.clone(), 2, 0, 0).unwrap().first().eval(&features);
+        let count = |circuit: CascadeCircuit| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            (cs.is_satisfied().unwrap(), cs.num_constraints())
+        };
+
+        // The zero input exits early unless the band holds its first-stage output
+        let easy = Cascade::from_prefix(model.clone(), 2, first_output + 1, first_output + 1).unwrap();
+        let ambiguous = Cascade::from_prefix(model.clone(), 2, first_output, first_output).unwrap();
+        let exit = CascadeCircuit::new(&easy, features.clone());
+        let deferred = CascadeCircuit::new(&ambiguous, features.clone());
+        assert_eq!((exit.stage, exit.prediction), (CascadeStage::First, Some(first_output)));
+        assert_eq!((deferred.stage, deferred.prediction), (CascadeStage::Full, Some(model.eval(&features))));
+        let (exit_ok, exit_cost) = count(exit);
+        let (deferred_ok, deferred_cost) = count(deferred);
+        assert!(exit_ok && deferred_ok);
+        assert!(exit_cost * 3 < deferred_cost * 2, "{} vs {}", exit_cost, deferred_cost);
+
+        // Proving the other stage than the simulator took is unsatisfiable
+        let wrong_exit = CascadeCircuit::blank(&ambiguous, CascadeStage::First);
+        assert!(!count(wrong_exit.with_witness(features.clone(), first_output)).0);
+        let wrong_full = CascadeCircuit::blank(&easy, CascadeStage::Full);
+        assert!(!count(wrong_full.with_witness(features.clone(), model.eval(&features))).0);
+    }
+
+    #[test]
+    fn test_shared_comparisons_save_constraints() {
+        let tree = |offset: i64| {
+            crate::Tree::new(Node::split(
+                0,
+                10 + offset,
+                Node::split(1, -20 + offset, Node::Leaf(1), Node::Leaf(2)),
+                Node::split(1, 30 + offset, Node::Leaf(3), Node::Leaf(4)),
+            ))
+        };
+        let repeated = Model::new(vec![tree(0), tree(0), tree(0)], 10).with_num_features(2);
+        let distinct = Model::new(vec![tree(0), tree(1), tree(2)], 10).with_num_features(2);
+        assert_eq!(repeated.share_comparisons().stats().tests, 3);
+        assert_eq!(distinct.share_comparisons().stats().tests, 9);
+
+        let count = |model: &Model, features: Vec<i64>| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            ModelCircuit::new(model, features.clone()).generate_constraints(cs.clone()).unwrap();
+            assert!(cs.is_satisfied().unwrap());
+            assert_eq!(field_to_fixed(cs.borrow().unwrap().instance_assignment[1]), Some(model.eval(&features)));
+            cs.num_constraints()
+        };
+        let saved = count(&distinct, vec![11, 0]) - count(&repeated, vec![11, 0]);
+        // Six comparisons of 64 bits each are no longer constrained
+        assert!(saved >= 6 * 64, "saved {} constraints", saved);
+    }
+
+    #[test]
+    fn test_averaged_model_circuit() {
+        let model = Model::from_xgboost_json(include_str!("../models/bst1_10.json"))
+            .unwrap()
+            .with_combiner(Combiner::Average)
+            .with_base_score(3);
+        let bounds = crate::ValueBounds::analyze_with_schema(&model, &crate::FeatureSchema::rainfall());
+        for (i, feature) in [(34, 130000000000), (77, -40000000000), (0, crate::MISSING_VALUE)] {
+            let mut features = vec![0i64; model.num_features()];
+            features[i] = feature;
+            for bounded in [false, true] {
+                let mut circuit = ModelCircuit::new(&model, features.clone());
+                if bounded {
+                    circuit = circuit.with_bounds(bounds.clone());
+                }
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                circuit.generate_constraints(cs.clone()).unwrap();
+                assert!(cs.is_satisfied().unwrap());
+            }
+
+            // Predictions rounded the other way are rejected
+            for offset in [-1, 1] {
+                let mut wrong = ModelCircuit::new(&model, features.clone());
+                wrong.prediction = wrong.prediction.map(|p| p + offset);
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                wrong.generate_constraints(cs.clone()).unwrap();
+                assert!(!cs.is_satisfied().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_linear_gadget() {
+        let model = LinearModel::from_f64(&[0.75, -1.5, 3.0e-10], -0.3, 10_000_000_000);
+        for inputs in [[1.25, -0.4, 0.5], [-7.0, 2.5, -0.5], [0.0, 0.0, 1.0e-10]] {
+            let features: Vec<i64> = inputs.iter().map(|&x| crate::to_fixed_point(x)).collect();
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let vars: Vec<FpVar<Fr>> = features
+                .iter()
+                .map(|&x| alloc_fixed_witness(cs.clone(), Some(x)).unwrap())
+                .collect();
+            let output = linear_gadget(&model, &vars).unwrap();
+
+            assert_eq!(field_to_fixed(output.value().unwrap()), Some(model.eval(&features)));
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_isotonic_gadget() {
+        let probabilities = IsotonicCalibration::from_f64(&[-2.0, -0.5, 0.0, 1.5, 3.0], &[0.02, 0.1, 0.1, 0.7, 0.97]).unwrap();
+        let decreasing = IsotonicCalibration::new(vec![-10, 0, 7], vec![50, 20, -1]).unwrap();
+        for (map, margins) in [
+            (&probabilities, vec![-25_000_000_000i64, -20_000_000_000, -3_333_333_333, 1, 14_999_999_999, 40_000_000_000]),
+            (&decreasing, vec![-11, -10, -9, -1, 0, 3, 6, 7, 8]),
+        ] {
+            for margin in margins {
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                let var = alloc_fixed_witness(cs.clone(), Some(margin)).unwrap();
+                let output = isotonic_gadget(map, &var).unwrap();
+
+                assert_eq!(field_to_fixed(output.value().unwrap()), Some(map.apply(margin)), "{}", margin);
+                assert!(cs.is_satisfied().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_pipeline_gadget() {
+        use crate::{to_fixed_point, ConstantImputer, MinMaxScaler, StandardScaler};
+
+        let model = crate::builtin_model();
+        let pipeline = Pipeline::new(model.clone())
+            .with_step(ConstantImputer::from_f64(&[0.5, -1.0, 2.0]))
+            .with_step(StandardScaler::from_f64(&[0.1, 1.5], &[0.8, 2.5]))
+            .with_step(MinMaxScaler::from_f64(&[0.5, 0.25, 4.0], &[0.1, -0.2, 0.0]));
+        let mut raw = vec![to_fixed_point(0.2); model.num_features()];
+        for (i, value) in [MISSING_VALUE, to_fixed_point(-7.25), MISSING_VALUE, MISSING_VALUE].into_iter().enumerate() {
+            raw[i] = value;
+        }
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let vars: Vec<FpVar<Fr>> = raw.iter().map(|&x| alloc_fixed_witness(cs.clone(), Some(x)).unwrap()).collect();
+        let output = pipeline_gadget(&pipeline, &vars).unwrap();
+
+        assert_eq!(field_to_fixed(output.value().unwrap()), Some(pipeline.predict(&raw)));
+        assert!(cs.is_satisfied().unwrap());
+
+        // Features after imputing and standardizing, with feature 3 still missing
+        let steps = pipeline.steps();
+        let transformed = transform_gadget(&steps[1], &transform_gadget(&steps[0], &vars).unwrap()).unwrap();
+        let mut expected = raw.clone();
+        steps[0].apply(&mut expected);
+        steps[1].apply(&mut expected);
+        assert_eq!(expected[3], MISSING_VALUE);
+        for (var, &value) in transformed.iter().zip(&expected) {
+            assert_eq!(field_to_fixed(var.value().unwrap()), Some(value));
+        }
+    }
+
+    #[test]
+    fn test_categorical_split_circuit() {
+        use crate::{Ensemble, Tree};
+
+        let unit = crate::PRECISION_MULTIPLIER;
+        let wide: BitSet = [0, 3, 5, 9, 64].into_iter().collect();
+        let tree = Node::categorical(
+            0,
+            [1, 3].into_iter().collect(),
+            unit,
+            Node::Leaf(10),
+            Node::categorical(1, wide, unit, Node::Leaf(20), Node::split(1, 0, Node::Leaf(30), Node::Leaf(40))),
+        );
+        let model = Ensemble::new(vec![Tree::new(tree)], unit);
+        for features in [[3 * unit, 0], [2 * unit, 64 * unit], [unit + 1, 5 * unit], [-unit, -1], [MISSING_VALUE, 7 * unit]] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            ModelCircuit::new(&model, features.to_vec()).generate_constraints(cs.clone()).unwrap();
+            assert!(cs.is_satisfied().unwrap(), "{:?}", features);
+        }
+
+        // A prediction from the other branch is rejected
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let mut circuit = ModelCircuit::new(&model, vec![3 * unit, 0]);
+        circuit.prediction = Some(20);
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_argmax_gadget() {
+        for margins in [vec![1i64, 9, 4], vec![5, 5, 2, 5], vec![-30000000000, -10000000000]] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let vars: Vec<FpVar<Fr>> = margins
+                .iter()
+                .map(|&m| alloc_fixed_witness(cs.clone(), Some(m)).unwrap())
+                .collect();
+            let (index, best) = argmax_gadget(&vars).unwrap();
+
+            let expected = crate::fixed_argmax(&margins);
+            assert_eq!(field_to_fixed(index.value().unwrap()), Some(expected as i64));
+            assert_eq!(field_to_fixed(best.value().unwrap()), Some(margins[expected]));
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_statistics_gadgets() {
+        let values = [40i64, -10, 25, 40, 7, -999990000000000];
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let vars: Vec<FpVar<Fr>> = values.iter().map(|&v| alloc_fixed_witness(cs.clone(), Some(v)).unwrap()).collect();
+        let value = |var: FpVar<Fr>| field_to_fixed(var.value().unwrap());
+
+        assert_eq!(value(mean_gadget(&vars).unwrap()), crate::fixed_mean(&values));
+        assert_eq!(value(mean_gadget(&vars[..2]).unwrap()), Some(15));
+        for percent in [0.0, 10.0, 50.0, 66.7, 100.0] {
+            let percent = crate::to_fixed_point(percent);
+            assert_eq!(value(percentile_gadget(&vars, percent).unwrap()), crate::fixed_percentile(&values, percent));
+        }
+        assert!(cs.is_satisfied().unwrap());
+
+        // A witness that is not a member, or sits at the wrong rank, is rejected
+        let values = [40i64, -10, 25, 40, 7];
+        for (claimed, rank, accepted) in [(25, 3, true), (26, 3, false), (24, 3, false), (40, 4, true), (40, 5, true), (25, 4, false)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let vars: Vec<FpVar<Fr>> = values.iter().map(|&v| alloc_fixed_witness(cs.clone(), Some(v)).unwrap()).collect();
+            let y = alloc_fixed_witness(cs.clone(), Some(claimed)).unwrap();
+            enforce_order_statistic(&vars, rank, &y).unwrap();
+            assert_eq!(cs.is_satisfied().unwrap(), accepted, "claimed {} at rank {}", claimed, rank);
+        }
+    }
+
+    #[test]
+    fn test_field_to_fixed_roundtrip() {
+        for v in [0i64, 1, -1, 220286213, -999990000000000, i64::MAX, i64::MIN + 1] {
+            assert_eq!(field_to_fixed(fixed_to_field::<Fr>(v)), Some(v));
+        }
+    }
+    #[test]
+    fn test_constraint_estimate_matches_circuit() {
+        use crate::{Ensemble, Tree};
+
+        let count = |circuit: ModelCircuit| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            cs.num_constraints()
+        };
+        let unit = crate::PRECISION_MULTIPLIER;
+        let wide: BitSet = [0, 3, 5, 9, 64].into_iter().collect();
+        let categorical = Node::categorical(
+            0,
+            [1, 3].into_iter().collect(),
+            unit,
+            Node::Leaf(10),
+            Node::split(1, 0, Node::Leaf(30), Node::categorical(1, wide, unit, Node::Leaf(5), Node::Leaf(6))),
+        );
+        let builtin = crate::builtin_model();
+        let models = [
+            Ensemble::new(vec![Tree::new(Node::Leaf(4)), Tree::new(categorical)], unit),
+            builtin.clone(),
+            builtin.with_combiner(Combiner::Average).with_base_score(3),
+        ];
+        for model in &models {
+            let features = vec![0i64; model.num_features()];
+            let report = model.inspect();
+            assert_eq!(report.constraints.total(), count(ModelCircuit::new(model, features.clone())));
+
+            let bounds = crate::ValueBounds::analyze(model, 1 << 40);
+            let bounded = report.with_bounds(model, &bounds).bounded.unwrap();
+            assert_eq!(bounded.total(), count(ModelCircuit::new(model, features).with_bounds(bounds)));
+        }
+    }
+}
@@ -0,0 +1,378 @@
+// Model registry
+// Holds several ensembles keyed by id and version and routes each request to the one it names.
+// Every id has an active version, used when a request names no version, and the registry has a
+// default id, used when a request names no model. Registering a version activates it, so an
+// upgrade is one `register` call and `activate` rolls it back. Entries are handed out as `Arc`s
+// and replaced under a write lock: a request in flight finishes on the model it started with and
+// the next request sees the new one, so models are swapped without restarting the server.
+//
+// With the `plonky2` feature each entry builds its proving circuit on first use, so proofs are
+// routed the same way as predictions and a swapped-out model's circuit is dropped with it.
+//
+// The gRPC and REST servers score through a `Predictor`. A registry is one that evaluates its
+// default model; the `predict` binary wraps its registry in one that also goes through the
+// prediction cache, replay log, shadow model and Prometheus counters of the JSONL server.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+#[cfg(feature = "plonky2")]
+use std::sync::OnceLock;
+
+use crate::checked::PredictError;
+use crate::ensemble::Ensemble;
+
+/// Error raised when a request names a model the registry does not hold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    /// No model is registered under the id
+    UnknownModel(String),
+    /// The model has no such version
+    UnknownVersion { id: String, version: String },
+    /// The version is the active one of a model that has others; activate another one first
+    ActiveVersion { id: String, version: String },
+    /// Nothing is registered yet
+    Empty,
+    /// The model rejected the features
+    Predict(PredictError),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::UnknownModel(id) => write!(f, "unknown model '{}'", id),
+            RegistryError::UnknownVersion { id, version } => write!(f, "model '{}' has no version '{}'", id, version),
+            RegistryError::ActiveVersion { id, version } => {
+                write!(f, "version '{}' is the active version of model '{}'", version, id)
+            }
+            RegistryError::Empty => write!(f, "no model is registered"),
+            RegistryError::Predict(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// A model with the id and version it is registered under
+pub struct RegisteredModel {
+    pub id: String,
+    pub version: String,
+    pub model: Ensemble,
+    /// SHA-256 of the model's binary encoding
+    pub content_hash: [u8; 32],
+    #[cfg(feature = "plonky2")]
+    circuit: OnceLock<crate::zk::plonky2::Plonky2Circuit>,
+}
+
+impl RegisteredModel {
+    fn new(id: &str, version: &str, model: Ensemble) -> RegisteredModel {
+        RegisteredModel {
+            id: id.to_string(),
+            version: version.to_string(),
+            content_hash: model.content_hash(),
+            model,
+            #[cfg(feature = "plonky2")]
+            circuit: OnceLock::new(),
+        }
+    }
+
+    /// Prove the prediction for `features` with this model's circuit, building it on first use
+    #[cfg(feature = "plonky2")]
+    pub fn prove(
+        &self,
+        features: &[i64],
+    ) -> Result<crate::zk::plonky2::ModelProof, crate::zk::plonky2::Plonky2Error> {
+        self.circuit.get_or_init(|| crate::zk::plonky2::Plonky2Circuit::new(&self.model)).prove_with_info(features)
+    }
+}
+
+impl fmt::Debug for RegisteredModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegisteredModel")
+            .field("id", &self.id)
+            .field("version", &self.version)
+            .field("trees", &self.model.num_trees())
+            .field("content_hash", &crate::to_hex(&self.content_hash))
+            .finish()
+    }
+}
+
+/// Versions of one model id
+#[derive(Debug, Default)]
+struct Versions {
+    active: String,
+    versions: BTreeMap<String, Arc<RegisteredModel>>,
+}
+
+#[derive(Debug, Default)]
+struct Models {
+    default: Option<String>,
+    ids: BTreeMap<String, Versions>,
+}
+
+/// Listing entry of [`ModelRegistry::models`]
+#[derive(Debug, Clone)]
+pub struct ModelListing {
+    pub entry: Arc<RegisteredModel>,
+    /// Whether requests without a version get this version
+    pub active: bool,
+    /// Whether requests without a model get this model's active version
+    pub default: bool,
+}
+
+/// Thread-safe set of models keyed by id and version
+#[derive(Debug, Default)]
+pub struct ModelRegistry {
+    models: RwLock<Models>,
+}
+
+impl ModelRegistry {
+    /// Empty registry
+    pub fn new() -> ModelRegistry {
+        ModelRegistry::default()
+    }
+
+    /// Register a model and make it the active version of its id
+    ///
+    /// The first id registered becomes the default. Registering an existing id and version
+    /// replaces that entry.
+    ///
+    /// # Arguments
+    /// * `id` - Model id requests route on
+    /// * `version` - Version of the model under that id
+    /// * `model` - The ensemble
+    ///
+    /// # Returns
+    /// * `Option<Arc<RegisteredModel>>` - The entry previously active under `id`, if any
+    pub fn register(&self, id: &str, version: &str, model: Ensemble) -> Option<Arc<RegisteredModel>> {
+        let entry = Arc::new(RegisteredModel::new(id, version, model));
+        let mut models = self.models.write().unwrap();
+        if models.default.is_none() {
+            models.default = Some(id.to_string());
+        }
+        let versions = models.ids.entry(id.to_string()).or_default();
+        let previous = versions.versions.get(&versions.active).cloned();
+        versions.versions.insert(version.to_string(), entry);
+        versions.active = version.to_string();
+        previous
+    }
+
+    /// Make a registered version the active one of its id (e.g. to roll an upgrade back)
+    pub fn activate(&self, id: &str, version: &str) -> Result<(), RegistryError> {
+        let mut models = self.models.write().unwrap();
+        let versions = models.ids.get_mut(id).ok_or_else(|| RegistryError::UnknownModel(id.to_string()))?;
+        if !versions.versions.contains_key(version) {
+            return Err(RegistryError::UnknownVersion { id: id.to_string(), version: version.to_string() });
+        }
+        versions.active = version.to_string();
+        Ok(())
+    }
+
+    /// Route requests that name no model to `id`
+    pub fn set_default(&self, id: &str) -> Result<(), RegistryError> {
+        let mut models = self.models.write().unwrap();
+        if !models.ids.contains_key(id) {
+            return Err(RegistryError::UnknownModel(id.to_string()));
+        }
+        models.default = Some(id.to_string());
+        Ok(())
+    }
+
+    /// Drop a version; the active version can only be removed when it is the id's last one
+    ///
+    /// Removing the default id's last version makes the first remaining id (in order) the default.
+    pub fn remove(&self, id: &str, version: &str) -> Result<Arc<RegisteredModel>, RegistryError> {
+        let mut models = self.models.write().unwrap();
+        let versions = models.ids.get_mut(id).ok_or_else(|| RegistryError::UnknownModel(id.to_string()))?;
+        let unknown = || RegistryError::UnknownVersion { id: id.to_string(), version: version.to_string() };
+        if !versions.versions.contains_key(version) {
+            return Err(unknown());
+        }
+        if versions.active == version && versions.versions.len() > 1 {
+            return Err(RegistryError::ActiveVersion { id: id.to_string(), version: version.to_string() });
+        }
+        let removed = versions.versions.remove(version).ok_or_else(unknown)?;
+        if versions.versions.is_empty() {
+            models.ids.remove(id);
+            if models.default.as_deref() == Some(id) {
+                models.default = models.ids.keys().next().cloned();
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Model a request is routed to
+    ///
+    /// # Arguments
+    /// * `id` - Model id, or `None` for the default model
+    /// * `version` - Version, or `None` for the id's active version
+    ///
+    /// # Returns
+    /// * `Result<Arc<RegisteredModel>, RegistryError>` - The entry, which stays valid when it is swapped out
+    pub fn get(&self, id: Option<&str>, version: Option<&str>) -> Result<Arc<RegisteredModel>, RegistryError> {
+        let models = self.models.read().unwrap();
+        let id = match id {
+            Some(id) => id,
+            None => models.default.as_deref().ok_or(RegistryError::Empty)?,
+        };
+        let versions = models.ids.get(id).ok_or_else(|| RegistryError::UnknownModel(id.to_string()))?;
+        let version = version.unwrap_or(&versions.active);
+        versions
+            .versions
+            .get(version)
+            .cloned()
+            .ok_or_else(|| RegistryError::UnknownVersion { id: id.to_string(), version: version.to_string() })
+    }
+
+    /// Predict with the model a request is routed to (see [`ModelRegistry::get`])
+    ///
+    /// # Returns
+    /// * `Result<(Arc<RegisteredModel>, i64), RegistryError>` - The entry used and its scaled prediction
+    pub fn predict(
+        &self,
+        id: Option<&str>,
+        version: Option<&str>,
+        features: &[i64],
+    ) -> Result<(Arc<RegisteredModel>, i64), RegistryError> {
+        let entry = self.get(id, version)?;
+        let prediction = entry.model.try_eval(features).map_err(RegistryError::Predict)?;
+        Ok((entry, prediction))
+    }
+
+    /// Every registered version, ids and versions in order
+    pub fn models(&self) -> Vec<ModelListing> {
+        let models = self.models.read().unwrap();
+        let mut listings = Vec::new();
+        for (id, versions) in &models.ids {
+            for (version, entry) in &versions.versions {
+                listings.push(ModelListing {
+                    entry: entry.clone(),
+                    active: *version == versions.active,
+                    default: models.default.as_deref() == Some(id),
+                });
+            }
+        }
+        listings
+    }
+
+    /// Number of registered versions over all ids
+    pub fn len(&self) -> usize {
+        self.models.read().unwrap().ids.values().map(|versions| versions.versions.len()).sum()
+    }
+
+    /// Whether nothing is registered
+    pub fn is_empty(&self) -> bool {
+        self.models.read().unwrap().ids.is_empty()
+    }
+}
+
+/// Scoring path of the gRPC and REST servers
+///
+/// A server takes one entry per request (per batch for batches), validates the features against
+/// it and asks for the prediction, then reports the outcome. The provided methods check the
+/// feature count, evaluate the entry's model and record nothing.
+pub trait Predictor: Send + Sync {
+    /// Entry a request naming `id` and `version` (see `ModelRegistry::get`) is scored and proven with
+    fn entry(&self, id: Option<&str>, version: Option<&str>) -> Result<Arc<RegisteredModel>, RegistryError>;
+
+    /// Check a scaled feature vector before it is scored with `entry`
+    fn validate(&self, entry: &RegisteredModel, features: &[i64]) -> Result<(), String> {
+        let expected = entry.model.num_features();
+        if features.len() != expected {
+            return Err(format!("expected {} features, got {}", expected, features.len()));
+        }
+        Ok(())
+    }
+
+    /// Scaled prediction of `entry` for validated features
+    fn predict(&self, entry: &RegisteredModel, features: &[i64]) -> Result<i64, String> {
+        entry.model.try_eval(features).map_err(|e| e.to_string())
+    }
+
+    /// A prediction was answered `latency` after its request arrived
+    fn served(&self, _latency: Duration) {}
+
+    /// A request was rejected before it was scored
+    fn rejected(&self) {}
+
+    /// A proof was attempted and took `duration`
+    fn proved(&self, _duration: Duration, _succeeded: bool) {}
+}
+
+impl Predictor for ModelRegistry {
+    fn entry(&self, id: Option<&str>, version: Option<&str>) -> Result<Arc<RegisteredModel>, RegistryError> {
+        self.get(id, version)
+    }
+}
+
+impl From<Ensemble> for ModelRegistry {
+    /// Registry serving just `model`, under its name and version ("model" and "1" if unset)
+    fn from(model: Ensemble) -> ModelRegistry {
+        let registry = ModelRegistry::new();
+        let id = if model.name().is_empty() { "model" } else { model.name() }.to_string();
+        let version = if model.version().is_empty() { "1" } else { model.version() }.to_string();
+        registry.register(&id, &version, model);
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::{Node, Tree};
+
+    fn model(leaf: i64) -> Ensemble {
+        Ensemble::new(vec![Tree::new(Node::split(0, 0, Node::Leaf(leaf), Node::Leaf(-leaf)))], 10)
+    }
+
+    #[test]
+    fn test_routing_and_hot_swap() {
+        let registry = ModelRegistry::new();
+        assert_eq!(registry.get(None, None).unwrap_err(), RegistryError::Empty);
+        assert!(registry.register("rain", "1", model(1)).is_none());
+        registry.register("hail", "1", model(5));
+        assert_eq!(registry.predict(None, None, &[0]).unwrap().1, 1);
+        assert_eq!(registry.predict(Some("hail"), None, &[0]).unwrap().1, 5);
+
+        // A request in flight keeps the entry it got; the next one sees the upgrade
+        let in_flight = registry.get(Some("rain"), None).unwrap();
+        let previous = registry.register("rain", "2", model(2)).unwrap();
+        assert!(Arc::ptr_eq(&previous, &in_flight));
+        assert_eq!(in_flight.model.predict(&[0]), 1);
+        let (entry, prediction) = registry.predict(None, None, &[0]).unwrap();
+        assert_eq!((entry.version.as_str(), prediction), ("2", 2));
+        assert_eq!(registry.predict(None, Some("1"), &[0]).unwrap().1, 1);
+
+        registry.activate("rain", "1").unwrap();
+        assert_eq!(registry.predict(Some("rain"), None, &[0]).unwrap().1, 1);
+        registry.set_default("hail").unwrap();
+        assert_eq!(registry.predict(None, None, &[1]).unwrap().1, -5);
+        assert_eq!(registry.len(), 3);
+    }
+
+    #[test]
+    fn test_errors_and_removal() {
+        let registry = ModelRegistry::new();
+        registry.register("rain", "1", model(1));
+        registry.register("rain", "2", model(2));
+        registry.register("hail", "1", model(5));
+        assert_eq!(registry.get(Some("snow"), None).unwrap_err().to_string(), "unknown model 'snow'");
+        assert_eq!(
+            registry.get(Some("rain"), Some("3")).unwrap_err(),
+            RegistryError::UnknownVersion { id: "rain".into(), version: "3".into() }
+        );
+        assert!(matches!(registry.predict(None, None, &[]), Err(RegistryError::Predict(_))));
+        assert_eq!(
+            registry.remove("rain", "2").unwrap_err(),
+            RegistryError::ActiveVersion { id: "rain".into(), version: "2".into() }
+        );
+
+        registry.remove("rain", "1").unwrap();
+        registry.remove("rain", "2").unwrap();
+        let listings = registry.models();
+        assert_eq!(listings.len(), 1);
+        assert!(listings[0].active && listings[0].default);
+        assert_eq!(registry.get(None, None).unwrap().id, "hail");
+    }
+}
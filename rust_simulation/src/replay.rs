@@ -0,0 +1,345 @@
+// Deterministic replay log
+// Every prediction can be appended to a line-based log (model, version, content hash, scale,
+// inputs, output) and re-executed later to find out whether today's build still predicts the
+// same thing. Each record replays with the registry's model of its id and version. The content
+// hash tells a retrained model apart from the one that made a record under the same name, so its
+// records are counted as such instead of replaying as output diffs.
+//
+// Line format v2: "v2", model, version, content hash (hex), scale, output, comma-separated
+// features, tab-separated. v1 lines (no version or hash) are still read; they replay against
+// the active version of their id.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+use crate::commitment::Digest;
+use crate::registry::ModelRegistry;
+use crate::sha256::to_hex;
+
+/// Format tag written at the start of every log line
+const RECORD_VERSION: &str = "v2";
+/// Tag of the first format, which had no model version or content hash
+const RECORD_VERSION_V1: &str = "v1";
+
+/// One recorded prediction request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayRecord {
+    /// Identifier of the model that produced the output
+    pub model: String,
+    /// Version of the model (empty in v1 records)
+    pub version: String,
+    /// `content_hash` of the model (`None` in v1 records)
+    pub commitment: Option<Digest>,
+    /// Fixed-point scale the inputs and output are expressed in
+    pub scale: i64,
+    /// Scaled input features
+    pub features: Vec<i64>,
+    /// Scaled prediction
+    pub output: i64,
+}
+
+impl ReplayRecord {
+    /// Encode the record as a single tab-separated log line (without newline)
+    pub fn to_line(&self) -> String {
+        let features: Vec<String> = self.features.iter().map(|v| v.to_string()).collect();
+        let commitment = self.commitment.map(|digest| to_hex(&digest)).unwrap_or_default();
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            RECORD_VERSION,
+            self.model,
+            self.version,
+            commitment,
+            self.scale,
+            self.output,
+            features.join(",")
+        )
+    }
+
+    /// Decode a log line produced by [`ReplayRecord::to_line`], or by the v1 format
+    pub fn parse_line(line: &str) -> Result<Self, String> {
+        let mut fields: Vec<&str> = line.split('\t').collect();
+        let (version, commitment) = match fields[0] {
+            RECORD_VERSION if fields.len() == 7 => {
+                let commitment = if fields[3].is_empty() { None } else { Some(parse_digest(fields[3])?) };
+                let version = fields[2].to_string();
+                fields.drain(2..4);
+                (version, commitment)
+            }
+            RECORD_VERSION_V1 if fields.len() == 5 => (String::new(), None),
+            RECORD_VERSION => return Err(format!("expected 7 tab-separated fields, got {}", fields.len())),
+            RECORD_VERSION_V1 => return Err(format!("expected 5 tab-separated fields, got {}", fields.len())),
+            tag => return Err(format!("unsupported record version '{}'", tag)),
+        };
+
+        let scale = fields[2]
+            .parse()
+            .map_err(|e| format!("invalid scale '{}': {}", fields[2], e))?;
+        let output = fields[3]
+            .parse()
+            .map_err(|e| format!("invalid output '{}': {}", fields[3], e))?;
+        let features = if fields[4].is_empty() {
+            Vec::new()
+        } else {
+            fields[4]
+                .split(',')
+                .map(|v| v.parse().map_err(|e| format!("invalid feature '{}': {}", v, e)))
+                .collect::<Result<Vec<i64>, String>>()?
+        };
+
+        Ok(ReplayRecord {
+            model: fields[1].to_string(),
+            version,
+            commitment,
+            scale,
+            features,
+            output,
+        })
+    }
+}
+
+/// Decode a 64-digit hex content hash
+fn parse_digest(hex: &str) -> Result<Digest, String> {
+    let invalid = || format!("invalid content hash '{}'", hex);
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let mut digest = [0u8; 32];
+    for (byte, pair) in digest.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+    }
+    Ok(digest)
+}
+
+/// Error raised while reading a replay log
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The log file could not be read
+    Io(io::Error),
+    /// A line could not be decoded (1-based line number)
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "replay log I/O error: {}", e),
+            ReplayError::Parse { line, message } => {
+                write!(f, "replay log line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(e: io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+/// Append-only writer for replay logs
+pub struct ReplayLog {
+    file: File,
+}
+
+impl ReplayLog {
+    /// Open a replay log for appending, creating it if needed
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(ReplayLog { file })
+    }
+
+    /// Append one record as a complete line
+    pub fn append(&mut self, record: &ReplayRecord) -> io::Result<()> {
+        // Write the line in one call so concurrent appenders never interleave partial lines
+        let line = format!("{}\n", record.to_line());
+        self.file.write_all(line.as_bytes())?;
+        self.file.flush()
+    }
+}
+
+/// Read every record of a replay log, skipping blank lines
+pub fn read_replay_log<P: AsRef<Path>>(path: P) -> Result<Vec<ReplayRecord>, ReplayError> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut records = Vec::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = ReplayRecord::parse_line(&line)
+            .map_err(|message| ReplayError::Parse { line: i + 1, message })?;
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// A recorded prediction that no longer reproduces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayDiff {
+    /// Position of the record in the log (0-based)
+    pub index: usize,
+    /// Output stored in the log
+    pub recorded: i64,
+    /// Output of the current predictor
+    pub replayed: i64,
+}
+
+/// Summary of a replay run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// Number of records re-executed
+    pub replayed: usize,
+    /// Records skipped because no model of their id and version is registered
+    pub skipped: usize,
+    /// Records skipped because the registered model of their id and version had other content
+    /// when they were made (it has been retrained or edited since)
+    pub model_changed: usize,
+    /// Records whose output changed
+    pub diffs: Vec<ReplayDiff>,
+}
+
+/// Re-execute recorded predictions and diff the outputs
+///
+/// # Arguments
+/// * `records` - Records read from a replay log
+/// * `registry` - Models to replay with. Each record is looked up by its id and version (v1
+///   records by their id's active version) and skipped if that is not registered. A record whose
+///   `content_hash`, scale or feature count does not match the entry is counted in
+///   `model_changed` instead of being replayed (v1 records carry no hash)
+///
+/// # Returns
+/// * `ReplayReport` - Counts and every output that differs from the log
+pub fn replay_records(records: &[ReplayRecord], registry: &ModelRegistry) -> ReplayReport {
+    let mut report = ReplayReport::default();
+
+    for (index, record) in records.iter().enumerate() {
+        let version = Some(record.version.as_str()).filter(|version| !version.is_empty());
+        let Ok(entry) = registry.get(Some(&record.model), version) else {
+            report.skipped += 1;
+            continue;
+        };
+        if record.commitment.is_some_and(|recorded| recorded != entry.content_hash)
+            || record.scale != entry.model.scale()
+            || record.features.len() < entry.model.num_features()
+        {
+            report.model_changed += 1;
+            continue;
+        }
+        report.replayed += 1;
+
+        let replayed = entry.model.predict(&record.features);
+        if replayed != record.output {
+            report.diffs.push(ReplayDiff {
+                index,
+                recorded: record.output,
+                replayed,
+            });
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::{Ensemble, Node, Tree};
+
+    fn sample_record(output: i64) -> ReplayRecord {
+        ReplayRecord {
+            model: "bst1_10".to_string(),
+            version: "3".to_string(),
+            commitment: Some([0xab; 32]),
+            scale: 10_000_000_000,
+            features: vec![220286213, -180000000, 0],
+            output,
+        }
+    }
+
+    #[test]
+    fn test_record_line_roundtrip() {
+        let record = sample_record(1_690_000_000);
+        let line = record.to_line();
+        let hash = "ab".repeat(32);
+        assert_eq!(line, format!("v2\tbst1_10\t3\t{}\t10000000000\t1690000000\t220286213,-180000000,0", hash));
+        assert_eq!(ReplayRecord::parse_line(&line).unwrap(), record);
+
+        // v1 lines still parse, without a version or content hash
+        let v1 = ReplayRecord::parse_line("v1\tbst1_10\t10000000000\t1690000000\t220286213,-180000000,0").unwrap();
+        assert_eq!(v1, ReplayRecord { version: String::new(), commitment: None, ..record });
+
+        assert!(ReplayRecord::parse_line("v3\tm\t1\t0\t").is_err());
+        assert!(ReplayRecord::parse_line("v2\tm\t1\t0\t").is_err());
+        assert!(ReplayRecord::parse_line("v1\tm\t1\t0\t1,x").is_err());
+        assert!(ReplayRecord::parse_line("v2\tm\t1\tabc\t1\t0\t").is_err());
+    }
+
+    #[test]
+    fn test_log_append_and_read() {
+        let path = std::env::temp_dir().join(format!("replay_test_{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut log = ReplayLog::open(&path).unwrap();
+        log.append(&sample_record(1)).unwrap();
+        log.append(&sample_record(2)).unwrap();
+        drop(log);
+
+        let mut log = ReplayLog::open(&path).unwrap();
+        log.append(&sample_record(3)).unwrap();
+
+        let outputs: Vec<i64> = read_replay_log(&path).unwrap().iter().map(|r| r.output).collect();
+        assert_eq!(outputs, vec![1, 2, 3]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_reports_diffs_and_skips() {
+        // Two versions of one id and a second id, each predicting its leaf
+        let model = |leaf: i64| Ensemble::new(vec![Tree::new(Node::Leaf(leaf))], 10_000_000_000);
+        let registry = ModelRegistry::new();
+        registry.register("bst1_10", "3", model(7));
+        registry.register("bst1_10", "4", model(5));
+        registry.register("bst2_10", "1", model(9));
+        let hash = |id: &str, version: &str| registry.get(Some(id), Some(version)).unwrap().content_hash;
+        let (v3, v4, bst2) = (hash("bst1_10", "3"), hash("bst1_10", "4"), hash("bst2_10", "1"));
+        let recorded = |id: &str, version: &str, hash: [u8; 32], output: i64| ReplayRecord {
+            model: id.to_string(),
+            version: version.to_string(),
+            commitment: Some(hash),
+            ..sample_record(output)
+        };
+        let mut v1 = sample_record(5);
+        v1.version.clear();
+        v1.commitment = None;
+        let mut rescaled = v1.clone();
+        rescaled.scale = 1000;
+
+        let records = vec![
+            recorded("bst1_10", "3", v3, 7),
+            // Each record replays with the version that made it, not the active one
+            recorded("bst1_10", "3", v3, 8),
+            recorded("bst1_10", "4", v4, 5),
+            recorded("bst2_10", "1", bst2, 9),
+            // v1 records replay with the active version
+            v1,
+            recorded("bst3_10", "1", [0; 32], 7),
+            recorded("bst1_10", "5", v3, 7),
+            // The retrained model's record is not an output diff
+            recorded("bst1_10", "3", [0xcd; 32], 9),
+            rescaled,
+        ];
+        let report = replay_records(&records, &registry);
+        assert_eq!(report.replayed, 5);
+        // Only records whose model is not registered are skipped
+        assert_eq!(report.skipped, 2);
+        assert_eq!(report.model_changed, 2);
+        assert_eq!(report.diffs, vec![ReplayDiff { index: 1, recorded: 8, replayed: 7 }]);
+    }
+}
@@ -0,0 +1,121 @@
+// Prediction requests of the JSON servers
+// `predict serve` (JSON lines) and the REST API (feature `rest`) take the same request body: an
+// object with exactly one of `features` (raw values, scaled by the model) and `features_scaled`
+// (integers at the model's scale, used bit for bit), or a bare array of raw values. Both parse it
+// here, so a body one server accepts is never refused by the other with a different message.
+// How many features a request must carry, and whether they are checked against a schema, stays
+// with the server: that depends on the model it routes to, not on the request. Raw values keep
+// their JSON text and are scaled with `parse_decimal`, so they are rounded once, not via f64.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::fixed::{parse_decimal, ParseFixedError};
+use crate::json::JsonValue;
+
+/// Feature vector of a prediction request, as sent
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequestFeatures {
+    /// `features`, or a bare array: raw values as written, scaled by the model
+    Raw(Vec<String>),
+    /// `features_scaled`: values already at the model's scale
+    Scaled(Vec<i64>),
+}
+
+impl RequestFeatures {
+    /// Features of a request object or array
+    ///
+    /// # Arguments
+    /// * `request` - Parsed request body; other members (`id`, `model`, ...) are ignored
+    ///
+    /// # Returns
+    /// * `Result<RequestFeatures, String>` - The values, or why the body is not a prediction request
+    pub fn parse(request: &JsonValue) -> Result<RequestFeatures, String> {
+        let raw = |values: &[JsonValue]| {
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| match value {
+                    JsonValue::Number(text) => Ok(text.clone()),
+                    _ => Err(format!("feature {} is not a number", i)),
+                })
+                .collect::<Result<Vec<String>, String>>()
+                .map(RequestFeatures::Raw)
+        };
+        if let JsonValue::Array(values) = request {
+            return raw(values);
+        }
+        if !matches!(request, JsonValue::Object(_)) {
+            return Err("request must be an array or an object".to_string());
+        }
+        match (request.get("features"), request.get("features_scaled")) {
+            (Some(values), None) => raw(values.as_array().ok_or("\"features\" must be an array")?),
+            (None, Some(values)) => values
+                .as_array()
+                .ok_or("\"features_scaled\" must be an array")?
+                .iter()
+                .enumerate()
+                .map(|(i, value)| value.as_i64().ok_or_else(|| format!("feature {} is not an i64", i)))
+                .collect::<Result<Vec<i64>, String>>()
+                .map(RequestFeatures::Scaled),
+            _ => Err("expected exactly one of \"features\" and \"features_scaled\"".to_string()),
+        }
+    }
+
+    /// The values at `scale`: raw ones rounded half away from zero, scaled ones unchanged
+    ///
+    /// # Returns
+    /// * `Result<Vec<i64>, String>` - The values, or the first raw one that does not fit an i64 at `scale`
+    pub fn to_scaled(&self, scale: i64) -> Result<Vec<i64>, String> {
+        match self {
+            RequestFeatures::Raw(values) => values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    parse_decimal(value, scale).map_err(|error| match error {
+                        ParseFixedError::Invalid => format!("feature {} is not a number", i),
+                        ParseFixedError::OutOfRange => format!("feature {} ({}) is out of range", i, value),
+                    })
+                })
+                .collect(),
+            RequestFeatures::Scaled(values) => Ok(values.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    fn parse(body: &str) -> Result<RequestFeatures, String> {
+        RequestFeatures::parse(&json::parse(body).unwrap())
+    }
+
+    #[test]
+    fn test_request_forms() {
+        let raw = |values: &[&str]| RequestFeatures::Raw(values.iter().map(|value| value.to_string()).collect());
+        assert_eq!(parse("[1.5, -2]"), Ok(raw(&["1.5", "-2"])));
+        assert_eq!(parse(r#"{"id": 3, "features": [0.25]}"#), Ok(raw(&["0.25"])));
+        let scaled = parse(r#"{"features_scaled": [15000000000, -1]}"#).unwrap();
+        assert_eq!(scaled, RequestFeatures::Scaled(vec![15_000_000_000, -1]));
+        assert_eq!(scaled.to_scaled(100), Ok(vec![15_000_000_000, -1]));
+        // Raw values round half away from zero, as `to_fixed_point` does
+        assert_eq!(parse("[0.25, -0.25, 0.2]").unwrap().to_scaled(2), Ok(vec![1, -1, 0]));
+        // ...once, from their text: through f64 this tie would round down
+        assert_eq!(parse("[1438.10345095225]").unwrap().to_scaled(10_000_000_000), Ok(vec![14_381_034_509_523]));
+        assert_eq!(parse("[1e300]").unwrap().to_scaled(10), Err("feature 0 (1e300) is out of range".to_string()));
+    }
+
+    #[test]
+    fn test_request_errors() {
+        assert_eq!(parse("7"), Err("request must be an array or an object".to_string()));
+        let neither = parse(r#"{"id": 1}"#).unwrap_err();
+        assert_eq!(neither, "expected exactly one of \"features\" and \"features_scaled\"");
+        assert_eq!(parse(r#"{"features": [], "features_scaled": []}"#).unwrap_err(), parse(r#"{}"#).unwrap_err());
+        assert_eq!(parse(r#"{"features": 1}"#), Err("\"features\" must be an array".to_string()));
+        assert_eq!(parse(r#"{"features": [1, "x"]}"#), Err("feature 1 is not a number".to_string()));
+        assert_eq!(parse(r#"{"features_scaled": [1.5]}"#), Err("feature 0 is not an i64".to_string()));
+    }
+}
@@ -0,0 +1,802 @@
+// REST API (feature `rest`)
+// An axum HTTP server for integration tests and services that would rather speak JSON than drive
+// the interactive CLI or gRPC. Like the gRPC service it scores through a `Predictor`; `router`
+// serves one model. Request and response bodies use the crate's
+// own `json` module, so numbers keep their exact text. The OpenAPI 3.0 document at
+// `/openapi.json` is generated from `ENDPOINTS`, the same table the router is checked against.
+//
+// `/predict/stream` is a WebSocket for clients that score rows as they arrive, such as a live
+// dashboard. Each text message is one `PredictRequest` and gets one reply, in order. A connection
+// handles one message at a time: the next one is read only after the reply has been written, so
+// a client that stops reading stalls its own stream instead of queueing work on the server.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::Router;
+
+use crate::json::{self, JsonValue};
+use crate::model::Model;
+use crate::poseidon::hash_features;
+use crate::registry::{ModelRegistry, Predictor, RegisteredModel, RegistryError};
+use crate::request::RequestFeatures;
+use crate::sha256::to_hex;
+
+/// One route of the API, as listed in the OpenAPI document
+#[derive(Debug, Clone, Copy)]
+pub struct Endpoint {
+    /// HTTP method in lower case, as OpenAPI path items spell it
+    pub method: &'static str,
+    pub path: &'static str,
+    pub summary: &'static str,
+    /// Schema of the JSON request body, if the route takes one
+    pub request: Option<&'static str>,
+    /// Schema of the 200 response, or of each reply message on a WebSocket
+    pub response: &'static str,
+    /// The route upgrades to a WebSocket; `request` and `response` describe its messages
+    pub websocket: bool,
+}
+
+/// Every route served by `router`
+pub const ENDPOINTS: [Endpoint; 6] = [
+    Endpoint {
+        method: "post",
+        path: "/predict",
+        summary: "Score one feature vector",
+        request: Some("PredictRequest"),
+        response: "Prediction",
+        websocket: false,
+    },
+    Endpoint {
+        method: "post",
+        path: "/predict/batch",
+        summary: "Score many feature vectors, answering in request order",
+        request: Some("BatchRequest"),
+        response: "BatchResponse",
+        websocket: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/predict/stream",
+        summary: "Score a stream of feature vectors over a WebSocket, one reply per message in order",
+        request: Some("StreamRequest"),
+        response: "StreamReply",
+        websocket: true,
+    },
+    Endpoint {
+        method: "post",
+        path: "/prove",
+        summary: "Score one feature vector and prove the prediction (plonky2 builds only)",
+        request: Some("PredictRequest"),
+        response: "ProveResponse",
+        websocket: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/model/info",
+        summary: "Metadata of the served model",
+        request: None,
+        response: "ModelInfo",
+        websocket: false,
+    },
+    Endpoint {
+        method: "get",
+        path: "/openapi.json",
+        summary: "This document",
+        request: None,
+        response: "OpenApi",
+        websocket: false,
+    },
+];
+
+/// Component schemas referenced by `ENDPOINTS`
+const SCHEMAS: &str = r##"{
+  "PredictRequest": {
+    "type": "object",
+    "description": "Exactly one of features (raw values, scaled by the model) or features_scaled, optionally naming the registered model to score with",
+    "properties": {
+      "features": {"type": "array", "items": {"type": "number"}},
+      "features_scaled": {"type": "array", "items": {"type": "integer", "format": "int64"}},
+      "model": {"type": "string", "description": "Registry id; the default model if absent"},
+      "version": {"type": "string", "description": "Version of the model; its active version if absent"}
+    }
+  },
+  "Prediction": {
+    "type": "object",
+    "required": ["prediction", "prediction_scaled", "scale"],
+    "properties": {
+      "prediction": {"type": "number"},
+      "prediction_scaled": {"type": "integer", "format": "int64"},
+      "scale": {"type": "integer", "format": "int64"}
+    }
+  },
+  "BatchRequest": {
+    "type": "object",
+    "description": "Rows scored by one model, named by model and version as in a PredictRequest",
+    "required": ["rows"],
+    "properties": {
+      "rows": {"type": "array", "items": {"$ref": "#/components/schemas/PredictRequest"}},
+      "model": {"type": "string"},
+      "version": {"type": "string"}
+    }
+  },
+  "BatchResponse": {
+    "type": "object",
+    "required": ["predictions"],
+    "properties": {"predictions": {"type": "array", "items": {"$ref": "#/components/schemas/Prediction"}}}
+  },
+  "StreamRequest": {
+    "description": "Text message on /predict/stream: a PredictRequest, optionally with an id to echo and witness",
+    "allOf": [
+      {"$ref": "#/components/schemas/PredictRequest"},
+      {"type": "object", "properties": {"id": {}, "witness": {"type": "boolean"}}}
+    ]
+  },
+  "StreamReply": {
+    "description": "Reply on /predict/stream: a Prediction (features_hash if witness was set) or an error, with the id",
+    "type": "object",
+    "properties": {
+      "id": {},
+      "prediction": {"type": "number"},
+      "prediction_scaled": {"type": "integer", "format": "int64"},
+      "scale": {"type": "integer", "format": "int64"},
+      "features_hash": {"type": "array", "items": {"type": "integer", "description": "Goldilocks field element"}},
+      "error": {"type": "string"}
+    }
+  },
+  "ProveResponse": {
+    "type": "object",
+    "required": ["prediction_scaled", "proof", "features_hash"],
+    "properties": {
+      "prediction_scaled": {"type": "integer", "format": "int64"},
+      "proof": {"type": "string", "description": "Hex-encoded ModelProof bundle"},
+      "features_hash": {"type": "array", "items": {"type": "integer", "description": "Goldilocks field element"}}
+    }
+  },
+  "ModelInfo": {
+    "type": "object",
+    "required": ["name", "version", "feature_count", "tree_count", "precision", "objective", "content_hash"],
+    "properties": {
+      "name": {"type": "string"},
+      "version": {"type": "string"},
+      "feature_count": {"type": "integer"},
+      "tree_count": {"type": "integer"},
+      "precision": {"type": "integer", "format": "int64"},
+      "objective": {"type": "string"},
+      "content_hash": {"type": "string", "description": "Hex SHA-256 of the binary model encoding"},
+      "training_hash": {"type": "string", "nullable": true}
+    }
+  },
+  "OpenApi": {"type": "object"},
+  "Error": {"type": "object", "required": ["error"], "properties": {"error": {"type": "string"}}}
+}"##;
+
+/// OpenAPI 3.0 description of the API
+///
+/// # Returns
+/// * `JsonValue` - Document with one path item per entry of `ENDPOINTS`
+pub fn openapi() -> JsonValue {
+    let string = |s: &str| JsonValue::String(s.to_string());
+    let object = |members: Vec<(&str, JsonValue)>| {
+        JsonValue::Object(members.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+    };
+    let content = |schema: &str| {
+        let reference = object(vec![("$ref", string(&format!("#/components/schemas/{}", schema)))]);
+        object(vec![("application/json", object(vec![("schema", reference)]))])
+    };
+
+    let mut paths: Vec<(String, JsonValue)> = Vec::new();
+    for endpoint in &ENDPOINTS {
+        let mut operation = vec![("summary", string(endpoint.summary))];
+        if endpoint.websocket {
+            // OpenAPI cannot describe messages, so the schemas are named in the description
+            let messages = format!(
+                "WebSocket: each text message is a {} and is answered by a {}",
+                endpoint.request.unwrap_or("message"),
+                endpoint.response
+            );
+            operation.push(("description", string(&messages)));
+            let upgrade = object(vec![("description", string("Switching to the WebSocket protocol"))]);
+            operation.push(("responses", object(vec![("101", upgrade)])));
+            paths.push((endpoint.path.to_string(), object(vec![(endpoint.method, object(operation))])));
+            continue;
+        }
+        let mut responses = vec![(
+            "200",
+            object(vec![("description", string("Success")), ("content", content(endpoint.response))]),
+        )];
+        if let Some(request) = endpoint.request {
+            let body = object(vec![("required", JsonValue::Bool(true)), ("content", content(request))]);
+            operation.push(("requestBody", body));
+            let error = object(vec![("description", string("Invalid request")), ("content", content("Error"))]);
+            responses.push(("400", error));
+            let unknown = object(vec![("description", string("Unknown model or version")), ("content", content("Error"))]);
+            responses.push(("404", unknown));
+        }
+        operation.push(("responses", object(responses)));
+        let operation = (endpoint.method.to_string(), object(operation));
+        match paths.iter_mut().find(|(path, _)| path == endpoint.path) {
+            Some((_, JsonValue::Object(item))) => item.push(operation),
+            _ => paths.push((endpoint.path.to_string(), JsonValue::Object(vec![operation]))),
+        }
+    }
+
+    const VERSION: &str = env!("CARGO_PKG_VERSION");
+    let schemas = json::parse(SCHEMAS).expect("SCHEMAS is valid JSON");
+    object(vec![
+        ("openapi", string("3.0.3")),
+        ("info", object(vec![("title", string("Rainfall prediction API")), ("version", string(VERSION))])),
+        ("paths", JsonValue::Object(paths)),
+        ("components", object(vec![("schemas", schemas)])),
+    ])
+}
+
+/// Scoring path shared by the handlers
+struct ApiState {
+    predictor: Arc<dyn Predictor>,
+}
+
+impl ApiState {
+    /// Entry to score a request with: one lookup per request, batch or stream message
+    fn entry(&self, id: Option<&str>, version: Option<&str>) -> Result<Arc<RegisteredModel>, ApiError> {
+        self.predictor.entry(id, version).map_err(|e| match e {
+            RegistryError::UnknownModel(_) | RegistryError::UnknownVersion { .. } => {
+                (StatusCode::NOT_FOUND, e.to_string())
+            }
+            _ => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()),
+        })
+    }
+
+    /// Entry a request names with its optional `"model"` and `"version"` strings, as `predict serve`
+    /// routes its requests; a request naming no registered model is reported to the predictor as rejected
+    fn route(&self, request: &JsonValue) -> Result<Arc<RegisteredModel>, ApiError> {
+        let field = |key: &str| match request.get(key) {
+            None => Ok(None),
+            Some(value) => value.as_str().map(Some).ok_or_else(|| bad_request(format!("\"{}\" must be a string", key))),
+        };
+        let entry = field("model").and_then(|id| self.entry(id, field("version")?));
+        entry.inspect_err(|_| self.predictor.rejected())
+    }
+}
+
+/// Error answered as `{"error": message}`
+type ApiError = (StatusCode, String);
+
+fn bad_request(message: impl Into<String>) -> ApiError {
+    (StatusCode::BAD_REQUEST, message.into())
+}
+
+/// Router serving every entry of `ENDPOINTS` for `model`
+pub fn router(model: Model) -> Router {
+    router_with(Arc::new(ModelRegistry::from(model)))
+}
+
+/// Router serving every entry of `ENDPOINTS`, scoring through `predictor`
+pub fn router_with(predictor: Arc<dyn Predictor>) -> Router {
+    let state = Arc::new(ApiState { predictor });
+    Router::new()
+        .route("/predict", post(predict))
+        .route("/predict/batch", post(predict_batch))
+        .route("/predict/stream", get(predict_stream))
+        .route("/prove", post(prove))
+        .route("/model/info", get(model_info))
+        .route("/openapi.json", get(|| async { json_response(Ok(openapi())) }))
+        .with_state(state)
+}
+
+/// Serve the API for `model` on `address` until the process exits
+///
+/// # Arguments
+/// * `model` - Model to score and prove
+/// * `address` - Socket address to listen on, e.g. 127.0.0.1:8080
+///
+/// # Returns
+/// * `std::io::Result<()>` - Error if the address cannot be bound or serving fails
+pub async fn serve(model: Model, address: SocketAddr) -> std::io::Result<()> {
+    serve_with(Arc::new(ModelRegistry::from(model)), address).await
+}
+
+/// Serve the API on `address`, scoring every request through `predictor`
+///
+/// # Arguments
+/// * `predictor` - Scoring path: the entry each request is routed to and what is recorded
+/// * `address` - Socket address to listen on, e.g. 127.0.0.1:8080
+///
+/// # Returns
+/// * `std::io::Result<()>` - Error if the address cannot be bound or serving fails
+pub async fn serve_with(predictor: Arc<dyn Predictor>, address: SocketAddr) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    axum::serve(listener, router_with(predictor)).await
+}
+
+fn json_response(result: Result<JsonValue, ApiError>) -> Response {
+    let (status, body) = match result {
+        Ok(body) => (StatusCode::OK, body),
+        Err((status, message)) => (status, JsonValue::Object(vec![("error".to_string(), JsonValue::String(message))])),
+    };
+    (status, [(header::CONTENT_TYPE, "application/json")], body.to_string()).into_response()
+}
+
+/// Scaled feature vector of a `PredictRequest` (parsed as `predict serve` parses its requests),
+/// validated by the predictor; a rejected request is reported to it
+fn request_features(state: &ApiState, entry: &RegisteredModel, request: &JsonValue) -> Result<Vec<i64>, ApiError> {
+    let features = RequestFeatures::parse(request)
+        .and_then(|features| features.to_scaled(entry.model.scale()))
+        .and_then(|features| state.predictor.validate(entry, &features).map(|()| features));
+    features.map_err(|message| {
+        state.predictor.rejected();
+        bad_request(message)
+    })
+}
+
+/// `Prediction` object of one `PredictRequest`
+fn prediction(state: &ApiState, entry: &RegisteredModel, request: &JsonValue, started: Instant) -> Result<JsonValue, ApiError> {
+    let features = request_features(state, entry, request)?;
+    prediction_members(state, entry, &features, started).map(JsonValue::Object)
+}
+
+fn prediction_members(
+    state: &ApiState,
+    entry: &RegisteredModel,
+    features: &[i64],
+    started: Instant,
+) -> Result<Vec<(String, JsonValue)>, ApiError> {
+    let scaled = state.predictor.predict(entry, features).map_err(bad_request)?;
+    state.predictor.served(started.elapsed());
+    let scale = entry.model.scale();
+    Ok(vec![
+        ("prediction".to_string(), JsonValue::Number((scaled as f64 / scale as f64).to_string())),
+        ("prediction_scaled".to_string(), JsonValue::Number(scaled.to_string())),
+        ("scale".to_string(), JsonValue::Number(scale.to_string())),
+    ])
+}
+
+/// `StreamReply` to one message of `/predict/stream`
+///
+/// An invalid message gets an error reply and leaves the stream open.
+fn stream_reply(state: &ApiState, message: &str) -> JsonValue {
+    let started = Instant::now();
+    let request = parse_request(state, message);
+    let id = request.as_ref().ok().and_then(|request| request.get("id")).cloned();
+    let reply = request.and_then(|request| {
+        let entry = state.route(&request)?;
+        let features = request_features(state, &entry, &request)?;
+        let mut members = prediction_members(state, &entry, &features, started)?;
+        if request.get("witness") == Some(&JsonValue::Bool(true)) {
+            let hash = hash_features(&features).iter().map(|x| JsonValue::Number(x.to_string())).collect();
+            members.push(("features_hash".to_string(), JsonValue::Array(hash)));
+        }
+        Ok(members)
+    });
+    let mut members = reply.unwrap_or_else(|(_, message)| vec![("error".to_string(), JsonValue::String(message))]);
+    if let Some(id) = id {
+        members.insert(0, ("id".to_string(), id));
+    }
+    JsonValue::Object(members)
+}
+
+fn parse_body(body: &str) -> Result<JsonValue, ApiError> {
+    json::parse(body).map_err(|e| bad_request(e.to_string()))
+}
+
+/// Parsed body of a request, reporting a malformed one to the predictor as rejected
+fn parse_request(state: &ApiState, body: &str) -> Result<JsonValue, ApiError> {
+    parse_body(body).inspect_err(|_| state.predictor.rejected())
+}
+
+async fn predict(State(state): State<Arc<ApiState>>, body: String) -> Response {
+    let started = Instant::now();
+    let result = parse_request(&state, &body).and_then(|request| {
+        let entry = state.route(&request)?;
+        prediction(&state, &entry, &request, started)
+    });
+    json_response(result)
+}
+
+async fn predict_batch(State(state): State<Arc<ApiState>>, body: String) -> Response {
+    let started = Instant::now();
+    let result = parse_request(&state, &body).and_then(|request| {
+        let rows = request.get("rows").and_then(JsonValue::as_array);
+        let rows = rows.ok_or_else(|| {
+            state.predictor.rejected();
+            bad_request("missing \"rows\" array")
+        })?;
+        // One entry for the whole batch, and every row checked before any is scored
+        let entry = state.route(&request)?;
+        let in_row = |row: usize| move |(status, message)| (status, format!("row {}: {}", row, message));
+        let features = rows
+            .iter()
+            .enumerate()
+            .map(|(row, request)| request_features(&state, &entry, request).map_err(in_row(row)))
+            .collect::<Result<Vec<Vec<i64>>, ApiError>>()?;
+        let predictions = features
+            .iter()
+            .enumerate()
+            .map(|(row, features)| {
+                prediction_members(&state, &entry, features, started).map(JsonValue::Object).map_err(in_row(row))
+            })
+            .collect::<Result<Vec<JsonValue>, ApiError>>()?;
+        Ok(JsonValue::Object(vec![("predictions".to_string(), JsonValue::Array(predictions))]))
+    });
+    json_response(result)
+}
+
+async fn predict_stream(State(state): State<Arc<ApiState>>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| stream_predictions(state, socket))
+}
+
+/// Answer each message before reading the next, until the client closes the stream
+async fn stream_predictions(state: Arc<ApiState>, mut socket: WebSocket) {
+    while let Some(Ok(message)) = socket.recv().await {
+        let reply = match message {
+            Message::Text(text) => stream_reply(&state, text.as_str()),
+            Message::Binary(_) => {
+                state.predictor.rejected();
+                JsonValue::Object(vec![("error".to_string(), JsonValue::String("expected a text message".to_string()))])
+            }
+            // The protocol layer answers pings and queues the reply to a close, which the next
+            // `recv` writes before it ends the stream
+            Message::Close(_) | Message::Ping(_) | Message::Pong(_) => continue,
+        };
+        if socket.send(Message::Text(reply.to_string().into())).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(feature = "plonky2")]
+async fn prove(State(state): State<Arc<ApiState>>, body: String) -> Response {
+    use crate::zk::plonky2::{proof_features_hash, proof_prediction};
+
+    let request = parse_request(&state, &body).and_then(|request| {
+        let entry = state.route(&request)?;
+        let features = request_features(&state, &entry, &request)?;
+        Ok((entry, features))
+    });
+    let (entry, features) = match request {
+        Ok(request) => request,
+        Err(e) => return json_response(Err(e)),
+    };
+    // Building the circuit and proving take seconds, so they run off the async workers
+    let proof = tokio::task::spawn_blocking(move || {
+        let started = Instant::now();
+        (entry.prove(&features), started.elapsed())
+    })
+    .await;
+    let result = match proof {
+        Ok((proof, duration)) => {
+            state.predictor.proved(duration, proof.is_ok());
+            match proof {
+                Ok(proof) => {
+                    let number = |value: u64| JsonValue::Number(value.to_string());
+                    let prediction = proof_prediction(&proof.proof).unwrap_or_default();
+                    let hash = proof_features_hash(&proof.proof).unwrap_or_default();
+                    Ok(JsonValue::Object(vec![
+                        ("prediction_scaled".to_string(), JsonValue::Number(prediction.to_string())),
+                        ("proof".to_string(), JsonValue::String(to_hex(&proof.to_bytes()))),
+                        ("features_hash".to_string(), JsonValue::Array(hash.iter().map(|&x| number(x)).collect())),
+                    ]))
+                }
+                Err(e) => Err((StatusCode::UNPROCESSABLE_ENTITY, e.to_string())),
+            }
+        }
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    };
+    json_response(result)
+}
+
+#[cfg(not(feature = "plonky2"))]
+async fn prove(State(state): State<Arc<ApiState>>, body: String) -> Response {
+    let result = parse_request(&state, &body).and_then(|request| {
+        let entry = state.route(&request)?;
+        request_features(&state, &entry, &request)
+    });
+    let unimplemented = |_| Err((StatusCode::NOT_IMPLEMENTED, "built without the plonky2 feature".to_string()));
+    json_response(result.and_then(unimplemented))
+}
+
+async fn model_info(State(state): State<Arc<ApiState>>) -> Response {
+    let entry = match state.entry(None, None) {
+        Ok(entry) => entry,
+        Err(e) => return json_response(Err(e)),
+    };
+    let model = &entry.model;
+    let info = model.model_info();
+    let string = |s: String| JsonValue::String(s);
+    let number = |n: String| JsonValue::Number(n);
+    json_response(Ok(JsonValue::Object(vec![
+        ("name".to_string(), string(info.name)),
+        ("version".to_string(), string(info.version)),
+        ("feature_count".to_string(), number(info.feature_count.to_string())),
+        ("tree_count".to_string(), number(info.tree_count.to_string())),
+        ("precision".to_string(), number(info.precision.to_string())),
+        ("objective".to_string(), string(model.objective().name().to_string())),
+        ("content_hash".to_string(), string(to_hex(&model.content_hash()))),
+        ("training_hash".to_string(), info.training_hash.map_or(JsonValue::Null, |hash| string(to_hex(&hash)))),
+    ])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builtin_model, xgboost_predict, MODEL_ID, NUM_FEATURES};
+    use std::io::{Read, Write};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Send one HTTP/1.1 request on a fresh connection, returning the status code and body
+    fn http(address: SocketAddr, method: &str, path: &str, body: &str) -> (u16, JsonValue) {
+        let mut stream = std::net::TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, json::parse(body).unwrap())
+    }
+
+    /// Client end of a WebSocket on `path`, after the opening handshake
+    fn websocket(address: SocketAddr, path: &str) -> std::net::TcpStream {
+        let mut stream = std::net::TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            path
+        )
+        .unwrap();
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let mut byte = [0u8];
+            stream.read_exact(&mut byte).unwrap();
+            head.push(byte[0]);
+        }
+        assert!(head.starts_with(b"HTTP/1.1 101"), "{}", String::from_utf8_lossy(&head));
+        stream
+    }
+
+    /// Send one frame; client frames are masked
+    fn send_frame(stream: &mut std::net::TcpStream, opcode: u8, payload: &[u8]) {
+        let mask = [0x37, 0xfa, 0x21, 0x3d];
+        let mut frame = vec![0x80 | opcode];
+        match payload.len() {
+            len if len < 126 => frame.push(0x80 | len as u8),
+            len => {
+                frame.push(0x80 | 126);
+                frame.extend((len as u16).to_be_bytes());
+            }
+        }
+        frame.extend(mask);
+        frame.extend(payload.iter().enumerate().map(|(i, byte)| byte ^ mask[i % 4]));
+        stream.write_all(&frame).unwrap();
+    }
+
+    /// Read one frame as (opcode, payload), `None` once the server has closed the connection
+    fn read_frame(stream: &mut std::net::TcpStream) -> Option<(u8, Vec<u8>)> {
+        let mut head = [0u8; 2];
+        stream.read_exact(&mut head).ok()?;
+        let len = match head[1] & 0x7f {
+            126 => {
+                let mut len = [0u8; 2];
+                stream.read_exact(&mut len).unwrap();
+                u16::from_be_bytes(len) as usize
+            }
+            127 => {
+                let mut len = [0u8; 8];
+                stream.read_exact(&mut len).unwrap();
+                u64::from_be_bytes(len) as usize
+            }
+            len => len as usize,
+        };
+        let mut payload = vec![0; len];
+        stream.read_exact(&mut payload).unwrap();
+        Some((head[0] & 0x0f, payload))
+    }
+
+    fn scaled_features(seed: i64) -> Vec<i64> {
+        (0..NUM_FEATURES as i64).map(|j| (j * seed % 200 - 100) * 1_000_000_000).collect()
+    }
+
+    fn request(features: &[i64]) -> String {
+        let values: Vec<String> = features.iter().map(i64::to_string).collect();
+        format!("{{\"features_scaled\":[{}]}}", values.join(","))
+    }
+
+    /// Registry scoring that counts the requests it serves and rejects
+    #[derive(Default)]
+    struct Counting {
+        registry: ModelRegistry,
+        served: AtomicUsize,
+        rejected: AtomicUsize,
+    }
+
+    impl Predictor for Counting {
+        fn entry(&self, id: Option<&str>, version: Option<&str>) -> Result<Arc<RegisteredModel>, crate::RegistryError> {
+            self.registry.entry(id, version)
+        }
+
+        fn served(&self, _latency: std::time::Duration) {
+            self.served.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn rejected(&self) {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+
+    #[test]
+    fn test_request_features() {
+        let state = ApiState { predictor: Arc::new(ModelRegistry::from(builtin_model())) };
+        let entry = state.entry(None, None).unwrap();
+        let request_features = |request: &JsonValue| request_features(&state, &entry, request);
+        let raw = json::parse(&format!("{{\"features\":[{}]}}", vec!["1.5"; NUM_FEATURES].join(","))).unwrap();
+        assert_eq!(request_features(&raw).unwrap(), vec![15_000_000_000; NUM_FEATURES]);
+
+        let scaled = json::parse(&request(&scaled_features(7))).unwrap();
+        assert_eq!(request_features(&scaled).unwrap(), scaled_features(7));
+
+        let short = json::parse("{\"features\":[1,2]}").unwrap();
+        assert_eq!(request_features(&short).unwrap_err().1, "expected 116 features, got 2");
+        let both = json::parse("{\"features\":[],\"features_scaled\":[]}").unwrap();
+        assert_eq!(request_features(&both).unwrap_err().0, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_openapi_lists_endpoints() {
+        let document = openapi();
+        assert_eq!(document.get("openapi").and_then(JsonValue::as_str), Some("3.0.3"));
+        let paths = document.get("paths").unwrap();
+        for endpoint in &ENDPOINTS {
+            let operation = paths.get(endpoint.path).and_then(|item| item.get(endpoint.method)).unwrap();
+            let status = if endpoint.websocket { "101" } else { "200" };
+            let schema = operation.get("responses").and_then(|r| r.get(status));
+            assert!(schema.is_some(), "{} {}", endpoint.method, endpoint.path);
+            let schemas = document.get("components").and_then(|c| c.get("schemas")).unwrap();
+            assert!(schemas.get(endpoint.response).is_some(), "{}", endpoint.response);
+        }
+    }
+
+    #[test]
+    fn test_stream_reply() {
+        let counting = Arc::new(Counting::default());
+        counting.registry.register(MODEL_ID, "1", builtin_model());
+        let state = ApiState { predictor: counting.clone() };
+        let features = scaled_features(5);
+        let message = format!("{{\"id\":\"row-1\",\"witness\":true,{}", &request(&features)[1..]);
+        let reply = stream_reply(&state, &message);
+        assert_eq!(reply.get("id").and_then(JsonValue::as_str), Some("row-1"));
+        assert_eq!(reply.get("prediction_scaled").and_then(JsonValue::as_i64), Some(xgboost_predict(&features)));
+        let hash = hash_features(&features).iter().map(|x| JsonValue::Number(x.to_string())).collect();
+        assert_eq!(reply.get("features_hash"), Some(&JsonValue::Array(hash)));
+
+        // No witness unless asked for, and a bad message is answered rather than dropped
+        assert!(stream_reply(&state, &request(&features)).get("features_hash").is_none());
+        let reply = stream_reply(&state, "{\"id\":7,\"features\":[1,2]}");
+        assert_eq!(reply.get("id").and_then(JsonValue::as_i64), Some(7));
+        assert_eq!(reply.get("error").and_then(JsonValue::as_str), Some("expected 116 features, got 2"));
+        assert!(stream_reply(&state, "not json").get("error").is_some());
+
+        // Replies go through the predictor, which sees what was scored and what was turned away
+        assert_eq!((counting.served.load(Ordering::Relaxed), counting.rejected.load(Ordering::Relaxed)), (2, 2));
+    }
+
+    #[test]
+    fn test_requests_route_by_model() {
+        let counting = Arc::new(Counting::default());
+        counting.registry.register(MODEL_ID, "1", builtin_model());
+        counting.registry.register("candidate", "2", builtin_model().with_base_score(builtin_model().base_score() - 3));
+        let state = ApiState { predictor: counting.clone() };
+        let features = scaled_features(5);
+        let expected = xgboost_predict(&features);
+        let named = |fields: &str| stream_reply(&state, &format!("{{{},{}", fields, &request(&features)[1..]));
+
+        let prediction = |reply: JsonValue| reply.get("prediction_scaled").and_then(JsonValue::as_i64);
+        assert_eq!(prediction(named("\"model\":\"candidate\"")), Some(expected - 3));
+        assert_eq!(prediction(named("\"model\":\"candidate\",\"version\":\"2\"")), Some(expected - 3));
+        assert_eq!(prediction(named(&format!("\"model\":\"{}\"", MODEL_ID))), Some(expected));
+
+        let error = |reply: JsonValue| reply.get("error").and_then(JsonValue::as_str).map(str::to_string);
+        assert_eq!(error(named("\"model\":\"missing\"")).as_deref(), Some("unknown model 'missing'"));
+        assert_eq!(error(named("\"model\":\"candidate\",\"version\":\"1\"")).as_deref(), Some("model 'candidate' has no version '1'"));
+        assert_eq!(error(named("\"model\":5")).as_deref(), Some("\"model\" must be a string"));
+        assert_eq!(state.entry(Some("missing"), None).unwrap_err().0, StatusCode::NOT_FOUND);
+        assert_eq!((counting.served.load(Ordering::Relaxed), counting.rejected.load(Ordering::Relaxed)), (3, 3));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_http_round_trip() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(builtin_model())).await });
+
+        tokio::task::spawn_blocking(move || {
+            let (status, body) = http(address, "POST", "/predict", &request(&scaled_features(11)));
+            assert_eq!(status, 200);
+            let expected = xgboost_predict(&scaled_features(11));
+            assert_eq!(body.get("prediction_scaled").and_then(JsonValue::as_i64), Some(expected));
+
+            let batch = format!("{{\"rows\":[{},{{\"features\":[]}}]}}", request(&scaled_features(3)));
+            let (status, body) = http(address, "POST", "/predict/batch", &batch);
+            assert_eq!(status, 400);
+            assert!(body.get("error").and_then(JsonValue::as_str).unwrap().starts_with("row 1:"));
+
+            let unknown = format!("{{\"model\":\"missing\",{}", &request(&scaled_features(11))[1..]);
+            assert_eq!(http(address, "POST", "/predict", &unknown).0, 404);
+
+            let (status, body) = http(address, "GET", "/model/info", "");
+            assert_eq!(status, 200);
+            assert_eq!(body.get("tree_count").and_then(JsonValue::as_usize), Some(10));
+            assert_eq!(body.get("objective").and_then(JsonValue::as_str), Some("reg:squarederror"));
+
+            // Every documented route is served
+            for endpoint in ENDPOINTS.iter().filter(|endpoint| endpoint.method == "get" && !endpoint.websocket) {
+                assert_eq!(http(address, "GET", endpoint.path, "").0, 200, "{}", endpoint.path);
+            }
+            #[cfg(not(feature = "plonky2"))]
+            assert_eq!(http(address, "POST", "/prove", &request(&scaled_features(11))).0, 501);
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_websocket_stream() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(builtin_model())).await });
+
+        tokio::task::spawn_blocking(move || {
+            let mut stream = websocket(address, "/predict/stream");
+
+            // Messages sent ahead of the replies are answered one by one, in order
+            let messages = [
+                format!("{{\"id\":1,{}", &request(&scaled_features(3))[1..]),
+                "{\"id\":2,\"features\":[1,2]}".to_string(),
+                format!("{{\"id\":3,\"witness\":true,{}", &request(&scaled_features(4))[1..]),
+            ];
+            for message in &messages {
+                send_frame(&mut stream, 0x1, message.as_bytes());
+            }
+            send_frame(&mut stream, 0x2, b"\x00\x01");
+            let mut replies = Vec::new();
+            for _ in 0..4 {
+                let (opcode, payload) = read_frame(&mut stream).unwrap();
+                assert_eq!(opcode, 0x1);
+                replies.push(json::parse(std::str::from_utf8(&payload).unwrap()).unwrap());
+            }
+            let ids: Vec<Option<i64>> =
+                replies.iter().map(|reply| reply.get("id").and_then(JsonValue::as_i64)).collect();
+            assert_eq!(ids, [Some(1), Some(2), Some(3), None]);
+            let expected = xgboost_predict(&scaled_features(3));
+            assert_eq!(replies[0].get("prediction_scaled").and_then(JsonValue::as_i64), Some(expected));
+            assert!(replies[0].get("features_hash").is_none());
+
+            // A bad message is answered with an error and the stream stays open
+            assert_eq!(replies[1].get("error").and_then(JsonValue::as_str), Some("expected 116 features, got 2"));
+            assert!(replies[2].get("features_hash").is_some());
+            assert_eq!(replies[3].get("error").and_then(JsonValue::as_str), Some("expected a text message"));
+
+            // Closing: the server echoes the close frame and ends the connection
+            send_frame(&mut stream, 0x8, &1000u16.to_be_bytes());
+            let (opcode, payload) = read_frame(&mut stream).unwrap();
+            assert_eq!((opcode, payload.get(..2)), (0x8, Some(&1000u16.to_be_bytes()[..])));
+            assert!(read_frame(&mut stream).is_none());
+        })
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,281 @@
+// Feature-range validation
+// The circuit proves `prediction = f(features)` for whatever features the prover commits to,
+// so physically impossible inputs (negative counts, reflectivity of 10^6 dBZ) produce valid
+// proofs of meaningless predictions. `FeatureSchema` rejects those before proving.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::{to_fixed_point, NUM_FEATURES};
+
+/// Value the preprocessing pipeline writes for aggregates with no observation (-99999, scaled)
+pub const MISSING_VALUE: i64 = -99999 * crate::PRECISION_MULTIPLIER;
+
+/// Upper bound on radar observations aggregated into one hourly row
+///
+/// A volume scan every ~5 minutes gives about 12 per hour; the bound leaves room for
+/// overlapping radars.
+const MAX_OBSERVATIONS: f64 = 64.0;
+
+/// Names of the 116 features of the compiled model, in input order
+pub const FEATURE_NAMES: [&str; NUM_FEATURES] = [
+    "MassWeightedMean_num_non_null", "MassWeightedMean_mean", "MassWeightedMean_min",
+    "MassWeightedMean_max", "MassWeightedMean_med", "MassWeightedMean_sum",
+    "MassWeightedSD_num_non_null", "MassWeightedSD_mean", "MassWeightedSD_min",
+    "MassWeightedSD_max", "MassWeightedSD_med", "MassWeightedSD_sum",
+    "RR1_num_00", "RR1_num_non_null", "RR1_mean", "RR1_min", "RR1_max", "RR1_med", "RR1_sum",
+    "ReflectivityQC_num_00", "ReflectivityQC_num_03", "ReflectivityQC_num_non_null",
+    "ReflectivityQC_mean", "ReflectivityQC_min", "ReflectivityQC_max", "ReflectivityQC_med",
+    "ReflectivityQC_sum",
+    "LogWaterVolume_num_non_null", "LogWaterVolume_mean", "LogWaterVolume_min",
+    "LogWaterVolume_max", "LogWaterVolume_med", "LogWaterVolume_sum",
+    "Reflectivity_num_non_null", "Reflectivity_mean", "Reflectivity_min", "Reflectivity_max",
+    "Reflectivity_med", "Reflectivity_sum",
+    "Composite_num_00", "Composite_num_non_null", "Composite_mean", "Composite_min",
+    "Composite_max", "Composite_med", "Composite_sum",
+    "RR3_num_00", "RR3_num_non_null", "RR3_mean", "RR3_min", "RR3_max", "RR3_med", "RR3_sum",
+    "Zdr_num_00", "Zdr_num_03", "Zdr_num_non_null", "Zdr_mean", "Zdr_min", "Zdr_max", "Zdr_med",
+    "Zdr_sum",
+    "Velocity_num_00", "Velocity_num_01", "Velocity_num_03", "Velocity_num_non_null",
+    "Velocity_mean", "Velocity_min", "Velocity_max", "Velocity_med", "Velocity_sum",
+    "HybridScan_num_non_null", "HybridScan_mean", "HybridScan_min", "HybridScan_max",
+    "HybridScan_med", "HybridScan_sum",
+    "TimeToEnd_num_non_null", "TimeToEnd_mean", "TimeToEnd_min", "TimeToEnd_max",
+    "TimeToEnd_med", "TimeToEnd_sum",
+    "RhoHV_num_00", "RhoHV_num_03", "RhoHV_num_non_null", "RhoHV_mean", "RhoHV_min",
+    "RhoHV_max", "RhoHV_med", "RhoHV_sum",
+    "RR2_num_00", "RR2_num_non_null", "RR2_mean", "RR2_min", "RR2_max", "RR2_med", "RR2_sum",
+    "RadarQualityIndex_num_non_null", "RadarQualityIndex_mean", "RadarQualityIndex_min",
+    "RadarQualityIndex_max", "RadarQualityIndex_med", "RadarQualityIndex_sum",
+    "Hydro_0", "Hydro_1", "Hydro_2", "Hydro_3", "Hydro_4", "Hydro_5", "Hydro_6", "Hydro_7",
+    "Hydro_8", "Hydro_9", "Hydro_10", "Hydro_11", "Hydro_13",
+];
+
+/// Physical range of a single radar observation: (quantity, unit, min, max)
+const QUANTITIES: [(&str, &str, f64, f64); 15] = [
+    ("MassWeightedMean", "mm", 0.0, 20.0),
+    ("MassWeightedSD", "mm", 0.0, 20.0),
+    ("RR1", "mm/h", -1000.0, 1000.0),
+    ("RR2", "mm/h", -1000.0, 1000.0),
+    ("RR3", "mm/h", -1000.0, 1000.0),
+    ("ReflectivityQC", "dBZ", -50.0, 100.0),
+    ("Reflectivity", "dBZ", -50.0, 100.0),
+    ("Composite", "dBZ", -50.0, 100.0),
+    ("HybridScan", "dBZ", -50.0, 100.0),
+    ("LogWaterVolume", "log10(m3/m3)", -20.0, 0.0),
+    ("Zdr", "dB", -10.0, 10.0),
+    ("Velocity", "m/s", -100.0, 100.0),
+    ("TimeToEnd", "min", 0.0, 60.0),
+    ("RhoHV", "", 0.0, 1.1),
+    ("RadarQualityIndex", "", 0.0, 1.0),
+];
+
+/// Accepted range of one feature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureRange {
+    pub name: String,
+    /// Unit of the unscaled value (empty for dimensionless quantities)
+    pub unit: String,
+    /// Smallest accepted value (scaled)
+    pub min: i64,
+    /// Largest accepted value (scaled)
+    pub max: i64,
+    /// Whether `MISSING_VALUE` is accepted in addition to the range
+    pub allow_missing: bool,
+}
+
+impl FeatureRange {
+    /// Range over unscaled bounds; the missing-value sentinel is accepted
+    pub fn new(name: &str, unit: &str, min: f64, max: f64) -> FeatureRange {
+        assert!(min <= max, "empty range for feature {}", name);
+        FeatureRange {
+            name: name.to_string(),
+            unit: unit.to_string(),
+            min: to_fixed_point(min),
+            max: to_fixed_point(max),
+            allow_missing: true,
+        }
+    }
+
+    /// Whether `value` (scaled) is accepted
+    pub fn contains(&self, value: i64) -> bool {
+        (self.min..=self.max).contains(&value) || (self.allow_missing && value == MISSING_VALUE)
+    }
+}
+
+/// Feature that failed validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeViolation {
+    pub index: usize,
+    /// Offending value (scaled)
+    pub value: i64,
+}
+
+/// Error type for feature validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The input does not have one value per schema feature
+    WrongLength { expected: usize, got: usize },
+    /// Features outside their range, in index order
+    OutOfRange(Vec<RangeViolation>),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::WrongLength { expected, got } => {
+                write!(f, "expected {} features, got {}", expected, got)
+            }
+            ValidationError::OutOfRange(violations) => {
+                let list: Vec<String> = violations
+                    .iter()
+                    .map(|v| format!("#{} = {}", v.index, crate::from_fixed_point(v.value)))
+                    .collect();
+                write!(f, "features out of range: {}", list.join(", "))
+            }
+        }
+    }
+}
+
+impl core::error::Error for ValidationError {}
+
+/// Accepted range and unit of every input feature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureSchema {
+    features: Vec<FeatureRange>,
+}
+
+impl FeatureSchema {
+    /// Create a schema from one range per feature, in input order
+    pub fn new(features: Vec<FeatureRange>) -> FeatureSchema {
+        FeatureSchema { features }
+    }
+
+    /// Schema of the 116 hourly radar aggregates used by the compiled model
+    ///
+    /// Counts lie in `[0, MAX_OBSERVATIONS]`, mean/min/max/median in the physical range of the
+    /// radar quantity and sums in that range times `MAX_OBSERVATIONS`. Bounds are deliberately
+    /// loose: they reject corrupt or adversarial rows, not unusual weather.
+    pub fn rainfall() -> FeatureSchema {
+        let features = FEATURE_NAMES
+            .iter()
+            .map(|&name| {
+                if name.starts_with("Hydro_") {
+                    return FeatureRange::new(name, "count", 0.0, MAX_OBSERVATIONS);
+                }
+                let (quantity, statistic) = name.split_once('_').expect("aggregate feature name");
+                let &(_, unit, min, max) = QUANTITIES
+                    .iter()
+                    .find(|q| q.0 == quantity)
+                    .expect("known radar quantity");
+                match statistic {
+                    s if s.starts_with("num_") => FeatureRange::new(name, "count", 0.0, MAX_OBSERVATIONS),
+                    "sum" => FeatureRange::new(name, unit, min * MAX_OBSERVATIONS, max * MAX_OBSERVATIONS),
+                    _ => FeatureRange::new(name, unit, min, max),
+                }
+            })
+            .collect();
+
+        FeatureSchema { features }
+    }
+
+    /// Per-feature ranges
+    pub fn features(&self) -> &[FeatureRange] {
+        &self.features
+    }
+
+    /// Number of features
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    /// Whether the schema has no features
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    /// Check a feature vector against the schema
+    ///
+    /// # Arguments
+    /// * `features` - Input feature vector as slice of i64 values (scaled by 10^10)
+    ///
+    /// # Returns
+    /// * `Result<(), ValidationError>` - Every out-of-range feature with its index and value
+    pub fn validate(&self, features: &[i64]) -> Result<(), ValidationError> {
+        if features.len() != self.features.len() {
+            return Err(ValidationError::WrongLength { expected: self.features.len(), got: features.len() });
+        }
+
+        let violations: Vec<RangeViolation> = self
+            .features
+            .iter()
+            .zip(features)
+            .enumerate()
+            .filter(|(_, (range, &value))| !range.contains(value))
+            .map(|(index, (_, &value))| RangeViolation { index, value })
+            .collect();
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError::OutOfRange(violations))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_rainfall_schema() {
+        let schema = FeatureSchema::rainfall();
+        assert_eq!(schema.len(), NUM_FEATURES);
+
+        let reflectivity = &schema.features()[34];
+        assert_eq!(reflectivity.name, "Reflectivity_mean");
+        assert_eq!(reflectivity.unit, "dBZ");
+        assert_eq!(schema.features()[0].unit, "count");
+        assert_eq!(schema.features()[115].name, "Hydro_13");
+
+        let mut features = vec![0i64; NUM_FEATURES];
+        features[34] = to_fixed_point(42.5);
+        features[5] = MISSING_VALUE;
+        assert_eq!(schema.validate(&features), Ok(()));
+    }
+
+    #[test]
+    fn test_violations_report_index_and_value() {
+        let schema = FeatureSchema::rainfall();
+        let mut features = vec![0i64; NUM_FEATURES];
+        features[0] = to_fixed_point(-1.0);
+        features[87] = to_fixed_point(1.5); // RhoHV_max
+
+        let err = schema.validate(&features).unwrap_err();
+        assert_eq!(
+            err,
+            ValidationError::OutOfRange(vec![
+                RangeViolation { index: 0, value: to_fixed_point(-1.0) },
+                RangeViolation { index: 87, value: to_fixed_point(1.5) },
+            ])
+        );
+        assert_eq!(err.to_string(), "features out of range: #0 = -1, #87 = 1.5");
+    }
+
+    #[test]
+    fn test_length_and_missing_policy() {
+        let mut range = FeatureRange::new("x", "", 0.0, 1.0);
+        assert!(range.contains(MISSING_VALUE));
+        range.allow_missing = false;
+        assert!(!range.contains(MISSING_VALUE));
+
+        let schema = FeatureSchema::new(vec![range]);
+        assert_eq!(schema.validate(&[0, 0]), Err(ValidationError::WrongLength { expected: 1, got: 2 }));
+        assert!(schema.validate(&[MISSING_VALUE]).is_err());
+    }
+}
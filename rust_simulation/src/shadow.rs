@@ -0,0 +1,167 @@
+// Shadow scoring
+// Before proofs are switched over to a new port, every input can be scored by both the
+// production model (the primary, whose prediction is served) and the candidate (the shadow,
+// whose prediction is only compared). `Divergence` accumulates how far the shadow's fixed-point
+// outputs are from the primary's: how many agree bit for bit, the mean and largest absolute
+// difference and the mean signed difference, all in scaled units so that nothing is hidden by a
+// float conversion. Sums are kept in 128 bits, so no difference of two i64 outputs can overflow.
+
+use alloc::{string::ToString, vec};
+use core::fmt;
+
+use crate::ensemble::Ensemble;
+use crate::json::JsonValue;
+
+/// Differences between primary and shadow predictions of the same inputs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Divergence {
+    /// Inputs scored by both models
+    pub count: usize,
+    /// Inputs where the two predictions are equal
+    pub identical: usize,
+    sum: i128,
+    sum_abs: u128,
+    /// Largest |shadow - primary| (scaled)
+    pub max_abs: u64,
+    /// Position of the first input with the largest difference, if any differs
+    pub max_abs_at: Option<usize>,
+}
+
+impl Divergence {
+    /// No inputs yet
+    pub const fn new() -> Divergence {
+        Divergence { count: 0, identical: 0, sum: 0, sum_abs: 0, max_abs: 0, max_abs_at: None }
+    }
+
+    /// Record the two predictions of one input
+    ///
+    /// # Arguments
+    /// * `primary` - Prediction of the served model (scaled)
+    /// * `shadow` - Prediction of the candidate model (scaled)
+    ///
+    /// # Returns
+    /// * `i128` - shadow - primary
+    pub fn add(&mut self, primary: i64, shadow: i64) -> i128 {
+        let difference = i128::from(shadow) - i128::from(primary);
+        let magnitude = difference.unsigned_abs() as u64;
+        if difference == 0 {
+            self.identical += 1;
+        } else if magnitude > self.max_abs {
+            self.max_abs = magnitude;
+            self.max_abs_at = Some(self.count);
+        }
+        self.count += 1;
+        self.sum += difference;
+        self.sum_abs += difference.unsigned_abs();
+        difference
+    }
+
+    /// Score `samples` with both models
+    pub fn of_models(primary: &Ensemble, shadow: &Ensemble, samples: &[impl AsRef<[i64]>]) -> Divergence {
+        let mut divergence = Divergence::new();
+        for sample in samples {
+            divergence.add(primary.predict(sample.as_ref()), shadow.predict(sample.as_ref()));
+        }
+        divergence
+    }
+
+    /// Whether every input got the same prediction from both models
+    pub fn is_identical(&self) -> bool {
+        self.identical == self.count
+    }
+
+    /// Share of inputs with equal predictions (1 with no inputs)
+    pub fn agreement(&self) -> f64 {
+        if self.count == 0 {
+            1.0
+        } else {
+            self.identical as f64 / self.count as f64
+        }
+    }
+
+    /// Mean |shadow - primary| (scaled)
+    pub fn mean_abs(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum_abs as f64 / self.count as f64
+        }
+    }
+
+    /// Mean shadow - primary (scaled; positive: the shadow predicts more)
+    pub fn bias(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum as f64 / self.count as f64
+        }
+    }
+
+    /// Machine-readable form (differences in scaled units)
+    pub fn to_json(&self) -> JsonValue {
+        let number = |value: &dyn ToString| JsonValue::Number(value.to_string());
+        JsonValue::Object(vec![
+            ("count".to_string(), number(&self.count)),
+            ("identical".to_string(), number(&self.identical)),
+            ("mean_abs".to_string(), number(&self.mean_abs())),
+            ("bias".to_string(), number(&self.bias())),
+            ("max_abs".to_string(), number(&self.max_abs)),
+            ("max_abs_at".to_string(), self.max_abs_at.map_or(JsonValue::Null, |at| number(&at))),
+        ])
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {} identical ({:.2}%)", self.identical, self.count, self.agreement() * 100.0)?;
+        if let Some(at) = self.max_abs_at {
+            write!(
+                f,
+                ", mean |diff| {:.2}, bias {:+.2}, max |diff| {} at #{} (scaled units)",
+                self.mean_abs(),
+                self.bias(),
+                self.max_abs,
+                at
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::{Node, Tree};
+
+    #[test]
+    fn test_divergence() {
+        let mut divergence = Divergence::new();
+        assert!(divergence.is_identical());
+        assert_eq!(divergence.to_string(), "0 of 0 identical (100.00%)");
+        for (primary, shadow) in [(10, 10), (10, 13), (-5, -9), (7, 7)] {
+            divergence.add(primary, shadow);
+        }
+        assert_eq!((divergence.count, divergence.identical, divergence.max_abs), (4, 2, 4));
+        assert_eq!(divergence.max_abs_at, Some(2));
+        assert_eq!((divergence.mean_abs(), divergence.bias()), (1.75, -0.25));
+        assert_eq!(
+            divergence.to_string(),
+            "2 of 4 identical (50.00%), mean |diff| 1.75, bias -0.25, max |diff| 4 at #2 (scaled units)"
+        );
+
+        // Extreme outputs do not overflow
+        assert_eq!(divergence.add(i64::MIN, i64::MAX), (u64::MAX as i128));
+        assert_eq!(divergence.max_abs, u64::MAX);
+    }
+
+    #[test]
+    fn test_of_models() {
+        let tree = |leaf| Tree::new(Node::split(0, 0, Node::Leaf(leaf), Node::Leaf(1)));
+        let primary = Ensemble::new(vec![tree(5)], 10);
+        let shadow = Ensemble::new(vec![tree(6)], 10);
+        let divergence = Divergence::of_models(&primary, &shadow, &[[-1], [1], [0]]);
+        assert_eq!((divergence.count, divergence.identical, divergence.max_abs_at), (3, 1, Some(0)));
+        assert_eq!(divergence.to_json().get("bias").and_then(JsonValue::as_f64), Some(2.0 / 3.0));
+        assert!(Divergence::of_models(&primary, &primary, &[[0]]).is_identical());
+    }
+}
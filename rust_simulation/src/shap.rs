@@ -0,0 +1,158 @@
+// Fixed-point TreeSHAP
+// Exact Shapley attributions of a prediction relative to a baseline input (interventional TreeSHAP
+// with a single reference), computed with i64/i128 arithmetic only so the same inputs always give
+// the same attributions and the computation could be replayed inside a circuit.
+//
+// The trees carry no node covers, so the path-dependent variant is not available; the baseline
+// plays the role of the background distribution instead.
+//
+// Each tree is walked with the input x and the baseline z together. Where they split differently
+// on a feature not yet fixed, both branches are explored: one with the feature following x, one
+// with it following z. A leaf reached with `a` features following x and `b` following z adds
+//   +v / ((a+b) * C(a+b-1, b))   to each x-feature
+//   -v / ((a+b) * C(a+b-1, a))   to each z-feature
+// which are the Shapley weights (a-1)! b! / (a+b)! and a! (b-1)! / (a+b)!.
+// Shapley values are linear, so for averaging ensembles the summed attributions are divided by
+// the number of trees.
+
+use alloc::{vec, vec::Vec};
+
+use crate::ensemble::{Combiner, Ensemble, Node};
+use crate::fixed::saturate;
+
+impl Ensemble {
+    /// Attribute a prediction to the input features, relative to an all-zero baseline
+    ///
+    /// # Arguments
+    /// * `features` - Input feature vector (at the model's scale)
+    ///
+    /// # Returns
+    /// * `Vec<i64>` - One attribution per feature, summing to `eval(features) - eval(zeros)`
+    pub fn explain(&self, features: &[i64]) -> Vec<i64> {
+        self.explain_against(features, &vec![0; self.num_features()])
+    }
+
+    /// Attribute a prediction to the input features, relative to a baseline input
+    ///
+    /// # Arguments
+    /// * `features` - Input feature vector (at the model's scale)
+    /// * `baseline` - Reference input the prediction is compared with
+    ///
+    /// # Returns
+    /// * `Vec<i64>` - One attribution per feature, summing to `eval(features) - eval(baseline)`
+    ///   up to one unit of rounding per leaf visited
+    pub fn explain_against(&self, features: &[i64], baseline: &[i64]) -> Vec<i64> {
+        assert!(features.len() >= self.num_features() && baseline.len() >= self.num_features(),
+                "Expected at least {} features", self.num_features());
+
+        let mut attributions = vec![0i128; features.len()];
+        let mut path = Vec::new();
+        for tree in self.trees() {
+            walk(&tree.root, features, baseline, &mut path, &mut attributions);
+        }
+        if self.combiner() == Combiner::Average && self.num_trees() > 1 {
+            let num_trees = self.num_trees() as i128;
+            attributions.iter_mut().for_each(|a| *a = div_round(*a, num_trees));
+        }
+        attributions.into_iter().map(saturate).collect()
+    }
+}
+
+/// Recursive walk; `path` holds (feature, follows x) for every feature fixed so far
+fn walk(node: &Node, x: &[i64], z: &[i64], path: &mut Vec<(usize, bool)>, attributions: &mut [i128]) {
+    match node {
+        Node::Leaf(value) => add_leaf(*value, path, attributions),
+        Node::Split { feature, left, right, .. } | Node::Categorical { feature, left, right, .. } => {
+            let child = |goes_left: bool| if goes_left { left } else { right };
+            let x_left = node.goes_left(x).expect("split node");
+            let z_left = node.goes_left(z).expect("split node");
+
+            if let Some(&(_, follows_x)) = path.iter().find(|(f, _)| f == feature) {
+                walk(child(if follows_x { x_left } else { z_left }), x, z, path, attributions);
+            } else if x_left == z_left {
+                walk(child(x_left), x, z, path, attributions);
+            } else {
+                for follows_x in [true, false] {
+                    path.push((*feature, follows_x));
+                    walk(child(if follows_x { x_left } else { z_left }), x, z, path, attributions);
+                    path.pop();
+                }
+            }
+        }
+    }
+}
+
+fn add_leaf(value: i64, path: &[(usize, bool)], attributions: &mut [i128]) {
+    let a = path.iter().filter(|(_, follows_x)| *follows_x).count();
+    let b = path.len() - a;
+    if a > 0 {
+        let share = div_round(value as i128, (a + b) as i128 * binomial(a + b - 1, b));
+        for &(feature, _) in path.iter().filter(|(_, follows_x)| *follows_x) {
+            attributions[feature] += share;
+        }
+    }
+    if b > 0 {
+        let share = div_round(value as i128, (a + b) as i128 * binomial(a + b - 1, a));
+        for &(feature, _) in path.iter().filter(|(_, follows_x)| !*follows_x) {
+            attributions[feature] -= share;
+        }
+    }
+}
+
+/// C(n, k), exact for the path lengths of real trees
+fn binomial(n: usize, k: usize) -> i128 {
+    let k = k.min(n - k);
+    (0..k).fold(1i128, |acc, i| acc * (n - i) as i128 / (i + 1) as i128)
+}
+
+/// n / d rounded to nearest, ties away from zero (d > 0)
+fn div_round(n: i128, d: i128) -> i128 {
+    let magnitude = (n.abs() + d / 2) / d;
+    if n < 0 { -magnitude } else { magnitude }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Tree, NUM_FEATURES};
+
+    #[test]
+    fn test_two_feature_tree() {
+        // x0 <= 0 ? (x1 <= 0 ? 10 : 20) : 30
+        let tree = Node::split(0, 0, Node::split(1, 0, Node::Leaf(10), Node::Leaf(20)), Node::Leaf(30));
+        let model = Ensemble::new(vec![Tree::new(tree)], 1).with_num_features(3);
+
+        // f(x) = 30, f(z) = 10, f(x0 only) = 30, f(x1 only) = 20
+        assert_eq!(model.explain(&[1, 1, 5]), vec![15, 5, 0]);
+        assert_eq!(model.explain(&[0, 0, 5]), vec![0, 0, 0]);
+        assert_eq!(model.explain_against(&[0, 0, 0], &[1, 1, 0]), vec![-15, -5, 0]);
+
+        // Averaged with a constant tree: f(x) = 15, f(z) = 5
+        let forest = Ensemble::new(vec![model.trees()[0].clone(), Tree::new(Node::Leaf(0))], 1)
+            .with_num_features(3)
+            .with_combiner(Combiner::Average);
+        assert_eq!(forest.explain(&[1, 1, 5]), vec![8, 3, 0]);
+    }
+
+    #[test]
+    fn test_attributions_sum_to_prediction_difference() {
+        let model = crate::builtin_model();
+        let mut features = vec![0i64; NUM_FEATURES];
+        features[34] = 130000000000;
+        features[22] = 9000000000;
+        features[77] = -20000000000;
+
+        let attributions = model.explain(&features);
+        let total: i64 = attributions.iter().sum();
+        let expected = model.eval(&features) - model.eval(&vec![0; NUM_FEATURES]);
+        assert!((total - expected).abs() <= 64, "{} vs {}", total, expected);
+        assert_eq!(attributions, model.explain(&features));
+    }
+
+    #[test]
+    fn test_binomial() {
+        assert_eq!(binomial(0, 0), 1);
+        assert_eq!(binomial(5, 2), 10);
+        assert_eq!(binomial(60, 30), 118264581564861424);
+    }
+}
@@ -0,0 +1,250 @@
+// Comparison sharing
+// Many trees test the same feature against the same threshold (the bundled model compares
+// f[54] <= 10000000000 in several trees). This pass collects the distinct split tests of an
+// ensemble into one table and rewrites every split as an index into it, so a prediction first
+// evaluates each distinct test once into a boolean vector and then walks the trees on that vector.
+// The circuit backends build their comparisons from the same table, which removes the range
+// checks of every repeated comparison. `SharingStats` reports the splits before and the distinct
+// tests after the pass.
+
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+use core::fmt;
+
+use crate::ensemble::{Accumulator, BitSet, Combiner, Ensemble, Node};
+use crate::fixed_le;
+
+/// One distinct split test of an ensemble
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SplitTest {
+    /// `features[feature] <= threshold`
+    Threshold { feature: usize, threshold: i64 },
+    /// `features[feature]` is `c * unit` for a category `c` in `categories`
+    Categories { feature: usize, categories: BitSet, unit: i64 },
+}
+
+impl SplitTest {
+    /// Outcome of the test on a feature vector (true: the left child is taken)
+    pub fn eval(&self, features: &[i64]) -> bool {
+        match self {
+            SplitTest::Threshold { feature, threshold } => fixed_le(features[*feature], *threshold),
+            SplitTest::Categories { feature, categories, unit } => categories.contains_value(features[*feature], *unit),
+        }
+    }
+}
+
+/// Tree whose splits refer to a test of the shared table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SharedNode {
+    Leaf(i64),
+    Test { test: usize, left: Box<SharedNode>, right: Box<SharedNode> },
+}
+
+impl SharedNode {
+    fn eval(&self, outcomes: &[bool]) -> i64 {
+        let mut node = self;
+        loop {
+            match node {
+                SharedNode::Leaf(value) => return *value,
+                SharedNode::Test { test, left, right } => node = if outcomes[*test] { left } else { right },
+            }
+        }
+    }
+}
+
+/// Splits before and distinct tests after comparison sharing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SharingStats {
+    /// Split nodes of the ensemble, i.e. tests evaluated without sharing
+    pub splits: usize,
+    /// Distinct tests evaluated with sharing
+    pub tests: usize,
+    /// Uses of the most repeated test
+    pub max_uses: usize,
+}
+
+impl SharingStats {
+    /// Tests saved by sharing
+    pub fn saved(&self) -> usize {
+        self.splits - self.tests
+    }
+}
+
+impl fmt::Display for SharingStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = if self.splits == 0 { 0.0 } else { 100.0 * self.saved() as f64 / self.splits as f64 };
+        write!(
+            f,
+            "{} splits -> {} distinct tests ({} shared, {:.1}%; most repeated test used {} times)",
+            self.splits,
+            self.tests,
+            self.saved(),
+            percent,
+            self.max_uses
+        )
+    }
+}
+
+/// Ensemble with its split tests hoisted into a shared table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedEnsemble {
+    tests: Vec<SplitTest>,
+    /// Number of splits using each test
+    uses: Vec<usize>,
+    trees: Vec<SharedNode>,
+    base_score: i64,
+    combiner: Combiner,
+    accumulator: Accumulator,
+    num_features: usize,
+}
+
+/// Builds the test table while rewriting the trees
+#[derive(Default)]
+struct TestTable {
+    tests: Vec<SplitTest>,
+    uses: Vec<usize>,
+    thresholds: BTreeMap<(usize, i64), usize>,
+}
+
+impl TestTable {
+    fn index(&mut self, test: SplitTest) -> usize {
+        let index = match &test {
+            SplitTest::Threshold { feature, threshold } => {
+                *self.thresholds.entry((*feature, *threshold)).or_insert(self.tests.len())
+            }
+            // Category sets are rare; a linear scan is enough
+            SplitTest::Categories { .. } => self.tests.iter().position(|t| *t == test).unwrap_or(self.tests.len()),
+        };
+        if index == self.tests.len() {
+            self.tests.push(test);
+            self.uses.push(0);
+        }
+        self.uses[index] += 1;
+        index
+    }
+
+    fn rewrite(&mut self, node: &Node) -> SharedNode {
+        let (test, left, right) = match node {
+            Node::Leaf(value) => return SharedNode::Leaf(*value),
+            Node::Split { feature, threshold, left, right } => {
+                (SplitTest::Threshold { feature: *feature, threshold: *threshold }, left, right)
+            }
+            Node::Categorical { feature, categories, unit, left, right } => {
+                (SplitTest::Categories { feature: *feature, categories: categories.clone(), unit: *unit }, left, right)
+            }
+        };
+        let test = self.index(test);
+        SharedNode::Test { test, left: Box::new(self.rewrite(left)), right: Box::new(self.rewrite(right)) }
+    }
+}
+
+impl Ensemble {
+    /// Hoist the distinct split tests of the ensemble into a shared table
+    ///
+    /// # Returns
+    /// * `SharedEnsemble` - The same model, evaluating every distinct test once per prediction
+    pub fn share_comparisons(&self) -> SharedEnsemble {
+        let mut table = TestTable::default();
+        let trees = self.trees().iter().map(|tree| table.rewrite(&tree.root)).collect();
+        SharedEnsemble {
+            tests: table.tests,
+            uses: table.uses,
+            trees,
+            base_score: self.base_score(),
+            combiner: self.combiner(),
+            accumulator: self.accumulator(),
+            num_features: self.num_features(),
+        }
+    }
+}
+
+impl SharedEnsemble {
+    /// Distinct tests, in order of first use (trees in order, nodes in pre-order)
+    pub fn tests(&self) -> &[SplitTest] {
+        &self.tests
+    }
+
+    /// Trees rewritten over the test table
+    pub(crate) fn trees(&self) -> &[SharedNode] {
+        &self.trees
+    }
+
+    /// Splits before and tests after sharing
+    pub fn stats(&self) -> SharingStats {
+        SharingStats {
+            splits: self.uses.iter().sum(),
+            tests: self.tests.len(),
+            max_uses: self.uses.iter().copied().max().unwrap_or(0),
+        }
+    }
+
+    /// Outcome of every distinct test on a feature vector, the precomputed boolean vector
+    pub fn outcomes(&self, features: &[i64]) -> Vec<bool> {
+        assert!(features.len() >= self.num_features,
+                "Expected at least {} features, got {}", self.num_features, features.len());
+        self.tests.iter().map(|test| test.eval(features)).collect()
+    }
+
+    /// Prediction from precomputed test outcomes
+    ///
+    /// # Arguments
+    /// * `outcomes` - One outcome per entry of `tests()`
+    ///
+    /// # Returns
+    /// * `i64` - Prediction, identical to `Ensemble::eval` on the features the outcomes came from
+    pub fn eval_outcomes(&self, outcomes: &[bool]) -> i64 {
+        assert_eq!(outcomes.len(), self.tests.len(), "one outcome per test");
+        self.accumulator.combine(self.combiner, self.base_score, self.trees().iter().map(|tree| tree.eval(outcomes)))
+    }
+
+    /// Evaluate every distinct test once, then walk the trees on the outcomes
+    ///
+    /// The tests evaluated do not depend on the input, only the tree walk does.
+    pub fn eval(&self, features: &[i64]) -> i64 {
+        self.eval_outcomes(&self.outcomes(features))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::Tree;
+    use crate::{builtin_model, xgboost_predict, NUM_FEATURES, PRECISION_MULTIPLIER};
+    use alloc::{format, vec};
+
+    #[test]
+    fn test_builtin_model_sharing() {
+        let model = builtin_model();
+        let shared = model.share_comparisons();
+        let stats = shared.stats();
+        assert_eq!(stats.splits, model.trees().iter().map(|t| t.num_nodes() / 2).sum::<usize>());
+        assert!(stats.tests < stats.splits && stats.max_uses > 1, "{}", stats);
+        assert!(shared.tests().contains(&SplitTest::Threshold { feature: 54, threshold: PRECISION_MULTIPLIER }));
+
+        for i in 0..64i64 {
+            let features: Vec<i64> =
+                (0..NUM_FEATURES as i64).map(|j| ((i * 41 + j * 17) % 300) * 1_000_000_000 - 60_000_000_000).collect();
+            assert_eq!(shared.eval(&features), xgboost_predict(&features));
+        }
+    }
+
+    #[test]
+    fn test_shared_tests_and_stats() {
+        let categories: BitSet = [1, 3].into_iter().collect();
+        let trees = vec![
+            Tree::new(Node::split(0, 5, Node::Leaf(1), Node::categorical(1, categories.clone(), 1, Node::Leaf(2), Node::Leaf(3)))),
+            Tree::new(Node::split(0, 5, Node::split(0, 4, Node::Leaf(4), Node::Leaf(5)), Node::Leaf(6))),
+            Tree::new(Node::categorical(1, categories, 1, Node::Leaf(7), Node::Leaf(8))),
+        ];
+        let model = Ensemble::new(trees, 1).with_base_score(100).with_combiner(Combiner::Average);
+        let shared = model.share_comparisons();
+        assert_eq!(shared.tests().len(), 3);
+        let stats = shared.stats();
+        assert_eq!((stats.splits, stats.tests, stats.saved(), stats.max_uses), (5, 3, 2, 2));
+        assert_eq!(format!("{}", stats), "5 splits -> 3 distinct tests (2 shared, 40.0%; most repeated test used 2 times)");
+
+        for features in [[0, 1], [5, 3], [6, 2], [4, 0], [i64::MIN, i64::MAX]] {
+            assert_eq!(shared.eval(&features), model.eval(&features), "{:?}", features);
+        }
+        assert_eq!(shared.outcomes(&[6, 3]), vec![false, true, false]);
+    }
+}
@@ -0,0 +1,223 @@
+// SIMD batch traversal
+// Breadth-first, data-parallel scoring: `LANES` samples walk each tree together, one level per
+// step, with the node lookups done as gathers and the threshold comparisons as one i64 vector
+// compare. Leaves point back to themselves, so every lane can run for the tree's full depth
+// without branching. Categorical splits are resolved lane by lane, only in trees that have them.
+// Needs `std::simd` (feature `simd`, nightly toolchain).
+
+use alloc::{vec, vec::Vec};
+use core::simd::cmp::{SimdPartialEq, SimdPartialOrd};
+use core::simd::num::SimdInt;
+use core::simd::{Select, Simd};
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+
+use crate::ensemble::{Accumulator, BitSet, Combiner, Ensemble, Node};
+
+/// Samples scored per vector step
+pub const LANES: usize = 8;
+
+/// Tree in structure-of-arrays form, nodes in pre-order
+#[derive(Debug, Clone)]
+struct FlatTree {
+    feature: Vec<usize>,
+    threshold: Vec<i64>,
+    left: Vec<usize>,
+    right: Vec<usize>,
+    /// Leaf output (0 for splits)
+    value: Vec<i64>,
+    /// Category set of categorical splits, whose `threshold` entry holds the category unit
+    categories: Vec<Option<BitSet>>,
+    /// Whether any node is a categorical split (those lanes are resolved one by one)
+    has_categorical: bool,
+    depth: usize,
+}
+
+impl FlatTree {
+    fn new(root: &Node) -> FlatTree {
+        let mut tree = FlatTree {
+            feature: Vec::new(),
+            threshold: Vec::new(),
+            left: Vec::new(),
+            right: Vec::new(),
+            value: Vec::new(),
+            categories: Vec::new(),
+            has_categorical: false,
+            depth: root.depth(),
+        };
+        tree.push(root);
+        tree.has_categorical = tree.categories.iter().any(Option::is_some);
+        tree
+    }
+
+    fn push(&mut self, node: &Node) -> usize {
+        let index = self.feature.len();
+        match node {
+            Node::Leaf(value) => self.push_node(0, 0, index, index, *value),
+            Node::Split { feature, threshold, left, right } => {
+                self.push_node(*feature, *threshold, 0, 0, 0);
+                self.left[index] = self.push(left);
+                self.right[index] = self.push(right);
+            }
+            Node::Categorical { feature, categories, unit, left, right } => {
+                self.push_node(*feature, *unit, 0, 0, 0);
+                self.categories[index] = Some(categories.clone());
+                self.left[index] = self.push(left);
+                self.right[index] = self.push(right);
+            }
+        }
+        index
+    }
+
+    fn push_node(&mut self, feature: usize, threshold: i64, left: usize, right: usize, value: i64) {
+        self.feature.push(feature);
+        self.threshold.push(threshold);
+        self.left.push(left);
+        self.right.push(right);
+        self.value.push(value);
+        self.categories.push(None);
+    }
+}
+
+/// Ensemble laid out for [`SimdForest::predict_batch`]
+#[derive(Debug, Clone)]
+pub struct SimdForest {
+    trees: Vec<FlatTree>,
+    base_score: i64,
+    combiner: Combiner,
+    accumulator: Accumulator,
+    num_features: usize,
+}
+
+impl SimdForest {
+    /// Flatten an ensemble for vectorized batch scoring
+    pub fn new(ensemble: &Ensemble) -> SimdForest {
+        SimdForest {
+            trees: ensemble.trees().iter().map(|tree| FlatTree::new(&tree.root)).collect(),
+            base_score: ensemble.base_score(),
+            combiner: ensemble.combiner(),
+            accumulator: ensemble.accumulator(),
+            num_features: ensemble.num_features(),
+        }
+    }
+
+    /// Score a batch, `LANES` samples at a time
+    ///
+    /// # Arguments
+    /// * `batch` - Feature vectors at the ensemble's scale
+    ///
+    /// # Returns
+    /// * `Vec<i64>` - One prediction per input, identical to `Ensemble::eval`
+    ///
+    /// Panics if a feature vector is shorter than the ensemble's `num_features`.
+    pub fn predict_batch(&self, batch: &[Vec<i64>]) -> Vec<i64> {
+        let mut predictions = Vec::with_capacity(batch.len());
+        // Row-major block of the chunk: feature j of lane l is at l * num_features + j
+        let mut block = vec![0i64; self.num_features * LANES];
+        let row_offsets = Simd::from_array(core::array::from_fn(|lane| lane * self.num_features));
+
+        for chunk in batch.chunks(LANES) {
+            for (row, features) in block.chunks_exact_mut(self.num_features.max(1)).zip(chunk) {
+                assert!(features.len() >= self.num_features,
+                        "Expected at least {} features, got {}", self.num_features, features.len());
+                row[..self.num_features].copy_from_slice(&features[..self.num_features]);
+            }
+
+            let initial = self.combiner.initial(self.base_score);
+            let mut y = Simd::<i64, LANES>::splat(initial);
+            // Exact per-lane sums for an i128 accumulator
+            let mut wide = [initial as i128; LANES];
+            for tree in &self.trees {
+                let mut node = Simd::<usize, LANES>::splat(0);
+                for _ in 0..tree.depth {
+                    let feature = Simd::gather_or_default(&tree.feature, node);
+                    let threshold = Simd::gather_or_default(&tree.threshold, node);
+                    let x = Simd::gather_or_default(&block, row_offsets + feature);
+                    let left = Simd::gather_or_default(&tree.left, node);
+                    // Leaves are their own children: stop once every lane sits on one
+                    if left.simd_eq(node).all() {
+                        break;
+                    }
+                    let mut go_left = x.simd_le(threshold);
+                    if tree.has_categorical {
+                        for lane in 0..LANES {
+                            if let Some(categories) = &tree.categories[node[lane]] {
+                                go_left.set(lane, categories.contains_value(x[lane], threshold[lane]));
+                            }
+                        }
+                    }
+                    node = go_left.cast::<isize>().select(left, Simd::gather_or_default(&tree.right, node));
+                }
+                let value = Simd::gather_or_default(&tree.value, node);
+                match self.accumulator {
+                    Accumulator::I64 => y = y.saturating_add(value),
+                    Accumulator::I128 => wide.iter_mut().zip(value.as_array()).for_each(|(y, &v)| *y += v as i128),
+                }
+            }
+            predictions.extend((0..chunk.len()).map(|lane| match self.accumulator {
+                Accumulator::I64 => self.combiner.finish(self.base_score, y[lane], self.trees.len()),
+                Accumulator::I128 => self.combiner.finish_wide(self.base_score, wide[lane], self.trees.len()),
+            }));
+        }
+        predictions
+    }
+}
+
+/// [`crate::xgboost_predict_batch`] through the vectorized traversal of the bundled model
+pub fn xgboost_predict_batch_simd(batch: &[Vec<i64>]) -> Vec<i64> {
+    #[cfg(feature = "std")]
+    {
+        static BUILTIN: OnceLock<SimdForest> = OnceLock::new();
+        BUILTIN.get_or_init(|| SimdForest::new(&crate::builtin_model())).predict_batch(batch)
+    }
+    // Without std there is no lazy static; flatten the bundled model on every call
+    #[cfg(not(feature = "std"))]
+    {
+        SimdForest::new(&crate::builtin_model()).predict_batch(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::Tree;
+    use crate::{xgboost_predict_batch, NUM_FEATURES};
+
+    #[test]
+    fn test_simd_matches_scalar_batch() {
+        // 19 samples: two full chunks and a partial one
+        let batch: Vec<Vec<i64>> = (0..19)
+            .map(|i| {
+                (0..NUM_FEATURES)
+                    .map(|j| ((i * 37 + j * 11) % 400) as i64 * 1_000_000_000 - 50_000_000_000)
+                    .collect()
+            })
+            .collect();
+        assert_eq!(xgboost_predict_batch_simd(&batch), xgboost_predict_batch(&batch));
+        assert!(xgboost_predict_batch_simd(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_uneven_depths_and_saturation() {
+        let trees = vec![
+            Tree::new(Node::split(1, 5, Node::Leaf(1), Node::split(0, 0, Node::Leaf(2), Node::Leaf(3)))),
+            Tree::new(Node::Leaf(i64::MAX)),
+        ];
+        let model = Ensemble::new(trees, 1).with_base_score(10);
+        let batch = vec![vec![0, 0], vec![0, 9], vec![1, 9], vec![-1, 5]];
+        let expected: Vec<i64> = batch.iter().map(|f| model.eval(f)).collect();
+        assert_eq!(SimdForest::new(&model).predict_batch(&batch), expected);
+
+        let categorical = Ensemble::new(
+            vec![Tree::new(Node::categorical(0, [0, 2].into_iter().collect(), 1, Node::Leaf(4), Node::split(1, 5, Node::Leaf(5), Node::Leaf(6))))],
+            1,
+        );
+        let expected: Vec<i64> = batch.iter().map(|f| categorical.eval(f)).collect();
+        assert_eq!(expected, vec![4, 4, 6, 5]);
+        assert_eq!(SimdForest::new(&categorical).predict_batch(&batch), expected);
+
+        let forest = model.with_combiner(Combiner::Average);
+        let expected: Vec<i64> = batch.iter().map(|f| forest.eval(f)).collect();
+        assert_eq!(SimdForest::new(&forest).predict_batch(&batch), expected);
+    }
+}
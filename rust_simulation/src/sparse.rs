@@ -0,0 +1,153 @@
+// Sparse feature-vector input
+// Most of the 116 features are zero for clear-sky cells, so batch jobs can pass
+// only the non-zero (index, value) pairs and expand them to the dense vector here.
+
+use alloc::{vec, vec::Vec};
+use core::fmt;
+
+use crate::{to_fixed_point_at, xgboost_predict, NUM_FEATURES, PRECISION_MULTIPLIER};
+
+/// Error raised while expanding a sparse feature vector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SparseFeatureError {
+    /// Feature index is not smaller than the number of model features
+    IndexOutOfBounds { index: usize, num_features: usize },
+    /// The same feature index was given more than once
+    DuplicateIndex { index: usize },
+    /// A floating-point value is NaN or infinite
+    NonFinite { index: usize },
+}
+
+impl fmt::Display for SparseFeatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SparseFeatureError::IndexOutOfBounds { index, num_features } => write!(
+                f,
+                "feature index {} out of bounds for {} features",
+                index, num_features
+            ),
+            SparseFeatureError::DuplicateIndex { index } => {
+                write!(f, "feature index {} given more than once", index)
+            }
+            SparseFeatureError::NonFinite { index } => {
+                write!(f, "feature {} is not a finite number", index)
+            }
+        }
+    }
+}
+
+impl core::error::Error for SparseFeatureError {}
+
+/// Expand sparse (index, value) pairs into a dense fixed-point feature vector
+///
+/// # Arguments
+/// * `entries` - Non-zero features as (index, value) pairs, values scaled by 10^10
+/// * `num_features` - Length of the dense vector
+///
+/// # Returns
+/// * `Result<Vec<i64>, SparseFeatureError>` - Dense vector with all other features set to zero
+pub fn expand_sparse(
+    entries: &[(usize, i64)],
+    num_features: usize,
+) -> Result<Vec<i64>, SparseFeatureError> {
+    let mut dense = vec![0i64; num_features];
+    let mut seen = vec![false; num_features];
+
+    for &(index, value) in entries {
+        if index >= num_features {
+            return Err(SparseFeatureError::IndexOutOfBounds { index, num_features });
+        }
+        if seen[index] {
+            return Err(SparseFeatureError::DuplicateIndex { index });
+        }
+        seen[index] = true;
+        dense[index] = value;
+    }
+
+    Ok(dense)
+}
+
+/// Expand sparse (index, value) pairs of floating-point features, scaling each value
+///
+/// # Arguments
+/// * `entries` - Non-zero features as (index, value) pairs in natural units
+/// * `num_features` - Length of the dense vector
+///
+/// # Returns
+/// * `Result<Vec<i64>, SparseFeatureError>` - Dense vector scaled by 10^10, or `NonFinite` for
+///   NaN and infinities, which `to_fixed_point` would turn into 0 or saturate
+pub fn expand_sparse_f64(
+    entries: &[(usize, f64)],
+    num_features: usize,
+) -> Result<Vec<i64>, SparseFeatureError> {
+    expand_sparse_f64_at(entries, num_features, PRECISION_MULTIPLIER)
+}
+
+/// [`expand_sparse_f64`] at a runtime scale, such as a loaded model's
+pub fn expand_sparse_f64_at(
+    entries: &[(usize, f64)],
+    num_features: usize,
+    scale: i64,
+) -> Result<Vec<i64>, SparseFeatureError> {
+    let scaled = entries
+        .iter()
+        .map(|&(index, value)| match value.is_finite() {
+            true => Ok((index, to_fixed_point_at(value, scale))),
+            false => Err(SparseFeatureError::NonFinite { index }),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    expand_sparse(&scaled, num_features)
+}
+
+/// Run the XGBoost model on a sparse feature vector
+///
+/// # Arguments
+/// * `entries` - Non-zero features as (index, value) pairs, values scaled by 10^10
+///
+/// # Returns
+/// * `Result<i64, SparseFeatureError>` - Prediction result (scaled by 10^10)
+pub fn xgboost_predict_sparse(entries: &[(usize, i64)]) -> Result<i64, SparseFeatureError> {
+    let features = expand_sparse(entries, NUM_FEATURES)?;
+    Ok(xgboost_predict(&features))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_sparse_matches_dense() {
+        let entries = [(0, 220286213), (34, 130000000000), (115, -180000000)];
+        let dense = expand_sparse(&entries, NUM_FEATURES).unwrap();
+
+        assert_eq!(dense.len(), NUM_FEATURES);
+        assert_eq!(dense[0], 220286213);
+        assert_eq!(dense[34], 130000000000);
+        assert_eq!(dense[115], -180000000);
+        assert_eq!(dense.iter().filter(|&&v| v != 0).count(), 3);
+
+        assert_eq!(xgboost_predict_sparse(&entries).unwrap(), xgboost_predict(&dense));
+    }
+
+    #[test]
+    fn test_expand_sparse_rejects_bad_indices() {
+        assert_eq!(
+            expand_sparse(&[(116, 1)], NUM_FEATURES),
+            Err(SparseFeatureError::IndexOutOfBounds { index: 116, num_features: 116 })
+        );
+        assert_eq!(
+            expand_sparse(&[(3, 1), (3, 2)], NUM_FEATURES),
+            Err(SparseFeatureError::DuplicateIndex { index: 3 })
+        );
+    }
+
+    #[test]
+    fn test_expand_sparse_f64_scales_values() {
+        let dense = expand_sparse_f64(&[(2, 1.5)], 4).unwrap();
+        assert_eq!(dense, vec![0, 0, crate::to_fixed_point(1.5), 0]);
+        assert_eq!(expand_sparse_f64_at(&[(2, 1.5)], 4, 1000).unwrap(), vec![0, 0, 1500, 0]);
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(expand_sparse_f64(&[(1, value)], 4), Err(SparseFeatureError::NonFinite { index: 1 }));
+        }
+    }
+}
@@ -0,0 +1,183 @@
+// Fixed-point statistics kernels
+// Integer versions of the aggregate statistics the rainfall features are built from, on values
+// scaled by 10^10. Sums are accumulated exactly in i128 and every kernel rounds once, in a way a
+// circuit can reproduce bit for bit (`r1cs::mean_gadget`, `r1cs::order_statistic_gadget`):
+//   mean        floor((2 * sum + n) / (2n)), i.e. to nearest with ties toward +infinity
+//   percentile  nearest rank: the ceil(p * n / 100)-th smallest value (the smallest for p = 0)
+//   median      the middle value, or the floored mean of the two middle values for an even count
+//   variance    sum((x - mean)^2) / (n - ddof) at scale 10^10, rounded like the mean
+//   std         square root of the variance, rounded to nearest
+// Statistics of an empty input (or of n <= ddof values for the variance) are `None`.
+
+use alloc::vec::Vec;
+
+use crate::fixed::saturate;
+use crate::PRECISION_MULTIPLIER;
+
+/// Saturating sum, accumulated exactly in i128
+pub fn fixed_sum(values: &[i64]) -> i64 {
+    saturate(values.iter().map(|&v| v as i128).sum())
+}
+
+/// Mean rounded to nearest (ties toward +infinity)
+///
+/// # Arguments
+/// * `values` - Values scaled by 10^10
+///
+/// # Returns
+/// * `Option<i64>` - `floor((2 * sum + n) / (2n))`, or None for an empty slice
+pub fn fixed_mean(values: &[i64]) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    let n = values.len() as i128;
+    let sum: i128 = values.iter().map(|&v| v as i128).sum();
+    Some(saturate((2 * sum + n).div_euclid(2 * n)))
+}
+
+/// 1-based nearest rank of percentile `percent` (scaled by 10^10, in [0, 100]) among `n` values
+pub fn percentile_rank(n: usize, percent: i64) -> usize {
+    let hundred = 100 * PRECISION_MULTIPLIER as i128;
+    assert!((0..=hundred).contains(&(percent as i128)), "percentile must lie in [0, 100]");
+    assert!(n > 0, "percentile of no values");
+    // ceil(p * n / 100), at least 1
+    let rank = (percent as i128 * n as i128 + hundred - 1) / hundred;
+    (rank as usize).max(1)
+}
+
+/// Nearest-rank percentile of already sorted values
+pub(crate) fn percentile_sorted(sorted: &[i64], percent: i64) -> i64 {
+    sorted[percentile_rank(sorted.len(), percent) - 1]
+}
+
+/// Median of already sorted, non-empty values
+pub(crate) fn median_sorted(sorted: &[i64]) -> i64 {
+    let n = sorted.len();
+    if n % 2 == 1 {
+        sorted[n / 2]
+    } else {
+        saturate((sorted[n / 2 - 1] as i128 + sorted[n / 2] as i128).div_euclid(2))
+    }
+}
+
+fn sorted(values: &[i64]) -> Vec<i64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    sorted
+}
+
+/// Nearest-rank percentile
+///
+/// # Arguments
+/// * `values` - Values in any order
+/// * `percent` - Percentile scaled by 10^10, in [0, 100] (e.g. `to_fixed_point(90.0)`)
+///
+/// # Returns
+/// * `Option<i64>` - The `ceil(p * n / 100)`-th smallest value, or None for an empty slice
+pub fn fixed_percentile(values: &[i64], percent: i64) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(percentile_sorted(&sorted(values), percent))
+}
+
+/// Median (floored mean of the two middle values for an even count), as pandas' `median`
+pub fn fixed_median(values: &[i64]) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(median_sorted(&sorted(values)))
+}
+
+/// `n * sum((x - mean)^2)` computed exactly around the rounded mean, with `n`
+fn scaled_squared_deviation(values: &[i64]) -> Option<(i128, i128)> {
+    let mean = fixed_mean(values)? as i128;
+    let n = values.len() as i128;
+    let (sum, sum_sq) = values.iter().fold((0i128, 0i128), |(sum, sum_sq), &v| {
+        let d = v as i128 - mean;
+        (sum + d, sum_sq.saturating_add(d * d))
+    });
+    // sum((x - mu)^2) = sum(d^2) - sum(d)^2 / n for the deviations d from any center
+    Some((n.saturating_mul(sum_sq) - sum * sum, n))
+}
+
+/// Variance with `ddof` delta degrees of freedom (0: population, 1: sample), at scale 10^10
+///
+/// # Arguments
+/// * `values` - Values scaled by 10^10
+/// * `ddof` - Subtracted from the count in the divisor, as in numpy and pandas
+///
+/// # Returns
+/// * `Option<i64>` - Variance rounded to nearest, or None for `n <= ddof` values
+pub fn fixed_variance(values: &[i64], ddof: usize) -> Option<i64> {
+    if values.len() <= ddof {
+        return None;
+    }
+    let (numerator, n) = scaled_squared_deviation(values)?;
+    // numerator / (n * (n - ddof)) is at scale 10^20; one more division by 10^10
+    let divisor = n * (n - ddof as i128) * PRECISION_MULTIPLIER as i128;
+    Some(saturate(numerator.saturating_mul(2).saturating_add(divisor).div_euclid(2 * divisor)))
+}
+
+/// Standard deviation with `ddof` delta degrees of freedom, at scale 10^10, rounded to nearest
+pub fn fixed_std(values: &[i64], ddof: usize) -> Option<i64> {
+    if values.len() <= ddof {
+        return None;
+    }
+    let (numerator, n) = scaled_squared_deviation(values)?;
+    // The variance at scale 10^20 is q = numerator / d; its root is already at scale 10^10
+    let d = n * (n - ddof as i128);
+    let root = ((numerator / d) as u128).isqrt() as i128;
+    // Round up when q >= (root + 1/2)^2, i.e. 4 * numerator >= (2 * root + 1)^2 * d
+    let half_up = (2 * root + 1).saturating_mul(2 * root + 1).saturating_mul(d);
+    let rounded = if numerator.saturating_mul(4) >= half_up { root + 1 } else { root };
+    Some(saturate(rounded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::to_fixed_point;
+
+    #[test]
+    fn test_mean_and_order_statistics() {
+        assert_eq!(fixed_mean(&[]), None);
+        assert_eq!(fixed_mean(&[0, 0, 1]), Some(0));
+        assert_eq!(fixed_mean(&[1, 1, 0]), Some(1));
+        assert_eq!(fixed_mean(&[-1, 0]), Some(0));
+        assert_eq!(fixed_mean(&[i64::MAX, i64::MAX]), Some(i64::MAX));
+        assert_eq!(fixed_sum(&[i64::MAX, 1]), i64::MAX);
+
+        let values = [15, 20, 35, 40, 50];
+        let p = |percent: f64| fixed_percentile(&values, to_fixed_point(percent)).unwrap();
+        // Wikipedia's nearest-rank example
+        assert_eq!((p(0.0), p(5.0), p(30.0), p(40.0), p(50.0), p(100.0)), (15, 15, 20, 20, 35, 50));
+        assert_eq!(fixed_percentile(&[3, 1, 2], to_fixed_point(50.0)), Some(2));
+        assert_eq!(fixed_median(&[40, 10, 20, 30]), Some(25));
+        assert_eq!(fixed_median(&[-3, 0]), Some(-2));
+        assert_eq!(fixed_median(&[7]), Some(7));
+        assert_eq!(percentile_rank(4, to_fixed_point(75.0)), 3);
+        assert_eq!(percentile_rank(4, to_fixed_point(75.1)), 4);
+    }
+
+    #[test]
+    fn test_variance_matches_float() {
+        let raw = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        let values: Vec<i64> = raw.iter().map(|&x| to_fixed_point(x)).collect();
+        assert_eq!(fixed_variance(&values, 0), Some(to_fixed_point(4.0)));
+        assert_eq!(fixed_std(&values, 0), Some(to_fixed_point(2.0)));
+        assert_eq!(fixed_variance(&values, 1), Some(to_fixed_point(32.0 / 7.0)));
+        assert_eq!(fixed_variance(&values[..1], 1), None);
+        assert_eq!(fixed_variance(&values[..1], 0), Some(0));
+
+        let raw = [0.1, -3.25, 12.0, 0.7, 0.7001];
+        let values: Vec<i64> = raw.iter().map(|&x| to_fixed_point(x)).collect();
+        let mean = raw.iter().sum::<f64>() / 5.0;
+        let var = raw.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / 4.0;
+        assert!((fixed_variance(&values, 1).unwrap() - to_fixed_point(var)).abs() <= 1);
+        assert!((fixed_std(&values, 1).unwrap() - to_fixed_point(var.sqrt())).abs() <= 1);
+        // Rounding of the root: variance 2 ulp^2 has root 1.41 -> 1, 3 ulp^2 -> 1.73 -> 2
+        assert_eq!(fixed_std(&[0, 2, 4], 1), Some(2));
+        assert_eq!(fixed_std(&[0, 0, 2], 0), Some(1));
+    }
+}
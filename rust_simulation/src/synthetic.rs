@@ -0,0 +1,382 @@
+// Synthetic feature generator
+// Reproducible feature vectors for benchmarking, fuzzing and constraint profiling without the
+// proprietary training data. Every feature draws from its own distribution (a constant, a uniform
+// or clamped normal range, or an observation count) and is replaced by the missing-value sentinel
+// with a configurable probability. The stream is `Lcg`, the 64-bit LCG the tests and benches draw
+// from too, so a seed fully determines the rows on every platform; normal values use the Irwin-Hall approximation (twelve
+// uniforms) to stay free of libm in no_std builds.
+//
+// `SyntheticGenerator::rainfall` covers the 116 radar aggregates with typical values of each
+// quantity clamped to the `FeatureSchema::rainfall` ranges. A JSON config overrides features by
+// name or `f<index>`:
+//
+//     {"features": {"Reflectivity_mean": {"normal": [35, 8], "min": 0, "max": 70, "missing": 0.2},
+//                   "f0": {"count": 4}, "RR1_sum": {"uniform": [0, 50]}, "Hydro_13": {"constant": 0}}}
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::csv::MISSING_FIELD;
+use crate::json::{self, JsonValue};
+use crate::schema::{FeatureSchema, FEATURE_NAMES};
+
+/// Radar observations in a typical hourly row, for the sums of the rainfall defaults
+const TYPICAL_OBSERVATIONS: f64 = 6.0;
+
+/// Most observation counts of the rainfall defaults (a volume scan every ~5 minutes)
+const TYPICAL_MAX_COUNT: u32 = 12;
+
+/// Share of missing values in the non-count features of the rainfall defaults
+const TYPICAL_MISSING_RATE: f64 = 0.05;
+
+/// Typical value of a single observation of each radar quantity: (quantity, mean, standard deviation)
+const TYPICAL: [(&str, f64, f64); 15] = [
+    ("MassWeightedMean", 1.5, 0.8),
+    ("MassWeightedSD", 0.6, 0.4),
+    ("RR1", 2.0, 5.0),
+    ("RR2", 2.0, 5.0),
+    ("RR3", 2.0, 5.0),
+    ("ReflectivityQC", 20.0, 12.0),
+    ("Reflectivity", 20.0, 12.0),
+    ("Composite", 25.0, 12.0),
+    ("HybridScan", 20.0, 12.0),
+    ("LogWaterVolume", -7.0, 2.0),
+    ("Zdr", 0.5, 1.0),
+    ("Velocity", 0.0, 10.0),
+    ("TimeToEnd", 30.0, 17.0),
+    ("RhoHV", 0.95, 0.05),
+    ("RadarQualityIndex", 0.8, 0.2),
+];
+
+/// Error raised by an invalid generator config
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntheticError {
+    /// The config is not valid JSON or a distribution is malformed
+    InvalidConfig(String),
+    /// The config names a feature the generator does not have
+    UnknownFeature(String),
+}
+
+impl fmt::Display for SyntheticError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyntheticError::InvalidConfig(message) => write!(f, "invalid generator config: {}", message),
+            SyntheticError::UnknownFeature(name) => write!(f, "unknown feature '{}' in generator config", name),
+        }
+    }
+}
+
+impl core::error::Error for SyntheticError {}
+
+/// Distribution of the raw (unscaled) values of one feature
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    /// Always the same value
+    Constant(f64),
+    /// Uniform over `[min, max)`
+    Uniform { min: f64, max: f64 },
+    /// Normal, clamped to `[min, max]`
+    Normal { mean: f64, std_dev: f64, min: f64, max: f64 },
+    /// Whole number, uniform over `0..=max`
+    Count { max: u32 },
+}
+
+/// Distribution of one feature and how often it is missing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeatureDistribution {
+    pub distribution: Distribution,
+    /// Probability of the missing-value sentinel (-99999) instead of a drawn value
+    pub missing_rate: f64,
+}
+
+impl FeatureDistribution {
+    /// Distribution without missing values
+    pub fn new(distribution: Distribution) -> FeatureDistribution {
+        FeatureDistribution { distribution, missing_rate: 0.0 }
+    }
+
+    /// Replace the value by the missing-value sentinel with probability `missing_rate`
+    pub fn with_missing_rate(mut self, missing_rate: f64) -> FeatureDistribution {
+        assert!((0.0..=1.0).contains(&missing_rate), "missing rate must lie in [0, 1]");
+        self.missing_rate = missing_rate;
+        self
+    }
+}
+
+/// 64-bit linear congruential generator (Knuth's MMIX constants)
+///
+/// Not for cryptography: it only gives reproducible streams with no dependencies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lcg {
+    state: u64,
+}
+
+impl Lcg {
+    /// Generator whose stream is fully determined by `seed`
+    pub fn new(seed: u64) -> Lcg {
+        Lcg { state: seed }
+    }
+
+    /// Advance the state and return its top 31 bits
+    pub fn next_u31(&mut self) -> u64 {
+        self.state = self.state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.state >> 33
+    }
+}
+
+/// Seeded generator of raw feature vectors; iterating yields one row per call, endlessly
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntheticGenerator {
+    names: Vec<String>,
+    features: Vec<FeatureDistribution>,
+    rng: Lcg,
+}
+
+impl SyntheticGenerator {
+    /// Generator over features named `f0`, `f1`, ...
+    ///
+    /// # Arguments
+    /// * `features` - One distribution per feature, in input order
+    /// * `seed` - Seed of the random stream: equal seeds give equal rows
+    pub fn new(features: Vec<FeatureDistribution>, seed: u64) -> SyntheticGenerator {
+        let names = (0..features.len()).map(|index| format!("f{}", index)).collect();
+        SyntheticGenerator { names, features, rng: Lcg::new(seed) }
+    }
+
+    /// Generator of the 116 radar aggregates of the compiled model
+    ///
+    /// Counts are whole numbers up to 12, per-observation statistics are normal around the
+    /// quantity's typical value and sums are those of six observations, all clamped to the
+    /// schema's physical ranges; 5% of the non-count values are missing.
+    pub fn rainfall(seed: u64) -> SyntheticGenerator {
+        let schema = FeatureSchema::rainfall();
+        let features = schema
+            .features()
+            .iter()
+            .map(|range| {
+                let name = range.name.as_str();
+                if name.starts_with("Hydro_") || name.contains("_num_") {
+                    return FeatureDistribution::new(Distribution::Count { max: TYPICAL_MAX_COUNT });
+                }
+                let (quantity, statistic) = name.split_once('_').expect("aggregate feature name");
+                let &(_, mean, std_dev) = TYPICAL.iter().find(|q| q.0 == quantity).expect("known radar quantity");
+                let observations = if statistic == "sum" { TYPICAL_OBSERVATIONS } else { 1.0 };
+                let distribution = Distribution::Normal {
+                    mean: mean * observations,
+                    std_dev: std_dev * observations,
+                    min: crate::from_fixed_point(range.min),
+                    max: crate::from_fixed_point(range.max),
+                };
+                FeatureDistribution::new(distribution).with_missing_rate(TYPICAL_MISSING_RATE)
+            })
+            .collect();
+        let names = FEATURE_NAMES.iter().map(|name| name.to_string()).collect();
+        SyntheticGenerator { names, features, rng: Lcg::new(seed) }
+    }
+
+    /// Override feature distributions from a JSON config (see the module header for the format)
+    ///
+    /// # Arguments
+    /// * `config` - Object whose `features` map feature names or `f<index>` ids to distributions
+    ///
+    /// # Returns
+    /// * `Result<SyntheticGenerator, SyntheticError>` - The generator with the named features replaced
+    pub fn with_json(mut self, config: &str) -> Result<SyntheticGenerator, SyntheticError> {
+        let document = json::parse(config).map_err(|e| SyntheticError::InvalidConfig(e.to_string()))?;
+        let entries = match document.get("features") {
+            None => return Ok(self),
+            Some(features) => features
+                .as_object()
+                .ok_or_else(|| SyntheticError::InvalidConfig("'features' must be an object".to_string()))?,
+        };
+        for (key, spec) in entries {
+            let index = self.feature_index(key).ok_or_else(|| SyntheticError::UnknownFeature(key.clone()))?;
+            self.features[index] = parse_feature(spec).map_err(|message| {
+                SyntheticError::InvalidConfig(format!("feature '{}': {}", key, message))
+            })?;
+        }
+        Ok(self)
+    }
+
+    fn feature_index(&self, key: &str) -> Option<usize> {
+        self.names.iter().position(|name| name == key).or_else(|| {
+            key.strip_prefix('f')?.parse().ok().filter(|&index| index < self.features.len())
+        })
+    }
+
+    /// Feature names, in input order
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Feature distributions, in input order
+    pub fn features(&self) -> &[FeatureDistribution] {
+        &self.features
+    }
+
+    /// Next row with every value scaled to fixed point (missing values become the scaled sentinel)
+    pub fn next_scaled(&mut self, scale: i64) -> Vec<i64> {
+        let row = self.next_row();
+        row.into_iter().map(|value| crate::fixed::round_half_away(value * scale as f64)).collect()
+    }
+
+    /// Next row of raw values
+    pub fn next_row(&mut self) -> Vec<f64> {
+        let features = core::mem::take(&mut self.features);
+        let row = features.iter().map(|feature| self.draw(feature)).collect();
+        self.features = features;
+        row
+    }
+
+    fn draw(&mut self, feature: &FeatureDistribution) -> f64 {
+        // Every feature takes the same draws whether or not it ends up missing, so changing one
+        // missing rate leaves the values of the other features unchanged
+        let missing = self.next_unit() < feature.missing_rate;
+        let value = match feature.distribution {
+            Distribution::Constant(value) => value,
+            Distribution::Uniform { min, max } => min + (max - min) * self.next_unit(),
+            Distribution::Normal { mean, std_dev, min, max } => {
+                let standard: f64 = (0..12).map(|_| self.next_unit()).sum::<f64>() - 6.0;
+                (mean + std_dev * standard).clamp(min, max)
+            }
+            Distribution::Count { max } => (self.next_unit() * (f64::from(max) + 1.0)) as u32 as f64,
+        };
+        if missing {
+            MISSING_FIELD
+        } else {
+            value
+        }
+    }
+
+    /// Uniform in `[0, 1)`
+    fn next_unit(&mut self) -> f64 {
+        self.rng.next_u31() as f64 / (1u64 << 31) as f64
+    }
+}
+
+impl Iterator for SyntheticGenerator {
+    type Item = Vec<f64>;
+
+    fn next(&mut self) -> Option<Vec<f64>> {
+        Some(self.next_row())
+    }
+}
+
+/// Parse one feature's distribution: exactly one of `constant`, `uniform`, `normal` or `count`,
+/// plus optional `min` / `max` clamps for `normal` and a `missing` rate
+fn parse_feature(spec: &JsonValue) -> Result<FeatureDistribution, String> {
+    let number = |key: &str| -> Result<Option<f64>, String> {
+        match spec.get(key) {
+            None => Ok(None),
+            Some(value) => {
+                value.as_f64().filter(|v| v.is_finite()).map(Some).ok_or(format!("'{}' must be a number", key))
+            }
+        }
+    };
+    let pair = |key: &str| -> Result<Option<(f64, f64)>, String> {
+        let Some(value) = spec.get(key) else { return Ok(None) };
+        match value.as_array().map(|items| items.iter().map(JsonValue::as_f64).collect::<Option<Vec<_>>>()) {
+            Some(Some(items)) if items.len() == 2 && items.iter().all(|v| v.is_finite()) => {
+                Ok(Some((items[0], items[1])))
+            }
+            _ => Err(format!("'{}' must be an array of two numbers", key)),
+        }
+    };
+    let mut kinds = Vec::new();
+    if let Some(value) = number("constant")? {
+        kinds.push(Distribution::Constant(value));
+    }
+    if let Some((min, max)) = pair("uniform")? {
+        if min > max {
+            return Err("uniform range is empty".to_string());
+        }
+        kinds.push(Distribution::Uniform { min, max });
+    }
+    if let Some((mean, std_dev)) = pair("normal")? {
+        if std_dev < 0.0 {
+            return Err("normal standard deviation is negative".to_string());
+        }
+        let (min, max) = (number("min")?.unwrap_or(f64::MIN), number("max")?.unwrap_or(f64::MAX));
+        if min > max {
+            return Err("normal clamp range is empty".to_string());
+        }
+        kinds.push(Distribution::Normal { mean, std_dev, min, max });
+    }
+    if let Some(value) = spec.get("count") {
+        let max = value.as_usize().and_then(|max| u32::try_from(max).ok()).ok_or("'count' must be a whole number")?;
+        kinds.push(Distribution::Count { max });
+    }
+    let [distribution] = kinds[..] else {
+        return Err("expected exactly one of constant, uniform, normal or count".to_string());
+    };
+    let missing_rate = number("missing")?.unwrap_or(0.0);
+    if !(0.0..=1.0).contains(&missing_rate) {
+        return Err("'missing' must lie in [0, 1]".to_string());
+    }
+    Ok(FeatureDistribution { distribution, missing_rate })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NUM_FEATURES;
+    use alloc::vec;
+
+    #[test]
+    fn test_seed_determines_rows() {
+        let rows: Vec<Vec<f64>> = SyntheticGenerator::rainfall(7).take(3).collect();
+        assert_eq!(rows, SyntheticGenerator::rainfall(7).take(3).collect::<Vec<_>>());
+        assert_ne!(rows[0], rows[1]);
+        assert_ne!(rows[0], SyntheticGenerator::rainfall(8).next_row());
+
+        let mut generator = SyntheticGenerator::new(vec![FeatureDistribution::new(Distribution::Count { max: 3 })], 1);
+        let counts: Vec<f64> = generator.by_ref().take(200).map(|row| row[0]).collect();
+        assert!(counts.iter().all(|&count| [0.0, 1.0, 2.0, 3.0].contains(&count)));
+        assert!((0..4).all(|count| counts.contains(&(count as f64))));
+        assert_eq!(generator.next_scaled(10).len(), 1);
+    }
+
+    #[test]
+    fn test_rainfall_rows_pass_the_schema() {
+        let schema = FeatureSchema::rainfall();
+        let mut generator = SyntheticGenerator::rainfall(42);
+        assert_eq!(generator.names().len(), NUM_FEATURES);
+        let mut missing = 0;
+        for _ in 0..200 {
+            let row = generator.next_scaled(crate::PRECISION_MULTIPLIER);
+            assert_eq!(schema.validate(&row), Ok(()));
+            missing += row.iter().filter(|&&value| value == crate::schema::MISSING_VALUE).count();
+        }
+        // 5% of the 82 non-count features of 200 rows
+        assert!((600..1050).contains(&missing), "{} missing values", missing);
+    }
+
+    #[test]
+    fn test_json_config() {
+        let config = r#"{"features": {
+            "Reflectivity_mean": {"normal": [35, 8], "min": 30, "max": 40, "missing": 0.5},
+            "f0": {"constant": 3}, "RR1_sum": {"uniform": [0, 50]}}}"#;
+        let mut generator = SyntheticGenerator::rainfall(3).with_json(config).unwrap();
+        assert_eq!(generator.features()[0].distribution, Distribution::Constant(3.0));
+        for row in generator.by_ref().take(50) {
+            assert_eq!(row[0], 3.0);
+            assert!(row[34] == MISSING_FIELD || (30.0..=40.0).contains(&row[34]));
+            assert!((0.0..50.0).contains(&row[18]));
+        }
+
+        let invalid = |config: &str| SyntheticGenerator::rainfall(0).with_json(config).unwrap_err();
+        let unknown = invalid(r#"{"features": {"rain": {"constant": 1}}}"#);
+        assert_eq!(unknown, SyntheticError::UnknownFeature("rain".into()));
+        assert_eq!(
+            invalid(r#"{"features": {"f1": {"constant": 1, "count": 2}}}"#).to_string(),
+            "invalid generator config: feature 'f1': expected exactly one of constant, uniform, normal or count"
+        );
+        assert!(matches!(invalid(r#"{"features": {"f1": {"uniform": [2, 1]}}}"#), SyntheticError::InvalidConfig(_)));
+        let rate = invalid(r#"{"features": {"f1": {"count": 2, "missing": 2}}}"#);
+        assert!(matches!(rate, SyntheticError::InvalidConfig(_)));
+    }
+}
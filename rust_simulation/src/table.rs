@@ -0,0 +1,266 @@
+// Decision-table compilation (QuickScorer layout)
+// Deep trees make nested ifs slow: every level is a data-dependent branch and a pointer chase.
+// This layout numbers the leaves of each tree from left to right and keeps one bit per leaf. A
+// split whose test fails ("false node") rules out the leaves of its left subtree, so a prediction
+// scans, per feature, the thresholds of all trees in ascending order, clears the left-subtree
+// leaf range of every threshold below the feature value, and stops at the first threshold the
+// value does not exceed. The exit leaf of a tree is then its leftmost remaining bit. Categorical
+// splits have no order to scan and are tested one by one.
+
+use alloc::vec::Vec;
+
+use crate::ensemble::{Accumulator, BitSet, Combiner, Ensemble, Node};
+
+/// Threshold split in the per-feature tables: when the value exceeds `threshold`, the leaves
+/// cleared by `mask` in bitvector word `word` cannot be reached. A left subtree whose leaves span
+/// several words has one condition per word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Condition {
+    threshold: i64,
+    word: usize,
+    mask: u64,
+}
+
+/// Categorical split, ruling out its left-subtree leaves when the value is not in the set
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CategoryCondition {
+    feature: usize,
+    categories: BitSet,
+    unit: i64,
+    masks: Vec<(usize, u64)>,
+}
+
+/// Ensemble compiled to sorted per-feature threshold tables and per-tree leaf bitvectors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecisionTable {
+    /// Threshold conditions grouped by feature, ascending by threshold within a feature
+    conditions: Vec<Condition>,
+    /// Start of each feature's conditions, followed by the total
+    feature_offsets: Vec<usize>,
+    categorical: Vec<CategoryCondition>,
+    /// Leaf values by bit position; each tree starts on a word boundary, leaves left to right
+    leaves: Vec<i64>,
+    /// First bitvector word of each tree, followed by the total
+    tree_words: Vec<usize>,
+    /// Bitvectors with every leaf set
+    initial: Vec<u64>,
+    splits: usize,
+    base_score: i64,
+    combiner: Combiner,
+    accumulator: Accumulator,
+    num_features: usize,
+}
+
+/// Word index and keep-mask of every word that bits `first..end` touch
+fn clear_masks(first: usize, end: usize) -> impl Iterator<Item = (usize, u64)> {
+    let words = if first < end { first / 64..(end - 1) / 64 + 1 } else { 0..0 };
+    words.map(move |word| {
+        let low = first.max(word * 64) - word * 64;
+        let high = end.min(word * 64 + 64) - word * 64;
+        let bits = if high - low == 64 { u64::MAX } else { ((1u64 << (high - low)) - 1) << low };
+        (word, !bits)
+    })
+}
+
+/// Collects the conditions of all trees while numbering their leaves
+#[derive(Default)]
+struct Compiler {
+    /// (feature, condition) before grouping by feature
+    conditions: Vec<(usize, Condition)>,
+    categorical: Vec<CategoryCondition>,
+    leaves: Vec<i64>,
+    splits: usize,
+}
+
+impl Compiler {
+    /// Number the leaves of a subtree from left to right and record its conditions
+    fn node(&mut self, node: &Node) {
+        let (left, right) = match node {
+            Node::Leaf(value) => {
+                self.leaves.push(*value);
+                return;
+            }
+            Node::Split { left, right, .. } | Node::Categorical { left, right, .. } => (left, right),
+        };
+        self.splits += 1;
+        let first = self.leaves.len();
+        self.node(left);
+        let masks = clear_masks(first, self.leaves.len());
+        match *node {
+            Node::Split { feature, threshold, .. } => {
+                self.conditions.extend(masks.map(|(word, mask)| (feature, Condition { threshold, word, mask })));
+            }
+            Node::Categorical { feature, ref categories, unit, .. } => self.categorical.push(CategoryCondition {
+                feature,
+                categories: categories.clone(),
+                unit,
+                masks: masks.collect(),
+            }),
+            Node::Leaf(_) => unreachable!(),
+        }
+        self.node(right);
+    }
+}
+
+impl DecisionTable {
+    /// Compile an ensemble to the decision-table layout
+    ///
+    /// # Arguments
+    /// * `model` - Ensemble to compile
+    ///
+    /// # Returns
+    /// * `DecisionTable` - Evaluator whose predictions equal `model.eval`
+    pub fn new(model: &Ensemble) -> DecisionTable {
+        let mut compiler = Compiler::default();
+        let mut tree_words = Vec::with_capacity(model.num_trees() + 1);
+        let mut initial = Vec::new();
+        for tree in model.trees() {
+            tree_words.push(initial.len());
+            compiler.node(&tree.root);
+
+            let count = compiler.leaves.len() - 64 * initial.len();
+            initial.resize(initial.len() + count.div_ceil(64), u64::MAX);
+            if !count.is_multiple_of(64) {
+                *initial.last_mut().expect("a tree has a leaf") = (1u64 << (count % 64)) - 1;
+            }
+            compiler.leaves.resize(64 * initial.len(), 0);
+        }
+        tree_words.push(initial.len());
+
+        // Stable sort: equal thresholds keep tree order, which does not affect the result
+        let mut conditions = compiler.conditions;
+        conditions.sort_by_key(|&(feature, condition)| (feature, condition.threshold));
+        let feature_offsets =
+            (0..=model.num_features()).map(|feature| conditions.partition_point(|&(f, _)| f < feature)).collect();
+
+        DecisionTable {
+            conditions: conditions.into_iter().map(|(_, condition)| condition).collect(),
+            feature_offsets,
+            categorical: compiler.categorical,
+            leaves: compiler.leaves,
+            tree_words,
+            initial,
+            splits: compiler.splits,
+            base_score: model.base_score(),
+            combiner: model.combiner(),
+            accumulator: model.accumulator(),
+            num_features: model.num_features(),
+        }
+    }
+
+    /// Number of split nodes compiled into the table
+    pub fn num_conditions(&self) -> usize {
+        self.splits
+    }
+
+    /// Number of 64-bit words of leaf bitvectors a prediction works on
+    pub fn num_words(&self) -> usize {
+        self.initial.len()
+    }
+
+    /// Evaluate the compiled ensemble
+    ///
+    /// # Arguments
+    /// * `features` - Input feature vector (at the ensemble's scale)
+    ///
+    /// # Returns
+    /// * `i64` - Prediction, identical to `Ensemble::eval`
+    pub fn eval(&self, features: &[i64]) -> i64 {
+        assert!(features.len() >= self.num_features,
+                "Expected at least {} features, got {}", self.num_features, features.len());
+
+        let mut words = self.initial.clone();
+        for (feature, bounds) in self.feature_offsets.windows(2).enumerate() {
+            let x = features[feature];
+            for condition in &self.conditions[bounds[0]..bounds[1]] {
+                // fixed_le(x, threshold) holds here and for every larger threshold
+                if x <= condition.threshold {
+                    break;
+                }
+                words[condition.word] &= condition.mask;
+            }
+        }
+        for condition in &self.categorical {
+            if !condition.categories.contains_value(features[condition.feature], condition.unit) {
+                for &(word, mask) in &condition.masks {
+                    words[word] &= mask;
+                }
+            }
+        }
+
+        self.accumulator.combine(
+            self.combiner,
+            self.base_score,
+            self.tree_words.windows(2).map(|bounds| {
+                // The exit leaf is never cleared, so some word of the tree is non-zero
+                let word = (bounds[0]..bounds[1]).find(|&w| words[w] != 0).expect("exit leaf");
+                self.leaves[64 * word + words[word].trailing_zeros() as usize]
+            }),
+        )
+    }
+}
+
+impl Ensemble {
+    /// Compile the ensemble to the decision-table layout (see [`DecisionTable`])
+    pub fn to_decision_table(&self) -> DecisionTable {
+        DecisionTable::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::Tree;
+    use crate::{builtin_model, xgboost_predict, Lcg, NUM_FEATURES};
+    use alloc::vec;
+
+    /// Random tree of the given depth, with categorical splits and early leaves
+    fn random_node(rng: &mut Lcg, depth: usize) -> Node {
+        if depth == 0 || rng.next_u31().is_multiple_of(16) {
+            return Node::Leaf((rng.next_u31() % 2000) as i64 - 1000);
+        }
+        let feature = (rng.next_u31() % 4) as usize;
+        let left = random_node(rng, depth - 1);
+        let right = random_node(rng, depth - 1);
+        if rng.next_u31().is_multiple_of(8) {
+            let categories: BitSet = [(rng.next_u31() % 5) as u32, (rng.next_u31() % 5) as u32].into_iter().collect();
+            Node::categorical(feature, categories, 1, left, right)
+        } else {
+            Node::split(feature, (rng.next_u31() % 20) as i64 - 10, left, right)
+        }
+    }
+
+    #[test]
+    fn test_builtin_decision_table() {
+        let table = builtin_model().to_decision_table();
+        assert_eq!(table.num_conditions(), 160);
+        for i in 0..64i64 {
+            let features: Vec<i64> =
+                (0..NUM_FEATURES as i64).map(|j| ((i * 37 + j * 23) % 300) * 1_000_000_000 - 60_000_000_000).collect();
+            assert_eq!(table.eval(&features), xgboost_predict(&features));
+        }
+    }
+
+    #[test]
+    fn test_deep_trees_span_words() {
+        let mut rng = Lcg::new(3);
+        let trees = (0..6).map(|_| Tree::new(random_node(&mut rng, 9))).chain([Tree::new(Node::Leaf(5))]).collect();
+        let model = Ensemble::new(trees, 1).with_base_score(-7).with_combiner(Combiner::Average);
+        let table = model.to_decision_table();
+        assert!(table.num_words() > 2 * model.num_trees(), "{} words", table.num_words());
+
+        for _ in 0..500 {
+            let features: Vec<i64> = (0..4).map(|_| (rng.next_u31() % 24) as i64 - 12).collect();
+            assert_eq!(table.eval(&features), model.eval(&features), "{:?}", features);
+        }
+        assert_eq!(table.eval(&[i64::MIN, i64::MAX, 0, 1]), model.eval(&[i64::MIN, i64::MAX, 0, 1]));
+    }
+
+    #[test]
+    fn test_clear_masks() {
+        let masks: Vec<(usize, u64)> = clear_masks(60, 130).collect();
+        assert_eq!(masks, vec![(0, (1u64 << 60) - 1), (1, 0), (2, !0b11)]);
+        assert_eq!(clear_masks(64, 128).collect::<Vec<_>>(), vec![(1, 0)]);
+        assert_eq!(clear_masks(5, 5).count(), 0);
+    }
+}
@@ -0,0 +1,361 @@
+// Execution trace for witness generation
+// Records what a prover has to assign as advice: every comparison with its outcome, the
+// visited node ids and the running accumulator after each tree. Node ids are pre-order
+// indices within their tree (root 0, left subtree before right subtree). For averaging
+// ensembles the accumulator is the running tree sum; the prover divides it once at the end.
+// Accumulators are recorded as i128 so traces of models with an `Accumulator::I128` keep their
+// exact partial sums; with the default i64 accumulator they are the saturated i64 values.
+// An `OutputGuard` attached with `with_guard` records its decision on the final output.
+// `with_fingerprint` records the build `Fingerprint`; `eval_with_trace` leaves it out because
+// hashing the model costs more than tracing, so batch producers attach one computed per batch.
+
+use alloc::{string::ToString, vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+
+use crate::ensemble::{Accumulator, BitSet, Combiner, Ensemble, Node};
+use crate::fingerprint::{EvaluatorMode, Fingerprint};
+use crate::guard::{GuardDecision, OutputGuard};
+use crate::json::JsonValue;
+use crate::poseidon::{hash_features, PoseidonDigest};
+use crate::{fixed_add, fixed_le};
+
+/// One split evaluated on the path
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Comparison {
+    /// Pre-order id of the split node
+    pub node_id: usize,
+    pub feature: usize,
+    /// Feature value compared (scaled)
+    pub value: i64,
+    /// Split threshold (scaled), or the category unit of a categorical split
+    pub threshold: i64,
+    /// Categories sent left by a categorical split (`None` for a threshold split)
+    pub categories: Option<BitSet>,
+    /// `value <= threshold` (or category membership), i.e. whether the left child was taken
+    pub outcome: bool,
+}
+
+/// Path of one tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TreeTrace {
+    /// Visited node ids from the root down to the leaf
+    pub path: Vec<usize>,
+    /// Comparisons in path order (one per visited split)
+    pub comparisons: Vec<Comparison>,
+    /// Value of the reached leaf
+    pub leaf_value: i64,
+    /// Accumulator after adding this tree's leaf (starting from `Combiner::initial`)
+    pub accumulator: i128,
+}
+
+/// Output guard applied to a trace and its decision
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GuardCheck {
+    pub guard: OutputGuard,
+    /// Decision on the unguarded output
+    pub decision: GuardDecision,
+}
+
+/// Witness data for one prediction
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExecutionTrace {
+    /// Poseidon digest of the input features (`hash_features`), the public handle on the
+    /// private inputs
+    pub features_hash: PoseidonDigest,
+    /// The ensemble's base score (the initial accumulator value when summing)
+    pub base_score: i64,
+    /// How the final accumulator becomes the prediction
+    pub combiner: Combiner,
+    /// Width of the running sum (exported only when not the default i64)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub accumulator: Accumulator,
+    /// One entry per tree, in evaluation order
+    pub trees: Vec<TreeTrace>,
+    /// Output guard, if one was applied
+    pub guard: Option<GuardCheck>,
+    /// Model, build and evaluator that produced the trace, if recorded
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fingerprint: Option<Fingerprint>,
+}
+
+impl ExecutionTrace {
+    /// The prediction: the final accumulator, averaged over the trees for averaging ensembles,
+    /// and clamped by a clamping guard
+    pub fn output(&self) -> i64 {
+        match &self.guard {
+            Some(check) => check.guard.apply(self.raw_output()).0,
+            None => self.raw_output(),
+        }
+    }
+
+    /// The prediction before any output guard
+    pub fn raw_output(&self) -> i64 {
+        let initial = self.combiner.initial(self.base_score) as i128;
+        let accumulator = self.trees.last().map_or(initial, |t| t.accumulator);
+        self.combiner.finish_wide(self.base_score, accumulator, self.trees.len())
+    }
+
+    /// Apply an output guard and record its decision
+    pub fn with_guard(mut self, guard: OutputGuard) -> Self {
+        self.guard = Some(GuardCheck { guard, decision: guard.decide(self.raw_output()) });
+        self
+    }
+
+    /// Record the fingerprint of the run that produced the trace
+    pub fn with_fingerprint(mut self, fingerprint: Fingerprint) -> Self {
+        self.fingerprint = Some(fingerprint);
+        self
+    }
+
+    /// Total number of comparisons on all paths
+    pub fn num_comparisons(&self) -> usize {
+        self.trees.iter().map(|t| t.comparisons.len()).sum()
+    }
+
+    /// JSON form for circuit builders outside Rust
+    ///
+    /// Values are written as integers so scaled numbers keep full precision.
+    pub fn to_json(&self) -> JsonValue {
+        let number = |v: i64| JsonValue::Number(v.to_string());
+        let trees = self
+            .trees
+            .iter()
+            .map(|tree| {
+                let comparisons = tree
+                    .comparisons
+                    .iter()
+                    .map(|c| {
+                        let mut fields = vec![
+                            ("node_id".to_string(), number(c.node_id as i64)),
+                            ("feature".to_string(), number(c.feature as i64)),
+                            ("value".to_string(), number(c.value)),
+                            ("threshold".to_string(), number(c.threshold)),
+                            ("outcome".to_string(), JsonValue::Bool(c.outcome)),
+                        ];
+                        if let Some(categories) = &c.categories {
+                            let categories = categories.iter().map(|category| number(category as i64)).collect();
+                            fields.insert(4, ("categories".to_string(), JsonValue::Array(categories)));
+                        }
+                        JsonValue::Object(fields)
+                    })
+                    .collect();
+                JsonValue::Object(vec![
+                    ("path".to_string(), JsonValue::Array(tree.path.iter().map(|&id| number(id as i64)).collect())),
+                    ("comparisons".to_string(), JsonValue::Array(comparisons)),
+                    ("leaf_value".to_string(), number(tree.leaf_value)),
+                    ("accumulator".to_string(), JsonValue::Number(tree.accumulator.to_string())),
+                ])
+            })
+            .collect();
+
+        // Goldilocks elements can exceed i64, so they are written unsigned
+        let features_hash = self.features_hash.iter().map(|x| JsonValue::Number(x.to_string())).collect();
+
+        let mut fields = vec![
+            ("output".to_string(), number(self.output())),
+            ("features_hash".to_string(), JsonValue::Array(features_hash)),
+            ("base_score".to_string(), number(self.base_score)),
+            ("combiner".to_string(), JsonValue::String(self.combiner.name().to_string())),
+            ("trees".to_string(), JsonValue::Array(trees)),
+        ];
+        if self.accumulator != Accumulator::I64 {
+            fields.insert(4, ("accumulator".to_string(), JsonValue::String(self.accumulator.name().to_string())));
+        }
+        if let Some(check) = &self.guard {
+            let guard = JsonValue::Object(vec![
+                ("min".to_string(), number(check.guard.min)),
+                ("max".to_string(), number(check.guard.max)),
+                ("clamp".to_string(), JsonValue::Bool(check.guard.clamp)),
+                ("decision".to_string(), JsonValue::String(check.decision.name().to_string())),
+            ]);
+            fields.push(("guard".to_string(), guard));
+        }
+        if let Some(fingerprint) = &self.fingerprint {
+            fields.push(("fingerprint".to_string(), fingerprint.to_json()));
+        }
+        JsonValue::Object(fields)
+    }
+}
+
+impl Ensemble {
+    /// Evaluate the ensemble and record the witness trace
+    ///
+    /// # Arguments
+    /// * `features` - Input feature vector (at the ensemble's scale)
+    ///
+    /// # Returns
+    /// * `(i64, ExecutionTrace)` - Prediction (same as `eval`) and the trace that produced it
+    pub fn eval_with_trace(&self, features: &[i64]) -> (i64, ExecutionTrace) {
+        assert!(features.len() >= self.num_features(),
+                "Expected at least {} features, got {}", self.num_features(), features.len());
+
+        let mut trace = ExecutionTrace {
+            features_hash: hash_features(features),
+            base_score: self.base_score(),
+            combiner: self.combiner(),
+            accumulator: self.accumulator(),
+            trees: Vec::with_capacity(self.num_trees()),
+            guard: None,
+            fingerprint: None,
+        };
+        let mut y = self.combiner().initial(self.base_score()) as i128;
+
+        for tree in self.trees() {
+            let mut node = &tree.root;
+            let mut node_id = 0;
+            let mut path = Vec::new();
+            let mut comparisons = Vec::new();
+
+            let leaf_value = loop {
+                path.push(node_id);
+                let (feature, threshold, categories, left, right) = match node {
+                    Node::Leaf(value) => break *value,
+                    Node::Split { feature, threshold, left, right } => (*feature, *threshold, None, left, right),
+                    Node::Categorical { feature, categories, unit, left, right } => {
+                        (*feature, *unit, Some(categories.clone()), left, right)
+                    }
+                };
+                let value = features[feature];
+                let outcome = match &categories {
+                    Some(categories) => categories.contains_value(value, threshold),
+                    None => fixed_le(value, threshold),
+                };
+                comparisons.push(Comparison { node_id, feature, value, threshold, categories, outcome });
+                if outcome {
+                    node_id += 1;
+                    node = left;
+                } else {
+                    node_id += 1 + left.num_nodes();
+                    node = right;
+                }
+            };
+
+            y = match self.accumulator() {
+                Accumulator::I64 => fixed_add(y as i64, leaf_value) as i128,
+                Accumulator::I128 => y + leaf_value as i128,
+            };
+            trace.trees.push(TreeTrace { path, comparisons, leaf_value, accumulator: y });
+        }
+
+        (trace.output(), trace)
+    }
+}
+
+/// `xgboost_predict` with the witness trace of the compiled model
+///
+/// # Arguments
+/// * `features` - Input feature vector as slice of i64 values (scaled by 10^10)
+///
+/// # Returns
+/// * `(i64, ExecutionTrace)` - Prediction (equal to `xgboost_predict`) and its trace, with the
+///   build fingerprint
+pub fn xgboost_predict_with_trace(features: &[i64]) -> (i64, ExecutionTrace) {
+    let traced = |model: &Ensemble, fingerprint: Fingerprint| {
+        let (prediction, trace) = model.eval_with_trace(features);
+        (prediction, trace.with_fingerprint(fingerprint))
+    };
+    #[cfg(feature = "std")]
+    {
+        static BUILTIN: OnceLock<(Ensemble, Fingerprint)> = OnceLock::new();
+        let (model, fingerprint) = BUILTIN.get_or_init(|| {
+            let model = crate::builtin_model();
+            let fingerprint = Fingerprint::new(&model, EvaluatorMode::Branching);
+            (model, fingerprint)
+        });
+        traced(model, fingerprint.clone())
+    }
+    // Without std there is no lazy static; parse the bundled dump on every call
+    #[cfg(not(feature = "std"))]
+    {
+        let model = crate::builtin_model();
+        let fingerprint = Fingerprint::new(&model, EvaluatorMode::Branching);
+        traced(&model, fingerprint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{xgboost_predict, Tree, NUM_FEATURES};
+
+    #[test]
+    fn test_trace_matches_prediction() {
+        let mut features = vec![0i64; NUM_FEATURES];
+        features[34] = 130000000000;
+        features[77] = -20000000000;
+
+        let (prediction, trace) = xgboost_predict_with_trace(&features);
+        assert_eq!(prediction, xgboost_predict(&features));
+        assert_eq!(trace.output(), prediction);
+        assert_eq!(trace.trees.len(), 10);
+
+        for tree in &trace.trees {
+            assert_eq!(tree.path.len(), tree.comparisons.len() + 1);
+            for c in &tree.comparisons {
+                assert_eq!(c.value, features[c.feature]);
+                assert_eq!(c.outcome, c.value <= c.threshold);
+            }
+        }
+    }
+
+    #[test]
+    fn test_node_ids_are_preorder() {
+        // 0: x0 <= 0 ? (1: x1 <= 0 ? [2] : [3]) : [4]
+        let tree = Tree::new(Node::split(
+            0,
+            0,
+            Node::split(1, 0, Node::Leaf(2), Node::Leaf(3)),
+            Node::Leaf(4),
+        ));
+        let ensemble = Ensemble::new(vec![tree.clone(), tree], 1);
+
+        let (y, trace) = ensemble.eval_with_trace(&[0, 1]);
+        assert_eq!(y, 6);
+        assert_eq!(trace.trees[0].path, vec![0, 1, 3]);
+        assert_eq!(trace.trees[0].accumulator, 3);
+        assert_eq!(ensemble.eval_with_trace(&[1, 0]).1.trees[1].path, vec![0, 4]);
+        assert_eq!(trace.num_comparisons(), 4);
+
+        // Averaging keeps the running sum and divides once
+        let forest = ensemble.with_combiner(Combiner::Average).with_base_score(100);
+        let (y, trace) = forest.eval_with_trace(&[0, 1]);
+        assert_eq!((y, trace.trees[1].accumulator), (103, 6));
+        assert_eq!(y, forest.eval(&[0, 1]));
+    }
+
+    #[test]
+    fn test_trace_json() {
+        let ensemble = Ensemble::new(vec![Tree::new(Node::split(0, 5, Node::Leaf(1), Node::Leaf(2)))], 1)
+            .with_base_score(10);
+        let trace = ensemble.eval_with_trace(&[7]).1;
+        assert_eq!(trace.features_hash, hash_features(&[7]));
+
+        let [h0, h1, h2, h3] = trace.features_hash;
+        let json = trace.to_json().to_string();
+        let hash = format!("[{},{},{},{}]", h0, h1, h2, h3);
+        assert_eq!(
+            json.replace(&hash, "H"),
+            r#"{"output":12,"features_hash":H,"base_score":10,"combiner":"sum","trees":[{"path":[0,2],"comparisons":[{"node_id":0,"feature":0,"value":7,"threshold":5,"outcome":false}],"leaf_value":2,"accumulator":12}]}"#
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_trace_serde_round_trip() {
+        let mut features = vec![0i64; NUM_FEATURES];
+        features[54] = 20000000000;
+        let trace = xgboost_predict_with_trace(&features).1.with_guard(OutputGuard::new(0, 1).clamping());
+        let json = serde_json::to_string(&trace).unwrap();
+        let back: ExecutionTrace = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, trace);
+        assert_eq!(back.output(), trace.output());
+
+        let info = crate::builtin_model().with_name("rain").with_training_hash([1; 32]).model_info();
+        assert_eq!(serde_json::from_str::<crate::ModelInfo>(&serde_json::to_string(&info).unwrap()).unwrap(), info);
+    }
+}
@@ -0,0 +1,250 @@
+// Tree depth limiting
+// Proving time grows with the depth of the trees: every level is one more comparison and select
+// on each path. `Ensemble::truncate` cuts every tree at a maximum depth and replaces each removed
+// subtree by one leaf, the average of its leaves weighted by how many training samples reached
+// them (their XGBoost cover), re-quantized to the nearest unit of the model's scale. The covers
+// come from a CSV of `tree,leaf,cover` rows, where `leaf` numbers a tree's leaves left to right
+// (the "yes" branch first), or are taken as uniform when no training statistics are available.
+
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt;
+
+use crate::csv::{is_header, CsvError};
+use crate::ensemble::{Ensemble, Node, Tree};
+use crate::fixed::round_half_away;
+
+/// Error loading leaf covers
+#[derive(Debug, Clone, PartialEq)]
+pub enum CoverError {
+    /// Malformed or out-of-range line
+    Csv(CsvError),
+    /// A leaf of the ensemble has no cover
+    MissingLeaf { tree: usize, leaf: usize },
+}
+
+impl fmt::Display for CoverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoverError::Csv(e) => write!(f, "invalid cover CSV: {}", e),
+            CoverError::MissingLeaf { tree, leaf } => write!(f, "no cover for leaf {} of tree {}", leaf, tree),
+        }
+    }
+}
+
+impl core::error::Error for CoverError {}
+
+/// Training cover of every leaf of an ensemble
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeafCovers {
+    covers: Vec<Vec<f64>>,
+}
+
+fn num_leaves(node: &Node) -> usize {
+    match node {
+        Node::Leaf(_) => 1,
+        Node::Split { left, right, .. } | Node::Categorical { left, right, .. } => num_leaves(left) + num_leaves(right),
+    }
+}
+
+impl LeafCovers {
+    /// Cover 1 for every leaf: truncated subtrees become the plain mean of their leaves
+    pub fn uniform(model: &Ensemble) -> LeafCovers {
+        LeafCovers { covers: model.trees().iter().map(|tree| vec![1.0; num_leaves(&tree.root)]).collect() }
+    }
+
+    /// Parse `tree,leaf,cover` rows for the leaves of `model`
+    ///
+    /// # Arguments
+    /// * `text` - CSV contents, with an optional header line
+    /// * `model` - Ensemble the covers belong to; every one of its leaves needs exactly one row
+    ///
+    /// # Returns
+    /// * `Result<LeafCovers, CoverError>` - The covers, or the first problem found
+    pub fn from_csv(text: &str, model: &Ensemble) -> Result<LeafCovers, CoverError> {
+        let mut covers: Vec<Vec<Option<f64>>> =
+            model.trees().iter().map(|tree| vec![None; num_leaves(&tree.root)]).collect();
+
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || (index == 0 && is_header(line)) {
+                continue;
+            }
+            let error = |message: String| CoverError::Csv(CsvError { line: index + 1, message });
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [tree, leaf, cover] = fields[..] else {
+                return Err(error(format!("expected 3 fields (tree,leaf,cover), got {}", fields.len())));
+            };
+            let (Ok(tree), Ok(leaf)) = (tree.parse::<usize>(), leaf.parse::<usize>()) else {
+                return Err(error(format!("'{}' and '{}' must be tree and leaf indices", tree, leaf)));
+            };
+            let cover = match cover.parse::<f64>() {
+                Ok(cover) if cover.is_finite() && cover >= 0.0 => cover,
+                _ => return Err(error(format!("cover '{}' is not a non-negative number", cover))),
+            };
+            let slot = covers
+                .get_mut(tree)
+                .and_then(|leaves| leaves.get_mut(leaf))
+                .ok_or_else(|| error(format!("the model has no leaf {} in tree {}", leaf, tree)))?;
+            if slot.replace(cover).is_some() {
+                return Err(error(format!("duplicate cover for leaf {} of tree {}", leaf, tree)));
+            }
+        }
+
+        let covers = covers
+            .into_iter()
+            .enumerate()
+            .map(|(tree, leaves)| {
+                leaves
+                    .into_iter()
+                    .enumerate()
+                    .map(|(leaf, cover)| cover.ok_or(CoverError::MissingLeaf { tree, leaf }))
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(LeafCovers { covers })
+    }
+
+    /// Covers of one tree's leaves, left to right
+    pub fn tree(&self, tree: usize) -> &[f64] {
+        &self.covers[tree]
+    }
+}
+
+/// Append the (value, cover) pairs of a subtree's leaves, left to right
+fn collect_leaves(node: &Node, covers: &[f64], next: &mut usize, leaves: &mut Vec<(i64, f64)>) {
+    match node {
+        Node::Leaf(value) => {
+            leaves.push((*value, covers[*next]));
+            *next += 1;
+        }
+        Node::Split { left, right, .. } | Node::Categorical { left, right, .. } => {
+            collect_leaves(left, covers, next, leaves);
+            collect_leaves(right, covers, next, leaves);
+        }
+    }
+}
+
+/// Cover-weighted average of leaves, rounded to the nearest unit (plain mean without cover)
+fn weighted_leaf(leaves: &[(i64, f64)]) -> i64 {
+    let total: f64 = leaves.iter().map(|&(_, cover)| cover).sum();
+    let mean = if total > 0.0 {
+        leaves.iter().map(|&(value, cover)| value as f64 * cover).sum::<f64>() / total
+    } else {
+        leaves.iter().map(|&(value, _)| value as f64).sum::<f64>() / leaves.len() as f64
+    };
+    round_half_away(mean)
+}
+
+fn truncate_node(node: &Node, depth: usize, covers: &[f64], next: &mut usize) -> Node {
+    match node {
+        Node::Leaf(value) => {
+            *next += 1;
+            Node::Leaf(*value)
+        }
+        _ if depth == 0 => {
+            let mut leaves = Vec::new();
+            collect_leaves(node, covers, next, &mut leaves);
+            Node::Leaf(weighted_leaf(&leaves))
+        }
+        Node::Split { feature, threshold, left, right } => {
+            let left = truncate_node(left, depth - 1, covers, next);
+            Node::split(*feature, *threshold, left, truncate_node(right, depth - 1, covers, next))
+        }
+        Node::Categorical { feature, categories, unit, left, right } => {
+            let left = truncate_node(left, depth - 1, covers, next);
+            Node::categorical(*feature, categories.clone(), *unit, left, truncate_node(right, depth - 1, covers, next))
+        }
+    }
+}
+
+impl Ensemble {
+    /// Cut every tree at `max_depth`, replacing deeper subtrees by their cover-weighted average leaf
+    ///
+    /// # Arguments
+    /// * `max_depth` - Largest number of splits on any path (0 turns every tree into one leaf)
+    /// * `covers` - Leaf covers of this ensemble (`LeafCovers::from_csv` or `LeafCovers::uniform`)
+    ///
+    /// # Returns
+    /// * `Ensemble` - Truncated ensemble, with the same metadata
+    pub fn truncate(&self, max_depth: usize, covers: &LeafCovers) -> Ensemble {
+        assert_eq!(covers.covers.len(), self.num_trees(), "covers belong to a different ensemble");
+        let trees = self
+            .trees()
+            .iter()
+            .enumerate()
+            .map(|(index, tree)| {
+                let covers = covers.tree(index);
+                assert_eq!(covers.len(), num_leaves(&tree.root), "covers belong to a different ensemble");
+                Tree::new(truncate_node(&tree.root, max_depth, covers, &mut 0))
+            })
+            .collect();
+        self.with_trees(trees)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builtin_model, BitSet};
+
+    fn model() -> Ensemble {
+        let categories: BitSet = [2].into_iter().collect();
+        let tree = Tree::new(Node::split(
+            0,
+            5,
+            Node::categorical(1, categories, 1, Node::Leaf(10), Node::split(2, 0, Node::Leaf(40), Node::Leaf(70))),
+            Node::Leaf(-3),
+        ));
+        Ensemble::new(vec![tree, Tree::new(Node::Leaf(8))], 1).with_base_score(1)
+    }
+
+    #[test]
+    fn test_truncate_weighted_average() {
+        let model = model();
+        let csv = "tree,leaf,cover\n0,0,1\n0,1,2\n0,2,1\n0,3,6\n1,0,4\n";
+        let covers = LeafCovers::from_csv(csv, &model).unwrap();
+        assert_eq!(covers.tree(0), [1.0, 2.0, 1.0, 6.0]);
+
+        assert_eq!(model.truncate(3, &covers), model);
+        let depth_one = model.truncate(1, &covers);
+        // (10 * 1 + 40 * 2 + 70 * 1) / 4 = 40
+        assert_eq!(depth_one.trees()[0].root, Node::split(0, 5, Node::Leaf(40), Node::Leaf(-3)));
+        assert_eq!(depth_one.trees()[1].root, Node::Leaf(8));
+        assert_eq!(depth_one.base_score(), 1);
+        // (160 - 18) / 10 = 14.2
+        assert_eq!(model.truncate(0, &covers).trees()[0].root, Node::Leaf(14));
+        // Plain mean (10 + 40 + 70 - 3) / 4 = 29.25
+        assert_eq!(model.truncate(0, &LeafCovers::uniform(&model)).trees()[0].root, Node::Leaf(29));
+        let depth_two = model.truncate(2, &LeafCovers::uniform(&model));
+        assert_eq!(depth_two.trees()[0].depth(), 2);
+        assert_eq!(depth_two.trees()[0].num_nodes(), 5);
+    }
+
+    #[test]
+    fn test_cover_csv_errors() {
+        let model = model();
+        let missing = LeafCovers::from_csv("0,0,1\n0,1,1\n0,3,1\n1,0,1\n", &model);
+        assert_eq!(missing, Err(CoverError::MissingLeaf { tree: 0, leaf: 2 }));
+        assert_eq!(missing.unwrap_err().to_string(), "no cover for leaf 2 of tree 0");
+
+        let line = |csv: &str| match LeafCovers::from_csv(csv, &model) {
+            Err(CoverError::Csv(e)) => e.line,
+            other => panic!("expected a CSV error, got {:?}", other),
+        };
+        assert_eq!(line("0,0,1\n0,4,1\n"), 2);
+        assert_eq!(line("0,0,1\n0,0,2\n"), 2);
+        assert_eq!(line("0,0,-1\n"), 1);
+        assert_eq!(line("0,0\n"), 1);
+        assert_eq!(line("0,0,1\nx,0,1\n"), 2);
+    }
+
+    #[test]
+    fn test_truncate_builtin_model() {
+        let model = builtin_model();
+        let covers = LeafCovers::uniform(&model);
+        let max_depth = model.trees().iter().map(Tree::depth).max().unwrap();
+        assert_eq!(model.truncate(max_depth, &covers), model);
+        let shallow = model.truncate(3, &covers);
+        assert!(shallow.trees().iter().all(|tree| tree.depth() <= 3));
+        assert!(shallow.trees().iter().map(Tree::num_nodes).sum::<usize>() < 330);
+    }
+}
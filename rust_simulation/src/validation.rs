@@ -0,0 +1,329 @@
+// Cross-check harness against native XGBoost
+// Runs the original booster (through converter/xgb_reference.py) and the fixed-point port over the
+// same samples and reports how far apart they are, per sample and per tree, in fixed-point units.
+// A deviation above one unit means a sample went down a different path than in XGBoost.
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use crate::csv::{parse_row, parse_samples, CsvError};
+use crate::ensemble::Ensemble;
+use crate::fixed::round_half_away;
+use crate::json::JsonValue;
+use crate::model::scale_dump_value;
+
+/// Largest per-tree deviation (in fixed-point units) still attributed to rounding
+pub const TOLERANCE: i64 = 1;
+
+/// Error raised while collecting reference predictions
+#[derive(Debug)]
+pub enum ValidationHarnessError {
+    /// A file or the reference process could not be accessed
+    Io(io::Error),
+    /// The samples CSV is malformed
+    Csv(CsvError),
+    /// The reference process failed or printed something unexpected
+    Reference(String),
+}
+
+impl fmt::Display for ValidationHarnessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationHarnessError::Io(e) => write!(f, "I/O error: {}", e),
+            ValidationHarnessError::Csv(e) => write!(f, "invalid samples CSV: {}", e),
+            ValidationHarnessError::Reference(message) => write!(f, "reference predictor failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ValidationHarnessError {}
+
+impl From<io::Error> for ValidationHarnessError {
+    fn from(e: io::Error) -> Self {
+        ValidationHarnessError::Io(e)
+    }
+}
+
+impl From<CsvError> for ValidationHarnessError {
+    fn from(e: CsvError) -> Self {
+        ValidationHarnessError::Csv(e)
+    }
+}
+
+/// Read the raw samples to cross-check from a CSV file
+pub fn read_samples<P: AsRef<Path>>(path: P, num_features: usize) -> Result<Vec<Vec<f64>>, ValidationHarnessError> {
+    Ok(parse_samples(&std::fs::read_to_string(path)?, num_features)?)
+}
+
+/// Per-tree leaf values of the native booster, computed by a Python subprocess
+///
+/// # Arguments
+/// * `python` - Python interpreter with `xgboost` installed
+/// * `script` - Path to `converter/xgb_reference.py`
+/// * `model` - Original XGBoost model file
+/// * `samples_csv` - Samples CSV, the same file passed to `read_samples`
+/// * `num_trees` - Number of leading trees to report (the port's tree count)
+///
+/// # Returns
+/// * `Result<Vec<Vec<f64>>, ValidationHarnessError>` - One row of `num_trees` leaf values per sample
+pub fn python_reference(
+    python: &str,
+    script: &Path,
+    model: &Path,
+    samples_csv: &Path,
+    num_trees: usize,
+) -> Result<Vec<Vec<f64>>, ValidationHarnessError> {
+    let output = Command::new(python)
+        .arg(script)
+        .arg(model)
+        .arg(samples_csv)
+        .arg(num_trees.to_string())
+        .output()?;
+    if !output.status.success() {
+        return Err(ValidationHarnessError::Reference(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+
+    let mut rows = Vec::new();
+    for (index, line) in String::from_utf8_lossy(&output.stdout).lines().enumerate() {
+        let row = parse_row(line)
+            .map_err(|message| ValidationHarnessError::Reference(format!("output line {}: {}", index + 1, message)))?;
+        if row.len() != num_trees {
+            return Err(ValidationHarnessError::Reference(format!(
+                "output line {}: expected {} leaf values, got {}",
+                index + 1,
+                num_trees,
+                row.len()
+            )));
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Deviation of one sample
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampleDeviation {
+    /// Row index in the samples CSV
+    pub index: usize,
+    /// Prediction of the fixed-point port
+    pub port: i64,
+    /// XGBoost prediction (sum of leaves) quantized to the port's scale, plus the port's base score
+    pub reference: i64,
+    /// |port - reference|
+    pub deviation: i64,
+    /// Largest deviation of a single tree
+    pub max_tree_deviation: i64,
+    /// Trees whose deviation exceeds `TOLERANCE`
+    pub mismatched_trees: Vec<usize>,
+}
+
+/// Deviation of one tree across all samples
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeDeviation {
+    pub tree: usize,
+    pub max: i64,
+    pub mean: f64,
+    /// Samples on which the tree deviates by more than `TOLERANCE`
+    pub mismatches: usize,
+}
+
+/// Result of a cross-check run
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossCheckReport {
+    /// Fixed-point scale all deviations are expressed in
+    pub scale: i64,
+    pub samples: Vec<SampleDeviation>,
+    pub trees: Vec<TreeDeviation>,
+}
+
+impl CrossCheckReport {
+    /// Largest prediction deviation over all samples
+    pub fn max_deviation(&self) -> i64 {
+        self.samples.iter().map(|s| s.deviation).max().unwrap_or(0)
+    }
+
+    /// Mean prediction deviation over all samples
+    pub fn mean_deviation(&self) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        self.samples.iter().map(|s| s.deviation as f64).sum::<f64>() / self.samples.len() as f64
+    }
+
+    /// Whether every tree agreed with XGBoost on every sample
+    pub fn passed(&self) -> bool {
+        self.samples.iter().all(|s| s.mismatched_trees.is_empty())
+    }
+
+    /// Machine-readable form of the report
+    pub fn to_json(&self) -> JsonValue {
+        let number = |value: String| JsonValue::Number(value);
+        let samples = self
+            .samples
+            .iter()
+            .map(|s| {
+                JsonValue::Object(vec![
+                    ("index".to_string(), number(s.index.to_string())),
+                    ("port".to_string(), number(s.port.to_string())),
+                    ("reference".to_string(), number(s.reference.to_string())),
+                    ("deviation".to_string(), number(s.deviation.to_string())),
+                    ("max_tree_deviation".to_string(), number(s.max_tree_deviation.to_string())),
+                    (
+                        "mismatched_trees".to_string(),
+                        JsonValue::Array(s.mismatched_trees.iter().map(|t| number(t.to_string())).collect()),
+                    ),
+                ])
+            })
+            .collect();
+        let trees = self
+            .trees
+            .iter()
+            .map(|t| {
+                JsonValue::Object(vec![
+                    ("tree".to_string(), number(t.tree.to_string())),
+                    ("max".to_string(), number(t.max.to_string())),
+                    ("mean".to_string(), number(t.mean.to_string())),
+                    ("mismatches".to_string(), number(t.mismatches.to_string())),
+                ])
+            })
+            .collect();
+        JsonValue::Object(vec![
+            ("scale".to_string(), number(self.scale.to_string())),
+            ("max_deviation".to_string(), number(self.max_deviation().to_string())),
+            ("mean_deviation".to_string(), number(self.mean_deviation().to_string())),
+            ("passed".to_string(), JsonValue::Bool(self.passed())),
+            ("samples".to_string(), JsonValue::Array(samples)),
+            ("trees".to_string(), JsonValue::Array(trees)),
+        ])
+    }
+}
+
+impl fmt::Display for CrossCheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Samples: {}, trees: {}, scale: {}", self.samples.len(), self.trees.len(), self.scale)?;
+        writeln!(f, "Prediction deviation: max {}, mean {:.3}", self.max_deviation(), self.mean_deviation())?;
+        for sample in self.samples.iter().filter(|s| !s.mismatched_trees.is_empty()) {
+            writeln!(
+                f,
+                "  sample {}: port {} vs reference {} (deviation {}), mismatched trees {:?}",
+                sample.index, sample.port, sample.reference, sample.deviation, sample.mismatched_trees
+            )?;
+        }
+        for tree in self.trees.iter().filter(|t| t.mismatches > 0) {
+            writeln!(
+                f,
+                "  tree {}: max {}, mean {:.3}, {} mismatching samples",
+                tree.tree, tree.max, tree.mean, tree.mismatches
+            )?;
+        }
+        write!(f, "{}", if self.passed() { "PASS" } else { "FAIL" })
+    }
+}
+
+/// Compare the port against reference leaf values
+///
+/// # Arguments
+/// * `model` - Fixed-point port
+/// * `samples` - Raw feature vectors
+/// * `reference` - XGBoost leaf value of every tree for every sample (see `python_reference`)
+///
+/// # Returns
+/// * `CrossCheckReport` - Deviations in units of the model's scale
+pub fn cross_check(model: &Ensemble, samples: &[Vec<f64>], reference: &[Vec<f64>]) -> CrossCheckReport {
+    assert_eq!(samples.len(), reference.len(), "one reference row per sample");
+    let scale = model.scale();
+    let num_trees = model.num_trees();
+
+    let mut tree_max = vec![0i64; num_trees];
+    let mut tree_sum = vec![0f64; num_trees];
+    let mut tree_mismatches = vec![0usize; num_trees];
+    let mut sample_deviations = Vec::with_capacity(samples.len());
+
+    for (index, (raw, leaves)) in samples.iter().zip(reference).enumerate() {
+        assert_eq!(leaves.len(), num_trees, "one reference leaf per tree");
+        let features: Vec<i64> = raw.iter().map(|&x| round_half_away(x * scale as f64)).collect();
+
+        let mut max_tree_deviation = 0;
+        let mut mismatched_trees = Vec::new();
+        for (tree, (port_tree, &leaf)) in model.trees().iter().zip(leaves).enumerate() {
+            let deviation = port_tree.eval(&features).abs_diff(scale_dump_value(leaf, scale)) as i64;
+            tree_max[tree] = tree_max[tree].max(deviation);
+            tree_sum[tree] += deviation as f64;
+            max_tree_deviation = max_tree_deviation.max(deviation);
+            if deviation > TOLERANCE {
+                tree_mismatches[tree] += 1;
+                mismatched_trees.push(tree);
+            }
+        }
+
+        let port = model.eval(&features);
+        let reference = model.base_score().saturating_add(scale_dump_value(leaves.iter().sum(), scale));
+        sample_deviations.push(SampleDeviation {
+            index,
+            port,
+            reference,
+            deviation: port.abs_diff(reference) as i64,
+            max_tree_deviation,
+            mismatched_trees,
+        });
+    }
+
+    let count = samples.len().max(1) as f64;
+    let trees = (0..num_trees)
+        .map(|tree| TreeDeviation {
+            tree,
+            max: tree_max[tree],
+            mean: tree_sum[tree] / count,
+            mismatches: tree_mismatches[tree],
+        })
+        .collect();
+
+    CrossCheckReport { scale, samples: sample_deviations, trees }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::{Node, Tree};
+
+    /// Two trees on one feature at scale 100: x <= 0.5 ? 0.25 : -0.25 and x <= 1.5 ? 1 : 2
+    fn small_model() -> Ensemble {
+        Ensemble::new(
+            vec![
+                Tree::new(Node::split(0, 50, Node::Leaf(25), Node::Leaf(-25))),
+                Tree::new(Node::split(0, 150, Node::Leaf(100), Node::Leaf(200))),
+            ],
+            100,
+        )
+    }
+
+    #[test]
+    fn test_matching_reference_passes() {
+        let samples = vec![vec![0.0], vec![1.0], vec![2.0]];
+        let reference = vec![vec![0.25, 1.0], vec![-0.25, 1.0], vec![-0.25, 2.0]];
+        let report = cross_check(&small_model(), &samples, &reference);
+
+        assert!(report.passed());
+        assert_eq!(report.max_deviation(), 0);
+        assert_eq!(report.samples[2].port, 175);
+        assert!(report.to_string().ends_with("PASS"));
+    }
+
+    #[test]
+    fn test_divergent_path_is_reported() {
+        // XGBoost took the right branch of tree 1 for sample 1; sample 0 is off by rounding only
+        let samples = vec![vec![0.0], vec![1.0]];
+        let reference = vec![vec![0.2501, 1.0], vec![-0.25, 2.0]];
+        let report = cross_check(&small_model(), &samples, &reference);
+
+        assert!(!report.passed());
+        assert_eq!(report.samples[0].deviation, 0);
+        assert_eq!(report.samples[1].mismatched_trees, vec![1]);
+        assert_eq!(report.samples[1].deviation, 100);
+        assert_eq!(report.trees[1].mismatches, 1);
+        assert_eq!(report.trees[1].mean, 50.0);
+        assert_eq!(report.to_json().get("passed"), Some(&JsonValue::Bool(false)));
+    }
+}
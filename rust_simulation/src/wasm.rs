@@ -0,0 +1,59 @@
+// WebAssembly bindings
+// wasm-bindgen exports of the compiled model so a browser can run exactly the computation a proof
+// attests to before requesting or producing one. Every export goes through the checked API, so a
+// bad input throws a JS `Error` instead of aborting the module.
+
+use alloc::string::{String, ToString};
+
+use wasm_bindgen::prelude::*;
+
+use crate::checked::{try_predict, try_xgboost_predict};
+use crate::{fixed_sigmoid, from_fixed_point, NUM_FEATURES};
+
+/// Prediction in real units for raw features (`Float64Array` of `numFeatures()` values)
+#[wasm_bindgen]
+pub fn predict(features: &[f64]) -> Result<f64, JsError> {
+    Ok(from_fixed_point(try_predict(features)?))
+}
+
+/// Scaled prediction for raw features, as a `bigint` (scaled by 10^10)
+#[wasm_bindgen(js_name = predictScaled)]
+pub fn predict_scaled(features: &[f64]) -> Result<i64, JsError> {
+    Ok(try_predict(features)?)
+}
+
+/// Scaled prediction for already scaled features (`BigInt64Array`), bit-identical to the circuit
+#[wasm_bindgen(js_name = predictFixed)]
+pub fn predict_fixed(features: &[i64]) -> Result<i64, JsError> {
+    Ok(try_xgboost_predict(features)?)
+}
+
+/// Probability of the positive class for already scaled features (scaled by 10^10)
+#[wasm_bindgen(js_name = predictProbaFixed)]
+pub fn predict_proba_fixed(features: &[i64]) -> Result<i64, JsError> {
+    Ok(fixed_sigmoid(try_xgboost_predict(features)?))
+}
+
+/// Scale a raw value to the model's fixed-point representation
+#[wasm_bindgen(js_name = toFixedPoint)]
+pub fn to_fixed_point(value: f64) -> i64 {
+    crate::to_fixed_point(value)
+}
+
+/// Convert a scaled value back to real units
+#[wasm_bindgen(js_name = fromFixedPoint)]
+pub fn from_fixed_point_js(value: i64) -> f64 {
+    from_fixed_point(value)
+}
+
+/// Number of features the model reads
+#[wasm_bindgen(js_name = numFeatures)]
+pub fn num_features() -> usize {
+    NUM_FEATURES
+}
+
+/// Identifier of the compiled model
+#[wasm_bindgen(js_name = modelId)]
+pub fn model_id() -> String {
+    crate::MODEL_ID.to_string()
+}
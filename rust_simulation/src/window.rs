@@ -0,0 +1,279 @@
+// Streaming feature engineering over radar scans
+// The model's 116 inputs are hourly aggregates of the individual radar scans over a gauge: per
+// radar quantity the number of valid readings, their mean, min, max, median and sum, counts of
+// the radar's special codes, and per-type counts of the hydrometeor classification. `ScanWindow`
+// keeps the most recent scans in a rolling buffer and computes those aggregates in fixed point,
+// so the whole path from raw scans to prediction runs on integers.
+//
+// Readings use the codes of the source data (scaled like any other value):
+//   -99900 no echo (counted by `num_00`), -99901 range folded (`num_01`),
+//   -99903 data not collected (`num_03`), and MISSING_VALUE for an absent reading.
+// None of the codes is a valid reading. Statistics over no valid reading are MISSING_VALUE, as in
+// the training data. The mean is rounded to nearest with ties toward +infinity,
+// floor((2 * sum + n) / (2n)); the median of an even count is the floored mean of the two middle
+// readings.
+
+use alloc::{collections::VecDeque, vec::Vec};
+
+use crate::stats::{fixed_mean, fixed_sum, median_sorted};
+use crate::{to_fixed_point, xgboost_predict, Model, FEATURE_NAMES, MISSING_VALUE, NUM_FEATURES, PRECISION_MULTIPLIER};
+
+/// Radar quantities of a scan, in the order their aggregates appear in the feature vector
+pub const RADAR_QUANTITIES: [&str; 15] = [
+    "MassWeightedMean", "MassWeightedSD", "RR1", "ReflectivityQC", "LogWaterVolume", "Reflectivity",
+    "Composite", "RR3", "Zdr", "Velocity", "HybridScan", "TimeToEnd", "RhoHV", "RR2", "RadarQualityIndex",
+];
+
+/// Number of radar quantities in a scan
+pub const NUM_QUANTITIES: usize = RADAR_QUANTITIES.len();
+
+/// Reading code: no echo above the noise threshold (-99900, scaled)
+pub const NO_ECHO: i64 = -99900 * PRECISION_MULTIPLIER;
+/// Reading code: range-folded echo (-99901, scaled)
+pub const RANGE_FOLDED: i64 = -99901 * PRECISION_MULTIPLIER;
+/// Reading code: data not collected, e.g. beam blockage (-99903, scaled)
+pub const NOT_COLLECTED: i64 = -99903 * PRECISION_MULTIPLIER;
+
+/// One radar scan over the gauge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scan {
+    /// Scaled readings in `RADAR_QUANTITIES` order (codes for invalid readings)
+    pub readings: [i64; NUM_QUANTITIES],
+    /// Hydrometeor classification of the scan, if any
+    pub hydrometeor: Option<u8>,
+}
+
+impl Scan {
+    /// Quantize raw readings (codes such as -99900 are kept exactly)
+    pub fn from_f64(readings: &[f64; NUM_QUANTITIES], hydrometeor: Option<u8>) -> Scan {
+        Scan { readings: readings.map(to_fixed_point), hydrometeor }
+    }
+}
+
+/// Whether a reading is a measurement rather than a code
+fn is_valid(reading: i64) -> bool {
+    reading > NO_ECHO
+}
+
+/// One entry of the feature vector
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Aggregate {
+    /// Number of readings equal to a code
+    CountCode(usize, i64),
+    CountValid(usize),
+    Mean(usize),
+    Min(usize),
+    Max(usize),
+    Median(usize),
+    Sum(usize),
+    /// Number of scans with this hydrometeor type
+    Hydrometeor(u8),
+}
+
+impl Aggregate {
+    /// Parse a feature name such as `Zdr_num_03` or `Hydro_13`
+    fn parse(name: &str) -> Aggregate {
+        if let Some(kind) = name.strip_prefix("Hydro_") {
+            return Aggregate::Hydrometeor(kind.parse().expect("hydrometeor type"));
+        }
+        let (quantity, statistic) = name.split_once('_').expect("aggregate feature name");
+        let q = RADAR_QUANTITIES.iter().position(|&n| n == quantity).expect("known radar quantity");
+        match statistic {
+            "num_00" => Aggregate::CountCode(q, NO_ECHO),
+            "num_01" => Aggregate::CountCode(q, RANGE_FOLDED),
+            "num_03" => Aggregate::CountCode(q, NOT_COLLECTED),
+            "num_non_null" => Aggregate::CountValid(q),
+            "mean" => Aggregate::Mean(q),
+            "min" => Aggregate::Min(q),
+            "max" => Aggregate::Max(q),
+            "med" => Aggregate::Median(q),
+            "sum" => Aggregate::Sum(q),
+            _ => panic!("unknown aggregate {}", statistic),
+        }
+    }
+}
+
+/// Rolling buffer of the most recent scans, aggregated into the model's feature vector
+#[derive(Debug, Clone)]
+pub struct ScanWindow {
+    scans: VecDeque<Scan>,
+    capacity: usize,
+    plan: Vec<Aggregate>,
+}
+
+impl ScanWindow {
+    /// Window keeping at most `capacity` scans (about 12 per hour at one volume scan per 5 min)
+    pub fn new(capacity: usize) -> ScanWindow {
+        assert!(capacity > 0, "window must hold at least one scan");
+        ScanWindow {
+            scans: VecDeque::with_capacity(capacity),
+            capacity,
+            plan: FEATURE_NAMES.iter().map(|name| Aggregate::parse(name)).collect(),
+        }
+    }
+
+    /// Add a scan, evicting the oldest one when the window is full
+    pub fn push(&mut self, scan: Scan) {
+        if self.scans.len() == self.capacity {
+            self.scans.pop_front();
+        }
+        self.scans.push_back(scan);
+    }
+
+    /// Drop all scans, e.g. at the start of a new hour
+    pub fn clear(&mut self) {
+        self.scans.clear();
+    }
+
+    /// Scans in the window, oldest first
+    pub fn scans(&self) -> impl Iterator<Item = &Scan> {
+        self.scans.iter()
+    }
+
+    /// Number of scans in the window
+    pub fn len(&self) -> usize {
+        self.scans.len()
+    }
+
+    /// Whether the window holds no scan
+    pub fn is_empty(&self) -> bool {
+        self.scans.is_empty()
+    }
+
+    /// Maximum number of scans kept
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Aggregate the window into the model's feature vector
+    ///
+    /// # Returns
+    /// * `Vec<i64>` - The 116 features in `FEATURE_NAMES` order (scaled by 10^10; counts are
+    ///   scaled too), with MISSING_VALUE for statistics over no valid reading
+    pub fn features(&self) -> Vec<i64> {
+        // Valid readings of every quantity, sorted once for min, max and median
+        let valid: Vec<Vec<i64>> = (0..NUM_QUANTITIES)
+            .map(|q| {
+                let mut values: Vec<i64> = self.scans.iter().map(|s| s.readings[q]).filter(|&r| is_valid(r)).collect();
+                values.sort_unstable();
+                values
+            })
+            .collect();
+        let count = |n: usize| n as i64 * PRECISION_MULTIPLIER;
+        let statistic = |values: &[i64], f: fn(&[i64]) -> i64| if values.is_empty() { MISSING_VALUE } else { f(values) };
+
+        let features: Vec<i64> = self
+            .plan
+            .iter()
+            .map(|&aggregate| match aggregate {
+                Aggregate::CountCode(q, code) => count(self.scans.iter().filter(|s| s.readings[q] == code).count()),
+                Aggregate::CountValid(q) => count(valid[q].len()),
+                Aggregate::Mean(q) => fixed_mean(&valid[q]).unwrap_or(MISSING_VALUE),
+                Aggregate::Min(q) => statistic(&valid[q], |v| v[0]),
+                Aggregate::Max(q) => statistic(&valid[q], |v| v[v.len() - 1]),
+                Aggregate::Median(q) => statistic(&valid[q], median_sorted),
+                Aggregate::Sum(q) => statistic(&valid[q], fixed_sum),
+                Aggregate::Hydrometeor(kind) => count(self.scans.iter().filter(|s| s.hydrometeor == Some(kind)).count()),
+            })
+            .collect();
+        debug_assert_eq!(features.len(), NUM_FEATURES);
+        features
+    }
+
+    /// Predict with the compiled model on the current window
+    pub fn predict(&self) -> i64 {
+        xgboost_predict(&self.features())
+    }
+
+    /// Predict with a runtime model on the current window
+    pub fn predict_with(&self, model: &Model) -> i64 {
+        model.predict(&self.features())
+    }
+}
+
+/// Lazily predict after every scan of a stream, over a rolling window of `capacity` scans
+///
+/// # Arguments
+/// * `scans` - Scans in time order
+/// * `capacity` - Number of most recent scans aggregated per prediction
+///
+/// # Returns
+/// * `impl Iterator<Item = i64>` - One prediction per scan
+pub fn predict_scan_stream<I>(scans: I, capacity: usize) -> impl Iterator<Item = i64>
+where
+    I: IntoIterator<Item = Scan>,
+{
+    let mut window = ScanWindow::new(capacity);
+    scans.into_iter().map(move |scan| {
+        window.push(scan);
+        window.predict()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feature(name: &str) -> usize {
+        FEATURE_NAMES.iter().position(|&n| n == name).unwrap()
+    }
+
+    fn scan(reflectivity: f64, zdr: f64, hydrometeor: Option<u8>) -> Scan {
+        let quantity = |name: &str| RADAR_QUANTITIES.iter().position(|&n| n == name).unwrap();
+        let mut readings = [MISSING_VALUE; NUM_QUANTITIES];
+        readings[quantity("Reflectivity")] = to_fixed_point(reflectivity);
+        readings[quantity("Zdr")] = to_fixed_point(zdr);
+        Scan { readings, hydrometeor }
+    }
+
+    #[test]
+    fn test_aggregates() {
+        let mut window = ScanWindow::new(8);
+        window.push(scan(20.0, -99900.0, Some(1)));
+        window.push(scan(35.5, 0.25, Some(1)));
+        window.push(scan(-99903.0, -99903.0, Some(13)));
+        window.push(scan(24.0, 1.0, None));
+        let f = window.features();
+        assert_eq!(f.len(), NUM_FEATURES);
+
+        assert_eq!(f[feature("Reflectivity_num_non_null")], 3 * PRECISION_MULTIPLIER);
+        assert_eq!(f[feature("Reflectivity_min")], to_fixed_point(20.0));
+        assert_eq!(f[feature("Reflectivity_max")], to_fixed_point(35.5));
+        assert_eq!(f[feature("Reflectivity_med")], to_fixed_point(24.0));
+        assert_eq!(f[feature("Reflectivity_sum")], to_fixed_point(79.5));
+        assert_eq!(f[feature("Reflectivity_mean")], to_fixed_point(26.5));
+        // Median of two readings is their mean
+        assert_eq!(f[feature("Zdr_med")], to_fixed_point(0.625));
+        assert_eq!(f[feature("Zdr_num_00")], PRECISION_MULTIPLIER);
+        assert_eq!(f[feature("Zdr_num_03")], PRECISION_MULTIPLIER);
+        assert_eq!(f[feature("Zdr_num_non_null")], 2 * PRECISION_MULTIPLIER);
+        assert_eq!(f[feature("Hydro_1")], 2 * PRECISION_MULTIPLIER);
+        assert_eq!(f[feature("Hydro_13")], PRECISION_MULTIPLIER);
+        assert_eq!(f[feature("Hydro_0")], 0);
+        // No valid reading: statistics are missing, counts are zero
+        assert_eq!(f[feature("RR1_mean")], MISSING_VALUE);
+        assert_eq!(f[feature("RR1_num_non_null")], 0);
+        assert_eq!(window.predict(), xgboost_predict(&f));
+    }
+
+    #[test]
+    fn test_window_rolls() {
+        let mut window = ScanWindow::new(2);
+        for r in [10.0, 20.0, 30.0] {
+            window.push(scan(r, 0.0, None));
+        }
+        assert_eq!(window.len(), 2);
+        assert_eq!(window.features()[feature("Reflectivity_min")], to_fixed_point(20.0));
+
+        let scans = [10.0, 20.0, 30.0].map(|r| scan(r, 0.0, None));
+        let mut rolling = ScanWindow::new(2);
+        let expected: Vec<i64> = scans
+            .iter()
+            .map(|s| {
+                rolling.push(s.clone());
+                rolling.predict()
+            })
+            .collect();
+        assert_eq!(predict_scan_stream(scans, 2).collect::<Vec<_>>(), expected);
+    }
+}
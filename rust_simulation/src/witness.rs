@@ -0,0 +1,172 @@
+// Proof-input preparation
+// Turns feature vectors into backend-agnostic witness bundles: the private features, the public
+// prediction and the execution trace, whose features hash is the public handle on the inputs.
+// The R1CS, plonky2 and external circuit builders all start from these. Tracing and Poseidon
+// hashing dominate the wall-clock time of proving large batches, so with feature `parallel` the
+// batch is prepared on the rayon thread pool; bundles are pure functions of their input and come
+// back in input order either way. `WitnessBundle::export` splits a bundle into the public inputs
+// and the private witness a `ProofPolicy` declares.
+
+use alloc::{string::ToString, vec, vec::Vec};
+
+use crate::ensemble::Ensemble;
+use crate::fingerprint::{EvaluatorMode, Fingerprint};
+use crate::json::JsonValue;
+use crate::policy::{ProofPolicy, ProofValue, Visibility};
+use crate::poseidon::PoseidonDigest;
+use crate::sha256::to_hex;
+use crate::trace::ExecutionTrace;
+
+/// Everything a proving backend needs for one prediction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WitnessBundle {
+    /// Private input features (at the model's scale)
+    pub features: Vec<i64>,
+    /// Public prediction
+    pub prediction: i64,
+    /// Advice: comparisons, paths and accumulators, with the features hash
+    pub trace: ExecutionTrace,
+    /// SHA-256 content hash of the model (`Ensemble::content_hash`)
+    pub model_hash: [u8; 32],
+}
+
+impl WitnessBundle {
+    /// Poseidon digest of the features, the public commitment to the private inputs
+    pub fn features_hash(&self) -> PoseidonDigest {
+        self.trace.features_hash
+    }
+
+    /// JSON form: the features, the prediction, the trace (see `ExecutionTrace::to_json`) and the
+    /// model hash
+    pub fn to_json(&self) -> JsonValue {
+        let values = [ProofValue::Features, ProofValue::Prediction, ProofValue::Trace, ProofValue::ModelHash];
+        JsonValue::Object(values.into_iter().map(|value| (value.name().to_string(), self.value_json(value))).collect())
+    }
+
+    /// JSON form split by a policy: `{"public": {...}, "private": {...}}`, each section holding
+    /// its values in the order of `ProofValue::ALL`
+    pub fn export(&self, policy: &ProofPolicy) -> JsonValue {
+        let section = |visibility: Visibility| {
+            JsonValue::Object(
+                ProofValue::ALL
+                    .into_iter()
+                    .filter(|&value| policy.visibility(value) == visibility)
+                    .map(|value| (value.name().to_string(), self.value_json(value)))
+                    .collect(),
+            )
+        };
+        JsonValue::Object(vec![
+            ("public".to_string(), section(Visibility::Public)),
+            ("private".to_string(), section(Visibility::Private)),
+        ])
+    }
+
+    fn value_json(&self, value: ProofValue) -> JsonValue {
+        let number = |v: i64| JsonValue::Number(v.to_string());
+        match value {
+            ProofValue::Features => JsonValue::Array(self.features.iter().map(|&x| number(x)).collect()),
+            ProofValue::Prediction => number(self.prediction),
+            // Goldilocks elements can exceed i64, so they are written unsigned
+            ProofValue::FeaturesHash => {
+                JsonValue::Array(self.features_hash().iter().map(|x| JsonValue::Number(x.to_string())).collect())
+            }
+            ProofValue::ModelHash => JsonValue::String(to_hex(&self.model_hash)),
+            ProofValue::Trace => self.trace.to_json(),
+        }
+    }
+}
+
+/// Prepare the witness bundle of one prediction
+///
+/// # Arguments
+/// * `model` - Model the proof is about
+/// * `features` - Input feature vector (at the model's scale)
+///
+/// # Returns
+/// * `WitnessBundle` - Features, prediction (equal to `model.eval`) and execution trace, with the
+///   build fingerprint
+pub fn prepare(model: &Ensemble, features: &[i64]) -> WitnessBundle {
+    prepare_with_hash(model, model.content_hash(), features)
+}
+
+pub(crate) fn prepare_with_hash(model: &Ensemble, model_hash: [u8; 32], features: &[i64]) -> WitnessBundle {
+    let (prediction, trace) = model.eval_with_trace(features);
+    let fingerprint = Fingerprint::with_model_hash(model_hash, model.scale(), EvaluatorMode::Branching);
+    WitnessBundle { features: features.to_vec(), prediction, trace: trace.with_fingerprint(fingerprint), model_hash }
+}
+
+/// Prepare the witness bundles of a batch, in parallel with feature `parallel`
+///
+/// # Arguments
+/// * `model` - Model the proofs are about
+/// * `batch` - Feature vectors (at the model's scale)
+///
+/// # Returns
+/// * `Vec<WitnessBundle>` - One bundle per input, in input order
+pub fn prepare_batch(model: &Ensemble, batch: &[Vec<i64>]) -> Vec<WitnessBundle> {
+    // Hashing the model once serves the whole batch
+    let model_hash = model.content_hash();
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+
+        batch.par_iter().map(|features| prepare_with_hash(model, model_hash, features)).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        batch.iter().map(|features| prepare_with_hash(model, model_hash, features)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon::hash_features;
+    use crate::{builtin_model, xgboost_predict_batch, NUM_FEATURES};
+
+    #[test]
+    fn test_prepare_batch_keeps_order() {
+        let batch: Vec<Vec<i64>> = (0..48)
+            .map(|i| (0..NUM_FEATURES).map(|j| ((i * 29 + j * 13) % 300) as i64 * 1_000_000_000 - 40_000_000_000).collect())
+            .collect();
+        let model = builtin_model();
+        let bundles = prepare_batch(&model, &batch);
+
+        let predictions: Vec<i64> = bundles.iter().map(|b| b.prediction).collect();
+        assert_eq!(predictions, xgboost_predict_batch(&batch));
+        for (bundle, features) in bundles.iter().zip(&batch) {
+            assert_eq!(&bundle.features, features);
+            assert_eq!(bundle.features_hash(), hash_features(features));
+            assert_eq!(bundle.trace.output(), bundle.prediction);
+            assert_eq!(*bundle, prepare(&model, features));
+        }
+        assert!(prepare_batch(&model, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_bundle_json() {
+        let model = Ensemble::new(vec![crate::Tree::new(crate::Node::split(0, 5, crate::Node::Leaf(1), crate::Node::Leaf(2)))], 1);
+        let bundle = prepare(&model, &[3]);
+        let json = bundle.to_json().to_string();
+        assert!(json.starts_with(r#"{"features":[3],"prediction":1,"trace":{"output":1,"features_hash":["#), "{}", json);
+        assert!(json.ends_with(&alloc::format!(r#""model_hash":"{}"}}"#, to_hex(&model.content_hash()))), "{}", json);
+    }
+
+    #[test]
+    fn test_export_follows_policy() {
+        let model = builtin_model();
+        let bundle = prepare(&model, &[0; NUM_FEATURES]);
+        let export = bundle.export(&ProofPolicy::default());
+        let public = export.get("public").and_then(JsonValue::as_object).unwrap();
+        let names: Vec<&str> = public.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["prediction", "features_hash", "model_hash"]);
+        assert_eq!(public[0].1.as_i64(), Some(bundle.prediction));
+        let private = export.get("private").and_then(JsonValue::as_object).unwrap();
+        assert_eq!(private.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), ["features", "trace"]);
+
+        let hidden = ProofPolicy::default().with(ProofValue::Prediction, Visibility::Private);
+        let export = bundle.export(&hidden);
+        assert!(export.get("public").and_then(|p| p.get("prediction")).is_none());
+        assert_eq!(export.get("private").and_then(|p| p.get("prediction")).and_then(JsonValue::as_i64), Some(bundle.prediction));
+    }
+}
@@ -0,0 +1,279 @@
+// Custom plonky2 gate for the split comparisons
+// `FixedComparisonGate` proves `a > b` for fixed-point values known to satisfy |x| < 2^n. For
+// such values b - a lies in (-2^(n+1), 2^(n+1)), so it is `low - gt * 2^(n+1)` for exactly one
+// `low` in [0, 2^(n+1)) and one bit `gt`, and that bit is `a > b`. The gate holds `low` as n + 1
+// boolean wires and checks the identity directly on `a` and `b`. Both sides stay below 2^63 in
+// magnitude, so the identity cannot wrap the Goldilocks modulus.
+//
+// Generic plonky2 needs arithmetic gates to form b - a + 2^(n+1) and a BaseSum split of that
+// sum. This gate uses neither, and it packs as many comparisons into a row as its wires allow:
+// two at 61 bits, more for the narrower ranges of `Plonky2Circuit::with_bounds`. Only `a`, `b`
+// and `gt` are routed. The gate outputs the strict comparison because plonky2 leaves the unused
+// operations of a row's last gate zero, and all-zero wires satisfy `0 > 0` = 0, but not `0 <= 0`.
+// `fixed_le_target` negates it.
+//
+// `GateSerializer` writes plonky2's default gates with their usual tags, followed by this one, so
+// verifier keys and proving keys can hold circuits that use it. It lives beside `zk::verifier`,
+// which reads those keys without the prover.
+
+use ::plonky2::field::extension::Extendable;
+use ::plonky2::field::types::{Field, PrimeField64};
+use ::plonky2::gates::arithmetic_base::ArithmeticGate;
+use ::plonky2::gates::arithmetic_extension::ArithmeticExtensionGate;
+use ::plonky2::gates::base_sum::BaseSumGate;
+use ::plonky2::gates::constant::ConstantGate;
+use ::plonky2::gates::coset_interpolation::CosetInterpolationGate;
+use ::plonky2::gates::exponentiation::ExponentiationGate;
+use ::plonky2::gates::gate::Gate;
+use ::plonky2::gates::lookup::LookupGate;
+use ::plonky2::gates::lookup_table::LookupTableGate;
+use ::plonky2::gates::multiplication_extension::MulExtensionGate;
+use ::plonky2::gates::noop::NoopGate;
+use ::plonky2::gates::poseidon::PoseidonGate;
+use ::plonky2::gates::poseidon_mds::PoseidonMdsGate;
+use ::plonky2::gates::public_input::PublicInputGate;
+use ::plonky2::gates::random_access::RandomAccessGate;
+use ::plonky2::gates::reducing::ReducingGate;
+use ::plonky2::gates::reducing_extension::ReducingExtensionGate;
+use ::plonky2::gates::util::StridedConstraintConsumer;
+use ::plonky2::iop::ext_target::ExtensionTarget;
+use ::plonky2::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGeneratorRef};
+use ::plonky2::iop::target::Target;
+use ::plonky2::iop::witness::{PartitionWitness, Witness, WitnessWrite};
+use ::plonky2::plonk::circuit_builder::CircuitBuilder;
+use ::plonky2::plonk::circuit_data::{CircuitConfig, CommonCircuitData};
+use ::plonky2::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBase};
+use ::plonky2::util::serialization::{Buffer, IoResult, Read, Write};
+use ::plonky2::{get_gate_tag_impl, impl_gate_serializer, read_gate_impl};
+
+use super::verifier::{D, F, VALUE_BITS};
+
+/// Gate proving `gt = a > b` for operands with |x| < 2^num_bits, `num_ops` times per row
+///
+/// Wires: `a`, `b` and `gt` of every operation (routed), then the n + 1 bits of `low` of every
+/// operation (unrouted).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FixedComparisonGate {
+    /// Operand range: |a|, |b| < 2^num_bits
+    pub num_bits: usize,
+    /// Comparisons per row
+    pub num_ops: usize,
+}
+
+impl FixedComparisonGate {
+    /// Gate with as many comparisons per row as `config` has wires for
+    ///
+    /// # Arguments
+    /// * `config` - Configuration of the circuit the gate is used in
+    /// * `num_bits` - Operand range, at most `VALUE_BITS`
+    ///
+    /// # Returns
+    /// * `FixedComparisonGate` - The gate; it holds at least one comparison for the standard configs
+    pub fn new_from_config(config: &CircuitConfig, num_bits: usize) -> FixedComparisonGate {
+        assert!(num_bits <= VALUE_BITS, "comparison of {} bits exceeds the circuit range", num_bits);
+        let num_ops = (config.num_routed_wires / 3).min(config.num_wires / (num_bits + 4));
+        assert!(num_ops > 0, "a {}-bit comparison does not fit in {} wires", num_bits, config.num_wires);
+        FixedComparisonGate { num_bits, num_ops }
+    }
+
+    /// Wire of the left operand of operation `i`
+    pub fn wire_a(&self, i: usize) -> usize {
+        3 * i
+    }
+
+    /// Wire of the right operand of operation `i`
+    pub fn wire_b(&self, i: usize) -> usize {
+        3 * i + 1
+    }
+
+    /// Wire of the result `a > b` of operation `i`
+    pub fn wire_gt(&self, i: usize) -> usize {
+        3 * i + 2
+    }
+
+    /// Wire of bit `j` of `low` of operation `i`
+    fn wire_bit(&self, i: usize, j: usize) -> usize {
+        3 * self.num_ops + i * (self.num_bits + 1) + j
+    }
+
+    /// Constraints of every operation: `low` and `gt` are bits, and b - a = low - gt * 2^(n+1)
+    fn constraints<T: Field>(&self, wire: impl Fn(usize) -> T) -> Vec<T> {
+        let mut constraints = Vec::with_capacity(self.num_ops * (self.num_bits + 3));
+        for i in 0..self.num_ops {
+            let mut low = T::ZERO;
+            for j in 0..=self.num_bits {
+                let bit = wire(self.wire_bit(i, j));
+                constraints.push(bit * (bit - T::ONE));
+                low += bit * T::from_canonical_u64(1 << j);
+            }
+            let gt = wire(self.wire_gt(i));
+            constraints.push(gt * (gt - T::ONE));
+            let wrap = gt * T::from_canonical_u64(1 << (self.num_bits + 1));
+            constraints.push(low - wrap - (wire(self.wire_b(i)) - wire(self.wire_a(i))));
+        }
+        constraints
+    }
+}
+
+impl Gate<F, D> for FixedComparisonGate {
+    fn id(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_usize(self.num_bits)?;
+        dst.write_usize(self.num_ops)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        Ok(FixedComparisonGate { num_bits: src.read_usize()?, num_ops: src.read_usize()? })
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<<F as Extendable<D>>::Extension> {
+        self.constraints(|wire| vars.local_wires[wire])
+    }
+
+    fn eval_unfiltered_base_one(&self, vars: EvaluationVarsBase<F>, mut yield_constr: StridedConstraintConsumer<F>) {
+        yield_constr.many(self.constraints(|wire| vars.local_wires[wire]));
+    }
+
+    fn eval_unfiltered_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_ops * (self.num_bits + 3));
+        for i in 0..self.num_ops {
+            let mut low = builder.zero_extension();
+            for j in 0..=self.num_bits {
+                let bit = vars.local_wires[self.wire_bit(i, j)];
+                constraints.push(builder.mul_sub_extension(bit, bit, bit));
+                low = builder.mul_const_add_extension(F::from_canonical_u64(1 << j), bit, low);
+            }
+            let gt = vars.local_wires[self.wire_gt(i)];
+            constraints.push(builder.mul_sub_extension(gt, gt, gt));
+            let wrapped = builder.mul_const_add_extension(-F::from_canonical_u64(1 << (self.num_bits + 1)), gt, low);
+            let difference = builder.sub_extension(vars.local_wires[self.wire_b(i)], vars.local_wires[self.wire_a(i)]);
+            constraints.push(builder.sub_extension(wrapped, difference));
+        }
+        constraints
+    }
+
+    fn generators(&self, row: usize, _local_constants: &[F]) -> Vec<WitnessGeneratorRef<F, D>> {
+        (0..self.num_ops)
+            .map(|op| WitnessGeneratorRef::new(FixedComparisonGenerator { gate: *self, row, op }.adapter()))
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.wire_bit(self.num_ops, 0)
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (self.num_bits + 3)
+    }
+}
+
+/// Fills `gt` and the bits of `low` of one operation of a `FixedComparisonGate`
+#[derive(Debug, Default)]
+pub struct FixedComparisonGenerator {
+    gate: FixedComparisonGate,
+    row: usize,
+    op: usize,
+}
+
+impl SimpleGenerator<F, D> for FixedComparisonGenerator {
+    fn id(&self) -> String {
+        "FixedComparisonGenerator".to_string()
+    }
+
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(self.row, self.gate.wire_a(self.op)), Target::wire(self.row, self.gate.wire_b(self.op))]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) -> anyhow::Result<()> {
+        let (gate, i) = (&self.gate, self.op);
+        let wire = |wire: usize| Target::wire(self.row, wire);
+        let a = witness.get_target(wire(gate.wire_a(i)));
+        let b = witness.get_target(wire(gate.wire_b(i)));
+        let shifted = (b - a + F::from_canonical_u64(1 << (gate.num_bits + 1))).to_canonical_u64();
+        if shifted >> (gate.num_bits + 2) != 0 {
+            anyhow::bail!("comparison operand exceeds {} bits", gate.num_bits);
+        }
+        // `shifted` is low + 2^(n+1) when a <= b and low otherwise
+        out_buffer.set_target(wire(gate.wire_gt(i)), F::from_bool(shifted >> (gate.num_bits + 1) == 0))?;
+        for j in 0..=gate.num_bits {
+            out_buffer.set_target(wire(gate.wire_bit(i, j)), F::from_bool((shifted >> j) & 1 == 1))?;
+        }
+        Ok(())
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        Gate::<F, D>::serialize(&self.gate, dst, common_data)?;
+        dst.write_usize(self.row)?;
+        dst.write_usize(self.op)
+    }
+
+    fn deserialize(src: &mut Buffer, common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        Ok(FixedComparisonGenerator {
+            gate: Gate::<F, D>::deserialize(src, common_data)?,
+            row: src.read_usize()?,
+            op: src.read_usize()?,
+        })
+    }
+}
+
+/// plonky2's default gates, in the order of `DefaultGateSerializer`, and `FixedComparisonGate`
+#[derive(Debug)]
+pub struct GateSerializer;
+
+impl ::plonky2::util::serialization::GateSerializer<F, D> for GateSerializer {
+    impl_gate_serializer! {
+        GateSerializer,
+        ArithmeticGate,
+        ArithmeticExtensionGate<D>,
+        BaseSumGate<2>,
+        ConstantGate,
+        CosetInterpolationGate<F, D>,
+        ExponentiationGate<F, D>,
+        LookupGate,
+        LookupTableGate,
+        MulExtensionGate<D>,
+        NoopGate,
+        PoseidonMdsGate<F, D>,
+        PoseidonGate<F, D>,
+        PublicInputGate,
+        RandomAccessGate<F, D>,
+        ReducingExtensionGate<D>,
+        ReducingGate<D>,
+        FixedComparisonGate
+    }
+}
+
+#[cfg(all(test, feature = "plonky2"))]
+mod tests {
+    use ::plonky2::gates::gate_testing::{test_eval_fns, test_low_degree};
+
+    use super::*;
+    use crate::zk::verifier::C;
+
+    #[test]
+    fn test_comparison_gate() {
+        let config = CircuitConfig::standard_recursion_config();
+        for num_bits in [VALUE_BITS, 20] {
+            let gate = FixedComparisonGate::new_from_config(&config, num_bits);
+            assert!(gate.num_wires() <= config.num_wires);
+            test_low_degree::<F, _, D>(gate);
+            test_eval_fns::<F, C, _, D>(gate).unwrap();
+        }
+        assert_eq!(FixedComparisonGate::new_from_config(&config, VALUE_BITS).num_ops, 2);
+    }
+}
@@ -0,0 +1,13 @@
+// Proof-system backends built from the `Model` IR
+// Each backend lives behind its own feature so the default build stays dependency-free.
+// Both implement `backend::CircuitBackend`, which owns the traversal they lower.
+// `verifier` holds the verification side of the plonky2 backend on its own, for devices that
+// only check proofs. `gates` holds the custom comparison gate of its circuits, which a verifier
+// needs to read their keys.
+
+pub mod gates;
+#[cfg(feature = "plonky2")]
+pub mod pipeline;
+#[cfg(feature = "plonky2")]
+pub mod plonky2;
+pub mod verifier;
@@ -0,0 +1,269 @@
+// Recursive composition of preprocessing and model proofs (plonky2)
+// A `Pipeline` is proved in two circuits: `PreprocessCircuit` proves that private raw readings
+// with hash R preprocess to features with hash H, and the model's `Plonky2Circuit` that features
+// with hash H predict P. Each can be proved on its own (e.g. on different machines).
+// `PipelineCircuit::compose` verifies both proofs inside a third circuit, connects the two
+// occurrences of H and publishes only P and R, so a verifier checks the single statement
+// "raw readings with hash R predict P" and never handles the intermediate commitment.
+//
+// Composed proofs have the public-input layout of model proofs (the prediction, then a hash), so
+// `proof_prediction` reads them and `proof_features_hash` returns the hash of the raw readings.
+
+use ::plonky2::field::types::PrimeField64;
+use ::plonky2::hash::poseidon::PoseidonHash;
+use ::plonky2::iop::target::Target;
+use ::plonky2::iop::witness::{PartialWitness, WitnessWrite};
+use ::plonky2::plonk::circuit_builder::CircuitBuilder;
+use ::plonky2::plonk::circuit_data::{CircuitConfig, CircuitData};
+use ::plonky2::plonk::proof::ProofWithPublicInputsTarget;
+
+use super::plonky2::{
+    fixed_to_field, proof_features_hash, proof_prediction, range_check_fixed, transform_target, Plonky2Circuit,
+    Plonky2Error, Plonky2Proof, C, D, F, VALUE_BITS,
+};
+use crate::poseidon::PoseidonDigest;
+use crate::preprocess::{Pipeline, Transform};
+
+/// Digest published at `offset` among a proof's public inputs
+fn digest_at(proof: &Plonky2Proof, offset: usize) -> Option<PoseidonDigest> {
+    let elements = proof.public_inputs.get(offset..offset + 4)?;
+    Some([0, 1, 2, 3].map(|i| elements[i].to_canonical_u64()))
+}
+
+/// Compiled circuit proving that private raw readings preprocess to committed features
+///
+/// The public inputs are the `hash_features` digest of the raw readings followed by the digest
+/// of the preprocessed features.
+pub struct PreprocessCircuit {
+    data: CircuitData<F, C, D>,
+    raw: Vec<Target>,
+    steps: Vec<Transform>,
+}
+
+impl PreprocessCircuit {
+    /// Build the circuit for preprocessing steps on `num_features` raw readings
+    pub fn new(steps: &[Transform], num_features: usize) -> PreprocessCircuit {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let raw = builder.add_virtual_targets(num_features);
+        for &value in &raw {
+            range_check_fixed(&mut builder, value);
+        }
+        let mut features = raw.clone();
+        for step in steps {
+            features = transform_target(&mut builder, step, &features);
+        }
+        let raw_hash = builder.hash_n_to_hash_no_pad::<PoseidonHash>(raw.clone());
+        builder.register_public_inputs(&raw_hash.elements);
+        let features_hash = builder.hash_n_to_hash_no_pad::<PoseidonHash>(features);
+        builder.register_public_inputs(&features_hash.elements);
+
+        PreprocessCircuit { data: builder.build::<C>(), raw, steps: steps.to_vec() }
+    }
+
+    /// Number of rows of the circuit (padded to a power of two)
+    pub fn num_rows(&self) -> usize {
+        self.data.common.degree()
+    }
+
+    /// Run the preprocessing steps natively, as the circuit does
+    pub fn transform(&self, raw: &[i64]) -> Vec<i64> {
+        let mut features = raw.to_vec();
+        for step in &self.steps {
+            step.apply(&mut features);
+        }
+        features
+    }
+
+    /// Prove the preprocessing of raw readings
+    ///
+    /// # Arguments
+    /// * `raw` - Private raw feature vector (scaled, |x| < 2^61, `MISSING_VALUE` for missing readings)
+    ///
+    /// # Returns
+    /// * `Result<Plonky2Proof, Plonky2Error>` - Proof whose public inputs are the raw and feature digests
+    pub fn prove(&self, raw: &[i64]) -> Result<Plonky2Proof, Plonky2Error> {
+        if raw.len() != self.raw.len() {
+            return Err(Plonky2Error::WrongLength { expected: self.raw.len(), got: raw.len() });
+        }
+        let out_of_range = raw.iter().enumerate().find(|(_, &value)| value.unsigned_abs() >= 1 << VALUE_BITS);
+        if let Some((index, &value)) = out_of_range {
+            return Err(Plonky2Error::OutOfRange { index, value, bits: VALUE_BITS });
+        }
+
+        let mut witness = PartialWitness::new();
+        for (&target, &value) in self.raw.iter().zip(raw) {
+            witness
+                .set_target(target, fixed_to_field(value))
+                .map_err(|e| Plonky2Error::Prover(e.to_string()))?;
+        }
+        self.data.prove(witness).map_err(|e| Plonky2Error::Prover(e.to_string()))
+    }
+
+    /// Verify a preprocessing proof
+    pub fn verify(&self, proof: &Plonky2Proof) -> Result<(), Plonky2Error> {
+        self.data.verify(proof.clone()).map_err(|e| Plonky2Error::Verifier(e.to_string()))
+    }
+}
+
+/// Hash of the raw readings a preprocessing proof was made for
+pub fn preprocess_raw_hash(proof: &Plonky2Proof) -> Option<PoseidonDigest> {
+    digest_at(proof, 0)
+}
+
+/// Hash of the preprocessed features a preprocessing proof commits to
+pub fn preprocess_features_hash(proof: &Plonky2Proof) -> Option<PoseidonDigest> {
+    digest_at(proof, 4)
+}
+
+/// Preprocessing and model circuits of a pipeline, and the circuit composing their proofs
+pub struct PipelineCircuit {
+    preprocess: PreprocessCircuit,
+    model: Plonky2Circuit,
+    data: CircuitData<F, C, D>,
+    preprocess_proof: ProofWithPublicInputsTarget<D>,
+    model_proof: ProofWithPublicInputsTarget<D>,
+}
+
+impl PipelineCircuit {
+    /// Build the three circuits for `pipeline`
+    pub fn new(pipeline: &Pipeline) -> PipelineCircuit {
+        let preprocess = PreprocessCircuit::new(pipeline.steps(), pipeline.model().num_features());
+        let model = Plonky2Circuit::new(pipeline.model());
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let mut verified = |inner: &CircuitData<F, C, D>| {
+            let verifier = builder.constant_verifier_data(&inner.verifier_only);
+            let proof = builder.add_virtual_proof_with_pis(&inner.common);
+            builder.verify_proof::<C>(&proof, &verifier, &inner.common);
+            proof
+        };
+        let preprocess_proof = verified(&preprocess.data);
+        let model_proof = verified(model.circuit_data());
+
+        // The features the model proof is about are the ones the preprocessing committed to
+        for i in 0..4 {
+            builder.connect(preprocess_proof.public_inputs[4 + i], model_proof.public_inputs[1 + i]);
+        }
+        builder.register_public_input(model_proof.public_inputs[0]);
+        builder.register_public_inputs(&preprocess_proof.public_inputs[..4]);
+
+        PipelineCircuit { preprocess, model, data: builder.build::<C>(), preprocess_proof, model_proof }
+    }
+
+    /// Circuit proving the preprocessing step
+    pub fn preprocess(&self) -> &PreprocessCircuit {
+        &self.preprocess
+    }
+
+    /// Circuit proving the model on preprocessed features
+    pub fn model(&self) -> &Plonky2Circuit {
+        &self.model
+    }
+
+    /// Compose a preprocessing proof and a model proof into one proof of raw readings → prediction
+    ///
+    /// # Arguments
+    /// * `preprocessing` - Proof made with `preprocess().prove`
+    /// * `model` - Proof made with `model().prove` on the preprocessed features
+    ///
+    /// # Returns
+    /// * `Result<Plonky2Proof, Plonky2Error>` - Proof whose public inputs are the prediction and the raw hash
+    pub fn compose(&self, preprocessing: &Plonky2Proof, model: &Plonky2Proof) -> Result<Plonky2Proof, Plonky2Error> {
+        if preprocess_features_hash(preprocessing).is_none()
+            || preprocess_features_hash(preprocessing) != proof_features_hash(model)
+        {
+            return Err(Plonky2Error::FeaturesHashMismatch);
+        }
+        let mut witness = PartialWitness::new();
+        for (target, proof) in [(&self.preprocess_proof, preprocessing), (&self.model_proof, model)] {
+            witness.set_proof_with_pis_target(target, proof).map_err(|e| Plonky2Error::Prover(e.to_string()))?;
+        }
+        self.data.prove(witness).map_err(|e| Plonky2Error::Prover(e.to_string()))
+    }
+
+    /// Prove both stages for raw readings and compose the proofs
+    pub fn prove(&self, raw: &[i64]) -> Result<Plonky2Proof, Plonky2Error> {
+        let preprocessing = self.preprocess.prove(raw)?;
+        let model = self.model.prove(&self.preprocess.transform(raw))?;
+        self.compose(&preprocessing, &model)
+    }
+
+    /// Verify a composed proof and check that it is for `prediction`
+    pub fn verify(&self, proof: &Plonky2Proof, prediction: i64) -> Result<(), Plonky2Error> {
+        let got = proof_prediction(proof);
+        if got != Some(prediction) {
+            return Err(Plonky2Error::PredictionMismatch { expected: prediction, got });
+        }
+        self.data.verify(proof.clone()).map_err(|e| Plonky2Error::Verifier(e.to_string()))
+    }
+
+    /// Verify a composed proof for `prediction` on raw readings whose `hash_features` digest is `raw_hash`
+    pub fn verify_with_hash(
+        &self,
+        proof: &Plonky2Proof,
+        raw_hash: &PoseidonDigest,
+        prediction: i64,
+    ) -> Result<(), Plonky2Error> {
+        if proof_features_hash(proof).as_ref() != Some(raw_hash) {
+            return Err(Plonky2Error::FeaturesHashMismatch);
+        }
+        self.verify(proof, prediction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hash_features, to_fixed_point, ConstantImputer, Ensemble, MinMaxScaler, Node, StandardScaler, Tree};
+    use crate::{MISSING_VALUE, PRECISION_MULTIPLIER};
+
+    fn pipeline() -> Pipeline {
+        let tree = Tree::new(Node::split(
+            0,
+            0,
+            Node::split(1, to_fixed_point(1.5), Node::Leaf(-20), Node::Leaf(35)),
+            Node::Leaf(to_fixed_point(2.5)),
+        ));
+        let model = Ensemble::new(vec![tree], PRECISION_MULTIPLIER).with_num_features(2);
+        Pipeline::new(model)
+            .with_step(ConstantImputer::from_f64(&[4.0, -1.0]))
+            .with_step(StandardScaler::from_f64(&[3.0, 0.5], &[2.0, 0.25]))
+            .with_step(MinMaxScaler::from_f64(&[-0.5], &[0.1]))
+    }
+
+    #[test]
+    fn test_composed_pipeline_proof() {
+        let pipeline = pipeline();
+        let circuit = PipelineCircuit::new(&pipeline);
+        let missing = [MISSING_VALUE, to_fixed_point(1.2)];
+        for raw in [[to_fixed_point(7.25), to_fixed_point(0.3)], missing, [0, MISSING_VALUE]] {
+            assert_eq!(circuit.preprocess().transform(&raw), pipeline.transform(&raw));
+            let prediction = pipeline.predict(&raw);
+            let proof = circuit.prove(&raw).unwrap();
+            circuit.verify_with_hash(&proof, &hash_features(&raw), prediction).unwrap();
+            assert_eq!(
+                circuit.verify(&proof, prediction + 1),
+                Err(Plonky2Error::PredictionMismatch { expected: prediction + 1, got: Some(prediction) })
+            );
+        }
+    }
+
+    #[test]
+    fn test_compose_checks_commitment() {
+        let pipeline = pipeline();
+        let circuit = PipelineCircuit::new(&pipeline);
+        let raw = [to_fixed_point(1.0), to_fixed_point(2.0)];
+        let preprocessing = circuit.preprocess().prove(&raw).unwrap();
+        circuit.preprocess().verify(&preprocessing).unwrap();
+        assert_eq!(preprocess_raw_hash(&preprocessing), Some(hash_features(&raw)));
+        assert_eq!(preprocess_features_hash(&preprocessing), Some(hash_features(&pipeline.transform(&raw))));
+
+        // A model proof about other features cannot be composed
+        let unrelated = circuit.model().prove(&raw).unwrap();
+        assert_eq!(circuit.compose(&preprocessing, &unrelated), Err(Plonky2Error::FeaturesHashMismatch));
+        let model = circuit.model().prove(&pipeline.transform(&raw)).unwrap();
+        let composed = circuit.compose(&preprocessing, &model).unwrap();
+        assert_eq!(proof_features_hash(&composed), Some(hash_features(&raw)));
+        circuit.verify(&composed, pipeline.predict(&raw)).unwrap();
+    }
+}
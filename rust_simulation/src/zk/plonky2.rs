@@ -0,0 +1,1362 @@
+// plonky2 circuit builder for the fixed-point tree traversal
+// Builds a Goldilocks circuit from the same `Model` trees the simulator runs. Every split is
+// a comparison on the custom `FixedComparisonGate` (`zk::gates`), built once per distinct test of
+// the ensemble (`Ensemble::share_comparisons`), and every node selects between its two subtrees.
+// The public inputs are the prediction followed by the Poseidon hash of the features, so a
+// proof states "features with hash H predict P" without revealing the features.
+//
+// Goldilocks has a 64-bit modulus, so values are range-checked to |x| < 2^61 (one bit less
+// than the R1CS gadgets): b - a then lies in (-2^62, 2^62), which the gate splits into 62 bits and
+// the sign bit `a > b` without wrapping the field.
+// `Plonky2Circuit::with_bounds` narrows the checks to the widths of the bit-width analysis.
+// Averaging ensembles divide the tree sum with a generated quotient and a remainder
+// range-checked to [0, num_trees).
+// `round_product_target` and `transform_target` mirror the preprocessing scalers. Their products
+// exceed the 64-bit field, so the rounding identity is checked over 16-bit limbs with carries.
+// A `ModelProof` bundles a proof with the `ModelInfo` of the model it was made with; verifiers
+// reject bundles whose info differs from their circuit's model. Verification itself lives in
+// `zk::verifier` (re-exported here), so it can be built without this module.
+//
+// `Plonky2Circuit::prove_batch` folds many predictions into one proof by recursion: the leaf
+// proofs are paired up by aggregation circuits that verify both children and hash their
+// statements, level by level, until one proof remains. Its public input is the root of that
+// Poseidon tree over the (prediction, features hash) statements, so a `BatchProof` carries the
+// statements in the clear and the verifier checks one proof against their recomputed root.
+// Batches are padded to a power of two by repeating the last leaf proof, which costs no proving.
+// `prove_batch_checkpointed` writes each leaf and aggregated proof to a `BatchCheckpoint` as soon
+// as it is made, so a long batch that is interrupted resumes from the proofs already on disk. A
+// job's files live in a directory named by the SHA-256 of the verifier key and the inputs; each
+// file is the SHA-256 of the proof followed by the proof, written under a temporary name and
+// renamed. Proofs read back are verified before use, and any that fail are proved again.
+
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use ::plonky2::field::types::{Field, PrimeField64};
+use ::plonky2::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGeneratorRef};
+use ::plonky2::iop::target::{BoolTarget, Target};
+use ::plonky2::iop::witness::{PartialWitness, PartitionWitness, Witness, WitnessWrite};
+use ::plonky2::hash::poseidon::PoseidonHash;
+use ::plonky2::plonk::circuit_builder::CircuitBuilder;
+use ::plonky2::plonk::circuit_data::{CircuitConfig, CircuitData, CommonCircuitData};
+use ::plonky2::plonk::proof::ProofWithPublicInputsTarget;
+use ::plonky2::util::serialization::{
+    Buffer, DefaultGeneratorSerializer, IoError, IoResult, Read, WitnessGeneratorSerializer,
+    Write,
+};
+
+use super::gates::{FixedComparisonGate, FixedComparisonGenerator, GateSerializer};
+use crate::backend::{lower_model, CircuitBackend};
+use crate::bitwidth::{magnitude_bits, ValueBounds};
+use crate::commitment::ModelCommitment;
+use crate::ensemble::BitSet;
+use crate::info::ModelInfo;
+use crate::keys::{KeyId, KeyKind, KeySource, KeyStore};
+use crate::poseidon::{fixed_to_goldilocks, poseidon_hash, PoseidonDigest};
+use crate::preprocess::{round_product, Transform};
+use crate::sha256::{sha256, to_hex};
+use crate::{Model, MISSING_VALUE};
+
+pub use super::verifier::{
+    field_to_fixed, fixed_to_field, proof_features_hash, proof_prediction, ModelProof, Plonky2Error, Plonky2Proof,
+    Plonky2Verifier, C, D, F, VALUE_BITS,
+};
+
+/// Enforce |value| < 2^61 on a fixed-point target
+pub fn range_check_fixed(builder: &mut CircuitBuilder<F, D>, value: Target) {
+    range_check_bits(builder, value, VALUE_BITS);
+}
+
+/// Enforce |value| < 2^bits on a fixed-point target (`bits <= VALUE_BITS`)
+pub fn range_check_bits(builder: &mut CircuitBuilder<F, D>, value: Target, bits: usize) {
+    assert!(bits <= VALUE_BITS, "range of {} bits exceeds the circuit range", bits);
+    let offset = builder.constant(F::from_canonical_u64(1 << bits));
+    let shifted = builder.add(value, offset);
+    builder.range_check(shifted, bits + 1);
+}
+
+/// Circuit version of `fixed_le`: true iff a <= b
+///
+/// Both inputs must already be range-checked to |x| < 2^61.
+pub fn fixed_le_target(builder: &mut CircuitBuilder<F, D>, a: Target, b: Target) -> BoolTarget {
+    fixed_le_target_bits(builder, a, b, VALUE_BITS)
+}
+
+/// `fixed_le_target` for inputs known to satisfy |x| < 2^bits
+pub fn fixed_le_target_bits(builder: &mut CircuitBuilder<F, D>, a: Target, b: Target, bits: usize) -> BoolTarget {
+    let gate = FixedComparisonGate::new_from_config(&builder.config, bits);
+    let (row, op) = builder.find_slot(gate, &[], &[]);
+    builder.connect(a, Target::wire(row, gate.wire_a(op)));
+    builder.connect(b, Target::wire(row, gate.wire_b(op)));
+    let gt = BoolTarget::new_unsafe(Target::wire(row, gate.wire_gt(op)));
+    builder.not(gt)
+}
+
+/// Circuit version of `fixed_add`: the sum must stay in range, so it never saturates
+pub fn fixed_add_target(builder: &mut CircuitBuilder<F, D>, a: Target, b: Target) -> Target {
+    fixed_add_target_bits(builder, a, b, VALUE_BITS)
+}
+
+/// `fixed_add_target` with the sum range-checked to |sum| < 2^bits
+pub fn fixed_add_target_bits(builder: &mut CircuitBuilder<F, D>, a: Target, b: Target, bits: usize) -> Target {
+    let sum = builder.add(a, b);
+    range_check_bits(builder, sum, bits);
+    sum
+}
+
+/// Generates the floored quotient of a signed fixed-point target by a constant
+#[derive(Debug, Default)]
+struct FloorDivGenerator {
+    dividend: Target,
+    divisor: usize,
+    quotient: Target,
+}
+
+impl SimpleGenerator<F, D> for FloorDivGenerator {
+    fn id(&self) -> String {
+        "FloorDivGenerator".to_string()
+    }
+
+    fn dependencies(&self) -> Vec<Target> {
+        vec![self.dividend]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) -> anyhow::Result<()> {
+        let dividend = field_to_fixed(witness.get_target(self.dividend))
+            .ok_or_else(|| anyhow::anyhow!("dividend exceeds the circuit range"))?;
+        out_buffer.set_target(self.quotient, fixed_to_field(dividend.div_euclid(self.divisor as i64)))
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_target(self.dividend)?;
+        dst.write_usize(self.divisor)?;
+        dst.write_target(self.quotient)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        Ok(FloorDivGenerator { dividend: src.read_target()?, divisor: src.read_usize()?, quotient: src.read_target()? })
+    }
+}
+
+/// Circuit version of the averaging combiner: `base_score + floor(sum / num_trees)`
+///
+/// `sum` must be range-checked to |sum| < 2^bits. The quotient is generated and range-checked to
+/// the same width, and the remainder `sum - q * num_trees` to [0, num_trees), so the field
+/// cannot wrap and q is the floored quotient.
+pub fn average_target(
+    builder: &mut CircuitBuilder<F, D>,
+    sum: Target,
+    num_trees: usize,
+    base_score: i64,
+    bits: usize,
+) -> Target {
+    let base_score = builder.constant(fixed_to_field(base_score));
+    if num_trees <= 1 {
+        return fixed_add_target(builder, base_score, sum);
+    }
+
+    let quotient = builder.add_virtual_target();
+    builder.add_simple_generator(FloorDivGenerator { dividend: sum, divisor: num_trees, quotient });
+    range_check_bits(builder, quotient, bits);
+
+    let product = builder.mul_const(F::from_canonical_usize(num_trees), quotient);
+    let remainder = builder.sub(sum, product);
+    let remainder_bits = magnitude_bits(num_trees as i128 - 1);
+    builder.range_check(remainder, remainder_bits);
+    let largest = builder.constant(F::from_canonical_usize(num_trees - 1));
+    let slack = builder.sub(largest, remainder);
+    builder.range_check(slack, remainder_bits);
+
+    fixed_add_target(builder, base_score, quotient)
+}
+
+/// Generates `round_product(value, factor)` for a signed target |value| < 2^62
+#[derive(Debug, Default)]
+struct RoundProductGenerator {
+    value: Target,
+    factor: i64,
+    quotient: Target,
+}
+
+impl SimpleGenerator<F, D> for RoundProductGenerator {
+    fn id(&self) -> String {
+        "RoundProductGenerator".to_string()
+    }
+
+    fn dependencies(&self) -> Vec<Target> {
+        vec![self.value]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) -> anyhow::Result<()> {
+        let value = witness.get_target(self.value);
+        let value = match value.to_canonical_u64() {
+            positive if positive < 1 << 62 => positive as i128,
+            _ if (-value).to_canonical_u64() <= 1 << 62 => -((-value).to_canonical_u64() as i128),
+            _ => anyhow::bail!("product operand exceeds the circuit range"),
+        };
+        out_buffer.set_target(self.quotient, fixed_to_field(round_product(value, self.factor)))
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_target(self.value)?;
+        dst.write_usize(self.factor as usize)?;
+        dst.write_target(self.quotient)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        Ok(RoundProductGenerator {
+            value: src.read_target()?,
+            factor: src.read_usize()? as i64,
+            quotient: src.read_target()?,
+        })
+    }
+}
+
+/// Bits of the 16-bit limbs of the products in `round_product_target`
+const LIMB_BITS: usize = 16;
+/// Limb columns of the 126-bit identity checked by `round_product_target`
+const PRODUCT_COLUMNS: usize = 8;
+
+/// Little-endian 16-bit limbs of the first `num_bits` bits of a target, which must lie in [0, 2^num_bits)
+fn limb_targets(builder: &mut CircuitBuilder<F, D>, value: Target, num_bits: usize) -> Vec<Target> {
+    let bits = builder.split_le(value, num_bits);
+    bits.chunks(LIMB_BITS).map(|limb| builder.le_sum(limb.iter())).collect()
+}
+
+/// Little-endian 16-bit limbs of a constant
+fn constant_limbs(value: u128) -> impl Iterator<Item = (usize, u64)> {
+    (0..PRODUCT_COLUMNS).map(move |k| (k, ((value >> (LIMB_BITS * k)) & 0xffff) as u64))
+}
+
+/// Circuit version of `round_product`: `floor((a * b + S/2) / S)` at S = 10^10 for a target
+/// |a| < 2^62 and a constant `b`, range-checked to |q| < 2^61
+///
+/// a * b can reach 2^125, far beyond the Goldilocks modulus, so the identity
+/// a * b + S/2 = q * S + r is checked over the integers instead. With A = a + 2^62 and
+/// Q = q + 2^61 decomposed into 16-bit limbs and r into [0, S), both sides are sums of limb
+/// products below 2^36 per column; the columns are compared with carries range-checked to
+/// 2^22, which cannot wrap the field.
+pub fn round_product_target(builder: &mut CircuitBuilder<F, D>, a: Target, b: i64) -> Target {
+    let scale = crate::PRECISION_MULTIPLIER;
+    let quotient = builder.add_virtual_target();
+    builder.add_simple_generator(RoundProductGenerator { value: a, factor: b, quotient });
+
+    // r = a * b + S/2 - q * S holds in the field; it is the true remainder once r is in [0, S)
+    let product = builder.mul_const(fixed_to_field(b), a);
+    let shifted = builder.add_const(product, fixed_to_field(scale / 2));
+    let remainder = builder.mul_const_add(-fixed_to_field(scale), quotient, shifted);
+    let remainder_bits = magnitude_bits(scale as i128 - 1);
+    let largest = builder.constant(fixed_to_field(scale - 1));
+    let slack = builder.sub(largest, remainder);
+    builder.range_check(slack, remainder_bits);
+
+    let offset_a = builder.add_const(a, F::from_canonical_u64(1 << 62));
+    let a_limbs = limb_targets(builder, offset_a, 63);
+    let offset_q = builder.add_const(quotient, F::from_canonical_u64(1 << VALUE_BITS));
+    let q_limbs = limb_targets(builder, offset_q, VALUE_BITS + 1);
+    let r_limbs = limb_targets(builder, remainder, remainder_bits);
+
+    // b >= 0:  A*|b| + S/2 + 2^61*S = Q*S + r + 2^62*|b|
+    // b < 0:   2^62*|b| + S/2 + 2^61*S = Q*S + r + A*|b|
+    // Each column holds left minus right
+    let magnitude = b.unsigned_abs() as u128;
+    let (a_sign, offset_sign) = if b < 0 { (-F::ONE, F::ONE) } else { (F::ONE, -F::ONE) };
+    let mut columns = vec![builder.zero(); PRODUCT_COLUMNS];
+    let constant = (scale as u128) / 2 + (scale as u128) * (1 << VALUE_BITS);
+    for ((k, limb), (_, offset)) in constant_limbs(constant).zip(constant_limbs(magnitude << 62)) {
+        let limb = F::from_canonical_u64(limb) + offset_sign * F::from_canonical_u64(offset);
+        columns[k] = builder.add_const(columns[k], limb);
+    }
+    for (i, &limb) in a_limbs.iter().enumerate() {
+        for (j, b_limb) in constant_limbs(magnitude).take(4) {
+            columns[i + j] = builder.mul_const_add(a_sign * F::from_canonical_u64(b_limb), limb, columns[i + j]);
+        }
+    }
+    for (i, &limb) in q_limbs.iter().enumerate() {
+        for (j, s_limb) in constant_limbs(scale as u128).take(3) {
+            columns[i + j] = builder.mul_const_add(-F::from_canonical_u64(s_limb), limb, columns[i + j]);
+        }
+    }
+    for (k, &limb) in r_limbs.iter().enumerate() {
+        columns[k] = builder.sub(columns[k], limb);
+    }
+
+    let inverse = F::from_canonical_u64(1 << LIMB_BITS).inverse();
+    let mut carry = builder.zero();
+    for column in columns {
+        let total = builder.add(column, carry);
+        carry = builder.mul_const(inverse, total);
+        range_check_bits(builder, carry, 22);
+    }
+    builder.assert_zero(carry);
+    quotient
+}
+
+/// Circuit version of `Transform::apply` on range-checked features
+///
+/// Scalers run on a neutral value for missing features (the mean, or zero) and select
+/// `MISSING_VALUE` back afterwards, like `transform_gadget`.
+pub fn transform_target(builder: &mut CircuitBuilder<F, D>, step: &Transform, features: &[Target]) -> Vec<Target> {
+    assert!(features.len() >= step.num_features(),
+            "Expected at least {} features, got {}", step.num_features(), features.len());
+
+    let missing_value = builder.constant(fixed_to_field(MISSING_VALUE));
+    let mut transformed = features.to_vec();
+    for (i, x) in transformed.iter_mut().take(step.num_features()).enumerate() {
+        let missing = builder.is_equal(*x, missing_value);
+        *x = match step {
+            Transform::Impute(imputer) => {
+                let fill = builder.constant(fixed_to_field(imputer.fill()[i]));
+                builder.select(missing, fill, *x)
+            }
+            Transform::Standard(scaler) => {
+                assert!(scaler.mean()[i].unsigned_abs() < 1 << VALUE_BITS, "scaler mean exceeds the circuit range");
+                let mean = builder.constant(fixed_to_field(scaler.mean()[i]));
+                let value = builder.select(missing, mean, *x);
+                let centered = builder.sub(value, mean);
+                let scaled = round_product_target(builder, centered, scaler.inv_scale()[i]);
+                builder.select(missing, missing_value, scaled)
+            }
+            Transform::MinMax(scaler) => {
+                let zero = builder.zero();
+                let value = builder.select(missing, zero, *x);
+                let product = round_product_target(builder, value, scaler.scale()[i]);
+                let min = builder.constant(fixed_to_field(scaler.min()[i]));
+                let scaled = fixed_add_target(builder, product, min);
+                builder.select(missing, missing_value, scaled)
+            }
+        };
+    }
+    transformed
+}
+
+/// Circuit version of `BitSet::contains_value` on a range-checked value
+///
+/// One equality test per category. The members are distinct constants, so at most one test
+/// holds and their sum is already a boolean.
+pub fn category_target(builder: &mut CircuitBuilder<F, D>, value: Target, categories: &BitSet, unit: i64) -> BoolTarget {
+    let mut sum = builder.zero();
+    for member in categories.values(unit) {
+        let member = builder.constant(fixed_to_field(member));
+        let matches = builder.is_equal(value, member);
+        sum = builder.add(sum, matches.target);
+    }
+    BoolTarget::new_unsafe(sum)
+}
+
+/// Circuit for a full model evaluation on `features`
+pub fn model_target(builder: &mut CircuitBuilder<F, D>, model: &Model, features: &[Target]) -> Target {
+    model_target_bits(builder, model, features, VALUE_BITS, VALUE_BITS)
+}
+
+/// Circuit for a model evaluation on features range-checked to the bounds' `comparison_bits`,
+/// with comparisons and accumulator checks sized from the bit-width analysis
+pub fn model_target_with_bounds(
+    builder: &mut CircuitBuilder<F, D>,
+    model: &Model,
+    features: &[Target],
+    bounds: &ValueBounds,
+) -> Target {
+    model_target_bits(builder, model, features, bounds.comparison_bits(), bounds.accumulator_bits())
+}
+
+fn model_target_bits(
+    builder: &mut CircuitBuilder<F, D>,
+    model: &Model,
+    features: &[Target],
+    comparison_bits: usize,
+    accumulator_bits: usize,
+) -> Target {
+    infallible(lower_model(builder, model, features, comparison_bits, accumulator_bits))
+}
+
+fn infallible<T>(result: Result<T, Infallible>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(never) => match never {},
+    }
+}
+
+/// The builder lowers the traversal itself. Witnesses are assigned at proving time, so
+/// `private_input` ignores the value; `commit_public` registers the prediction and the Poseidon
+/// hash of the features.
+impl CircuitBackend for CircuitBuilder<F, D> {
+    type Value = Target;
+    type Bit = BoolTarget;
+    type Error = Infallible;
+    type Output = CircuitData<F, C, D>;
+
+    fn private_input(&mut self, _value: Option<i64>, bits: usize) -> Result<Target, Infallible> {
+        let input = self.add_virtual_target();
+        range_check_bits(self, input, bits);
+        Ok(input)
+    }
+
+    fn constant(&mut self, value: i64) -> Result<Target, Infallible> {
+        Ok(CircuitBuilder::constant(self, fixed_to_field(value)))
+    }
+
+    fn lower_le(&mut self, a: &Target, b: &Target, bits: usize) -> Result<BoolTarget, Infallible> {
+        Ok(fixed_le_target_bits(self, *a, *b, bits))
+    }
+
+    fn lower_categories(&mut self, value: &Target, categories: &BitSet, unit: i64) -> Result<BoolTarget, Infallible> {
+        Ok(category_target(self, *value, categories, unit))
+    }
+
+    fn lower_select(
+        &mut self,
+        bit: &BoolTarget,
+        when_true: &Target,
+        when_false: &Target,
+    ) -> Result<Target, Infallible> {
+        Ok(self.select(*bit, *when_true, *when_false))
+    }
+
+    fn lower_add(&mut self, a: &Target, b: &Target, bits: usize) -> Result<Target, Infallible> {
+        Ok(fixed_add_target_bits(self, *a, *b, bits))
+    }
+
+    fn lower_average(
+        &mut self,
+        sum: &Target,
+        num_trees: usize,
+        base_score: i64,
+        bits: usize,
+    ) -> Result<Target, Infallible> {
+        Ok(average_target(self, *sum, num_trees, base_score, bits))
+    }
+
+    fn commit_public(&mut self, prediction: &Target, features: &[Target]) -> Result<(), Infallible> {
+        self.register_public_input(*prediction);
+        let features_hash = self.hash_n_to_hash_no_pad::<PoseidonHash>(features.to_vec());
+        self.register_public_inputs(&features_hash.elements);
+        Ok(())
+    }
+
+    fn finalize(self) -> Result<CircuitData<F, C, D>, Infallible> {
+        Ok(self.build::<C>())
+    }
+}
+
+/// Writes this module's generators by tag and defers the others to plonky2's default serializer
+struct GeneratorSerializer;
+
+const DEFAULT_GENERATOR: u32 = 0;
+const FLOOR_DIV_GENERATOR: u32 = 1;
+const ROUND_PRODUCT_GENERATOR: u32 = 2;
+const COMPARISON_GENERATOR: u32 = 3;
+
+impl WitnessGeneratorSerializer<F, D> for GeneratorSerializer {
+    fn read_generator(
+        &self,
+        buf: &mut Buffer,
+        common: &CommonCircuitData<F, D>,
+    ) -> IoResult<WitnessGeneratorRef<F, D>> {
+        match buf.read_u32()? {
+            DEFAULT_GENERATOR => DefaultGeneratorSerializer::<C, D>::default().read_generator(buf, common),
+            FLOOR_DIV_GENERATOR => Ok(WitnessGeneratorRef::new(FloorDivGenerator::deserialize(buf, common)?.adapter())),
+            ROUND_PRODUCT_GENERATOR => {
+                Ok(WitnessGeneratorRef::new(RoundProductGenerator::deserialize(buf, common)?.adapter()))
+            }
+            COMPARISON_GENERATOR => {
+                Ok(WitnessGeneratorRef::new(FixedComparisonGenerator::deserialize(buf, common)?.adapter()))
+            }
+            _ => Err(IoError),
+        }
+    }
+
+    fn write_generator(
+        &self,
+        buf: &mut Vec<u8>,
+        generator: &WitnessGeneratorRef<F, D>,
+        common: &CommonCircuitData<F, D>,
+    ) -> IoResult<()> {
+        let id = generator.0.id();
+        let tag = if id == FloorDivGenerator::default().id() {
+            FLOOR_DIV_GENERATOR
+        } else if id == RoundProductGenerator::default().id() {
+            ROUND_PRODUCT_GENERATOR
+        } else if id == FixedComparisonGenerator::default().id() {
+            COMPARISON_GENERATOR
+        } else {
+            buf.write_u32(DEFAULT_GENERATOR)?;
+            return DefaultGeneratorSerializer::<C, D>::default().write_generator(buf, generator, common);
+        };
+        buf.write_u32(tag)?;
+        generator.0.serialize(buf, common)
+    }
+}
+
+/// Read the feature range, feature targets and verifier key length of a proving key
+fn read_key_header(buffer: &mut Buffer) -> IoResult<(usize, Vec<Target>, usize)> {
+    Ok((buffer.read_usize()?, buffer.read_target_vec()?, buffer.read_usize()?))
+}
+
+/// Name of this backend's keys in a `KeyStore`; circuits may change with the crate version
+const KEY_BACKEND: &str = concat!("plonky2-", env!("CARGO_PKG_VERSION"));
+
+/// Compiled circuit proving that private features produce the public prediction under a model
+pub struct Plonky2Circuit {
+    data: CircuitData<F, C, D>,
+    features: Vec<Target>,
+    /// Input range |x| < 2^feature_bits enforced by the circuit
+    feature_bits: usize,
+    verifier: Plonky2Verifier,
+    /// Aggregation circuits of `prove_batch`, level 1 first, built on first use
+    levels: Mutex<Vec<Arc<AggregationLevel>>>,
+}
+
+/// Circuit verifying two proofs of the level below and hashing their statements into one digest
+struct AggregationLevel {
+    data: CircuitData<F, C, D>,
+    children: [ProofWithPublicInputsTarget<D>; 2],
+}
+
+impl AggregationLevel {
+    /// Aggregate two proofs of `inner`; leaf proofs are hashed from their five public inputs,
+    /// aggregated ones already publish their digest
+    fn build(inner: &CircuitData<F, C, D>, leaves: bool) -> AggregationLevel {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let verifier = builder.constant_verifier_data(&inner.verifier_only);
+        let children = [(); 2].map(|_| {
+            let child = builder.add_virtual_proof_with_pis(&inner.common);
+            builder.verify_proof::<C>(&child, &verifier, &inner.common);
+            child
+        });
+        let mut digests = Vec::with_capacity(8);
+        for child in &children {
+            if leaves {
+                let digest = builder.hash_n_to_hash_no_pad::<PoseidonHash>(child.public_inputs.clone());
+                digests.extend(digest.elements);
+            } else {
+                digests.extend(&child.public_inputs);
+            }
+        }
+        let digest = builder.hash_n_to_hash_no_pad::<PoseidonHash>(digests);
+        builder.register_public_inputs(&digest.elements);
+        AggregationLevel { data: builder.build::<C>(), children }
+    }
+
+    fn prove(&self, left: &Plonky2Proof, right: &Plonky2Proof) -> Result<Plonky2Proof, Plonky2Error> {
+        let mut witness = PartialWitness::new();
+        for (target, proof) in self.children.iter().zip([left, right]) {
+            witness.set_proof_with_pis_target(target, proof).map_err(|e| Plonky2Error::Prover(e.to_string()))?;
+        }
+        self.data.prove(witness).map_err(|e| Plonky2Error::Prover(e.to_string()))
+    }
+}
+
+/// One proof of a batch of predictions made with the same model
+#[derive(Debug, Clone)]
+pub struct BatchProof {
+    pub info: ModelInfo,
+    /// Scaled prediction of each input, in order
+    pub predictions: Vec<i64>,
+    /// `hash_features` digest of each input, in order
+    pub features_hashes: Vec<PoseidonDigest>,
+    /// Aggregated proof; its public inputs are the digest of the statements
+    pub proof: Plonky2Proof,
+}
+
+/// Aggregation levels needed for `len` predictions (at least one)
+fn batch_depth(len: usize) -> usize {
+    (len.next_power_of_two().trailing_zeros() as usize).max(1)
+}
+
+/// Root of the Poseidon tree over (prediction, features hash) statements, padded to `2^depth`
+/// leaves with the last statement, exactly as the aggregation circuits hash them
+fn batch_digest(predictions: &[i64], features_hashes: &[PoseidonDigest], depth: usize) -> PoseidonDigest {
+    let mut digests: Vec<PoseidonDigest> = predictions
+        .iter()
+        .zip(features_hashes)
+        .map(|(&prediction, hash)| {
+            let mut statement = vec![fixed_to_goldilocks(prediction)];
+            statement.extend(hash);
+            poseidon_hash(&statement)
+        })
+        .collect();
+    let last = *digests.last().expect("non-empty batch");
+    digests.resize(1 << depth, last);
+    while digests.len() > 1 {
+        digests = digests.chunks(2).map(|pair| poseidon_hash(&[pair[0], pair[1]].concat())).collect();
+    }
+    digests[0]
+}
+
+/// Directory where `prove_batch_checkpointed` keeps the proofs it has finished
+#[derive(Debug, Clone)]
+pub struct BatchCheckpoint {
+    dir: PathBuf,
+}
+
+impl BatchCheckpoint {
+    /// Checkpoint keeping its jobs in `dir`, created on the first batch
+    pub fn new(dir: impl Into<PathBuf>) -> BatchCheckpoint {
+        BatchCheckpoint { dir: dir.into() }
+    }
+
+    /// Directory holding the jobs
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Delete the proofs of every job (nothing happens if none was started)
+    pub fn clear(&self) -> Result<(), Plonky2Error> {
+        match std::fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(checkpoint_error(e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn checkpoint_error(e: std::io::Error) -> Plonky2Error {
+    Plonky2Error::Checkpoint(e.to_string())
+}
+
+/// Proof files of one batch job, named "<level>-<index>.proof" with the leaves at level 0
+struct CheckpointJob {
+    dir: PathBuf,
+}
+
+impl CheckpointJob {
+    fn path(&self, level: usize, index: usize) -> PathBuf {
+        self.dir.join(format!("{}-{}.proof", level, index))
+    }
+
+    /// Checkpointed proof of a circuit, `None` if it is missing, torn or does not verify
+    fn load(
+        &self,
+        level: usize,
+        index: usize,
+        data: &CircuitData<F, C, D>,
+    ) -> Result<Option<Plonky2Proof>, Plonky2Error> {
+        let bytes = match std::fs::read(self.path(level, index)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(checkpoint_error(e)),
+        };
+        let Some((hash, proof)) = bytes.split_at_checked(32) else { return Ok(None) };
+        if sha256(proof)[..] != *hash {
+            return Ok(None);
+        }
+        let Ok(proof) = Plonky2Proof::from_bytes(proof.to_vec(), &data.common) else { return Ok(None) };
+        Ok(data.verify(proof.clone()).ok().map(|()| proof))
+    }
+
+    fn store(&self, level: usize, index: usize, proof: &Plonky2Proof) -> Result<(), Plonky2Error> {
+        let bytes = proof.to_bytes();
+        let mut file = sha256(&bytes).to_vec();
+        file.extend(bytes);
+        let path = self.path(level, index);
+        let partial = path.with_extension(format!("proof.{}.tmp", std::process::id()));
+        std::fs::write(&partial, file).map_err(checkpoint_error)?;
+        std::fs::rename(&partial, &path).map_err(|e| {
+            let _ = std::fs::remove_file(&partial);
+            checkpoint_error(e)
+        })
+    }
+}
+
+/// Proof from the checkpoint if there is one, else made by `prove` and checkpointed
+fn checkpointed(
+    job: Option<&CheckpointJob>,
+    (level, index): (usize, usize),
+    data: &CircuitData<F, C, D>,
+    resumed: &mut usize,
+    prove: impl FnOnce() -> Result<Plonky2Proof, Plonky2Error>,
+) -> Result<Plonky2Proof, Plonky2Error> {
+    let Some(job) = job else { return prove() };
+    if let Some(proof) = job.load(level, index, data)? {
+        *resumed += 1;
+        return Ok(proof);
+    }
+    let proof = prove()?;
+    job.store(level, index, &proof)?;
+    Ok(proof)
+}
+
+impl Plonky2Circuit {
+    /// Build the circuit for `model` with the standard recursion config
+    pub fn new(model: &Model) -> Plonky2Circuit {
+        Plonky2Circuit::build(model, None)
+    }
+
+    /// Build the circuit with range checks sized from a bit-width analysis of `model`
+    ///
+    /// Features are range-checked to the bounds' `comparison_bits`; `prove` rejects larger inputs.
+    pub fn with_bounds(model: &Model, bounds: &ValueBounds) -> Plonky2Circuit {
+        Plonky2Circuit::build(model, Some(bounds))
+    }
+
+    fn build(model: &Model, bounds: Option<&ValueBounds>) -> Plonky2Circuit {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+
+        let (feature_bits, accumulator_bits) =
+            bounds.map_or((VALUE_BITS, VALUE_BITS), |bounds| (bounds.comparison_bits(), bounds.accumulator_bits()));
+        let features: Vec<Target> = (0..model.num_features())
+            .map(|_| infallible(builder.private_input(None, feature_bits)))
+            .collect();
+        let prediction = infallible(lower_model(&mut builder, model, &features, feature_bits, accumulator_bits));
+        infallible(builder.commit_public(&prediction, &features));
+
+        let data = infallible(builder.finalize());
+        let commitment = ModelCommitment::new(model).root();
+        let verifier = Plonky2Verifier::new(data.verifier_data(), model.model_info(), commitment);
+        Plonky2Circuit { data, features, feature_bits, verifier, levels: Mutex::new(Vec::new()) }
+    }
+
+    /// Encode the circuit as a proving key
+    ///
+    /// Layout: feature_bits | feature targets | verifier key length | verifier key | circuit data,
+    /// in plonky2's serialization (lengths as little-endian u64).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let verifier = self.verifier.to_bytes();
+        out.write_usize(self.feature_bits).expect("writing to a Vec");
+        out.write_target_vec(&self.features).expect("writing to a Vec");
+        out.write_usize(verifier.len()).expect("writing to a Vec");
+        out.extend(verifier);
+        let data = self.data.to_bytes(&GateSerializer, &GeneratorSerializer);
+        out.extend(data.expect("circuit generators serialize"));
+        out
+    }
+
+    /// Decode a proving key written by `to_bytes`
+    ///
+    /// # Arguments
+    /// * `bytes` - Encoded proving key
+    ///
+    /// # Returns
+    /// * `Result<Plonky2Circuit, Plonky2Error>` - The circuit, or `InvalidKey` if the bytes are malformed
+    pub fn from_bytes(bytes: &[u8]) -> Result<Plonky2Circuit, Plonky2Error> {
+        let invalid = |message: &str| Plonky2Error::InvalidKey(format!("proving key: {}", message));
+        let mut buffer = Buffer::new(bytes);
+        let (feature_bits, features, verifier_len) =
+            read_key_header(&mut buffer).map_err(|_| invalid("truncated header"))?;
+        let rest = buffer.unread_bytes();
+        let verifier = rest.get(..verifier_len).ok_or_else(|| invalid("truncated verifier key"))?;
+        let verifier = Plonky2Verifier::from_bytes(verifier)?;
+        let data = CircuitData::from_bytes(&rest[verifier_len..], &GateSerializer, &GeneratorSerializer)
+            .map_err(|_| invalid("malformed circuit data"))?;
+        if data.common != *verifier.common() {
+            return Err(invalid("circuit data does not match its verifier key"));
+        }
+        Ok(Plonky2Circuit { data, features, feature_bits, verifier, levels: Mutex::new(Vec::new()) })
+    }
+
+    /// Circuit for `model` from `store`, building and storing it on a miss
+    ///
+    /// Keys are looked up by the model's `content_hash`, so a renamed or retrained model gets its own.
+    ///
+    /// # Arguments
+    /// * `model` - Model to prove with
+    /// * `store` - Key cache
+    ///
+    /// # Returns
+    /// * `Result<(Plonky2Circuit, KeySource), Plonky2Error>` - The circuit and whether it was cached
+    pub fn cached(model: &Model, store: &KeyStore) -> Result<(Plonky2Circuit, KeySource), Plonky2Error> {
+        let id = KeyId::new(model.content_hash(), KEY_BACKEND, KeyKind::Proving);
+        let cached = store.load(&id).ok().flatten().and_then(|bytes| Plonky2Circuit::from_bytes(&bytes).ok());
+        if let Some(circuit) = cached {
+            return Ok((circuit, KeySource::Cached));
+        }
+        let circuit = Plonky2Circuit::new(model);
+        store.store(&id, &circuit.to_bytes())?;
+        Ok((circuit, KeySource::Generated))
+    }
+
+    /// Verifier for `model` from `store`, building (or loading) the circuit on a miss
+    pub fn cached_verifier(model: &Model, store: &KeyStore) -> Result<(Plonky2Verifier, KeySource), Plonky2Error> {
+        let id = KeyId::new(model.content_hash(), KEY_BACKEND, KeyKind::Verifying);
+        let cached = store.load(&id).ok().flatten().and_then(|bytes| Plonky2Verifier::from_bytes(&bytes).ok());
+        if let Some(verifier) = cached {
+            return Ok((verifier, KeySource::Cached));
+        }
+        let verifier = Plonky2Circuit::cached(model, store)?.0.verifier;
+        store.store(&id, &verifier.to_bytes())?;
+        Ok((verifier, KeySource::Generated))
+    }
+
+    /// Compiled circuit, for verifying its proofs inside other circuits
+    pub(crate) fn circuit_data(&self) -> &CircuitData<F, C, D> {
+        &self.data
+    }
+
+    /// Metadata of the model the circuit was built for
+    pub fn info(&self) -> &ModelInfo {
+        self.verifier.info()
+    }
+
+    /// Verifier of this circuit's proofs, to export with `Plonky2Verifier::to_bytes`
+    pub fn verifier(&self) -> &Plonky2Verifier {
+        &self.verifier
+    }
+
+    /// Number of rows of the circuit (padded to a power of two)
+    pub fn num_rows(&self) -> usize {
+        self.data.common.degree()
+    }
+
+    /// Prove the prediction for a feature vector
+    ///
+    /// # Arguments
+    /// * `features` - Private input feature vector (scaled, |x| < 2^61 or the bounds' range)
+    ///
+    /// # Returns
+    /// * `Result<Plonky2Proof, Plonky2Error>` - Proof whose public input is the prediction
+    pub fn prove(&self, features: &[i64]) -> Result<Plonky2Proof, Plonky2Error> {
+        if features.len() != self.features.len() {
+            return Err(Plonky2Error::WrongLength { expected: self.features.len(), got: features.len() });
+        }
+        if let Some((index, &value)) = features
+            .iter()
+            .enumerate()
+            .find(|(_, &value)| value.unsigned_abs() >= 1 << self.feature_bits)
+        {
+            return Err(Plonky2Error::OutOfRange { index, value, bits: self.feature_bits });
+        }
+
+        let mut witness = PartialWitness::new();
+        for (&target, &value) in self.features.iter().zip(features) {
+            witness
+                .set_target(target, fixed_to_field(value))
+                .map_err(|e| Plonky2Error::Prover(e.to_string()))?;
+        }
+
+        self.data.prove(witness).map_err(|e| Plonky2Error::Prover(e.to_string()))
+    }
+
+    /// Verify a proof and check that it is for `prediction`
+    ///
+    /// # Arguments
+    /// * `proof` - Proof produced by `prove`
+    /// * `prediction` - Expected scaled prediction
+    ///
+    /// # Returns
+    /// * `Result<(), Plonky2Error>` - Ok if the proof is valid for this prediction
+    pub fn verify(&self, proof: &Plonky2Proof, prediction: i64) -> Result<(), Plonky2Error> {
+        self.verifier.verify(proof, prediction)
+    }
+
+    /// Prove the prediction for a feature vector and attach the model's metadata
+    pub fn prove_with_info(&self, features: &[i64]) -> Result<ModelProof, Plonky2Error> {
+        Ok(ModelProof { info: self.info().clone(), proof: self.prove(features)? })
+    }
+
+    /// Verify a bundled proof: it must name this circuit's model and be valid for `prediction`
+    ///
+    /// # Arguments
+    /// * `proof` - Bundle produced by `prove_with_info` (or read with `read_proof`)
+    /// * `prediction` - Expected scaled prediction
+    ///
+    /// # Returns
+    /// * `Result<(), Plonky2Error>` - Ok if the proof is for this model and this prediction
+    pub fn verify_with_info(&self, proof: &ModelProof, prediction: i64) -> Result<(), Plonky2Error> {
+        self.verifier.verify_with_info(proof, prediction)
+    }
+
+    /// Read a bundle written by `ModelProof::to_bytes` for this circuit
+    pub fn read_proof(&self, bytes: &[u8]) -> Result<ModelProof, Plonky2Error> {
+        self.verifier.read_proof(bytes)
+    }
+
+    /// Aggregation circuit of level `depth` (1-based), building the missing levels
+    fn level(&self, depth: usize) -> Arc<AggregationLevel> {
+        let mut levels = self.levels.lock().unwrap();
+        while levels.len() < depth {
+            let level = match levels.last() {
+                Some(below) => AggregationLevel::build(&below.data, false),
+                None => AggregationLevel::build(&self.data, true),
+            };
+            levels.push(Arc::new(level));
+        }
+        levels[depth - 1].clone()
+    }
+
+    /// Prove the predictions for many feature vectors with one aggregated proof
+    ///
+    /// Each input is proved on its own, then proofs are verified pairwise by recursive
+    /// aggregation circuits until one remains, so verifying a batch costs one verification.
+    ///
+    /// # Arguments
+    /// * `inputs` - Private input feature vectors (scaled, each as accepted by `prove`)
+    ///
+    /// # Returns
+    /// * `Result<BatchProof, Plonky2Error>` - Proof of every prediction, with the statements in order
+    pub fn prove_batch(&self, inputs: &[impl AsRef<[i64]>]) -> Result<BatchProof, Plonky2Error> {
+        self.aggregate(inputs, None).map(|(batch, _)| batch)
+    }
+
+    /// Prove a batch like `prove_batch`, keeping every finished proof in `checkpoint`
+    ///
+    /// Run again with the same inputs and checkpoint after an interruption, the job only proves
+    /// what is missing. The proofs stay on disk after the batch completes, until
+    /// `BatchCheckpoint::clear`.
+    ///
+    /// # Arguments
+    /// * `inputs` - Private input feature vectors (scaled, each as accepted by `prove`)
+    /// * `checkpoint` - Directory of finished proofs
+    ///
+    /// # Returns
+    /// * `Result<(BatchProof, usize), Plonky2Error>` - The batch proof, and how many of the leaf and
+    ///   aggregated proofs were read from the checkpoint instead of proved
+    pub fn prove_batch_checkpointed(
+        &self,
+        inputs: &[impl AsRef<[i64]>],
+        checkpoint: &BatchCheckpoint,
+    ) -> Result<(BatchProof, usize), Plonky2Error> {
+        self.aggregate(inputs, Some(checkpoint))
+    }
+
+    /// Directory of the job proving `inputs` with this circuit, created if needed
+    fn checkpoint_job(
+        &self,
+        inputs: &[impl AsRef<[i64]>],
+        checkpoint: &BatchCheckpoint,
+    ) -> Result<CheckpointJob, Plonky2Error> {
+        let mut identity = self.verifier.to_bytes();
+        for input in inputs {
+            identity.extend((input.as_ref().len() as u64).to_le_bytes());
+            identity.extend(input.as_ref().iter().flat_map(|value| value.to_le_bytes()));
+        }
+        let job = CheckpointJob { dir: checkpoint.dir.join(to_hex(&sha256(&identity))) };
+        std::fs::create_dir_all(&job.dir).map_err(checkpoint_error)?;
+        Ok(job)
+    }
+
+    /// Leaf proofs folded level by level, each read from the checkpoint when it holds a valid one
+    fn aggregate(
+        &self,
+        inputs: &[impl AsRef<[i64]>],
+        checkpoint: Option<&BatchCheckpoint>,
+    ) -> Result<(BatchProof, usize), Plonky2Error> {
+        if inputs.is_empty() {
+            return Err(Plonky2Error::EmptyBatch);
+        }
+        let job = checkpoint.map(|checkpoint| self.checkpoint_job(inputs, checkpoint)).transpose()?;
+        let job = job.as_ref();
+        let mut resumed = 0;
+        let mut proofs = inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| {
+                checkpointed(job, (0, index), &self.data, &mut resumed, || self.prove(input.as_ref()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let predictions = proofs.iter().map(|proof| proof_prediction(proof).expect("leaf prediction")).collect();
+        let features_hashes = proofs.iter().map(|proof| proof_features_hash(proof).expect("leaf hash")).collect();
+
+        let depth = batch_depth(inputs.len());
+        let last = proofs.last().cloned().expect("non-empty batch");
+        proofs.resize(1 << depth, last);
+        for depth in 1..=depth {
+            let level = self.level(depth);
+            proofs = proofs
+                .chunks(2)
+                .enumerate()
+                .map(|(index, pair)| {
+                    checkpointed(job, (depth, index), &level.data, &mut resumed, || level.prove(&pair[0], &pair[1]))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+        }
+        let proof = proofs.pop().expect("one aggregated proof");
+        Ok((BatchProof { info: self.info().clone(), predictions, features_hashes, proof }, resumed))
+    }
+
+    /// Verify a batch proof: it must name this circuit's model and be valid for `predictions`
+    ///
+    /// # Arguments
+    /// * `batch` - Proof produced by `prove_batch`
+    /// * `predictions` - Expected scaled predictions, in input order
+    ///
+    /// # Returns
+    /// * `Result<(), Plonky2Error>` - Ok if the proof covers exactly these predictions
+    pub fn verify_batch(&self, batch: &BatchProof, predictions: &[i64]) -> Result<(), Plonky2Error> {
+        if &batch.info != self.info() {
+            return Err(Plonky2Error::ModelMismatch {
+                expected: Box::new(self.info().clone()),
+                got: Box::new(batch.info.clone()),
+            });
+        }
+        for len in [batch.predictions.len(), batch.features_hashes.len()] {
+            if len != predictions.len() {
+                return Err(Plonky2Error::BatchSize { expected: predictions.len(), got: len });
+            }
+        }
+        if predictions.is_empty() {
+            return Err(Plonky2Error::EmptyBatch);
+        }
+        if let Some((&expected, &got)) = predictions.iter().zip(&batch.predictions).find(|(a, b)| a != b) {
+            return Err(Plonky2Error::PredictionMismatch { expected, got: Some(got) });
+        }
+
+        let depth = batch_depth(predictions.len());
+        let digest = batch_digest(&batch.predictions, &batch.features_hashes, depth);
+        let published: Vec<u64> = batch.proof.public_inputs.iter().map(|input| input.to_canonical_u64()).collect();
+        if published != digest {
+            return Err(Plonky2Error::Verifier("the proof is for other statements".to_string()));
+        }
+        self.level(depth).data.verify(batch.proof.clone()).map_err(|e| Plonky2Error::Verifier(e.to_string()))
+    }
+
+    /// Verify a proof for `prediction` on features whose `hash_features` digest is `features_hash`
+    pub fn verify_with_hash(
+        &self,
+        proof: &Plonky2Proof,
+        features_hash: &PoseidonDigest,
+        prediction: i64,
+    ) -> Result<(), Plonky2Error> {
+        self.verifier.verify_with_hash(proof, features_hash, prediction)
+    }
+}
+
+/// Circuit of the compiled model, built on first use
+fn builtin_circuit() -> &'static Plonky2Circuit {
+    static BUILTIN: OnceLock<Plonky2Circuit> = OnceLock::new();
+    BUILTIN.get_or_init(|| Plonky2Circuit::new(&crate::builtin_model()))
+}
+
+/// Prove `xgboost_predict(features)` with the compiled model
+///
+/// # Arguments
+/// * `features` - Input feature vector as slice of i64 values (scaled by 10^10)
+///
+/// # Returns
+/// * `Result<Plonky2Proof, Plonky2Error>` - Proof whose public input is the prediction
+pub fn prove(features: &[i64]) -> Result<Plonky2Proof, Plonky2Error> {
+    builtin_circuit().prove(features)
+}
+
+/// Verify a proof of the compiled model for `prediction`
+pub fn verify(proof: &Plonky2Proof, prediction: i64) -> Result<(), Plonky2Error> {
+    builtin_circuit().verify(proof, prediction)
+}
+
+/// Prove `xgboost_predict` for many feature vectors with one aggregated proof of the compiled model
+pub fn prove_batch(inputs: &[impl AsRef<[i64]>]) -> Result<BatchProof, Plonky2Error> {
+    builtin_circuit().prove_batch(inputs)
+}
+
+/// Prove a batch of the compiled model, resuming from the proofs already in `checkpoint`
+pub fn prove_batch_checkpointed(
+    inputs: &[impl AsRef<[i64]>],
+    checkpoint: &BatchCheckpoint,
+) -> Result<(BatchProof, usize), Plonky2Error> {
+    builtin_circuit().prove_batch_checkpointed(inputs, checkpoint)
+}
+
+/// Verify a batch proof of the compiled model for `predictions`
+pub fn verify_batch(batch: &BatchProof, predictions: &[i64]) -> Result<(), Plonky2Error> {
+    builtin_circuit().verify_batch(batch, predictions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Combiner, Ensemble, Node, Tree};
+
+    #[test]
+    fn test_fixed_le_target() {
+        // Several comparisons share a gate row, and the last row is only partly used
+        let cases = [(3i64, 5i64), (5, 5), (6, 5), (-(1 << 60), 1 << 60), (1 << 60, -(1 << 60)), (0, 0), (-1, -2)];
+        for bits in [VALUE_BITS, 20] {
+            let range = (1i64 << bits) - 1;
+            let cases: Vec<(i64, i64)> =
+                cases.iter().map(|&(a, b)| (a.clamp(-range, range), b.clamp(-range, range))).collect();
+            let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+            let targets = builder.add_virtual_targets(2 * cases.len());
+            for pair in targets.chunks(2) {
+                let le = fixed_le_target_bits(&mut builder, pair[0], pair[1], bits);
+                builder.register_public_input(le.target);
+            }
+            let data = builder.build::<C>();
+
+            let mut witness = PartialWitness::new();
+            for (pair, &(a, b)) in targets.chunks(2).zip(&cases) {
+                witness.set_target(pair[0], fixed_to_field(a)).unwrap();
+                witness.set_target(pair[1], fixed_to_field(b)).unwrap();
+            }
+            let proof = data.prove(witness).unwrap();
+            for (&le, &(a, b)) in proof.public_inputs.iter().zip(&cases) {
+                assert_eq!(le, F::from_bool(a <= b), "{} <= {} on {} bits", a, b, bits);
+            }
+            data.verify(proof).unwrap();
+        }
+
+        // Operands beyond the comparison range cannot be proved
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let targets = builder.add_virtual_targets(2);
+        fixed_le_target_bits(&mut builder, targets[0], targets[1], 20);
+        let data = builder.build::<C>();
+        let mut witness = PartialWitness::new();
+        witness.set_target(targets[0], fixed_to_field(-(1 << 21))).unwrap();
+        witness.set_target(targets[1], fixed_to_field(1 << 21)).unwrap();
+        assert!(data.prove(witness).is_err());
+    }
+
+    #[test]
+    fn test_round_product_target() {
+        let cases = [
+            (3 * crate::PRECISION_MULTIPLIER, 25_000_000_000i64),
+            (-7, 5_000_000_000),
+            (-(1 << 62), 3_333_333_333),
+            ((1 << 62) - 1, -(1 << 20)),
+            (123_456_789_012_345, -987_654_321),
+            (0, i64::MIN),
+        ];
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let inputs = builder.add_virtual_targets(cases.len());
+        for (&input, &(_, b)) in inputs.iter().zip(&cases) {
+            let product = round_product_target(&mut builder, input, b);
+            builder.register_public_input(product);
+        }
+        let data = builder.build::<C>();
+
+        let mut witness = PartialWitness::new();
+        for (&input, &(a, _)) in inputs.iter().zip(&cases) {
+            witness.set_target(input, fixed_to_field(a)).unwrap();
+        }
+        let proof = data.prove(witness).unwrap();
+        for (&output, &(a, b)) in proof.public_inputs.iter().zip(&cases) {
+            assert_eq!(field_to_fixed(output), Some(round_product(a as i128, b)), "{} * {}", a, b);
+        }
+        data.verify(proof).unwrap();
+
+        // A quotient beyond 2^61 cannot be proved
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let input = builder.add_virtual_target();
+        round_product_target(&mut builder, input, -(1 << 40));
+        let data = builder.build::<C>();
+        let mut witness = PartialWitness::new();
+        witness.set_target(input, fixed_to_field(1 << 61)).unwrap();
+        assert!(data.prove(witness).is_err());
+    }
+
+    #[test]
+    fn test_small_model_proof() {
+        let tree = Tree::new(Node::split(
+            0,
+            10,
+            Node::split(1, -5, Node::Leaf(7), Node::Leaf(-3)),
+            Node::Leaf(100),
+        ));
+        let model = Ensemble::new(vec![tree.clone(), tree], 1).with_base_score(-20);
+        let circuit = Plonky2Circuit::new(&model);
+
+        for features in [[10i64, -5], [10, 0], [11, -9]] {
+            let proof = circuit.prove(&features).unwrap();
+            let prediction = model.predict(&features);
+            assert_eq!(proof_prediction(&proof), Some(prediction));
+            circuit.verify(&proof, prediction).unwrap();
+            assert!(matches!(
+                circuit.verify(&proof, prediction + 1),
+                Err(Plonky2Error::PredictionMismatch { .. })
+            ));
+        }
+        assert_eq!(circuit.prove(&[1 << 61, 0]), Err(Plonky2Error::OutOfRange { index: 0, value: 1 << 61, bits: 61 }));
+    }
+
+    #[test]
+    fn test_bounded_circuit() {
+        let tree = Tree::new(Node::split(0, 10, Node::split(1, -5, Node::Leaf(7), Node::Leaf(-3)), Node::Leaf(100)));
+        let model = Ensemble::new(vec![tree.clone(), tree], 1).with_base_score(-20);
+        let bounds = ValueBounds::analyze(&model, 1000);
+        let circuit = Plonky2Circuit::with_bounds(&model, &bounds);
+        assert!(circuit.num_rows() <= Plonky2Circuit::new(&model).num_rows());
+
+        for features in [[10i64, -5], [1000, -1000]] {
+            let proof = circuit.prove(&features).unwrap();
+            circuit.verify(&proof, model.predict(&features)).unwrap();
+        }
+        assert_eq!(circuit.prove(&[0, 1024]), Err(Plonky2Error::OutOfRange { index: 1, value: 1024, bits: 10 }));
+    }
+
+    #[test]
+    fn test_averaged_circuit() {
+        let tree = Tree::new(Node::split(0, 10, Node::split(1, -5, Node::Leaf(7), Node::Leaf(-3)), Node::Leaf(100)));
+        let stump = Tree::new(Node::split(1, 0, Node::Leaf(-8), Node::Leaf(1)));
+        let model = Ensemble::new(vec![tree.clone(), tree, stump], 1)
+            .with_base_score(-20)
+            .with_combiner(Combiner::Average);
+        let circuit = Plonky2Circuit::new(&model);
+
+        // Tree sums -14 (floors to -5), 6 and 201
+        for features in [[0i64, 0], [0, -5], [11, 1]] {
+            let prediction = model.predict(&features);
+            let proof = circuit.prove(&features).unwrap();
+            circuit.verify(&proof, prediction).unwrap();
+            assert!(circuit.verify(&proof, prediction + 1).is_err());
+        }
+        assert_eq!(model.predict(&[0, 0]), -25);
+    }
+
+    #[test]
+    fn test_categorical_circuit() {
+        let categories: BitSet = [1, 2, 6].into_iter().collect();
+        let tree = Tree::new(Node::categorical(0, categories, 10, Node::Leaf(5), Node::split(1, 0, Node::Leaf(-1), Node::Leaf(2))));
+        let model = Ensemble::new(vec![tree], 10);
+        let circuit = Plonky2Circuit::new(&model);
+
+        for features in [[20i64, 3], [60, -4], [30, -4], [-10, 1], [21, 1]] {
+            let prediction = model.predict(&features);
+            let proof = circuit.prove(&features).unwrap();
+            circuit.verify(&proof, prediction).unwrap();
+            assert!(circuit.verify(&proof, prediction + 1).is_err());
+        }
+    }
+
+    #[test]
+    fn test_model_proof_bundle() {
+        let tree = Tree::new(Node::split(0, 10, Node::Leaf(7), Node::Leaf(-3)));
+        let model = Ensemble::new(vec![tree], 1).with_name("rain").with_version("1");
+        let circuit = Plonky2Circuit::new(&model);
+        assert_eq!(circuit.info(), &model.model_info());
+
+        let bundle = circuit.prove_with_info(&[4]).unwrap();
+        let bytes = bundle.to_bytes();
+        let read = circuit.read_proof(&bytes).unwrap();
+        assert_eq!(read.info, model.model_info());
+        circuit.verify_with_info(&read, 7).unwrap();
+
+        // Same trees under another version: the proof verifies but names the wrong model
+        let upgraded = Plonky2Circuit::new(&model.clone().with_version("2"));
+        assert!(matches!(upgraded.verify_with_info(&read, 7), Err(Plonky2Error::ModelMismatch { .. })));
+        assert!(matches!(circuit.read_proof(&bytes[..20]), Err(Plonky2Error::Decode(_))));
+    }
+
+    #[test]
+    fn test_exported_verifier() {
+        let tree = Tree::new(Node::split(0, 10, Node::Leaf(7), Node::Leaf(-3)));
+        let model = Ensemble::new(vec![tree], 1).with_name("rain");
+        let circuit = Plonky2Circuit::new(&model);
+        let verifier = Plonky2Verifier::from_bytes(&circuit.verifier().to_bytes()).unwrap();
+        assert_eq!(verifier.info(), circuit.info());
+        verifier.check_commitment(&ModelCommitment::new(&model).root()).unwrap();
+
+        let bundle = verifier.read_proof(&circuit.prove_with_info(&[12]).unwrap().to_bytes()).unwrap();
+        verifier.verify_with_info(&bundle, -3).unwrap();
+        assert!(matches!(verifier.verify(&bundle.proof, 7), Err(Plonky2Error::PredictionMismatch { .. })));
+
+        let other = Ensemble::new(vec![Tree::new(Node::Leaf(1))], 1);
+        let mismatch = verifier.check_commitment(&ModelCommitment::new(&other).root());
+        assert!(matches!(mismatch, Err(Plonky2Error::CommitmentMismatch { .. })));
+    }
+
+    #[test]
+    fn test_cached_circuit() {
+        // Averaging uses this module's FloorDivGenerator, which the proving key must carry
+        let stump = |leaf| Tree::new(Node::split(0, 0, Node::Leaf(leaf), Node::Leaf(9)));
+        let model = Ensemble::new(vec![stump(-4), stump(1)], 1).with_combiner(Combiner::Average);
+        let dir = std::env::temp_dir().join(format!("plonky2_keys_test_{}", std::process::id()));
+        let store = KeyStore::new(&dir);
+
+        let (built, source) = Plonky2Circuit::cached(&model, &store).unwrap();
+        assert_eq!(source, KeySource::Generated);
+        let (circuit, source) = Plonky2Circuit::cached(&model, &store).unwrap();
+        assert_eq!(source, KeySource::Cached);
+        assert_eq!((circuit.info(), circuit.num_rows()), (built.info(), built.num_rows()));
+        let proof = circuit.prove(&[-1]).unwrap();
+        built.verify(&proof, -2).unwrap();
+
+        let (verifier, source) = Plonky2Circuit::cached_verifier(&model, &store).unwrap();
+        assert_eq!(source, KeySource::Generated);
+        assert_eq!(Plonky2Circuit::cached_verifier(&model, &store).unwrap().1, KeySource::Cached);
+        verifier.verify(&proof, -2).unwrap();
+
+        let key = circuit.to_bytes();
+        assert!(matches!(Plonky2Circuit::from_bytes(&key[..key.len() / 2]), Err(Plonky2Error::InvalidKey(_))));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_batch_proof() {
+        let tree = Tree::new(Node::split(0, 10, Node::split(1, -5, Node::Leaf(7), Node::Leaf(-3)), Node::Leaf(100)));
+        let model = Ensemble::new(vec![tree], 1);
+        let circuit = Plonky2Circuit::new(&model);
+        assert_eq!(circuit.prove_batch(&[] as &[[i64; 2]]).unwrap_err(), Plonky2Error::EmptyBatch);
+
+        // Three inputs are padded to four leaves under two aggregation levels
+        let inputs = [[10i64, -5], [10, 0], [11, -9]];
+        let predictions: Vec<i64> = inputs.iter().map(|input| model.predict(input)).collect();
+        let batch = circuit.prove_batch(&inputs).unwrap();
+        assert_eq!(batch.predictions, [7, -3, 100]);
+        assert_eq!(batch.features_hashes[1], crate::hash_features(&inputs[1]));
+        circuit.verify_batch(&batch, &predictions).unwrap();
+        assert_eq!(
+            circuit.verify_batch(&batch, &[7, -3, 99]),
+            Err(Plonky2Error::PredictionMismatch { expected: 99, got: Some(100) })
+        );
+        assert_eq!(circuit.verify_batch(&batch, &[7, -3]), Err(Plonky2Error::BatchSize { expected: 2, got: 3 }));
+
+        // Claiming other statements for the same proof
+        let mut forged = batch.clone();
+        forged.predictions = vec![7, -3, 99];
+        assert!(matches!(circuit.verify_batch(&forged, &[7, -3, 99]), Err(Plonky2Error::Verifier(_))));
+        let mut swapped = batch;
+        swapped.features_hashes.swap(0, 2);
+        assert!(matches!(circuit.verify_batch(&swapped, &predictions), Err(Plonky2Error::Verifier(_))));
+
+        let single = circuit.prove_batch(&[[0i64, 0]]).unwrap();
+        circuit.verify_batch(&single, &[-3]).unwrap();
+    }
+
+    #[test]
+    fn test_checkpointed_batch_proof() {
+        let tree = Tree::new(Node::split(0, 10, Node::split(1, -5, Node::Leaf(7), Node::Leaf(-3)), Node::Leaf(100)));
+        let model = Ensemble::new(vec![tree], 1);
+        let circuit = Plonky2Circuit::new(&model);
+        let dir = std::env::temp_dir().join(format!("plonky2_checkpoint_{}", std::process::id()));
+        let checkpoint = BatchCheckpoint::new(&dir);
+        checkpoint.clear().unwrap();
+        let empty = circuit.prove_batch_checkpointed(&[] as &[[i64; 2]], &checkpoint);
+        assert_eq!(empty.unwrap_err(), circuit.prove_batch(&[] as &[[i64; 2]]).unwrap_err());
+        assert!(!dir.exists());
+
+        // Three leaves, two proofs at level 1 and one at level 2, all kept
+        let inputs = [[10i64, -5], [10, 0], [11, -9]];
+        let (batch, resumed) = circuit.prove_batch_checkpointed(&inputs, &checkpoint).unwrap();
+        assert_eq!(resumed, 0);
+        circuit.verify_batch(&batch, &[7, -3, 100]).unwrap();
+        let job = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        assert_eq!(std::fs::read_dir(&job).unwrap().count(), 6);
+        assert_eq!(circuit.prove_batch_checkpointed(&inputs, &checkpoint).unwrap().1, 6);
+
+        // As if interrupted before the last proof, with a leaf torn: both are proved again
+        std::fs::remove_file(job.join("2-0.proof")).unwrap();
+        let leaf = job.join("0-1.proof");
+        let bytes = std::fs::read(&leaf).unwrap();
+        std::fs::write(&leaf, &bytes[..bytes.len() / 2]).unwrap();
+        let (batch, resumed) = circuit.prove_batch_checkpointed(&inputs, &checkpoint).unwrap();
+        assert_eq!(resumed, 4);
+        circuit.verify_batch(&batch, &[7, -3, 100]).unwrap();
+
+        // Other inputs are another job
+        circuit.prove_batch_checkpointed(&[[0i64, 0]], &checkpoint).unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+        checkpoint.clear().unwrap();
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_builtin_model_proof() {
+        let mut features = vec![0i64; crate::NUM_FEATURES];
+        features[34] = 130000000000;
+        features[77] = -20000000000;
+
+        let proof = prove(&features).unwrap();
+        let prediction = crate::xgboost_predict(&features);
+        verify(&proof, prediction).unwrap();
+
+        // The circuit hashes the features exactly like `hash_features`
+        let features_hash = crate::hash_features(&features);
+        assert_eq!(proof_features_hash(&proof), Some(features_hash));
+        builtin_circuit().verify_with_hash(&proof, &features_hash, prediction).unwrap();
+        features[0] = 1;
+        assert_eq!(
+            builtin_circuit().verify_with_hash(&proof, &crate::hash_features(&features), prediction),
+            Err(Plonky2Error::FeaturesHashMismatch)
+        );
+    }
+}
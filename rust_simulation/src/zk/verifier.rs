@@ -0,0 +1,348 @@
+// plonky2 proof verification without the prover stack
+// Everything a verifier needs, and nothing that builds circuits: the field and proof types, the
+// public-input layout of model proofs, `ModelProof` bundles and `Plonky2Verifier`, which holds a
+// circuit's serialized verifier data instead of the circuit. Provers export it once with
+// `Plonky2Circuit::verifier().to_bytes()`; edge devices and off-chain workers load it with the
+// `verifier` feature, which pulls plonky2 without its parallel prover and randomness features.
+//
+// A verifier key also carries the `ModelInfo` and the `ModelCommitment` root of the model it was
+// exported for, so a device holding a published commitment can check that the key is for that
+// model before trusting any proof it verifies.
+//
+// Key layout: magic "ZKVK" | version u8 | info_len u32 | ModelInfo bytes | commitment 32 bytes |
+// plonky2 verifier circuit data (little-endian lengths)
+
+use std::fmt;
+
+use ::plonky2::field::types::{Field, PrimeField64};
+use ::plonky2::plonk::circuit_data::{CommonCircuitData, VerifierCircuitData};
+use ::plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use ::plonky2::plonk::proof::ProofWithPublicInputs;
+
+use super::gates::GateSerializer;
+use crate::commitment::Digest;
+use crate::info::ModelInfo;
+use crate::keys::KeyError;
+use crate::poseidon::PoseidonDigest;
+use crate::sha256::to_hex;
+
+/// Extension degree used by the standard recursion config
+pub const D: usize = 2;
+/// Poseidon over Goldilocks
+pub type C = PoseidonGoldilocksConfig;
+/// Goldilocks field
+pub type F = <C as GenericConfig<D>>::F;
+/// Proof of one prediction; the public inputs are the scaled prediction and the features hash
+pub type Plonky2Proof = ProofWithPublicInputs<F, C, D>;
+
+/// Bits of magnitude allowed for any fixed-point value inside the circuit
+pub const VALUE_BITS: usize = 61;
+
+/// First bytes of an encoded verifier key
+pub const KEY_MAGIC: [u8; 4] = *b"ZKVK";
+
+const KEY_VERSION: u8 = 1;
+
+/// Error type for plonky2 proving and verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Plonky2Error {
+    /// The input does not have one value per model feature
+    WrongLength { expected: usize, got: usize },
+    /// A feature does not fit in the circuit's input range |x| < 2^bits
+    OutOfRange { index: usize, value: i64, bits: usize },
+    /// Witness generation or proving failed
+    Prover(String),
+    /// The proof does not verify
+    Verifier(String),
+    /// The proof verifies but for a different prediction
+    PredictionMismatch { expected: i64, got: Option<i64> },
+    /// The proof is for features with a different hash
+    FeaturesHashMismatch,
+    /// The proof was made with a different model (or model version)
+    ModelMismatch { expected: Box<ModelInfo>, got: Box<ModelInfo> },
+    /// The verifier key was exported for a model with a different commitment
+    CommitmentMismatch { expected: Digest, got: Digest },
+    /// A serialized proof is malformed
+    Decode(String),
+    /// A serialized verifier or proving key is malformed
+    InvalidKey(String),
+    /// Reading or writing a key cache failed
+    KeyCache(String),
+    /// Reading or writing a batch checkpoint failed
+    Checkpoint(String),
+    /// A batch proof was requested for no inputs
+    EmptyBatch,
+    /// A batch proof covers a different number of predictions
+    BatchSize { expected: usize, got: usize },
+}
+
+impl fmt::Display for Plonky2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Plonky2Error::WrongLength { expected, got } => {
+                write!(f, "expected {} features, got {}", expected, got)
+            }
+            Plonky2Error::OutOfRange { index, value, bits } => {
+                write!(f, "feature {} = {} exceeds the circuit range of 2^{}", index, value, bits)
+            }
+            Plonky2Error::Prover(message) => write!(f, "proving failed: {}", message),
+            Plonky2Error::Verifier(message) => write!(f, "verification failed: {}", message),
+            Plonky2Error::PredictionMismatch { expected, got } => match got {
+                Some(got) => write!(f, "proof is for prediction {}, not {}", got, expected),
+                None => write!(f, "proof has no valid prediction, expected {}", expected),
+            },
+            Plonky2Error::FeaturesHashMismatch => write!(f, "proof is for different features"),
+            Plonky2Error::ModelMismatch { expected, got } => {
+                write!(f, "proof was made with {}, not {}", got, expected)
+            }
+            Plonky2Error::CommitmentMismatch { expected, got } => {
+                write!(f, "verifier key is for model commitment {}, not {}", to_hex(got), to_hex(expected))
+            }
+            Plonky2Error::Decode(message) => write!(f, "malformed proof: {}", message),
+            Plonky2Error::InvalidKey(message) => write!(f, "malformed key: {}", message),
+            Plonky2Error::KeyCache(message) => write!(f, "key cache: {}", message),
+            Plonky2Error::Checkpoint(message) => write!(f, "batch checkpoint: {}", message),
+            Plonky2Error::EmptyBatch => write!(f, "cannot prove an empty batch"),
+            Plonky2Error::BatchSize { expected, got } => {
+                write!(f, "batch proof covers {} predictions, not {}", got, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Plonky2Error {}
+
+impl From<KeyError> for Plonky2Error {
+    fn from(e: KeyError) -> Self {
+        Plonky2Error::KeyCache(e.to_string())
+    }
+}
+
+/// Embed a fixed-point value as a field element (negative values wrap around the modulus)
+pub fn fixed_to_field(value: i64) -> F {
+    F::from_noncanonical_i64(value)
+}
+
+/// Field element as a signed fixed-point value, if it lies in the circuit range
+pub fn field_to_fixed(value: F) -> Option<i64> {
+    let canonical = value.to_canonical_u64();
+    let negated = (-value).to_canonical_u64();
+    if canonical < 1 << VALUE_BITS {
+        Some(canonical as i64)
+    } else if negated < 1 << VALUE_BITS {
+        Some(-(negated as i64))
+    } else {
+        None
+    }
+}
+
+/// Public inputs of a proof that features with hash `features_hash` predict `prediction`
+pub fn public_inputs(prediction: i64, features_hash: &PoseidonDigest) -> Vec<F> {
+    let mut inputs = vec![fixed_to_field(prediction)];
+    inputs.extend(features_hash.iter().map(|&element| F::from_canonical_u64(element)));
+    inputs
+}
+
+/// Scaled prediction a proof was made for
+pub fn proof_prediction(proof: &Plonky2Proof) -> Option<i64> {
+    match proof.public_inputs.as_slice() {
+        [prediction, _, _, _, _] => field_to_fixed(*prediction),
+        _ => None,
+    }
+}
+
+/// Poseidon hash of the private features a proof was made for
+pub fn proof_features_hash(proof: &Plonky2Proof) -> Option<PoseidonDigest> {
+    match proof.public_inputs.as_slice() {
+        [_, h0, h1, h2, h3] => Some([h0, h1, h2, h3].map(|h| h.to_canonical_u64())),
+        _ => None,
+    }
+}
+
+/// Proof bundled with the metadata of the model that produced it
+#[derive(Debug, Clone)]
+pub struct ModelProof {
+    pub info: ModelInfo,
+    pub proof: Plonky2Proof,
+}
+
+impl ModelProof {
+    /// Serialize as `info_len u32 | ModelInfo bytes | proof bytes` (little-endian length)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let info = self.info.to_bytes();
+        let mut out = Vec::with_capacity(4 + info.len());
+        out.extend_from_slice(&(info.len() as u32).to_le_bytes());
+        out.extend_from_slice(&info);
+        out.extend_from_slice(&self.proof.to_bytes());
+        out
+    }
+
+    /// Read a bundle written by `to_bytes` for a circuit with `common` data
+    pub fn from_bytes(bytes: &[u8], common: &CommonCircuitData<F, D>) -> Result<ModelProof, Plonky2Error> {
+        let (info, rest) = read_info(bytes)?;
+        let proof = Plonky2Proof::from_bytes(rest.to_vec(), common).map_err(|e| decode(e.to_string()))?;
+        Ok(ModelProof { info, proof })
+    }
+}
+
+fn decode(message: String) -> Plonky2Error {
+    Plonky2Error::Decode(message)
+}
+
+/// Split a length-prefixed `ModelInfo` off the front of `bytes`
+fn read_info(bytes: &[u8]) -> Result<(ModelInfo, &[u8]), Plonky2Error> {
+    let info_len = bytes
+        .get(..4)
+        .map(|len| u32::from_le_bytes(len.try_into().expect("4 bytes")) as usize)
+        .ok_or_else(|| decode("missing model info length".to_string()))?;
+    let info_bytes = bytes.get(4..4 + info_len).ok_or_else(|| decode("truncated model info".to_string()))?;
+    let info = ModelInfo::from_bytes(info_bytes).map_err(|e| decode(e.to_string()))?;
+    Ok((info, &bytes[4 + info_len..]))
+}
+
+/// Verifier data of one circuit, with the model it was exported for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plonky2Verifier {
+    data: VerifierCircuitData<F, C, D>,
+    info: ModelInfo,
+    commitment: Digest,
+}
+
+impl Plonky2Verifier {
+    /// Verifier of a circuit built for the model with metadata `info` and commitment root `commitment`
+    pub fn new(data: VerifierCircuitData<F, C, D>, info: ModelInfo, commitment: Digest) -> Plonky2Verifier {
+        Plonky2Verifier { data, info, commitment }
+    }
+
+    /// Metadata of the model the circuit was built for
+    pub fn info(&self) -> &ModelInfo {
+        &self.info
+    }
+
+    /// `ModelCommitment` root of the model the circuit was built for
+    pub fn commitment(&self) -> &Digest {
+        &self.commitment
+    }
+
+    /// Common circuit data, needed to read proofs
+    pub fn common(&self) -> &CommonCircuitData<F, D> {
+        &self.data.common
+    }
+
+    /// Encode the key (see the layout at the top of this file)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let info = self.info.to_bytes();
+        let mut out = Vec::with_capacity(4 + 1 + 4 + info.len() + 32);
+        out.extend_from_slice(&KEY_MAGIC);
+        out.push(KEY_VERSION);
+        out.extend_from_slice(&(info.len() as u32).to_le_bytes());
+        out.extend_from_slice(&info);
+        out.extend_from_slice(&self.commitment);
+        out.extend(self.data.to_bytes(&GateSerializer).expect("circuit gates serialize"));
+        out
+    }
+
+    /// Decode a key written by `to_bytes`
+    ///
+    /// # Arguments
+    /// * `bytes` - Encoded key
+    ///
+    /// # Returns
+    /// * `Result<Plonky2Verifier, Plonky2Error>` - The verifier, or why the bytes are malformed
+    pub fn from_bytes(bytes: &[u8]) -> Result<Plonky2Verifier, Plonky2Error> {
+        let invalid = |message: String| Plonky2Error::InvalidKey(message);
+        if bytes.get(..4) != Some(&KEY_MAGIC[..]) {
+            return Err(invalid("not a verifier key".to_string()));
+        }
+        match bytes.get(4) {
+            Some(&KEY_VERSION) => {}
+            Some(version) => return Err(invalid(format!("unsupported version {}", version))),
+            None => return Err(invalid("truncated header".to_string())),
+        }
+        let (info, rest) = read_info(&bytes[5..]).map_err(|e| invalid(e.to_string()))?;
+        let commitment: Digest = rest
+            .get(..32)
+            .and_then(|commitment| commitment.try_into().ok())
+            .ok_or_else(|| invalid("truncated model commitment".to_string()))?;
+        let data = VerifierCircuitData::from_bytes(rest[32..].to_vec(), &GateSerializer)
+            .map_err(|e| invalid(e.to_string()))?;
+        Ok(Plonky2Verifier { data, info, commitment })
+    }
+
+    /// Check that the key was exported for the model with the published commitment root
+    pub fn check_commitment(&self, expected: &Digest) -> Result<(), Plonky2Error> {
+        if &self.commitment != expected {
+            return Err(Plonky2Error::CommitmentMismatch { expected: *expected, got: self.commitment });
+        }
+        Ok(())
+    }
+
+    /// Verify a proof and check that it is for `prediction`
+    ///
+    /// # Arguments
+    /// * `proof` - Proof of the circuit this key was exported from
+    /// * `prediction` - Expected scaled prediction
+    ///
+    /// # Returns
+    /// * `Result<(), Plonky2Error>` - Ok if the proof is valid for this prediction
+    pub fn verify(&self, proof: &Plonky2Proof, prediction: i64) -> Result<(), Plonky2Error> {
+        let got = proof_prediction(proof);
+        if got != Some(prediction) {
+            return Err(Plonky2Error::PredictionMismatch { expected: prediction, got });
+        }
+        self.data.verify(proof.clone()).map_err(|e| Plonky2Error::Verifier(e.to_string()))
+    }
+
+    /// Verify a proof for `prediction` on features whose `hash_features` digest is `features_hash`
+    pub fn verify_with_hash(
+        &self,
+        proof: &Plonky2Proof,
+        features_hash: &PoseidonDigest,
+        prediction: i64,
+    ) -> Result<(), Plonky2Error> {
+        if proof_features_hash(proof).as_ref() != Some(features_hash) {
+            return Err(Plonky2Error::FeaturesHashMismatch);
+        }
+        self.verify(proof, prediction)
+    }
+
+    /// Verify a bundled proof: it must name this key's model and be valid for `prediction`
+    pub fn verify_with_info(&self, proof: &ModelProof, prediction: i64) -> Result<(), Plonky2Error> {
+        if proof.info != self.info {
+            return Err(Plonky2Error::ModelMismatch {
+                expected: Box::new(self.info.clone()),
+                got: Box::new(proof.info.clone()),
+            });
+        }
+        self.verify(&proof.proof, prediction)
+    }
+
+    /// Read a bundle written by `ModelProof::to_bytes` for this key's circuit
+    pub fn read_proof(&self, bytes: &[u8]) -> Result<ModelProof, Plonky2Error> {
+        ModelProof::from_bytes(bytes, &self.data.common)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_public_inputs_layout() {
+        let hash = [1, 2, 3, u64::MAX - (1 << 32)];
+        let inputs = public_inputs(-42, &hash);
+        assert_eq!(inputs.len(), 5);
+        assert_eq!(field_to_fixed(inputs[0]), Some(-42));
+        assert_eq!(inputs[4].to_canonical_u64(), hash[3]);
+    }
+
+    #[test]
+    fn test_key_decode_errors() {
+        assert!(matches!(Plonky2Verifier::from_bytes(b"ZKMI"), Err(Plonky2Error::InvalidKey(_))));
+        assert_eq!(
+            Plonky2Verifier::from_bytes(b"ZKVK\x07").unwrap_err().to_string(),
+            "malformed key: unsupported version 7"
+        );
+        assert!(matches!(Plonky2Verifier::from_bytes(b"ZKVK\x01\x00"), Err(Plonky2Error::InvalidKey(_))));
+    }
+}
@@ -0,0 +1,158 @@
+// Golden-vector corpus generator and file format
+// Samples feature vectors around the split thresholds of the bundled model, where a change in
+// comparison or rounding semantics flips a branch: each feature the model reads is set to one of
+// its thresholds, a threshold +/- 1 (one unit in the last place at scale 10^10), MISSING_VALUE, or
+// a uniform value across the threshold range. Features the model never reads stay zero.
+//
+// File format, one vector per line after the `#` comments:
+//   <margin> <probability> <feature>:<value> ...
+// with all numbers scaled by 10^10 and only the non-zero features listed.
+
+use std::fmt::Write as _;
+
+use rainfall_prediction::{
+    builtin_model, expand_sparse, xgboost_predict, xgboost_predict_proba, Lcg, Model, Node, MISSING_VALUE,
+    NUM_FEATURES,
+};
+
+/// Seed of the checked-in corpus
+pub const SEED: u64 = 0x5eed_0804;
+
+/// Number of vectors in the checked-in corpus
+pub const COUNT: usize = 2000;
+
+/// One golden vector: sparse input and the expected scaled outputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoldenVector {
+    pub features: Vec<(usize, i64)>,
+    pub margin: i64,
+    pub probability: i64,
+}
+
+impl GoldenVector {
+    /// Dense feature vector of the model's width
+    pub fn dense(&self) -> Vec<i64> {
+        expand_sparse(&self.features, NUM_FEATURES).expect("golden features are in range")
+    }
+}
+
+fn collect_thresholds(node: &Node, thresholds: &mut [Vec<i64>]) {
+    match node {
+        Node::Leaf(_) => {}
+        Node::Split { feature, threshold, left, right } => {
+            thresholds[*feature].push(*threshold);
+            collect_thresholds(left, thresholds);
+            collect_thresholds(right, thresholds);
+        }
+        Node::Categorical { feature, categories, unit, left, right } => {
+            thresholds[*feature].extend(categories.values(*unit));
+            collect_thresholds(left, thresholds);
+            collect_thresholds(right, thresholds);
+        }
+    }
+}
+
+/// Distinct split values of every feature the model reads, in feature order
+pub fn split_points(model: &Model) -> Vec<(usize, Vec<i64>)> {
+    let mut thresholds = vec![Vec::new(); model.num_features()];
+    for tree in model.trees() {
+        collect_thresholds(&tree.root, &mut thresholds);
+    }
+    thresholds
+        .into_iter()
+        .enumerate()
+        .filter(|(_, values)| !values.is_empty())
+        .map(|(feature, mut values)| {
+            values.sort_unstable();
+            values.dedup();
+            (feature, values)
+        })
+        .collect()
+}
+
+/// Sample `count` vectors from `seed` and label them with the compiled model
+///
+/// # Arguments
+/// * `seed` - Generator seed
+/// * `count` - Number of vectors
+///
+/// # Returns
+/// * `Vec<GoldenVector>` - Vectors with their `xgboost_predict` margin and probability
+pub fn generate(seed: u64, count: usize) -> Vec<GoldenVector> {
+    let points = split_points(&builtin_model());
+    let mut rng = Lcg::new(seed);
+    (0..count)
+        .map(|_| {
+            let features: Vec<(usize, i64)> = points
+                .iter()
+                .map(|(feature, values)| {
+                    let threshold = values[rng.next_u31() as usize % values.len()];
+                    let value = match rng.next_u31() % 8 {
+                        0 => MISSING_VALUE,
+                        1..=3 => threshold,
+                        4 => threshold - 1,
+                        5 => threshold + 1,
+                        _ => {
+                            let (low, high) = (values[0] - 10_000_000_000, values[values.len() - 1] + 10_000_000_000);
+                            low + (rng.next_u31() % (high - low) as u64) as i64
+                        }
+                    };
+                    (*feature, value)
+                })
+                .filter(|&(_, value)| value != 0)
+                .collect();
+            let dense = expand_sparse(&features, NUM_FEATURES).expect("model features are in range");
+            GoldenVector { margin: xgboost_predict(&dense), probability: xgboost_predict_proba(&dense), features }
+        })
+        .collect()
+}
+
+/// Render a corpus in the file format, with a header recording how it was produced
+pub fn format(vectors: &[GoldenVector], seed: u64) -> String {
+    let mut out = String::new();
+    writeln!(out, "# Golden vectors for the bundled model; regenerate with").unwrap();
+    writeln!(out, "#   GOLDEN_UPDATE=1 cargo test --test golden").unwrap();
+    writeln!(out, "# seed {:#x}, {} vectors; columns: margin probability feature:value ...", seed, vectors.len()).unwrap();
+    for vector in vectors {
+        write!(out, "{} {}", vector.margin, vector.probability).unwrap();
+        for (feature, value) in &vector.features {
+            write!(out, " {}:{}", feature, value).unwrap();
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Parse a corpus file
+///
+/// # Arguments
+/// * `text` - File contents
+///
+/// # Returns
+/// * `Result<Vec<GoldenVector>, String>` - The vectors, or the first malformed line
+pub fn parse(text: &str) -> Result<Vec<GoldenVector>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            let error = |message: &str| format!("line {}: {}", number + 1, message);
+            let mut fields = line.split_whitespace();
+            let mut output = || -> Result<i64, String> {
+                fields.next().and_then(|f| f.parse().ok()).ok_or_else(|| error("missing or invalid output"))
+            };
+            let (margin, probability) = (output()?, output()?);
+            let features = line
+                .split_whitespace()
+                .skip(2)
+                .map(|pair| {
+                    let (feature, value) = pair.split_once(':').ok_or_else(|| error("expected feature:value"))?;
+                    match (feature.parse(), value.parse()) {
+                        (Ok(feature), Ok(value)) => Ok((feature, value)),
+                        _ => Err(error("invalid feature:value")),
+                    }
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(GoldenVector { features, margin, probability })
+        })
+        .collect()
+}
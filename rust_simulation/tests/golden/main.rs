@@ -0,0 +1,60 @@
+// Golden-vector regression test
+// Replays the checked-in corpus (vectors.txt) through every evaluator of the bundled model and
+// requires bit-exact margins and probabilities, so a refactor of the fixed-point arithmetic or of
+// any execution mode cannot silently change a prediction. An intended change of outputs is made
+// by regenerating the corpus and reviewing its diff:
+//
+//   GOLDEN_UPDATE=1 cargo test --test golden
+
+mod corpus;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use rainfall_prediction::{
+    builtin_model, xgboost_predict, xgboost_predict_batch, xgboost_predict_oblivious, xgboost_predict_proba, Model,
+};
+
+use corpus::{GoldenVector, COUNT, SEED};
+
+fn corpus_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden/vectors.txt")
+}
+
+fn load() -> Vec<GoldenVector> {
+    let path = corpus_path();
+    if env::var_os("GOLDEN_UPDATE").is_some() {
+        fs::write(&path, corpus::format(&corpus::generate(SEED, COUNT), SEED)).expect("write golden corpus");
+    }
+    let text = fs::read_to_string(&path).expect("read golden corpus");
+    corpus::parse(&text).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+#[test]
+fn test_golden_vectors() {
+    let vectors = load();
+    assert_eq!(vectors.len(), COUNT);
+    // The stored inputs are exactly what the generator samples, so regenerating the corpus only
+    // ever changes the expected outputs
+    let sampled = corpus::generate(SEED, COUNT);
+    assert!(sampled.iter().zip(&vectors).all(|(s, v)| s.features == v.features), "corpus inputs drifted from the generator");
+
+    let model = builtin_model();
+    let reloaded = Model::from_bytes(&model.to_bytes()).expect("binary round trip");
+    let dense: Vec<Vec<i64>> = vectors.iter().map(GoldenVector::dense).collect();
+
+    for (i, (vector, features)) in vectors.iter().zip(&dense).enumerate() {
+        let expected = vector.margin;
+        assert_eq!(xgboost_predict(features), expected, "vector {}: unrolled", i);
+        assert_eq!(model.predict(features), expected, "vector {}: interpreter", i);
+        assert_eq!(reloaded.predict(features), expected, "vector {}: binary model", i);
+        assert_eq!(xgboost_predict_oblivious(features), expected, "vector {}: oblivious", i);
+        assert_eq!(xgboost_predict_proba(features), vector.probability, "vector {}: probability", i);
+    }
+
+    let margins: Vec<i64> = vectors.iter().map(|v| v.margin).collect();
+    assert_eq!(xgboost_predict_batch(&dense), margins, "batch");
+    #[cfg(feature = "simd")]
+    assert_eq!(rainfall_prediction::xgboost_predict_batch_simd(&dense), margins, "simd");
+}
@@ -0,0 +1,51 @@
+// JS interop test for the `wasm` feature
+// Loads the wasm-bindgen output for node and checks the exports against the native crate:
+//
+//   cargo rustc --lib --release --features wasm --target wasm32-unknown-unknown --crate-type cdylib
+//   wasm-bindgen --target nodejs --out-dir wasm/pkg target/wasm32-unknown-unknown/release/rainfall_prediction.wasm
+//   node --test wasm/
+
+import assert from 'node:assert/strict';
+import { createRequire } from 'node:module';
+import test from 'node:test';
+
+const require = createRequire(import.meta.url);
+const model = require('./pkg/rainfall_prediction.js');
+
+// xgboost_predict(&[0; 116]) in the native crate
+const ZERO_PREDICTION = 252610968n;
+
+test('model metadata', () => {
+  assert.equal(model.numFeatures(), 116);
+  assert.equal(model.modelId(), 'bst1_10');
+});
+
+test('fixed-point prediction matches the native crate', () => {
+  const features = new BigInt64Array(model.numFeatures());
+  assert.equal(model.predictFixed(features), ZERO_PREDICTION);
+  assert.equal(model.predict(new Float64Array(model.numFeatures())), Number(ZERO_PREDICTION) / 1e10);
+});
+
+test('raw and scaled entry points agree', () => {
+  const raw = new Float64Array(model.numFeatures());
+  raw[22] = 1.0;
+  raw[34] = 15.0;
+  const scaled = BigInt64Array.from(raw, (x) => model.toFixedPoint(x));
+  assert.equal(scaled[34], 150000000000n);
+  assert.equal(model.predictScaled(raw), model.predictFixed(scaled));
+  assert.equal(model.predict(raw), model.fromFixedPoint(model.predictFixed(scaled)));
+
+  const proba = model.predictProbaFixed(scaled);
+  assert.ok(proba > 0n && proba < 10000000000n);
+});
+
+test('bad input throws instead of aborting', () => {
+  assert.throws(() => model.predict(new Float64Array(3)), /expected at least 116 features, got 3/);
+
+  const raw = new Float64Array(model.numFeatures());
+  raw[5] = NaN;
+  assert.throws(() => model.predict(raw), /feature 5 is not a finite number/);
+
+  // The module is still usable afterwards
+  assert.equal(model.predictFixed(new BigInt64Array(model.numFeatures())), ZERO_PREDICTION);
+});